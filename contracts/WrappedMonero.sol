@@ -5,6 +5,7 @@ import "@openzeppelin/contracts/token/ERC20/ERC20.sol";
 import "@openzeppelin/contracts/token/ERC20/extensions/ERC20Permit.sol";
 import "@openzeppelin/contracts/token/ERC20/IERC20.sol";
 import "@openzeppelin/contracts/utils/ReentrancyGuard.sol";
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
 import "@pythnetwork/pyth-sdk-solidity/IPyth.sol";
 import "@pythnetwork/pyth-sdk-solidity/PythStructs.sol";
 import "./interfaces/IPlonkVerifier.sol";
@@ -57,11 +58,20 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
     uint256 public constant MINT_INTENT_TIMEOUT = 2 hours;
     uint256 public constant MIN_INTENT_DEPOSIT = 0.001 ether;  // 0.001 ETH minimum deposit
     uint256 public constant MIN_MINT_BPS = 100;         // Minimum 1% of LP capacity (Sybil defense)
+    uint256 public constant MAX_LEASE_DURATION = 1 hours;
     
     // Pyth price feed IDs
     bytes32 public constant XMR_USD_PRICE_ID = 0x46b8cc9347f04391764a0361e0b17c3ba394b001e7c304f7650f6376e37c321d;
     bytes32 public constant ETH_USD_PRICE_ID = 0xff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace;
     
+    // EIP-712 type of a signer's block attestation
+    bytes32 public constant MONERO_BLOCK_TYPEHASH = keccak256(
+        "MoneroBlock(uint256 blockHeight,bytes32 blockHash,bytes32 txMerkleRoot,bytes32 outputMerkleRoot)"
+    );
+    
+    // secp256k1 group order, for threshold (Schnorr) signature checks
+    uint256 internal constant SECP256K1_N = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141;
+    
     // ════════════════════════════════════════════════════════════════════════
     // STATE VARIABLES
     // ════════════════════════════════════════════════════════════════════════
@@ -103,6 +113,9 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
     
     // Track used Monero outputs
     mapping(bytes32 => bool) public usedOutputs;
+    // One-time keys of minted outputs: transactions reusing a tx key repeat
+    // the key, and only one of those outputs can ever be spent
+    mapping(bytes32 => bool) public usedOutputKeys;
     
     // Burn requests
     struct BurnRequest {
@@ -130,6 +143,41 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
     mapping(uint256 => MoneroBlockData) public moneroBlocks;
     uint256 public latestMoneroBlock;
     
+    // Reorg rollbacks clear at most MAX_ROLLBACK_BLOCKS blocks per call. A deeper one
+    // leaves rollbackCursor at the next orphaned height to clear (0 when none is
+    // unfinished) and rollbackEnd at the last; continueRollback() goes on from there.
+    uint256 public constant MAX_ROLLBACK_BLOCKS = 256;
+    uint256 public rollbackCursor;
+    uint256 public rollbackEnd;
+    
+    // Multi-signature oracle: blocks attested by signerThreshold signers can be posted by anyone
+    mapping(address => bool) public isSigner;
+    address[] public signers;
+    uint256 public signerThreshold;
+    
+    // Threshold oracle: blocks signed by a FROST group key can be posted by anyone
+    uint256 public thresholdKeyX;
+    uint8 public thresholdKeyParity;
+    
+    // Leader election: the redundant oracle instance allowed to send transactions
+    bytes32 public leaseHolder;
+    uint256 public leaseExpiresAt;
+    
+    // Liveness: when the oracle last called heartbeat()
+    uint256 public lastHeartbeat;
+    
+    // Tree encodings: the version new blocks are posted with, and the one each
+    // posted block was built with (0 for blocks from before versioning, read as 1).
+    // Version 2 prefixes leaves, nodes and roots, in the tx tree and the output tree alike.
+    // Output tree version 3 is version 1 with SHA-256 nodes, as legacy deployments built them.
+    uint8 public constant LEAF_PREFIX = 0x00;
+    uint8 public constant NODE_PREFIX = 0x01;
+    uint8 public constant ROOT_PREFIX = 0x02;
+    uint8 public outputTreeVersion;
+    mapping(uint256 => uint8) public outputTreeVersionOf;
+    uint8 public txTreeVersion;
+    mapping(uint256 => uint8) public txTreeVersionOf;
+    
     struct MoneroTxOutput {
         bytes32 txHash;
         uint256 outputIndex;
@@ -178,10 +226,22 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
     event Minted(address indexed recipient, address indexed lp, uint256 amount, uint256 fee, bytes32 indexed outputId);
     event BurnRequested(uint256 indexed burnId, address indexed user, address indexed lp, uint256 amount, string xmrAddress);
     event BurnFulfilled(uint256 indexed burnId, bytes32 xmrTxHash);
+    event WithdrawalConfirmed(uint256 indexed burnId, bytes32 xmrTxHash, bytes32 xmrTxKey);
     event BurnDefaulted(uint256 indexed burnId, uint256 collateralSeized);
     
     event PriceUpdated(uint256 xmrPrice, uint256 ethPrice, uint256 timestamp);
     event MoneroBlockPosted(uint256 indexed blockHeight, bytes32 indexed blockHash);
+    event MoneroBlocksRolledBack(uint256 indexed fromHeight, uint256 previousLatest);
+    event MoneroBlocksCleared(uint256 fromHeight, uint256 toHeight, bool finished);
+    event SignersUpdated(address[] signers, uint256 threshold);
+    event ThresholdKeyUpdated(uint256 keyX, uint8 keyParity);
+    event OutputTreeVersionUpdated(uint8 version);
+    event OutputTreeVersionRecorded(uint256 fromHeight, uint256 toHeight, uint8 version);
+    event TxTreeVersionUpdated(uint8 version);
+    event LeaseAcquired(bytes32 indexed holder, uint256 expiresAt);
+    event LeaseReleased(bytes32 indexed holder);
+    event OracleHeartbeat(address indexed sender, uint256 moneroTip, uint256 latestMoneroBlock);
+    event OracleTransferred(address indexed previousOracle, address indexed newOracle);
     event OracleYieldClaimed(address indexed oracle, uint256 amount);
     event MintIntentCreated(bytes32 indexed intentId, address indexed user, address indexed lp, uint256 expectedAmount);
     event MintIntentFulfilled(bytes32 indexed intentId, uint256 actualAmount);
@@ -536,27 +596,15 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         );
         
         // Verify TX exists in Monero block via Merkle proof
-        require(moneroBlocks[blockHeight].exists, "Block not posted");
+        require(isBlockPosted(blockHeight), "Block not posted");
         require(
             verifyTxInBlock(output.txHash, blockHeight, txMerkleProof, txIndex),
             "TX not in block"
         );
         
         // Verify output exists in block's output Merkle tree
-        bytes32 outputLeaf = keccak256(abi.encodePacked(
-            output.txHash,
-            output.outputIndex,
-            output.ecdhAmount,
-            output.outputPubKey,
-            output.commitment
-        ));
         require(
-            verifyMerkleProofSHA256(
-                outputLeaf,
-                moneroBlocks[blockHeight].outputMerkleRoot,
-                outputMerkleProof,
-                outputIndex
-            ),
+            verifyOutputInBlock(output, blockHeight, outputMerkleProof, outputIndex),
             "Output not in block"
         );
         
@@ -566,7 +614,9 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         // Prevent double-spending
         bytes32 outputId = keccak256(abi.encodePacked(output.txHash, output.outputIndex));
         require(!usedOutputs[outputId], "Output spent");
+        require(!usedOutputKeys[output.outputPubKey], "Output key spent");
         usedOutputs[outputId] = true;
+        usedOutputKeys[output.outputPubKey] = true;
         
         // Calculate amounts (v is in piconero, we mint 1:1)
         uint256 fee = (v * lpData.mintFeeBps) / 10000;
@@ -588,6 +638,47 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         emit Minted(recipient, lp, netAmount, fee, output.txHash);
     }
     
+    /**
+     * @notice Mint for a deposit the oracle detected by scanning with the bridge view key
+     * @dev The output has to be in a posted block's output tree, so only a real
+     *      Monero output can be minted for, and only once. Shares usedOutputs
+     *      and usedOutputKeys with mint(), so an output can only be claimed
+     *      once either way. The amount is the oracle's decryption of the
+     *      output's ecdhAmount with the view key.
+     * @param recipient Address registered for the depositing subaddress
+     * @param amount Deposit amount in piconero (minted 1:1)
+     * @param output The deposit output, as it appears in the block's output tree
+     * @param blockHeight Posted block containing the output
+     * @param outputMerkleProof Sibling path of the output's leaf
+     * @param leafIndex Position of the output's leaf in the block's output tree
+     */
+    function mintDeposit(
+        address recipient,
+        uint256 amount,
+        MoneroTxOutput calldata output,
+        uint256 blockHeight,
+        bytes32[] calldata outputMerkleProof,
+        uint256 leafIndex
+    ) external onlyOracle nonReentrant {
+        require(recipient != address(0), "Invalid recipient");
+        require(amount > 0, "Invalid amount");
+        require(isBlockPosted(blockHeight), "Block not posted");
+        require(
+            verifyOutputInBlock(output, blockHeight, outputMerkleProof, leafIndex),
+            "Output not in block"
+        );
+        
+        bytes32 outputId = keccak256(abi.encodePacked(output.txHash, output.outputIndex));
+        require(!usedOutputs[outputId], "Output spent");
+        require(!usedOutputKeys[output.outputPubKey], "Output key spent");
+        usedOutputs[outputId] = true;
+        usedOutputKeys[output.outputPubKey] = true;
+        
+        _mint(recipient, amount);
+        
+        emit Minted(recipient, address(0), amount, 0, output.txHash);
+    }
+    
     // ════════════════════════════════════════════════════════════════════════
     // BURN (2-hour window)
     // ════════════════════════════════════════════════════════════════════════
@@ -662,6 +753,37 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         emit BurnFulfilled(burnId, xmrTxHash);
     }
     
+    /**
+     * @notice Oracle records that it paid a burn out of the bridge wallet
+     * @dev The tx key lets anyone check the payment with check_tx_key against
+     *      the burn's xmrAddress. The LP didn't pay, so the collateral locked
+     *      for the burn goes to the oracle, which did, rather than back to the LP.
+     * @param burnId Burn that was paid
+     * @param xmrTxHash Monero transaction paying the burn
+     * @param xmrTxKey Secret key of that transaction
+     */
+    function confirmWithdrawal(
+        uint256 burnId,
+        bytes32 xmrTxHash,
+        bytes32 xmrTxKey
+    ) external onlyOracle nonReentrant {
+        BurnRequest storage request = burnRequests[burnId];
+        require(request.user != address(0), "Unknown burn");
+        require(!request.fulfilled && !request.defaulted, "Already processed");
+        
+        request.fulfilled = true;
+        
+        // Pay the locked collateral to the oracle, which paid the XMR
+        wstETH.transfer(oracle, request.collateralLocked);
+        
+        // Return deposit to user
+        (bool success, ) = request.user.call{value: request.depositAmount}("");
+        require(success, "Deposit refund failed");
+        
+        emit BurnFulfilled(burnId, xmrTxHash);
+        emit WithdrawalConfirmed(burnId, xmrTxHash, xmrTxKey);
+    }
+    
     /**
      * @notice User claims collateral if LP defaults
      */
@@ -696,7 +818,96 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         bytes32 txMerkleRoot,
         bytes32 outputMerkleRoot
     ) external onlyOracle {
+        _postMoneroBlock(blockHeight, blockHash, txMerkleRoot, outputMerkleRoot);
+    }
+    
+    /**
+     * @notice Post Monero block attested by at least signerThreshold signers
+     * @param signatures EIP-712 MoneroBlock signatures, ordered by ascending signer address
+     */
+    function postMoneroBlockWithSignatures(
+        uint256 blockHeight,
+        bytes32 blockHash,
+        bytes32 txMerkleRoot,
+        bytes32 outputMerkleRoot,
+        bytes[] calldata signatures
+    ) external {
+        require(signerThreshold > 0, "Signers not configured");
+        require(signatures.length >= signerThreshold, "Not enough signatures");
+        
+        bytes32 digest = _hashTypedDataV4(keccak256(abi.encode(
+            MONERO_BLOCK_TYPEHASH,
+            blockHeight,
+            blockHash,
+            txMerkleRoot,
+            outputMerkleRoot
+        )));
+        
+        // Ascending order rules out counting one signer twice
+        address lastSigner = address(0);
+        for (uint256 i = 0; i < signatures.length; i++) {
+            address signer = ECDSA.recover(digest, signatures[i]);
+            require(signer > lastSigner, "Signers not sorted");
+            require(isSigner[signer], "Not a signer");
+            lastSigner = signer;
+        }
+        
+        _postMoneroBlock(blockHeight, blockHash, txMerkleRoot, outputMerkleRoot);
+    }
+    
+    /**
+     * @notice Post Monero block with a Schnorr signature by the threshold key
+     * @param signature s of the signature, s = k + e * x
+     * @param nonceAddress Address of the signature's nonce point R = s * G - e * X
+     * @dev e = keccak256(keyX, keyParity, digest, nonceAddress). R is checked with
+     *      ecrecover, which returns the address of (s * G - e * X) for the inputs below.
+     */
+    function postMoneroBlockWithThresholdSignature(
+        uint256 blockHeight,
+        bytes32 blockHash,
+        bytes32 txMerkleRoot,
+        bytes32 outputMerkleRoot,
+        uint256 signature,
+        address nonceAddress
+    ) external {
+        uint256 keyX = thresholdKeyX;
+        require(keyX != 0, "Threshold key not configured");
+        require(signature != 0 && signature < SECP256K1_N, "Invalid signature");
+        require(nonceAddress != address(0), "Invalid nonce");
+        
+        bytes32 digest = _hashTypedDataV4(keccak256(abi.encode(
+            MONERO_BLOCK_TYPEHASH,
+            blockHeight,
+            blockHash,
+            txMerkleRoot,
+            outputMerkleRoot
+        )));
+        uint256 e = uint256(keccak256(abi.encodePacked(
+            keyX,
+            thresholdKeyParity,
+            digest,
+            nonceAddress
+        ))) % SECP256K1_N;
+        
+        address recovered = ecrecover(
+            bytes32(SECP256K1_N - mulmod(signature, keyX, SECP256K1_N)),
+            thresholdKeyParity + 27,
+            bytes32(keyX),
+            bytes32(SECP256K1_N - mulmod(e, keyX, SECP256K1_N))
+        );
+        require(recovered != address(0) && recovered == nonceAddress, "Invalid threshold signature");
+        
+        _postMoneroBlock(blockHeight, blockHash, txMerkleRoot, outputMerkleRoot);
+    }
+    
+    function _postMoneroBlock(
+        uint256 blockHeight,
+        bytes32 blockHash,
+        bytes32 txMerkleRoot,
+        bytes32 outputMerkleRoot
+    ) internal {
         require(blockHeight > latestMoneroBlock, "Height must increase");
+        require(rollbackCursor == 0, "Rollback in progress");
         require(!moneroBlocks[blockHeight].exists, "Block exists");
         
         // Use positional initialization to avoid any named parameter issues
@@ -707,12 +918,265 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
             block.timestamp,
             true
         );
+        outputTreeVersionOf[blockHeight] = outputTreeVersion;
+        txTreeVersionOf[blockHeight] = txTreeVersion;
         
         latestMoneroBlock = blockHeight;
         emit MoneroBlockPosted(blockHeight, blockHash);
     }
     
+    /**
+     * @notice Post a block the oracle skipped, below the latest posted one
+     * @dev Oracles posting only checkpoint blocks fill in the blocks between
+     *      them on demand, when someone needs to prove a transaction in one.
+     *      latestMoneroBlock is left alone.
+     */
+    function fillMoneroBlock(
+        uint256 blockHeight,
+        bytes32 blockHash,
+        bytes32 txMerkleRoot,
+        bytes32 outputMerkleRoot
+    ) external onlyOracle {
+        require(blockHeight < latestMoneroBlock, "Not below latest block");
+        require(rollbackCursor == 0, "Rollback in progress");
+        require(!moneroBlocks[blockHeight].exists, "Block exists");
+        
+        moneroBlocks[blockHeight] = MoneroBlockData(
+            blockHash,
+            txMerkleRoot,
+            outputMerkleRoot,
+            block.timestamp,
+            true
+        );
+        outputTreeVersionOf[blockHeight] = outputTreeVersion;
+        txTreeVersionOf[blockHeight] = txTreeVersion;
+        
+        emit MoneroBlockPosted(blockHeight, blockHash);
+    }
+    
+    /**
+     * @notice Discard posted blocks orphaned by a Monero reorg
+     * @param fromHeight First orphaned height; it and all later blocks are cleared
+     * @dev latestMoneroBlock drops to fromHeight - 1 at once, and blocks above it no
+     *      longer count as posted. Their storage is cleared MAX_ROLLBACK_BLOCKS at a
+     *      time: this clears the first batch, continueRollback() the rest, and no
+     *      block can be posted or filled until the last is gone. A deeper fork found
+     *      before then starts over from its own height.
+     */
+    function rollbackMoneroBlocks(uint256 fromHeight) external onlyOracle {
+        require(fromHeight > 0 && fromHeight <= latestMoneroBlock, "Invalid rollback height");
+        
+        uint256 previousLatest = rollbackCursor == 0 ? latestMoneroBlock : rollbackEnd;
+        rollbackCursor = fromHeight;
+        rollbackEnd = previousLatest;
+        latestMoneroBlock = fromHeight - 1;
+        emit MoneroBlocksRolledBack(fromHeight, previousLatest);
+        
+        _clearRolledBackBlocks();
+    }
+    
+    /**
+     * @notice Clear the next MAX_ROLLBACK_BLOCKS blocks of an unfinished rollback
+     */
+    function continueRollback() external onlyOracle {
+        require(rollbackCursor != 0, "No rollback in progress");
+        _clearRolledBackBlocks();
+    }
+    
+    function _clearRolledBackBlocks() internal {
+        uint256 from = rollbackCursor;
+        uint256 end = rollbackEnd;
+        uint256 to = end - from < MAX_ROLLBACK_BLOCKS ? end : from + MAX_ROLLBACK_BLOCKS - 1;
+        for (uint256 h = from; h <= to; h++) {
+            delete moneroBlocks[h];
+            delete outputTreeVersionOf[h];
+            delete txTreeVersionOf[h];
+        }
+        
+        bool finished = to == end;
+        if (finished) {
+            rollbackCursor = 0;
+            rollbackEnd = 0;
+        } else {
+            rollbackCursor = to + 1;
+        }
+        emit MoneroBlocksCleared(from, to, finished);
+    }
+    
+    /**
+     * @notice Whether the block at blockHeight is posted: stored, and not above
+     *         latestMoneroBlock as orphaned blocks an unfinished rollback has yet
+     *         to clear are
+     */
+    function isBlockPosted(uint256 blockHeight) public view returns (bool) {
+        return moneroBlocks[blockHeight].exists && blockHeight <= latestMoneroBlock;
+    }
+    
+    /**
+     * @notice Replace the attestation signer set
+     * @param newSigners Signer addresses; an empty set with threshold 0 disables signature posting
+     * @param threshold Signatures required by postMoneroBlockWithSignatures
+     */
+    function setSigners(address[] calldata newSigners, uint256 threshold) external onlyOracle {
+        require(threshold <= newSigners.length, "Threshold exceeds signers");
+        require(threshold > 0 || newSigners.length == 0, "Threshold required");
+        
+        for (uint256 i = 0; i < signers.length; i++) {
+            isSigner[signers[i]] = false;
+        }
+        for (uint256 i = 0; i < newSigners.length; i++) {
+            require(newSigners[i] != address(0), "Invalid signer");
+            require(!isSigner[newSigners[i]], "Duplicate signer");
+            isSigner[newSigners[i]] = true;
+        }
+        
+        signers = newSigners;
+        signerThreshold = threshold;
+        emit SignersUpdated(newSigners, threshold);
+    }
+    
+    /**
+     * @notice Set the FROST group key for postMoneroBlockWithThresholdSignature
+     * @param keyX x coordinate of the group key; 0 disables threshold posting
+     * @param keyParity 0 if the key's y coordinate is even, 1 if odd
+     */
+    function setThresholdKey(uint256 keyX, uint8 keyParity) external onlyOracle {
+        require(keyX < SECP256K1_N, "Key x not below curve order");
+        require(keyParity <= 1, "Invalid parity");
+        
+        thresholdKeyX = keyX;
+        thresholdKeyParity = keyParity;
+        emit ThresholdKeyUpdated(keyX, keyParity);
+    }
+    
+    /**
+     * @notice Set the output tree version blocks are posted with from now on
+     * @dev Blocks already posted keep the version they were posted with, so their
+     *      proofs stay valid. Switch only together with the oracle's OUTPUT_MERKLE_HASHER.
+     * @param version 1 (legacy leaves and nodes), 2 (canonical, domain-separated)
+     *        or 3 (legacy leaves, SHA-256 nodes)
+     */
+    function setOutputTreeVersion(uint8 version) external onlyOracle {
+        require(version >= 1 && version <= 3, "Unknown output tree version");
+        
+        outputTreeVersion = version;
+        emit OutputTreeVersionUpdated(version);
+    }
+    
+    /**
+     * @notice Correct the output tree version recorded for posted blocks built
+     *         with SHA-256 nodes (3) or keccak256 nodes (1)
+     * @dev Blocks posted by an oracle on OUTPUT_MERKLE_HASHER=sha256 before version 3
+     *      existed were recorded as 0 or 1 and verify as keccak256 trees, so their
+     *      outputs can't be minted until this records them as 3. Version 2 blocks
+     *      are left alone: their roots commit to their version. Heights not posted
+     *      are skipped; split long ranges to stay under the block gas limit.
+     * @param fromHeight First block to record
+     * @param toHeight Last block to record
+     * @param version 1 or 3
+     */
+    function recordOutputTreeVersion(
+        uint256 fromHeight,
+        uint256 toHeight,
+        uint8 version
+    ) external onlyOracle {
+        require(version == 1 || version == 3, "Only versions 1 and 3 share leaves");
+        require(fromHeight <= toHeight, "Empty range");
+        
+        for (uint256 h = fromHeight; h <= toHeight; h++) {
+            if (isBlockPosted(h) && outputTreeVersionOf[h] != 2) {
+                outputTreeVersionOf[h] = version;
+            }
+        }
+        emit OutputTreeVersionRecorded(fromHeight, toHeight, version);
+    }
+    
+    /**
+     * @notice Set the tx tree version blocks are posted with from now on
+     * @dev Version 1 tx trees take raw tx hashes as leaves, so an internal node
+     *      passes verifyTxInBlock as a transaction; version 2 closes that.
+     *      Switch only together with the oracle's TX_MERKLE_HASHER.
+     * @param version 1 (raw leaves, plain nodes) or 2 (domain-separated)
+     */
+    function setTxTreeVersion(uint8 version) external onlyOracle {
+        require(version == 1 || version == 2, "Unknown tx tree version");
+        
+        txTreeVersion = version;
+        emit TxTreeVersionUpdated(version);
+    }
+    
+    /**
+     * @notice Current attestation signer set
+     */
+    function getSigners() external view returns (address[] memory) {
+        return signers;
+    }
+    
+    /**
+     * @notice Take or renew the lease of a redundant oracle instance
+     * @dev Succeeds for the instance holding it, or for any once it has run
+     *      out. Callable by the oracle or a signer, whose instances share
+     *      keys; the lease only coordinates them and guards nothing else.
+     */
+    function acquireLease(bytes32 holder, uint256 duration) external {
+        require(msg.sender == oracle || isSigner[msg.sender], "Only oracle or signer");
+        require(holder != bytes32(0), "Invalid holder");
+        require(duration > 0 && duration <= MAX_LEASE_DURATION, "Invalid lease duration");
+        require(
+            leaseHolder == holder || block.timestamp >= leaseExpiresAt,
+            "Lease held by another instance"
+        );
+        
+        leaseHolder = holder;
+        leaseExpiresAt = block.timestamp + duration;
+        emit LeaseAcquired(holder, leaseExpiresAt);
+    }
+    
+    /**
+     * @notice End the lease early, so a standby instance takes over at once
+     */
+    function releaseLease(bytes32 holder) external {
+        require(msg.sender == oracle || isSigner[msg.sender], "Only oracle or signer");
+        require(leaseHolder == holder, "Not the lease holder");
+        
+        if (leaseExpiresAt > block.timestamp) {
+            leaseExpiresAt = block.timestamp;
+        }
+        emit LeaseReleased(holder);
+    }
+    
+    /**
+     * @notice Current lease holder and the seconds left on its lease
+     */
+    function getLease() external view returns (bytes32 holder, uint256 remaining) {
+        holder = leaseHolder;
+        remaining = leaseExpiresAt > block.timestamp ? leaseExpiresAt - block.timestamp : 0;
+    }
+    
+    /**
+     * @notice Show the oracle is alive while no block needs posting
+     * @param moneroTip Monero chain height the oracle sees, for monitoring
+     */
+    function heartbeat(uint256 moneroTip) external {
+        require(msg.sender == oracle || isSigner[msg.sender], "Only oracle or signer");
+        
+        lastHeartbeat = block.timestamp;
+        emit OracleHeartbeat(msg.sender, moneroTip, latestMoneroBlock);
+    }
+    
+    /**
+     * @notice When the oracle was last active on-chain: its latest heartbeat
+     *         or the posting of the latest block, whichever is later
+     * @dev Contracts relying on fresh blocks treat the oracle as down once
+     *      this is older than they tolerate
+     */
+    function lastOracleActivity() external view returns (uint256) {
+        uint256 posted = moneroBlocks[latestMoneroBlock].timestamp;
+        return posted > lastHeartbeat ? posted : lastHeartbeat;
+    }
+    
     function transferOracle(address newOracle) external onlyOracle {
+        emit OracleTransferred(oracle, newOracle);
         oracle = newOracle;
     }
     
@@ -764,9 +1228,23 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         bytes32[] memory merkleProof,
         uint256 index
     ) public view returns (bool) {
-        require(moneroBlocks[blockHeight].exists, "Block not posted");
+        require(isBlockPosted(blockHeight), "Block not posted");
         bytes32 root = moneroBlocks[blockHeight].txMerkleRoot;
         
+        if (txTreeVersionOf[blockHeight] == 2) {
+            // Leaf keccak256(LEAF_PREFIX ++ txHash), nodes and root as in verifyOutputProofV2
+            bytes32 leafHash = keccak256(abi.encodePacked(LEAF_PREFIX, txHash));
+            for (uint256 i = 0; i < merkleProof.length; i++) {
+                if (index % 2 == 0) {
+                    leafHash = keccak256(abi.encodePacked(NODE_PREFIX, leafHash, merkleProof[i]));
+                } else {
+                    leafHash = keccak256(abi.encodePacked(NODE_PREFIX, merkleProof[i], leafHash));
+                }
+                index = index / 2;
+            }
+            return keccak256(abi.encodePacked(ROOT_PREFIX, uint8(2), leafHash)) == root;
+        }
+        
         // Manually verify instead of calling verifyMerkleProof to avoid calldata/memory issues
         bytes32 computedHash = txHash;
         for (uint256 i = 0; i < merkleProof.length; i++) {
@@ -804,6 +1282,86 @@ contract WrappedMonero is ERC20, ERC20Permit, ReentrancyGuard {
         return computedHash == root;
     }
     
+    /**
+     * @notice Check an output against a posted block's output tree, in the
+     *         format the block was posted with
+     */
+    function verifyOutputInBlock(
+        MoneroTxOutput calldata output,
+        uint256 blockHeight,
+        bytes32[] calldata proof,
+        uint256 index
+    ) public view returns (bool) {
+        if (!isBlockPosted(blockHeight)) {
+            return false;
+        }
+        bytes32 root = moneroBlocks[blockHeight].outputMerkleRoot;
+        uint8 version = outputTreeVersionOf[blockHeight];
+        if (version == 2) {
+            return verifyOutputProofV2(outputLeafV2(output), root, proof, index);
+        }
+        bytes32 leaf = keccak256(abi.encodePacked(
+            output.txHash,
+            output.outputIndex,
+            output.ecdhAmount,
+            output.outputPubKey,
+            output.commitment
+        ));
+        if (version == 3) {
+            return verifyMerkleProofSHA256(leaf, root, proof, index);
+        }
+        return verifyMerkleProof(leaf, root, proof, index);
+    }
+    
+    /**
+     * @notice Output leaf in version 2 trees
+     * @dev keccak256(0x00 ++ txHash ++ outputIndex ++ ecdhAmount ++ outputPubKey ++ commitment)
+     */
+    function outputLeafV2(MoneroTxOutput calldata output) public pure returns (bytes32) {
+        return keccak256(abi.encodePacked(
+            LEAF_PREFIX,
+            output.txHash,
+            output.outputIndex,
+            output.ecdhAmount,
+            output.outputPubKey,
+            output.commitment
+        ));
+    }
+    
+    /**
+     * @notice Output proof check for version 2 trees
+     * @dev Nodes are keccak256(0x01 ++ left ++ right); the root is
+     *      keccak256(0x02 ++ uint8(2) ++ top node), so a proof built for another
+     *      version never verifies against it
+     */
+    function verifyOutputProofV2(
+        bytes32 leaf,
+        bytes32 root,
+        bytes32[] calldata proof,
+        uint256 index
+    ) public pure returns (bool) {
+        bytes32 computedHash = leaf;
+        
+        for (uint256 i = 0; i < proof.length; i++) {
+            bytes32 proofElement = proof[i];
+            
+            if (index % 2 == 0) {
+                computedHash = keccak256(abi.encodePacked(NODE_PREFIX, computedHash, proofElement));
+            } else {
+                computedHash = keccak256(abi.encodePacked(NODE_PREFIX, proofElement, computedHash));
+            }
+            
+            index = index / 2;
+        }
+        
+        return keccak256(abi.encodePacked(ROOT_PREFIX, uint8(2), computedHash)) == root;
+    }
+    
+    /**
+     * @notice Legacy output proof check for trees with SHA-256 internal nodes
+     * @dev Output tree version 3; version 1 uses keccak256 throughout, see
+     *      verifyMerkleProof
+     */
     function verifyMerkleProofSHA256(
         bytes32 leaf,
         bytes32 root,
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/**
+ * @title MockWrappedMonero
+ * @notice The oracle-facing part of WrappedMonero, for end-to-end tests
+ * @dev Block posting, rollback and Merkle proof checks are copied from
+ *      WrappedMonero unchanged; the LP system, Pyth and the ZK verifier are
+ *      left out so it deploys on a bare local chain. Keep the copied
+ *      functions in step with WrappedMonero.sol.
+ */
+contract MockWrappedMonero {
+
+    struct MoneroBlockData {
+        bytes32 blockHash;
+        bytes32 txMerkleRoot;
+        bytes32 outputMerkleRoot;
+        uint256 timestamp;
+        bool exists;
+    }
+
+    address public oracle;
+    mapping(uint256 => MoneroBlockData) public moneroBlocks;
+    uint256 public latestMoneroBlock;
+    uint256 public constant MAX_ROLLBACK_BLOCKS = 256;
+    uint256 public rollbackCursor;
+    uint256 public rollbackEnd;
+    mapping(bytes32 => bool) public usedOutputs;
+
+    // Signature posting isn't mocked; a zero threshold disables it
+    mapping(address => bool) public isSigner;
+    uint256 public signerThreshold;
+
+    event MoneroBlockPosted(uint256 indexed blockHeight, bytes32 indexed blockHash);
+    event MoneroBlocksRolledBack(uint256 indexed fromHeight, uint256 previousLatest);
+    event MoneroBlocksCleared(uint256 fromHeight, uint256 toHeight, bool finished);
+    event OracleTransferred(address indexed previousOracle, address indexed newOracle);
+
+    modifier onlyOracle() {
+        require(msg.sender == oracle, "Only oracle");
+        _;
+    }
+
+    constructor(uint256 _initialMoneroBlock) {
+        oracle = msg.sender;
+        latestMoneroBlock = _initialMoneroBlock;
+    }
+
+    function postMoneroBlock(
+        uint256 blockHeight,
+        bytes32 blockHash,
+        bytes32 txMerkleRoot,
+        bytes32 outputMerkleRoot
+    ) external onlyOracle {
+        require(blockHeight > latestMoneroBlock, "Height must increase");
+        require(rollbackCursor == 0, "Rollback in progress");
+        require(!moneroBlocks[blockHeight].exists, "Block exists");
+
+        moneroBlocks[blockHeight] = MoneroBlockData(
+            blockHash,
+            txMerkleRoot,
+            outputMerkleRoot,
+            block.timestamp,
+            true
+        );
+
+        latestMoneroBlock = blockHeight;
+        emit MoneroBlockPosted(blockHeight, blockHash);
+    }
+
+    function fillMoneroBlock(
+        uint256 blockHeight,
+        bytes32 blockHash,
+        bytes32 txMerkleRoot,
+        bytes32 outputMerkleRoot
+    ) external onlyOracle {
+        require(blockHeight < latestMoneroBlock, "Not below latest block");
+        require(rollbackCursor == 0, "Rollback in progress");
+        require(!moneroBlocks[blockHeight].exists, "Block exists");
+
+        moneroBlocks[blockHeight] = MoneroBlockData(
+            blockHash,
+            txMerkleRoot,
+            outputMerkleRoot,
+            block.timestamp,
+            true
+        );
+
+        emit MoneroBlockPosted(blockHeight, blockHash);
+    }
+
+    function rollbackMoneroBlocks(uint256 fromHeight) external onlyOracle {
+        require(fromHeight > 0 && fromHeight <= latestMoneroBlock, "Invalid rollback height");
+
+        uint256 previousLatest = rollbackCursor == 0 ? latestMoneroBlock : rollbackEnd;
+        rollbackCursor = fromHeight;
+        rollbackEnd = previousLatest;
+        latestMoneroBlock = fromHeight - 1;
+        emit MoneroBlocksRolledBack(fromHeight, previousLatest);
+
+        _clearRolledBackBlocks();
+    }
+
+    function continueRollback() external onlyOracle {
+        require(rollbackCursor != 0, "No rollback in progress");
+        _clearRolledBackBlocks();
+    }
+
+    function _clearRolledBackBlocks() internal {
+        uint256 from = rollbackCursor;
+        uint256 end = rollbackEnd;
+        uint256 to = end - from < MAX_ROLLBACK_BLOCKS ? end : from + MAX_ROLLBACK_BLOCKS - 1;
+        for (uint256 h = from; h <= to; h++) {
+            delete moneroBlocks[h];
+        }
+
+        bool finished = to == end;
+        if (finished) {
+            rollbackCursor = 0;
+            rollbackEnd = 0;
+        } else {
+            rollbackCursor = to + 1;
+        }
+        emit MoneroBlocksCleared(from, to, finished);
+    }
+
+    function isBlockPosted(uint256 blockHeight) public view returns (bool) {
+        return moneroBlocks[blockHeight].exists && blockHeight <= latestMoneroBlock;
+    }
+
+    function transferOracle(address newOracle) external onlyOracle {
+        emit OracleTransferred(oracle, newOracle);
+        oracle = newOracle;
+    }
+
+    function verifyTxInBlock(
+        bytes32 txHash,
+        uint256 blockHeight,
+        bytes32[] memory merkleProof,
+        uint256 index
+    ) public view returns (bool) {
+        require(isBlockPosted(blockHeight), "Block not posted");
+        bytes32 root = moneroBlocks[blockHeight].txMerkleRoot;
+
+        bytes32 computedHash = txHash;
+        for (uint256 i = 0; i < merkleProof.length; i++) {
+            bytes32 proofElement = merkleProof[i];
+            if (index % 2 == 0) {
+                computedHash = keccak256(abi.encodePacked(computedHash, proofElement));
+            } else {
+                computedHash = keccak256(abi.encodePacked(proofElement, computedHash));
+            }
+            index = index / 2;
+        }
+        return computedHash == root;
+    }
+
+    function verifyMerkleProof(
+        bytes32 leaf,
+        bytes32 root,
+        bytes32[] calldata proof,
+        uint256 index
+    ) public pure returns (bool) {
+        bytes32 computedHash = leaf;
+
+        for (uint256 i = 0; i < proof.length; i++) {
+            bytes32 proofElement = proof[i];
+
+            if (index % 2 == 0) {
+                computedHash = keccak256(abi.encodePacked(computedHash, proofElement));
+            } else {
+                computedHash = keccak256(abi.encodePacked(proofElement, computedHash));
+            }
+
+            index = index / 2;
+        }
+
+        return computedHash == root;
+    }
+
+    /**
+     * @notice The output leaf WrappedMonero.mint hashes before checking its proof
+     */
+    function outputLeaf(
+        bytes32 txHash,
+        uint256 outputIndex,
+        bytes32 ecdhAmount,
+        bytes32 outputPubKey,
+        bytes32 commitment
+    ) external pure returns (bytes32) {
+        return keccak256(abi.encodePacked(
+            txHash,
+            outputIndex,
+            ecdhAmount,
+            outputPubKey,
+            commitment
+        ));
+    }
+}
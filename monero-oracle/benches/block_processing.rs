@@ -0,0 +1,101 @@
+//! The backfill hot path for each block recorded under `tests/fixtures`:
+//! parsing monerod's JSON responses and decoding the transactions, extracting
+//! the outputs, and building both Merkle roots. Blocks are labelled by their
+//! transaction count and fixture directory, which says whether the block is
+//! from mainnet or synthetic; blocks with none, about 50 and about 500 cover
+//! the range (see `tests/fixtures/README.md`). Without them there is nothing to
+//! time, so the bench fails instead of passing empty.
+//!
+//! Run with `cargo bench --bench block_processing`. Save a baseline before a
+//! change with `-- --save-baseline main` and compare against it afterwards
+//! with `-- --baseline main`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use monero_oracle::{
+    fixtures::{RecordedBlock, RecordedResponses, EXPECTED_FILE},
+    merkle::{compute_output_merkle_root, compute_tx_merkle_root},
+    monero_rpc::{outputs_from_transactions, transaction_order},
+};
+use std::{fs, path::PathBuf};
+
+/// Each recorded block's directory name and responses, with what it was
+/// recorded with, in order of transaction count
+fn recorded_blocks() -> Vec<(String, RecordedBlock, RecordedResponses, usize)> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut blocks: Vec<_> = fs::read_dir(&root)
+        .unwrap_or_else(|e| panic!("{}: {}", root.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|dir| dir.join(EXPECTED_FILE).is_file())
+        .map(|dir| {
+            let json = fs::read_to_string(dir.join(EXPECTED_FILE)).unwrap();
+            let expected: RecordedBlock =
+                serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+            let responses = RecordedResponses::load(&dir, expected.height)
+                .unwrap_or_else(|e| panic!("{}: {:#}", dir.display(), e));
+            let tx_count = responses.tx_count().unwrap();
+            let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+            (name, expected, responses, tx_count)
+        })
+        .collect();
+    blocks.sort_by_key(|(_, block, _, tx_count)| (*tx_count, block.height));
+    assert!(
+        !blocks.is_empty(),
+        "No blocks recorded under {}: record some with `record-fixture`",
+        root.display()
+    );
+    blocks
+}
+
+fn id(name: &str, tx_count: usize) -> BenchmarkId {
+    BenchmarkId::from_parameter(format!("{} txs ({})", tx_count, name))
+}
+
+fn parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_block");
+    for (name, _, responses, tx_count) in recorded_blocks() {
+        group.throughput(Throughput::Elements(tx_count as u64 + 1));
+        group.bench_with_input(id(&name, tx_count), &responses, |b, responses| {
+            b.iter(|| responses.parse().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn extraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_outputs");
+    for (name, block, responses, tx_count) in recorded_blocks() {
+        let fetched = responses.parse().unwrap();
+        group.throughput(Throughput::Elements(block.output_count as u64));
+        group.bench_with_input(
+            id(&name, tx_count),
+            &fetched.transactions,
+            |b, transactions| b.iter(|| outputs_from_transactions(transactions).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn merkle_roots(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_roots");
+    for (name, block, responses, tx_count) in recorded_blocks() {
+        let fetched = responses.parse().unwrap();
+        let outputs = outputs_from_transactions(&fetched.transactions).unwrap();
+        let tx_order = transaction_order(&fetched.transactions).unwrap();
+        group.throughput(Throughput::Elements(block.output_count as u64));
+        group.bench_function(id(&name, tx_count), |b| {
+            b.iter(|| {
+                (
+                    compute_tx_merkle_root(&fetched.tx_hashes, block.tx_merkle_hasher),
+                    compute_output_merkle_root(
+                        &block.output_merkle_hasher.output_order(&outputs, &tx_order),
+                        block.output_merkle_hasher,
+                    ),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parsing, extraction, merkle_roots);
+criterion_main!(benches);
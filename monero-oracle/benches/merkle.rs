@@ -0,0 +1,77 @@
+//! Output and tx Merkle roots of busy blocks, on one thread and on the full
+//! rayon pool, to show what splitting large levels buys backfills.
+//!
+//! Run with `cargo bench --bench merkle`.
+
+use alloy::primitives::{keccak256, B256};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use monero_oracle::{
+    merkle::{compute_output_merkle_root, compute_tx_merkle_root, MerkleHasher},
+    monero_rpc::MoneroOutput,
+};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Outputs (or transactions) per block, from busy to far busier than Monero
+/// has seen
+const SIZES: [usize; 4] = [256, 1_024, 4_096, 16_384];
+
+fn outputs(count: usize) -> Vec<MoneroOutput> {
+    (0..count as u64)
+        .map(|i| MoneroOutput {
+            tx_hash: keccak256(i.to_be_bytes()),
+            output_index: i % 2,
+            ecdh_amount: B256::repeat_byte(0x11),
+            output_pub_key: keccak256((i + 1).to_be_bytes()),
+            commitment: B256::repeat_byte(0x22),
+        })
+        .collect()
+}
+
+fn tx_hashes(count: usize) -> Vec<String> {
+    (0..count as u64)
+        .map(|i| hex::encode(keccak256(i.to_be_bytes())))
+        .collect()
+}
+
+fn pools() -> [(&'static str, ThreadPool); 2] {
+    let single = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    let all = ThreadPoolBuilder::new().build().unwrap();
+    [("1 thread", single), ("all threads", all)]
+}
+
+fn output_roots(c: &mut Criterion) {
+    let pools = pools();
+    let mut group = c.benchmark_group("output_merkle_root");
+    for size in SIZES {
+        let outputs = outputs(size);
+        group.throughput(Throughput::Elements(size as u64));
+        for (name, pool) in &pools {
+            group.bench_with_input(BenchmarkId::new(*name, size), &outputs, |b, outputs| {
+                b.iter(|| {
+                    pool.install(|| compute_output_merkle_root(outputs, MerkleHasher::Keccak256))
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn tx_roots(c: &mut Criterion) {
+    let pools = pools();
+    let mut group = c.benchmark_group("tx_merkle_root");
+    for size in SIZES {
+        let tx_hashes = tx_hashes(size);
+        group.throughput(Throughput::Elements(size as u64));
+        for (name, pool) in &pools {
+            group.bench_with_input(BenchmarkId::new(*name, size), &tx_hashes, |b, tx_hashes| {
+                b.iter(|| {
+                    pool.install(|| compute_tx_merkle_root(tx_hashes, MerkleHasher::Keccak256))
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, output_roots, tx_roots);
+criterion_main!(benches);
@@ -0,0 +1,6 @@
+fn main() {
+    // The gRPC API's types and service trait, generated with protoc
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/monero_oracle/v1/oracle.proto")
+        .expect("Failed to compile oracle.proto");
+}
@@ -0,0 +1,254 @@
+-- Schema as of the first versioned release. Every statement is idempotent,
+-- so databases created before migrations were versioned take it as is.
+
+CREATE TABLE IF NOT EXISTS posted_blocks (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    height              INTEGER NOT NULL,
+    block_hash          TEXT NOT NULL,
+    tx_merkle_root      TEXT NOT NULL,
+    output_merkle_root  TEXT NOT NULL,
+    unichain_tx_hash    TEXT NOT NULL,
+    unichain_block      INTEGER,
+    gas_used            INTEGER NOT NULL,
+    posted_at           TEXT NOT NULL,
+    orphaned            INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS idx_posted_blocks_height ON posted_blocks(height);
+
+CREATE TABLE IF NOT EXISTS deposits (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    block_height        INTEGER NOT NULL,
+    tx_hash             TEXT NOT NULL,
+    output_index        INTEGER NOT NULL,
+    amount              INTEGER NOT NULL,
+    output_pub_key      TEXT NOT NULL,
+    subaddress_major    INTEGER NOT NULL DEFAULT 0,
+    subaddress_minor    INTEGER NOT NULL DEFAULT 0,
+    detected_at         TEXT NOT NULL,
+    orphaned            INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (tx_hash, output_index)
+);
+CREATE INDEX IF NOT EXISTS idx_deposits_height ON deposits(block_height);
+CREATE INDEX IF NOT EXISTS idx_deposits_subaddress ON deposits(subaddress_major, subaddress_minor);
+
+CREATE TABLE IF NOT EXISTS pending_deposits (
+    tx_hash             TEXT NOT NULL,
+    output_index        INTEGER NOT NULL,
+    amount              INTEGER NOT NULL,
+    subaddress_major    INTEGER NOT NULL,
+    subaddress_minor    INTEGER NOT NULL,
+    block_height        INTEGER,
+    first_seen_at       TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, output_index)
+);
+
+CREATE TABLE IF NOT EXISTS deposit_payment_ids (
+    tx_hash             TEXT NOT NULL,
+    output_index        INTEGER NOT NULL,
+    payment_id          TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, output_index)
+);
+CREATE INDEX IF NOT EXISTS idx_deposit_payment_ids ON deposit_payment_ids(payment_id);
+
+CREATE TABLE IF NOT EXISTS payment_id_recipients (
+    payment_id          TEXT PRIMARY KEY,
+    address             TEXT NOT NULL,
+    registered_at       TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS recipients (
+    subaddress_major    INTEGER NOT NULL,
+    subaddress_minor    INTEGER NOT NULL,
+    address             TEXT NOT NULL,
+    registered_at       TEXT NOT NULL,
+    PRIMARY KEY (subaddress_major, subaddress_minor)
+);
+
+CREATE TABLE IF NOT EXISTS mints (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    tx_hash             TEXT NOT NULL,
+    output_index        INTEGER NOT NULL,
+    recipient           TEXT NOT NULL,
+    amount              INTEGER NOT NULL,
+    unichain_tx_hash    TEXT NOT NULL,
+    minted_at           TEXT NOT NULL,
+    UNIQUE (tx_hash, output_index)
+);
+CREATE INDEX IF NOT EXISTS idx_mints_recipient ON mints(recipient, minted_at);
+
+-- Deposits outside the deposit limits, waiting for an operator
+CREATE TABLE IF NOT EXISTS deposit_reviews (
+    tx_hash             TEXT NOT NULL,
+    output_index        INTEGER NOT NULL,
+    reason              TEXT NOT NULL,
+    status              TEXT NOT NULL,
+    held_at             TEXT NOT NULL,
+    reviewed_at         TEXT,
+    PRIMARY KEY (tx_hash, output_index)
+);
+
+-- Bridge fees, quoted once per mint or payout and collected when it goes
+-- through
+CREATE TABLE IF NOT EXISTS bridge_fees (
+    kind                TEXT NOT NULL,
+    reference           TEXT NOT NULL,
+    amount              INTEGER NOT NULL,
+    fee                 INTEGER NOT NULL,
+    usd_per_xmr         REAL,
+    recorded_at         TEXT NOT NULL,
+    collected_at        TEXT,
+    PRIMARY KEY (kind, reference)
+);
+
+-- Collected fees sent to the treasury
+CREATE TABLE IF NOT EXISTS fee_sweeps (
+    tx_hash             TEXT PRIMARY KEY,
+    amount              INTEGER NOT NULL,
+    network_fee         INTEGER NOT NULL,
+    swept_at            TEXT NOT NULL
+);
+
+-- Multisig signing rounds this oracle coordinated or co-signed
+CREATE TABLE IF NOT EXISTS multisig_rounds (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    key                 TEXT NOT NULL,
+    role                TEXT NOT NULL,
+    status              TEXT NOT NULL,
+    signers             TEXT NOT NULL DEFAULT '',
+    tx_data_hex         TEXT NOT NULL,
+    tx_hash             TEXT,
+    error               TEXT,
+    created_at          TEXT NOT NULL,
+    updated_at          TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_multisig_rounds_status ON multisig_rounds(status);
+
+CREATE TABLE IF NOT EXISTS withdrawals (
+    burn_id             INTEGER PRIMARY KEY,
+    user                TEXT NOT NULL,
+    lp                  TEXT NOT NULL,
+    amount              INTEGER NOT NULL,
+    xmr_address         TEXT NOT NULL,
+    unichain_tx_hash    TEXT NOT NULL,
+    unichain_block      INTEGER NOT NULL,
+    status              TEXT NOT NULL,
+    error               TEXT,
+    attempts            INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at     TEXT,
+    xmr_tx_hash         TEXT,
+    xmr_tx_key          TEXT,
+    xmr_fee             INTEGER,
+    xmr_tx_metadata     TEXT,
+    created_at          TEXT NOT NULL,
+    updated_at          TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_withdrawals_status ON withdrawals(status);
+
+CREATE TABLE IF NOT EXISTS withdrawal_payouts (
+    burn_id             INTEGER PRIMARY KEY,
+    xmr_tx_hash         TEXT NOT NULL,
+    amount              INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_withdrawal_payouts_tx ON withdrawal_payouts(xmr_tx_hash);
+
+CREATE TABLE IF NOT EXISTS bridge_outputs (
+    key_image           TEXT PRIMARY KEY,
+    tx_hash             TEXT NOT NULL,
+    amount              INTEGER NOT NULL,
+    spent_height        INTEGER,
+    spent_tx_hash       TEXT,
+    discovered_at       TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS attestations (
+    height              INTEGER NOT NULL,
+    signer              TEXT NOT NULL,
+    block_hash          TEXT NOT NULL,
+    tx_merkle_root      TEXT NOT NULL,
+    output_merkle_root  TEXT NOT NULL,
+    signature           TEXT NOT NULL,
+    created_at          TEXT NOT NULL,
+    PRIMARY KEY (height, signer)
+);
+
+CREATE TABLE IF NOT EXISTS sync_state (
+    name                TEXT PRIMARY KEY,
+    value               INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chain_work (
+    height                  INTEGER PRIMARY KEY,
+    block_hash              TEXT NOT NULL,
+    cumulative_difficulty   TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS indexed_blocks (
+    height              INTEGER PRIMARY KEY,
+    block_hash          TEXT NOT NULL,
+    output_count        INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS outputs (
+    block_height        INTEGER NOT NULL,
+    leaf_index          INTEGER NOT NULL,
+    tx_hash             TEXT NOT NULL,
+    output_index        INTEGER NOT NULL,
+    ecdh_amount         TEXT NOT NULL,
+    output_pub_key      TEXT NOT NULL,
+    commitment          TEXT NOT NULL,
+    PRIMARY KEY (block_height, leaf_index)
+);
+CREATE INDEX IF NOT EXISTS idx_outputs_tx ON outputs(tx_hash, output_index);
+CREATE INDEX IF NOT EXISTS idx_outputs_pub_key ON outputs(output_pub_key);
+
+CREATE TABLE IF NOT EXISTS merkle_trees (
+    height              INTEGER NOT NULL,
+    kind                TEXT NOT NULL,
+    block_hash          TEXT NOT NULL,
+    hasher              TEXT NOT NULL,
+    leaf_count          INTEGER NOT NULL,
+    -- Every node, level by level from the leaves
+    nodes               BLOB NOT NULL,
+    PRIMARY KEY (height, kind)
+);
+
+CREATE TABLE IF NOT EXISTS safe_proposals (
+    height              INTEGER PRIMARY KEY,
+    block_hash          TEXT NOT NULL,
+    safe_tx_hash        TEXT NOT NULL,
+    nonce               INTEGER NOT NULL,
+    proposed_at         TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS operations (
+    key                 TEXT PRIMARY KEY,
+    kind                TEXT NOT NULL,
+    status              TEXT NOT NULL,
+    tx_hash             TEXT,
+    error               TEXT,
+    created_at          TEXT NOT NULL,
+    updated_at          TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_operations_status ON operations(status);
+
+CREATE TABLE IF NOT EXISTS operation_transactions (
+    key                 TEXT NOT NULL,
+    tx_hash             TEXT NOT NULL,
+    sent_at             TEXT NOT NULL,
+    PRIMARY KEY (key, tx_hash)
+);
+
+CREATE TABLE IF NOT EXISTS tx_costs (
+    tx_hash             TEXT PRIMARY KEY,
+    kind                TEXT NOT NULL,
+    gas_used            INTEGER NOT NULL,
+    -- Wei, as text: it can exceed an INTEGER
+    gas_price           TEXT NOT NULL,
+    xmr_fee             INTEGER NOT NULL,
+    recorded_at         TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_tx_costs_recorded_at ON tx_costs(recorded_at);
+
+-- Withdrawal stages before the Monero confirmation step was split out
+UPDATE withdrawals SET status = 'broadcast' WHERE status = 'sent';
+UPDATE withdrawals SET status = 'confirmed_evm' WHERE status = 'confirmed';
@@ -0,0 +1,5 @@
+-- When each posting's receipt was re-checked at UNICHAIN_CONFIRMATION_DEPTH.
+-- Postings from before this migration are taken as settled.
+
+ALTER TABLE posted_blocks ADD COLUMN confirmed_at TEXT;
+UPDATE posted_blocks SET confirmed_at = posted_at;
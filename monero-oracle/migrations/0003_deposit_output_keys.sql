@@ -0,0 +1,24 @@
+-- One deposit per one-time output key. Transactions that reuse a tx secret
+-- key give their outputs the same one-time key (Monero's "burning bug"),
+-- and the wallet can only ever spend one of them. Later outputs with a key
+-- already credited are held for review rather than recorded.
+--
+-- Duplicates recorded before this migration are held the same way and
+-- dropped from deposits, keeping the first.
+
+INSERT OR IGNORE INTO deposit_reviews (tx_hash, output_index, reason, status, held_at)
+SELECT d.tx_hash, d.output_index,
+       'Output key ' || d.output_pub_key || ' already credited (burnt output)',
+       'held', strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+FROM deposits d
+WHERE EXISTS (
+    SELECT 1 FROM deposits e WHERE e.output_pub_key = d.output_pub_key AND e.id < d.id
+);
+
+DELETE FROM deposits
+WHERE EXISTS (
+    SELECT 1 FROM deposits e
+    WHERE e.output_pub_key = deposits.output_pub_key AND e.id < deposits.id
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_deposits_output_pub_key ON deposits(output_pub_key);
@@ -0,0 +1,165 @@
+//! Authenticated control actions for a running oracle
+//!
+//! With `ADMIN_API_KEY` set, the HTTP API also serves `POST /admin/...`
+//! endpoints for the interventions that would otherwise take SSH and a
+//! restart: pausing and resuming posting, backfilling a range of blocks,
+//! switching to another configured Monero node, requeueing a failed
+//! withdrawal and draining the service. Each request must carry the key as
+//! `Authorization: Bearer <key>`.
+//!
+//! Actions that touch the poll loop are handed to it over a channel and run
+//! between polls, so they never interleave with a poll in progress. In
+//! checkpoint mode the channel also carries `POST /fill/:height` requests,
+//! which take the same key: each one spends gas.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Shortest `ADMIN_API_KEY` accepted
+pub const MIN_API_KEY_LEN: usize = 16;
+
+/// An action run by the poll loop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminCommand {
+    /// Stop sending transactions until resumed; blocks are still tracked
+    Pause,
+    Resume,
+    /// Post confirmed blocks `from..=to`, as the `backfill` command does
+    Backfill {
+        from: u64,
+        to: u64,
+    },
+    /// Post skipped blocks `from..=to` below the contract's latest block, as
+    /// the `fill` command does
+    Fill {
+        from: u64,
+        to: u64,
+    },
+    /// Let a catch-up longer than `MAX_CATCH_UP_BLOCKS` go ahead
+    ApproveCatchUp,
+    /// Follow the configured Monero node at `url`, or the next one
+    RotateNode {
+        url: Option<String>,
+    },
+    /// Stop polling and exit once the current poll is done
+    Drain,
+}
+
+/// A command and where its outcome goes
+#[derive(Debug)]
+pub struct AdminRequest {
+    pub command: AdminCommand,
+    pub reply: oneshot::Sender<Result<String>>,
+}
+
+/// `POST /admin/backfill` and `POST /admin/fill` body
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct BackfillRequest {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// `POST /admin/rotate-node` body; without a URL the next node is used
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RotateNodeRequest {
+    pub url: Option<String>,
+}
+
+/// The API's side of the channel to the poll loop
+#[derive(Debug, Clone)]
+pub struct AdminHandle {
+    api_key: Arc<str>,
+    commands: mpsc::Sender<AdminRequest>,
+}
+
+/// A handle for the API and the receiver the poll loop listens on
+pub fn channel(api_key: &str) -> (AdminHandle, mpsc::Receiver<AdminRequest>) {
+    let (commands, requests) = mpsc::channel(8);
+    let handle = AdminHandle {
+        api_key: api_key.into(),
+        commands,
+    };
+    (handle, requests)
+}
+
+impl AdminHandle {
+    /// Hand `command` to the poll loop and wait for its outcome
+    pub async fn send(&self, command: AdminCommand) -> Result<String> {
+        let (reply, outcome) = oneshot::channel();
+        self.commands
+            .send(AdminRequest { command, reply })
+            .await
+            .map_err(|_| anyhow::anyhow!("The oracle is no longer polling"))?;
+        outcome
+            .await
+            .map_err(|_| anyhow::anyhow!("The oracle stopped before answering"))?
+    }
+}
+
+impl BearerAuth for AdminHandle {
+    fn api_key(&self) -> Option<&str> {
+        Some(&self.api_key)
+    }
+}
+
+/// A service whose HTTP endpoints take its key as `Authorization: Bearer
+/// <key>`: the admin API, the multisig co-signer and the FROST participant
+pub trait BearerAuth {
+    /// The key callers must present; `None` admits no one
+    fn api_key(&self) -> Option<&str>;
+
+    /// Whether an `Authorization` header value carries the key
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(api_key) = self.api_key() else {
+            return false;
+        };
+        authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), api_key.as_bytes()))
+    }
+}
+
+impl<T: BearerAuth + ?Sized> BearerAuth for Arc<T> {
+    fn api_key(&self) -> Option<&str> {
+        (**self).api_key()
+    }
+}
+
+/// Compare without returning early, so response times don't reveal how much
+/// of a guessed key was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization() {
+        let (handle, _requests) = channel("0123456789abcdef");
+        assert!(handle.authorized(Some("Bearer 0123456789abcdef")));
+        assert!(!handle.authorized(Some("Bearer 0123456789abcdeg")));
+        assert!(!handle.authorized(Some("Bearer 0123456789abcde")));
+        assert!(!handle.authorized(Some("0123456789abcdef")));
+        assert!(!handle.authorized(Some("Basic 0123456789abcdef")));
+        assert!(!handle.authorized(None));
+    }
+
+    #[tokio::test]
+    async fn test_commands_reach_the_loop() {
+        let (handle, mut requests) = channel("0123456789abcdef");
+        let poll_loop = tokio::spawn(async move {
+            let request = requests.recv().await.unwrap();
+            assert_eq!(request.command, AdminCommand::Pause);
+            request.reply.send(Ok("Paused".to_string())).unwrap();
+        });
+        assert_eq!(handle.send(AdminCommand::Pause).await.unwrap(), "Paused");
+        poll_loop.await.unwrap();
+
+        // Nothing listening
+        assert!(handle.send(AdminCommand::Drain).await.is_err());
+    }
+}
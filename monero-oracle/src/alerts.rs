@@ -0,0 +1,472 @@
+//! Notifications for events an operator has to act on
+//!
+//! Every alert is logged; with `ALERT_WEBHOOK_URL` set it is also posted to a
+//! webhook. Discord webhooks and the Telegram Bot API get their own payloads,
+//! anything else receives a generic JSON body (Slack incoming webhooks accept
+//! it as is). Repeats of the same alert are suppressed for `ALERT_COOLDOWN_SECS`
+//! unless the condition clears in between; reorgs are always sent.
+
+use crate::{error::ErrorKind, gas::BudgetExceeded};
+use alloy::primitives::{Address, B256};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::{error, warn};
+
+/// Request timeout for webhook deliveries
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Payload shape expected by the webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    /// `{"text", "alert", "severity"}`
+    Generic,
+    /// `{"content"}`
+    Discord,
+    /// `sendMessage` of the Telegram Bot API; needs `ALERT_TELEGRAM_CHAT_ID`
+    Telegram,
+}
+
+impl WebhookFormat {
+    /// Guess the format from the webhook URL
+    pub fn detect(url: &str) -> Self {
+        if url.contains("discord.com/api/webhooks") || url.contains("discordapp.com/api/webhooks") {
+            Self::Discord
+        } else if url.contains("api.telegram.org") {
+            Self::Telegram
+        } else {
+            Self::Generic
+        }
+    }
+}
+
+impl FromStr for WebhookFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "generic" => Ok(Self::Generic),
+            "discord" => Ok(Self::Discord),
+            "telegram" => Ok(Self::Telegram),
+            other => anyhow::bail!(
+                "Unknown webhook format '{}' (expected generic, discord or telegram)",
+                other
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertConfig {
+    pub webhook_url: Option<String>,
+    pub format: WebhookFormat,
+    pub telegram_chat_id: Option<String>,
+    pub cooldown: Duration,
+    /// Consecutive failed poll cycles before alerting
+    pub poll_failures: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alert {
+    LowBalance {
+        address: Address,
+        balance_eth: f64,
+        minimum_eth: f64,
+    },
+    PostingPaused {
+        address: Address,
+        balance_eth: f64,
+        minimum_eth: f64,
+    },
+    PollFailures {
+        count: u32,
+        kind: ErrorKind,
+        error: String,
+    },
+    Reorg {
+        height: u64,
+    },
+    /// A Unichain reorg dropped the transaction posting `height`
+    PostingReorged {
+        height: u64,
+        unichain_tx_hash: B256,
+    },
+    Lag {
+        lag: u64,
+        maximum: u64,
+    },
+    RoleMismatch {
+        contract_oracle: Address,
+        wallet: Address,
+    },
+    RootMismatch {
+        height: u64,
+        fields: String,
+    },
+    ExtraContractFailing {
+        contract: Address,
+        error: String,
+    },
+    CatchUpBlocked {
+        behind: u64,
+        maximum: u64,
+    },
+    GasBudgetExceeded(BudgetExceeded),
+    /// The bridge contract is paused; posting waits for it
+    ContractPaused,
+    ContractRevert {
+        action: String,
+        reason: String,
+        /// Sends stopped until an operator resumes
+        halted: bool,
+    },
+    DepositHeld {
+        tx_hash: B256,
+        output_index: u64,
+        reason: String,
+    },
+    LeaseTakeover {
+        instance: String,
+        /// Queued withdrawals held for review
+        withdrawals_held: usize,
+    },
+}
+
+impl Alert {
+    /// Identifies repeats of the same condition
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::LowBalance { .. } => "low_balance",
+            Self::PostingPaused { .. } => "posting_paused",
+            Self::PollFailures { .. } => "poll_failures",
+            Self::Reorg { .. } => "reorg",
+            Self::PostingReorged { .. } => "posting_reorged",
+            Self::Lag { .. } => "lag",
+            Self::RoleMismatch { .. } => "role_mismatch",
+            Self::RootMismatch { .. } => "root_mismatch",
+            Self::ExtraContractFailing { .. } => "extra_contract_failing",
+            Self::CatchUpBlocked { .. } => "catch_up_blocked",
+            Self::GasBudgetExceeded(_) => "gas_budget_exceeded",
+            Self::ContractPaused => "contract_paused",
+            Self::ContractRevert { .. } => "contract_revert",
+            Self::DepositHeld { .. } => "deposit_held",
+            Self::LeaseTakeover { .. } => "lease_takeover",
+        }
+    }
+
+    pub fn severity(&self) -> &'static str {
+        match self {
+            Self::LowBalance { .. }
+            | Self::Lag { .. }
+            | Self::DepositHeld { .. }
+            | Self::ContractPaused => "warning",
+            _ => "critical",
+        }
+    }
+
+    /// Reorgs, root mismatches, held deposits and takeovers are separate
+    /// events, not a lasting condition
+    fn throttled(&self) -> bool {
+        !matches!(
+            self,
+            Self::Reorg { .. }
+                | Self::PostingReorged { .. }
+                | Self::RootMismatch { .. }
+                | Self::DepositHeld { .. }
+                | Self::LeaseTakeover { .. }
+        )
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LowBalance {
+                address,
+                balance_eth,
+                minimum_eth,
+            } => write!(
+                f,
+                "Oracle wallet {} is low on gas: {:.6} ETH (warning at {})",
+                address, balance_eth, minimum_eth
+            ),
+            Self::PostingPaused {
+                address,
+                balance_eth,
+                minimum_eth,
+            } => write!(
+                f,
+                "Block posting paused: oracle wallet {} holds {:.6} ETH (needs {})",
+                address, balance_eth, minimum_eth
+            ),
+            Self::PollFailures {
+                count: 1,
+                kind,
+                error,
+            } => write!(f, "Poll failed with a {} error: {}", kind, error),
+            Self::PollFailures { count, kind, error } => write!(
+                f,
+                "{} poll cycles failed in a row ({} error): {}",
+                count, kind, error
+            ),
+            Self::Reorg { height } => {
+                write!(f, "Monero reorg detected at block {}; rolling back", height)
+            }
+            Self::PostingReorged {
+                height,
+                unichain_tx_hash,
+            } => write!(
+                f,
+                "Unichain reorg dropped posting of block {} ({}); posting it again",
+                height, unichain_tx_hash
+            ),
+            Self::Lag { lag, maximum } => write!(
+                f,
+                "Contract is {} blocks behind the Monero tip (maximum {})",
+                lag, maximum
+            ),
+            Self::RoleMismatch {
+                contract_oracle,
+                wallet,
+            } => write!(
+                f,
+                "Wallet {} is not the contract oracle ({})",
+                wallet, contract_oracle
+            ),
+            Self::RootMismatch { height, fields } => write!(
+                f,
+                "Contract data for block {} doesn't match the computed {}",
+                height, fields
+            ),
+            Self::ExtraContractFailing { contract, error } => {
+                write!(
+                    f,
+                    "Posting to extra contract {} failed: {}",
+                    contract, error
+                )
+            }
+            Self::CatchUpBlocked { behind, maximum } => write!(
+                f,
+                "Contract is {} confirmed blocks behind (maximum catch-up {}); \
+                 posting waits for an operator to approve",
+                behind, maximum
+            ),
+            Self::GasBudgetExceeded(exceeded) => {
+                write!(f, "Block posting paused: {}", exceeded)
+            }
+            Self::ContractPaused => {
+                f.write_str("Bridge contract is paused; block posting resumes when it is unpaused")
+            }
+            Self::ContractRevert {
+                action,
+                reason,
+                halted,
+            } => {
+                write!(f, "{} reverted with {}", action, reason)?;
+                if *halted {
+                    f.write_str("; no transactions are sent until an operator resumes")?;
+                }
+                Ok(())
+            }
+            Self::DepositHeld {
+                tx_hash,
+                output_index,
+                reason,
+            } => write!(
+                f,
+                "Deposit {}:{} held for review: {}",
+                tx_hash, output_index, reason
+            ),
+            Self::LeaseTakeover {
+                instance,
+                withdrawals_held,
+            } => {
+                write!(f, "Instance '{}' took over as leader", instance)?;
+                if *withdrawals_held > 0 {
+                    write!(
+                        f,
+                        "; {} queued withdrawal(s) held for review, as the previous leader may have paid them",
+                        withdrawals_held
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Webhook request body for `alert`
+pub fn payload(format: WebhookFormat, chat_id: Option<&str>, alert: &Alert) -> Value {
+    let text = format!("🚨 Monero oracle: {}", alert);
+    match format {
+        WebhookFormat::Generic => json!({
+            "text": text,
+            "alert": alert.kind(),
+            "severity": alert.severity(),
+        }),
+        WebhookFormat::Discord => json!({ "content": text }),
+        WebhookFormat::Telegram => json!({ "chat_id": chat_id, "text": text }),
+    }
+}
+
+/// Sends alerts, suppressing repeats within the cooldown
+pub struct Alerter {
+    config: RwLock<AlertConfig>,
+    client: reqwest::Client,
+    last_sent: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl Alerter {
+    pub fn new(config: AlertConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            client: reqwest::Client::new(),
+            last_sent: Mutex::default(),
+        }
+    }
+
+    pub fn config(&self) -> AlertConfig {
+        self.config.read().expect("alert config poisoned").clone()
+    }
+
+    /// Replace the webhook, cooldown and thresholds; suppressed repeats stay
+    /// suppressed
+    pub fn set_config(&self, config: AlertConfig) {
+        *self.config.write().expect("alert config poisoned") = config;
+    }
+
+    /// Log `alert` and deliver it unless it repeats one sent within the
+    /// cooldown. Delivery failures are logged, never returned.
+    pub async fn notify(&self, alert: Alert) {
+        if !self.should_send(&alert, Instant::now()) {
+            return;
+        }
+        warn!("   🚨 Alert: {}", alert);
+
+        let config = self.config();
+        let Some(url) = &config.webhook_url else {
+            return;
+        };
+        let body = payload(config.format, config.telegram_chat_id.as_deref(), &alert);
+        let result = self
+            .client
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            error!("❌ Failed to deliver {} alert: {}", alert.kind(), e);
+        }
+    }
+
+    /// The condition behind `kind` is over; the next occurrence alerts again
+    pub fn clear(&self, kind: &'static str) {
+        self.last_sent
+            .lock()
+            .expect("alert state poisoned")
+            .remove(kind);
+    }
+
+    fn should_send(&self, alert: &Alert, now: Instant) -> bool {
+        if !alert.throttled() {
+            return true;
+        }
+        let cooldown = self.config().cooldown;
+        let mut last_sent = self.last_sent.lock().expect("alert state poisoned");
+        match last_sent.get(alert.kind()) {
+            Some(sent) if now.duration_since(*sent) < cooldown => false,
+            _ => {
+                last_sent.insert(alert.kind(), now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alerter() -> Alerter {
+        Alerter::new(AlertConfig {
+            webhook_url: None,
+            format: WebhookFormat::Generic,
+            telegram_chat_id: None,
+            cooldown: Duration::from_secs(3600),
+            poll_failures: 3,
+        })
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            WebhookFormat::detect("https://discord.com/api/webhooks/1/abc"),
+            WebhookFormat::Discord
+        );
+        assert_eq!(
+            WebhookFormat::detect("https://api.telegram.org/bot123:abc/sendMessage"),
+            WebhookFormat::Telegram
+        );
+        assert_eq!(
+            WebhookFormat::detect("https://hooks.slack.com/services/T/B/x"),
+            WebhookFormat::Generic
+        );
+    }
+
+    #[test]
+    fn test_payloads() {
+        let alert = Alert::Lag {
+            lag: 80,
+            maximum: 30,
+        };
+
+        let generic = payload(WebhookFormat::Generic, None, &alert);
+        assert_eq!(generic["alert"], "lag");
+        assert_eq!(generic["severity"], "warning");
+        assert!(generic["text"].as_str().unwrap().contains("80 blocks"));
+
+        let discord = payload(WebhookFormat::Discord, None, &alert);
+        assert_eq!(discord["content"], generic["text"]);
+
+        let telegram = payload(WebhookFormat::Telegram, Some("-100123"), &alert);
+        assert_eq!(telegram["chat_id"], "-100123");
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let alerter = alerter();
+        let now = Instant::now();
+        let lag = Alert::Lag {
+            lag: 80,
+            maximum: 30,
+        };
+
+        assert!(alerter.should_send(&lag, now));
+        assert!(!alerter.should_send(&lag, now + Duration::from_secs(60)));
+        assert!(alerter.should_send(&lag, now + Duration::from_secs(3601)));
+
+        // Clearing the condition re-arms the alert
+        alerter.clear("lag");
+        assert!(alerter.should_send(&lag, now + Duration::from_secs(3602)));
+
+        let reorg = Alert::Reorg { height: 3_100_000 };
+        assert!(alerter.should_send(&reorg, now));
+        assert!(alerter.should_send(&reorg, now));
+
+        let mismatch = Alert::RootMismatch {
+            height: 3_100_000,
+            fields: "outputMerkleRoot".to_string(),
+        };
+        assert_eq!(mismatch.severity(), "critical");
+        assert!(alerter.should_send(&mismatch, now));
+        assert!(alerter.should_send(&mismatch, now));
+    }
+}
@@ -0,0 +1,1026 @@
+//! HTTP API for wallets, frontends and operators
+//!
+//! Enabled by setting `ORACLE_HTTP_PORT`. Serves oracle health, posted block
+//! records from the local database, and Merkle proofs for minting. With
+//! `ADMIN_API_KEY` set it also takes the control actions in [`crate::admin`].
+//!
+//! - `GET /healthz` - liveness probe
+//! - `GET /readyz` - readiness probe
+//! - `GET /status`
+//! - `GET /block/:height`
+//! - `GET /block/:height/deposits`
+//! - `GET /block/:height/outputs` - indexed outputs in leaf order
+//! - `GET /tx/:tx_hash/outputs` - indexed outputs of a transaction
+//! - `GET /output/:pub_key` - indexed output by one-time public key
+//! - `GET /subaddress/:major/:minor`
+//! - `GET /payment-id/:payment_id` - integrated address and its deposits
+//! - `GET /withdrawal/:burn_id`
+//! - `GET /withdrawal-fees` - payout fee quoted at each priority
+//! - `GET /attestation/:height`
+//! - `GET /proof/tx/:tx_hash`
+//! - `GET /proof/output/:tx_hash/:index`
+//! - `GET /events` - server-sent [`crate::feed`] events as they happen
+//!
+//! Co-signer endpoints of a multisig bridge wallet, with
+//! `Authorization: Bearer <MULTISIG_API_KEY>` (see [`crate::multisig`]):
+//!
+//! - `GET /multisig/info` - this wallet's multisig info
+//! - `POST /multisig/sign` - check and sign a payout or fee sweep txset
+//!
+//! Threshold signing endpoints, with `Authorization: Bearer <FROST_API_KEY>`
+//! (see [`crate::frost`]):
+//!
+//! - `POST /frost/commit` - `{"height": h}`, nonce commitments for a block
+//!   this signer has attested
+//! - `POST /frost/sign` - signature share over a signing package, if its
+//!   digest is that of this signer's own attestation
+//!
+//! Admin endpoints, with `Authorization: Bearer <ADMIN_API_KEY>`:
+//!
+//! - `POST /admin/pause` - stop sending transactions
+//! - `POST /admin/resume`
+//! - `POST /admin/backfill` - `{"from": h, "to": h}`
+//! - `POST /admin/fill` - `{"from": h, "to": h}`, skipped blocks only
+//! - `POST /fill/:height` - post one block checkpoint mode skipped, when
+//!   `CHECKPOINT_INTERVAL_BLOCKS` is set
+//! - `POST /admin/approve-catch-up` - post past `MAX_CATCH_UP_BLOCKS`
+//! - `POST /admin/rotate-node` - `{"url": ...}`, or the next node without one
+//! - `POST /admin/withdrawal/:burn_id/requeue` - retry a failed withdrawal
+//! - `GET /admin/deposits/held` - deposits held by the deposit limits
+//! - `POST /admin/deposit/:tx_hash/:index/approve` - mint a held deposit
+//! - `POST /admin/deposit/:tx_hash/:index/reject` - never mint it
+//! - `GET /admin/fees` - bridge fees collected this month and not yet swept
+//! - `GET /admin/fees/:month` - the same for another month (`YYYY-MM`)
+//! - `GET /admin/multisig/rounds` - the latest multisig signing rounds
+//! - `POST /admin/drain` - exit once the current poll is done
+
+use crate::{
+    admin::{AdminCommand, AdminHandle, BackfillRequest, BearerAuth, RotateNodeRequest},
+    attestation::Attestation,
+    bridge_fee::FeeReport,
+    costs::Month,
+    deposit_policy::{HeldDeposit, ReviewStatus},
+    feed::Feed,
+    fees::{self, FeePriority, FeeQuote},
+    frost::{CommitRequest, Commitment, Participant, SignatureShare, SigningPackage},
+    health::{self, HealthConfig, HealthReport},
+    merkle::MerkleHasher,
+    monero_rpc::{parse_hex_to_b256, MoneroRpcClient},
+    monero_wallet::SignedMultisig,
+    multisig::{Cosigner, MultisigInfo, SignRequest, SigningRound},
+    proof,
+    scanner::{format_xmr, Deposit, ViewKeys},
+    storage::{Database, IndexedBlock, IndexedOutput, PendingDeposit},
+    subaddress::{Network, SubaddressIndex},
+    withdrawal::Withdrawal,
+};
+use alloy::primitives::{Address, B64};
+use anyhow::Result;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::{header, request::Parts, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::Serialize;
+use std::{
+    convert::Infallible,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tower_http::cors::CorsLayer;
+use tracing::{info, warn};
+
+/// How long `/readyz` waits for each RPC endpoint
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Live oracle state, updated by the poll loop
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleStatus {
+    pub oracle_address: Option<Address>,
+    pub bridge_address: Option<Address>,
+    pub monero_tip: Option<u64>,
+    pub monero_confirmed_height: Option<u64>,
+    pub contract_latest_block: Option<u64>,
+    /// Oracle wallet balance, refreshed every poll
+    pub balance_eth: Option<f64>,
+    /// Below `BALANCE_PAUSE_ETH`: blocks are tracked but not posted
+    pub posting_paused: bool,
+    /// No configured key is the contract's oracle: blocks are indexed, not posted
+    pub read_only: bool,
+    /// Another instance holds the leader lease: blocks are indexed, nothing
+    /// is sent
+    pub standby: bool,
+    /// Paused with `POST /admin/pause`, or halted by a revert that needs an
+    /// operator: blocks are tracked, nothing is sent
+    pub paused_by_operator: bool,
+    /// `GAS_BUDGET_DAILY_ETH` or `GAS_BUDGET_WEEKLY_ETH` used up: blocks are
+    /// tracked, nothing is sent
+    pub over_gas_budget: bool,
+    /// The bridge contract's `paused()`: blocks are tracked, nothing is sent
+    /// until it is unpaused
+    pub contract_paused: bool,
+    /// Confirmed blocks waiting on an approved catch-up, when more than
+    /// `MAX_CATCH_UP_BLOCKS`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catch_up_blocked: Option<u64>,
+    pub last_poll_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// `EXTRA_BRIDGE_ADDRESSES` contracts, each synced independently
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_contracts: Vec<ContractStatus>,
+}
+
+/// Sync state of one extra contract
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractStatus {
+    pub address: Address,
+    pub latest_block: Option<u64>,
+    /// Monero tip minus this contract's tip
+    pub lag: Option<u64>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Why the last poll couldn't bring it up to date, if it failed
+    pub last_error: Option<String>,
+}
+
+pub type SharedStatus = Arc<RwLock<OracleStatus>>;
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub monero_client: MoneroRpcClient,
+    pub db: Arc<Database>,
+    pub status: SharedStatus,
+    pub output_merkle_hasher: MerkleHasher,
+    pub tx_merkle_hasher: MerkleHasher,
+    pub view_keys: Option<ViewKeys>,
+    pub network: Network,
+    /// Address this oracle signs attestations with, in multi-signature mode
+    pub attester: Option<Address>,
+    /// Signs multisig payouts, with `MULTISIG_API_KEY`
+    pub cosigner: Option<Cosigner>,
+    /// Takes part in threshold signing, with `FROST_KEY_SHARE`
+    pub frost: Option<Arc<Participant>>,
+    /// Probed by `/readyz`
+    pub unichain_rpc_url: String,
+    pub http_client: reqwest::Client,
+    pub health: HealthConfig,
+    pub started_at: DateTime<Utc>,
+    /// Control actions, with `ADMIN_API_KEY`
+    pub admin: Option<AdminHandle>,
+    /// Serve `/fill/:height` to admins (checkpoint mode)
+    pub fill_on_demand: bool,
+    /// Streamed by `/events`
+    pub feed: Feed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymentIdResponse {
+    payment_id: B64,
+    integrated_address: String,
+    deposits: Vec<Deposit>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubaddressResponse {
+    #[serde(flatten)]
+    index: SubaddressIndex,
+    address: String,
+    deposits: Vec<Deposit>,
+    /// Seen in the txpool or a not yet posted block, awaiting confirmations
+    pending_deposits: Vec<PendingDeposit>,
+}
+
+/// `/status` body, also printed by the `status` command
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    #[serde(flatten)]
+    pub status: OracleStatus,
+    pub stored_checkpoint: Option<u64>,
+    /// Monero tip minus contract tip
+    pub lag: Option<u64>,
+}
+
+impl StatusResponse {
+    pub fn new(status: OracleStatus, stored_checkpoint: Option<u64>) -> Self {
+        let lag = match (status.monero_tip, status.contract_latest_block) {
+            (Some(tip), Some(latest)) => Some(tip.saturating_sub(latest)),
+            _ => None,
+        };
+        Self {
+            status,
+            stored_checkpoint,
+            lag,
+        }
+    }
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: message.into(),
+        }
+    }
+
+    /// An admin action that couldn't be carried out
+    fn refused(e: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            message: format!("{:#}", e),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+/// Outcome of an admin action
+#[derive(Debug, Serialize)]
+struct AdminResponse {
+    message: String,
+}
+
+/// A keyed service in [`ApiState`], reached through [`Authorized`]
+trait Keyed: BearerAuth + Clone + Send + Sized {
+    /// Why its routes 404 when it isn't configured
+    const DISABLED: &'static str;
+    /// Which key a rejected caller got wrong
+    const KEY: &'static str;
+
+    fn from_state(state: &ApiState) -> Option<Self>;
+}
+
+impl Keyed for AdminHandle {
+    const DISABLED: &'static str = "Admin API is disabled (no ADMIN_API_KEY)";
+    const KEY: &'static str = "admin API key";
+
+    fn from_state(state: &ApiState) -> Option<Self> {
+        state.admin.clone()
+    }
+}
+
+impl Keyed for Cosigner {
+    const DISABLED: &'static str = "Multisig signing is disabled (no MULTISIG_API_KEY)";
+    const KEY: &'static str = "multisig API key";
+
+    fn from_state(state: &ApiState) -> Option<Self> {
+        state.cosigner.clone()
+    }
+}
+
+impl Keyed for Arc<Participant> {
+    const DISABLED: &'static str = "Threshold signing is disabled (no FROST_KEY_SHARE)";
+    const KEY: &'static str = "FROST API key";
+
+    fn from_state(state: &ApiState) -> Option<Self> {
+        state.frost.clone()
+    }
+}
+
+/// Extractor that admits only requests carrying the service's key
+struct Authorized<S>(S);
+
+/// A caller with `ADMIN_API_KEY`
+type Admin = Authorized<AdminHandle>;
+/// A multisig peer with `MULTISIG_API_KEY`
+type Cosign = Authorized<Cosigner>;
+/// The threshold key coordinator with `FROST_API_KEY`
+type Participate = Authorized<Arc<Participant>>;
+
+#[async_trait]
+impl<S: Keyed> FromRequestParts<ApiState> for Authorized<S> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ApiState,
+    ) -> std::result::Result<Self, ApiError> {
+        let service = S::from_state(state).ok_or_else(|| ApiError::not_found(S::DISABLED))?;
+        let authorization = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        if !service.authorized(authorization) {
+            return Err(ApiError {
+                status: StatusCode::UNAUTHORIZED,
+                message: format!("Missing or wrong {}", S::KEY),
+            });
+        }
+        Ok(Self(service))
+    }
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/status", get(status))
+        .route("/block/:height", get(block))
+        .route("/block/:height/deposits", get(deposits))
+        .route("/block/:height/outputs", get(block_outputs))
+        .route("/tx/:tx_hash/outputs", get(tx_outputs))
+        .route("/output/:pub_key", get(output))
+        .route("/subaddress/:major/:minor", get(subaddress))
+        .route("/payment-id/:payment_id", get(payment_id))
+        .route("/withdrawal/:burn_id", get(withdrawal))
+        .route("/withdrawal-fees", get(withdrawal_fees))
+        .route("/attestation/:height", get(attestation))
+        .route("/proof/tx/:tx_hash", get(tx_proof))
+        .route("/proof/output/:tx_hash/:index", get(output_proof))
+        .route("/events", get(events))
+        .route("/fill/:height", post(fill))
+        .route("/multisig/info", get(multisig_info))
+        .route("/multisig/sign", post(multisig_sign))
+        .route("/frost/commit", post(frost_commit))
+        .route("/frost/sign", post(frost_sign))
+        .route("/admin/pause", post(admin_pause))
+        .route("/admin/resume", post(admin_resume))
+        .route("/admin/backfill", post(admin_backfill))
+        .route("/admin/fill", post(admin_fill))
+        .route("/admin/approve-catch-up", post(admin_approve_catch_up))
+        .route("/admin/rotate-node", post(admin_rotate_node))
+        .route("/admin/withdrawal/:burn_id/requeue", post(admin_requeue))
+        .route("/admin/deposits/held", get(admin_held_deposits))
+        .route(
+            "/admin/deposit/:tx_hash/:index/approve",
+            post(admin_approve_deposit),
+        )
+        .route(
+            "/admin/deposit/:tx_hash/:index/reject",
+            post(admin_reject_deposit),
+        )
+        .route("/admin/fees", get(admin_fees))
+        .route("/admin/fees/:month", get(admin_fees_in))
+        .route("/admin/multisig/rounds", get(admin_multisig_rounds))
+        .route("/admin/drain", post(admin_drain))
+        .layer(CorsLayer::permissive())
+        .with_state(state)
+}
+
+/// Serve the API until the process exits
+pub async fn serve(port: u16, state: ApiState) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("🌐 HTTP API listening on {}", listener.local_addr()?);
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+fn health_response(report: HealthReport) -> Response {
+    let code = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report)).into_response()
+}
+
+async fn healthz(State(state): State<ApiState>) -> Response {
+    let status = state.status.read().expect("status lock poisoned").clone();
+    health_response(health::liveness(
+        &status,
+        state.started_at,
+        Utc::now(),
+        &state.health,
+    ))
+}
+
+async fn readyz(State(state): State<ApiState>) -> Response {
+    let (monero, unichain) = tokio::join!(probe_monero(&state), probe_unichain(&state));
+    let status = state.status.read().expect("status lock poisoned").clone();
+    health_response(health::readiness(
+        &status,
+        state.started_at,
+        Utc::now(),
+        &state.health,
+        monero,
+        unichain,
+    ))
+}
+
+async fn probe_monero(state: &ApiState) -> Result<()> {
+    tokio::time::timeout(PROBE_TIMEOUT, state.monero_client.get_info())
+        .await
+        .map_err(|_| anyhow::anyhow!("get_info timed out"))??;
+    Ok(())
+}
+
+async fn probe_unichain(state: &ApiState) -> Result<()> {
+    let response: serde_json::Value = state
+        .http_client
+        .post(&state.unichain_rpc_url)
+        .timeout(PROBE_TIMEOUT)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_blockNumber",
+            "params": [],
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if let Some(error) = response.get("error") {
+        anyhow::bail!("eth_blockNumber failed: {}", error);
+    }
+    Ok(())
+}
+
+async fn status(State(state): State<ApiState>) -> ApiResult<StatusResponse> {
+    let status = state.status.read().expect("status lock poisoned").clone();
+    Ok(Json(StatusResponse::new(status, state.db.checkpoint()?)))
+}
+
+async fn block(
+    State(state): State<ApiState>,
+    Path(height): Path<u64>,
+) -> ApiResult<crate::storage::PostedBlockRecord> {
+    state
+        .db
+        .posted_block(height)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Block {} has not been posted", height)))
+}
+
+async fn deposits(
+    State(state): State<ApiState>,
+    Path(height): Path<u64>,
+) -> ApiResult<Vec<Deposit>> {
+    Ok(Json(state.db.deposits_in_block(height)?))
+}
+
+async fn block_outputs(
+    State(state): State<ApiState>,
+    Path(height): Path<u64>,
+) -> ApiResult<IndexedBlock> {
+    state
+        .db
+        .indexed_block(height)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Block {} has not been indexed", height)))
+}
+
+async fn tx_outputs(
+    State(state): State<ApiState>,
+    Path(tx_hash): Path<String>,
+) -> ApiResult<Vec<IndexedOutput>> {
+    let tx_hash = parse_hex_to_b256(&tx_hash).map_err(|e| ApiError::not_found(e.to_string()))?;
+    let outputs = state.db.indexed_tx_outputs(tx_hash)?;
+    if outputs.is_empty() {
+        return Err(ApiError::not_found(format!(
+            "Transaction {} has not been indexed",
+            tx_hash
+        )));
+    }
+    Ok(Json(outputs))
+}
+
+async fn output(
+    State(state): State<ApiState>,
+    Path(pub_key): Path<String>,
+) -> ApiResult<IndexedOutput> {
+    let pub_key = parse_hex_to_b256(&pub_key).map_err(|e| ApiError::not_found(e.to_string()))?;
+    state
+        .db
+        .output_by_pub_key(pub_key)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Output {} has not been indexed", pub_key)))
+}
+
+async fn subaddress(
+    State(state): State<ApiState>,
+    Path((major, minor)): Path<(u32, u32)>,
+) -> ApiResult<SubaddressResponse> {
+    let index = SubaddressIndex::new(major, minor);
+    let keys = state
+        .view_keys
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("Deposit scanning is disabled"))?;
+    if !keys.tracks(index) {
+        return Err(ApiError::not_found(format!(
+            "Subaddress {} is not watched",
+            index
+        )));
+    }
+
+    Ok(Json(SubaddressResponse {
+        index,
+        address: keys.address(index, state.network),
+        deposits: state.db.deposits_for_subaddress(index)?,
+        pending_deposits: state.db.pending_deposits_for_subaddress(index)?,
+    }))
+}
+
+async fn payment_id(
+    State(state): State<ApiState>,
+    Path(payment_id): Path<String>,
+) -> ApiResult<PaymentIdResponse> {
+    let payment_id: B64 = payment_id
+        .parse()
+        .map_err(|_| ApiError::not_found(format!("Invalid payment ID {}", payment_id)))?;
+    let keys = state
+        .view_keys
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("Deposit scanning is disabled"))?;
+
+    Ok(Json(PaymentIdResponse {
+        payment_id,
+        integrated_address: keys.integrated_address(payment_id, state.network),
+        deposits: state.db.deposits_for_payment_id(payment_id)?,
+    }))
+}
+
+async fn withdrawal(
+    State(state): State<ApiState>,
+    Path(burn_id): Path<u64>,
+) -> ApiResult<Withdrawal> {
+    state
+        .db
+        .withdrawal(burn_id)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Burn {} has not been seen", burn_id)))
+}
+
+/// Payout fee at each priority, from monerod's current estimate
+async fn withdrawal_fees(State(state): State<ApiState>) -> ApiResult<Vec<FeeQuote>> {
+    let estimate = state.monero_client.get_fee_estimate().await?;
+    Ok(Json(
+        FeePriority::ALL
+            .into_iter()
+            .map(|priority| fees::quote(&estimate, priority))
+            .collect(),
+    ))
+}
+
+async fn attestation(
+    State(state): State<ApiState>,
+    Path(height): Path<u64>,
+) -> ApiResult<Attestation> {
+    own_attestation(&state, height).map(Json)
+}
+
+/// This signer's attestation of block `height`
+fn own_attestation(state: &ApiState, height: u64) -> std::result::Result<Attestation, ApiError> {
+    let signer = state
+        .attester
+        .ok_or_else(|| ApiError::not_found("Not running as an attestation signer"))?;
+    state
+        .db
+        .attestation(height, signer)?
+        .ok_or_else(|| ApiError::not_found(format!("Block {} has not been attested", height)))
+}
+
+async fn tx_proof(
+    State(state): State<ApiState>,
+    Path(tx_hash): Path<String>,
+) -> ApiResult<proof::TxInclusionProof> {
+    proof::prove_tx(
+        &state.monero_client,
+        Some(&state.db),
+        &tx_hash,
+        state.tx_merkle_hasher,
+    )
+    .await
+    .map(Json)
+    .map_err(|e| ApiError::not_found(e.to_string()))
+}
+
+async fn output_proof(
+    State(state): State<ApiState>,
+    Path((tx_hash, index)): Path<(String, u64)>,
+) -> ApiResult<proof::OutputInclusionProof> {
+    // Blocks the oracle has indexed need no round trip to monerod
+    if let Some(proof) =
+        proof::prove_indexed_output(&state.db, &tx_hash, index, state.output_merkle_hasher)
+            .map_err(|e| ApiError::not_found(e.to_string()))?
+    {
+        return Ok(Json(proof));
+    }
+    proof::prove_output(
+        &state.monero_client,
+        &tx_hash,
+        index,
+        state.output_merkle_hasher,
+    )
+    .await
+    .map(Json)
+    .map_err(|e| ApiError::not_found(e.to_string()))
+}
+
+async fn events(
+    State(state): State<ApiState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = futures::stream::unfold(state.feed.subscribe(), |mut events| async move {
+        let event = match events.recv().await {
+            Ok(event) => Event::default()
+                .event(event.name())
+                .json_data(&event)
+                .expect("bridge events serialize"),
+            // Tell the client to catch up from the other endpoints
+            Err(RecvError::Lagged(missed)) => {
+                Event::default().event("lagged").data(missed.to_string())
+            }
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), events))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Run an admin action in the poll loop
+async fn run_admin(admin: AdminHandle, command: AdminCommand) -> ApiResult<AdminResponse> {
+    info!("🔑 Admin request: {:?}", command);
+    let message = admin.send(command).await.map_err(ApiError::refused)?;
+    Ok(Json(AdminResponse { message }))
+}
+
+async fn admin_pause(Authorized(admin): Admin) -> ApiResult<AdminResponse> {
+    run_admin(admin, AdminCommand::Pause).await
+}
+
+async fn admin_resume(Authorized(admin): Admin) -> ApiResult<AdminResponse> {
+    run_admin(admin, AdminCommand::Resume).await
+}
+
+async fn admin_backfill(
+    Authorized(admin): Admin,
+    Json(BackfillRequest { from, to }): Json<BackfillRequest>,
+) -> ApiResult<AdminResponse> {
+    if from > to {
+        return Err(ApiError::refused(anyhow::anyhow!(
+            "Empty block range {}..{}",
+            from,
+            to
+        )));
+    }
+    run_admin(admin, AdminCommand::Backfill { from, to }).await
+}
+
+async fn admin_fill(
+    Authorized(admin): Admin,
+    Json(BackfillRequest { from, to }): Json<BackfillRequest>,
+) -> ApiResult<AdminResponse> {
+    if from > to {
+        return Err(ApiError::refused(anyhow::anyhow!(
+            "Empty block range {}..{}",
+            from,
+            to
+        )));
+    }
+    run_admin(admin, AdminCommand::Fill { from, to }).await
+}
+
+/// Each fill spends gas, so like the other actions it takes the admin key
+async fn fill(
+    State(state): State<ApiState>,
+    Authorized(admin): Admin,
+    Path(height): Path<u64>,
+) -> ApiResult<AdminResponse> {
+    if !state.fill_on_demand {
+        return Err(ApiError::not_found("On-demand filling is disabled"));
+    }
+    info!("🧩 Fill requested for block {}", height);
+    let message = admin
+        .send(AdminCommand::Fill {
+            from: height,
+            to: height,
+        })
+        .await
+        .map_err(ApiError::refused)?;
+    Ok(Json(AdminResponse { message }))
+}
+
+async fn admin_approve_catch_up(Authorized(admin): Admin) -> ApiResult<AdminResponse> {
+    run_admin(admin, AdminCommand::ApproveCatchUp).await
+}
+
+async fn admin_rotate_node(
+    Authorized(admin): Admin,
+    request: Option<Json<RotateNodeRequest>>,
+) -> ApiResult<AdminResponse> {
+    let Json(RotateNodeRequest { url }) = request.unwrap_or_default();
+    run_admin(admin, AdminCommand::RotateNode { url }).await
+}
+
+/// Handled here rather than in the poll loop: the loop never touches a
+/// failed withdrawal, and the update only applies to one still failed
+async fn admin_requeue(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+    Path(burn_id): Path<u64>,
+) -> ApiResult<AdminResponse> {
+    let status = state
+        .db
+        .requeue_withdrawal(burn_id)
+        .map_err(ApiError::refused)?;
+    info!("🔑 Admin requeued burn #{} as {}", burn_id, status);
+    Ok(Json(AdminResponse {
+        message: format!("Burn #{} requeued as {}", burn_id, status),
+    }))
+}
+
+async fn admin_held_deposits(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+) -> ApiResult<Vec<HeldDeposit>> {
+    Ok(Json(state.db.held_deposits()?))
+}
+
+async fn admin_approve_deposit(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+    Path((tx_hash, index)): Path<(String, u64)>,
+) -> ApiResult<AdminResponse> {
+    review_deposit(&state, &tx_hash, index, ReviewStatus::Approved)
+}
+
+async fn admin_reject_deposit(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+    Path((tx_hash, index)): Path<(String, u64)>,
+) -> ApiResult<AdminResponse> {
+    review_deposit(&state, &tx_hash, index, ReviewStatus::Rejected)
+}
+
+/// Handled here like requeues: the poll loop only reads the review, and
+/// mints an approved deposit at the next poll
+fn review_deposit(
+    state: &ApiState,
+    tx_hash: &str,
+    index: u64,
+    status: ReviewStatus,
+) -> ApiResult<AdminResponse> {
+    let tx_hash = parse_hex_to_b256(tx_hash).map_err(|e| ApiError::not_found(e.to_string()))?;
+    state
+        .db
+        .review_deposit(tx_hash, index, status)
+        .map_err(ApiError::refused)?;
+    info!("🔑 Admin {} deposit {}:{}", status, tx_hash, index);
+    Ok(Json(AdminResponse {
+        message: match status {
+            ReviewStatus::Approved => format!(
+                "Deposit {}:{} approved; it is minted at the next poll",
+                tx_hash, index
+            ),
+            _ => format!("Deposit {}:{} {}", tx_hash, index, status),
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FeesResponse {
+    /// Collected and not yet swept to the treasury, in any month
+    unswept_xmr: String,
+    #[serde(flatten)]
+    report: FeeReport,
+}
+
+async fn admin_fees(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+) -> ApiResult<FeesResponse> {
+    fees_in(&state, Month::containing(Utc::now()))
+}
+
+async fn admin_fees_in(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+    Path(month): Path<String>,
+) -> ApiResult<FeesResponse> {
+    let month = month
+        .parse()
+        .map_err(|e: anyhow::Error| ApiError::not_found(e.to_string()))?;
+    fees_in(&state, month)
+}
+
+fn fees_in(state: &ApiState, month: Month) -> ApiResult<FeesResponse> {
+    let collected = state.db.collected_fees(month.start(), month.end())?;
+    let sweeps = state.db.fee_sweeps(month.start(), month.end())?;
+    Ok(Json(FeesResponse {
+        unswept_xmr: format_xmr(state.db.unswept_fees()?),
+        report: FeeReport::new(month, &collected, &sweeps),
+    }))
+}
+
+async fn multisig_info(Authorized(cosigner): Cosign) -> ApiResult<MultisigInfo> {
+    Ok(Json(cosigner.info().await?))
+}
+
+async fn multisig_sign(
+    State(state): State<ApiState>,
+    Authorized(cosigner): Cosign,
+    Json(request): Json<SignRequest>,
+) -> ApiResult<SignedMultisig> {
+    let signed = cosigner.sign(&state.db, &request).await.map_err(|e| {
+        warn!("🔏 Refused to sign {}: {:#}", request.purpose, e);
+        ApiError::refused(e)
+    })?;
+    info!("🔏 Co-signed {}", request.purpose);
+    Ok(Json(signed))
+}
+
+async fn frost_commit(
+    State(state): State<ApiState>,
+    Authorized(participant): Participate,
+    Json(request): Json<CommitRequest>,
+) -> ApiResult<Commitment> {
+    own_attestation(&state, request.height)?;
+    Ok(Json(participant.commit(request.height)))
+}
+
+async fn frost_sign(
+    State(state): State<ApiState>,
+    Authorized(participant): Participate,
+    Json(package): Json<SigningPackage>,
+) -> ApiResult<SignatureShare> {
+    let own = own_attestation(&state, package.height)?;
+    let share = participant.sign(&package, &own).map_err(|e| {
+        warn!("✍️  Refused to sign block {}: {:#}", package.height, e);
+        ApiError::refused(e)
+    })?;
+    info!("✍️  Signed block {} with the threshold key", package.height);
+    Ok(Json(share))
+}
+
+async fn admin_multisig_rounds(
+    State(state): State<ApiState>,
+    Authorized(_): Admin,
+) -> ApiResult<Vec<SigningRound>> {
+    Ok(Json(state.db.multisig_rounds(50)?))
+}
+
+async fn admin_drain(Authorized(admin): Admin) -> ApiResult<AdminResponse> {
+    run_admin(admin, AdminCommand::Drain).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::{self, AdminRequest};
+    use reqwest::StatusCode;
+    use serde_json::Value;
+
+    const ADMIN_KEY: &str = "0123456789abcdef";
+
+    fn state(admin: Option<AdminHandle>) -> ApiState {
+        ApiState {
+            // Nothing listens there
+            monero_client: MoneroRpcClient::new("http://127.0.0.1:9".to_string()),
+            db: Arc::new(Database::open_in_memory().unwrap()),
+            status: SharedStatus::default(),
+            output_merkle_hasher: MerkleHasher::default(),
+            tx_merkle_hasher: MerkleHasher::Keccak256,
+            view_keys: None,
+            network: Network::default(),
+            attester: None,
+            cosigner: None,
+            frost: None,
+            unichain_rpc_url: "http://127.0.0.1:9".to_string(),
+            http_client: reqwest::Client::new(),
+            health: HealthConfig {
+                max_poll_age: Duration::from_secs(600),
+                max_lag: 100,
+                min_balance_eth: None,
+            },
+            started_at: Utc::now(),
+            admin,
+            fill_on_demand: false,
+            feed: Feed::new(),
+        }
+    }
+
+    /// Serve `state` on a free port and return its URL
+    async fn serve(state: ApiState) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_status_and_health() {
+        let state = state(None);
+        {
+            let mut status = state.status.write().unwrap();
+            status.monero_tip = Some(120);
+            status.contract_latest_block = Some(100);
+            status.last_poll_at = Some(Utc::now());
+        }
+        let url = serve(state).await;
+        let client = reqwest::Client::new();
+
+        let status: Value = client
+            .get(format!("{}/status", url))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(status["moneroTip"], 120);
+        assert_eq!(status["lag"], 20);
+        assert_eq!(status["storedCheckpoint"], Value::Null);
+
+        let health = client.get(format!("{}/healthz", url)).send().await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+
+        let withdrawal = client
+            .get(format!("{}/withdrawal/7", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(withdrawal.status(), StatusCode::NOT_FOUND);
+        let body: Value = withdrawal.json().await.unwrap();
+        assert_eq!(body["error"], "Burn 7 has not been seen");
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_take_the_key() {
+        let client = reqwest::Client::new();
+
+        // Without ADMIN_API_KEY the routes don't exist
+        let url = serve(state(None)).await;
+        let response = client
+            .post(format!("{}/admin/pause", url))
+            .bearer_auth(ADMIN_KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let (handle, mut requests) = admin::channel(ADMIN_KEY);
+        let url = serve(state(Some(handle))).await;
+        for key in [None, Some("fedcba9876543210")] {
+            let mut request = client.post(format!("{}/admin/pause", url));
+            if let Some(key) = key {
+                request = request.bearer_auth(key);
+            }
+            let response = request.send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        assert!(requests.try_recv().is_err());
+
+        // The poll loop's answer is the response
+        tokio::spawn(async move {
+            let AdminRequest { command, reply } = requests.recv().await.unwrap();
+            assert_eq!(command, AdminCommand::Pause);
+            let _ = reply.send(Ok("Paused".to_string()));
+            // Refused actions come back as conflicts
+            let AdminRequest { reply, .. } = requests.recv().await.unwrap();
+            let _ = reply.send(Err(anyhow::anyhow!("Not paused")));
+        });
+        let response = client
+            .post(format!("{}/admin/pause", url))
+            .bearer_auth(ADMIN_KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["message"], "Paused");
+
+        let response = client
+            .post(format!("{}/admin/resume", url))
+            .bearer_auth(ADMIN_KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "Not paused");
+    }
+}
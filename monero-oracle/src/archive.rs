@@ -0,0 +1,418 @@
+//! Object storage for block exports and database backups
+//!
+//! Archives live behind the [`Storage`] trait: a local directory, or an
+//! S3-compatible bucket (`s3://`, or `gs://` for Google Cloud Storage) through
+//! [`S3Bucket`]. Block exports (`BLOCK_EXPORT_TARGET`) and database backups
+//! (`DB_BACKUP_TARGET`) each take one, so they can go to different places.
+//!
+//! With `DB_BACKUP_TARGET` set, the oracle copies its SQLite database every
+//! `DB_BACKUP_INTERVAL_SECS` to `oracle-{time}.db` and keeps the newest
+//! `DB_BACKUP_KEEP`. A copy is consistent: SQLite writes it in one
+//! transaction while the oracle carries on. After a host is lost,
+//! `restore-db` fetches the newest backup to `DATABASE_PATH`, and the oracle
+//! catches up from there: blocks posted since are indexed from the contract,
+//! and the operation journal and withdrawal queue pick up where the backup
+//! left them.
+
+use crate::{
+    metrics,
+    s3::{S3Bucket, S3Config},
+    storage::Database,
+    withdrawal::WithdrawalStatus,
+};
+use anyhow::{Context, Result};
+use axum::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{error, info, warn};
+
+/// Key prefix of database backups
+pub const BACKUP_PREFIX: &str = "oracle-";
+
+/// Time format of backup keys, which sorts them oldest first
+const BACKUP_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Where objects are kept
+#[async_trait]
+pub trait Storage: fmt::Display + Send + Sync {
+    /// Write `body` to `key`, replacing any object there
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Delete `key`; deleting a missing key succeeds
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Every key starting with `prefix`, in lexicographic order
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+}
+
+/// `BLOCK_EXPORT_TARGET` or `DB_BACKUP_TARGET`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveTarget {
+    Dir(PathBuf),
+    /// `s3://` or `gs://`
+    S3(S3Config),
+}
+
+/// Storage for `target`, creating a directory that doesn't exist yet
+pub fn open(target: ArchiveTarget, client: reqwest::Client) -> Result<Arc<dyn Storage>> {
+    Ok(match target {
+        ArchiveTarget::Dir(dir) => Arc::new(LocalDir::new(dir)?),
+        ArchiveTarget::S3(config) => Arc::new(S3Bucket::new(config, client)),
+    })
+}
+
+/// A directory of files named by key
+pub struct LocalDir {
+    dir: PathBuf,
+}
+
+impl LocalDir {
+    pub fn new(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+}
+
+impl fmt::Display for LocalDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dir.display())
+    }
+}
+
+#[async_trait]
+impl Storage for LocalDir {
+    /// Written under a temporary name and renamed, so readers never see
+    /// half of a file
+    async fn put(&self, key: &str, body: Vec<u8>, _content_type: &str) -> Result<()> {
+        let path = self.dir.join(key);
+        let partial = self.dir.join(format!(".{}.partial", key));
+        tokio::fs::write(&partial, body)
+            .await
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        tokio::fs::rename(&partial, &path)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.dir.join(key);
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.dir.join(key);
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to delete {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut entries = tokio::fs::read_dir(&self.dir)
+            .await
+            .with_context(|| format!("Failed to list {}", self.dir.display()))?;
+        let mut keys = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(name) = entry.file_name().to_str() {
+                if name.starts_with(prefix) && !name.starts_with('.') {
+                    keys.push(name.to_string());
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+impl fmt::Display for S3Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.location())
+    }
+}
+
+#[async_trait]
+impl Storage for S3Bucket {
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        S3Bucket::put(self, key, body, content_type).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        S3Bucket::get(self, key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        S3Bucket::delete(self, key).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        S3Bucket::list(self, prefix).await
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupConfig {
+    pub target: ArchiveTarget,
+    /// `DB_BACKUP_INTERVAL_SECS`
+    pub interval: Duration,
+    /// `DB_BACKUP_KEEP`: backups kept, newest first
+    pub keep: usize,
+}
+
+/// Key of a backup taken at `time`
+pub fn backup_key(time: DateTime<Utc>) -> String {
+    format!("{}{}.db", BACKUP_PREFIX, time.format(BACKUP_TIME_FORMAT))
+}
+
+/// When the backup at `key` was taken, if it is one
+pub fn backup_time(key: &str) -> Option<DateTime<Utc>> {
+    let time = key.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".db")?;
+    NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT)
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// Periodic copies of the database, with the oldest deleted
+pub struct DbBackups {
+    storage: Arc<dyn Storage>,
+    interval: Duration,
+    keep: usize,
+}
+
+impl DbBackups {
+    pub fn new(config: BackupConfig, client: reqwest::Client) -> Result<Self> {
+        Ok(Self {
+            storage: open(config.target, client)?,
+            interval: config.interval,
+            keep: config.keep,
+        })
+    }
+
+    /// Backups in storage, oldest first
+    pub async fn list(&self) -> Result<Vec<String>> {
+        Ok(self
+            .storage
+            .list(BACKUP_PREFIX)
+            .await?
+            .into_iter()
+            .filter(|key| backup_time(key).is_some())
+            .collect())
+    }
+
+    /// Copy `db` to `scratch`, upload it and return its key
+    pub async fn backup(&self, db: Arc<Database>, scratch: &Path) -> Result<String> {
+        let key = backup_key(Utc::now());
+        let _ = tokio::fs::remove_file(scratch).await;
+        let copy = scratch.to_path_buf();
+        tokio::task::spawn_blocking(move || db.backup_to(&copy)).await??;
+        let body = tokio::fs::read(scratch).await;
+        let _ = tokio::fs::remove_file(scratch).await;
+        self.storage
+            .put(&key, body?, "application/vnd.sqlite3")
+            .await?;
+        Ok(key)
+    }
+
+    /// Delete all but the newest `DB_BACKUP_KEEP` backups; returns how many
+    /// went
+    pub async fn prune(&self) -> Result<usize> {
+        let backups = self.list().await?;
+        let expired = backups.len().saturating_sub(self.keep);
+        for key in &backups[..expired] {
+            self.storage.delete(key).await?;
+            metrics::get().archive_objects_pruned.inc();
+        }
+        Ok(expired)
+    }
+
+    /// Write backup `key`, or the newest, to `path`, which must not exist
+    /// yet; returns the key restored and how many withdrawals were held.
+    ///
+    /// Withdrawals the backup has queued may have been paid since it was
+    /// taken, so they are marked failed for an operator to requeue once
+    /// they know, as after a leader takeover.
+    pub async fn restore(&self, key: Option<String>, path: &Path) -> Result<(String, usize)> {
+        anyhow::ensure!(
+            !path.exists(),
+            "{} already exists; move it aside to restore over it",
+            path.display()
+        );
+        let key = match key {
+            Some(key) => key,
+            None => self
+                .list()
+                .await?
+                .pop()
+                .with_context(|| format!("No backups in {}", self.storage))?,
+        };
+        let body = self.storage.get(&key).await?;
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, body)
+            .await
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        let held = match Database::open(&partial)
+            .with_context(|| format!("{} is not a usable database", key))
+            .and_then(|db| hold_queued_withdrawals(&db))
+        {
+            Ok(held) => held,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e);
+            }
+        };
+        tokio::fs::rename(&partial, path)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok((key, held))
+    }
+
+    /// Back up `db` every `DB_BACKUP_INTERVAL_SECS`, the first time once the
+    /// newest backup in storage is that old. `scratch` holds each copy until
+    /// it is uploaded.
+    pub async fn run(self, db: Arc<Database>, scratch: PathBuf) {
+        let latest = match self.list().await {
+            Ok(backups) => backups.last().and_then(|key| backup_time(key)),
+            Err(e) => {
+                warn!("   ⚠️  Failed to list backups in {}: {:#}", self.storage, e);
+                None
+            }
+        };
+        let mut wait = due_in(latest, self.interval, Utc::now());
+        info!(
+            "🗄️  Backing up the database to {} every {}s (next in {}s)",
+            self.storage,
+            self.interval.as_secs(),
+            wait.as_secs()
+        );
+        loop {
+            tokio::time::sleep(wait).await;
+            wait = self.interval;
+            match self.backup(db.clone(), &scratch).await {
+                Ok(key) => {
+                    metrics::get().db_backups.inc();
+                    info!("🗄️  Backed up the database to {}{}", self.storage, key);
+                }
+                Err(e) => {
+                    metrics::get().db_backup_failures.inc();
+                    error!("❌ Database backup to {} failed: {:#}", self.storage, e);
+                    continue;
+                }
+            }
+            if let Err(e) = self.prune().await {
+                warn!(
+                    "   ⚠️  Failed to prune backups in {}: {:#}",
+                    self.storage, e
+                );
+            }
+        }
+    }
+}
+
+fn hold_queued_withdrawals(db: &Database) -> Result<usize> {
+    let queued = db.withdrawals_with_status(WithdrawalStatus::Queued)?;
+    for withdrawal in &queued {
+        db.set_withdrawal_status(
+            withdrawal.burn_id,
+            WithdrawalStatus::Failed,
+            Some("Queued in a restored backup; requeue once known not to have been paid since"),
+        )?;
+    }
+    Ok(queued.len())
+}
+
+/// Time until the next backup, given when the newest was taken
+pub fn due_in(latest: Option<DateTime<Utc>>, interval: Duration, now: DateTime<Utc>) -> Duration {
+    let interval = chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::MAX);
+    latest
+        .and_then(|latest| (latest + interval - now).to_std().ok())
+        .unwrap_or(Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("monero-oracle-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_backup_keys() {
+        let time = DateTime::parse_from_rfc3339("2025-03-01T04:05:06Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(backup_key(time), "oracle-20250301T040506Z.db");
+        assert_eq!(backup_time(&backup_key(time)), Some(time));
+        assert_eq!(backup_time("oracle-latest.db"), None);
+        assert_eq!(backup_time("0003100000.json"), None);
+
+        let day = Duration::from_secs(86_400);
+        assert_eq!(due_in(None, day, time), Duration::ZERO);
+        assert_eq!(
+            due_in(Some(time - chrono::Duration::hours(20)), day, time),
+            Duration::from_secs(4 * 3600)
+        );
+        assert_eq!(
+            due_in(Some(time - chrono::Duration::days(3)), day, time),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backup_prune_and_restore() {
+        let dir = scratch_dir("backups");
+        let _ = std::fs::remove_dir_all(&dir);
+        let backups = DbBackups::new(
+            BackupConfig {
+                target: ArchiveTarget::Dir(dir.join("archive")),
+                interval: Duration::from_secs(3600),
+                keep: 2,
+            },
+            reqwest::Client::new(),
+        )
+        .unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        db.set_unichain_cursor(1_234).unwrap();
+
+        // Older backups, and something that isn't one
+        for key in ["oracle-20240101T000000Z.db", "oracle-20240102T000000Z.db"] {
+            backups.storage.put(key, Vec::new(), "").await.unwrap();
+        }
+        backups
+            .storage
+            .put("notes.txt", Vec::new(), "")
+            .await
+            .unwrap();
+
+        let key = backups.backup(db, &dir.join("scratch.db")).await.unwrap();
+        assert!(!dir.join("scratch.db").exists());
+        assert_eq!(backups.prune().await.unwrap(), 1);
+        assert_eq!(
+            backups.list().await.unwrap(),
+            vec!["oracle-20240102T000000Z.db".to_string(), key.clone()]
+        );
+
+        let restored = dir.join("restored.db");
+        assert_eq!(backups.restore(None, &restored).await.unwrap(), (key, 0));
+        assert_eq!(
+            Database::open(&restored)
+                .unwrap()
+                .unichain_cursor()
+                .unwrap(),
+            Some(1_234)
+        );
+        assert!(backups.restore(None, &restored).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+//! Multi-signature oracle: EIP-712 block attestations
+//!
+//! Instead of one oracle key posting blocks, N signers each compute the block
+//! data independently and sign a `MoneroBlock` attestation, which they serve
+//! over the HTTP API. A submitter collects them and posts the block with
+//! `postMoneroBlockWithSignatures` once the contract's signer threshold is
+//! met. The contract recovers every signature against its own signer set, so
+//! the submitter can't forge or alter a block, only withhold it. With a
+//! threshold key (see [`crate::frost`]) the signers instead sign the same
+//! attestation together, as one Schnorr signature.
+
+use crate::frost;
+use alloy::{
+    primitives::{Address, Bytes, PrimitiveSignature, B256, U256},
+    signers::{local::PrivateKeySigner, SignerSync},
+    sol,
+    sol_types::{eip712_domain, Eip712Domain, SolStruct},
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+sol! {
+    /// Block data a signer vouches for; mirrors `MONERO_BLOCK_TYPEHASH`
+    struct MoneroBlock {
+        uint256 blockHeight;
+        bytes32 blockHash;
+        bytes32 txMerkleRoot;
+        bytes32 outputMerkleRoot;
+    }
+}
+
+/// How this oracle instance takes part in posting blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OracleMode {
+    /// Sole oracle key, posts with `postMoneroBlock`
+    Single,
+    /// Signs attestations and serves them; never sends transactions
+    Signer,
+    /// Signs, collects peer attestations and posts them on-chain
+    Submitter,
+}
+
+impl FromStr for OracleMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "single" => Ok(Self::Single),
+            "signer" => Ok(Self::Signer),
+            "submitter" => Ok(Self::Submitter),
+            other => anyhow::bail!(
+                "Unknown oracle mode '{}' (expected single, signer or submitter)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for OracleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Single => "single",
+            Self::Signer => "signer",
+            Self::Submitter => "submitter",
+        })
+    }
+}
+
+/// EIP-712 domain of the WrappedMonero contract (its `ERC20Permit` name)
+pub fn domain(chain_id: u64, bridge: Address) -> Eip712Domain {
+    eip712_domain! {
+        name: "Hooked Monero",
+        version: "1",
+        chain_id: chain_id,
+        verifying_contract: bridge,
+    }
+}
+
+/// One signer's signature over a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attestation {
+    pub height: u64,
+    pub block_hash: B256,
+    pub tx_merkle_root: B256,
+    pub output_merkle_root: B256,
+    pub signer: Address,
+    /// 65-byte `r || s || v` signature over the EIP-712 digest
+    pub signature: Bytes,
+}
+
+impl Attestation {
+    /// Sign the block data with a local `signer`
+    pub fn sign(
+        signer: &PrivateKeySigner,
+        domain: &Eip712Domain,
+        height: u64,
+        block_hash: B256,
+        tx_merkle_root: B256,
+        output_merkle_root: B256,
+    ) -> Result<Self> {
+        let attestation = Self::unsigned(
+            signer.address(),
+            height,
+            block_hash,
+            tx_merkle_root,
+            output_merkle_root,
+        );
+        let signature = signer.sign_hash_sync(&attestation.signing_hash(domain))?;
+        Ok(attestation.with_signature(signature))
+    }
+
+    /// Block data claimed by `signer`, to be signed with a key that signs
+    /// asynchronously (KMS) and attached with [`Attestation::with_signature`]
+    pub fn unsigned(
+        signer: Address,
+        height: u64,
+        block_hash: B256,
+        tx_merkle_root: B256,
+        output_merkle_root: B256,
+    ) -> Self {
+        Self {
+            height,
+            block_hash,
+            tx_merkle_root,
+            output_merkle_root,
+            signer,
+            signature: Bytes::new(),
+        }
+    }
+
+    pub fn with_signature(mut self, signature: PrimitiveSignature) -> Self {
+        self.signature = Bytes::copy_from_slice(&signature.as_bytes());
+        self
+    }
+
+    pub fn message(&self) -> MoneroBlock {
+        MoneroBlock {
+            blockHeight: U256::from(self.height),
+            blockHash: self.block_hash,
+            txMerkleRoot: self.tx_merkle_root,
+            outputMerkleRoot: self.output_merkle_root,
+        }
+    }
+
+    pub fn signing_hash(&self, domain: &Eip712Domain) -> B256 {
+        self.message().eip712_signing_hash(domain)
+    }
+
+    /// Check that the signature was made by `signer` over this block data
+    pub fn verify(&self, domain: &Eip712Domain) -> Result<()> {
+        let signature = PrimitiveSignature::try_from(self.signature.as_ref())
+            .context("Malformed attestation signature")?;
+        let recovered = signature.recover_address_from_prehash(&self.signing_hash(domain))?;
+        anyhow::ensure!(
+            recovered == self.signer,
+            "Attestation signed by {}, claims {}",
+            recovered,
+            self.signer
+        );
+        Ok(())
+    }
+
+    /// Whether both attestations vouch for the same block data
+    pub fn same_block(&self, other: &Self) -> bool {
+        self.height == other.height
+            && self.block_hash == other.block_hash
+            && self.tx_merkle_root == other.tx_merkle_root
+            && self.output_merkle_root == other.output_merkle_root
+    }
+}
+
+/// Signatures for `postMoneroBlockWithSignatures`: one per distinct signer,
+/// ordered by ascending signer address as the contract requires. `None` until
+/// `threshold` attestations are available.
+pub fn aggregate(attestations: &[Attestation], threshold: usize) -> Option<Vec<Bytes>> {
+    let mut sorted: Vec<&Attestation> = attestations.iter().collect();
+    sorted.sort_by_key(|attestation| attestation.signer);
+    sorted.dedup_by_key(|attestation| attestation.signer);
+
+    if threshold == 0 || sorted.len() < threshold {
+        return None;
+    }
+    Some(
+        sorted
+            .into_iter()
+            .map(|attestation| attestation.signature.clone())
+            .collect(),
+    )
+}
+
+/// What a block is posted with outside single mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockSignatures {
+    /// One per signer, for `postMoneroBlockWithSignatures`
+    Signers(Vec<Bytes>),
+    /// The threshold key's, for `postMoneroBlockWithThresholdSignature`
+    Threshold(frost::Signature),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(signer: &PrivateKeySigner, domain: &Eip712Domain, block_hash: B256) -> Attestation {
+        Attestation::sign(
+            signer,
+            domain,
+            3_100_000,
+            block_hash,
+            B256::repeat_byte(0x02),
+            B256::repeat_byte(0x03),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_signing_hash_matches_typehash() {
+        let typehash = alloy::primitives::keccak256(
+            "MoneroBlock(uint256 blockHeight,bytes32 blockHash,bytes32 txMerkleRoot,bytes32 outputMerkleRoot)",
+        );
+        let block = MoneroBlock {
+            blockHeight: U256::ZERO,
+            blockHash: B256::ZERO,
+            txMerkleRoot: B256::ZERO,
+            outputMerkleRoot: B256::ZERO,
+        };
+        assert_eq!(block.eip712_type_hash(), typehash);
+    }
+
+    #[test]
+    fn test_attestations_verify_and_aggregate() {
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let signers: Vec<PrivateKeySigner> = (0..3).map(|_| PrivateKeySigner::random()).collect();
+        let attestations: Vec<Attestation> = signers
+            .iter()
+            .map(|signer| sign(signer, &bridge, B256::repeat_byte(0x01)))
+            .collect();
+
+        for attestation in &attestations {
+            attestation.verify(&bridge).unwrap();
+        }
+
+        // Valid for this contract only
+        let other_bridge = domain(130, Address::repeat_byte(0x43));
+        assert!(attestations[0].verify(&other_bridge).is_err());
+
+        // Altered block data no longer matches the signature
+        let mut forged = attestations[0].clone();
+        forged.block_hash = B256::repeat_byte(0xff);
+        assert!(forged.verify(&bridge).is_err());
+        assert!(!forged.same_block(&attestations[0]));
+
+        assert_eq!(aggregate(&attestations[..1], 2), None);
+
+        let mut duplicated = attestations.clone();
+        duplicated.push(attestations[1].clone());
+        let signatures = aggregate(&duplicated, 2).unwrap();
+        assert_eq!(signatures.len(), 3);
+
+        let mut by_signer = attestations.clone();
+        by_signer.sort_by_key(|attestation| attestation.signer);
+        let expected: Vec<Bytes> = by_signer.into_iter().map(|a| a.signature).collect();
+        assert_eq!(signatures, expected);
+    }
+}
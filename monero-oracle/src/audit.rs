@@ -0,0 +1,201 @@
+//! Reconciliation of the Monero chain, the contract and the local database
+//!
+//! `audit` recomputes each block's hash and Merkle roots from monerod and
+//! compares them with what the contract stores and what the database
+//! recorded as posted. The report lists every disagreement, so anyone with a
+//! node can check that the bridge's data matches the chain.
+
+use crate::eth::StoredBlock;
+use alloy::primitives::Address;
+use serde::Serialize;
+
+/// Where a block's data was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+    Contract,
+    Database,
+}
+
+/// How a source disagrees with the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Issue {
+    /// At or below the contract's latest block, but not stored there; or
+    /// stored in the contract but not recorded in the database
+    Missing,
+    /// Stored with a hash or root that differs from the computed one
+    Mismatch,
+    /// Recorded as posted in the database, but not stored in the contract
+    NotInContract,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discrepancy {
+    pub height: u64,
+    pub source: Source,
+    pub issue: Issue,
+    /// Fields that differ, for a mismatch
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<&'static str>,
+    /// Block data computed from monerod
+    pub computed: StoredBlock,
+    /// What the source holds, if anything
+    pub found: Option<StoredBlock>,
+}
+
+/// Result of auditing blocks `from` to `to`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditReport {
+    pub from: u64,
+    pub to: u64,
+    pub bridge_address: Address,
+    pub contract_latest_block: u64,
+    pub blocks_checked: u64,
+    /// Blocks above the contract's latest block, which only the database
+    /// is checked for
+    pub blocks_not_posted: u64,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl AuditReport {
+    pub fn new(from: u64, to: u64, bridge_address: Address, contract_latest_block: u64) -> Self {
+        Self {
+            from,
+            to,
+            bridge_address,
+            contract_latest_block,
+            blocks_checked: 0,
+            blocks_not_posted: 0,
+            discrepancies: Vec::new(),
+        }
+    }
+
+    /// Compare the contract's and database's data for `height` with the
+    /// block computed from the chain
+    pub fn check(
+        &mut self,
+        height: u64,
+        computed: &StoredBlock,
+        contract: Option<&StoredBlock>,
+        database: Option<&StoredBlock>,
+    ) {
+        self.blocks_checked += 1;
+        if height > self.contract_latest_block {
+            self.blocks_not_posted += 1;
+        }
+
+        let mut report = |source, issue, found: Option<&StoredBlock>| {
+            self.discrepancies.push(Discrepancy {
+                height,
+                source,
+                issue,
+                fields: found
+                    .map(|found| found.mismatches(computed))
+                    .unwrap_or_default(),
+                computed: *computed,
+                found: found.copied(),
+            })
+        };
+
+        match contract {
+            Some(stored) if stored != computed => {
+                report(Source::Contract, Issue::Mismatch, Some(stored))
+            }
+            Some(_) => {}
+            None if height <= self.contract_latest_block => {
+                report(Source::Contract, Issue::Missing, None)
+            }
+            None => {}
+        }
+
+        match (database, contract) {
+            (Some(recorded), _) if recorded != computed => {
+                report(Source::Database, Issue::Mismatch, Some(recorded))
+            }
+            (Some(recorded), None) => {
+                report(Source::Database, Issue::NotInContract, Some(recorded))
+            }
+            (None, Some(_)) => report(Source::Database, Issue::Missing, None),
+            _ => {}
+        }
+    }
+
+    /// Whether every source agrees with the chain
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::B256;
+
+    fn block(byte: u8) -> StoredBlock {
+        StoredBlock {
+            block_hash: B256::from([byte; 32]),
+            tx_merkle_root: B256::from([byte.wrapping_add(1); 32]),
+            output_merkle_root: B256::from([byte.wrapping_add(2); 32]),
+        }
+    }
+
+    #[test]
+    fn test_audit_agreeing_sources() {
+        let mut report = AuditReport::new(100, 102, Address::ZERO, 101);
+        report.check(100, &block(1), Some(&block(1)), Some(&block(1)));
+        report.check(101, &block(2), Some(&block(2)), Some(&block(2)));
+        // Not posted yet, and not recorded either
+        report.check(102, &block(3), None, None);
+
+        assert!(report.is_clean());
+        assert_eq!(report.blocks_checked, 3);
+        assert_eq!(report.blocks_not_posted, 1);
+    }
+
+    #[test]
+    fn test_audit_discrepancies() {
+        let mut report = AuditReport::new(100, 102, Address::ZERO, 102);
+        let wrong_root = StoredBlock {
+            output_merkle_root: B256::ZERO,
+            ..block(1)
+        };
+        report.check(100, &block(1), Some(&wrong_root), None);
+        report.check(101, &block(2), None, Some(&block(2)));
+        report.check(102, &block(3), Some(&block(3)), Some(&block(4)));
+
+        let found: Vec<_> = report
+            .discrepancies
+            .iter()
+            .map(|d| (d.height, d.source, d.issue, d.fields.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    100,
+                    Source::Contract,
+                    Issue::Mismatch,
+                    vec!["outputMerkleRoot"]
+                ),
+                (100, Source::Database, Issue::Missing, vec![]),
+                (101, Source::Contract, Issue::Missing, vec![]),
+                (101, Source::Database, Issue::NotInContract, vec![]),
+                (
+                    102,
+                    Source::Database,
+                    Issue::Mismatch,
+                    vec!["blockHash", "txMerkleRoot", "outputMerkleRoot"]
+                ),
+            ]
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["discrepancies"][0]["source"], "contract");
+        assert_eq!(json["discrepancies"][0]["issue"], "mismatch");
+        assert_eq!(json["discrepancies"][3]["issue"], "notInContract");
+        assert!(json["discrepancies"][1]["found"].is_null());
+    }
+}
@@ -0,0 +1,520 @@
+//! Canonical JSON export of every processed block
+//!
+//! With `BLOCK_EXPORT_TARGET` set, each block the oracle processes is written
+//! out as `{height}.json` (zero-padded to ten digits, so keys sort by
+//! height) to one of the [archive](crate::archive) backends: a directory, or
+//! an S3-compatible or Google Cloud Storage bucket. The
+//! artifact holds everything the posted roots are built from: the header
+//! fields the block hash commits to, the tx hashes that are the tx tree's
+//! leaves, and every output with its leaf, in leaf order. Anyone can
+//! recompute the block hash, both trees and their roots from it alone and
+//! hold them against the contract's `moneroBlocks(height)`, as
+//! [`BlockArtifact::verify`] and the `verify-export` command do.
+//!
+//! The JSON is deterministic: fields in a fixed order, 32-byte values as
+//! lowercase `0x` hex, and numbers that can exceed 64 bits as decimal
+//! strings. A block processed again (after a reorg, say) replaces its file.
+//! With `BLOCK_EXPORT_RETAIN_BLOCKS`, artifacts further below the latest than
+//! that are deleted.
+
+use crate::{
+    archive::{self, ArchiveTarget, Storage},
+    merkle::{self, MerkleHasher, MerkleTree},
+    metrics,
+    monero_rpc::{parse_hex_to_b256, BlockHeader, MoneroOutput},
+};
+use alloy::primitives::B256;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// `format` of the artifacts written by this version
+pub const FORMAT_VERSION: u32 = 1;
+
+/// How often expired artifacts are looked for, with
+/// `BLOCK_EXPORT_RETAIN_BLOCKS`
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How an output leaf is hashed, as recorded in each artifact
+pub const OUTPUT_LEAF: &str =
+    "keccak256(txHash ++ uint256(outputIndex) ++ ecdhAmount ++ outputPubKey ++ commitment)";
+
+/// How a tx leaf is formed in version 2 tx trees; version 1 takes `txHash` as is
+pub const TX_LEAF_V2: &str = "keccak256(0x00 ++ txHash)";
+
+/// [`OUTPUT_LEAF`] of version 2 output trees
+pub const OUTPUT_LEAF_V2: &str =
+    "keccak256(0x00 ++ txHash ++ uint256(outputIndex) ++ ecdhAmount ++ outputPubKey ++ commitment)";
+
+/// Header fields the block hash commits to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactHeader {
+    pub height: u64,
+    pub hash: B256,
+    pub prev_hash: B256,
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub timestamp: u64,
+    pub nonce: u32,
+    /// Decimal, as it outgrows 64 bits
+    pub cumulative_difficulty: String,
+}
+
+/// An output tree leaf and what it is hashed from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactOutput {
+    #[serde(flatten)]
+    pub output: MoneroOutput,
+    pub leaf: B256,
+}
+
+/// Shape and root of one of a block's trees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeSummary {
+    /// Hash of internal nodes; an odd level duplicates its last node
+    pub hasher: MerkleHasher,
+    /// How a leaf is formed
+    pub leaf: String,
+    pub leaf_count: usize,
+    /// As posted to the contract; zero for an empty tree
+    pub root: B256,
+}
+
+/// Everything a block's posted roots are computed from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockArtifact {
+    /// [`FORMAT_VERSION`]
+    pub format: u32,
+    pub header: ArtifactHeader,
+    /// First in Monero's tree hash, but not a leaf of the tx tree
+    pub miner_tx_hash: B256,
+    /// Monero's tree hash over the miner tx and `txHashes`, in the block hash
+    pub monero_tree_root: B256,
+    /// Every other tx hash in block order, from which the tx tree's leaves
+    /// are formed
+    pub tx_hashes: Vec<B256>,
+    /// The output tree's leaves in order: the outputs of each transaction,
+    /// miner tx first, by output index
+    pub outputs: Vec<ArtifactOutput>,
+    pub tx_tree: TreeSummary,
+    pub output_tree: TreeSummary,
+}
+
+impl BlockArtifact {
+    /// Artifact of a processed block, from the trees already built for it
+    pub fn new(
+        header: &BlockHeader,
+        miner_tx_hash: B256,
+        monero_tree_root: B256,
+        tx_hashes: &[String],
+        tx_tree: &MerkleTree,
+        outputs: &[MoneroOutput],
+        output_tree: &MerkleTree,
+    ) -> Result<Self> {
+        let tx_hashes = merkle::tx_leaves(tx_hashes);
+        anyhow::ensure!(
+            tx_hashes.len() == tx_tree.len(),
+            "{} tx hashes for {} tx leaves",
+            tx_hashes.len(),
+            tx_tree.len()
+        );
+        anyhow::ensure!(
+            outputs.len() == output_tree.len(),
+            "{} outputs for {} output leaves",
+            outputs.len(),
+            output_tree.len()
+        );
+        let tx_order: Vec<B256> = std::iter::once(miner_tx_hash)
+            .chain(tx_hashes.iter().copied().map(B256::from))
+            .collect();
+        let outputs = output_tree.hasher().output_order(outputs, &tx_order);
+        Ok(Self {
+            format: FORMAT_VERSION,
+            header: ArtifactHeader {
+                height: header.height,
+                hash: parse_hex_to_b256(&header.hash)?,
+                prev_hash: parse_hex_to_b256(&header.prev_hash)?,
+                major_version: header.major_version,
+                minor_version: header.minor_version,
+                timestamp: header.timestamp,
+                nonce: header.nonce,
+                cumulative_difficulty: header.cumulative_difficulty()?.to_string(),
+            },
+            miner_tx_hash,
+            monero_tree_root,
+            tx_hashes: tx_hashes.into_iter().map(B256::from).collect(),
+            outputs: outputs
+                .iter()
+                .zip(output_tree.leaves())
+                .map(|(output, &leaf)| ArtifactOutput {
+                    output: output.clone(),
+                    leaf: B256::from(leaf),
+                })
+                .collect(),
+            tx_tree: TreeSummary {
+                hasher: tx_tree.hasher(),
+                leaf: match tx_tree.hasher() {
+                    MerkleHasher::V2 => TX_LEAF_V2,
+                    MerkleHasher::Keccak256 | MerkleHasher::Sha256 => "txHash",
+                }
+                .to_string(),
+                leaf_count: tx_tree.len(),
+                root: tx_tree.root(),
+            },
+            output_tree: TreeSummary {
+                hasher: output_tree.hasher(),
+                leaf: match output_tree.hasher() {
+                    MerkleHasher::V2 => OUTPUT_LEAF_V2,
+                    MerkleHasher::Keccak256 | MerkleHasher::Sha256 => OUTPUT_LEAF,
+                }
+                .to_string(),
+                leaf_count: output_tree.len(),
+                root: output_tree.root(),
+            },
+        })
+    }
+
+    /// The canonical bytes written for this artifact
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        Ok(json)
+    }
+
+    /// Recompute the block hash, every leaf and both roots from the
+    /// artifact's own data, and check them against what it states
+    pub fn verify(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.format == FORMAT_VERSION,
+            "Unsupported artifact format {}",
+            self.format
+        );
+        let header = BlockHeader {
+            height: self.header.height,
+            hash: hex::encode(self.header.hash),
+            major_version: self.header.major_version,
+            minor_version: self.header.minor_version,
+            timestamp: self.header.timestamp,
+            prev_hash: hex::encode(self.header.prev_hash),
+            nonce: self.header.nonce,
+            difficulty: 0,
+            wide_difficulty: None,
+            cumulative_difficulty: 0,
+            wide_cumulative_difficulty: None,
+        };
+        let tx_hashes: Vec<String> = self.tx_hashes.iter().map(hex::encode).collect();
+        let tree_root =
+            merkle::verify_block_tx_hashes(&header, &hex::encode(self.miner_tx_hash), &tx_hashes)?;
+        anyhow::ensure!(
+            tree_root == self.monero_tree_root,
+            "Monero tree root is {}, not {}",
+            tree_root,
+            self.monero_tree_root
+        );
+
+        let tx_tree = merkle::tx_tree(&tx_hashes, self.tx_tree.hasher);
+        check_tree("tx", &self.tx_tree, &tx_tree)?;
+
+        let hasher = self.output_tree.hasher;
+        let outputs: Vec<MoneroOutput> = self.outputs.iter().map(|o| o.output.clone()).collect();
+        anyhow::ensure!(
+            *hasher.output_order(&outputs, &self.tx_order()) == outputs[..],
+            "Outputs are not in the output tree's leaf order"
+        );
+        for (index, output) in self.outputs.iter().enumerate() {
+            let leaf = B256::from(hasher.output_leaf(&output.output));
+            anyhow::ensure!(
+                leaf == output.leaf,
+                "Output leaf {} is {}, not {}",
+                index,
+                leaf,
+                output.leaf
+            );
+        }
+        let output_tree = merkle::output_tree(&outputs, hasher);
+        check_tree("output", &self.output_tree, &output_tree)
+    }
+
+    /// Every tx hash in block order, miner tx first
+    fn tx_order(&self) -> Vec<B256> {
+        std::iter::once(self.miner_tx_hash)
+            .chain(self.tx_hashes.iter().copied())
+            .collect()
+    }
+}
+
+fn check_tree(kind: &str, stated: &TreeSummary, computed: &MerkleTree) -> Result<()> {
+    anyhow::ensure!(
+        stated.hasher == computed.hasher(),
+        "{} tree is hashed with {}, not {}",
+        kind,
+        computed.hasher(),
+        stated.hasher
+    );
+    anyhow::ensure!(
+        stated.leaf_count == computed.len(),
+        "{} tree has {} leaves, not {}",
+        kind,
+        computed.len(),
+        stated.leaf_count
+    );
+    anyhow::ensure!(
+        stated.root == computed.root(),
+        "{} tree root is {}, not {}",
+        kind,
+        computed.root(),
+        stated.root
+    );
+    Ok(())
+}
+
+/// Object key or file name of block `height`'s artifact
+pub fn artifact_key(height: u64) -> String {
+    format!("{:010}.json", height)
+}
+
+/// Height of the artifact at `key`, if it is one
+pub fn artifact_height(key: &str) -> Option<u64> {
+    let height = key.strip_suffix(".json")?;
+    (height.len() == 10).then(|| height.parse().ok())?
+}
+
+/// Writes block artifacts to `BLOCK_EXPORT_TARGET`, deleting any more than
+/// `BLOCK_EXPORT_RETAIN_BLOCKS` below the latest
+pub struct BlockExporter {
+    storage: Arc<dyn Storage>,
+    retain: Option<u64>,
+    /// When old artifacts were last looked for
+    last_prune: Mutex<Option<Instant>>,
+}
+
+impl BlockExporter {
+    pub fn new(
+        target: ArchiveTarget,
+        retain: Option<u64>,
+        client: reqwest::Client,
+    ) -> Result<Self> {
+        Ok(Self {
+            storage: archive::open(target, client)?,
+            retain,
+            last_prune: Mutex::default(),
+        })
+    }
+
+    /// Write `artifact`, replacing any earlier one of its height, and
+    /// delete expired ones at most every [`PRUNE_INTERVAL`]
+    pub async fn export(&self, artifact: &BlockArtifact) -> Result<()> {
+        let height = artifact.header.height;
+        self.storage
+            .put(
+                &artifact_key(height),
+                artifact.to_json()?,
+                "application/json",
+            )
+            .await?;
+
+        let due = {
+            let mut last_prune = self.last_prune.lock().expect("prune time poisoned");
+            let due = self.retain.is_some()
+                && last_prune.is_none_or(|last| last.elapsed() >= PRUNE_INTERVAL);
+            if due {
+                *last_prune = Some(Instant::now());
+            }
+            due
+        };
+        if due {
+            if let Err(e) = self.prune(height).await {
+                warn!("   ⚠️  Failed to prune block exports in {}: {:#}", self, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the artifacts more than `BLOCK_EXPORT_RETAIN_BLOCKS` below
+    /// `height`; returns how many went
+    pub async fn prune(&self, height: u64) -> Result<usize> {
+        let Some(retain) = self.retain else {
+            return Ok(0);
+        };
+        let mut pruned = 0;
+        for key in self.storage.list("").await? {
+            if artifact_height(&key).is_some_and(|h| h + retain <= height) {
+                self.storage.delete(&key).await?;
+                metrics::get().archive_objects_pruned.inc();
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+impl std::fmt::Display for BlockExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A block whose hash is consistent with its transactions
+    fn artifact() -> BlockArtifact {
+        artifact_with(MerkleHasher::Keccak256)
+    }
+
+    /// Both trees in the version of `hasher`
+    fn artifact_with(hasher: MerkleHasher) -> BlockArtifact {
+        let miner_tx_hash = B256::repeat_byte(0x11);
+        let tx_hashes = vec![hex::encode([0x22; 32]), hex::encode([0x33; 32])];
+        let outputs: Vec<MoneroOutput> = (0..3)
+            .map(|index| MoneroOutput {
+                tx_hash: if index == 0 {
+                    miner_tx_hash
+                } else {
+                    B256::repeat_byte(0x22)
+                },
+                output_index: index,
+                ecdh_amount: B256::repeat_byte(0x40 + index as u8),
+                output_pub_key: B256::repeat_byte(0x50 + index as u8),
+                commitment: B256::repeat_byte(0x60 + index as u8),
+            })
+            .collect();
+        let mut header = BlockHeader {
+            height: 3_100_000,
+            hash: String::new(),
+            major_version: 16,
+            minor_version: 16,
+            timestamp: 1_700_000_000,
+            prev_hash: hex::encode([0x0a; 32]),
+            nonce: 42,
+            difficulty: 0,
+            wide_difficulty: None,
+            cumulative_difficulty: 0,
+            wide_cumulative_difficulty: Some("0x1000000000000000000".to_string()),
+        };
+        let leaves = [[0x11; 32], [0x22; 32], [0x33; 32]];
+        let tree_root = merkle::monero_tree_hash(&leaves);
+        header.hash = hex::encode(merkle::compute_block_id(&header, &tree_root, 3).unwrap());
+
+        BlockArtifact::new(
+            &header,
+            miner_tx_hash,
+            B256::from(tree_root),
+            &tx_hashes,
+            &merkle::tx_tree(&tx_hashes, hasher),
+            &outputs,
+            &merkle::output_tree(&outputs, hasher),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_artifact_verifies_and_reads_back() {
+        let artifact = artifact();
+        artifact.verify().unwrap();
+        assert_eq!(artifact.tx_tree.leaf_count, 2);
+        assert_eq!(
+            artifact.header.cumulative_difficulty,
+            "4722366482869645213696"
+        );
+
+        let json = artifact.to_json().unwrap();
+        assert_eq!(artifact.to_json().unwrap(), json);
+        let read: BlockArtifact = serde_json::from_slice(&json).unwrap();
+        assert_eq!(read, artifact);
+        let text = String::from_utf8(json).unwrap();
+        assert!(text.contains("\"outputPubKey\""));
+        assert!(text.ends_with("}\n"));
+        assert_eq!(artifact_key(artifact.header.height), "0003100000.json");
+    }
+
+    #[test]
+    fn test_verify_catches_edits() {
+        let mut reordered = artifact();
+        reordered.outputs.swap(1, 2);
+        assert!(reordered.verify().is_err());
+
+        let mut dropped = artifact();
+        dropped.tx_hashes.pop();
+        assert!(dropped.verify().is_err());
+
+        let mut wrong_root = artifact();
+        wrong_root.output_tree.root = B256::repeat_byte(0xff);
+        assert!(wrong_root.verify().is_err());
+
+        let mut other_hasher = artifact();
+        other_hasher.output_tree.hasher = MerkleHasher::Sha256;
+        assert!(other_hasher.verify().is_err());
+    }
+
+    #[test]
+    fn test_v2_artifact() {
+        let v2 = artifact_with(MerkleHasher::V2);
+        v2.verify().unwrap();
+        assert_eq!(v2.output_tree.leaf, OUTPUT_LEAF_V2);
+        assert_eq!(v2.tx_tree.leaf, TX_LEAF_V2);
+        assert_eq!(v2.tx_hashes, artifact().tx_hashes);
+        assert_ne!(v2.output_tree.root, artifact().output_tree.root);
+        assert_ne!(v2.tx_tree.root, artifact().tx_tree.root);
+
+        // Out of canonical order, even with the root left alone
+        let mut reordered = v2.clone();
+        reordered.outputs.swap(1, 2);
+        assert!(reordered.verify().is_err());
+
+        let mut relabelled = v2;
+        relabelled.output_tree.hasher = MerkleHasher::Keccak256;
+        assert!(relabelled.verify().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_to_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("monero-oracle-block-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for height in [3_099_000, 3_099_999, 3_100_001] {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(artifact_key(height)), b"{}").unwrap();
+        }
+        std::fs::write(dir.join("README.txt"), b"").unwrap();
+
+        let artifact = artifact();
+        let exporter = BlockExporter::new(
+            ArchiveTarget::Dir(dir.clone()),
+            Some(1_000),
+            reqwest::Client::new(),
+        )
+        .unwrap();
+        exporter.export(&artifact).await.unwrap();
+
+        let written = std::fs::read(dir.join("0003100000.json")).unwrap();
+        assert_eq!(written, artifact.to_json().unwrap());
+        // Only the artifact 1,000 blocks down went
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "0003099999.json",
+                "0003100000.json",
+                "0003100001.json",
+                "README.txt"
+            ]
+        );
+        assert_eq!(artifact_height("0003100000.json"), Some(3_100_000));
+        assert_eq!(artifact_height("3100000.json"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
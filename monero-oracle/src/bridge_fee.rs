@@ -0,0 +1,348 @@
+//! Bridge fees charged on mints and withdrawals, and the treasury they go to
+//!
+//! Deposits are minted and withdrawals paid net of a fee: `BRIDGE_FEE_BPS`
+//! of the amount plus `BRIDGE_FEE_USD` dollars' worth of XMR, converted at
+//! the [`price`](crate::price) rate read when the operation is first
+//! attempted. The charge is recorded with its rate before the mint or payout
+//! is sent and reused by any retry, so an operation is charged once, at one
+//! rate, and it counts as collected once the mint or payout goes through.
+//!
+//! The contract can only mint what a deposit paid to its recipient, so fees
+//! are retained as XMR in the bridge wallet rather than minted. The
+//! `sweep-fees` command sends what has been collected and not yet swept to
+//! `TREASURY_XMR_ADDRESS`, and `fee-report` sums a month of collections.
+
+use crate::{costs::Month, price::Rate, scanner::format_xmr};
+use alloy::primitives::B256;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Basis points in the whole amount
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Highest `BRIDGE_FEE_BPS` accepted (10%)
+pub const MAX_BPS: u32 = 1_000;
+
+/// What each mint and payout is charged
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeeSchedule {
+    /// Share of the amount, in basis points
+    pub bps: u32,
+    /// Fixed dollar value, converted at the XMR/USD rate
+    pub usd: Option<f64>,
+}
+
+impl FeeSchedule {
+    pub fn is_free(&self) -> bool {
+        self.bps == 0 && self.usd.is_none()
+    }
+
+    /// Fee on `amount`, rounded up. The dollar part needs `rate`, and is left
+    /// out without one.
+    pub fn fee(&self, amount: u64, rate: Option<&Rate>) -> u64 {
+        let share = (amount as u128 * self.bps as u128).div_ceil(BPS_DENOMINATOR as u128) as u64;
+        let fixed = match (self.usd, rate) {
+            (Some(usd), Some(rate)) => rate.piconero(usd),
+            _ => 0,
+        };
+        share.saturating_add(fixed)
+    }
+}
+
+impl fmt::Display for FeeSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.bps, self.usd) {
+            (0, None) => f.write_str("none"),
+            (bps, None) => write!(f, "{} bps", bps),
+            (0, Some(usd)) => write!(f, "${:.2}", usd),
+            (bps, Some(usd)) => write!(f, "{} bps + ${:.2}", bps, usd),
+        }
+    }
+}
+
+/// Operation a fee is charged on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeKind {
+    Mint,
+    Withdrawal,
+}
+
+impl fmt::Display for FeeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mint => "mint",
+            Self::Withdrawal => "withdrawal",
+        })
+    }
+}
+
+impl FromStr for FeeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mint" => Ok(Self::Mint),
+            "withdrawal" => Ok(Self::Withdrawal),
+            other => anyhow::bail!("Unknown fee kind '{}'", other),
+        }
+    }
+}
+
+/// Fee charged on one operation, in piconero
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeCharge {
+    pub kind: FeeKind,
+    /// `<tx hash>:<output index>` of a deposit, or the burn ID of a withdrawal
+    pub reference: String,
+    /// Amount before the fee
+    pub amount: u64,
+    pub fee: u64,
+    /// Rate the dollar part was converted at, if there is one
+    pub usd_per_xmr: Option<f64>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl FeeCharge {
+    /// Charge `schedule` on `amount`, with `rate` for its dollar part
+    pub fn quote(
+        kind: FeeKind,
+        reference: String,
+        amount: u64,
+        schedule: &FeeSchedule,
+        rate: Option<&Rate>,
+    ) -> Self {
+        Self {
+            kind,
+            reference,
+            amount,
+            fee: schedule.fee(amount, rate),
+            usd_per_xmr: rate.map(|rate| rate.usd_per_xmr),
+            recorded_at: Utc::now(),
+        }
+    }
+
+    /// What is minted or paid after the fee, or `None` when the fee takes all
+    /// of it
+    pub fn net(&self) -> Option<u64> {
+        self.amount.checked_sub(self.fee).filter(|&net| net > 0)
+    }
+}
+
+impl fmt::Display for FeeCharge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} XMR", format_xmr(self.fee))?;
+        if let Some(usd_per_xmr) = self.usd_per_xmr {
+            write!(f, " at ${:.2}/XMR", usd_per_xmr)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reference of the mint of a deposit
+pub fn mint_reference(tx_hash: B256, output_index: u64) -> String {
+    format!("{}:{}", tx_hash, output_index)
+}
+
+/// Reference of the payout of a withdrawal
+pub fn withdrawal_reference(burn_id: u64) -> String {
+    burn_id.to_string()
+}
+
+/// Collected fees sent to the treasury by `sweep-fees`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSweep {
+    pub tx_hash: B256,
+    /// Sent to the treasury
+    pub amount: u64,
+    /// Monero network fee, paid by the bridge wallet
+    pub network_fee: u64,
+    pub swept_at: DateTime<Utc>,
+}
+
+/// Fees collected on one kind of operation, or on all of them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeLine {
+    /// Fee kind, or `total`
+    pub kind: String,
+    pub operations: u64,
+    /// XMR before fees, exact to the piconero
+    pub gross_xmr: String,
+    pub fees_xmr: String,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    operations: u64,
+    gross: u64,
+    fees: u64,
+}
+
+impl Totals {
+    fn add(&mut self, charge: &FeeCharge) {
+        self.operations += 1;
+        self.gross = self.gross.saturating_add(charge.amount);
+        self.fees = self.fees.saturating_add(charge.fee);
+    }
+
+    fn line(&self, kind: String) -> FeeLine {
+        FeeLine {
+            kind,
+            operations: self.operations,
+            gross_xmr: format_xmr(self.gross),
+            fees_xmr: format_xmr(self.fees),
+        }
+    }
+}
+
+/// A month's collected fees by kind, and what was swept to the treasury
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeReport {
+    pub month: String,
+    /// One line per kind with any collections, in kind order
+    pub lines: Vec<FeeLine>,
+    pub total: FeeLine,
+    pub swept_xmr: String,
+}
+
+impl FeeReport {
+    pub fn new(month: Month, collected: &[FeeCharge], sweeps: &[FeeSweep]) -> Self {
+        let mut by_kind: BTreeMap<FeeKind, Totals> = BTreeMap::new();
+        let mut total = Totals::default();
+        for charge in collected {
+            by_kind.entry(charge.kind).or_default().add(charge);
+            total.add(charge);
+        }
+        Self {
+            month: month.to_string(),
+            lines: by_kind
+                .iter()
+                .map(|(kind, totals)| totals.line(kind.to_string()))
+                .collect(),
+            total: total.line("total".to_string()),
+            swept_xmr: format_xmr(sweeps.iter().map(|sweep| sweep.amount).sum()),
+        }
+    }
+
+    /// One row per line, then the total, under a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("month,kind,operations,gross_xmr,fees_xmr\n");
+        for line in self.lines.iter().chain(std::iter::once(&self.total)) {
+            csv += &format!(
+                "{},{},{},{},{}\n",
+                self.month, line.kind, line.operations, line.gross_xmr, line.fees_xmr
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XMR: u64 = 1_000_000_000_000;
+
+    #[test]
+    fn test_quote() {
+        let rate = Rate {
+            usd_per_xmr: 200.0,
+            updated_at: Utc::now(),
+        };
+        // $1.50 at $200 is 0.0075 XMR
+        let dollars = FeeSchedule {
+            bps: 0,
+            usd: Some(1.5),
+        };
+        let charge = FeeCharge::quote(
+            FeeKind::Mint,
+            mint_reference(B256::ZERO, 0),
+            XMR,
+            &dollars,
+            Some(&rate),
+        );
+        assert_eq!(charge.fee, 7_500_000_000);
+        assert_eq!(charge.net(), Some(992_500_000_000));
+        assert_eq!(charge.to_string(), "0.007500000000 XMR at $200.00/XMR");
+
+        let dust = FeeCharge::quote(
+            FeeKind::Withdrawal,
+            withdrawal_reference(7),
+            7_500_000_000,
+            &dollars,
+            Some(&rate),
+        );
+        assert_eq!(dust.net(), None);
+        assert_eq!("withdrawal".parse::<FeeKind>().unwrap(), dust.kind);
+    }
+
+    #[test]
+    fn test_schedule() {
+        let rate = Rate {
+            usd_per_xmr: 200.0,
+            updated_at: Utc::now(),
+        };
+        let bps = FeeSchedule { bps: 25, usd: None };
+        // 0.25% of 1 XMR, and of 1 piconero rounded up
+        assert_eq!(bps.fee(XMR, None), 2_500_000_000);
+        assert_eq!(bps.fee(1, None), 1);
+        assert_eq!(bps.to_string(), "25 bps");
+
+        let both = FeeSchedule {
+            bps: 25,
+            usd: Some(1.0),
+        };
+        assert_eq!(both.fee(XMR, Some(&rate)), 2_500_000_000 + 5_000_000_000);
+        assert_eq!(both.to_string(), "25 bps + $1.00");
+
+        assert!(FeeSchedule::default().is_free());
+        assert_eq!(FeeSchedule::default().fee(XMR, Some(&rate)), 0);
+    }
+
+    #[test]
+    fn test_report_sums_by_kind() {
+        let charge = |kind, amount, fee| FeeCharge {
+            kind,
+            reference: String::new(),
+            amount,
+            fee,
+            usd_per_xmr: None,
+            recorded_at: Utc::now(),
+        };
+        let collected = [
+            charge(FeeKind::Withdrawal, 2 * XMR, 5_000_000_000),
+            charge(FeeKind::Mint, XMR, 2_500_000_000),
+            charge(FeeKind::Mint, 3 * XMR, 7_500_000_000),
+        ];
+        let sweeps = [FeeSweep {
+            tx_hash: B256::ZERO,
+            amount: 10_000_000_000,
+            network_fee: 30_000_000,
+            swept_at: Utc::now(),
+        }];
+        let report = FeeReport::new("2025-01".parse().unwrap(), &collected, &sweeps);
+
+        let kinds: Vec<&str> = report.lines.iter().map(|l| l.kind.as_str()).collect();
+        assert_eq!(kinds, ["mint", "withdrawal"]);
+        assert_eq!(report.lines[0].operations, 2);
+        assert_eq!(report.lines[0].gross_xmr, format_xmr(4 * XMR));
+        assert_eq!(report.lines[0].fees_xmr, format_xmr(10_000_000_000));
+        assert_eq!(report.total.operations, 3);
+        assert_eq!(report.total.fees_xmr, format_xmr(15_000_000_000));
+        assert_eq!(report.swept_xmr, format_xmr(10_000_000_000));
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("2025-01,total,3,6.000000000000,"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Posting cadence: every block while deposits are moving, fewer when idle
+//!
+//! Posting every Monero block costs the same gas whether or not anyone is
+//! using the bridge. With `IDLE_POST_INTERVAL_BLOCKS` above 1, a quiet
+//! oracle waits until the confirmed tip reaches a multiple of the interval
+//! and then posts only that block, plus any block on the way that holds a
+//! deposit. While deposits are pending in the txpool (`WATCH_MEMPOOL`), or
+//! mined but not yet posted, it posts every confirmed block as before, so
+//! deposit latency doesn't change. The contract only needs heights to
+//! increase, so skipped blocks leave no gap it objects to.
+//!
+//! `CHECKPOINT_INTERVAL_BLOCKS` goes further: only every Kth block and blocks
+//! holding deposits are posted, whatever is pending, and skipped blocks are
+//! filled in with `fillMoneroBlock` when someone needs to prove a
+//! transaction in one.
+
+/// Which blocks a poll processes and posts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cadence {
+    /// Post every this many blocks when idle; 1 posts every block
+    pub idle_interval: u64,
+    /// Stay idle while deposits are pending too (checkpoint mode)
+    pub checkpoints_only: bool,
+}
+
+impl Default for Cadence {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Cadence {
+    pub fn new(idle_interval: u64) -> Self {
+        Self {
+            idle_interval: idle_interval.max(1),
+            checkpoints_only: false,
+        }
+    }
+
+    /// Post only every `interval`th block and blocks holding deposits
+    pub fn checkpoints(interval: u64) -> Self {
+        Self {
+            checkpoints_only: true,
+            ..Self::new(interval)
+        }
+    }
+
+    /// Whether to post every block, given whether deposits are pending
+    pub fn is_active(&self, deposits_pending: bool) -> bool {
+        self.idle_interval == 1 || (deposits_pending && !self.checkpoints_only)
+    }
+
+    /// Last block to process this poll, given the confirmed tip and whether
+    /// deposits are pending. Blocks past it wait for a later poll.
+    pub fn target(&self, confirmed_height: u64, active: bool) -> u64 {
+        if active {
+            confirmed_height
+        } else {
+            confirmed_height - confirmed_height % self.idle_interval
+        }
+    }
+
+    /// Whether to post `height` in a run ending at `target`. Blocks that
+    /// aren't posted are still scanned.
+    pub fn should_post(&self, height: u64, target: u64, has_deposits: bool, active: bool) -> bool {
+        active || has_deposits || height == target || height.is_multiple_of(self.idle_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_block_by_default() {
+        let cadence = Cadence::default();
+        assert_eq!(cadence.target(3_100_007, false), 3_100_007);
+        assert!(cadence.should_post(3_100_003, 3_100_007, false, false));
+        assert_eq!(Cadence::new(0), cadence);
+    }
+
+    #[test]
+    fn test_idle_posts_checkpoints_and_deposits() {
+        let cadence = Cadence::new(10);
+        assert_eq!(cadence.target(3_100_007, false), 3_100_000);
+        assert_eq!(cadence.target(3_100_010, false), 3_100_010);
+
+        let target = cadence.target(3_100_017, false);
+        assert!(cadence.should_post(target, target, false, false));
+        assert!(!cadence.should_post(3_100_004, target, false, false));
+        assert!(cadence.should_post(3_100_004, target, true, false));
+    }
+
+    #[test]
+    fn test_checkpoints_ignore_pending_deposits() {
+        assert!(Cadence::default().is_active(false));
+        assert!(!Cadence::new(10).is_active(false));
+        assert!(Cadence::new(10).is_active(true));
+        assert!(!Cadence::checkpoints(10).is_active(true));
+    }
+
+    #[test]
+    fn test_active_posts_everything() {
+        let cadence = Cadence::new(10);
+        assert_eq!(cadence.target(3_100_007, true), 3_100_007);
+        assert!(cadence.should_post(3_100_003, 3_100_007, false, true));
+    }
+}
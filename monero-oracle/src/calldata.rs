@@ -0,0 +1,100 @@
+//! ABI-encoded contract calls for sending outside the oracle
+//!
+//! Instead of signing and sending a call itself, the oracle can write it out
+//! as `{to, value, data}` for a multisig (Safe) or a manual broadcast. Each
+//! call is one JSON line, so a range of blocks becomes a file of calls to
+//! send in order. `data` is the exact calldata the oracle would have sent.
+
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// A contract call to send elsewhere
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedCall {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    /// Solidity signature of the function `data` calls
+    pub function: String,
+    /// What the call does, for whoever approves it
+    pub description: String,
+}
+
+impl ExportedCall {
+    pub fn new<C: SolCall>(to: Address, call: &C, description: impl Into<String>) -> Self {
+        Self {
+            to,
+            value: U256::ZERO,
+            data: call.abi_encode().into(),
+            function: C::SIGNATURE.to_string(),
+            description: description.into(),
+        }
+    }
+
+    /// Append the call to `out` as one JSON line
+    pub fn write_line(&self, out: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer(&mut *out, self)?;
+        writeln!(out)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        primitives::{address, B256},
+        sol,
+    };
+
+    sol! {
+        function postMoneroBlock(
+            uint256 blockHeight,
+            bytes32 blockHash,
+            bytes32 txMerkleRoot,
+            bytes32 outputMerkleRoot
+        ) external;
+    }
+
+    #[test]
+    fn test_export_call() {
+        let bridge = address!("1111111111111111111111111111111111111111");
+        let call = postMoneroBlockCall {
+            blockHeight: U256::from(3_100_000u64),
+            blockHash: B256::from([0xaa; 32]),
+            txMerkleRoot: B256::from([0xbb; 32]),
+            outputMerkleRoot: B256::from([0xcc; 32]),
+        };
+        let exported = ExportedCall::new(bridge, &call, "Post Monero block 3100000");
+
+        assert_eq!(
+            exported.function,
+            "postMoneroBlock(uint256,bytes32,bytes32,bytes32)"
+        );
+        assert_eq!(exported.data[..4], postMoneroBlockCall::SELECTOR);
+        assert_eq!(exported.data.len(), 4 + 4 * 32);
+        assert_eq!(
+            postMoneroBlockCall::abi_decode(&exported.data, true)
+                .unwrap()
+                .blockHash,
+            B256::from([0xaa; 32])
+        );
+
+        let mut out = Vec::new();
+        exported.write_line(&mut out).unwrap();
+        exported.write_line(&mut out).unwrap();
+        let lines: Vec<ExportedCall> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, [exported.clone(), exported]);
+    }
+}
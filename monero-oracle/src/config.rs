@@ -0,0 +1,1189 @@
+//! Oracle configuration from environment variables and `config.toml`
+//!
+//! Settings are looked up through [`settings`](crate::settings): the
+//! environment first, then the config file.
+
+use crate::{
+    admin,
+    alerts::{AlertConfig, WebhookFormat},
+    archive::{ArchiveTarget, BackupConfig},
+    attestation::OracleMode,
+    bridge_fee::{FeeSchedule, MAX_BPS},
+    cadence::Cadence,
+    deposit_policy::DepositPolicy,
+    digest_auth::Credentials,
+    fees::{FeePriority, MAX_BATCH_SIZE},
+    fixtures::Fixtures,
+    frost::FrostConfig,
+    gas::{self, GasBudget, GasConfig, GasStrategy},
+    gossip::GossipConfig,
+    health::{BalanceThresholds, HealthConfig},
+    http::HttpSettings,
+    lease::{self, LeaseConfig},
+    merkle::MerkleHasher,
+    monero_rpc::Connection,
+    multisig::MultisigConfig,
+    price::{PriceFeed, PriceSource},
+    rate_limit::RateLimit,
+    retry::RetryPolicy,
+    s3::{S3Config, S3Credentials, S3Location, GCS_ENDPOINT},
+    safe::SafeConfig,
+    scanner::{parse_xmr, ViewKeys},
+    settings::{flag, list, parse, parse_or, require, var},
+    signer::{SignerConfig, SignerKind},
+    subaddress::{MoneroAddress, Network},
+};
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+
+/// Oracle settings, read from the environment and config file
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Oracle key; `None` only from [`Config::from_env_unsigned`]
+    pub signer: Option<SignerConfig>,
+    /// Key to switch to if the oracle role is transferred to it
+    pub standby_signer: Option<SignerConfig>,
+    pub bridge_address: Address,
+    /// Further contracts every block is also posted to, e.g. during a
+    /// migration to a new deployment
+    pub extra_bridge_addresses: Vec<Address>,
+    pub unichain_rpc_url: String,
+    /// Timeouts and pooling for `UNICHAIN_RPC_URL`
+    pub unichain_http: HttpSettings,
+    /// WebSocket endpoint for following contract events as they happen
+    pub unichain_ws_url: Option<String>,
+    pub monero_rpc_url: String,
+    /// Further monerod nodes; each poll follows the heaviest chain among all
+    pub monero_fallback_rpc_urls: Vec<String>,
+    /// Nodes that must agree on a block's hash before it is processed
+    pub monero_quorum: usize,
+    /// monerod's ZMQ publisher; new blocks trigger a poll right away
+    pub monero_zmq_url: Option<String>,
+    pub monero_rpc_login: Option<Credentials>,
+    pub monero_connection: Connection,
+    /// Record monerod's responses, or replay them instead of asking it
+    pub monero_fixtures: Option<Fixtures>,
+    pub rpc_retry: RetryPolicy,
+    /// Request limits applied to each monerod node separately
+    pub monero_rate_limit: RateLimit,
+    pub poll_interval_secs: u64,
+    /// A poll still running after this long is cancelled as stalled
+    pub poll_timeout: Option<Duration>,
+    /// Level of the oracle's own log lines
+    pub log_level: LevelFilter,
+    pub min_confirmations: u64,
+    pub fetch_concurrency: usize,
+    pub output_merkle_hasher: MerkleHasher,
+    /// Tx tree version: `keccak256` (1) or `v2`
+    pub tx_merkle_hasher: MerkleHasher,
+    /// Keep every output of posted blocks in the database
+    pub index_outputs: bool,
+    pub view_keys: Option<ViewKeys>,
+    pub network: Network,
+    pub auto_mint: bool,
+    /// Deposits `AUTO_MINT` holds for review instead of minting
+    pub deposit_policy: DepositPolicy,
+    /// Taken from each mint and withdrawal payout
+    pub bridge_fee: FeeSchedule,
+    /// XMR/USD rate the dollar part of the bridge fee is converted at
+    pub price_feed: Option<PriceFeed>,
+    /// Where `sweep-fees` sends collected bridge fees
+    pub treasury_xmr_address: Option<String>,
+    pub watch_mempool: bool,
+    /// Which blocks to post while no deposits are pending
+    pub cadence: Cadence,
+    /// Further behind than this, posting waits for an operator to approve
+    pub max_catch_up: Option<u64>,
+    pub verify_pow: bool,
+    pub watch_withdrawals: bool,
+    pub withdrawal_start_block: Option<u64>,
+    pub withdrawal_confirmations: u64,
+    pub wallet_rpc_url: Option<String>,
+    /// The bridge wallet is multisig; payouts need co-signers
+    pub multisig: Option<MultisigConfig>,
+    pub withdrawal_max_attempts: u32,
+    /// wallet2 priority payouts are sent and quoted at
+    pub withdrawal_fee_priority: FeePriority,
+    /// Pay withdrawals net of the quoted fee instead of covering it from the
+    /// bridge wallet
+    pub withdrawal_deduct_fee: bool,
+    /// Most withdrawals paid by one Monero transaction
+    pub withdrawal_batch_size: u64,
+    /// Time a partial batch waits after the previous payout for more
+    /// withdrawals to join it
+    pub withdrawal_batch_interval: Duration,
+    pub subaddress_account: u32,
+    pub mode: OracleMode,
+    pub attestation_peers: Vec<String>,
+    /// Threshold key share, with `FROST_KEY_SHARE`
+    pub frost: Option<FrostConfig>,
+    /// Operators' network attestations are gossiped on, with `GOSSIP_LISTEN`
+    pub gossip: Option<GossipConfig>,
+    /// Leader election among redundant instances, with `LEASE_INSTANCE_ID`
+    pub lease: Option<LeaseConfig>,
+    /// Longest the contract goes without a posting or `heartbeat()` from the
+    /// oracle, with `HEARTBEAT_INTERVAL_SECS`
+    pub heartbeat_interval: Option<Duration>,
+    pub reorg_tracked_blocks: usize,
+    pub reorg_confirmation_depth: u64,
+    /// Unichain blocks after which a posting's receipt is re-checked;
+    /// `None` with `UNICHAIN_CONFIRMATION_DEPTH=0`
+    pub unichain_confirmation_depth: Option<u64>,
+    pub database_path: String,
+    /// Apply pending schema migrations at startup instead of refusing to run
+    pub db_auto_migrate: bool,
+    /// Where each processed block is exported, with `BLOCK_EXPORT_TARGET`
+    pub block_export: Option<ArchiveTarget>,
+    /// Exported blocks kept below the latest; all by default
+    pub block_export_retain: Option<u64>,
+    /// Periodic database copies, with `DB_BACKUP_TARGET`
+    pub db_backup: Option<BackupConfig>,
+    pub http_port: Option<u16>,
+    /// Serves the gRPC API, in builds with the `grpc` feature
+    pub grpc_port: Option<u16>,
+    /// Enables the admin endpoints of the HTTP API
+    pub admin_api_key: Option<String>,
+    pub metrics_port: Option<u16>,
+    pub balance: BalanceThresholds,
+    pub health: HealthConfig,
+    pub alerts: AlertConfig,
+    pub gas: GasConfig,
+    /// Safe holding the oracle role; postings are proposed to it
+    pub safe: Option<SafeConfig>,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        Self::load(Some(signer_config_from_env()?))
+    }
+
+    /// Everything but the oracle key, for commands that never sign
+    pub fn from_env_unsigned() -> Result<Self> {
+        Self::load(None)
+    }
+
+    fn load(signer: Option<SignerConfig>) -> Result<Self> {
+        let network = monero_network_from_env()?;
+        let balance = balance_thresholds_from_env()?;
+        let monero_rpc_url = monero_rpc_url_from_env(network);
+        let monero_fallback_rpc_urls = list("MONERO_RPC_FALLBACK_URLS");
+        let monero_quorum = parse_or("MONERO_QUORUM", 1)?;
+        let nodes = monero_fallback_rpc_urls.len() + 1;
+        anyhow::ensure!(
+            (1..=nodes).contains(&monero_quorum),
+            "MONERO_QUORUM must be between 1 and the {} configured Monero node(s)",
+            nodes
+        );
+        let monero_zmq_url = var("MONERO_ZMQ_URL");
+        if let Some(url) = &monero_zmq_url {
+            anyhow::ensure!(
+                url.starts_with("tcp://") || url.starts_with("ipc://"),
+                "MONERO_ZMQ_URL must be a tcp:// or ipc:// endpoint, got {}",
+                url
+            );
+        }
+        let unichain_rpc_url =
+            var("UNICHAIN_RPC_URL").unwrap_or_else(|| "https://mainnet.unichain.org".to_string());
+        anyhow::ensure!(
+            unichain_rpc_url.starts_with("http://") || unichain_rpc_url.starts_with("https://"),
+            "UNICHAIN_RPC_URL must be an http:// or https:// URL (set UNICHAIN_WS_URL for WebSocket), got {}",
+            unichain_rpc_url
+        );
+        let unichain_ws_url = var("UNICHAIN_WS_URL");
+        if let Some(url) = &unichain_ws_url {
+            anyhow::ensure!(
+                url.starts_with("ws://") || url.starts_with("wss://"),
+                "UNICHAIN_WS_URL must be a ws:// or wss:// URL, got {}",
+                url
+            );
+        }
+        let bridge_address = bridge_address_from_env()?;
+        let extra_bridge_addresses = extra_bridge_addresses_from_env(bridge_address)?;
+        let config = Self {
+            signer,
+            standby_signer: standby_signer_from_env(),
+            bridge_address,
+            extra_bridge_addresses,
+            unichain_rpc_url,
+            unichain_http: http_settings_from_env("UNICHAIN_RPC", HttpSettings::UNICHAIN)?,
+            unichain_ws_url,
+            monero_connection: monero_connection_from_env(
+                std::iter::once(&monero_rpc_url)
+                    .chain(&monero_fallback_rpc_urls)
+                    .map(String::as_str),
+            )?,
+            monero_rpc_url,
+            monero_fallback_rpc_urls,
+            monero_quorum,
+            monero_zmq_url,
+            monero_rpc_login: monero_rpc_login_from_env()?,
+            monero_fixtures: monero_fixtures_from_env()?,
+            rpc_retry: rpc_retry_from_env()?,
+            monero_rate_limit: monero_rate_limit_from_env()?,
+            poll_interval_secs: poll_interval_from_env()?,
+            poll_timeout: Some(parse_or("POLL_TIMEOUT_SECS", 600u64)?)
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            log_level: log_level_from_env()?,
+            min_confirmations: parse_or("MIN_CONFIRMATIONS", 10)?,
+            fetch_concurrency: parse_or("FETCH_CONCURRENCY", 4usize)?.max(1),
+            output_merkle_hasher: output_merkle_hasher_from_env()?,
+            tx_merkle_hasher: tx_merkle_hasher_from_env()?,
+            index_outputs: parse_or("INDEX_OUTPUTS", true)?,
+            view_keys: view_keys_from_env()?,
+            network,
+            auto_mint: flag("AUTO_MINT")?,
+            deposit_policy: deposit_policy_from_env()?,
+            bridge_fee: bridge_fee_from_env()?,
+            price_feed: price_feed_from_env()?,
+            treasury_xmr_address: treasury_xmr_address_from_env(network)?,
+            watch_mempool: flag("WATCH_MEMPOOL")?,
+            cadence: cadence_from_env()?,
+            max_catch_up: parse("MAX_CATCH_UP_BLOCKS")?,
+            verify_pow: flag("VERIFY_POW")?,
+            watch_withdrawals: flag("WATCH_WITHDRAWALS")?,
+            withdrawal_start_block: parse("WITHDRAWAL_START_BLOCK")?,
+            withdrawal_confirmations: parse_or("WITHDRAWAL_CONFIRMATIONS", 10)?,
+            wallet_rpc_url: wallet_rpc_url_from_env(),
+            multisig: multisig_from_env()?,
+            withdrawal_max_attempts: parse_or("WITHDRAWAL_MAX_ATTEMPTS", 5)?,
+            withdrawal_fee_priority: parse_or("WITHDRAWAL_FEE_PRIORITY", FeePriority::Normal)?,
+            withdrawal_deduct_fee: parse_or("WITHDRAWAL_DEDUCT_FEE", true)?,
+            withdrawal_batch_size: withdrawal_batch_size_from_env()?,
+            withdrawal_batch_interval: Duration::from_secs(parse_or(
+                "WITHDRAWAL_BATCH_INTERVAL_SECS",
+                0,
+            )?),
+            subaddress_account: subaddress_account_from_env()?,
+            mode: parse_or("ORACLE_MODE", OracleMode::Single)?,
+            attestation_peers: list("ATTESTATION_PEERS")
+                .into_iter()
+                .map(|peer| peer.trim_end_matches('/').to_string())
+                .collect(),
+            frost: frost_from_env()?,
+            gossip: gossip_from_env()?,
+            lease: lease_from_env(poll_interval_from_env()?)?,
+            heartbeat_interval: parse::<u64>("HEARTBEAT_INTERVAL_SECS")?
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            reorg_tracked_blocks: parse_or("REORG_TRACKED_BLOCKS", 64)?,
+            reorg_confirmation_depth: parse_or("REORG_CONFIRMATION_DEPTH", 10)?,
+            unichain_confirmation_depth: Some(parse_or("UNICHAIN_CONFIRMATION_DEPTH", 60u64)?)
+                .filter(|&depth| depth > 0),
+            database_path: database_path_from_env(),
+            db_auto_migrate: db_auto_migrate_from_env()?,
+            block_export: archive_target_from_env("BLOCK_EXPORT_TARGET")?,
+            block_export_retain: parse("BLOCK_EXPORT_RETAIN_BLOCKS")?,
+            db_backup: db_backup_from_env()?,
+            http_port: parse("ORACLE_HTTP_PORT")?,
+            grpc_port: parse("GRPC_PORT")?,
+            admin_api_key: var("ADMIN_API_KEY").filter(|key| !key.is_empty()),
+            metrics_port: parse("METRICS_PORT")?,
+            health: HealthConfig {
+                max_poll_age: Duration::from_secs(parse_or("HEALTH_MAX_POLL_AGE_SECS", 1800)?),
+                max_lag: parse_or("HEALTH_MAX_LAG_BLOCKS", 30)?,
+                min_balance_eth: Some(balance.pause_eth),
+            },
+            balance,
+            alerts: alert_config_from_env()?,
+            gas: gas_config_from_env()?,
+            safe: safe_config_from_env()?,
+        };
+        if config.grpc_port.is_some() {
+            anyhow::ensure!(
+                cfg!(feature = "grpc"),
+                "GRPC_PORT needs a build with the grpc feature"
+            );
+        }
+        if let Some(key) = &config.admin_api_key {
+            anyhow::ensure!(
+                key.len() >= admin::MIN_API_KEY_LEN,
+                "ADMIN_API_KEY must be at least {} characters",
+                admin::MIN_API_KEY_LEN
+            );
+            anyhow::ensure!(
+                config.http_port.is_some(),
+                "ADMIN_API_KEY needs ORACLE_HTTP_PORT"
+            );
+        }
+        if config.safe.is_some() {
+            // Only block postings go through the Safe; these send directly
+            anyhow::ensure!(
+                config.mode == OracleMode::Single,
+                "SAFE_ADDRESS needs ORACLE_MODE=single"
+            );
+            anyhow::ensure!(
+                !config.auto_mint,
+                "AUTO_MINT can't be used with SAFE_ADDRESS"
+            );
+            anyhow::ensure!(
+                config.wallet_rpc_url.is_none(),
+                "Withdrawal payouts (MONERO_WALLET_RPC_URL) can't be used with SAFE_ADDRESS"
+            );
+        }
+        if config.multisig.is_some() {
+            anyhow::ensure!(
+                config.wallet_rpc_url.is_some() && config.http_port.is_some(),
+                "MULTISIG_API_KEY needs MONERO_WALLET_RPC_URL and ORACLE_HTTP_PORT"
+            );
+        }
+        if let Some(frost) = &config.frost {
+            match config.mode {
+                OracleMode::Single => {
+                    anyhow::bail!("FROST_KEY_SHARE needs ORACLE_MODE=signer or submitter")
+                }
+                OracleMode::Signer => {
+                    anyhow::ensure!(
+                        frost.peers.is_empty(),
+                        "FROST_PEERS is for the submitter; signers are its participants"
+                    );
+                    anyhow::ensure!(
+                        config.http_port.is_some(),
+                        "FROST_KEY_SHARE needs ORACLE_HTTP_PORT on a signer"
+                    );
+                }
+                OracleMode::Submitter => anyhow::ensure!(
+                    !frost.peers.is_empty(),
+                    "FROST_KEY_SHARE needs FROST_PEERS on the submitter"
+                ),
+            }
+        }
+        if config.gossip.is_some() {
+            anyhow::ensure!(
+                config.mode != OracleMode::Single && config.frost.is_none(),
+                "GOSSIP_LISTEN needs ORACLE_MODE=signer or submitter without FROST_KEY_SHARE"
+            );
+        }
+        if let Some(lease) = &config.lease {
+            anyhow::ensure!(
+                config.mode != OracleMode::Signer && config.safe.is_none(),
+                "LEASE_INSTANCE_ID needs ORACLE_MODE=single or submitter without SAFE_ADDRESS"
+            );
+            // Renewed at the first poll after half the lease has gone, which
+            // has to land before the margin
+            let poll_interval = Duration::from_secs(config.poll_interval_secs);
+            anyhow::ensure!(
+                lease.duration > 2 * (poll_interval + lease::MARGIN),
+                "LEASE_DURATION_SECS must be over twice POLL_INTERVAL_SECS plus {}s",
+                lease::MARGIN.as_secs()
+            );
+        }
+        if let Some(interval) = config.heartbeat_interval {
+            anyhow::ensure!(
+                config.mode != OracleMode::Signer && config.safe.is_none(),
+                "HEARTBEAT_INTERVAL_SECS needs ORACLE_MODE=single or submitter without SAFE_ADDRESS"
+            );
+            anyhow::ensure!(
+                interval >= Duration::from_secs(config.poll_interval_secs),
+                "HEARTBEAT_INTERVAL_SECS must be at least POLL_INTERVAL_SECS"
+            );
+        }
+        if let Some(retain) = config.block_export_retain {
+            anyhow::ensure!(
+                config.block_export.is_some() && retain > 0,
+                "BLOCK_EXPORT_RETAIN_BLOCKS needs BLOCK_EXPORT_TARGET and must be at least 1"
+            );
+        }
+        if config.bridge_fee.usd.is_some() {
+            anyhow::ensure!(
+                config.price_feed.is_some(),
+                "BRIDGE_FEE_USD needs PRICE_FEED_ADDRESS or PRICE_FEED_URL"
+            );
+        }
+        if config.cadence.checkpoints_only {
+            // Skipped blocks are filled with the oracle-only fillMoneroBlock
+            anyhow::ensure!(
+                config.mode == OracleMode::Single && config.safe.is_none(),
+                "CHECKPOINT_INTERVAL_BLOCKS needs ORACLE_MODE=single without SAFE_ADDRESS"
+            );
+        }
+        if !config.extra_bridge_addresses.is_empty() {
+            anyhow::ensure!(
+                config.mode == OracleMode::Single && config.safe.is_none(),
+                "EXTRA_BRIDGE_ADDRESSES needs ORACLE_MODE=single without SAFE_ADDRESS"
+            );
+        }
+        Ok(config)
+    }
+
+    /// The settings a config file change can update at runtime
+    pub fn tunables(&self) -> Tunables {
+        Tunables {
+            poll_interval_secs: self.poll_interval_secs,
+            log_level: self.log_level,
+            alerts: self.alerts.clone(),
+            gas: self.gas.clone(),
+        }
+    }
+}
+
+/// Settings that are safe to change while the oracle runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tunables {
+    pub poll_interval_secs: u64,
+    pub log_level: LevelFilter,
+    pub alerts: AlertConfig,
+    pub gas: GasConfig,
+}
+
+impl Tunables {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            poll_interval_secs: poll_interval_from_env()?,
+            log_level: log_level_from_env()?,
+            alerts: alert_config_from_env()?,
+            gas: gas_config_from_env()?,
+        })
+    }
+}
+
+/// `POLL_INTERVAL_SECS` (default: 120)
+pub fn poll_interval_from_env() -> Result<u64> {
+    let secs = parse_or("POLL_INTERVAL_SECS", 120)?;
+    anyhow::ensure!(secs > 0, "POLL_INTERVAL_SECS must be at least 1");
+    Ok(secs)
+}
+
+/// `LOG_LEVEL` for the oracle's own logs; `RUST_LOG` still filters the rest
+/// (default: info)
+pub fn log_level_from_env() -> Result<LevelFilter> {
+    parse_or("LOG_LEVEL", LevelFilter::INFO)
+}
+
+/// `IDLE_POST_INTERVAL_BLOCKS`, or checkpoint mode with
+/// `CHECKPOINT_INTERVAL_BLOCKS` (default: post every block)
+fn cadence_from_env() -> Result<Cadence> {
+    let idle_interval: u64 = parse_or("IDLE_POST_INTERVAL_BLOCKS", 1)?;
+    anyhow::ensure!(
+        idle_interval >= 1,
+        "IDLE_POST_INTERVAL_BLOCKS must be at least 1"
+    );
+    let Some(interval) = parse::<u64>("CHECKPOINT_INTERVAL_BLOCKS")? else {
+        return Ok(Cadence::new(idle_interval));
+    };
+    anyhow::ensure!(
+        interval >= 2,
+        "CHECKPOINT_INTERVAL_BLOCKS must be at least 2"
+    );
+    anyhow::ensure!(
+        idle_interval == 1,
+        "Set IDLE_POST_INTERVAL_BLOCKS or CHECKPOINT_INTERVAL_BLOCKS, not both"
+    );
+    Ok(Cadence::checkpoints(interval))
+}
+
+/// Fee strategy, cap and replacement settings for oracle transactions
+pub fn gas_config_from_env() -> Result<GasConfig> {
+    Ok(GasConfig {
+        strategy: parse_or("GAS_STRATEGY", GasStrategy::Normal)?,
+        max_fee_per_gas: parse("MAX_FEE_PER_GAS_GWEI")?.map(gas::gwei_to_wei),
+        priority_fee: parse("PRIORITY_FEE_GWEI")?.map(gas::gwei_to_wei),
+        bump_percent: parse_or("FEE_BUMP_PERCENT", 15)?,
+        stuck_timeout: Duration::from_secs(parse_or("STUCK_TX_TIMEOUT_SECS", 180)?),
+        max_bumps: parse_or("MAX_FEE_BUMPS", 5)?,
+        budget: GasBudget {
+            daily_eth: parse("GAS_BUDGET_DAILY_ETH")?,
+            weekly_eth: parse("GAS_BUDGET_WEEKLY_ETH")?,
+        },
+    })
+}
+
+/// `BRIDGE_ADDRESS`, the WrappedMonero contract
+pub fn bridge_address_from_env() -> Result<Address> {
+    parse("BRIDGE_ADDRESS")?.context("BRIDGE_ADDRESS not set")
+}
+
+/// monero-wallet-rpc holding the bridge wallet, which pays withdrawals out
+pub fn wallet_rpc_url_from_env() -> Option<String> {
+    var("MONERO_WALLET_RPC_URL")
+}
+
+/// `MULTISIG_API_KEY`, and `MULTISIG_PEERS` on the oracle paying
+/// withdrawals; multisig is off without the key
+pub fn multisig_from_env() -> Result<Option<MultisigConfig>> {
+    let peers: Vec<String> = list("MULTISIG_PEERS")
+        .into_iter()
+        .map(|peer| peer.trim_end_matches('/').to_string())
+        .collect();
+    let Some(api_key) = var("MULTISIG_API_KEY").filter(|key| !key.is_empty()) else {
+        anyhow::ensure!(peers.is_empty(), "MULTISIG_PEERS needs MULTISIG_API_KEY");
+        return Ok(None);
+    };
+    anyhow::ensure!(
+        api_key.len() >= admin::MIN_API_KEY_LEN,
+        "MULTISIG_API_KEY must be at least {} characters",
+        admin::MIN_API_KEY_LEN
+    );
+    Ok(Some(MultisigConfig { api_key, peers }))
+}
+
+/// `FROST_KEY_SHARE` with `FROST_API_KEY`, and `FROST_PEERS` on the
+/// submitter; threshold signing is off without a key share
+pub fn frost_from_env() -> Result<Option<FrostConfig>> {
+    let peers: Vec<String> = list("FROST_PEERS")
+        .into_iter()
+        .map(|peer| peer.trim_end_matches('/').to_string())
+        .collect();
+    let Some(key_share) = var("FROST_KEY_SHARE").filter(|path| !path.is_empty()) else {
+        anyhow::ensure!(peers.is_empty(), "FROST_PEERS needs FROST_KEY_SHARE");
+        return Ok(None);
+    };
+    let api_key = require("FROST_API_KEY")?;
+    anyhow::ensure!(
+        api_key.len() >= admin::MIN_API_KEY_LEN,
+        "FROST_API_KEY must be at least {} characters",
+        admin::MIN_API_KEY_LEN
+    );
+    Ok(Some(FrostConfig {
+        key_share: key_share.into(),
+        api_key,
+        peers,
+    }))
+}
+
+/// `GOSSIP_LISTEN`, with the `GOSSIP_PEERS` to join through and the
+/// `GOSSIP_KEY` file; gossip is off without a listen address
+pub fn gossip_from_env() -> Result<Option<GossipConfig>> {
+    let peers = list("GOSSIP_PEERS")
+        .into_iter()
+        .map(|peer| {
+            peer.parse()
+                .with_context(|| format!("GOSSIP_PEERS: invalid multiaddr '{}'", peer))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Some(listen) = var("GOSSIP_LISTEN").filter(|listen| !listen.is_empty()) else {
+        anyhow::ensure!(peers.is_empty(), "GOSSIP_PEERS needs GOSSIP_LISTEN");
+        return Ok(None);
+    };
+    Ok(Some(GossipConfig {
+        listen: listen
+            .parse()
+            .with_context(|| format!("GOSSIP_LISTEN: invalid multiaddr '{}'", listen))?,
+        peers,
+        key: var("GOSSIP_KEY")
+            .unwrap_or_else(|| "gossip.key".to_string())
+            .into(),
+    }))
+}
+
+/// `LEASE_INSTANCE_ID` and `LEASE_DURATION_SECS`, by default three poll
+/// intervals and twice the margin
+pub fn lease_from_env(poll_interval_secs: u64) -> Result<Option<LeaseConfig>> {
+    let Some(instance) = var("LEASE_INSTANCE_ID").filter(|id| !id.is_empty()) else {
+        return Ok(None);
+    };
+    let duration = Duration::from_secs(parse_or(
+        "LEASE_DURATION_SECS",
+        (3 * poll_interval_secs + 2 * lease::MARGIN.as_secs())
+            .clamp(lease::MIN_DURATION.as_secs(), lease::MAX_DURATION.as_secs()),
+    )?);
+    anyhow::ensure!(
+        (lease::MIN_DURATION..=lease::MAX_DURATION).contains(&duration),
+        "LEASE_DURATION_SECS must be from {} to {}",
+        lease::MIN_DURATION.as_secs(),
+        lease::MAX_DURATION.as_secs()
+    );
+    Ok(Some(LeaseConfig { instance, duration }))
+}
+
+/// Archive at `name`: a directory, `s3://bucket/prefix` with `S3_REGION`,
+/// `S3_ENDPOINT` and the AWS access key variables, or `gs://bucket/prefix`
+/// with a Google Cloud Storage HMAC key
+pub fn archive_target_from_env(name: &str) -> Result<Option<ArchiveTarget>> {
+    let Some(target) = var(name).filter(|target| !target.is_empty()) else {
+        return Ok(None);
+    };
+    if !target.starts_with("s3://") && !target.starts_with("gs://") {
+        return Ok(Some(ArchiveTarget::Dir(target.into())));
+    }
+    let location: S3Location = target
+        .parse()
+        .with_context(|| format!("Invalid {}", name))?;
+    let config = if location.scheme == "gs" {
+        S3Config {
+            location,
+            endpoint: GCS_ENDPOINT.to_string(),
+            region: "auto".to_string(),
+            credentials: S3Credentials {
+                access_key_id: require("GCS_HMAC_ACCESS_ID")?,
+                secret_access_key: require("GCS_HMAC_SECRET")?,
+            },
+        }
+    } else {
+        let region = var("S3_REGION").unwrap_or_else(|| "us-east-1".to_string());
+        S3Config {
+            location,
+            endpoint: var("S3_ENDPOINT")
+                .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region)),
+            region,
+            credentials: S3Credentials {
+                access_key_id: require("AWS_ACCESS_KEY_ID")?,
+                secret_access_key: require("AWS_SECRET_ACCESS_KEY")?,
+            },
+        }
+    };
+    Ok(Some(ArchiveTarget::S3(config)))
+}
+
+/// `DB_BACKUP_TARGET`, every `DB_BACKUP_INTERVAL_SECS` (a day by default)
+/// keeping `DB_BACKUP_KEEP` (7)
+pub fn db_backup_from_env() -> Result<Option<BackupConfig>> {
+    let Some(target) = archive_target_from_env("DB_BACKUP_TARGET")? else {
+        return Ok(None);
+    };
+    let interval = Duration::from_secs(parse_or("DB_BACKUP_INTERVAL_SECS", 86_400)?);
+    let keep = parse_or("DB_BACKUP_KEEP", 7)?;
+    anyhow::ensure!(
+        interval >= Duration::from_secs(60),
+        "DB_BACKUP_INTERVAL_SECS must be at least 60"
+    );
+    anyhow::ensure!(keep >= 1, "DB_BACKUP_KEEP must be at least 1");
+    Ok(Some(BackupConfig {
+        target,
+        interval,
+        keep,
+    }))
+}
+
+pub fn db_auto_migrate_from_env() -> Result<bool> {
+    parse_or("DB_AUTO_MIGRATE", true)
+}
+
+/// `DATABASE_PATH` (default: `oracle.db`)
+pub fn database_path_from_env() -> String {
+    var("DATABASE_PATH").unwrap_or_else(|| "oracle.db".to_string())
+}
+
+/// `MONERO_RPC_URL`; defaults to a public node on mainnet and to a local
+/// monerod on the test networks' default RPC port
+pub fn monero_rpc_url_from_env(network: Network) -> String {
+    var("MONERO_RPC_URL").unwrap_or_else(|| match network {
+        Network::Mainnet => "http://xmr.privex.io:18081".to_string(),
+        Network::Testnet | Network::Stagenet => {
+            format!("http://127.0.0.1:{}", network.default_rpc_port())
+        }
+    })
+}
+
+/// `MONERO_RPC_PROXY` and `MONERO_RPC_CA_CERT` for reaching the nodes at
+/// `rpc_urls`. Onion nodes need a proxy that resolves names itself
+/// (`socks5h://`).
+pub fn monero_connection_from_env<'a>(
+    rpc_urls: impl IntoIterator<Item = &'a str>,
+) -> Result<Connection> {
+    let proxy = var("MONERO_RPC_PROXY");
+    for rpc_url in rpc_urls {
+        let onion = reqwest::Url::parse(rpc_url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.ends_with(".onion")))
+            .unwrap_or(false);
+        if onion {
+            match &proxy {
+                None => anyhow::bail!(
+                    "{} is an onion address; set MONERO_RPC_PROXY \
+                     (e.g. socks5h://127.0.0.1:9050)",
+                    rpc_url
+                ),
+                Some(proxy) if !proxy.starts_with("socks5h://") => anyhow::bail!(
+                    "MONERO_RPC_PROXY must be socks5h:// for onion addresses, so Tor resolves them"
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(Connection {
+        proxy,
+        ca_cert: var("MONERO_RPC_CA_CERT").map(Into::into),
+        http: http_settings_from_env("MONERO_RPC", HttpSettings::MONERO)?,
+    })
+}
+
+/// `{prefix}_CONNECT_TIMEOUT_SECS`, `{prefix}_READ_TIMEOUT_SECS`,
+/// `{prefix}_POOL_MAX_IDLE`, `{prefix}_POOL_IDLE_SECS` and
+/// `{prefix}_KEEPALIVE_SECS` over `defaults`; `0` turns a timeout or
+/// keep-alive off
+pub fn http_settings_from_env(prefix: &str, defaults: HttpSettings) -> Result<HttpSettings> {
+    let secs = |name: &str, default: Option<Duration>| -> Result<Option<Duration>> {
+        Ok(match parse(&format!("{}_{}", prefix, name))? {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => default,
+        })
+    };
+    Ok(HttpSettings {
+        connect_timeout: secs("CONNECT_TIMEOUT_SECS", defaults.connect_timeout)?,
+        read_timeout: secs("READ_TIMEOUT_SECS", defaults.read_timeout)?,
+        pool_max_idle: parse(&format!("{}_POOL_MAX_IDLE", prefix))?.or(defaults.pool_max_idle),
+        pool_idle_timeout: secs("POOL_IDLE_SECS", defaults.pool_idle_timeout)?,
+        tcp_keepalive: secs("KEEPALIVE_SECS", defaults.tcp_keepalive)?,
+    })
+}
+
+/// `MONERO_RPC_LOGIN`, the `user:password` monerod was started with
+/// (`--rpc-login`)
+pub fn monero_rpc_login_from_env() -> Result<Option<Credentials>> {
+    parse("MONERO_RPC_LOGIN")
+}
+
+/// `MONERO_RPC_RECORD_DIR` to save every monerod response there, or
+/// `MONERO_RPC_REPLAY_DIR` to answer requests from such a recording
+pub fn monero_fixtures_from_env() -> Result<Option<Fixtures>> {
+    match (var("MONERO_RPC_RECORD_DIR"), var("MONERO_RPC_REPLAY_DIR")) {
+        (Some(_), Some(_)) => {
+            anyhow::bail!("Set MONERO_RPC_RECORD_DIR or MONERO_RPC_REPLAY_DIR, not both")
+        }
+        (Some(dir), None) => Ok(Some(Fixtures::Record(dir.into()))),
+        (None, Some(dir)) => Ok(Some(Fixtures::Replay(dir.into()))),
+        (None, None) => Ok(None),
+    }
+}
+
+/// `ORACLE_SIGNER` and the settings of the chosen backend: `PRIVATE_KEY` for
+/// a local key, `KEYSTORE_PATH` for a keystore (the default when it is set),
+/// `AWS_KMS_*` or `GCP_KMS_*` for a KMS key
+pub fn signer_config_from_env() -> Result<SignerConfig> {
+    let keystore = var("KEYSTORE_PATH");
+    let kind = match parse::<SignerKind>("ORACLE_SIGNER")? {
+        Some(kind) => kind,
+        None if keystore.is_some() => SignerKind::Keystore,
+        None => SignerKind::Local,
+    };
+    Ok(match kind {
+        SignerKind::Local => SignerConfig::Local(
+            var("PRIVATE_KEY")
+                .context("PRIVATE_KEY not set (used for both deployment and oracle)")?,
+        ),
+        SignerKind::Keystore => SignerConfig::Keystore {
+            path: keystore.context("KEYSTORE_PATH not set")?.into(),
+            password_file: var("KEYSTORE_PASSWORD_FILE").map(Into::into),
+        },
+        SignerKind::AwsKms => SignerConfig::AwsKms {
+            key_id: var("AWS_KMS_KEY_ID")
+                .context("AWS_KMS_KEY_ID not set (key ID, ARN or alias)")?,
+            region: var("AWS_KMS_REGION"),
+            profile: var("AWS_KMS_PROFILE"),
+        },
+        SignerKind::GcpKms => SignerConfig::GcpKms {
+            project_id: require("GCP_KMS_PROJECT")?,
+            location: require("GCP_KMS_LOCATION")?,
+            key_ring: require("GCP_KMS_KEY_RING")?,
+            key: require("GCP_KMS_KEY")?,
+            version: parse_or("GCP_KMS_KEY_VERSION", 1)?,
+        },
+    })
+}
+
+/// `STANDBY_KEYSTORE_PATH` or `STANDBY_PRIVATE_KEY`
+pub fn standby_signer_from_env() -> Option<SignerConfig> {
+    if let Some(path) = var("STANDBY_KEYSTORE_PATH") {
+        return Some(SignerConfig::Keystore {
+            path: path.into(),
+            password_file: var("STANDBY_KEYSTORE_PASSWORD_FILE").map(Into::into),
+        });
+    }
+    var("STANDBY_PRIVATE_KEY").map(SignerConfig::Local)
+}
+
+/// `EXTRA_BRIDGE_ADDRESSES`, comma-separated (default: none)
+pub fn extra_bridge_addresses_from_env(bridge_address: Address) -> Result<Vec<Address>> {
+    let mut addresses: Vec<Address> = Vec::new();
+    for item in list("EXTRA_BRIDGE_ADDRESSES") {
+        let address = item
+            .parse()
+            .with_context(|| format!("Invalid address in EXTRA_BRIDGE_ADDRESSES: {}", item))?;
+        anyhow::ensure!(
+            address != bridge_address && !addresses.contains(&address),
+            "{} is listed twice in BRIDGE_ADDRESS and EXTRA_BRIDGE_ADDRESSES",
+            address
+        );
+        addresses.push(address);
+    }
+    Ok(addresses)
+}
+
+/// `SAFE_ADDRESS` and `SAFE_TX_SERVICE_URL` (default: post directly)
+pub fn safe_config_from_env() -> Result<Option<SafeConfig>> {
+    let Some(address) = parse("SAFE_ADDRESS")? else {
+        return Ok(None);
+    };
+    let service_url = require("SAFE_TX_SERVICE_URL")?;
+    Ok(Some(SafeConfig {
+        address,
+        service_url: service_url.trim_end_matches('/').to_string(),
+    }))
+}
+
+/// `RPC_MAX_RETRIES` and `RPC_RETRY_BASE_MS`, the backoff for transient
+/// Monero and Unichain RPC failures
+pub fn rpc_retry_from_env() -> Result<RetryPolicy> {
+    let default = RetryPolicy::default();
+    Ok(RetryPolicy {
+        max_retries: parse_or("RPC_MAX_RETRIES", default.max_retries)?,
+        base_delay: parse("RPC_RETRY_BASE_MS")?
+            .map(Duration::from_millis)
+            .unwrap_or(default.base_delay),
+        ..default
+    })
+}
+
+/// `MONERO_RPC_MAX_RPS`, `MONERO_RPC_MAX_CONCURRENT` and
+/// `MONERO_RPC_DELAY_MS` (default: no limits)
+pub fn monero_rate_limit_from_env() -> Result<RateLimit> {
+    let max_per_sec: Option<f64> = parse("MONERO_RPC_MAX_RPS")?;
+    if let Some(rate) = max_per_sec {
+        anyhow::ensure!(
+            rate.is_finite() && rate > 0.0,
+            "MONERO_RPC_MAX_RPS must be positive"
+        );
+    }
+    let max_concurrent: Option<usize> = parse("MONERO_RPC_MAX_CONCURRENT")?;
+    anyhow::ensure!(
+        max_concurrent != Some(0),
+        "MONERO_RPC_MAX_CONCURRENT must be at least 1"
+    );
+    Ok(RateLimit {
+        max_per_sec,
+        max_concurrent,
+        delay: Duration::from_millis(parse_or("MONERO_RPC_DELAY_MS", 0)?),
+    })
+}
+
+/// `BALANCE_WARN_ETH` and `BALANCE_PAUSE_ETH`
+pub fn balance_thresholds_from_env() -> Result<BalanceThresholds> {
+    let pause_eth: f64 = parse_or("BALANCE_PAUSE_ETH", 0.001)?;
+    let warn_eth: f64 = parse_or("BALANCE_WARN_ETH", 0.01)?;
+    Ok(BalanceThresholds {
+        warn_eth: warn_eth.max(pause_eth),
+        pause_eth,
+    })
+}
+
+/// `ALERT_*` settings; without `ALERT_WEBHOOK_URL` alerts are only logged
+pub fn alert_config_from_env() -> Result<AlertConfig> {
+    let webhook_url = var("ALERT_WEBHOOK_URL");
+    let format = match parse("ALERT_WEBHOOK_FORMAT")? {
+        Some(format) => format,
+        None => webhook_url
+            .as_deref()
+            .map(WebhookFormat::detect)
+            .unwrap_or(WebhookFormat::Generic),
+    };
+    let telegram_chat_id = var("ALERT_TELEGRAM_CHAT_ID");
+    if webhook_url.is_some() && format == WebhookFormat::Telegram && telegram_chat_id.is_none() {
+        anyhow::bail!("ALERT_TELEGRAM_CHAT_ID must be set for Telegram alerts");
+    }
+
+    Ok(AlertConfig {
+        webhook_url,
+        format,
+        telegram_chat_id,
+        cooldown: Duration::from_secs(parse_or("ALERT_COOLDOWN_SECS", 3600)?),
+        poll_failures: parse_or("ALERT_POLL_FAILURES", 3u32)?.max(1),
+    })
+}
+
+/// `OUTPUT_MERKLE_HASHER`; set to `sha256` (version 3) for contracts that
+/// still verify outputs with `verifyMerkleProofSHA256`, or `v2` once the
+/// contract takes version 2 output trees
+pub fn output_merkle_hasher_from_env() -> Result<MerkleHasher> {
+    parse_or("OUTPUT_MERKLE_HASHER", MerkleHasher::default())
+}
+
+/// `TX_MERKLE_HASHER`: `keccak256` for version 1 tx trees, or `v2` once the
+/// contract takes domain-separated ones
+pub fn tx_merkle_hasher_from_env() -> Result<MerkleHasher> {
+    let hasher = parse_or("TX_MERKLE_HASHER", MerkleHasher::default())?;
+    anyhow::ensure!(
+        hasher != MerkleHasher::Sha256,
+        "TX_MERKLE_HASHER must be keccak256 or v2; sha256 is only for output trees"
+    );
+    Ok(hasher)
+}
+
+/// `MIN_DEPOSIT_XMR`, `MAX_DEPOSIT_XMR` and `MAX_DEPOSIT_PER_USER_XMR`
+pub fn deposit_policy_from_env() -> Result<DepositPolicy> {
+    let xmr = |key: &str| {
+        var(key)
+            .map(|value| parse_xmr(&value).with_context(|| format!("Invalid {}", key)))
+            .transpose()
+    };
+    let policy = DepositPolicy {
+        min: xmr("MIN_DEPOSIT_XMR")?,
+        max: xmr("MAX_DEPOSIT_XMR")?,
+        max_per_user: xmr("MAX_DEPOSIT_PER_USER_XMR")?,
+    };
+    if let (Some(min), Some(max)) = (policy.min, policy.max) {
+        anyhow::ensure!(
+            min <= max,
+            "MIN_DEPOSIT_XMR must not be above MAX_DEPOSIT_XMR"
+        );
+    }
+    Ok(policy)
+}
+
+/// `BRIDGE_FEE_BPS` and `BRIDGE_FEE_USD`, the fee taken from each mint and
+/// payout
+pub fn bridge_fee_from_env() -> Result<FeeSchedule> {
+    let bps = parse_or("BRIDGE_FEE_BPS", 0)?;
+    anyhow::ensure!(
+        bps <= MAX_BPS,
+        "BRIDGE_FEE_BPS must be at most {} ({}%)",
+        MAX_BPS,
+        MAX_BPS / 100
+    );
+    let usd: Option<f64> = parse("BRIDGE_FEE_USD")?;
+    if let Some(usd) = usd {
+        anyhow::ensure!(
+            usd.is_finite() && usd >= 0.0,
+            "BRIDGE_FEE_USD must be a non-negative amount"
+        );
+    }
+    Ok(FeeSchedule {
+        bps,
+        usd: usd.filter(|&usd| usd > 0.0),
+    })
+}
+
+/// `TREASURY_XMR_ADDRESS`, checked against `network`
+pub fn treasury_xmr_address_from_env(network: Network) -> Result<Option<String>> {
+    let Some(address) = var("TREASURY_XMR_ADDRESS") else {
+        return Ok(None);
+    };
+    let parsed = MoneroAddress::parse(&address).context("Invalid TREASURY_XMR_ADDRESS")?;
+    anyhow::ensure!(
+        parsed.network == network,
+        "TREASURY_XMR_ADDRESS is for {}, expected {}",
+        parsed.network,
+        network
+    );
+    Ok(Some(address))
+}
+
+/// `PRICE_FEED_ADDRESS` (a Chainlink aggregator) or `PRICE_FEED_URL` and
+/// `PRICE_FEED_JSON_POINTER` (default: CoinGecko's `/monero/usd`), with
+/// `PRICE_MAX_AGE_SECS`
+pub fn price_feed_from_env() -> Result<Option<PriceFeed>> {
+    let source = match (parse("PRICE_FEED_ADDRESS")?, var("PRICE_FEED_URL")) {
+        (None, None) => return Ok(None),
+        (Some(address), None) => PriceSource::Chainlink(address),
+        (None, Some(url)) => PriceSource::Http {
+            url,
+            pointer: var("PRICE_FEED_JSON_POINTER").unwrap_or_else(|| "/monero/usd".to_string()),
+        },
+        (Some(_), Some(_)) => {
+            anyhow::bail!("Set only one of PRICE_FEED_ADDRESS and PRICE_FEED_URL")
+        }
+    };
+    Ok(Some(PriceFeed {
+        source,
+        max_age: Duration::from_secs(parse_or("PRICE_MAX_AGE_SECS", 86400)?),
+    }))
+}
+
+/// `MONERO_NETWORK`, used for address prefixes (default: mainnet)
+pub fn monero_network_from_env() -> Result<Network> {
+    parse_or("MONERO_NETWORK", Network::default())
+}
+
+/// `WITHDRAWAL_BATCH_SIZE`, from 1 (no batching) to the recipients one
+/// transaction can pay
+pub fn withdrawal_batch_size_from_env() -> Result<u64> {
+    let size = parse_or("WITHDRAWAL_BATCH_SIZE", MAX_BATCH_SIZE)?;
+    anyhow::ensure!(
+        (1..=MAX_BATCH_SIZE).contains(&size),
+        "WITHDRAWAL_BATCH_SIZE must be between 1 and {}",
+        MAX_BATCH_SIZE
+    );
+    Ok(size)
+}
+
+/// `SUBADDRESS_ACCOUNT`, the account whose subaddresses are handed to users
+pub fn subaddress_account_from_env() -> Result<u32> {
+    parse_or("SUBADDRESS_ACCOUNT", 0)
+}
+
+/// `BRIDGE_VIEW_KEY` and `BRIDGE_SPEND_PUBLIC_KEY`; deposit scanning is off
+/// unless both are set. Subaddresses `0..SUBADDRESS_LOOKAHEAD` of
+/// `SUBADDRESS_ACCOUNT` are watched alongside the primary address.
+pub fn view_keys_from_env() -> Result<Option<ViewKeys>> {
+    match (var("BRIDGE_VIEW_KEY"), var("BRIDGE_SPEND_PUBLIC_KEY")) {
+        (Some(view_key), Some(spend_key)) => {
+            let keys = ViewKeys::from_hex(&view_key, &spend_key)
+                .context("Invalid BRIDGE_VIEW_KEY or BRIDGE_SPEND_PUBLIC_KEY")?;
+            let lookahead = parse_or("SUBADDRESS_LOOKAHEAD", 1000)?;
+            Ok(Some(keys.with_subaddresses(
+                subaddress_account_from_env()?,
+                lookahead,
+            )))
+        }
+        (None, None) => Ok(None),
+        _ => anyhow::bail!("BRIDGE_VIEW_KEY and BRIDGE_SPEND_PUBLIC_KEY must be set together"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+
+    const REQUIRED: &str = "bridge_address = \"0x00000000000000000000000000000000000000cc\"\n\
+                            private_key = \"0x0123456789012345678901234567890123456789012345678901234567890123\"\n";
+
+    /// The config `settings` (TOML, on top of the required ones) load to
+    fn load(settings: &str) -> Result<Config> {
+        settings::set_for_test(&format!("{}{}", REQUIRED, settings));
+        Config::from_env()
+    }
+
+    fn rejected(settings: &str) -> String {
+        format!("{:#}", load(settings).err().unwrap())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = load("").unwrap();
+        assert_eq!(
+            config.bridge_address,
+            "0x00000000000000000000000000000000000000cc"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(config.min_confirmations, 10);
+        assert_eq!(config.poll_interval_secs, 120);
+        assert_eq!(config.unichain_confirmation_depth, Some(60));
+        assert_eq!(config.database_path, "oracle.db");
+        assert_eq!(config.mode, OracleMode::Single);
+        assert_eq!(config.cadence, Cadence::new(1));
+        assert_eq!(config.gas.stuck_timeout, Duration::from_secs(180));
+        assert_eq!(config.gas.budget, GasBudget::default());
+        assert!(config.withdrawal_deduct_fee);
+        assert!(!config.auto_mint);
+        assert!(config.lease.is_none());
+        assert!(config.admin_api_key.is_none());
+    }
+
+    #[test]
+    fn test_tables_and_overrides() {
+        let config = load(
+            "min_confirmations = 2\n\
+             unichain_confirmation_depth = 0\n\
+             poll_timeout_secs = 0\n\
+             [monero]\n\
+             rpc_url = \"http://127.0.0.1:18081\"\n\
+             rpc_fallback_urls = [\"http://127.0.0.1:18082\"]\n\
+             quorum = 2\n\
+             [gas]\n\
+             budget_daily_eth = 0.5\n",
+        )
+        .unwrap();
+        assert_eq!(config.min_confirmations, 2);
+        assert_eq!(config.unichain_confirmation_depth, None);
+        assert_eq!(config.poll_timeout, None);
+        assert_eq!(config.monero_rpc_url, "http://127.0.0.1:18081");
+        assert_eq!(config.monero_fallback_rpc_urls, ["http://127.0.0.1:18082"]);
+        assert_eq!(config.monero_quorum, 2);
+        assert_eq!(config.gas.budget.daily_eth, Some(0.5));
+    }
+
+    #[test]
+    fn test_requires_bridge_and_key() {
+        settings::set_for_test("private_key = \"0x01\"\n");
+        let e = Config::from_env().err().unwrap();
+        assert!(format!("{:#}", e).contains("BRIDGE_ADDRESS not set"));
+
+        settings::set_for_test("bridge_address = \"0x00000000000000000000000000000000000000cc\"\n");
+        let e = Config::from_env().err().unwrap();
+        assert!(format!("{:#}", e).contains("PRIVATE_KEY not set"));
+        // Commands that never sign don't need it
+        assert!(Config::from_env_unsigned().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_settings() {
+        for (settings, error) in [
+            (
+                "poll_interval_secs = 0\n",
+                "POLL_INTERVAL_SECS must be at least 1",
+            ),
+            (
+                "monero_quorum = 2\n",
+                "MONERO_QUORUM must be between 1 and the 1 configured",
+            ),
+            (
+                "monero_zmq_url = \"http://127.0.0.1:18083\"\n",
+                "MONERO_ZMQ_URL must be a tcp:// or ipc://",
+            ),
+            (
+                "unichain_rpc_url = \"wss://unichain.example\"\n",
+                "UNICHAIN_RPC_URL must be an http:// or https:// URL",
+            ),
+            (
+                "admin_api_key = \"short\"\n",
+                "ADMIN_API_KEY must be at least 16 characters",
+            ),
+            (
+                "admin_api_key = \"0123456789abcdef\"\n",
+                "ADMIN_API_KEY needs ORACLE_HTTP_PORT",
+            ),
+            (
+                "safe_address = \"0x00000000000000000000000000000000000000dd\"\n\
+                 safe_tx_service_url = \"https://safe.example\"\n\
+                 oracle_mode = \"submitter\"\n",
+                "SAFE_ADDRESS needs ORACLE_MODE=single",
+            ),
+            (
+                "lease_instance_id = \"a\"\npoll_interval_secs = 30\nlease_duration_secs = 60\n",
+                "LEASE_DURATION_SECS must be over twice POLL_INTERVAL_SECS plus 15s",
+            ),
+            (
+                "lease_instance_id = \"a\"\nlease_duration_secs = 30\n",
+                "LEASE_DURATION_SECS must be from 60 to 3600",
+            ),
+            (
+                "poll_interval_secs = 60\nheartbeat_interval_secs = 30\n",
+                "HEARTBEAT_INTERVAL_SECS must be at least POLL_INTERVAL_SECS",
+            ),
+            (
+                "checkpoint_interval_blocks = 1\n",
+                "CHECKPOINT_INTERVAL_BLOCKS must be at least 2",
+            ),
+            (
+                "checkpoint_interval_blocks = 10\nidle_post_interval_blocks = 5\n",
+                "Set IDLE_POST_INTERVAL_BLOCKS or CHECKPOINT_INTERVAL_BLOCKS, not both",
+            ),
+            (
+                "checkpoint_interval_blocks = 10\noracle_mode = \"submitter\"\n",
+                "CHECKPOINT_INTERVAL_BLOCKS needs ORACLE_MODE=single",
+            ),
+            (
+                "tx_merkle_hasher = \"sha256\"\n",
+                "TX_MERKLE_HASHER must be keccak256 or v2",
+            ),
+            ("min_confirmations = \"ten\"\n", "Invalid MIN_CONFIRMATIONS"),
+        ] {
+            let e = rejected(settings);
+            assert!(e.contains(error), "{:?}: {}", settings, e);
+        }
+    }
+
+    #[test]
+    fn test_lease_duration_defaults_to_three_polls() {
+        let config = load("lease_instance_id = \"a\"\npoll_interval_secs = 60\n").unwrap();
+        let lease = config.lease.unwrap();
+        assert_eq!(lease.instance, "a");
+        assert_eq!(lease.duration, Duration::from_secs(3 * 60 + 30));
+        // Never under the contract's minimum
+        let config = load("lease_instance_id = \"a\"\npoll_interval_secs = 5\n").unwrap();
+        assert_eq!(config.lease.unwrap().duration, lease::MIN_DURATION);
+    }
+
+    #[test]
+    fn test_checkpoint_cadence() {
+        let config = load("checkpoint_interval_blocks = 10\n").unwrap();
+        assert_eq!(config.cadence, Cadence::checkpoints(10));
+        let config = load("idle_post_interval_blocks = 5\n").unwrap();
+        assert_eq!(config.cadence, Cadence::new(5));
+    }
+}
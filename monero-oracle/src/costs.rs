@@ -0,0 +1,346 @@
+//! What running the bridge costs, for runway planning and fee calibration
+//!
+//! Every Unichain transaction the oracle pays for is recorded with its gas
+//! and effective gas price once mined (reverted ones too: they still cost
+//! gas), and every withdrawal payout with its Monero network fee. The
+//! `report` command sums a calendar month of them by kind as JSON or CSV.
+
+use crate::{gas::format_gwei, journal::OperationKind, scanner::format_xmr};
+use alloy::primitives::{utils::format_ether, B256, U256};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// What a transaction was sent for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CostKind {
+    #[serde(rename = "post")]
+    PostBlock,
+    Mint,
+    ConfirmWithdrawal,
+    Rollback,
+    /// Monero withdrawal payout; costs XMR, not gas
+    Payout,
+    /// `acquireLease` or `releaseLease`, with `LEASE_INSTANCE_ID`
+    Lease,
+    /// `heartbeat`, with `HEARTBEAT_INTERVAL_SECS`
+    Heartbeat,
+}
+
+impl From<OperationKind> for CostKind {
+    fn from(kind: OperationKind) -> Self {
+        match kind {
+            OperationKind::PostBlock => Self::PostBlock,
+            OperationKind::Mint => Self::Mint,
+            OperationKind::ConfirmWithdrawal => Self::ConfirmWithdrawal,
+            OperationKind::Payout => Self::Payout,
+        }
+    }
+}
+
+impl fmt::Display for CostKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PostBlock => "post",
+            Self::Mint => "mint",
+            Self::ConfirmWithdrawal => "confirm-withdrawal",
+            Self::Rollback => "rollback",
+            Self::Payout => "payout",
+            Self::Lease => "lease",
+            Self::Heartbeat => "heartbeat",
+        })
+    }
+}
+
+impl FromStr for CostKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "post" => Ok(Self::PostBlock),
+            "mint" => Ok(Self::Mint),
+            "confirm-withdrawal" => Ok(Self::ConfirmWithdrawal),
+            "rollback" => Ok(Self::Rollback),
+            "payout" => Ok(Self::Payout),
+            "lease" => Ok(Self::Lease),
+            "heartbeat" => Ok(Self::Heartbeat),
+            other => anyhow::bail!("Unknown cost kind '{}'", other),
+        }
+    }
+}
+
+/// The cost of one transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxCost {
+    /// Unichain transaction, or Monero transaction for payouts
+    pub tx_hash: B256,
+    pub kind: CostKind,
+    pub gas_used: u64,
+    /// Effective gas price in wei
+    pub gas_price: u128,
+    /// Monero network fee in piconero
+    pub xmr_fee: u64,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl TxCost {
+    /// Gas paid for a mined Unichain transaction, from its receipt
+    pub fn gas(kind: CostKind, tx_hash: B256, gas_used: u64, gas_price: u128) -> Self {
+        Self {
+            tx_hash,
+            kind,
+            gas_used,
+            gas_price,
+            xmr_fee: 0,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    /// Network fee paid by a relayed withdrawal payout
+    pub fn payout(tx_hash: B256, xmr_fee: u64) -> Self {
+        Self {
+            tx_hash,
+            kind: CostKind::Payout,
+            gas_used: 0,
+            gas_price: 0,
+            xmr_fee,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    /// ETH paid, in wei
+    pub fn cost_wei(&self) -> U256 {
+        U256::from(self.gas_used) * U256::from(self.gas_price)
+    }
+}
+
+/// A calendar month, written `YYYY-MM`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Month {
+    first_day: NaiveDate,
+}
+
+impl Month {
+    /// The month `at` falls in
+    pub fn containing(at: DateTime<Utc>) -> Self {
+        Self {
+            first_day: at
+                .date_naive()
+                .with_day(1)
+                .expect("every month has a first day"),
+        }
+    }
+
+    /// First instant of the month, UTC
+    pub fn start(&self) -> DateTime<Utc> {
+        self.first_day.and_time(Default::default()).and_utc()
+    }
+
+    /// First instant of the next month, UTC
+    pub fn end(&self) -> DateTime<Utc> {
+        let next = match self.first_day.month() {
+            12 => NaiveDate::from_ymd_opt(self.first_day.year() + 1, 1, 1),
+            month => NaiveDate::from_ymd_opt(self.first_day.year(), month + 1, 1),
+        };
+        next.expect("first of a month is a valid date")
+            .and_time(Default::default())
+            .and_utc()
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first_day.format("%Y-%m"))
+    }
+}
+
+impl FromStr for Month {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let first_day = NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d")
+            .with_context(|| format!("Invalid month '{}', expected YYYY-MM", s))?;
+        Ok(Self { first_day })
+    }
+}
+
+/// Costs of one kind of transaction, or of all of them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostLine {
+    /// Cost kind, or `total`
+    pub kind: String,
+    pub transactions: u64,
+    pub gas_used: u64,
+    /// ETH, exact to the wei
+    pub eth_spent: String,
+    /// Gas-weighted, in gwei
+    pub average_gas_price_gwei: String,
+    /// XMR, exact to the piconero
+    pub xmr_fees: String,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    transactions: u64,
+    gas_used: u64,
+    cost_wei: U256,
+    xmr_fees: u64,
+}
+
+impl Totals {
+    fn add(&mut self, cost: &TxCost) {
+        self.transactions += 1;
+        self.gas_used = self.gas_used.saturating_add(cost.gas_used);
+        self.cost_wei += cost.cost_wei();
+        self.xmr_fees = self.xmr_fees.saturating_add(cost.xmr_fee);
+    }
+
+    fn line(&self, kind: String) -> CostLine {
+        let average_gas_price = match self.gas_used {
+            0 => 0,
+            gas_used => (self.cost_wei / U256::from(gas_used))
+                .try_into()
+                .unwrap_or(u128::MAX),
+        };
+        CostLine {
+            kind,
+            transactions: self.transactions,
+            gas_used: self.gas_used,
+            eth_spent: format_ether(self.cost_wei),
+            average_gas_price_gwei: format_gwei(average_gas_price),
+            xmr_fees: format_xmr(self.xmr_fees),
+        }
+    }
+}
+
+/// A month's costs by kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostReport {
+    pub month: String,
+    /// One line per kind with any transactions, in kind order
+    pub lines: Vec<CostLine>,
+    pub total: CostLine,
+}
+
+impl CostReport {
+    pub fn new(month: Month, costs: &[TxCost]) -> Self {
+        let mut by_kind: BTreeMap<CostKind, Totals> = BTreeMap::new();
+        let mut total = Totals::default();
+        for cost in costs {
+            by_kind.entry(cost.kind).or_default().add(cost);
+            total.add(cost);
+        }
+        Self {
+            month: month.to_string(),
+            lines: by_kind
+                .iter()
+                .map(|(kind, totals)| totals.line(kind.to_string()))
+                .collect(),
+            total: total.line("total".to_string()),
+        }
+    }
+
+    /// One row per line, then the total, under a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "month,kind,transactions,gas_used,eth_spent,average_gas_price_gwei,xmr_fees\n",
+        );
+        for line in self.lines.iter().chain(std::iter::once(&self.total)) {
+            csv += &format!(
+                "{},{},{},{},{},{},{}\n",
+                self.month,
+                line.kind,
+                line.transactions,
+                line.gas_used,
+                line.eth_spent,
+                line.average_gas_price_gwei,
+                line.xmr_fees
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(kind: CostKind, gas_used: u64, gas_price: u128, xmr_fee: u64) -> TxCost {
+        TxCost {
+            tx_hash: B256::ZERO,
+            kind,
+            gas_used,
+            gas_price,
+            xmr_fee,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        let month: Month = "2025-01".parse().unwrap();
+        assert_eq!(month.to_string(), "2025-01");
+        assert_eq!(month.start().to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(month.end().to_rfc3339(), "2025-02-01T00:00:00+00:00");
+
+        let december: Month = "2024-12".parse().unwrap();
+        assert_eq!(december.end().to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+        for bad in ["2025-13", "2025", "January", "2025-01-15"] {
+            assert!(bad.parse::<Month>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_report_sums_by_kind() {
+        let gwei = 1_000_000_000;
+        let costs = [
+            cost(CostKind::PostBlock, 100_000, 2 * gwei, 0),
+            cost(CostKind::Mint, 50_000, gwei, 0),
+            cost(CostKind::PostBlock, 100_000, 4 * gwei, 0),
+            cost(CostKind::Payout, 0, 0, 30_000_000),
+        ];
+        let report = CostReport::new("2025-01".parse().unwrap(), &costs);
+
+        let kinds: Vec<&str> = report.lines.iter().map(|l| l.kind.as_str()).collect();
+        assert_eq!(kinds, ["post", "mint", "payout"]);
+        let post = &report.lines[0];
+        assert_eq!(post.transactions, 2);
+        assert_eq!(post.gas_used, 200_000);
+        assert_eq!(post.eth_spent, "0.000600000000000000");
+        assert_eq!(post.average_gas_price_gwei, "3.0000");
+
+        assert_eq!(report.total.transactions, 4);
+        assert_eq!(report.total.gas_used, 250_000);
+        assert_eq!(report.total.eth_spent, "0.000650000000000000");
+        assert_eq!(report.total.xmr_fees, format_xmr(30_000_000));
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.starts_with("month,kind,"));
+        assert!(csv
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("2025-01,total,4,250000,"));
+    }
+
+    #[test]
+    fn test_kinds_round_trip() {
+        for kind in [
+            CostKind::PostBlock,
+            CostKind::Mint,
+            CostKind::ConfirmWithdrawal,
+            CostKind::Rollback,
+            CostKind::Payout,
+            CostKind::Lease,
+            CostKind::Heartbeat,
+        ] {
+            assert_eq!(kind.to_string().parse::<CostKind>().unwrap(), kind);
+        }
+    }
+}
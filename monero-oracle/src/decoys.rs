@@ -0,0 +1,344 @@
+//! Ring member selection for withdrawal transactions
+//!
+//! Every input of a Monero transaction hides the output it spends among
+//! decoys. If decoys are picked differently from what wallets do, the real
+//! output stands out, so this follows wallet2's gamma picker: an output age
+//! in seconds is drawn as `exp(Gamma(19.28, 1/1.61))`, converted to a number
+//! of outputs using the average time between outputs over the last year,
+//! and counted back from the newest spendable output. The block found that
+//! way supplies a uniformly chosen output.
+//!
+//! monero-wallet-rpc picks its own rings; this is for transactions the bridge
+//! builds and signs elsewhere (`decoys` prints a ring as JSON).
+
+use crate::monero_rpc::{MoneroRpcClient, OutputDistribution};
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use std::{collections::BTreeSet, f64::consts::PI};
+
+/// Ring members per input, the real output included
+pub const RING_SIZE: usize = 16;
+
+const GAMMA_SHAPE: f64 = 19.28;
+const GAMMA_SCALE: f64 = 1.0 / 1.61;
+const DIFFICULTY_TARGET: u64 = 120;
+/// Blocks on top of an output before it can be spent
+const SPENDABLE_AGE: usize = 10;
+const DEFAULT_UNLOCK_TIME: f64 = (SPENDABLE_AGE as u64 * DIFFICULTY_TARGET) as f64;
+/// Picks younger than the unlock time land uniformly in this many seconds
+const RECENT_SPEND_WINDOW: u64 = 15 * DIFFICULTY_TARGET;
+const BLOCKS_IN_A_YEAR: usize = (86_400 * 365 / DIFFICULTY_TARGET) as usize;
+/// Draws allowed per decoy before giving up on a distribution
+const MAX_DRAWS_PER_DECOY: usize = 100;
+
+/// wallet2's decoy age distribution over the chain's RingCT outputs
+#[derive(Debug, Clone)]
+pub struct GammaPicker {
+    /// Cumulative output count at the end of each block
+    offsets: Vec<u64>,
+    /// Outputs created before the first block
+    base: u64,
+    /// Outputs at least `SPENDABLE_AGE` blocks old
+    spendable: u64,
+    /// Seconds between outputs over the last year
+    average_output_time: f64,
+}
+
+impl GammaPicker {
+    pub fn new(distribution: &OutputDistribution) -> Result<Self> {
+        let mut total = distribution.base;
+        let offsets: Vec<u64> = distribution
+            .distribution
+            .iter()
+            .map(|&outputs| {
+                total += outputs;
+                total
+            })
+            .collect();
+        anyhow::ensure!(
+            offsets.len() > SPENDABLE_AGE,
+            "Output distribution covers only {} blocks",
+            offsets.len()
+        );
+
+        let blocks = offsets.len().min(BLOCKS_IN_A_YEAR);
+        let before = match offsets.len().checked_sub(blocks + 1) {
+            Some(i) => offsets[i],
+            None => distribution.base,
+        };
+        let recent_outputs = offsets[offsets.len() - 1] - before;
+        anyhow::ensure!(
+            recent_outputs > 0,
+            "No RingCT outputs in the last {} blocks",
+            blocks
+        );
+
+        Ok(Self {
+            spendable: offsets[offsets.len() - SPENDABLE_AGE],
+            average_output_time: (DIFFICULTY_TARGET * blocks as u64) as f64 / recent_outputs as f64,
+            base: distribution.base,
+            offsets,
+        })
+    }
+
+    /// Global index of one candidate decoy. `None` when the draw falls
+    /// outside the spendable outputs or on an empty block; wallet2 draws
+    /// again in both cases.
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<u64> {
+        let mut seconds = sample_gamma(rng, GAMMA_SHAPE, GAMMA_SCALE).exp();
+        if seconds > DEFAULT_UNLOCK_TIME {
+            seconds -= DEFAULT_UNLOCK_TIME;
+        } else {
+            seconds = rng.gen_range(0..RECENT_SPEND_WINDOW) as f64;
+        }
+
+        let age = (seconds / self.average_output_time) as u64;
+        if age >= self.spendable - self.base {
+            return None;
+        }
+        let index = self.spendable - 1 - age;
+
+        let block = self.offsets.partition_point(|&offset| offset <= index);
+        let first = match block {
+            0 => self.base,
+            _ => self.offsets[block - 1],
+        };
+        let outputs = self.offsets[block] - first;
+        (outputs > 0).then(|| first + rng.gen_range(0..outputs))
+    }
+
+    /// `count` distinct decoys, none of them in `excluded`, in ascending order
+    pub fn pick_decoys<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        count: usize,
+        excluded: &BTreeSet<u64>,
+    ) -> Result<Vec<u64>> {
+        let mut decoys = BTreeSet::new();
+        let mut draws = 0;
+        while decoys.len() < count {
+            draws += 1;
+            anyhow::ensure!(
+                draws <= count * MAX_DRAWS_PER_DECOY,
+                "Found only {} of {} decoys; too few spendable outputs",
+                decoys.len(),
+                count
+            );
+            if let Some(index) = self.pick(rng).filter(|index| !excluded.contains(index)) {
+                decoys.insert(index);
+            }
+        }
+        Ok(decoys.into_iter().collect())
+    }
+}
+
+/// One output of a ring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RingMember {
+    pub global_index: u64,
+    pub key: B256,
+    pub commitment: B256,
+    pub height: u64,
+    pub txid: B256,
+}
+
+/// The real output and its decoys, ordered by global index as inputs
+/// reference them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ring {
+    /// Position of the real output in `members`
+    pub real_position: usize,
+    pub members: Vec<RingMember>,
+}
+
+impl Ring {
+    /// `key_offsets` of the input: the first global index, then the gap to
+    /// each following one
+    pub fn key_offsets(&self) -> Vec<u64> {
+        let mut previous = 0;
+        self.members
+            .iter()
+            .map(|member| {
+                let offset = member.global_index - previous;
+                previous = member.global_index;
+                offset
+            })
+            .collect()
+    }
+}
+
+/// A ring of [`RING_SIZE`] unlocked outputs for spending the RingCT output
+/// with global index `real`
+pub async fn build_ring(client: &MoneroRpcClient, real: u64) -> Result<Ring> {
+    let distribution = client.get_output_distribution(0, 0).await?;
+    let picker = GammaPicker::new(&distribution)?;
+    let mut rng = StdRng::from_entropy();
+
+    let mut excluded = BTreeSet::from([real]);
+    let mut members = Vec::with_capacity(RING_SIZE);
+    // Outputs still locked (e.g. by a long unlock time) are replaced
+    while members.len() < RING_SIZE - 1 {
+        let picks = picker.pick_decoys(&mut rng, RING_SIZE - 1 - members.len(), &excluded)?;
+        excluded.extend(&picks);
+        let outputs = client.get_outs_bin(&picks).await?;
+        members.extend(
+            picks
+                .into_iter()
+                .zip(outputs)
+                .filter(|(_, output)| output.unlocked)
+                .map(|(global_index, output)| RingMember {
+                    global_index,
+                    key: output.key,
+                    commitment: output.mask,
+                    height: output.height,
+                    txid: output.txid,
+                }),
+        );
+    }
+
+    let output = client
+        .get_outs_bin(&[real])
+        .await?
+        .pop()
+        .with_context(|| format!("monerod returned no output {}", real))?;
+    members.push(RingMember {
+        global_index: real,
+        key: output.key,
+        commitment: output.mask,
+        height: output.height,
+        txid: output.txid,
+    });
+    members.sort_by_key(|member| member.global_index);
+
+    Ok(Ring {
+        real_position: members
+            .iter()
+            .position(|member| member.global_index == real)
+            .expect("real output is a member"),
+        members,
+    })
+}
+
+/// Marsaglia-Tsang sampling, valid for `shape >= 1`
+fn sample_gamma<R: Rng + ?Sized>(rng: &mut R, shape: f64, scale: f64) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let (x, v) = loop {
+            let x = standard_normal(rng);
+            let v = 1.0 + c * x;
+            if v > 0.0 {
+                break (x, v * v * v);
+            }
+        };
+        let u: f64 = rng.gen();
+        if u < 1.0 - 0.0331 * x.powi(4) || u.ln() < 0.5 * x * x + d * (1.0 - v + v.ln()) {
+            return d * v * scale;
+        }
+    }
+}
+
+/// Box-Muller
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two years of blocks with 40 outputs each
+    fn distribution() -> OutputDistribution {
+        OutputDistribution {
+            amount: 0,
+            start_height: 1_000_000,
+            base: 5_000_000,
+            distribution: vec![40; 2 * BLOCKS_IN_A_YEAR],
+        }
+    }
+
+    #[test]
+    fn test_gamma_mean() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let samples = 20_000;
+        let mean = (0..samples)
+            .map(|_| sample_gamma(&mut rng, GAMMA_SHAPE, GAMMA_SCALE))
+            .sum::<f64>()
+            / samples as f64;
+        assert!((mean - GAMMA_SHAPE * GAMMA_SCALE).abs() < 0.05, "{}", mean);
+    }
+
+    #[test]
+    fn test_picks_favor_recent_spendable_outputs() {
+        let picker = GammaPicker::new(&distribution()).unwrap();
+        assert_eq!(picker.average_output_time, 3.0);
+        let newest = picker.offsets[picker.offsets.len() - 1];
+        assert_eq!(picker.spendable, newest - 40 * (SPENDABLE_AGE as u64 - 1));
+
+        let mut rng = StdRng::seed_from_u64(2);
+        let picks: Vec<u64> = (0..2_000).filter_map(|_| picker.pick(&mut rng)).collect();
+        assert!(picks.len() > 1_900);
+        assert!(picks
+            .iter()
+            .all(|&index| index >= picker.base && index < picker.spendable));
+
+        // Half of real spends are under about two days old; so are the decoys
+        let two_days = 2 * 86_400 / 3;
+        let recent = picks
+            .iter()
+            .filter(|&&index| picker.spendable - index < two_days)
+            .count();
+        assert!(recent > picks.len() / 3, "{} recent", recent);
+    }
+
+    #[test]
+    fn test_pick_decoys() {
+        let picker = GammaPicker::new(&distribution()).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let real = picker.spendable - 100;
+        let decoys = picker
+            .pick_decoys(&mut rng, RING_SIZE - 1, &BTreeSet::from([real]))
+            .unwrap();
+        assert_eq!(decoys.len(), RING_SIZE - 1);
+        assert!(decoys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!decoys.contains(&real));
+
+        // Too few outputs to choose from
+        let tiny = OutputDistribution {
+            distribution: vec![0; 20],
+            ..distribution()
+        };
+        assert!(GammaPicker::new(&tiny).is_err());
+        let sparse = OutputDistribution {
+            base: 0,
+            distribution: [vec![1; 5], vec![0; 15]].concat(),
+            ..distribution()
+        };
+        let picker = GammaPicker::new(&sparse).unwrap();
+        assert!(picker
+            .pick_decoys(&mut rng, RING_SIZE - 1, &BTreeSet::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_key_offsets() {
+        let member = |global_index| RingMember {
+            global_index,
+            key: B256::ZERO,
+            commitment: B256::ZERO,
+            height: 0,
+            txid: B256::ZERO,
+        };
+        let ring = Ring {
+            real_position: 1,
+            members: vec![member(100), member(105), member(230)],
+        };
+        assert_eq!(ring.key_offsets(), [100, 5, 125]);
+    }
+}
@@ -0,0 +1,196 @@
+//! Limits on the deposits minted automatically
+//!
+//! `MIN_DEPOSIT_XMR` keeps dust from being minted for more gas than it is
+//! worth, `MAX_DEPOSIT_XMR` caps a single output and
+//! `MAX_DEPOSIT_PER_USER_XMR` what one recipient is minted over
+//! [`USER_WINDOW`]. A deposit outside them isn't minted: it is held for
+//! review, and an operator mints or rejects it through the admin API.
+
+use crate::scanner::{format_xmr, Deposit};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Period `MAX_DEPOSIT_PER_USER_XMR` applies to
+pub const USER_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Deposit limits in piconero; `None` means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepositPolicy {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    /// Most minted to one recipient over [`USER_WINDOW`]
+    pub max_per_user: Option<u64>,
+}
+
+/// Why a deposit was held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    BelowMinimum {
+        amount: u64,
+        minimum: u64,
+    },
+    AboveMaximum {
+        amount: u64,
+        maximum: u64,
+    },
+    UserLimit {
+        amount: u64,
+        minted: u64,
+        limit: u64,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BelowMinimum { amount, minimum } => write!(
+                f,
+                "{} XMR is below MIN_DEPOSIT_XMR ({})",
+                format_xmr(*amount),
+                format_xmr(*minimum)
+            ),
+            Self::AboveMaximum { amount, maximum } => write!(
+                f,
+                "{} XMR is above MAX_DEPOSIT_XMR ({})",
+                format_xmr(*amount),
+                format_xmr(*maximum)
+            ),
+            Self::UserLimit {
+                amount,
+                minted,
+                limit,
+            } => write!(
+                f,
+                "{} XMR on top of {} minted to the recipient in 24 hours exceeds MAX_DEPOSIT_PER_USER_XMR ({})",
+                format_xmr(*amount),
+                format_xmr(*minted),
+                format_xmr(*limit)
+            ),
+        }
+    }
+}
+
+impl DepositPolicy {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check a deposit of `amount` to a recipient already minted `minted`
+    /// over [`USER_WINDOW`]
+    pub fn check(&self, amount: u64, minted: u64) -> Option<Violation> {
+        if let Some(minimum) = self.min.filter(|&minimum| amount < minimum) {
+            return Some(Violation::BelowMinimum { amount, minimum });
+        }
+        if let Some(maximum) = self.max.filter(|&maximum| amount > maximum) {
+            return Some(Violation::AboveMaximum { amount, maximum });
+        }
+        match self.max_per_user {
+            Some(limit) if minted.saturating_add(amount) > limit => Some(Violation::UserLimit {
+                amount,
+                minted,
+                limit,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DepositPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let xmr = |amount: Option<u64>| match amount {
+            Some(amount) => format!("{} XMR", format_xmr(amount)),
+            None => "none".to_string(),
+        };
+        write!(
+            f,
+            "min {}, max {}, per user per day {}",
+            xmr(self.min),
+            xmr(self.max),
+            xmr(self.max_per_user)
+        )
+    }
+}
+
+/// Where a held deposit is in review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    /// Waiting for an operator
+    Held,
+    /// Minted at the next poll, whatever the limits say
+    Approved,
+    /// Never minted
+    Rejected,
+}
+
+impl fmt::Display for ReviewStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Held => "held",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        })
+    }
+}
+
+impl FromStr for ReviewStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "held" => Ok(Self::Held),
+            "approved" => Ok(Self::Approved),
+            "rejected" => Ok(Self::Rejected),
+            other => anyhow::bail!("Unknown review status '{}'", other),
+        }
+    }
+}
+
+/// A deposit held for review, as listed by `GET /admin/deposits/held`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldDeposit {
+    #[serde(flatten)]
+    pub deposit: Deposit,
+    pub reason: String,
+    pub held_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XMR: u64 = 1_000_000_000_000;
+
+    #[test]
+    fn test_check_limits() {
+        let policy = DepositPolicy {
+            min: Some(XMR / 100),
+            max: Some(50 * XMR),
+            max_per_user: Some(100 * XMR),
+        };
+        assert_eq!(policy.check(XMR, 0), None);
+        assert_eq!(
+            policy.check(XMR / 1000, 0),
+            Some(Violation::BelowMinimum {
+                amount: XMR / 1000,
+                minimum: XMR / 100
+            })
+        );
+        assert!(matches!(
+            policy.check(51 * XMR, 0),
+            Some(Violation::AboveMaximum { .. })
+        ));
+        assert_eq!(policy.check(40 * XMR, 60 * XMR), None);
+        assert_eq!(
+            policy.check(40 * XMR, 61 * XMR).unwrap().to_string(),
+            "40.000000000000 XMR on top of 61.000000000000 minted to the recipient in 24 hours \
+             exceeds MAX_DEPOSIT_PER_USER_XMR (100.000000000000)"
+        );
+
+        assert!(DepositPolicy::default().is_unlimited());
+        assert_eq!(DepositPolicy::default().check(u64::MAX, u64::MAX), None);
+    }
+}
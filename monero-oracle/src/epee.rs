@@ -0,0 +1,379 @@
+//! monerod's binary "portable storage" format (epee)
+//!
+//! The `.bin` RPC endpoints take and return this instead of JSON. A document
+//! is a header followed by a section: named, typed values, where sections
+//! nest and any type can also appear as a homogeneous array. Binary data
+//! (hashes, block and transaction blobs) travels as strings of raw bytes
+//! rather than hex, which is most of the saving over JSON.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+/// Signature A, signature B and format version
+const HEADER: [u8; 9] = [0x01, 0x11, 0x01, 0x01, 0x01, 0x01, 0x02, 0x01, 0x01];
+
+/// Deepest nesting accepted from the daemon
+const MAX_DEPTH: usize = 64;
+
+const TYPE_INT64: u8 = 1;
+const TYPE_INT32: u8 = 2;
+const TYPE_INT16: u8 = 3;
+const TYPE_INT8: u8 = 4;
+const TYPE_UINT64: u8 = 5;
+const TYPE_UINT32: u8 = 6;
+const TYPE_UINT16: u8 = 7;
+const TYPE_UINT8: u8 = 8;
+const TYPE_DOUBLE: u8 = 9;
+const TYPE_STRING: u8 = 10;
+const TYPE_BOOL: u8 = 11;
+const TYPE_OBJECT: u8 = 12;
+const ARRAY_FLAG: u8 = 0x80;
+
+/// Named values of one object
+pub type Section = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    I64(i64),
+    I32(i32),
+    I16(i16),
+    I8(i8),
+    U64(u64),
+    U32(u32),
+    U16(u16),
+    U8(u8),
+    F64(f64),
+    /// Text or raw bytes; epee doesn't distinguish them
+    String(Vec<u8>),
+    Bool(bool),
+    Object(Section),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Any integer that fits in a `u64`
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Self::U64(v) => Some(v),
+            Self::U32(v) => Some(v.into()),
+            Self::U16(v) => Some(v.into()),
+            Self::U8(v) => Some(v.into()),
+            Self::I64(v) => v.try_into().ok(),
+            Self::I32(v) => v.try_into().ok(),
+            Self::I16(v) => v.try_into().ok(),
+            Self::I8(v) => v.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Section> {
+        match self {
+            Self::Object(section) => Some(section),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn type_code(&self) -> u8 {
+        match self {
+            Self::I64(_) => TYPE_INT64,
+            Self::I32(_) => TYPE_INT32,
+            Self::I16(_) => TYPE_INT16,
+            Self::I8(_) => TYPE_INT8,
+            Self::U64(_) => TYPE_UINT64,
+            Self::U32(_) => TYPE_UINT32,
+            Self::U16(_) => TYPE_UINT16,
+            Self::U8(_) => TYPE_UINT8,
+            Self::F64(_) => TYPE_DOUBLE,
+            Self::String(_) => TYPE_STRING,
+            Self::Bool(_) => TYPE_BOOL,
+            Self::Object(_) => TYPE_OBJECT,
+            // Only the element type is written for arrays
+            Self::Array(values) => values.first().map_or(TYPE_STRING, Value::type_code),
+        }
+    }
+}
+
+/// Field accessors for response sections
+pub trait SectionExt {
+    fn field(&self, name: &str) -> Result<&Value>;
+    fn u64(&self, name: &str) -> Result<u64>;
+    fn bytes(&self, name: &str) -> Result<&[u8]>;
+    /// Elements of an array field; monerod leaves out empty arrays, so a
+    /// missing field is empty
+    fn array(&self, name: &str) -> Result<&[Value]>;
+}
+
+impl SectionExt for Section {
+    fn field(&self, name: &str) -> Result<&Value> {
+        self.get(name)
+            .with_context(|| format!("Missing field {}", name))
+    }
+
+    fn u64(&self, name: &str) -> Result<u64> {
+        self.field(name)?
+            .as_u64()
+            .with_context(|| format!("Field {} is not an integer", name))
+    }
+
+    fn bytes(&self, name: &str) -> Result<&[u8]> {
+        self.field(name)?
+            .as_bytes()
+            .with_context(|| format!("Field {} is not a string", name))
+    }
+
+    fn array(&self, name: &str) -> Result<&[Value]> {
+        match self.get(name) {
+            None => Ok(&[]),
+            Some(value) => value
+                .as_array()
+                .with_context(|| format!("Field {} is not an array", name)),
+        }
+    }
+}
+
+/// Serialize a request document
+pub fn to_bytes(section: &Section) -> Vec<u8> {
+    let mut out = HEADER.to_vec();
+    write_section(&mut out, section);
+    out
+}
+
+/// Parse a response document
+pub fn from_bytes(bytes: &[u8]) -> Result<Section> {
+    let body = bytes
+        .strip_prefix(&HEADER[..])
+        .context("Not an epee portable storage document")?;
+    let mut reader = Reader { bytes: body };
+    let section = reader.section(0)?;
+    anyhow::ensure!(
+        reader.bytes.is_empty(),
+        "{} trailing bytes after document",
+        reader.bytes.len()
+    );
+    Ok(section)
+}
+
+/// epee varint: the low two bits give the width (1, 2, 4 or 8 bytes)
+fn write_varint(out: &mut Vec<u8>, value: u64) {
+    if value < 1 << 6 {
+        out.push((value << 2) as u8);
+    } else if value < 1 << 14 {
+        out.extend_from_slice(&(((value << 2) | 1) as u16).to_le_bytes());
+    } else if value < 1 << 30 {
+        out.extend_from_slice(&(((value << 2) | 2) as u32).to_le_bytes());
+    } else {
+        out.extend_from_slice(&((value << 2) | 3).to_le_bytes());
+    }
+}
+
+fn write_section(out: &mut Vec<u8>, section: &Section) {
+    write_varint(out, section.len() as u64);
+    for (name, value) in section {
+        out.push(name.len() as u8);
+        out.extend_from_slice(name.as_bytes());
+        match value {
+            Value::Array(values) => {
+                out.push(value.type_code() | ARRAY_FLAG);
+                write_varint(out, values.len() as u64);
+                for value in values {
+                    write_value(out, value);
+                }
+            }
+            _ => {
+                out.push(value.type_code());
+                write_value(out, value);
+            }
+        }
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::I64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::I32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::I16(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::I8(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::U64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::U32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::U16(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::U8(v) => out.push(*v),
+        Value::F64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::String(bytes) => {
+            write_varint(out, bytes.len() as u64);
+            out.extend_from_slice(bytes);
+        }
+        Value::Bool(v) => out.push(u8::from(*v)),
+        Value::Object(section) => write_section(out, section),
+        // Nested arrays aren't produced by monerod and can't be written
+        // without their own type marker
+        Value::Array(_) => unreachable!("nested epee arrays are not supported"),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        anyhow::ensure!(len <= self.bytes.len(), "Truncated epee document");
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("length checked"))
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let first = *self.bytes.first().context("Truncated epee document")?;
+        let value = match first & 3 {
+            0 => u64::from(self.array::<1>()?[0]),
+            1 => u64::from(u16::from_le_bytes(self.array()?)),
+            2 => u64::from(u32::from_le_bytes(self.array()?)),
+            _ => u64::from_le_bytes(self.array()?),
+        };
+        Ok(value >> 2)
+    }
+
+    /// A count or length, bounded by the bytes left so a corrupt document
+    /// can't make us allocate gigabytes
+    fn len(&mut self) -> Result<usize> {
+        let len = self.varint()?;
+        anyhow::ensure!(
+            len <= self.bytes.len() as u64,
+            "epee length {} exceeds document",
+            len
+        );
+        Ok(len as usize)
+    }
+
+    fn section(&mut self, depth: usize) -> Result<Section> {
+        anyhow::ensure!(depth <= MAX_DEPTH, "epee document nested too deeply");
+        let count = self.len()?;
+        let mut section = Section::new();
+        for _ in 0..count {
+            let name_len = self.array::<1>()?[0] as usize;
+            let name = String::from_utf8(self.take(name_len)?.to_vec())
+                .context("Invalid epee field name")?;
+            let type_code = self.array::<1>()?[0];
+            let value = if type_code & ARRAY_FLAG != 0 {
+                let len = self.len()?;
+                let values = (0..len)
+                    .map(|_| self.value(type_code & !ARRAY_FLAG, depth))
+                    .collect::<Result<_>>()?;
+                Value::Array(values)
+            } else {
+                self.value(type_code, depth)?
+            };
+            section.insert(name, value);
+        }
+        Ok(section)
+    }
+
+    fn value(&mut self, type_code: u8, depth: usize) -> Result<Value> {
+        Ok(match type_code {
+            TYPE_INT64 => Value::I64(i64::from_le_bytes(self.array()?)),
+            TYPE_INT32 => Value::I32(i32::from_le_bytes(self.array()?)),
+            TYPE_INT16 => Value::I16(i16::from_le_bytes(self.array()?)),
+            TYPE_INT8 => Value::I8(i8::from_le_bytes(self.array()?)),
+            TYPE_UINT64 => Value::U64(u64::from_le_bytes(self.array()?)),
+            TYPE_UINT32 => Value::U32(u32::from_le_bytes(self.array()?)),
+            TYPE_UINT16 => Value::U16(u16::from_le_bytes(self.array()?)),
+            TYPE_UINT8 => Value::U8(self.array::<1>()?[0]),
+            TYPE_DOUBLE => Value::F64(f64::from_le_bytes(self.array()?)),
+            TYPE_STRING => {
+                let len = self.len()?;
+                Value::String(self.take(len)?.to_vec())
+            }
+            TYPE_BOOL => Value::Bool(self.array::<1>()?[0] != 0),
+            TYPE_OBJECT => Value::Object(self.section(depth + 1)?),
+            other => anyhow::bail!("Unsupported epee type {}", other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_widths() {
+        for (value, width) in [
+            (0, 1),
+            (63, 1),
+            (64, 2),
+            (16_383, 2),
+            (16_384, 4),
+            (1 << 30, 8),
+        ] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            assert_eq!(out.len(), width, "{}", value);
+            assert_eq!(Reader { bytes: &out }.varint().unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut inner = Section::new();
+        inner.insert("key".to_string(), Value::String(vec![0xab; 32]));
+        inner.insert("unlocked".to_string(), Value::Bool(true));
+
+        let mut section = Section::new();
+        section.insert(
+            "heights".to_string(),
+            Value::Array(vec![Value::U64(3_100_000), Value::U64(3_100_001)]),
+        );
+        section.insert("outs".to_string(), Value::Array(vec![Value::Object(inner)]));
+        section.insert("status".to_string(), Value::String(b"OK".to_vec()));
+        section.insert("count".to_string(), Value::U32(7));
+
+        let bytes = to_bytes(&section);
+        assert_eq!(&bytes[..9], &HEADER);
+        let parsed = from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, section);
+
+        assert_eq!(parsed.u64("count").unwrap(), 7);
+        assert_eq!(parsed.bytes("status").unwrap(), b"OK");
+        assert_eq!(parsed.array("heights").unwrap().len(), 2);
+        assert!(parsed.array("missing").unwrap().is_empty());
+        assert!(parsed.u64("status").is_err());
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        assert!(from_bytes(b"{\"status\":\"OK\"}").is_err());
+
+        // A string claiming more bytes than the document holds
+        let mut bytes = HEADER.to_vec();
+        write_varint(&mut bytes, 1);
+        bytes.push(1);
+        bytes.push(b'x');
+        bytes.push(TYPE_STRING);
+        write_varint(&mut bytes, 1_000_000);
+        assert!(from_bytes(&bytes).is_err());
+    }
+}
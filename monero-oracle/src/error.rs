@@ -0,0 +1,260 @@
+//! Error classes the oracle acts on
+//!
+//! Errors still travel as `anyhow::Error`, gathering context on the way up,
+//! but failures the retry layer, alerts and metrics need to tell apart are
+//! raised as an [`OracleError`] where they happen. Errors from libraries
+//! that only one class produces - alloy transports for Unichain, rusqlite for
+//! the database, JSON and hex decoding - are classified by type, so
+//! [`classify`] never looks at message text.
+
+use crate::retry::ErrorClass;
+use alloy::{contract, transports::TransportError};
+use std::fmt;
+
+/// A failure of a known class
+#[derive(Debug, thiserror::Error)]
+pub enum OracleError {
+    /// monerod or monero-wallet-rpc unreachable, answering with an HTTP
+    /// error, or refusing the call
+    #[error("Monero RPC {method} failed: {message}")]
+    MoneroRpc {
+        method: &'static str,
+        message: String,
+        retry: ErrorClass,
+    },
+    /// A Unichain RPC call failed
+    #[error(transparent)]
+    EvmRpc(#[from] TransportError),
+    /// Data that doesn't decode: blobs, epee, hex, RPC responses
+    #[error("{0}")]
+    Parse(String),
+    /// Chain data that contradicts itself or the contract: hashes that don't
+    /// match their blobs, blocks that don't link, failed proof of work
+    #[error("{0}")]
+    Consensus(String),
+    #[error(transparent)]
+    Storage(#[from] rusqlite::Error),
+    /// Missing or invalid settings
+    #[error("{0}")]
+    Config(String),
+}
+
+/// Class of an error, used as a metric label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    MoneroRpc,
+    EvmRpc,
+    Parse,
+    Consensus,
+    Storage,
+    Config,
+    /// Anything not classified
+    Other,
+}
+
+impl ErrorKind {
+    /// Failures that won't clear up by themselves however often the poll
+    /// is retried
+    pub fn needs_operator(self) -> bool {
+        matches!(self, Self::Consensus | Self::Storage | Self::Config)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MoneroRpc => "monero_rpc",
+            Self::EvmRpc => "evm_rpc",
+            Self::Parse => "parse",
+            Self::Consensus => "consensus",
+            Self::Storage => "storage",
+            Self::Config => "config",
+            Self::Other => "other",
+        })
+    }
+}
+
+impl OracleError {
+    /// A failed request to monerod or the wallet; responses that don't
+    /// decode are [`Parse`](Self::Parse) errors
+    pub fn monero(method: &'static str, error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            return Self::Parse(format!("Invalid {} response: {}", method, error));
+        }
+        Self::MoneroRpc {
+            method,
+            message: error.to_string(),
+            retry: reqwest_class(&error),
+        }
+    }
+
+    /// An error response from monerod or the wallet; `BUSY` (still syncing,
+    /// or serving another long request) is worth retrying
+    pub fn monero_status(method: &'static str, status: &str) -> Self {
+        Self::MoneroRpc {
+            method,
+            message: status.to_string(),
+            retry: if status == "BUSY" {
+                ErrorClass::Transient
+            } else {
+                ErrorClass::Permanent
+            },
+        }
+    }
+
+    /// Data that failed to decode, with its context
+    pub fn parse(error: anyhow::Error) -> Self {
+        Self::Parse(format!("{:#}", error))
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::MoneroRpc { .. } => ErrorKind::MoneroRpc,
+            Self::EvmRpc(_) => ErrorKind::EvmRpc,
+            Self::Parse(_) => ErrorKind::Parse,
+            Self::Consensus(_) => ErrorKind::Consensus,
+            Self::Storage(_) => ErrorKind::Storage,
+            Self::Config(_) => ErrorKind::Config,
+        }
+    }
+
+    /// How the retry layer treats it
+    pub fn retry_class(&self) -> ErrorClass {
+        match self {
+            Self::MoneroRpc { retry, .. } => *retry,
+            Self::EvmRpc(error) => transport_class(error),
+            Self::Storage(error) => storage_class(error),
+            Self::Parse(_) | Self::Consensus(_) | Self::Config(_) => ErrorClass::Permanent,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.retry_class() != ErrorClass::Permanent
+    }
+}
+
+/// Class and retry treatment of `error`, from the first [`OracleError`] or
+/// classifiable library error in its chain
+pub fn classify(error: &anyhow::Error) -> (ErrorKind, ErrorClass) {
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<OracleError>() {
+            return (error.kind(), error.retry_class());
+        }
+        if let Some(error) = cause.downcast_ref::<TransportError>() {
+            return (ErrorKind::EvmRpc, transport_class(error));
+        }
+        if let Some(contract::Error::TransportError(error)) = cause.downcast_ref() {
+            return (ErrorKind::EvmRpc, transport_class(error));
+        }
+        if let Some(error) = cause.downcast_ref::<rusqlite::Error>() {
+            return (ErrorKind::Storage, storage_class(error));
+        }
+        if cause.is::<serde_json::Error>() || cause.is::<hex::FromHexError>() {
+            return (ErrorKind::Parse, ErrorClass::Permanent);
+        }
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            return (ErrorKind::Other, reqwest_class(error));
+        }
+    }
+    (ErrorKind::Other, ErrorClass::Permanent)
+}
+
+fn reqwest_class(error: &reqwest::Error) -> ErrorClass {
+    if let Some(status) = error.status() {
+        return status_class(status.as_u16());
+    }
+    if error.is_timeout() || error.is_connect() || error.is_request() || error.is_body() {
+        ErrorClass::Transient
+    } else {
+        ErrorClass::Permanent
+    }
+}
+
+/// Unichain failures: HTTP errors by status, error responses carrying a
+/// rate-limit code, and dropped connections
+fn transport_class(error: &TransportError) -> ErrorClass {
+    use alloy::transports::{RpcError, TransportErrorKind};
+
+    match error {
+        // 429 from Alchemy and others, -32005 "limit exceeded" from Infura
+        RpcError::ErrorResp(payload) if matches!(payload.code, 429 | -32005) => {
+            ErrorClass::RateLimited
+        }
+        RpcError::Transport(TransportErrorKind::HttpError(error)) => status_class(error.status),
+        RpcError::Transport(TransportErrorKind::Custom(error)) => error
+            .downcast_ref::<reqwest::Error>()
+            .map_or(ErrorClass::Transient, reqwest_class),
+        RpcError::Transport(_) | RpcError::NullResp => ErrorClass::Transient,
+        _ => ErrorClass::Permanent,
+    }
+}
+
+/// Another connection holding the database lock
+fn storage_class(error: &rusqlite::Error) -> ErrorClass {
+    match error.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+            ErrorClass::Transient
+        }
+        _ => ErrorClass::Permanent,
+    }
+}
+
+pub(crate) fn status_class(status: u16) -> ErrorClass {
+    match status {
+        429 => ErrorClass::RateLimited,
+        500..=599 => ErrorClass::Transient,
+        _ => ErrorClass::Permanent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::transports::TransportErrorKind;
+
+    #[test]
+    fn test_classify_by_type() {
+        let rate_limited: anyhow::Error =
+            TransportErrorKind::http_error(429, "slow down".to_string()).into();
+        assert_eq!(
+            classify(&rate_limited),
+            (ErrorKind::EvmRpc, ErrorClass::RateLimited)
+        );
+        let unavailable: anyhow::Error = TransportErrorKind::http_error(503, String::new()).into();
+        assert_eq!(classify(&unavailable).1, ErrorClass::Transient);
+
+        let busy = anyhow::Error::from(OracleError::monero_status("get_blocks.bin", "BUSY"))
+            .context("Failed to fetch block 3100000");
+        assert_eq!(
+            classify(&busy),
+            (ErrorKind::MoneroRpc, ErrorClass::Transient)
+        );
+
+        let locked = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert_eq!(
+            classify(&locked.into()),
+            (ErrorKind::Storage, ErrorClass::Transient)
+        );
+
+        let bad_hex = anyhow::Error::from(hex::decode("zz").unwrap_err());
+        assert_eq!(classify(&bad_hex).0, ErrorKind::Parse);
+
+        // Message text doesn't matter
+        assert_eq!(
+            classify(&anyhow::anyhow!("HTTP error 503: connection timed out")),
+            (ErrorKind::Other, ErrorClass::Permanent)
+        );
+    }
+
+    #[test]
+    fn test_kinds_needing_an_operator() {
+        let consensus = OracleError::Consensus("Blob hashes to 0x01".to_string());
+        assert!(consensus.kind().needs_operator());
+        assert!(!consensus.is_retryable());
+        assert!(!ErrorKind::MoneroRpc.needs_operator());
+        assert_eq!(ErrorKind::EvmRpc.to_string(), "evm_rpc");
+    }
+}
@@ -0,0 +1,736 @@
+//! Unichain side: the WrappedMonero contract binding and transaction sending
+
+use crate::{
+    gas::{self, GasConfig},
+    http::HttpSettings,
+    metrics,
+    monero_rpc::MoneroOutput,
+    nonce::{self, NonceTracker},
+    revert::Revert,
+};
+use alloy::{
+    contract::{CallBuilder, CallDecoder},
+    primitives::{keccak256, Address, B256, U256},
+    providers::Provider,
+    rpc::{
+        client::RpcClient,
+        types::{BlockId, TransactionReceipt},
+    },
+    sol,
+    transports::{http::Http, utils::guess_local_url, Transport},
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+sol! {
+    #[sol(rpc)]
+    contract WrappedMonero {
+        address public oracle;
+        uint256 public latestMoneroBlock;
+        mapping(bytes32 => bool) public usedOutputs;
+        mapping(address => bool) public isSigner;
+        uint256 public signerThreshold;
+        uint256 public thresholdKeyX;
+        uint8 public thresholdKeyParity;
+
+        function moneroBlocks(uint256 blockHeight) external view returns (
+            bytes32 blockHash,
+            bytes32 txMerkleRoot,
+            bytes32 outputMerkleRoot,
+            uint256 timestamp,
+            bool exists
+        );
+
+        event BurnRequested(
+            uint256 indexed burnId,
+            address indexed user,
+            address indexed lp,
+            uint256 amount,
+            string xmrAddress
+        );
+
+        function postMoneroBlock(
+            uint256 blockHeight,
+            bytes32 blockHash,
+            bytes32 txMerkleRoot,
+            bytes32 outputMerkleRoot
+        ) external;
+
+        function postMoneroBlockWithSignatures(
+            uint256 blockHeight,
+            bytes32 blockHash,
+            bytes32 txMerkleRoot,
+            bytes32 outputMerkleRoot,
+            bytes[] calldata signatures
+        ) external;
+
+        function postMoneroBlockWithThresholdSignature(
+            uint256 blockHeight,
+            bytes32 blockHash,
+            bytes32 txMerkleRoot,
+            bytes32 outputMerkleRoot,
+            uint256 signature,
+            address nonceAddress
+        ) external;
+
+        function fillMoneroBlock(
+            uint256 blockHeight,
+            bytes32 blockHash,
+            bytes32 txMerkleRoot,
+            bytes32 outputMerkleRoot
+        ) external;
+
+        function rollbackMoneroBlocks(uint256 fromHeight) external;
+
+        function continueRollback() external;
+
+        function rollbackCursor() external view returns (uint256);
+
+        function MAX_ROLLBACK_BLOCKS() external view returns (uint256);
+
+        function setSigners(address[] calldata newSigners, uint256 threshold) external;
+
+        function getSigners() external view returns (address[] memory);
+
+        function setThresholdKey(uint256 keyX, uint8 keyParity) external;
+
+        function acquireLease(bytes32 holder, uint256 duration) external;
+
+        function releaseLease(bytes32 holder) external;
+
+        function getLease() external view returns (bytes32 holder, uint256 remaining);
+
+        function paused() external view returns (bool);
+
+        function heartbeat(uint256 moneroTip) external;
+
+        function lastOracleActivity() external view returns (uint256);
+
+        function outputTreeVersion() external view returns (uint8);
+
+        function txTreeVersion() external view returns (uint8);
+
+        function outputTreeVersionOf(uint256 blockHeight) external view returns (uint8);
+
+        function confirmWithdrawal(
+            uint256 burnId,
+            bytes32 xmrTxHash,
+            bytes32 xmrTxKey
+        ) external;
+
+        struct MoneroTxOutput {
+            bytes32 txHash;
+            uint256 outputIndex;
+            bytes32 ecdhAmount;
+            bytes32 outputPubKey;
+            bytes32 commitment;
+        }
+
+        function mintDeposit(
+            address recipient,
+            uint256 amount,
+            MoneroTxOutput calldata output,
+            uint256 blockHeight,
+            bytes32[] calldata outputMerkleProof,
+            uint256 leafIndex
+        ) external;
+
+        function transferOracle(address newOracle) external;
+    }
+}
+
+sol! {
+    /// The parts of a Safe multisig the oracle reads when proposing to it
+    #[sol(rpc)]
+    contract Safe {
+        function nonce() external view returns (uint256);
+        function getOwners() external view returns (address[] memory);
+        function getThreshold() external view returns (uint256);
+    }
+}
+
+sol! {
+    /// Chainlink price feed, read for the XMR/USD rate bridge fees are
+    /// quoted at
+    #[sol(rpc)]
+    contract AggregatorV3 {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (
+            uint80 roundId,
+            int256 answer,
+            uint256 startedAt,
+            uint256 updatedAt,
+            uint80 answeredInRound
+        );
+    }
+}
+
+/// Key of a Monero output in the contract's `usedOutputs`:
+/// `keccak256(abi.encodePacked(txHash, outputIndex))`
+pub fn output_id(tx_hash: B256, output_index: u64) -> B256 {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(tx_hash.as_slice());
+    data[32..].copy_from_slice(&U256::from(output_index).to_be_bytes::<32>());
+    keccak256(data)
+}
+
+impl From<&MoneroOutput> for WrappedMonero::MoneroTxOutput {
+    fn from(output: &MoneroOutput) -> Self {
+        Self {
+            txHash: output.tx_hash,
+            outputIndex: U256::from(output.output_index),
+            ecdhAmount: output.ecdh_amount,
+            outputPubKey: output.output_pub_key,
+            commitment: output.commitment,
+        }
+    }
+}
+
+/// Block data the contract stores for a posted height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredBlock {
+    pub block_hash: B256,
+    pub tx_merkle_root: B256,
+    pub output_merkle_root: B256,
+}
+
+impl StoredBlock {
+    /// Fields that differ from `expected`
+    pub fn mismatches(&self, expected: &StoredBlock) -> Vec<&'static str> {
+        [
+            ("blockHash", self.block_hash == expected.block_hash),
+            (
+                "txMerkleRoot",
+                self.tx_merkle_root == expected.tx_merkle_root,
+            ),
+            (
+                "outputMerkleRoot",
+                self.output_merkle_root == expected.output_merkle_root,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, matches)| !matches)
+        .map(|(field, _)| field)
+        .collect()
+    }
+}
+
+/// Read back the block stored at `height`, or `None` if it isn't posted
+pub async fn get_block<T, P>(
+    contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    height: u64,
+) -> Result<Option<StoredBlock>>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    let block = contract
+        .moneroBlocks(U256::from(height))
+        .call()
+        .await
+        .with_context(|| format!("Failed to read block {} from the contract", height))?;
+    Ok(block.exists.then_some(StoredBlock {
+        block_hash: block.blockHash,
+        tx_merkle_root: block.txMerkleRoot,
+        output_merkle_root: block.outputMerkleRoot,
+    }))
+}
+
+/// Whether the contract is paused. Deployments without OpenZeppelin's
+/// `Pausable` revert the call (or return nothing), and are never paused.
+pub async fn is_paused<T, P>(contract: &WrappedMonero::WrappedMoneroInstance<T, P>) -> Result<bool>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    match contract.paused().call().await {
+        Ok(paused) => Ok(paused._0),
+        // No code at the address, or no such function: nothing to decode
+        Err(alloy::contract::Error::AbiError(_)) => Ok(false),
+        Err(e) => {
+            let e = anyhow::Error::from(e);
+            match Revert::from_error(&e) {
+                Some(_) => Ok(false),
+                None => Err(e.context("Failed to read paused() from the contract")),
+            }
+        }
+    }
+}
+
+/// Output tree version the contract records for blocks posted now.
+/// Deployments from before versioning don't have the call and, like a
+/// contract that never set it, take version 1 trees.
+pub async fn output_tree_version<T, P>(
+    contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+) -> Result<u8>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    tree_version(
+        "outputTreeVersion",
+        contract.outputTreeVersion().call().await.map(|v| v._0),
+    )
+}
+
+/// Tx tree version the contract records for blocks posted now, 1 where
+/// unversioned as with [`output_tree_version`]
+pub async fn tx_tree_version<T, P>(
+    contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+) -> Result<u8>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    tree_version(
+        "txTreeVersion",
+        contract.txTreeVersion().call().await.map(|v| v._0),
+    )
+}
+
+/// Output tree version `block_height` was posted with, which its proofs
+/// have to be built in. Blocks posted before versions were recorded (and
+/// deployments without the mapping) read 1, as with [`output_tree_version`].
+pub async fn block_output_tree_version<T, P>(
+    contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    block_height: u64,
+) -> Result<u8>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    tree_version(
+        "outputTreeVersionOf",
+        contract
+            .outputTreeVersionOf(U256::from(block_height))
+            .call()
+            .await
+            .map(|v| v._0),
+    )
+}
+
+fn tree_version(call: &str, result: Result<u8, alloy::contract::Error>) -> Result<u8> {
+    Ok(optional_call(call, result)?.map_or(1, |version| version.max(1)))
+}
+
+/// Next orphaned height an unfinished rollback has yet to clear with
+/// `continueRollback`, if any. Deployments from before rollbacks were
+/// batched clear every block in `rollbackMoneroBlocks` and never have one.
+pub async fn rollback_cursor<T, P>(
+    contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+) -> Result<Option<u64>>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    let cursor = optional_call(
+        "rollbackCursor",
+        contract.rollbackCursor().call().await.map(|c| c._0),
+    )?;
+    Ok(cursor
+        .filter(|cursor| !cursor.is_zero())
+        .map(|cursor| cursor.try_into().unwrap_or(u64::MAX)))
+}
+
+/// Transactions it takes to roll back blocks `from_height` up to the
+/// contract's latest: `rollbackMoneroBlocks`, then a `continueRollback` per
+/// further `MAX_ROLLBACK_BLOCKS`
+pub async fn rollback_calls<T, P>(
+    contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    from_height: u64,
+) -> Result<u64>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+{
+    let latest: u64 = contract
+        .latestMoneroBlock()
+        .call()
+        .await
+        .context("Failed to read latestMoneroBlock() from the contract")?
+        .latestMoneroBlock
+        .try_into()
+        .unwrap_or(u64::MAX);
+    let batch = optional_call(
+        "MAX_ROLLBACK_BLOCKS",
+        contract.MAX_ROLLBACK_BLOCKS().call().await.map(|b| b._0),
+    )?
+    .map(|batch| batch.try_into().unwrap_or(u64::MAX));
+    Ok(rollback_call_count(from_height, latest, batch))
+}
+
+/// See [`rollback_calls`]; without a batch size everything goes in one call
+fn rollback_call_count(from_height: u64, latest: u64, batch: Option<u64>) -> u64 {
+    let blocks = latest.saturating_sub(from_height) + 1;
+    match batch {
+        Some(batch) if batch > 0 => blocks.div_ceil(batch),
+        _ => 1,
+    }
+}
+
+/// The result of a view call that deployments from before it was added
+/// don't have: they revert (or return nothing), read as `None`
+fn optional_call<V>(call: &str, result: Result<V, alloy::contract::Error>) -> Result<Option<V>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(alloy::contract::Error::AbiError(_)) => Ok(None),
+        Err(e) => {
+            let e = anyhow::Error::from(e);
+            match Revert::from_error(&e) {
+                Some(_) => Ok(None),
+                None => Err(e.context(format!("Failed to read {}() from the contract", call))),
+            }
+        }
+    }
+}
+
+/// Send `call` with fees from the configured gas strategy. If it sits
+/// unconfirmed past the stuck timeout it is replaced (same nonce) with
+/// bumped fees, up to `max_bumps` times.
+///
+/// The nonce comes from `nonces`: a transaction left pending by an earlier
+/// send (or an earlier run) is replaced rather than queued behind.
+///
+/// `on_sent` is told the hash of each transaction as it is broadcast, so a
+/// crash while waiting doesn't lose track of it.
+///
+/// The receipt is returned once mined, whether or not the call reverted:
+/// check `status()`, and see [`reverted_receipt`] for why one failed.
+pub async fn send_with_fee_bumps<'a, T, P, D>(
+    provider: &'a P,
+    from: Address,
+    gas: &GasConfig,
+    nonces: &Mutex<NonceTracker>,
+    call: CallBuilder<T, &'a P, D>,
+    on_sent: impl Fn(B256),
+) -> Result<TransactionReceipt>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+    D: CallDecoder + Clone,
+{
+    let mut fees = gas::suggest_fees(provider, gas).await?;
+    let latest = provider.get_transaction_count(from).latest().await?;
+    let pending = provider.get_transaction_count(from).pending().await?;
+    let next = nonces
+        .lock()
+        .expect("nonce tracker poisoned")
+        .next(latest, pending);
+    let nonce = next.nonce;
+
+    if next.stuck {
+        warn!(
+            "   ⛽ Nonce {} is held by a pending transaction, replacing it",
+            nonce
+        );
+        if let Some(stuck_fees) = next.stuck_fees {
+            fees = gas
+                .replacement(fees, stuck_fees)
+                .context("Fee cap reached, can't replace the pending transaction")?;
+        }
+        metrics::get().transactions_replaced.inc();
+    }
+
+    let mined = |receipt: TransactionReceipt| {
+        nonces.lock().expect("nonce tracker poisoned").mined(nonce);
+        record_gas(receipt)
+    };
+    let mut sent = Vec::new();
+
+    for attempt in 0..=gas.max_bumps {
+        if attempt > 0 {
+            fees = match gas.bump(fees) {
+                Some(bumped) => bumped,
+                None => {
+                    warn!("   ⚠️  Fee cap reached, not replacing transaction");
+                    break;
+                }
+            };
+            warn!(
+                "   ⛽ Replacing transaction with bumped fees ({}/{})",
+                attempt, gas.max_bumps
+            );
+            metrics::get().transactions_replaced.inc();
+        }
+
+        info!(
+            "   Fees: max {} gwei, priority {} gwei (nonce {})",
+            gas::format_gwei(fees.max_fee_per_gas),
+            gas::format_gwei(fees.max_priority_fee_per_gas),
+            nonce
+        );
+
+        let send_result = call
+            .clone()
+            .nonce(nonce)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .send()
+            .await;
+
+        let pending_tx =
+            match metrics::get().observe_rpc("unichain", "sendTransaction", send_result) {
+                Ok(pending_tx) => pending_tx,
+                Err(e) => {
+                    // A replacement fails with "nonce too low" if an earlier
+                    // attempt was mined in the meantime
+                    if let Some(receipt) = find_receipt(provider, &sent).await? {
+                        return Ok(mined(receipt));
+                    }
+                    // A pending transaction we have no fees for still pays
+                    // more: bump and try again
+                    if nonce::is_underpriced(&e.to_string()) {
+                        warn!(
+                            "   ⚠️  Nonce {} taken by a better-paying transaction",
+                            nonce
+                        );
+                        continue;
+                    }
+                    return Err(e.into());
+                }
+            };
+
+        let tx_hash = *pending_tx.tx_hash();
+        info!("   TX: {}", tx_hash);
+        info!("   ⏳ Waiting for confirmation...");
+        sent.push(tx_hash);
+        on_sent(tx_hash);
+        nonces
+            .lock()
+            .expect("nonce tracker poisoned")
+            .sent(nonce, fees);
+
+        if let Ok(receipt) = tokio::time::timeout(gas.stuck_timeout, pending_tx.get_receipt()).await
+        {
+            return Ok(mined(receipt?));
+        }
+
+        if let Some(receipt) = find_receipt(provider, &sent).await? {
+            return Ok(mined(receipt));
+        }
+    }
+
+    // Left pending: the next send replaces it
+    anyhow::bail!(
+        "Transaction with nonce {} still unconfirmed after {} attempt(s)",
+        nonce,
+        sent.len()
+    )
+}
+
+/// The error behind `receipt`, mined with status 0 for `call`. Receipts
+/// carry no revert data, so the call is replayed from the same sender at
+/// the block it was mined in, and the replay's revert is what
+/// [`Revert::from_error`] decodes. If the replay passes (the transaction ran
+/// out of gas, or what made it revert changed later in the block) the error
+/// has no reason to decode.
+pub async fn reverted_receipt<T, P, D>(
+    call: CallBuilder<T, &P, D>,
+    receipt: &TransactionReceipt,
+) -> anyhow::Error
+where
+    T: Transport + Clone,
+    P: Provider<T>,
+    D: CallDecoder,
+{
+    let mut call = call.from(receipt.from);
+    if let Some(block) = receipt.block_number {
+        call = call.block(BlockId::number(block));
+    }
+    let error = match call.call_raw().await {
+        Err(e) => anyhow::Error::from(e),
+        Ok(_) => anyhow::anyhow!("the call doesn't revert when replayed"),
+    };
+    error.context(format!(
+        "Transaction {} reverted in block {}",
+        receipt.transaction_hash,
+        receipt.block_number.unwrap_or(0)
+    ))
+}
+
+/// Count a mined transaction's gas in the metrics
+fn record_gas(receipt: TransactionReceipt) -> TransactionReceipt {
+    let gas_used: u64 = receipt.gas_used.try_into().unwrap_or(u64::MAX);
+    let gas_cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+    metrics::get().gas_used.inc_by(gas_used);
+    metrics::get().gas_spent_eth.inc_by(wei_to_ether(gas_cost));
+    receipt
+}
+
+/// Receipt for whichever of `tx_hashes` (replacements of one nonce) was mined
+pub async fn find_receipt<T, P>(
+    provider: &P,
+    tx_hashes: &[B256],
+) -> Result<Option<TransactionReceipt>>
+where
+    T: Transport + Clone,
+    P: Provider<T>,
+{
+    for tx_hash in tx_hashes {
+        if let Some(receipt) = provider.get_transaction_receipt(*tx_hash).await? {
+            return Ok(Some(receipt));
+        }
+    }
+    Ok(None)
+}
+
+/// JSON-RPC client for `url` with the given timeouts and pooling, for
+/// `ProviderBuilder::on_client`
+pub fn rpc_client(url: &str, http: &HttpSettings) -> Result<RpcClient<Http<reqwest::Client>>> {
+    let parsed = url
+        .parse()
+        .with_context(|| format!("Invalid Unichain RPC URL {}", url))?;
+    let transport = Http::with_client(http.client()?, parsed);
+    Ok(RpcClient::new(transport, guess_local_url(url)))
+}
+
+/// Convert wei to ether for display and metrics
+pub fn wei_to_ether(wei: U256) -> f64 {
+    let wei_u128: u128 = wei.try_into().unwrap_or(u128::MAX);
+    wei_u128 as f64 / 1e18
+}
+
+pub fn format_ether(wei: U256) -> String {
+    format!("{:.6}", wei_to_ether(wei))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        providers::ProviderBuilder, sol_types::Revert as RequireFailed, sol_types::SolError,
+    };
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    /// A node answering `eth_call` with a `require` revert, keeping the calls
+    async fn reverting_node(calls: Arc<Mutex<Vec<Value>>>) -> String {
+        let data = format!(
+            "0x{}",
+            hex::encode(RequireFailed::from("Block exists").abi_encode())
+        );
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| async move {
+                calls.lock().unwrap().push(request.clone());
+                Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {
+                        "code": 3,
+                        "message": "execution reverted: Block exists",
+                        "data": data,
+                    },
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_reverted_receipt_replays_the_call() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let url = reverting_node(calls.clone()).await;
+        let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+        let contract = WrappedMonero::new(Address::repeat_byte(0xcc), &provider);
+        let call = contract.postMoneroBlock(U256::from(100), B256::ZERO, B256::ZERO, B256::ZERO);
+
+        // Mined, but with status 0
+        let oracle = Address::repeat_byte(0x0a);
+        let receipt: TransactionReceipt = serde_json::from_value(json!({
+            "type": "0x2",
+            "status": "0x0",
+            "cumulativeGasUsed": "0x5208",
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "transactionHash": B256::repeat_byte(0x01),
+            "transactionIndex": "0x0",
+            "blockHash": B256::repeat_byte(0x02),
+            "blockNumber": "0x2a",
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x1",
+            "from": oracle,
+            "to": contract.address(),
+            "contractAddress": null,
+        }))
+        .unwrap();
+        assert!(!receipt.status());
+
+        let error = reverted_receipt(call, &receipt).await;
+        assert_eq!(
+            Revert::from_error(&error),
+            Some(Revert::Require("Block exists".to_string()))
+        );
+        assert!(format!("{:#}", error).contains("reverted in block 42"));
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["method"], "eth_call");
+        assert_eq!(calls[0]["params"][0]["from"], json!(oracle));
+        assert_eq!(calls[0]["params"][1], "0x2a");
+    }
+
+    #[test]
+    fn test_rollback_call_count() {
+        assert_eq!(rollback_call_count(101, 100 + 256, Some(256)), 1);
+        assert_eq!(rollback_call_count(100, 100 + 256, Some(256)), 2);
+        assert_eq!(rollback_call_count(100, 100, Some(256)), 1);
+        assert_eq!(rollback_call_count(1, 1_000, Some(256)), 4);
+        // Deployments from before batching clear it all at once
+        assert_eq!(rollback_call_count(1, 1_000, None), 1);
+    }
+
+    /// A node answering every `eth_call` with `result`
+    async fn answering_node(result: U256) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| async move {
+                Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": format!("0x{}", hex::encode(result.to_be_bytes::<32>())),
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_block_output_tree_version() {
+        let url = answering_node(U256::from(2)).await;
+        let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+        let contract = WrappedMonero::new(Address::repeat_byte(0xcc), &provider);
+        assert_eq!(block_output_tree_version(&contract, 100).await.unwrap(), 2);
+
+        // Posted before versions were recorded
+        let url = answering_node(U256::ZERO).await;
+        let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+        let contract = WrappedMonero::new(Address::repeat_byte(0xcc), &provider);
+        assert_eq!(block_output_tree_version(&contract, 100).await.unwrap(), 1);
+
+        // A deployment without the mapping
+        let url = reverting_node(Arc::new(Mutex::new(Vec::new()))).await;
+        let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+        let contract = WrappedMonero::new(Address::repeat_byte(0xcc), &provider);
+        assert_eq!(block_output_tree_version(&contract, 100).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_rollback_cursor_on_older_deployments() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let url = reverting_node(calls).await;
+        let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+        let contract = WrappedMonero::new(Address::repeat_byte(0xcc), &provider);
+        assert_eq!(rollback_cursor(&contract).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! Contract events over a WebSocket subscription (`UNICHAIN_WS_URL`)
+//!
+//! Posting is otherwise fire-and-forget: the oracle only learns about
+//! on-chain changes it didn't make on its next poll, and about an oracle
+//! rotation only when its transactions start reverting. Subscribed to the
+//! contract's logs, it sees blocks posted by other submitters, rollbacks,
+//! `transferOracle` and pausing as they happen. The subscription reconnects on its own;
+//! while it is down the oracle carries on polling.
+
+use alloy::{
+    primitives::{Address, Log, B256},
+    providers::{Provider, ProviderBuilder, WsConnect},
+    rpc::types::Filter,
+    sol,
+    sol_types::SolEvent,
+};
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+sol! {
+    event MoneroBlockPosted(uint256 indexed blockHeight, bytes32 indexed blockHash);
+    event MoneroBlocksRolledBack(uint256 indexed fromHeight, uint256 previousLatest);
+    event OracleTransferred(address indexed previousOracle, address indexed newOracle);
+    event Paused(address account);
+    event Unpaused(address account);
+}
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// A contract event the oracle reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractEvent {
+    BlockPosted {
+        height: u64,
+        block_hash: B256,
+    },
+    BlocksRolledBack {
+        from_height: u64,
+    },
+    OracleTransferred {
+        previous: Address,
+        new: Address,
+    },
+    /// OpenZeppelin `Pausable`: the contract refuses postings until unpaused
+    Paused {
+        by: Address,
+    },
+    Unpaused {
+        by: Address,
+    },
+}
+
+impl ContractEvent {
+    /// Decode one of the subscribed events; `None` for anything else
+    pub fn decode(log: &Log) -> Option<Self> {
+        if let Ok(event) = MoneroBlockPosted::decode_log_data(&log.data, true) {
+            return Some(Self::BlockPosted {
+                height: event.blockHeight.try_into().ok()?,
+                block_hash: event.blockHash,
+            });
+        }
+        if let Ok(event) = MoneroBlocksRolledBack::decode_log_data(&log.data, true) {
+            return Some(Self::BlocksRolledBack {
+                from_height: event.fromHeight.try_into().ok()?,
+            });
+        }
+        if let Ok(event) = OracleTransferred::decode_log_data(&log.data, true) {
+            return Some(Self::OracleTransferred {
+                previous: event.previousOracle,
+                new: event.newOracle,
+            });
+        }
+        if let Ok(event) = Paused::decode_log_data(&log.data, true) {
+            return Some(Self::Paused { by: event.account });
+        }
+        if let Ok(event) = Unpaused::decode_log_data(&log.data, true) {
+            return Some(Self::Unpaused { by: event.account });
+        }
+        None
+    }
+}
+
+/// Subscribe to `bridge`'s events over `url` in the background
+pub fn spawn(url: String, bridge: Address, events: mpsc::Sender<ContractEvent>) {
+    tokio::spawn(async move {
+        loop {
+            match listen(&url, bridge, &events).await {
+                Ok(()) => warn!("⚠️  Contract event subscription at {} closed", url),
+                Err(e) => warn!("⚠️  Contract event subscription at {} failed: {:#}", url, e),
+            }
+            if events.is_closed() {
+                return;
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+async fn listen(url: &str, bridge: Address, events: &mpsc::Sender<ContractEvent>) -> Result<()> {
+    let provider = ProviderBuilder::new()
+        .on_ws(WsConnect::new(url))
+        .await
+        .context("Failed to connect")?;
+    let filter = Filter::new().address(bridge).event_signature(vec![
+        MoneroBlockPosted::SIGNATURE_HASH,
+        MoneroBlocksRolledBack::SIGNATURE_HASH,
+        OracleTransferred::SIGNATURE_HASH,
+        Paused::SIGNATURE_HASH,
+        Unpaused::SIGNATURE_HASH,
+    ]);
+    let mut logs = provider.subscribe_logs(&filter).await?.into_stream();
+    info!("📡 Subscribed to contract events at {}", url);
+
+    while let Some(log) = logs.next().await {
+        // Removed logs belong to a Unichain reorg; the next poll re-reads state
+        if log.removed {
+            continue;
+        }
+        if let Some(event) = ContractEvent::decode(&log.inner) {
+            if events.send(event).await.is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256, U256};
+
+    #[test]
+    fn test_decode_events() {
+        let bridge = address!("1111111111111111111111111111111111111111");
+        let block_hash = b256!("771fbcd656ec1464d3a02ead5e18644030007a0fc664c0a964d30922821a8148");
+
+        let posted = MoneroBlockPosted {
+            blockHeight: U256::from(3_100_000u64),
+            blockHash: block_hash,
+        };
+        let log = Log {
+            address: bridge,
+            data: posted.encode_log_data(),
+        };
+        assert_eq!(
+            ContractEvent::decode(&log),
+            Some(ContractEvent::BlockPosted {
+                height: 3_100_000,
+                block_hash
+            })
+        );
+
+        let previous = address!("2222222222222222222222222222222222222222");
+        let new = address!("3333333333333333333333333333333333333333");
+        let log = Log {
+            address: bridge,
+            data: OracleTransferred {
+                previousOracle: previous,
+                newOracle: new,
+            }
+            .encode_log_data(),
+        };
+        assert_eq!(
+            ContractEvent::decode(&log),
+            Some(ContractEvent::OracleTransferred { previous, new })
+        );
+
+        let log = Log {
+            address: bridge,
+            data: Unpaused { account: previous }.encode_log_data(),
+        };
+        assert_eq!(
+            ContractEvent::decode(&log),
+            Some(ContractEvent::Unpaused { by: previous })
+        );
+
+        let log = Log {
+            address: bridge,
+            data: MoneroBlocksRolledBack {
+                fromHeight: U256::from(3_099_990u64),
+                previousLatest: U256::from(3_100_000u64),
+            }
+            .encode_log_data(),
+        };
+        assert_eq!(
+            ContractEvent::decode(&log),
+            Some(ContractEvent::BlocksRolledBack {
+                from_height: 3_099_990
+            })
+        );
+
+        // Heights past u64 aren't Monero heights
+        let log = Log {
+            address: bridge,
+            data: MoneroBlockPosted {
+                blockHeight: U256::MAX,
+                blockHash: block_hash,
+            }
+            .encode_log_data(),
+        };
+        assert_eq!(ContractEvent::decode(&log), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Live bridge events for frontends
+//!
+//! The oracle publishes what it does to a [`Feed`] as it happens, and the
+//! HTTP API streams it to every client connected to `GET /events` as
+//! server-sent events: one `event:` per [`BridgeEvent`] kind, with the event
+//! as JSON in `data:`. Nothing is stored; a client that connects late or
+//! falls behind gets a `lagged` event and should catch up from the HTTP API.
+
+use crate::{scanner::Deposit, subaddress::SubaddressIndex};
+use alloy::primitives::{Address, B256, B64};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Events buffered for each subscriber before it counts as lagging
+const CAPACITY: usize = 256;
+
+/// Something a frontend may want to show as it happens
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BridgeEvent {
+    /// A Monero block was posted to a contract
+    #[serde(rename_all = "camelCase")]
+    BlockPosted {
+        contract: Address,
+        height: u64,
+        block_hash: B256,
+        unichain_tx_hash: B256,
+    },
+    /// A deposit to the bridge was seen in the txpool
+    #[serde(rename_all = "camelCase")]
+    DepositDetected(DepositEvent),
+    /// A deposit's block was posted, so it can be minted
+    #[serde(rename_all = "camelCase")]
+    DepositConfirmed(DepositEvent),
+    /// A withdrawal's payout was broadcast on Monero
+    #[serde(rename_all = "camelCase")]
+    WithdrawalSent {
+        burn_id: u64,
+        xmr_address: String,
+        /// Piconero sent, net of any deducted fee
+        amount: u64,
+        xmr_tx_hash: B256,
+    },
+}
+
+impl BridgeEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BlockPosted { .. } => "blockPosted",
+            Self::DepositDetected(_) => "depositDetected",
+            Self::DepositConfirmed(_) => "depositConfirmed",
+            Self::WithdrawalSent { .. } => "withdrawalSent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositEvent {
+    pub tx_hash: B256,
+    pub output_index: u64,
+    /// Amount in piconero
+    pub amount: u64,
+    pub subaddress: SubaddressIndex,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<B64>,
+    /// `None` while in the txpool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<u64>,
+}
+
+impl DepositEvent {
+    /// A deposit seen in the txpool
+    pub fn pending(deposit: &Deposit) -> Self {
+        Self {
+            block_height: None,
+            ..Self::from(deposit)
+        }
+    }
+}
+
+impl From<&Deposit> for DepositEvent {
+    fn from(deposit: &Deposit) -> Self {
+        Self {
+            tx_hash: deposit.tx_hash,
+            output_index: deposit.output_index,
+            amount: deposit.amount,
+            subaddress: deposit.subaddress,
+            payment_id: deposit.payment_id,
+            block_height: Some(deposit.block_height),
+        }
+    }
+}
+
+/// Fan-out of [`BridgeEvent`]s to whoever is listening
+#[derive(Debug, Clone)]
+pub struct Feed {
+    events: broadcast::Sender<BridgeEvent>,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Feed {
+    pub fn new() -> Self {
+        Self {
+            events: broadcast::channel(CAPACITY).0,
+        }
+    }
+
+    /// Send `event` to current subscribers; with none it is dropped
+    pub fn publish(&self, event: BridgeEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<BridgeEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::RecvError;
+
+    #[test]
+    fn test_event_json() {
+        let event = BridgeEvent::WithdrawalSent {
+            burn_id: 7,
+            xmr_address: "4Adr".to_string(),
+            amount: 1_000_000,
+            xmr_tx_hash: B256::repeat_byte(0xab),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.name());
+        assert_eq!(json["burnId"], 7);
+        assert_eq!(json["xmrTxHash"], B256::repeat_byte(0xab).to_string());
+
+        let deposit = DepositEvent {
+            tx_hash: B256::repeat_byte(0x11),
+            output_index: 1,
+            amount: 5,
+            subaddress: SubaddressIndex::new(0, 3),
+            payment_id: None,
+            block_height: None,
+        };
+        let json = serde_json::to_value(BridgeEvent::DepositDetected(deposit)).unwrap();
+        assert_eq!(json["type"], "depositDetected");
+        assert_eq!(json["outputIndex"], 1);
+        assert!(json.get("blockHeight").is_none());
+        assert!(json.get("paymentId").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_get_later_events() {
+        let feed = Feed::new();
+        let block = BridgeEvent::BlockPosted {
+            contract: Address::repeat_byte(0x01),
+            height: 3_100_000,
+            block_hash: B256::repeat_byte(0x02),
+            unichain_tx_hash: B256::repeat_byte(0x03),
+        };
+        // Nobody listening yet
+        feed.publish(block.clone());
+
+        let mut events = feed.subscribe();
+        feed.publish(block.clone());
+        assert_eq!(events.recv().await.unwrap(), block);
+
+        for _ in 0..=CAPACITY {
+            feed.publish(block.clone());
+        }
+        assert!(matches!(events.recv().await, Err(RecvError::Lagged(1))));
+    }
+}
@@ -0,0 +1,278 @@
+//! Monero fee quotes for withdrawal payouts
+//!
+//! monerod's `get_fee_estimate` gives a per-byte fee for each of wallet2's
+//! four priority levels and a quantization mask; a transaction pays its
+//! weight times the per-byte fee, rounded up to a multiple of the mask. The
+//! real weight depends on the inputs the wallet picks, so quotes use wallet2's
+//! estimate for a typical payout: two inputs, one output per recipient and
+//! change. A batched payout's fee is split evenly between its recipients.
+//! Every withdrawal quoted from the same estimate and batch size is charged
+//! the same fee, however the wallet ends up funding it.
+
+use crate::{decoys::RING_SIZE, monero_rpc::FeeEstimate};
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// Inputs assumed for a payout
+pub const PAYOUT_INPUTS: u64 = 2;
+/// Outputs per transaction allowed by consensus
+pub const MAX_OUTPUTS: u64 = 16;
+/// Recipients of one payout, leaving an output for change
+pub const MAX_BATCH_SIZE: u64 = MAX_OUTPUTS - 1;
+
+/// Per-byte fee multipliers for daemons without per-priority fees
+const LEGACY_MULTIPLIERS: [u64; 4] = [1, 5, 25, 1000];
+
+/// wallet2's transfer priorities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeePriority {
+    Unimportant,
+    Normal,
+    Elevated,
+    Priority,
+}
+
+impl FeePriority {
+    pub const ALL: [Self; 4] = [
+        Self::Unimportant,
+        Self::Normal,
+        Self::Elevated,
+        Self::Priority,
+    ];
+
+    /// `priority` argument of wallet-rpc's `transfer`
+    pub fn wallet_priority(self) -> u32 {
+        match self {
+            Self::Unimportant => 1,
+            Self::Normal => 2,
+            Self::Elevated => 3,
+            Self::Priority => 4,
+        }
+    }
+
+    fn tier(self) -> usize {
+        self.wallet_priority() as usize - 1
+    }
+}
+
+impl FromStr for FeePriority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "unimportant" | "low" => Ok(Self::Unimportant),
+            "normal" => Ok(Self::Normal),
+            "elevated" => Ok(Self::Elevated),
+            "priority" | "high" => Ok(Self::Priority),
+            other => anyhow::bail!(
+                "Unknown fee priority '{}' (expected unimportant, normal, elevated or priority)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for FeePriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Unimportant => "unimportant",
+            Self::Normal => "normal",
+            Self::Elevated => "elevated",
+            Self::Priority => "priority",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Fee charged to each recipient of a payout at one priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeQuote {
+    pub priority: FeePriority,
+    /// Withdrawals paid by the transaction
+    pub recipients: u64,
+    /// Piconero per byte of weight
+    pub fee_per_byte: u64,
+    /// Estimated weight of the payout transaction
+    pub weight: u64,
+    /// Piconero per recipient
+    pub fee: u64,
+}
+
+impl FeeQuote {
+    /// What the recipient of a withdrawal of `amount` receives, or `None`
+    /// when the fee takes all of it
+    pub fn payout(&self, amount: u64) -> Option<u64> {
+        amount.checked_sub(self.fee).filter(|&payout| payout > 0)
+    }
+}
+
+/// Per-byte fee at `priority`. Daemons from before the 2021 fee scaling only
+/// report a base fee, which wallet2 multiplies.
+pub fn fee_per_byte(estimate: &FeeEstimate, priority: FeePriority) -> u64 {
+    match estimate.fees.get(priority.tier()) {
+        Some(&fee) if estimate.fees.len() == LEGACY_MULTIPLIERS.len() => fee,
+        _ => estimate.fee * LEGACY_MULTIPLIERS[priority.tier()],
+    }
+}
+
+/// Fee for `weight` bytes, rounded up to a multiple of `quantization_mask`
+/// as wallet2 does
+pub fn fee_for_weight(weight: u64, fee_per_byte: u64, quantization_mask: u64) -> u64 {
+    let mask = quantization_mask.max(1);
+    (weight * fee_per_byte).div_ceil(mask) * mask
+}
+
+/// wallet2's `estimate_tx_weight` for a CLSAG transaction with a
+/// Bulletproofs+ range proof and view tags
+pub fn estimate_tx_weight(inputs: u64, outputs: u64, extra_size: u64) -> u64 {
+    let ring = RING_SIZE as u64;
+    let mut log_padded_outputs = 0;
+    while (1 << log_padded_outputs) < outputs {
+        log_padded_outputs += 1;
+    }
+
+    // Prefix: version and unlock time, inputs with their key offsets and key
+    // image, outputs with view tags, extra
+    let mut weight = 1 + 6;
+    weight += inputs * (1 + 6 + ring * 2 + 32);
+    weight += outputs * (6 + 32 + 1);
+    weight += extra_size;
+    // RingCT type, range proof, CLSAGs, pseudo outputs, amounts, commitments, fee
+    weight += 1;
+    weight += (2 * (6 + log_padded_outputs) + 6) * 32 + 3;
+    weight += inputs * (32 * ring + 64);
+    weight += 32 * inputs;
+    weight += 8 * outputs + 32 * outputs;
+    weight += 4;
+
+    // Aggregate proofs are cheap to verify per output only up to a point;
+    // beyond two outputs the weight claws some of the saving back
+    if outputs > 2 {
+        let log_padded_outputs = log_padded_outputs.max(2);
+        let bp_base = 32 * (6 + 7 * 2) / 2;
+        let bp_size = 32 * (6 + 2 * (6 + log_padded_outputs));
+        weight += (bp_base * (1 << log_padded_outputs) - bp_size) * 4 / 5;
+    }
+    weight
+}
+
+/// Tx public key, plus the dummy encrypted payment ID wallets add to
+/// two-output transactions
+fn payout_extra_size(outputs: u64) -> u64 {
+    match outputs {
+        2 => 33 + 11,
+        _ => 33,
+    }
+}
+
+/// Fee for a typical payout to one recipient at `priority`
+pub fn quote(estimate: &FeeEstimate, priority: FeePriority) -> FeeQuote {
+    quote_batch(estimate, priority, 1)
+}
+
+/// Fee each recipient of a payout to `recipients` withdrawals is charged,
+/// rounded up so the shares cover the whole fee
+pub fn quote_batch(estimate: &FeeEstimate, priority: FeePriority, recipients: u64) -> FeeQuote {
+    let recipients = recipients.clamp(1, MAX_BATCH_SIZE);
+    let outputs = recipients + 1;
+    let fee_per_byte = fee_per_byte(estimate, priority);
+    let weight = estimate_tx_weight(PAYOUT_INPUTS, outputs, payout_extra_size(outputs));
+    let fee = fee_for_weight(weight, fee_per_byte, estimate.quantization_mask);
+    FeeQuote {
+        priority,
+        recipients,
+        fee_per_byte,
+        weight,
+        fee: fee.div_ceil(recipients),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate() -> FeeEstimate {
+        FeeEstimate {
+            fee: 20_000,
+            fees: vec![20_000, 80_000, 320_000, 4_000_000],
+            quantization_mask: 10_000,
+        }
+    }
+
+    #[test]
+    fn test_payout_weight() {
+        // A 2-in/2-out transaction is a little over 2 kB
+        assert_eq!(estimate_tx_weight(2, 2, payout_extra_size(2)), 2215);
+        assert_eq!(estimate_tx_weight(1, 2, payout_extra_size(2)), 1536);
+        // Extra outputs pay for the clawback
+        let three = estimate_tx_weight(2, 3, payout_extra_size(3));
+        let four = estimate_tx_weight(2, 4, payout_extra_size(4));
+        assert_eq!(three, four - 39 - 40);
+        assert_eq!(four, 2886);
+    }
+
+    #[test]
+    fn test_fee_rounds_up_to_quantization_mask() {
+        assert_eq!(fee_for_weight(3, 7, 10), 30);
+        assert_eq!(fee_for_weight(10, 2, 10), 20);
+        assert_eq!(fee_for_weight(3, 7, 0), 21);
+    }
+
+    #[test]
+    fn test_quote() {
+        let normal = quote(&estimate(), FeePriority::Normal);
+        assert_eq!(normal.fee_per_byte, 80_000);
+        assert_eq!(normal.fee, 2215 * 80_000);
+        assert_eq!(normal.payout(normal.fee + 1), Some(1));
+        assert_eq!(normal.payout(normal.fee), None);
+        assert_eq!(normal.payout(1), None);
+
+        let fees: Vec<u64> = FeePriority::ALL
+            .iter()
+            .map(|&priority| quote(&estimate(), priority).fee)
+            .collect();
+        assert!(fees.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Daemons without per-priority fees
+        let legacy = FeeEstimate {
+            fees: vec![],
+            ..estimate()
+        };
+        assert_eq!(fee_per_byte(&legacy, FeePriority::Elevated), 500_000);
+        assert_eq!(fee_per_byte(&legacy, FeePriority::Priority), 20_000_000);
+    }
+
+    #[test]
+    fn test_batching_splits_fee() {
+        let single = quote(&estimate(), FeePriority::Normal);
+        let batch = quote_batch(&estimate(), FeePriority::Normal, 10);
+        assert_eq!(batch.recipients, 10);
+        assert!(batch.weight > single.weight);
+        // Shares cover the fee and cost each recipient far less than paying alone
+        let total = fee_for_weight(batch.weight, 80_000, 10_000);
+        assert!(batch.fee * 10 >= total && batch.fee * 10 - total < 10);
+        assert!(batch.fee * 3 < single.fee);
+
+        // Clamped to what one transaction can pay
+        let oversized = quote_batch(&estimate(), FeePriority::Normal, 40);
+        assert_eq!(oversized.recipients, MAX_BATCH_SIZE);
+        assert_eq!(quote_batch(&estimate(), FeePriority::Normal, 0), single);
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        for priority in FeePriority::ALL {
+            assert_eq!(
+                priority.to_string().parse::<FeePriority>().unwrap(),
+                priority
+            );
+        }
+        assert_eq!(
+            "LOW".parse::<FeePriority>().unwrap(),
+            FeePriority::Unimportant
+        );
+        assert!("urgent".parse::<FeePriority>().is_err());
+    }
+}
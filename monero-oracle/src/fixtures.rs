@@ -0,0 +1,346 @@
+//! Recorded monerod responses for offline regression tests
+//!
+//! With `MONERO_RPC_RECORD_DIR` set, every successful monerod response is
+//! also written to that directory, one file per distinct request. With
+//! `MONERO_RPC_REPLAY_DIR` set instead, requests are answered from such a
+//! directory and never reach the network; a request that wasn't recorded
+//! fails. `record-fixture` captures a block this way together with the
+//! roots computed from it, and `tests/replay.rs` recomputes every recorded
+//! block, so a change to Merkle roots or output extraction that would alter
+//! what gets posted fails without a node. `benches/block_processing.rs` times
+//! the same blocks, parsed from [`RecordedResponses`].
+//!
+//! [`synthetic`] writes the same files without a node; it is dev tooling,
+//! left out of the production binary.
+
+#[cfg(any(test, feature = "synthetic-fixtures"))]
+pub mod synthetic;
+
+use crate::{
+    merkle::{
+        compute_output_merkle_root, compute_tx_merkle_root, verify_block_tx_hashes, MerkleHasher,
+    },
+    monero_rpc::{
+        outputs_from_transactions, parse_hex_to_b256, transaction_order, FetchedBlock,
+        GetBlockResponse, GetTransactionsRequest, GetTransactionsResponse, JsonRpcRequest,
+        JsonRpcResponse, MoneroRpcClient,
+    },
+};
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Hash and roots a fixture directory was recorded with
+pub const EXPECTED_FILE: &str = "expected.json";
+
+/// Where monerod responses are recorded to or replayed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fixtures {
+    /// Send requests as usual and save each response
+    Record(PathBuf),
+    /// Answer requests from saved responses only
+    Replay(PathBuf),
+}
+
+impl Fixtures {
+    /// File holding the response to `body` posted to `path`. Requests are
+    /// deterministic, so the same call always maps to the same file.
+    pub fn file_name(method: &str, path: &str, body: &[u8]) -> String {
+        let digest = Sha256::new()
+            .chain_update(path)
+            .chain_update([0])
+            .chain_update(body)
+            .finalize();
+        let extension = if path.ends_with(".bin") {
+            "bin"
+        } else {
+            "json"
+        };
+        format!("{}-{}.{}", method, hex::encode(&digest[..8]), extension)
+    }
+
+    /// The recorded response to a request, when replaying
+    pub fn load(&self, method: &str, path: &str, body: &[u8]) -> Result<Option<Vec<u8>>> {
+        let Self::Replay(dir) = self else {
+            return Ok(None);
+        };
+        let name = Self::file_name(method, path, body);
+        fs::read(dir.join(&name)).map(Some).with_context(|| {
+            format!(
+                "No recorded {} response {} in {}",
+                method,
+                name,
+                dir.display()
+            )
+        })
+    }
+
+    /// Save the response to a request, when recording
+    pub fn save(&self, method: &str, path: &str, body: &[u8], response: &[u8]) -> Result<()> {
+        let Self::Record(dir) = self else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let file = dir.join(Self::file_name(method, path, body));
+        fs::write(&file, response).with_context(|| format!("Failed to write {}", file.display()))
+    }
+}
+
+/// What the oracle would post for a block, stored next to its recording
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedBlock {
+    pub height: u64,
+    pub block_hash: B256,
+    pub tx_merkle_root: B256,
+    /// Absent in fixtures recorded before tx tree versions: `keccak256`
+    #[serde(default)]
+    pub tx_merkle_hasher: MerkleHasher,
+    pub output_merkle_root: B256,
+    pub output_merkle_hasher: MerkleHasher,
+    pub output_count: usize,
+}
+
+impl RecordedBlock {
+    /// Fetch block `height` and compute its roots the way the oracle does
+    /// before posting
+    pub async fn compute(
+        client: &MoneroRpcClient,
+        height: u64,
+        tx_hasher: MerkleHasher,
+        hasher: MerkleHasher,
+    ) -> Result<Self> {
+        let block = client.fetch_block(height).await?;
+        verify_block_tx_hashes(&block.header, block.miner_tx_hash(), &block.tx_hashes)?;
+
+        let outputs = outputs_from_transactions(&block.transactions)?;
+        let outputs = hasher.output_order(&outputs, &transaction_order(&block.transactions)?);
+        Ok(Self {
+            height,
+            block_hash: parse_hex_to_b256(&block.header.hash)?,
+            tx_merkle_root: compute_tx_merkle_root(&block.tx_hashes, tx_hasher),
+            tx_merkle_hasher: tx_hasher,
+            output_merkle_root: compute_output_merkle_root(&outputs, hasher),
+            output_merkle_hasher: hasher,
+            output_count: outputs.len(),
+        })
+    }
+}
+
+/// The monerod responses [`MoneroRpcClient::fetch_block`] reads for a
+/// recorded block, held in memory so parsing them can be timed without the
+/// disk
+#[derive(Debug, Clone)]
+pub struct RecordedResponses {
+    pub height: u64,
+    get_block: Vec<u8>,
+    /// Absent for a block without transactions, which needs no call
+    get_transactions: Option<Vec<u8>>,
+}
+
+impl RecordedResponses {
+    /// Read the responses for block `height` from the fixture directory `dir`
+    pub fn load(dir: &Path, height: u64) -> Result<Self> {
+        let read = |method: &str, path: &str, body: &[u8]| {
+            let file = dir.join(Fixtures::file_name(method, path, body));
+            fs::read(&file).with_context(|| format!("Failed to read {}", file.display()))
+        };
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method: "get_block",
+            params: serde_json::json!({ "height": height }),
+        };
+        let get_block = read("get_block", "/json_rpc", &serde_json::to_vec(&request)?)?;
+
+        let block = parse_get_block(&get_block)?.decode()?;
+        let tx_hashes: Vec<String> = block.tx_hashes.iter().map(hex::encode).collect();
+        let get_transactions = if tx_hashes.is_empty() {
+            None
+        } else {
+            let request = GetTransactionsRequest {
+                txs_hashes: tx_hashes,
+                decode_as_json: false,
+            };
+            Some(read(
+                "get_transactions",
+                "/get_transactions",
+                &serde_json::to_vec(&request)?,
+            )?)
+        };
+
+        Ok(Self {
+            height,
+            get_block,
+            get_transactions,
+        })
+    }
+
+    /// Number of transactions in the block, not counting the miner tx
+    pub fn tx_count(&self) -> Result<usize> {
+        Ok(parse_get_block(&self.get_block)?.decode()?.tx_hashes.len())
+    }
+
+    /// Parse the responses into the block `fetch_block` returns for them
+    pub fn parse(&self) -> Result<FetchedBlock> {
+        let block_data = parse_get_block(&self.get_block)?;
+        let block = block_data.decode()?;
+        let transactions = match &self.get_transactions {
+            Some(body) => {
+                let response: GetTransactionsResponse = serde_json::from_slice(body)?;
+                anyhow::ensure!(
+                    response.status == "OK",
+                    "Recorded get_transactions status {}",
+                    response.status
+                );
+                response.txs.unwrap_or_default()
+            }
+            None => Vec::new(),
+        };
+        FetchedBlock::from_rpc(block_data.block_header, block, transactions)
+    }
+}
+
+fn parse_get_block(body: &[u8]) -> Result<GetBlockResponse> {
+    let response: JsonRpcResponse<GetBlockResponse> = serde_json::from_slice(body)?;
+    response
+        .result
+        .context("Recorded get_block response has no result")
+}
+
+/// Record everything needed to process block `height` into `dir`, along
+/// with the roots computed from it in [`EXPECTED_FILE`]. The node's info and
+/// tip are recorded too, so `post-block` can replay the directory.
+pub async fn record_block(
+    client: MoneroRpcClient,
+    height: u64,
+    tx_hasher: MerkleHasher,
+    hasher: MerkleHasher,
+    dir: &Path,
+) -> Result<RecordedBlock> {
+    let client = client.with_fixtures(Fixtures::Record(dir.to_path_buf()));
+    client.get_info().await?;
+    client.get_last_block_header().await?;
+    let block = RecordedBlock::compute(&client, height, tx_hasher, hasher).await?;
+    write_expected(dir, &block)?;
+    Ok(block)
+}
+
+fn write_expected(dir: &Path, block: &RecordedBlock) -> Result<()> {
+    let file = dir.join(EXPECTED_FILE);
+    fs::write(&file, serde_json::to_string_pretty(block)? + "\n")
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("monero-oracle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir = scratch_dir("fixtures");
+        let body = br#"{"jsonrpc":"2.0","id":"0","method":"get_block","params":{"height":1}}"#;
+        let name = Fixtures::file_name("get_block", "/json_rpc", body);
+        assert!(name.starts_with("get_block-") && name.ends_with(".json"));
+        assert_ne!(name, Fixtures::file_name("get_block", "/json_rpc", b"{}"));
+        assert!(Fixtures::file_name("get_blocks.bin", "/get_blocks.bin", b"").ends_with(".bin"));
+
+        let record = Fixtures::Record(dir.clone());
+        assert_eq!(record.load("get_block", "/json_rpc", body).unwrap(), None);
+        record
+            .save("get_block", "/json_rpc", body, b"{\"result\":{}}")
+            .unwrap();
+
+        let replay = Fixtures::Replay(dir.clone());
+        assert_eq!(
+            replay
+                .load("get_block", "/json_rpc", body)
+                .unwrap()
+                .as_deref(),
+            Some(&b"{\"result\":{}}"[..])
+        );
+        let missing = replay.load("get_block", "/json_rpc", b"{}").unwrap_err();
+        assert!(format!("{:#}", missing).contains("No recorded get_block response"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recorded_responses_parse_like_fetch_block() {
+        let dir = scratch_dir("responses");
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method: "get_block",
+            params: serde_json::json!({ "height": 7 }),
+        };
+        let blob = crate::monero_tx::tests::block_blob(&[]);
+        let miner_tx_hash = crate::monero_tx::decode_block(&blob).unwrap().miner_tx.hash;
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "0",
+            "result": {
+                "block_header": {
+                    "height": 7,
+                    "hash": "11".repeat(32),
+                    "major_version": 16,
+                    "minor_version": 16,
+                    "timestamp": 1_700_000_000,
+                    "prev_hash": "22".repeat(32),
+                    "nonce": 0,
+                },
+                "blob": hex::encode(&blob),
+                "json": r#"{"miner_tx":{"vout":[]},"tx_hashes":[]}"#,
+                "miner_tx_hash": hex::encode(miner_tx_hash),
+            },
+        });
+        Fixtures::Record(dir.clone())
+            .save(
+                "get_block",
+                "/json_rpc",
+                &serde_json::to_vec(&request).unwrap(),
+                &serde_json::to_vec(&response).unwrap(),
+            )
+            .unwrap();
+
+        let recorded = RecordedResponses::load(&dir, 7).unwrap();
+        assert_eq!(recorded.tx_count().unwrap(), 0);
+        let parsed = recorded.parse().unwrap();
+        let fetched = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+            .with_fixtures(Fixtures::Replay(dir.clone()))
+            .fetch_block(7)
+            .await
+            .unwrap();
+        assert_eq!(parsed.header.hash, fetched.header.hash);
+        assert_eq!(parsed.miner_tx_hash(), fetched.miner_tx_hash());
+        assert_eq!(parsed.miner_tx_hash(), hex::encode(miner_tx_hash));
+        assert_eq!(
+            parsed.transactions[0].1.vout.as_ref().map(Vec::len),
+            Some(1)
+        );
+        assert!(parsed.tx_hashes.is_empty());
+
+        assert!(RecordedResponses::load(&dir, 8).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_never_reaches_the_network() {
+        let dir = scratch_dir("replay");
+        let client = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+            .with_fixtures(Fixtures::Replay(dir));
+        let error = client.get_block(1).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("No recorded get_block response"));
+    }
+}
@@ -0,0 +1,367 @@
+//! Fixture blocks written without a node, for development only
+//!
+//! Built with the `synthetic-fixtures` feature (`record-fixture --synthetic`)
+//! and in the crate's own tests, never into the production binary.
+//! [`synthesize_block`] makes a block up, [`synthesize_chain`] a run of
+//! them; [`write_block`] writes the
+//! responses monerod would serve for given blobs, which is also how the
+//! mainnet genesis block in `tests/fixtures/mainnet-0` was written from the
+//! blob in monerod's source.
+
+use super::{write_expected, Fixtures, RecordedBlock};
+use crate::{
+    merkle::{compute_block_id, monero_tree_hash, write_varint, MerkleHasher},
+    monero_rpc::{BlockHeader, GetTransactionsRequest, JsonRpcRequest, MoneroRpcClient},
+    monero_tx::{
+        self, RCT_TYPE_BULLETPROOF_PLUS, RCT_TYPE_NULL, TXIN_GEN, TXIN_TO_KEY, TXOUT_TO_TAGGED_KEY,
+    },
+};
+use alloy::primitives::{keccak256, B256};
+use anyhow::Result;
+use std::path::Path;
+
+/// Ring size of synthetic transactions, as on mainnet since v15
+const SYNTHETIC_RING_SIZE: u64 = 16;
+
+/// Like [`record_block`](super::record_block), for a made-up block `height`
+/// with `tx_count` transactions besides the miner tx. The blobs decode and
+/// hash like real ones, and the header hash covers them, so the block goes
+/// through every check a recorded one does; its keys and proofs are random
+/// bytes derived from the height, so the same block is written every time.
+pub async fn synthesize_block(
+    height: u64,
+    tx_count: usize,
+    tx_hasher: MerkleHasher,
+    hasher: MerkleHasher,
+    dir: &Path,
+) -> Result<RecordedBlock> {
+    let prev_hash = random(height, "prev_id", 0);
+    synthesize(height, prev_hash, tx_count, tx_hasher, hasher, dir).await
+}
+
+/// Like [`synthesize_block`] for blocks `from..=to` in one directory, each
+/// the parent of the next, as a node serves a stretch of its chain. The
+/// directory's tip and node info are the last block's.
+pub async fn synthesize_chain(
+    from: u64,
+    to: u64,
+    tx_count: usize,
+    tx_hasher: MerkleHasher,
+    hasher: MerkleHasher,
+    dir: &Path,
+) -> Result<Vec<RecordedBlock>> {
+    let mut prev_hash = random(from, "prev_id", 0);
+    let mut blocks = Vec::new();
+    for height in from..=to {
+        let block = synthesize(height, prev_hash, tx_count, tx_hasher, hasher, dir).await?;
+        prev_hash = block.block_hash;
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+/// Bytes for `label` (the `index`th of them) in made-up block `height`
+fn random(height: u64, label: &str, index: u64) -> B256 {
+    let mut data = format!("synthetic {} {}", height, label).into_bytes();
+    data.extend_from_slice(&index.to_le_bytes());
+    keccak256(data)
+}
+
+async fn synthesize(
+    height: u64,
+    prev_hash: B256,
+    tx_count: usize,
+    tx_hasher: MerkleHasher,
+    hasher: MerkleHasher,
+    dir: &Path,
+) -> Result<RecordedBlock> {
+    let random = |label: &str, index: u64| random(height, label, index);
+
+    let miner_tx = synthetic_miner_tx(height, random("miner tx", 0));
+    let txs: Vec<Vec<u8>> = (0..tx_count as u64)
+        .map(|index| synthetic_tx(|label, n| random(label, index * 16 + n)))
+        .collect();
+    let header = BlockHeader {
+        height,
+        hash: String::new(),
+        major_version: 16,
+        minor_version: 16,
+        timestamp: 1_700_000_000 + height * 120,
+        prev_hash: hex::encode(prev_hash),
+        nonce: 0,
+        difficulty: 300_000_000_000,
+        wide_difficulty: None,
+        cumulative_difficulty: 300_000_000_000 * (height + 1),
+        wide_cumulative_difficulty: None,
+    };
+    write_block(header, &miner_tx, &txs, tx_hasher, hasher, dir).await
+}
+
+/// Write block `header` with `miner_tx` and `txs` (blobs) into `dir` as
+/// monerod would have served it, the node's info and tip included. The
+/// header's hash is computed from the blobs, not taken from `header`. It
+/// then replays the files like `tests/replay.rs` does to compute
+/// `expected.json`.
+pub async fn write_block(
+    mut header: BlockHeader,
+    miner_tx: &[u8],
+    txs: &[Vec<u8>],
+    tx_hasher: MerkleHasher,
+    hasher: MerkleHasher,
+    dir: &Path,
+) -> Result<RecordedBlock> {
+    let height = header.height;
+    let miner_tx_hash = monero_tx::decode(miner_tx)?.hash;
+    let tx_hashes = txs
+        .iter()
+        .map(|tx| Ok(monero_tx::decode(tx)?.hash))
+        .collect::<Result<Vec<B256>>>()?;
+
+    let mut leaves = vec![miner_tx_hash.0];
+    leaves.extend(tx_hashes.iter().map(|hash| hash.0));
+    header.hash = hex::encode(compute_block_id(
+        &header,
+        &monero_tree_hash(&leaves),
+        leaves.len() as u64,
+    )?);
+
+    let mut blob = Vec::new();
+    write_varint(&mut blob, header.major_version.into());
+    write_varint(&mut blob, header.minor_version.into());
+    write_varint(&mut blob, header.timestamp);
+    blob.extend_from_slice(&hex::decode(&header.prev_hash)?);
+    blob.extend_from_slice(&header.nonce.to_le_bytes());
+    blob.extend_from_slice(miner_tx);
+    write_varint(&mut blob, tx_hashes.len() as u64);
+    for hash in &tx_hashes {
+        blob.extend_from_slice(hash.as_slice());
+    }
+
+    let tx_hashes: Vec<String> = tx_hashes.iter().map(hex::encode).collect();
+    let header_json = serde_json::json!({
+        "height": header.height,
+        "hash": header.hash,
+        "major_version": header.major_version,
+        "minor_version": header.minor_version,
+        "timestamp": header.timestamp,
+        "prev_hash": header.prev_hash,
+        "nonce": header.nonce,
+        "difficulty": header.difficulty,
+        "cumulative_difficulty": header.cumulative_difficulty,
+        "num_txes": tx_hashes.len(),
+        "miner_tx_hash": hex::encode(miner_tx_hash),
+        "depth": 0,
+        "orphan_status": false,
+    });
+
+    let fixtures = Fixtures::Record(dir.to_path_buf());
+    let json_rpc = |method: &'static str, params, result| -> Result<()> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method,
+            params,
+        };
+        let response = serde_json::json!({ "jsonrpc": "2.0", "id": "0", "result": result });
+        fixtures.save(
+            method,
+            "/json_rpc",
+            &serde_json::to_vec(&request)?,
+            &serde_json::to_vec_pretty(&response)?,
+        )
+    };
+    json_rpc(
+        "get_info",
+        serde_json::json!({}),
+        serde_json::json!({
+            "height": height + 1,
+            "top_block_hash": header.hash,
+            "nettype": "mainnet",
+            "mainnet": true,
+            "testnet": false,
+            "stagenet": false,
+            "restricted": false,
+            "status": "OK",
+        }),
+    )?;
+    json_rpc(
+        "get_last_block_header",
+        serde_json::json!({}),
+        serde_json::json!({ "block_header": header_json, "status": "OK" }),
+    )?;
+    json_rpc(
+        "get_block",
+        serde_json::json!({ "height": height }),
+        serde_json::json!({
+            "block_header": header_json,
+            "blob": hex::encode(&blob),
+            "json": serde_json::json!({
+                "major_version": header.major_version,
+                "minor_version": header.minor_version,
+                "timestamp": header.timestamp,
+                "prev_id": header.prev_hash,
+                "nonce": header.nonce,
+                "tx_hashes": tx_hashes,
+            })
+            .to_string(),
+            "miner_tx_hash": hex::encode(miner_tx_hash),
+            "status": "OK",
+        }),
+    )?;
+    if !txs.is_empty() {
+        let request = GetTransactionsRequest {
+            txs_hashes: tx_hashes.clone(),
+            decode_as_json: false,
+        };
+        let entries: Vec<_> = tx_hashes
+            .iter()
+            .zip(txs)
+            .map(|(tx_hash, tx)| {
+                serde_json::json!({
+                    "tx_hash": tx_hash,
+                    "as_hex": hex::encode(tx),
+                    "block_height": height,
+                    "block_timestamp": header.timestamp,
+                    "in_pool": false,
+                })
+            })
+            .collect();
+        fixtures.save(
+            "get_transactions",
+            "/get_transactions",
+            &serde_json::to_vec(&request)?,
+            &serde_json::to_vec_pretty(&serde_json::json!({ "status": "OK", "txs": entries }))?,
+        )?;
+    }
+
+    // Nothing listens there: every response has to come from the files
+    let client = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+        .with_fixtures(Fixtures::Replay(dir.to_path_buf()));
+    let block = RecordedBlock::compute(&client, height, tx_hasher, hasher).await?;
+    write_expected(dir, &block)?;
+    Ok(block)
+}
+
+/// A v2 miner transaction paying the block reward to one tagged key
+fn synthetic_miner_tx(height: u64, key: B256) -> Vec<u8> {
+    let mut tx = Vec::new();
+    write_varint(&mut tx, 2); // version
+    write_varint(&mut tx, height + 60); // unlock_time
+    write_varint(&mut tx, 1);
+    tx.push(TXIN_GEN);
+    write_varint(&mut tx, height);
+    write_varint(&mut tx, 1);
+    write_varint(&mut tx, 600_000_000_000);
+    tx.push(TXOUT_TO_TAGGED_KEY);
+    tx.extend_from_slice(key.as_slice());
+    tx.push(key[0]); // view tag
+    write_extra(&mut tx, keccak256(key));
+    tx.push(RCT_TYPE_NULL);
+    tx
+}
+
+/// A one-input, two-output Bulletproof+ transaction. `random(label, n)`
+/// supplies its keys, amounts and commitments, and stands in for its
+/// proofs, which are hashed but never parsed.
+fn synthetic_tx(random: impl Fn(&str, u64) -> B256) -> Vec<u8> {
+    let mut tx = Vec::new();
+    write_varint(&mut tx, 2); // version
+    write_varint(&mut tx, 0); // unlock_time
+    write_varint(&mut tx, 1);
+    tx.push(TXIN_TO_KEY);
+    write_varint(&mut tx, 0); // amount
+    write_varint(&mut tx, SYNTHETIC_RING_SIZE);
+    for offset in 0..SYNTHETIC_RING_SIZE {
+        write_varint(&mut tx, 1 + offset);
+    }
+    tx.extend_from_slice(random("key image", 0).as_slice());
+    write_varint(&mut tx, 2);
+    for output in 0..2 {
+        write_varint(&mut tx, 0); // amount
+        tx.push(TXOUT_TO_TAGGED_KEY);
+        let key = random("output key", output);
+        tx.extend_from_slice(key.as_slice());
+        tx.push(key[0]); // view tag
+    }
+    write_extra(&mut tx, random("tx key", 0));
+
+    tx.push(RCT_TYPE_BULLETPROOF_PLUS);
+    write_varint(&mut tx, 30_000_000); // fee
+    for output in 0..2 {
+        tx.extend_from_slice(&random("amount", output)[..8]);
+    }
+    for output in 0..2 {
+        tx.extend_from_slice(random("commitment", output).as_slice());
+    }
+    for part in 0..8 {
+        tx.extend_from_slice(random("proofs", part).as_slice());
+    }
+    tx
+}
+
+/// `tx_extra` holding just the tx public key
+fn write_extra(tx: &mut Vec<u8>, tx_public_key: B256) {
+    write_varint(tx, 33);
+    tx.push(0x01);
+    tx.extend_from_slice(tx_public_key.as_slice());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::RecordedResponses;
+    use std::{fs, path::PathBuf};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("monero-oracle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_blocks_replay() {
+        let dir = scratch_dir("synthetic");
+        let block = synthesize_block(40, 3, MerkleHasher::Keccak256, MerkleHasher::V2, &dir)
+            .await
+            .unwrap();
+        // The miner output and two per transaction
+        assert_eq!(block.output_count, 7);
+
+        let recorded = RecordedResponses::load(&dir, 40).unwrap();
+        assert_eq!(recorded.tx_count().unwrap(), 3);
+        let parsed = recorded.parse().unwrap();
+        assert_eq!(parsed.header.hash, hex::encode(block.block_hash));
+        let client = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+            .with_fixtures(Fixtures::Replay(dir.clone()));
+        assert!(!client.get_info().await.unwrap().restricted);
+        assert_eq!(client.get_last_block_header().await.unwrap().height, 40);
+
+        // Derived from the height alone
+        let again = scratch_dir("synthetic-again");
+        let second = synthesize_block(40, 3, MerkleHasher::Keccak256, MerkleHasher::V2, &again)
+            .await
+            .unwrap();
+        assert_eq!(second, block);
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(again).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_chain_links_blocks() {
+        let dir = scratch_dir("synthetic-chain");
+        let blocks = synthesize_chain(40, 42, 1, MerkleHasher::Keccak256, MerkleHasher::V2, &dir)
+            .await
+            .unwrap();
+        assert_eq!(blocks.len(), 3);
+
+        let client = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+            .with_fixtures(Fixtures::Replay(dir.clone()));
+        for pair in blocks.windows(2) {
+            let header = client.get_block(pair[1].height).await.unwrap().block_header;
+            assert_eq!(header.prev_hash, hex::encode(pair[0].block_hash));
+        }
+        assert_eq!(client.get_last_block_header().await.unwrap().height, 42);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! Fork choice across the monerod pool
+//!
+//! With fallback nodes configured (`MONERO_RPC_FALLBACK_URLS`) the oracle asks
+//! every node for its tip at the start of each poll and follows the one whose
+//! chain has the most cumulative difficulty, the same rule monerod uses to
+//! pick between forks. A node that is lagging, stuck on a minority fork or
+//! unreachable is passed over instead of being trusted because it answered
+//! first.
+//!
+//! With `MONERO_QUORUM` set, a block is also only processed once that many
+//! nodes report the same hash for its height, so a single compromised public
+//! node can't get a block posted on its own.
+
+/// A node's view of the chain tip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// Position in the pool, the primary node first
+    pub node: usize,
+    pub height: u64,
+    pub cumulative_difficulty: u128,
+}
+
+/// The candidate on the heaviest chain. Ties keep the node currently
+/// followed, then prefer the earlier node, so equal nodes don't flap.
+pub fn heaviest(candidates: &[Candidate], current: usize) -> Option<&Candidate> {
+    candidates.iter().max_by_key(|candidate| {
+        (
+            candidate.cumulative_difficulty,
+            candidate.node == current,
+            std::cmp::Reverse(candidate.node),
+        )
+    })
+}
+
+/// Nodes whose reported hash (`None` for unreachable ones) equals `hash`
+pub fn agreeing<T: PartialEq>(reported: &[Option<T>], hash: &T) -> usize {
+    reported
+        .iter()
+        .filter(|reported| reported.as_ref() == Some(hash))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(node: usize, cumulative_difficulty: u128) -> Candidate {
+        Candidate {
+            node,
+            height: 3_100_000,
+            cumulative_difficulty,
+        }
+    }
+
+    #[test]
+    fn test_heaviest_chain_wins() {
+        let candidates = [candidate(0, 100), candidate(1, 250), candidate(2, 200)];
+        assert_eq!(heaviest(&candidates, 0).unwrap().node, 1);
+        assert!(heaviest(&[], 0).is_none());
+    }
+
+    #[test]
+    fn test_ties_keep_current_node() {
+        let candidates = [candidate(0, 100), candidate(1, 100), candidate(2, 100)];
+        assert_eq!(heaviest(&candidates, 2).unwrap().node, 2);
+        // Without the current node among them, the primary is preferred
+        assert_eq!(heaviest(&candidates[..2], 2).unwrap().node, 0);
+    }
+
+    #[test]
+    fn test_agreeing() {
+        let reported = [Some(1), None, Some(1), Some(2)];
+        assert_eq!(agreeing(&reported, &1), 2);
+        assert_eq!(agreeing(&reported, &2), 1);
+        assert_eq!(agreeing(&reported, &3), 0);
+    }
+}
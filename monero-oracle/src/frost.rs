@@ -0,0 +1,1304 @@
+//! FROST threshold signing of block attestations
+//!
+//! In multi-signature mode the contract checks one ECDSA signature per
+//! signer. With a threshold key it checks a single Schnorr signature by a
+//! secp256k1 key that no machine holds: the N operators generate it together
+//! ([`DkgState`], `frost-dkg-*`), each ending up with a share, any T of which
+//! sign together, and the contract stores only the group public key
+//! (`setThresholdKey`). Posting costs the same gas however many operators
+//! sign.
+//!
+//! Signing follows the two rounds of FROST (Komlo and Goldberg, RFC 9591):
+//!
+//! 1. The submitter, as coordinator, asks participants for commitments to a
+//!    pair of fresh nonces for the block (`POST /frost/commit`) until it has
+//!    T, counting its own.
+//! 2. It sends them all the commitments and the attestation digest
+//!    (`POST /frost/sign`). Each participant signs only if the digest is
+//!    that of the block data it computed itself, and returns its signature
+//!    share, using up its nonces.
+//!
+//! The coordinator checks every share against the participant's public key
+//! share, so a bad one is named rather than just failing on-chain, and adds
+//! them up. The challenge is the keccak256 hash the contract computes, which
+//! lets it verify the signature with the `ecrecover` precompile; signatures
+//! don't verify under the RFC 9591 ciphersuites. Participants authenticate
+//! the coordinator with the shared `FROST_API_KEY`.
+//!
+//! Monero withdrawals are signed with Monero's own multisig (see
+//! [`crate::multisig`]): CLSAG ring signatures can't be made by FROST.
+
+use crate::{admin::BearerAuth, attestation::Attestation, multisig::peer_response};
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    sol_types::Eip712Domain,
+};
+use anyhow::{Context, Result};
+use k256::{
+    elliptic_curve::{
+        ops::Reduce,
+        sec1::{FromEncodedPoint, ToEncodedPoint},
+        Field, PrimeField,
+    },
+    AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar,
+};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// Time allowed for a participant to answer
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Blocks a participant keeps nonces for at once; the oldest are dropped
+const MAX_OPEN_ROUNDS: usize = 64;
+
+/// `FROST_KEY_SHARE`, `FROST_API_KEY` and `FROST_PEERS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrostConfig {
+    /// This operator's key share, written by `frost-dkg-finish`
+    pub key_share: PathBuf,
+    /// Shared by every operator; authenticates the `/frost` endpoints
+    pub api_key: String,
+    /// Participants' HTTP APIs; empty on a participant
+    pub peers: Vec<String>,
+}
+
+/// Participant number, from 1
+pub type Identifier = u16;
+
+/// A curve point, serialized as compressed SEC1 hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point(pub ProjectivePoint);
+
+impl Serialize for Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let encoded = self.0.to_affine().to_encoded_point(true);
+        serializer.serialize_str(&hex::encode(encoded.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let point = hex::decode(&text)
+            .ok()
+            .and_then(|bytes| EncodedPoint::from_bytes(bytes).ok())
+            .and_then(|encoded| {
+                Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded))
+            })
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid curve point {}", text)))?;
+        Ok(Self(ProjectivePoint::from(point)))
+    }
+}
+
+/// Scalars serialized as 32-byte big-endian hex
+mod scalar_hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        scalar: &Scalar,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(scalar.to_bytes()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Scalar, D::Error> {
+        parse(&String::deserialize(deserializer)?)
+    }
+
+    fn parse<E: serde::de::Error>(text: &str) -> std::result::Result<Scalar, E> {
+        hex::decode(text)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .and_then(|bytes| Option::from(Scalar::from_repr(FieldBytes::from(bytes))))
+            .ok_or_else(|| E::custom(format!("invalid scalar {}", text)))
+    }
+
+    /// A list of scalars, each as above
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            scalars: &[Scalar],
+            serializer: S,
+        ) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_seq(scalars.iter().map(|scalar| hex::encode(scalar.to_bytes())))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> std::result::Result<Vec<Scalar>, D::Error> {
+            Vec::<String>::deserialize(deserializer)?
+                .iter()
+                .map(|text| parse(text))
+                .collect()
+        }
+    }
+}
+
+/// Public part of a split key, the same in every share
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupKey {
+    /// Shares needed to sign
+    pub threshold: u16,
+    pub public_key: Point,
+    /// Each participant's public key share, for checking its signature shares
+    pub verifying_shares: BTreeMap<Identifier, Point>,
+}
+
+impl GroupKey {
+    /// The key as `setThresholdKey` takes it
+    pub fn contract_key(&self) -> Result<ContractKey> {
+        ContractKey::new(&self.public_key.0)
+    }
+}
+
+/// One operator's share of the key, as written by `frost-dkg-finish`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyShare {
+    pub identifier: Identifier,
+    #[serde(with = "scalar_hex")]
+    signing_share: Scalar,
+    pub group: GroupKey,
+}
+
+impl KeyShare {
+    pub fn read(path: &Path) -> Result<Self> {
+        let share: Self = read_json(path, "FROST key share")?;
+        anyhow::ensure!(
+            share.group.verifying_shares.get(&share.identifier)
+                == Some(&Point(ProjectivePoint::GENERATOR * share.signing_share)),
+            "{} doesn't match its own public key share",
+            path.display()
+        );
+        Ok(share)
+    }
+
+    /// Write the share readable by its owner only
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_private(path, self, false)
+    }
+}
+
+/// Group key as the contract stores it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractKey {
+    /// x coordinate
+    pub x: U256,
+    /// 0 if y is even, 1 if odd
+    pub parity: u8,
+}
+
+impl ContractKey {
+    pub fn new(point: &ProjectivePoint) -> Result<Self> {
+        let encoded = point.to_affine().to_encoded_point(true);
+        let x = encoded.x().context("Group key is the point at infinity")?;
+        // ecrecover takes the x coordinate as its r, which must be below the
+        // group order
+        anyhow::ensure!(
+            bool::from(Scalar::from_repr(*x).is_some()),
+            "Group key's x coordinate is not below the curve order"
+        );
+        Ok(Self {
+            x: U256::from_be_slice(x),
+            parity: encoded.as_bytes()[0] - 2,
+        })
+    }
+}
+
+/// Distributed key generation (Pedersen's, with the proofs of knowledge of
+/// FROST's KeyGen): each operator deals a random polynomial of degree T−1
+/// and the group key is the sum of their constant terms, so no one ever
+/// holds it. One [`DkgState`] per operator, kept on its own machine between
+/// the three steps:
+///
+/// 1. [`DkgState::start`] commits to the polynomial and proves knowledge of
+///    its constant term; the [`Round1Package`] goes to every other operator.
+/// 2. [`DkgState::shares`] checks everyone's round 1 and evaluates the
+///    polynomial at each other operator; each [`Round2Package`] is secret
+///    and goes to its recipient only.
+/// 3. [`DkgState::finish`] checks the shares received against their
+///    senders' commitments and adds them up into the operator's [`KeyShare`].
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DkgState {
+    pub identifier: Identifier,
+    pub threshold: u16,
+    pub participants: u16,
+    /// This operator's polynomial, lowest degree first
+    #[serde(with = "scalar_hex::vec")]
+    coefficients: Vec<Scalar>,
+    /// Every operator's commitments, once step 2 has checked them
+    #[serde(default)]
+    commitments: BTreeMap<Identifier, Vec<Point>>,
+}
+
+/// Step 1 of the DKG, sent to every other operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Round1Package {
+    pub identifier: Identifier,
+    pub threshold: u16,
+    pub participants: u16,
+    /// The sender's polynomial coefficients times G, lowest degree first
+    pub commitments: Vec<Point>,
+    /// Schnorr proof of knowledge of the constant term: R, and z = k + a₀·c
+    pub proof_nonce: Point,
+    #[serde(with = "scalar_hex")]
+    pub proof: Scalar,
+}
+
+/// Step 2 of the DKG: `from`'s polynomial at `to`, for `to`'s eyes only
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Round2Package {
+    pub from: Identifier,
+    pub to: Identifier,
+    #[serde(with = "scalar_hex")]
+    share: Scalar,
+}
+
+impl DkgState {
+    /// Deal this operator's polynomial for a `threshold`-of-`participants`
+    /// key
+    pub fn start(
+        identifier: Identifier,
+        threshold: u16,
+        participants: u16,
+    ) -> Result<(Self, Round1Package)> {
+        anyhow::ensure!(
+            threshold >= 2 && threshold <= participants,
+            "Threshold must be at least 2 and at most the number of participants"
+        );
+        anyhow::ensure!(
+            (1..=participants).contains(&identifier),
+            "Identifier must be from 1 to the number of participants"
+        );
+        let coefficients: Vec<Scalar> =
+            (0..threshold).map(|_| Scalar::random(&mut OsRng)).collect();
+        let commitments: Vec<Point> = coefficients
+            .iter()
+            .map(|coefficient| Point(ProjectivePoint::GENERATOR * coefficient))
+            .collect();
+        let nonce = Scalar::random(&mut OsRng);
+        let proof_nonce = ProjectivePoint::GENERATOR * nonce;
+        let c = dkg_challenge(
+            identifier,
+            threshold,
+            participants,
+            &commitments[0].0,
+            &proof_nonce,
+        );
+        let package = Round1Package {
+            identifier,
+            threshold,
+            participants,
+            commitments,
+            proof_nonce: Point(proof_nonce),
+            proof: nonce + coefficients[0] * c,
+        };
+        let state = Self {
+            identifier,
+            threshold,
+            participants,
+            coefficients,
+            commitments: BTreeMap::new(),
+        };
+        Ok((state, package))
+    }
+
+    /// Check the other operators' round 1 packages, one from each, and make
+    /// the shares to send them
+    pub fn shares(&mut self, round1: &[Round1Package]) -> Result<Vec<Round2Package>> {
+        let mut commitments = BTreeMap::new();
+        commitments.insert(
+            self.identifier,
+            self.coefficients
+                .iter()
+                .map(|coefficient| Point(ProjectivePoint::GENERATOR * coefficient))
+                .collect::<Vec<_>>(),
+        );
+        for package in round1 {
+            if package.identifier == self.identifier {
+                continue;
+            }
+            anyhow::ensure!(
+                package.threshold == self.threshold && package.participants == self.participants,
+                "Participant {} is making a {}-of-{} key, not {}-of-{}",
+                package.identifier,
+                package.threshold,
+                package.participants,
+                self.threshold,
+                self.participants
+            );
+            anyhow::ensure!(
+                (1..=self.participants).contains(&package.identifier),
+                "Unknown participant {}",
+                package.identifier
+            );
+            anyhow::ensure!(
+                package.commitments.len() == self.threshold as usize,
+                "Participant {} committed to {} coefficients, not {}",
+                package.identifier,
+                package.commitments.len(),
+                self.threshold
+            );
+            let c = dkg_challenge(
+                package.identifier,
+                package.threshold,
+                package.participants,
+                &package.commitments[0].0,
+                &package.proof_nonce.0,
+            );
+            anyhow::ensure!(
+                ProjectivePoint::GENERATOR * package.proof
+                    == package.proof_nonce.0 + package.commitments[0].0 * c,
+                "Participant {}'s proof of knowledge doesn't verify",
+                package.identifier
+            );
+            anyhow::ensure!(
+                commitments
+                    .insert(package.identifier, package.commitments.clone())
+                    .is_none(),
+                "Two round 1 packages from participant {}",
+                package.identifier
+            );
+        }
+        let missing: Vec<Identifier> = (1..=self.participants)
+            .filter(|identifier| !commitments.contains_key(identifier))
+            .collect();
+        anyhow::ensure!(
+            missing.is_empty(),
+            "No round 1 package from participants {:?}",
+            missing
+        );
+        // Shares already sent were made for these commitments
+        anyhow::ensure!(
+            self.commitments.is_empty() || self.commitments == commitments,
+            "Round 1 packages differ from those the shares were made for"
+        );
+        self.commitments = commitments;
+
+        Ok((1..=self.participants)
+            .filter(|&to| to != self.identifier)
+            .map(|to| Round2Package {
+                from: self.identifier,
+                to,
+                share: evaluate(&self.coefficients, to),
+            })
+            .collect())
+    }
+
+    /// This operator's share of the key from the others' round 2 packages,
+    /// one from each
+    pub fn finish(&self, round2: &[Round2Package]) -> Result<KeyShare> {
+        anyhow::ensure!(
+            !self.commitments.is_empty(),
+            "Make the shares for the other participants first"
+        );
+        let mut received = BTreeMap::new();
+        for package in round2 {
+            anyhow::ensure!(
+                package.to == self.identifier,
+                "Share from participant {} is for participant {}",
+                package.from,
+                package.to
+            );
+            let commitments = self
+                .commitments
+                .get(&package.from)
+                .filter(|_| package.from != self.identifier)
+                .with_context(|| format!("Unknown participant {}", package.from))?;
+            anyhow::ensure!(
+                ProjectivePoint::GENERATOR * package.share
+                    == evaluate_commitments(commitments, self.identifier),
+                "Participant {}'s share doesn't match its commitments",
+                package.from
+            );
+            anyhow::ensure!(
+                received.insert(package.from, package.share).is_none(),
+                "Two shares from participant {}",
+                package.from
+            );
+        }
+        let missing: Vec<Identifier> = self
+            .commitments
+            .keys()
+            .copied()
+            .filter(|&from| from != self.identifier && !received.contains_key(&from))
+            .collect();
+        anyhow::ensure!(
+            missing.is_empty(),
+            "No share from participants {:?}",
+            missing
+        );
+
+        let signing_share = received.values().fold(
+            evaluate(&self.coefficients, self.identifier),
+            |sum, share| sum + share,
+        );
+        let public_key = self
+            .commitments
+            .values()
+            .fold(ProjectivePoint::IDENTITY, |sum, commitments| {
+                sum + commitments[0].0
+            });
+        // Rare, and the same for every operator: start again rather than
+        // make a key the contract can't use
+        ContractKey::new(&public_key).context("Run the DKG again")?;
+        let group = GroupKey {
+            threshold: self.threshold,
+            public_key: Point(public_key),
+            verifying_shares: (1..=self.participants)
+                .map(|identifier| {
+                    let share = self
+                        .commitments
+                        .values()
+                        .fold(ProjectivePoint::IDENTITY, |sum, commitments| {
+                            sum + evaluate_commitments(commitments, identifier)
+                        });
+                    (identifier, Point(share))
+                })
+                .collect(),
+        };
+        Ok(KeyShare {
+            identifier: self.identifier,
+            signing_share,
+            group,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        read_json(path, "FROST DKG state")
+    }
+
+    /// Write the state readable by its owner only, replacing the previous one
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_private(path, self, true)
+    }
+}
+
+impl Round1Package {
+    pub fn read(path: &Path) -> Result<Self> {
+        read_json(path, "FROST DKG round 1 package")
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_private(path, self, false)
+    }
+}
+
+impl Round2Package {
+    pub fn read(path: &Path) -> Result<Self> {
+        read_json(path, "FROST DKG share")
+    }
+
+    /// Write the share readable by its owner only
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_private(path, self, false)
+    }
+}
+
+/// sha256 of the proof of knowledge's statement, reduced mod n: binds it to
+/// the sender and the key's parameters, so it can't be replayed as another
+/// participant's
+fn dkg_challenge(
+    identifier: Identifier,
+    threshold: u16,
+    participants: u16,
+    commitment: &ProjectivePoint,
+    nonce: &ProjectivePoint,
+) -> Scalar {
+    let hash = Sha256::new()
+        .chain_update(b"monero-oracle FROST DKG")
+        .chain_update(identifier.to_be_bytes())
+        .chain_update(threshold.to_be_bytes())
+        .chain_update(participants.to_be_bytes())
+        .chain_update(commitment.to_affine().to_encoded_point(true).as_bytes())
+        .chain_update(nonce.to_affine().to_encoded_point(true).as_bytes())
+        .finalize();
+    <Scalar as Reduce<k256::U256>>::reduce_bytes(&hash)
+}
+
+/// The polynomial at `identifier`
+fn evaluate(coefficients: &[Scalar], identifier: Identifier) -> Scalar {
+    let x = Scalar::from(identifier as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+/// The polynomial at `identifier` times G, from its commitments
+fn evaluate_commitments(commitments: &[Point], identifier: Identifier) -> ProjectivePoint {
+    let x = Scalar::from(identifier as u64);
+    commitments
+        .iter()
+        .rev()
+        .fold(ProjectivePoint::IDENTITY, |acc, commitment| {
+            acc * x + commitment.0
+        })
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path, what: &str) -> Result<T> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("{} is not a {}", path.display(), what))
+}
+
+/// Write `value` as JSON readable by its owner only; an existing file is
+/// kept unless `replace`
+fn write_private<T: Serialize>(path: &Path, value: &T, replace: bool) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    if replace {
+        options.write(true).create(true).truncate(true);
+    } else {
+        options.write(true).create_new(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(serde_json::to_string_pretty(value)?.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A participant's secret nonces for one block; used at most once
+struct SigningNonces {
+    hiding: Scalar,
+    binding: Scalar,
+}
+
+/// Round 1: a participant's commitment to its nonces
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Commitment {
+    pub identifier: Identifier,
+    pub hiding: Point,
+    pub binding: Point,
+}
+
+/// Body of `POST /frost/commit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitRequest {
+    pub height: u64,
+}
+
+/// Round 2: what every participant signs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningPackage {
+    pub height: u64,
+    /// EIP-712 digest of the block's `MoneroBlock` attestation
+    pub message: B256,
+    /// One per signer, ascending by identifier
+    pub commitments: Vec<Commitment>,
+}
+
+/// A participant's share of the signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureShare {
+    pub identifier: Identifier,
+    #[serde(with = "scalar_hex")]
+    pub share: Scalar,
+}
+
+/// Schnorr signature for `postMoneroBlockWithThresholdSignature`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Signature {
+    /// Address of the group commitment R
+    pub nonce_address: Address,
+    pub s: U256,
+}
+
+impl Signature {
+    /// The contract's check: R = s·G − c·Y
+    pub fn verify(&self, key: &ProjectivePoint, message: B256) -> Result<()> {
+        let s = Option::<Scalar>::from(Scalar::from_repr(self.s.to_be_bytes::<32>().into()))
+            .context("Signature s is not below the curve order")?;
+        let c = challenge(&ContractKey::new(key)?, message, self.nonce_address);
+        anyhow::ensure!(
+            point_address(&(ProjectivePoint::GENERATOR * s - *key * c)) == self.nonce_address,
+            "Threshold signature doesn't verify"
+        );
+        Ok(())
+    }
+}
+
+/// Ethereum address of a point, as `ecrecover` returns it
+fn point_address(point: &ProjectivePoint) -> Address {
+    let encoded = point.to_affine().to_encoded_point(false);
+    Address::from_slice(&keccak256(&encoded.as_bytes()[1..])[12..])
+}
+
+/// keccak256(x ‖ parity ‖ message ‖ R address) mod n, as the contract hashes
+/// it with `abi.encodePacked`
+fn challenge(key: &ContractKey, message: B256, nonce_address: Address) -> Scalar {
+    let mut preimage = Vec::with_capacity(32 + 1 + 32 + 20);
+    preimage.extend_from_slice(&key.x.to_be_bytes::<32>());
+    preimage.push(key.parity);
+    preimage.extend_from_slice(message.as_slice());
+    preimage.extend_from_slice(nonce_address.as_slice());
+    <Scalar as Reduce<k256::U256>>::reduce_bytes(&keccak256(preimage).0.into())
+}
+
+/// Binding factor of each signer, tying its nonces to the message and to the
+/// other signers' commitments
+fn binding_factors(group: &GroupKey, package: &SigningPackage) -> BTreeMap<Identifier, Scalar> {
+    let mut encoded = Vec::new();
+    for commitment in &package.commitments {
+        encoded.extend_from_slice(&commitment.identifier.to_be_bytes());
+        encoded.extend_from_slice(
+            commitment
+                .hiding
+                .0
+                .to_affine()
+                .to_encoded_point(true)
+                .as_bytes(),
+        );
+        encoded.extend_from_slice(
+            commitment
+                .binding
+                .0
+                .to_affine()
+                .to_encoded_point(true)
+                .as_bytes(),
+        );
+    }
+    let prefix = Sha256::new()
+        .chain_update(b"monero-oracle FROST rho")
+        .chain_update(
+            group
+                .public_key
+                .0
+                .to_affine()
+                .to_encoded_point(true)
+                .as_bytes(),
+        )
+        .chain_update(package.message)
+        .chain_update(Sha256::digest(&encoded));
+    package
+        .commitments
+        .iter()
+        .map(|commitment| {
+            let hash = prefix
+                .clone()
+                .chain_update(commitment.identifier.to_be_bytes())
+                .finalize();
+            (
+                commitment.identifier,
+                <Scalar as Reduce<k256::U256>>::reduce_bytes(&hash),
+            )
+        })
+        .collect()
+}
+
+/// Lagrange coefficient of `identifier` at zero over the package's signers
+fn lagrange(identifier: Identifier, package: &SigningPackage) -> Scalar {
+    let x = Scalar::from(identifier as u64);
+    let (numerator, denominator) = package
+        .commitments
+        .iter()
+        .map(|commitment| Scalar::from(commitment.identifier as u64))
+        .filter(|&other| other != x)
+        .fold((Scalar::ONE, Scalar::ONE), |(num, den), other| {
+            (num * other, den * (other - x))
+        });
+    numerator * denominator.invert().unwrap()
+}
+
+/// Group commitment R, and each signer's share of it
+fn group_commitment(
+    group: &GroupKey,
+    package: &SigningPackage,
+) -> (ProjectivePoint, BTreeMap<Identifier, ProjectivePoint>) {
+    let rho = binding_factors(group, package);
+    let shares: BTreeMap<Identifier, ProjectivePoint> = package
+        .commitments
+        .iter()
+        .map(|commitment| {
+            (
+                commitment.identifier,
+                commitment.hiding.0 + commitment.binding.0 * rho[&commitment.identifier],
+            )
+        })
+        .collect();
+    (shares.values().copied().sum(), shares)
+}
+
+impl GroupKey {
+    /// Check that a package names `threshold` distinct known signers, in order
+    fn check_package(&self, package: &SigningPackage) -> Result<()> {
+        anyhow::ensure!(
+            package.commitments.len() == self.threshold as usize,
+            "Signing package has {} commitments, the threshold is {}",
+            package.commitments.len(),
+            self.threshold
+        );
+        anyhow::ensure!(
+            package
+                .commitments
+                .windows(2)
+                .all(|pair| pair[0].identifier < pair[1].identifier),
+            "Signing package commitments aren't in ascending identifier order"
+        );
+        for commitment in &package.commitments {
+            anyhow::ensure!(
+                self.verifying_shares.contains_key(&commitment.identifier),
+                "Unknown participant {} in signing package",
+                commitment.identifier
+            );
+        }
+        Ok(())
+    }
+
+    /// Check one participant's signature share against its public key share
+    pub fn verify_share(&self, package: &SigningPackage, share: &SignatureShare) -> Result<()> {
+        let (commitment, shares) = group_commitment(self, package);
+        let own = shares
+            .get(&share.identifier)
+            .with_context(|| format!("Participant {} isn't in the package", share.identifier))?;
+        let c = challenge(
+            &self.contract_key()?,
+            package.message,
+            point_address(&commitment),
+        );
+        let expected = *own
+            + self.verifying_shares[&share.identifier].0
+                * (lagrange(share.identifier, package) * c);
+        anyhow::ensure!(
+            ProjectivePoint::GENERATOR * share.share == expected,
+            "Invalid signature share from participant {}",
+            share.identifier
+        );
+        Ok(())
+    }
+
+    /// Add up the signers' shares into the group's signature
+    pub fn aggregate(
+        &self,
+        package: &SigningPackage,
+        shares: &[SignatureShare],
+    ) -> Result<Signature> {
+        self.check_package(package)?;
+        let (commitment, _) = group_commitment(self, package);
+        let s: Scalar = shares.iter().map(|share| share.share).sum();
+        let signature = Signature {
+            nonce_address: point_address(&commitment),
+            s: U256::from_be_slice(&s.to_bytes()),
+        };
+        signature.verify(&self.public_key.0, package.message)?;
+        Ok(signature)
+    }
+}
+
+/// Participant side: commits to nonces and signs blocks it attested itself
+pub struct Participant {
+    share: KeyShare,
+    api_key: String,
+    domain: Eip712Domain,
+    /// Nonces committed to, by block height
+    nonces: Mutex<BTreeMap<u64, SigningNonces>>,
+}
+
+/// The coordinator presents `FROST_API_KEY`
+impl BearerAuth for Participant {
+    fn api_key(&self) -> Option<&str> {
+        Some(&self.api_key)
+    }
+}
+
+impl Participant {
+    pub fn new(share: KeyShare, api_key: String, domain: Eip712Domain) -> Self {
+        Self {
+            share,
+            api_key,
+            domain,
+            nonces: Mutex::default(),
+        }
+    }
+
+    pub fn identifier(&self) -> Identifier {
+        self.share.identifier
+    }
+
+    pub fn group(&self) -> &GroupKey {
+        &self.share.group
+    }
+
+    /// Commit to fresh nonces for block `height`, replacing any earlier ones
+    pub fn commit(&self, height: u64) -> Commitment {
+        let nonces = SigningNonces {
+            hiding: Scalar::random(&mut OsRng),
+            binding: Scalar::random(&mut OsRng),
+        };
+        let commitment = Commitment {
+            identifier: self.share.identifier,
+            hiding: Point(ProjectivePoint::GENERATOR * nonces.hiding),
+            binding: Point(ProjectivePoint::GENERATOR * nonces.binding),
+        };
+        let mut open = self.nonces.lock().expect("nonce lock poisoned");
+        open.insert(height, nonces);
+        while open.len() > MAX_OPEN_ROUNDS {
+            open.pop_first();
+        }
+        commitment
+    }
+
+    /// Sign `package` if its message is the digest of `own`, this oracle's
+    /// attestation of the block. The block's nonces are used up either way.
+    pub fn sign(&self, package: &SigningPackage, own: &Attestation) -> Result<SignatureShare> {
+        let nonces = self
+            .nonces
+            .lock()
+            .expect("nonce lock poisoned")
+            .remove(&package.height)
+            .with_context(|| format!("No commitment for block {}", package.height))?;
+
+        anyhow::ensure!(
+            own.height == package.height && own.signing_hash(&self.domain) == package.message,
+            "Block {} data differs from this oracle's (block hash {})",
+            package.height,
+            own.block_hash
+        );
+        self.share.group.check_package(package)?;
+        let expected = Commitment {
+            identifier: self.share.identifier,
+            hiding: Point(ProjectivePoint::GENERATOR * nonces.hiding),
+            binding: Point(ProjectivePoint::GENERATOR * nonces.binding),
+        };
+        anyhow::ensure!(
+            package.commitments.contains(&expected),
+            "Signing package doesn't carry this participant's commitment"
+        );
+
+        let rho = binding_factors(&self.share.group, package);
+        let (commitment, _) = group_commitment(&self.share.group, package);
+        let c = challenge(
+            &self.share.group.contract_key()?,
+            package.message,
+            point_address(&commitment),
+        );
+        let lambda = lagrange(self.share.identifier, package);
+        Ok(SignatureShare {
+            identifier: self.share.identifier,
+            share: nonces.hiding
+                + nonces.binding * rho[&self.share.identifier]
+                + lambda * self.share.signing_share * c,
+        })
+    }
+}
+
+/// Coordinating side: runs each block's signing round with the participants
+pub struct Coordinator {
+    participant: Arc<Participant>,
+    peers: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl Coordinator {
+    pub fn new(participant: Arc<Participant>, peers: Vec<String>, client: reqwest::Client) -> Self {
+        Self {
+            participant,
+            peers,
+            client,
+        }
+    }
+
+    /// Have the group sign `own`, this oracle's attestation of a block.
+    /// Returns `None` while fewer than the threshold of participants can
+    /// commit to it.
+    pub async fn sign(&self, own: &Attestation) -> Result<Option<Signature>> {
+        let group = self.participant.group();
+        let threshold = group.threshold as usize;
+
+        let mut commitments = vec![self.participant.commit(own.height)];
+        let mut peers: HashMap<Identifier, &str> = HashMap::new();
+        for peer in &self.peers {
+            if commitments.len() == threshold {
+                break;
+            }
+            let commitment = match self.peer_commit(peer, own.height).await {
+                Ok(commitment) => commitment,
+                Err(e) => {
+                    warn!("   ⚠️  FROST participant {} didn't commit: {:#}", peer, e);
+                    continue;
+                }
+            };
+            if !group.verifying_shares.contains_key(&commitment.identifier)
+                || commitments
+                    .iter()
+                    .any(|other| other.identifier == commitment.identifier)
+            {
+                warn!(
+                    "   ⚠️  FROST participant {} answered as {}, which is unknown or taken",
+                    peer, commitment.identifier
+                );
+                continue;
+            }
+            peers.insert(commitment.identifier, peer);
+            commitments.push(commitment);
+        }
+        if commitments.len() < threshold {
+            info!(
+                "   ⏳ Block {} has {}/{} FROST commitments; waiting for participants",
+                own.height,
+                commitments.len(),
+                threshold
+            );
+            return Ok(None);
+        }
+        commitments.sort_by_key(|commitment| commitment.identifier);
+
+        let package = SigningPackage {
+            height: own.height,
+            message: own.signing_hash(&self.participant.domain),
+            commitments,
+        };
+        let mut shares = vec![self.participant.sign(&package, own)?];
+        for (&identifier, peer) in &peers {
+            let share = self
+                .peer_sign(peer, &package)
+                .await
+                .with_context(|| format!("FROST participant {} didn't sign", peer))?;
+            anyhow::ensure!(
+                share.identifier == identifier,
+                "FROST participant {} signed as {}, committed as {}",
+                peer,
+                share.identifier,
+                identifier
+            );
+            group
+                .verify_share(&package, &share)
+                .with_context(|| format!("FROST participant {}", peer))?;
+            shares.push(share);
+        }
+        group.aggregate(&package, &shares).map(Some)
+    }
+
+    async fn peer_commit(&self, peer: &str, height: u64) -> Result<Commitment> {
+        let response = self
+            .client
+            .post(format!("{}/frost/commit", peer))
+            .bearer_auth(&self.participant.api_key)
+            .timeout(PEER_TIMEOUT)
+            .json(&CommitRequest { height })
+            .send()
+            .await?;
+        peer_response(response).await
+    }
+
+    async fn peer_sign(&self, peer: &str, package: &SigningPackage) -> Result<SignatureShare> {
+        let response = self
+            .client
+            .post(format!("{}/frost/sign", peer))
+            .bearer_auth(&self.participant.api_key)
+            .timeout(PEER_TIMEOUT)
+            .json(package)
+            .send()
+            .await?;
+        peer_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attestation::domain;
+    use alloy::primitives::PrimitiveSignature;
+
+    fn attestation(block_hash: B256) -> Attestation {
+        Attestation::unsigned(
+            Address::ZERO,
+            3_100_000,
+            block_hash,
+            B256::repeat_byte(0x02),
+            B256::repeat_byte(0x03),
+        )
+    }
+
+    /// Every operator's key share, from a DKG run in-process
+    fn dkg(threshold: u16, participants: u16) -> Vec<KeyShare> {
+        let (mut states, round1): (Vec<DkgState>, Vec<Round1Package>) = (1..=participants)
+            .map(|identifier| DkgState::start(identifier, threshold, participants).unwrap())
+            .unzip();
+        let round2: Vec<Round2Package> = states
+            .iter_mut()
+            .flat_map(|state| state.shares(&round1).unwrap())
+            .collect();
+        states
+            .iter()
+            .map(|state| {
+                let own: Vec<Round2Package> = round2
+                    .iter()
+                    .filter(|package| package.to == state.identifier)
+                    .cloned()
+                    .collect();
+                state.finish(&own).unwrap()
+            })
+            .collect()
+    }
+
+    fn round(
+        participants: &[&Participant],
+        own: &Attestation,
+    ) -> (SigningPackage, Vec<SignatureShare>) {
+        let commitments = participants
+            .iter()
+            .map(|participant| participant.commit(own.height))
+            .collect();
+        let package = SigningPackage {
+            height: own.height,
+            message: own.signing_hash(&participants[0].domain),
+            commitments,
+        };
+        let shares = participants
+            .iter()
+            .map(|participant| participant.sign(&package, own).unwrap())
+            .collect();
+        (package, shares)
+    }
+
+    #[test]
+    fn test_threshold_signature() {
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let participants: Vec<Participant> = dkg(2, 3)
+            .into_iter()
+            .map(|share| Participant::new(share, "key".repeat(11), bridge.clone()))
+            .collect();
+        let group = participants[0].group().clone();
+        let own = attestation(B256::repeat_byte(0x01));
+
+        // Any two of the three sign
+        for pair in [[0, 1], [0, 2], [1, 2]] {
+            let signers = [&participants[pair[0]], &participants[pair[1]]];
+            let (package, shares) = round(&signers, &own);
+            for share in &shares {
+                group.verify_share(&package, share).unwrap();
+            }
+            let signature = group.aggregate(&package, &shares).unwrap();
+            signature
+                .verify(&group.public_key.0, own.signing_hash(&bridge))
+                .unwrap();
+        }
+
+        // One share alone is not enough, and a tampered share is caught
+        let (package, mut shares) = round(&[&participants[0], &participants[1]], &own);
+        assert!(group.aggregate(&package, &shares[..1]).is_err());
+        shares[1].share += Scalar::ONE;
+        assert!(group.verify_share(&package, &shares[1]).is_err());
+        assert!(group.aggregate(&package, &shares).is_err());
+    }
+
+    #[test]
+    fn test_participant_refuses() {
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let shares = dkg(2, 2);
+        let participants: Vec<Participant> = shares
+            .into_iter()
+            .map(|share| Participant::new(share, "key".repeat(11), bridge.clone()))
+            .collect();
+        let own = attestation(B256::repeat_byte(0x01));
+
+        // Different block data than its own
+        let commitments = vec![
+            participants[0].commit(own.height),
+            participants[1].commit(own.height),
+        ];
+        let forged = SigningPackage {
+            height: own.height,
+            message: attestation(B256::repeat_byte(0xff)).signing_hash(&bridge),
+            commitments,
+        };
+        assert!(participants[0].sign(&forged, &own).is_err());
+
+        // The nonces went with the refused package: no second signature
+        let retry = SigningPackage {
+            message: own.signing_hash(&bridge),
+            ..forged
+        };
+        assert!(participants[0].sign(&retry, &own).is_err());
+        assert!(participants[1].sign(&retry, &own).is_ok());
+        assert!(participants[1].sign(&retry, &own).is_err());
+    }
+
+    #[test]
+    fn test_ecrecover_verification() {
+        // What postMoneroBlockWithThresholdSignature computes
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let participants: Vec<Participant> = dkg(2, 2)
+            .into_iter()
+            .map(|share| Participant::new(share, "key".repeat(11), bridge.clone()))
+            .collect();
+        let group = participants[0].group().clone();
+        let own = attestation(B256::repeat_byte(0x01));
+        let (package, shares) = round(&[&participants[0], &participants[1]], &own);
+        let signature = group.aggregate(&package, &shares).unwrap();
+
+        let key = group.contract_key().unwrap();
+        let px = Scalar::from_repr(key.x.to_be_bytes::<32>().into()).unwrap();
+        let s = Scalar::from_repr(signature.s.to_be_bytes::<32>().into()).unwrap();
+        let c = challenge(&key, package.message, signature.nonce_address);
+        let hash = B256::from_slice(&(-(s * px)).to_bytes());
+        let recovered = PrimitiveSignature::new(
+            key.x,
+            U256::from_be_slice(&(-(c * px)).to_bytes()),
+            key.parity == 1,
+        )
+        .recover_address_from_prehash(&hash)
+        .unwrap();
+        assert_eq!(recovered, signature.nonce_address);
+    }
+
+    #[test]
+    fn test_dkg() {
+        let shares = dkg(3, 4);
+        let group = &shares[0].group;
+        for share in &shares {
+            assert_eq!(&share.group, group);
+            assert_eq!(
+                group.verifying_shares[&share.identifier],
+                Point(ProjectivePoint::GENERATOR * share.signing_share)
+            );
+        }
+        // Any three shares interpolate to the key no operator held
+        let x = |share: &KeyShare| Scalar::from(share.identifier as u64);
+        let secret = |signers: &[&KeyShare]| {
+            signers.iter().fold(Scalar::ZERO, |sum, share| {
+                let coefficient = signers
+                    .iter()
+                    .filter(|other| other.identifier != share.identifier)
+                    .fold(Scalar::ONE, |acc, other| {
+                        acc * x(other) * (x(other) - x(share)).invert().unwrap()
+                    });
+                sum + share.signing_share * coefficient
+            })
+        };
+        let key = ProjectivePoint::GENERATOR * secret(&[&shares[0], &shares[1], &shares[3]]);
+        assert_eq!(key, group.public_key.0);
+        assert_eq!(
+            secret(&[&shares[0], &shares[1], &shares[3]]),
+            secret(&[&shares[1], &shares[2], &shares[3]])
+        );
+        assert_ne!(
+            ProjectivePoint::GENERATOR * secret(&[&shares[0], &shares[1]]),
+            group.public_key.0
+        );
+    }
+
+    #[test]
+    fn test_dkg_rejects_bad_packages() {
+        let (mut first, round1_first) = DkgState::start(1, 2, 3).unwrap();
+        let (mut second, round1_second) = DkgState::start(2, 2, 3).unwrap();
+        let (mut third, round1_third) = DkgState::start(3, 2, 3).unwrap();
+        assert!(DkgState::start(4, 2, 3).is_err());
+        assert!(DkgState::start(1, 1, 3).is_err());
+
+        // A proof of knowledge that doesn't verify, or replayed by another
+        // participant, is named
+        let mut forged = round1_third.clone();
+        forged.proof += Scalar::ONE;
+        let error = first
+            .shares(&[round1_second.clone(), forged])
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Participant 3's proof of knowledge doesn't verify"
+        );
+        let replayed = Round1Package {
+            identifier: 3,
+            ..round1_second.clone()
+        };
+        assert!(first.shares(&[round1_second.clone(), replayed]).is_err());
+        // Everyone's package is needed, and for the same key
+        let error = first
+            .shares(std::slice::from_ref(&round1_second))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "No round 1 package from participants [3]"
+        );
+        let other = DkgState::start(3, 2, 4).unwrap().1;
+        assert!(first.shares(&[round1_second.clone(), other]).is_err());
+        assert!(first.finish(&[]).is_err());
+
+        let round1 = [round1_first, round1_second, round1_third];
+        let from_first = first.shares(&round1).unwrap();
+        let from_second = second.shares(&round1).unwrap();
+        third.shares(&round1).unwrap();
+        // Shares already went out for these commitments
+        let mut changed = round1.clone();
+        changed[2] = DkgState::start(3, 2, 3).unwrap().1;
+        assert!(first.shares(&changed).is_err());
+
+        // A share that doesn't match its sender's commitments is named
+        let for_third = |packages: &[Round2Package]| {
+            packages
+                .iter()
+                .find(|package| package.to == 3)
+                .unwrap()
+                .clone()
+        };
+        let mut bad = for_third(&from_second);
+        bad.share += Scalar::ONE;
+        let error = third.finish(&[for_third(&from_first), bad]).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Participant 2's share doesn't match its commitments"
+        );
+        let error = third.finish(&[for_third(&from_first)]).err().unwrap();
+        assert_eq!(error.to_string(), "No share from participants [2]");
+        assert!(third.finish(&from_first).is_err());
+        let share = third
+            .finish(&[for_third(&from_first), for_third(&from_second)])
+            .unwrap();
+        assert_eq!(share.identifier, 3);
+
+        // The state survives between the steps
+        let path = std::env::temp_dir().join(format!(
+            "monero-oracle-frost-dkg-{}.json",
+            std::process::id()
+        ));
+        third.write(&path).unwrap();
+        third.write(&path).unwrap();
+        let read = DkgState::read(&path).unwrap();
+        assert_eq!(read.coefficients, third.coefficients);
+        assert_eq!(read.commitments, third.commitments);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_key_share_file() {
+        let shares = dkg(2, 3);
+        let path = std::env::temp_dir().join(format!(
+            "monero-oracle-frost-share-{}.json",
+            std::process::id()
+        ));
+        shares[1].write(&path).unwrap();
+        let read = KeyShare::read(&path).unwrap();
+        assert_eq!(read.identifier, 2);
+        assert_eq!(read.signing_share, shares[1].signing_share);
+        assert_eq!(read.group, shares[0].group);
+        // Never overwritten
+        assert!(shares[0].write(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
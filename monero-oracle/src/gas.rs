@@ -0,0 +1,403 @@
+//! EIP-1559 fee selection and bumping for oracle transactions
+//!
+//! Fees are derived from `eth_feeHistory`: the strategy picks which percentile
+//! of recent priority fees to pay and how much headroom to leave over the next
+//! block's base fee. A transaction that stays unconfirmed past the stuck
+//! timeout is replaced (same nonce) with fees bumped by `bump_percent`.
+//!
+//! An optional [`GasBudget`] caps what the oracle spends over the last day
+//! and week, so a runaway catch-up or a reverting call retried every poll
+//! can't drain the wallet.
+
+use crate::{costs::TxCost, eth::wei_to_ether};
+use alloy::{eips::BlockNumberOrTag, primitives::U256, providers::Provider, transports::Transport};
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Number of recent blocks sampled for priority fees
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasStrategy {
+    Economy,
+    Normal,
+    Aggressive,
+}
+
+impl GasStrategy {
+    /// Percentile of recent priority fees to pay
+    fn reward_percentile(self) -> f64 {
+        match self {
+            Self::Economy => 10.0,
+            Self::Normal => 50.0,
+            Self::Aggressive => 90.0,
+        }
+    }
+
+    /// Max fee headroom over the next base fee, in percent
+    fn base_fee_percent(self) -> u128 {
+        match self {
+            Self::Economy => 125,
+            Self::Normal => 200,
+            Self::Aggressive => 300,
+        }
+    }
+}
+
+impl FromStr for GasStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "economy" => Ok(Self::Economy),
+            "normal" => Ok(Self::Normal),
+            "aggressive" => Ok(Self::Aggressive),
+            other => anyhow::bail!(
+                "Unknown gas strategy '{}' (expected economy, normal or aggressive)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for GasStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Economy => "economy",
+            Self::Normal => "normal",
+            Self::Aggressive => "aggressive",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasConfig {
+    pub strategy: GasStrategy,
+    /// Hard cap on `maxFeePerGas`, in wei
+    pub max_fee_per_gas: Option<u128>,
+    /// Fixed `maxPriorityFeePerGas` in wei, overriding the fee history
+    pub priority_fee: Option<u128>,
+    pub bump_percent: u128,
+    pub stuck_timeout: Duration,
+    pub max_bumps: u32,
+    pub budget: GasBudget,
+}
+
+/// Most ETH the oracle may spend on gas in any 24 hours and any 7 days
+/// (`GAS_BUDGET_DAILY_ETH`, `GAS_BUDGET_WEEKLY_ETH`)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GasBudget {
+    pub daily_eth: Option<f64>,
+    pub weekly_eth: Option<f64>,
+}
+
+/// Window a gas budget covers, ending now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Day,
+    Week,
+}
+
+impl BudgetPeriod {
+    pub fn length(self) -> TimeDelta {
+        match self {
+            Self::Day => TimeDelta::days(1),
+            Self::Week => TimeDelta::weeks(1),
+        }
+    }
+}
+
+impl fmt::Display for BudgetPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Day => "daily",
+            Self::Week => "weekly",
+        })
+    }
+}
+
+/// A gas budget that has been used up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetExceeded {
+    pub period: BudgetPeriod,
+    pub spent_eth: f64,
+    pub budget_eth: f64,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.6} ETH spent on gas, {} budget is {} ETH",
+            self.spent_eth, self.period, self.budget_eth
+        )
+    }
+}
+
+impl GasBudget {
+    fn limits(&self) -> impl Iterator<Item = (BudgetPeriod, f64)> {
+        [
+            (BudgetPeriod::Day, self.daily_eth),
+            (BudgetPeriod::Week, self.weekly_eth),
+        ]
+        .into_iter()
+        .filter_map(|(period, budget)| Some((period, budget?)))
+    }
+
+    /// How far back costs count against a budget; `None` without one
+    pub fn lookback(&self) -> Option<TimeDelta> {
+        self.limits().map(|(period, _)| period.length()).max()
+    }
+
+    /// The first budget used up by `costs` recorded in its period before
+    /// `now`
+    pub fn exceeded(&self, costs: &[TxCost], now: DateTime<Utc>) -> Option<BudgetExceeded> {
+        self.limits().find_map(|(period, budget_eth)| {
+            let since = now - period.length();
+            let spent: U256 = costs
+                .iter()
+                .filter(|cost| cost.recorded_at > since)
+                .map(TxCost::cost_wei)
+                .sum();
+            let spent_eth = wei_to_ether(spent);
+            (spent_eth >= budget_eth).then_some(BudgetExceeded {
+                period,
+                spent_eth,
+                budget_eth,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl GasConfig {
+    /// Fees for a fresh transaction given the next base fee and the strategy's
+    /// percentile of recent priority fees
+    pub fn fees(&self, base_fee: u128, suggested_priority_fee: u128) -> Fees {
+        let priority = self.priority_fee.unwrap_or(suggested_priority_fee);
+        let max_fee = base_fee * self.strategy.base_fee_percent() / 100 + priority;
+        self.capped(Fees {
+            max_fee_per_gas: max_fee,
+            max_priority_fee_per_gas: priority,
+        })
+    }
+
+    /// Fees for a replacement transaction, or `None` once the cap stops them
+    /// from rising (nodes reject replacements that don't pay more)
+    pub fn bump(&self, fees: Fees) -> Option<Fees> {
+        let bump = |fee: u128| (fee * (100 + self.bump_percent) / 100).max(fee + 1);
+        let bumped = self.capped(Fees {
+            max_fee_per_gas: bump(fees.max_fee_per_gas),
+            max_priority_fee_per_gas: bump(fees.max_priority_fee_per_gas),
+        });
+
+        (bumped.max_fee_per_gas > fees.max_fee_per_gas
+            && bumped.max_priority_fee_per_gas > fees.max_priority_fee_per_gas)
+            .then_some(bumped)
+    }
+
+    /// Fees for replacing a pending transaction that paid `stuck`: the
+    /// current suggestion, raised to at least one bump over `stuck`
+    pub fn replacement(&self, suggested: Fees, stuck: Fees) -> Option<Fees> {
+        let bumped = self.bump(stuck)?;
+        Some(
+            self.capped(Fees {
+                max_fee_per_gas: suggested.max_fee_per_gas.max(bumped.max_fee_per_gas),
+                max_priority_fee_per_gas: suggested
+                    .max_priority_fee_per_gas
+                    .max(bumped.max_priority_fee_per_gas),
+            }),
+        )
+    }
+
+    fn capped(&self, fees: Fees) -> Fees {
+        let max_fee = match self.max_fee_per_gas {
+            Some(cap) => fees.max_fee_per_gas.min(cap),
+            None => fees.max_fee_per_gas,
+        };
+        Fees {
+            max_fee_per_gas: max_fee,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas.min(max_fee),
+        }
+    }
+}
+
+/// Suggest fees for the next oracle transaction from recent fee history
+pub async fn suggest_fees<T, P>(provider: &P, config: &GasConfig) -> Result<Fees>
+where
+    T: Transport + Clone,
+    P: Provider<T>,
+{
+    let history = provider
+        .get_fee_history(
+            FEE_HISTORY_BLOCKS,
+            BlockNumberOrTag::Latest,
+            &[config.strategy.reward_percentile()],
+        )
+        .await?;
+
+    let base_fee = history
+        .next_block_base_fee()
+        .context("Fee history did not include a base fee")?;
+    let rewards: Vec<u128> = history
+        .reward
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|block| block.first().copied())
+        .collect();
+
+    Ok(config.fees(base_fee, median(rewards)))
+}
+
+fn median(mut values: Vec<u128>) -> u128 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    values[values.len() / 2]
+}
+
+/// Convert a gwei amount from configuration into wei
+pub fn gwei_to_wei(gwei: f64) -> u128 {
+    (gwei * 1e9) as u128
+}
+
+/// Format a wei-per-gas amount as gwei for logging
+pub fn format_gwei(wei: u128) -> String {
+    format!("{:.4}", wei as f64 / 1e9)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(strategy: GasStrategy) -> GasConfig {
+        GasConfig {
+            strategy,
+            max_fee_per_gas: None,
+            priority_fee: None,
+            bump_percent: 15,
+            stuck_timeout: Duration::from_secs(180),
+            max_bumps: 5,
+            budget: GasBudget::default(),
+        }
+    }
+
+    #[test]
+    fn test_gas_budget() {
+        let now = Utc::now();
+        let spent = |hours_ago: i64| TxCost {
+            recorded_at: now - TimeDelta::hours(hours_ago),
+            ..TxCost::gas(
+                crate::costs::CostKind::PostBlock,
+                Default::default(),
+                100_000,
+                1_000_000_000_000, // 0.1 ETH
+            )
+        };
+        let costs = [spent(1), spent(30), spent(100), spent(200)];
+
+        assert_eq!(GasBudget::default().exceeded(&costs, now), None);
+        assert_eq!(GasBudget::default().lookback(), None);
+
+        let daily = GasBudget {
+            daily_eth: Some(0.15),
+            weekly_eth: None,
+        };
+        assert_eq!(daily.exceeded(&costs, now), None);
+        assert_eq!(daily.lookback(), Some(TimeDelta::days(1)));
+
+        let weekly = GasBudget {
+            daily_eth: Some(0.15),
+            weekly_eth: Some(0.3),
+        };
+        let exceeded = weekly.exceeded(&costs, now).unwrap();
+        assert_eq!(exceeded.period, BudgetPeriod::Week);
+        assert!((exceeded.spent_eth - 0.3).abs() < 1e-9);
+        assert_eq!(weekly.lookback(), Some(TimeDelta::weeks(1)));
+    }
+
+    #[test]
+    fn test_strategy_headroom() {
+        let economy = config(GasStrategy::Economy).fees(1_000, 10);
+        let aggressive = config(GasStrategy::Aggressive).fees(1_000, 10);
+
+        assert_eq!(economy.max_fee_per_gas, 1_260);
+        assert_eq!(aggressive.max_fee_per_gas, 3_010);
+        assert_eq!(economy.max_priority_fee_per_gas, 10);
+    }
+
+    #[test]
+    fn test_priority_override_and_cap() {
+        let mut cfg = config(GasStrategy::Normal);
+        cfg.priority_fee = Some(500);
+        cfg.max_fee_per_gas = Some(1_000);
+
+        let fees = cfg.fees(1_000, 10);
+        assert_eq!(fees.max_fee_per_gas, 1_000);
+        assert_eq!(fees.max_priority_fee_per_gas, 500);
+    }
+
+    #[test]
+    fn test_bump_until_cap() {
+        let mut cfg = config(GasStrategy::Normal);
+        cfg.max_fee_per_gas = Some(2_500);
+
+        let fees = cfg.fees(1_000, 100);
+        let bumped = cfg.bump(fees).unwrap();
+        assert_eq!(bumped.max_fee_per_gas, 2_415);
+        assert_eq!(bumped.max_priority_fee_per_gas, 115);
+
+        let capped = cfg.bump(bumped).unwrap();
+        assert_eq!(capped.max_fee_per_gas, 2_500);
+        assert!(cfg.bump(capped).is_none());
+    }
+
+    #[test]
+    fn test_replacement_outbids_stuck_fees() {
+        let cfg = config(GasStrategy::Normal);
+        let stuck = Fees {
+            max_fee_per_gas: 2_000,
+            max_priority_fee_per_gas: 100,
+        };
+
+        // Fees have fallen since: pay one bump over the stuck transaction
+        let low = cfg.fees(100, 10);
+        let fees = cfg.replacement(low, stuck).unwrap();
+        assert_eq!(fees.max_fee_per_gas, 2_300);
+        assert_eq!(fees.max_priority_fee_per_gas, 115);
+
+        // Fees have risen past it: the fresh suggestion already outbids
+        let high = cfg.fees(5_000, 500);
+        assert_eq!(cfg.replacement(high, stuck), Some(high));
+    }
+
+    #[test]
+    fn test_bump_zero_fees() {
+        let cfg = config(GasStrategy::Economy);
+        let bumped = cfg
+            .bump(Fees {
+                max_fee_per_gas: 0,
+                max_priority_fee_per_gas: 0,
+            })
+            .unwrap();
+        assert_eq!(bumped.max_fee_per_gas, 1);
+        assert_eq!(bumped.max_priority_fee_per_gas, 1);
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!(
+            "Aggressive".parse::<GasStrategy>().unwrap(),
+            GasStrategy::Aggressive
+        );
+        assert!("fast".parse::<GasStrategy>().is_err());
+    }
+}
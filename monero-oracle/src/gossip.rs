@@ -0,0 +1,897 @@
+//! Attestation gossip between multi-signature operators, over libp2p
+//!
+//! Without gossip, a submitter polls each signer in `ATTESTATION_PEERS` and
+//! is the only place the signatures come together. With `GOSSIP_LISTEN` set,
+//! the operators form a libp2p network instead and publish the attestations
+//! they sign on a gossipsub topic of the bridge contract. Every operator ends
+//! up holding every signer's attestations, so any of them can post a block,
+//! and none has to know the submitter.
+//!
+//! - **Discovery**: an operator joins through any of `GOSSIP_PEERS` and finds
+//!   the others with a Kademlia random walk; the peers it learns of are
+//!   dialed and kept connected.
+//! - **Peer authentication**: once connected, operators exchange a
+//!   [`PeerCertificate`], an EIP-712 signature by a signer of the contract
+//!   over the operator's libp2p peer id. A peer whose certificate doesn't
+//!   verify, whose signer isn't in the contract's signer set, or that sends
+//!   none within [`HELLO_TIMEOUT`] is disconnected, as are peers whose signer
+//!   is later removed. Messages are only taken from authenticated peers.
+//! - **Validation**: an attestation is accepted only if it verifies against
+//!   the contract's EIP-712 domain and its signer is in the signer set, so a
+//!   relay can drop attestations but can't forge or alter them. Gossipsub
+//!   forwards a message only once every attestation in it is valid.
+//! - **Catch-up**: an operator short of signatures for a block, e.g. after
+//!   being offline, asks its peers for every attestation they hold of it.
+
+use crate::{attestation::Attestation, metrics, signer::OracleSigner, storage::Database};
+use alloy::{
+    primitives::{Address, Bytes, PrimitiveSignature},
+    sol,
+    sol_types::{Eip712Domain, SolStruct},
+};
+use anyhow::{Context, Result};
+use libp2p::{
+    futures::StreamExt,
+    gossipsub, identify,
+    identity::Keypair,
+    kad,
+    request_response::{self, OutboundRequestId, ProtocolSupport},
+    swarm::{NetworkBehaviour, SwarmEvent},
+    Multiaddr, PeerId, StreamProtocol, Swarm,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+/// Time allowed for a peer to answer a request
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time a connected peer has to authenticate
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval of the Kademlia random walk, and of dropping peers that haven't
+/// authenticated or are no longer signers
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most attestations accepted in one message
+pub const MAX_BATCH: usize = 64;
+
+/// Requests, Kademlia and identify protocols of the operators' network
+const REQUEST_PROTOCOL: StreamProtocol = StreamProtocol::new("/hooked-monero/gossip/1.0.0");
+const KAD_PROTOCOL: StreamProtocol = StreamProtocol::new("/hooked-monero/kad/1.0.0");
+const IDENTIFY_PROTOCOL: &str = "/hooked-monero/1.0.0";
+
+/// `GOSSIP_LISTEN`, `GOSSIP_PEERS` and `GOSSIP_KEY`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GossipConfig {
+    /// Address the other operators reach this one at
+    pub listen: Multiaddr,
+    /// Operators to join the network through; the rest are discovered
+    pub peers: Vec<Multiaddr>,
+    /// This operator's libp2p key, created on first start
+    pub key: PathBuf,
+}
+
+sol! {
+    /// Ties an operator's libp2p peer id to the signer running it
+    struct GossipPeer {
+        bytes peerId;
+    }
+}
+
+/// A signer vouching for the libp2p peer id it gossips from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerCertificate {
+    pub signer: Address,
+    /// 65-byte `r || s || v` signature over the EIP-712 digest
+    pub signature: Bytes,
+}
+
+impl PeerCertificate {
+    pub async fn sign(signer: &OracleSigner, domain: &Eip712Domain, peer: &PeerId) -> Result<Self> {
+        let signature = signer.sign_hash(&signing_hash(domain, peer)).await?;
+        Ok(Self {
+            signer: signer.address(),
+            signature: Bytes::copy_from_slice(&signature.as_bytes()),
+        })
+    }
+
+    /// Check that the certificate was signed by its signer for `peer`
+    pub fn verify(&self, domain: &Eip712Domain, peer: &PeerId) -> Result<()> {
+        let signature = PrimitiveSignature::try_from(self.signature.as_ref())
+            .context("Malformed peer certificate signature")?;
+        let recovered = signature.recover_address_from_prehash(&signing_hash(domain, peer))?;
+        anyhow::ensure!(
+            recovered == self.signer,
+            "Peer certificate for {} signed by {}, claims {}",
+            peer,
+            recovered,
+            self.signer
+        );
+        Ok(())
+    }
+}
+
+fn signing_hash(domain: &Eip712Domain, peer: &PeerId) -> alloy::primitives::B256 {
+    GossipPeer {
+        peerId: peer.to_bytes().into(),
+    }
+    .eip712_signing_hash(domain)
+}
+
+/// What operators ask each other directly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Request {
+    /// The dialing operator's certificate
+    Hello(PeerCertificate),
+    /// Every attestation held of a block
+    Attestations(u64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Response {
+    /// The answering operator's certificate
+    Hello(PeerCertificate),
+    Attestations(Vec<Attestation>),
+    /// To a request from a peer that hasn't authenticated
+    Refused,
+}
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    gossipsub: gossipsub::Behaviour,
+    kad: kad::Behaviour<kad::store::MemoryStore>,
+    identify: identify::Behaviour,
+    requests: request_response::json::Behaviour<Request, Response>,
+}
+
+/// Work for the network task
+enum Command {
+    Publish(Vec<Attestation>),
+    /// Every authenticated peer's attestations of a block
+    Pull {
+        height: u64,
+        reply: oneshot::Sender<Vec<Vec<Attestation>>>,
+    },
+}
+
+/// Attestations received from gossip or a pull
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Received {
+    /// Valid and not stored before; now stored
+    pub new: Vec<Attestation>,
+    /// Not verifying, or not by a registered signer
+    pub invalid: usize,
+}
+
+/// Publishes, takes and pulls attestations on the operators' network
+pub struct Gossip {
+    domain: Eip712Domain,
+    /// The contract's signer set, refreshed every poll
+    signers: RwLock<HashSet<Address>>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Gossip {
+    /// Join the operators' network and run it in the background for as long
+    /// as the returned handle is kept
+    pub async fn start(
+        config: &GossipConfig,
+        domain: Eip712Domain,
+        signer: &OracleSigner,
+        signers: impl IntoIterator<Item = Address>,
+        db: Arc<Database>,
+    ) -> Result<Arc<Self>> {
+        let keypair = load_key(&config.key)?;
+        let local = keypair.public().to_peer_id();
+        let certificate = PeerCertificate::sign(signer, &domain, &local).await?;
+        let topic = gossipsub::IdentTopic::new(format!(
+            "/hooked-monero/attestations/{}/{}",
+            domain.chain_id.unwrap_or_default(),
+            domain.verifying_contract.unwrap_or_default()
+        ));
+
+        let mut swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_tcp(
+                libp2p::tcp::Config::default(),
+                libp2p::noise::Config::new,
+                libp2p::yamux::Config::default,
+            )?
+            .with_dns()?
+            .with_behaviour(
+                |key| -> Result<Behaviour, Box<dyn std::error::Error + Send + Sync>> {
+                    let gossipsub = gossipsub::Behaviour::new(
+                        gossipsub::MessageAuthenticity::Signed(key.clone()),
+                        gossipsub::ConfigBuilder::default()
+                            .validation_mode(gossipsub::ValidationMode::Strict)
+                            .validate_messages()
+                            // The same attestations relayed twice are one message
+                            .message_id_fn(|message| {
+                                gossipsub::MessageId::from(Sha256::digest(&message.data).to_vec())
+                            })
+                            .build()?,
+                    )?;
+                    let mut kad = kad::Behaviour::with_config(
+                        key.public().to_peer_id(),
+                        kad::store::MemoryStore::new(key.public().to_peer_id()),
+                        kad::Config::new(KAD_PROTOCOL),
+                    );
+                    kad.set_mode(Some(kad::Mode::Server));
+                    Ok(Behaviour {
+                        gossipsub,
+                        kad,
+                        identify: identify::Behaviour::new(identify::Config::new(
+                            IDENTIFY_PROTOCOL.to_string(),
+                            key.public(),
+                        )),
+                        requests: request_response::json::Behaviour::new(
+                            [(REQUEST_PROTOCOL, ProtocolSupport::Full)],
+                            request_response::Config::default().with_request_timeout(PEER_TIMEOUT),
+                        ),
+                    })
+                },
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to set up gossip: {}", e))?
+            .with_swarm_config(|swarm| swarm.with_idle_connection_timeout(Duration::MAX))
+            .build();
+        swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
+        swarm
+            .listen_on(config.listen.clone())
+            .with_context(|| format!("Failed to listen on {}", config.listen))?;
+        for peer in &config.peers {
+            if let Err(e) = swarm.dial(peer.clone()) {
+                warn!("   ⚠️  Gossip peer {} can't be dialed: {}", peer, e);
+            }
+        }
+        info!("   Gossip peer id: {}", local);
+
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let gossip = Arc::new(Self {
+            domain,
+            signers: RwLock::new(signers.into_iter().collect()),
+            commands,
+        });
+        let node = Node {
+            swarm,
+            topic,
+            certificate,
+            bootstrap: config.peers.clone(),
+            gossip: Arc::downgrade(&gossip),
+            db,
+            authenticated: HashMap::new(),
+            unauthenticated: HashMap::new(),
+            pulls: HashMap::new(),
+            requests: HashMap::new(),
+            next_pull: 0,
+        };
+        tokio::spawn(node.run(receiver));
+        Ok(gossip)
+    }
+
+    pub fn set_signers(&self, signers: impl IntoIterator<Item = Address>) {
+        *self.signers.write().expect("signer set poisoned") = signers.into_iter().collect();
+    }
+
+    fn is_signer(&self, address: &Address) -> bool {
+        self.signers
+            .read()
+            .expect("signer set poisoned")
+            .contains(address)
+    }
+
+    /// Store the valid attestations among `received` that are new
+    pub fn accept(&self, db: &Database, received: Vec<Attestation>) -> Result<Received> {
+        anyhow::ensure!(
+            received.len() <= MAX_BATCH,
+            "At most {} attestations per message",
+            MAX_BATCH
+        );
+        let signers = self.signers.read().expect("signer set poisoned").clone();
+        let mut result = Received::default();
+        for attestation in received {
+            if !signers.contains(&attestation.signer) {
+                debug!(
+                    "Gossiped attestation from {}, not a registered signer",
+                    attestation.signer
+                );
+                result.invalid += 1;
+                continue;
+            }
+            if let Err(e) = attestation.verify(&self.domain) {
+                warn!(
+                    "   ⚠️  Invalid gossiped attestation of block {}: {:#}",
+                    attestation.height, e
+                );
+                result.invalid += 1;
+                continue;
+            }
+            let stored = db.attestation(attestation.height, attestation.signer)?;
+            if stored.is_some_and(|stored| stored.signature == attestation.signature) {
+                continue;
+            }
+            db.record_attestation(&attestation)?;
+            metrics::get().attestations_gossiped.inc();
+            result.new.push(attestation);
+        }
+        Ok(result)
+    }
+
+    /// Publish `attestations` to the other operators. Those not reached now
+    /// catch up by pulling.
+    pub fn broadcast(&self, attestations: &[Attestation]) {
+        for batch in attestations.chunks(MAX_BATCH) {
+            // Only fails once the network task has stopped
+            let _ = self.commands.send(Command::Publish(batch.to_vec()));
+        }
+    }
+
+    /// Fetch every authenticated peer's attestations of block `height` and
+    /// keep the new ones, as [`Gossip::accept`] would
+    pub async fn pull(&self, db: &Database, height: u64) -> Result<usize> {
+        let (reply, answers) = oneshot::channel();
+        self.commands
+            .send(Command::Pull { height, reply })
+            .map_err(|_| anyhow::anyhow!("Gossip network task has stopped"))?;
+        let answers = answers.await.context("Gossip network task has stopped")?;
+        let mut pulled = 0;
+        for attestations in answers {
+            for batch in attestations.chunks(MAX_BATCH) {
+                pulled += self.accept(db, batch.to_vec())?.new.len();
+            }
+        }
+        Ok(pulled)
+    }
+}
+
+/// A pull waiting on its peers' answers
+struct Pull {
+    waiting: usize,
+    answers: Vec<Vec<Attestation>>,
+    reply: oneshot::Sender<Vec<Vec<Attestation>>>,
+}
+
+/// The network task: owns the swarm
+struct Node {
+    swarm: Swarm<Behaviour>,
+    topic: gossipsub::IdentTopic,
+    certificate: PeerCertificate,
+    bootstrap: Vec<Multiaddr>,
+    gossip: Weak<Gossip>,
+    db: Arc<Database>,
+    /// Connected peers that presented a valid certificate, and their signer
+    authenticated: HashMap<PeerId, Address>,
+    /// Connected peers yet to, and since when
+    unauthenticated: HashMap<PeerId, Instant>,
+    /// Open pulls, and the pull each outstanding request is for
+    pulls: HashMap<u64, Pull>,
+    requests: HashMap<OutboundRequestId, u64>,
+    next_pull: u64,
+}
+
+impl Node {
+    async fn run(mut self, mut commands: mpsc::UnboundedReceiver<Command>) {
+        let mut discovery = tokio::time::interval(DISCOVERY_INTERVAL);
+        loop {
+            let Some(gossip) = self.gossip.upgrade() else {
+                return;
+            };
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(Command::Publish(batch)) => self.publish(&batch),
+                    Some(Command::Pull { height, reply }) => self.pull(height, reply),
+                    None => return,
+                },
+                event = self.swarm.select_next_some() => {
+                    self.handle(&gossip, event);
+                }
+                _ = discovery.tick() => self.discover(&gossip),
+            }
+        }
+    }
+
+    fn publish(&mut self, batch: &[Attestation]) {
+        let data = match serde_json::to_vec(batch) {
+            Ok(data) => data,
+            Err(e) => return warn!("   ⚠️  Failed to encode attestations: {}", e),
+        };
+        match self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(self.topic.clone(), data)
+        {
+            Ok(_) | Err(gossipsub::PublishError::Duplicate) => {}
+            Err(gossipsub::PublishError::InsufficientPeers) => {
+                debug!("No gossip peers to publish attestations to")
+            }
+            Err(e) => warn!("   ⚠️  Failed to publish attestations: {}", e),
+        }
+    }
+
+    fn pull(&mut self, height: u64, reply: oneshot::Sender<Vec<Vec<Attestation>>>) {
+        let peers: Vec<PeerId> = self.authenticated.keys().copied().collect();
+        if peers.is_empty() {
+            let _ = reply.send(Vec::new());
+            return;
+        }
+        for peer in &peers {
+            let request = self
+                .swarm
+                .behaviour_mut()
+                .requests
+                .send_request(peer, Request::Attestations(height));
+            self.requests.insert(request, self.next_pull);
+        }
+        let pull = Pull {
+            waiting: peers.len(),
+            answers: Vec::new(),
+            reply,
+        };
+        self.pulls.insert(self.next_pull, pull);
+        self.next_pull += 1;
+    }
+
+    fn handle(&mut self, gossip: &Gossip, event: SwarmEvent<BehaviourEvent>) {
+        match event {
+            SwarmEvent::NewListenAddr { address, .. } => {
+                info!("   Gossip listening on {}", address);
+            }
+            SwarmEvent::ConnectionEstablished {
+                peer_id,
+                endpoint,
+                num_established,
+                ..
+            } if num_established.get() == 1 => {
+                self.unauthenticated.insert(peer_id, Instant::now());
+                // The dialer introduces itself; the other side answers with
+                // its own certificate
+                if endpoint.is_dialer() {
+                    self.swarm
+                        .behaviour_mut()
+                        .requests
+                        .send_request(&peer_id, Request::Hello(self.certificate.clone()));
+                }
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established: 0,
+                ..
+            } => {
+                self.unauthenticated.remove(&peer_id);
+                if self.authenticated.remove(&peer_id).is_some() {
+                    self.count_peers();
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Requests(event)) => {
+                self.handle_request(gossip, event)
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                propagation_source,
+                message_id,
+                message,
+            })) => {
+                let acceptance = self.validate(gossip, &propagation_source, &message.data);
+                let _ = self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .report_message_validation_result(&message_id, &propagation_source, acceptance);
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Identify(identify::Event::Received {
+                peer_id,
+                info,
+                ..
+            })) if self.authenticated.contains_key(&peer_id) => {
+                // Operators others can be told about
+                for address in info.listen_addrs {
+                    self.swarm
+                        .behaviour_mut()
+                        .kad
+                        .add_address(&peer_id, address);
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Kad(kad::Event::RoutingUpdated {
+                peer, ..
+            })) if !self.swarm.is_connected(&peer) => {
+                debug!("Discovered gossip peer {}", peer);
+                let _ = self.swarm.dial(peer);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_request(
+        &mut self,
+        gossip: &Gossip,
+        event: request_response::Event<Request, Response>,
+    ) {
+        match event {
+            request_response::Event::Message {
+                peer,
+                message:
+                    request_response::Message::Request {
+                        request, channel, ..
+                    },
+            } => {
+                let response = match request {
+                    Request::Hello(certificate) => {
+                        self.authenticate(gossip, peer, &certificate);
+                        Response::Hello(self.certificate.clone())
+                    }
+                    Request::Attestations(height) if self.authenticated.contains_key(&peer) => {
+                        match self.db.attestations(height) {
+                            Ok(attestations) => Response::Attestations(attestations),
+                            Err(e) => {
+                                warn!("   ⚠️  Failed to read attestations: {:#}", e);
+                                return;
+                            }
+                        }
+                    }
+                    Request::Attestations(_) => Response::Refused,
+                };
+                let _ = self
+                    .swarm
+                    .behaviour_mut()
+                    .requests
+                    .send_response(channel, response);
+            }
+            request_response::Event::Message {
+                peer,
+                message:
+                    request_response::Message::Response {
+                        request_id,
+                        response,
+                    },
+            } => match response {
+                Response::Hello(certificate) => self.authenticate(gossip, peer, &certificate),
+                Response::Attestations(attestations) => self.answer(request_id, Some(attestations)),
+                Response::Refused => self.answer(request_id, None),
+            },
+            request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            } => {
+                debug!("Gossip request to {} failed: {}", peer, error);
+                self.answer(request_id, None);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record a peer's answer to a pull, replying once all have answered
+    fn answer(&mut self, request_id: OutboundRequestId, attestations: Option<Vec<Attestation>>) {
+        let Some(key) = self.requests.remove(&request_id) else {
+            return;
+        };
+        let Some(pull) = self.pulls.get_mut(&key) else {
+            return;
+        };
+        pull.answers.extend(attestations);
+        pull.waiting -= 1;
+        if pull.waiting == 0 {
+            let pull = self.pulls.remove(&key).expect("pull is open");
+            let _ = pull.reply.send(pull.answers);
+        }
+    }
+
+    /// Keep `peer` if its certificate is a signer's, otherwise disconnect it
+    fn authenticate(&mut self, gossip: &Gossip, peer: PeerId, certificate: &PeerCertificate) {
+        if let Err(e) = check_certificate(gossip, &peer, certificate) {
+            warn!("   ⚠️  Dropping gossip peer {}: {:#}", peer, e);
+            let _ = self.swarm.disconnect_peer_id(peer);
+            return;
+        }
+        self.unauthenticated.remove(&peer);
+        if self
+            .authenticated
+            .insert(peer, certificate.signer)
+            .is_none()
+        {
+            info!(
+                "   🤝 Gossip peer {} authenticated as signer {}",
+                peer, certificate.signer
+            );
+            self.count_peers();
+        }
+    }
+
+    /// Whether a gossiped message may be forwarded
+    fn validate(
+        &self,
+        gossip: &Gossip,
+        source: &PeerId,
+        data: &[u8],
+    ) -> gossipsub::MessageAcceptance {
+        if !self.authenticated.contains_key(source) {
+            return gossipsub::MessageAcceptance::Ignore;
+        }
+        let Ok(batch) = serde_json::from_slice::<Vec<Attestation>>(data) else {
+            return gossipsub::MessageAcceptance::Reject;
+        };
+        match gossip.accept(&self.db, batch) {
+            Ok(received) if received.invalid == 0 => gossipsub::MessageAcceptance::Accept,
+            Ok(_) => gossipsub::MessageAcceptance::Reject,
+            Err(e) => {
+                warn!("   ⚠️  Failed to take gossiped attestations: {:#}", e);
+                gossipsub::MessageAcceptance::Ignore
+            }
+        }
+    }
+
+    /// Walk the network for operators not yet known, and drop peers that
+    /// didn't authenticate in time or whose signer was removed
+    fn discover(&mut self, gossip: &Gossip) {
+        let late: Vec<PeerId> = self
+            .unauthenticated
+            .iter()
+            .filter(|(_, since)| since.elapsed() > HELLO_TIMEOUT)
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in late {
+            warn!("   ⚠️  Dropping gossip peer {}: no certificate", peer);
+            self.unauthenticated.remove(&peer);
+            let _ = self.swarm.disconnect_peer_id(peer);
+        }
+        let removed: Vec<PeerId> = self
+            .authenticated
+            .iter()
+            .filter(|(_, signer)| !gossip.is_signer(signer))
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in removed {
+            warn!("   ⚠️  Dropping gossip peer {}: no longer a signer", peer);
+            self.authenticated.remove(&peer);
+            let _ = self.swarm.disconnect_peer_id(peer);
+        }
+        self.count_peers();
+
+        if self.authenticated.is_empty() {
+            for peer in &self.bootstrap {
+                let _ = self.swarm.dial(peer.clone());
+            }
+        }
+        self.swarm
+            .behaviour_mut()
+            .kad
+            .get_closest_peers(PeerId::random());
+    }
+
+    fn count_peers(&self) {
+        metrics::get()
+            .gossip_peers
+            .set(self.authenticated.len() as i64);
+    }
+}
+
+/// `peer`'s certificate is for it and by a registered signer
+fn check_certificate(gossip: &Gossip, peer: &PeerId, certificate: &PeerCertificate) -> Result<()> {
+    certificate.verify(&gossip.domain, peer)?;
+    anyhow::ensure!(
+        gossip.is_signer(&certificate.signer),
+        "{} is not a registered signer",
+        certificate.signer
+    );
+    Ok(())
+}
+
+/// This operator's libp2p key from `path`, generated and written readable by
+/// its owner only if there is none
+fn load_key(path: &Path) -> Result<Keypair> {
+    use std::io::Write;
+
+    match std::fs::read(path) {
+        Ok(bytes) => Keypair::from_protobuf_encoding(&bytes)
+            .with_context(|| format!("{} is not a libp2p key", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let keypair = Keypair::generate_ed25519();
+            let encoded = keypair.to_protobuf_encoding()?;
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            options
+                .open(path)
+                .and_then(|mut file| file.write_all(&encoded))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            info!("   Generated gossip key {}", path.display());
+            Ok(keypair)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attestation::domain;
+    use alloy::{primitives::B256, signers::local::PrivateKeySigner};
+
+    fn sign(signer: &PrivateKeySigner, domain: &Eip712Domain, height: u64) -> Attestation {
+        Attestation::sign(
+            signer,
+            domain,
+            height,
+            B256::repeat_byte(0x01),
+            B256::repeat_byte(0x02),
+            B256::repeat_byte(0x03),
+        )
+        .unwrap()
+    }
+
+    /// A handle without a network behind it
+    fn detached(domain: Eip712Domain, signers: &[Address]) -> Gossip {
+        Gossip {
+            domain,
+            signers: RwLock::new(signers.iter().copied().collect()),
+            commands: mpsc::unbounded_channel().0,
+        }
+    }
+
+    #[test]
+    fn test_accept_stores_new_valid_attestations() {
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let db = Database::open_in_memory().unwrap();
+        let signer = PrivateKeySigner::random();
+        let outsider = PrivateKeySigner::random();
+        let gossip = detached(bridge.clone(), &[signer.address()]);
+
+        let valid = sign(&signer, &bridge, 600);
+        let mut forged = sign(&signer, &bridge, 601);
+        forged.block_hash = B256::repeat_byte(0xff);
+        let received = vec![
+            valid.clone(),
+            forged,
+            sign(&outsider, &bridge, 600),
+            sign(&signer, &domain(130, Address::repeat_byte(0x43)), 602),
+        ];
+        let result = gossip.accept(&db, received).unwrap();
+        assert_eq!(result.new, vec![valid.clone()]);
+        assert_eq!(result.invalid, 3);
+        assert_eq!(db.attestations(600).unwrap(), vec![valid.clone()]);
+        assert!(db.attestations(601).unwrap().is_empty());
+
+        // Already known: stored once, not new again
+        assert_eq!(
+            gossip.accept(&db, vec![valid]).unwrap(),
+            Received::default()
+        );
+
+        let flood = vec![sign(&signer, &bridge, 603); MAX_BATCH + 1];
+        assert!(gossip.accept(&db, flood).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_peer_certificate() {
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let key = PrivateKeySigner::random();
+        let signer = OracleSigner::Local(key.clone());
+        let peer = Keypair::generate_ed25519().public().to_peer_id();
+        let certificate = PeerCertificate::sign(&signer, &bridge, &peer)
+            .await
+            .unwrap();
+        certificate.verify(&bridge, &peer).unwrap();
+
+        // Not transferable to another peer id or bridge
+        let other = Keypair::generate_ed25519().public().to_peer_id();
+        assert!(certificate.verify(&bridge, &other).is_err());
+        assert!(certificate
+            .verify(&domain(130, Address::repeat_byte(0x43)), &peer)
+            .is_err());
+
+        // Valid, but only a registered signer's is accepted
+        let gossip = detached(bridge.clone(), &[key.address()]);
+        check_certificate(&gossip, &peer, &certificate).unwrap();
+        gossip.set_signers([Address::repeat_byte(0x01)]);
+        let error = check_certificate(&gossip, &peer, &certificate).unwrap_err();
+        assert!(error.to_string().contains("not a registered signer"));
+    }
+
+    /// A free local TCP port
+    fn local_address() -> Multiaddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap()
+    }
+
+    async fn operator(
+        name: &str,
+        key: &PrivateKeySigner,
+        bridge: &Eip712Domain,
+        signers: &[Address],
+        peers: Vec<Multiaddr>,
+    ) -> (Multiaddr, Arc<Gossip>, Arc<Database>) {
+        let config = GossipConfig {
+            listen: local_address(),
+            peers,
+            key: std::env::temp_dir().join(format!(
+                "monero-oracle-gossip-{}-{}",
+                name,
+                std::process::id()
+            )),
+        };
+        let _ = std::fs::remove_file(&config.key);
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let gossip = Gossip::start(
+            &config,
+            bridge.clone(),
+            &OracleSigner::Local(key.clone()),
+            signers.iter().copied(),
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        std::fs::remove_file(&config.key).unwrap();
+        (config.listen, gossip, db)
+    }
+
+    #[tokio::test]
+    async fn test_operators_gossip_and_pull() {
+        let bridge = domain(130, Address::repeat_byte(0x42));
+        let keys: Vec<PrivateKeySigner> = (0..3).map(|_| PrivateKeySigner::random()).collect();
+        // The third is not a signer
+        let signers = [keys[0].address(), keys[1].address()];
+        let (first_address, first, first_db) =
+            operator("first", &keys[0], &bridge, &signers, Vec::new()).await;
+        let (_, second, second_db) = operator(
+            "second",
+            &keys[1],
+            &bridge,
+            &signers,
+            vec![first_address.clone()],
+        )
+        .await;
+
+        // Published by the second operator, stored by the first once they
+        // have authenticated each other
+        let attestation = sign(&keys[1], &bridge, 700);
+        second_db.record_attestation(&attestation).unwrap();
+        let mut received = false;
+        for _ in 0..100 {
+            second.broadcast(std::slice::from_ref(&attestation));
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if !first_db.attestations(700).unwrap().is_empty() {
+                received = true;
+                break;
+            }
+        }
+        assert!(received, "attestation never gossiped");
+        assert_eq!(first_db.attestations(700).unwrap(), vec![attestation]);
+
+        // Missed while offline: pulled
+        let missed = sign(&keys[1], &bridge, 701);
+        second_db.record_attestation(&missed).unwrap();
+        assert_eq!(first.pull(&first_db, 701).await.unwrap(), 1);
+        assert_eq!(first_db.attestations(701).unwrap(), vec![missed]);
+
+        // An outsider is dropped once it presents its certificate: it has
+        // no one to pull from, and nothing it publishes is taken
+        let (_, outsider, outsider_db) =
+            operator("outsider", &keys[2], &bridge, &signers, vec![first_address]).await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(outsider.pull(&outsider_db, 701).await.unwrap(), 0);
+        outsider.broadcast(&[sign(&keys[0], &bridge, 702)]);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(first_db.attestations(702).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_key_is_kept() {
+        let path =
+            std::env::temp_dir().join(format!("monero-oracle-gossip-key-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let first = load_key(&path).unwrap();
+        let again = load_key(&path).unwrap();
+        assert_eq!(first.public(), again.public());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
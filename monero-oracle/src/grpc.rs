@@ -0,0 +1,375 @@
+//! gRPC query API, built with the `grpc` feature
+//!
+//! Serves `proto/monero_oracle/v1/oracle.proto` on `GRPC_PORT`: the status,
+//! blocks, proofs, deposits and withdrawals of the HTTP API as typed
+//! messages, plus server streams that follow the oracle's status and a
+//! withdrawal's progress. Answers come from the same [`ApiState`] as the
+//! HTTP API, so the two never disagree.
+
+use crate::{
+    api::{ApiState, StatusResponse},
+    monero_rpc::MoneroOutput,
+    proof::{self, OutputInclusionProof, TxInclusionProof},
+    scanner::Deposit,
+    storage::PostedBlockRecord,
+    subaddress::SubaddressIndex,
+    withdrawal::{Withdrawal, WithdrawalStatus},
+};
+use alloy::primitives::B64;
+use anyhow::Result;
+use std::{net::SocketAddr, pin::Pin, time::Duration};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+/// Types and service traits generated from `oracle.proto`
+pub mod proto {
+    tonic::include_proto!("monero_oracle.v1");
+}
+
+use proto::{
+    list_deposits_request::Filter,
+    oracle_server::{Oracle, OracleServer},
+};
+
+/// How often `WatchStatus` checks for a change
+const STATUS_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often `WatchWithdrawal` re-reads the withdrawal
+const WITHDRAWAL_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+type WatchStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+pub struct GrpcService {
+    state: ApiState,
+}
+
+/// Serve the gRPC API until the process exits
+pub async fn serve(port: u16, state: ApiState) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!("🛰️  gRPC API listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(OracleServer::new(GrpcService { state }))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+fn internal(e: anyhow::Error) -> Status {
+    Status::internal(format!("{:#}", e))
+}
+
+fn current_status(state: &ApiState) -> Result<proto::OracleStatus, Status> {
+    let status = state.status.read().expect("status lock poisoned").clone();
+    let checkpoint = state.db.checkpoint().map_err(internal)?;
+    Ok(StatusResponse::new(status, checkpoint).into())
+}
+
+fn find_withdrawal(state: &ApiState, burn_id: u64) -> Result<Withdrawal, Status> {
+    state
+        .db
+        .withdrawal(burn_id)
+        .map_err(internal)?
+        .ok_or_else(|| Status::not_found(format!("Burn {} has not been seen", burn_id)))
+}
+
+/// Stream what `poll` returns every `period`, skipping repeats of the last
+/// item sent, until it reports a final item or an error or the client goes
+/// away
+fn watch<T, F>(period: Duration, mut poll: F) -> WatchStream<T>
+where
+    T: Clone + PartialEq + Send + 'static,
+    F: FnMut() -> Result<(T, bool), Status> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        let mut last = None;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = tx.closed() => return,
+            }
+            let (item, last_item) = match poll() {
+                Ok(polled) => polled,
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            if last.as_ref() != Some(&item) {
+                last = Some(item.clone());
+                if tx.send(Ok(item)).await.is_err() {
+                    return;
+                }
+            }
+            if last_item {
+                return;
+            }
+        }
+    });
+    Box::pin(ReceiverStream::new(rx))
+}
+
+#[tonic::async_trait]
+impl Oracle for GrpcService {
+    type WatchStatusStream = WatchStream<proto::OracleStatus>;
+    type WatchWithdrawalStream = WatchStream<proto::Withdrawal>;
+
+    async fn get_status(
+        &self,
+        _request: Request<proto::GetStatusRequest>,
+    ) -> Result<Response<proto::OracleStatus>, Status> {
+        Ok(Response::new(current_status(&self.state)?))
+    }
+
+    async fn watch_status(
+        &self,
+        _request: Request<proto::WatchStatusRequest>,
+    ) -> Result<Response<Self::WatchStatusStream>, Status> {
+        let state = self.state.clone();
+        Ok(Response::new(watch(STATUS_WATCH_INTERVAL, move || {
+            Ok((current_status(&state)?, false))
+        })))
+    }
+
+    async fn get_block(
+        &self,
+        request: Request<proto::GetBlockRequest>,
+    ) -> Result<Response<proto::PostedBlock>, Status> {
+        let height = request.into_inner().height;
+        self.state
+            .db
+            .posted_block(height)
+            .map_err(internal)?
+            .map(|block| Response::new(block.into()))
+            .ok_or_else(|| Status::not_found(format!("Block {} has not been posted", height)))
+    }
+
+    async fn prove_tx(
+        &self,
+        request: Request<proto::ProveTxRequest>,
+    ) -> Result<Response<proto::TxProof>, Status> {
+        let tx_hash = request.into_inner().tx_hash;
+        let hasher = self.state.tx_merkle_hasher;
+        proof::prove_tx(
+            &self.state.monero_client,
+            Some(&self.state.db),
+            &tx_hash,
+            hasher,
+        )
+        .await
+        .map(|proof| Response::new(proof.into()))
+        .map_err(|e| Status::not_found(e.to_string()))
+    }
+
+    async fn prove_output(
+        &self,
+        request: Request<proto::ProveOutputRequest>,
+    ) -> Result<Response<proto::OutputProof>, Status> {
+        let proto::ProveOutputRequest {
+            tx_hash,
+            output_index,
+        } = request.into_inner();
+        let hasher = self.state.output_merkle_hasher;
+        // Blocks the oracle has indexed need no round trip to monerod
+        let indexed = proof::prove_indexed_output(&self.state.db, &tx_hash, output_index, hasher)
+            .map_err(|e| Status::not_found(e.to_string()))?;
+        let proof = match indexed {
+            Some(proof) => proof,
+            None => proof::prove_output(&self.state.monero_client, &tx_hash, output_index, hasher)
+                .await
+                .map_err(|e| Status::not_found(e.to_string()))?,
+        };
+        Ok(Response::new(proof.into()))
+    }
+
+    async fn list_deposits(
+        &self,
+        request: Request<proto::ListDepositsRequest>,
+    ) -> Result<Response<proto::ListDepositsResponse>, Status> {
+        let db = &self.state.db;
+        let deposits = match request.into_inner().filter {
+            None => return Err(Status::invalid_argument("A filter is required")),
+            Some(Filter::BlockHeight(height)) => db.deposits_in_block(height),
+            Some(Filter::Subaddress(proto::Subaddress { major, minor })) => {
+                let index = SubaddressIndex::new(major, minor);
+                let keys =
+                    self.state.view_keys.as_ref().ok_or_else(|| {
+                        Status::failed_precondition("Deposit scanning is disabled")
+                    })?;
+                if !keys.tracks(index) {
+                    return Err(Status::not_found(format!(
+                        "Subaddress {} is not watched",
+                        index
+                    )));
+                }
+                db.deposits_for_subaddress(index)
+            }
+            Some(Filter::PaymentId(payment_id)) => {
+                let payment_id: B64 = payment_id.parse().map_err(|_| {
+                    Status::invalid_argument(format!("Invalid payment ID {}", payment_id))
+                })?;
+                db.deposits_for_payment_id(payment_id)
+            }
+        }
+        .map_err(internal)?;
+        Ok(Response::new(proto::ListDepositsResponse {
+            deposits: deposits.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_withdrawal(
+        &self,
+        request: Request<proto::GetWithdrawalRequest>,
+    ) -> Result<Response<proto::Withdrawal>, Status> {
+        let withdrawal = find_withdrawal(&self.state, request.into_inner().burn_id)?;
+        Ok(Response::new(withdrawal.into()))
+    }
+
+    async fn watch_withdrawal(
+        &self,
+        request: Request<proto::GetWithdrawalRequest>,
+    ) -> Result<Response<Self::WatchWithdrawalStream>, Status> {
+        let burn_id = request.into_inner().burn_id;
+        // An unknown burn fails the call rather than the stream
+        find_withdrawal(&self.state, burn_id)?;
+        let state = self.state.clone();
+        Ok(Response::new(watch(WITHDRAWAL_WATCH_INTERVAL, move || {
+            let withdrawal = find_withdrawal(&state, burn_id)?;
+            let last = withdrawal.status.next().is_none();
+            Ok((withdrawal.into(), last))
+        })))
+    }
+}
+
+impl From<StatusResponse> for proto::OracleStatus {
+    fn from(response: StatusResponse) -> Self {
+        let status = response.status;
+        Self {
+            oracle_address: status.oracle_address.map(|address| address.to_string()),
+            bridge_address: status.bridge_address.map(|address| address.to_string()),
+            monero_tip: status.monero_tip,
+            monero_confirmed_height: status.monero_confirmed_height,
+            contract_latest_block: status.contract_latest_block,
+            lag: response.lag,
+            stored_checkpoint: response.stored_checkpoint,
+            balance_eth: status.balance_eth,
+            posting_paused: status.posting_paused,
+            read_only: status.read_only,
+            paused_by_operator: status.paused_by_operator,
+            contract_paused: status.contract_paused,
+            last_poll_at: status.last_poll_at.map(|at| at.to_rfc3339()),
+            last_error: status.last_error,
+        }
+    }
+}
+
+impl From<PostedBlockRecord> for proto::PostedBlock {
+    fn from(block: PostedBlockRecord) -> Self {
+        Self {
+            height: block.height,
+            block_hash: block.block_hash.to_string(),
+            tx_merkle_root: block.tx_merkle_root.to_string(),
+            output_merkle_root: block.output_merkle_root.to_string(),
+            unichain_tx_hash: block.unichain_tx_hash.to_string(),
+            unichain_block: block.unichain_block,
+            gas_used: block.gas_used,
+            posted_at: block.posted_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<TxInclusionProof> for proto::TxProof {
+    fn from(proof: TxInclusionProof) -> Self {
+        Self {
+            block_height: proof.block_height,
+            block_hash: proof.block_hash.to_string(),
+            tx_hash: proof.tx_hash.to_string(),
+            tx_index: proof.tx_index,
+            tx_merkle_root: proof.tx_merkle_root.to_string(),
+            proof: proof.proof.iter().map(ToString::to_string).collect(),
+            hasher: proof.hasher.to_string(),
+        }
+    }
+}
+
+impl From<MoneroOutput> for proto::Output {
+    fn from(output: MoneroOutput) -> Self {
+        Self {
+            tx_hash: output.tx_hash.to_string(),
+            output_index: output.output_index,
+            ecdh_amount: output.ecdh_amount.to_string(),
+            output_pub_key: output.output_pub_key.to_string(),
+            commitment: output.commitment.to_string(),
+        }
+    }
+}
+
+impl From<OutputInclusionProof> for proto::OutputProof {
+    fn from(proof: OutputInclusionProof) -> Self {
+        Self {
+            block_height: proof.block_height,
+            block_hash: proof.block_hash.to_string(),
+            output: Some(proof.output.into()),
+            leaf_index: proof.leaf_index,
+            leaf: proof.leaf.to_string(),
+            output_merkle_root: proof.output_merkle_root.to_string(),
+            proof: proof.proof.iter().map(ToString::to_string).collect(),
+            hasher: proof.hasher.to_string(),
+            verifier: proof.verifier,
+        }
+    }
+}
+
+impl From<Deposit> for proto::Deposit {
+    fn from(deposit: Deposit) -> Self {
+        Self {
+            block_height: deposit.block_height,
+            tx_hash: deposit.tx_hash.to_string(),
+            output_index: deposit.output_index,
+            amount: deposit.amount,
+            output_pub_key: deposit.output_pub_key.to_string(),
+            subaddress: Some(proto::Subaddress {
+                major: deposit.subaddress.major,
+                minor: deposit.subaddress.minor,
+            }),
+            payment_id: deposit.payment_id.map(|payment_id| payment_id.to_string()),
+        }
+    }
+}
+
+impl From<WithdrawalStatus> for proto::WithdrawalStatus {
+    fn from(status: WithdrawalStatus) -> Self {
+        match status {
+            WithdrawalStatus::Queued => Self::Queued,
+            WithdrawalStatus::Signed => Self::Signed,
+            WithdrawalStatus::Broadcast => Self::Broadcast,
+            WithdrawalStatus::ConfirmedXmr => Self::ConfirmedXmr,
+            WithdrawalStatus::ConfirmedEvm => Self::ConfirmedEvm,
+            WithdrawalStatus::Failed => Self::Failed,
+            WithdrawalStatus::Rejected => Self::Rejected,
+        }
+    }
+}
+
+impl From<Withdrawal> for proto::Withdrawal {
+    fn from(withdrawal: Withdrawal) -> Self {
+        Self {
+            burn_id: withdrawal.burn_id,
+            user: withdrawal.user.to_string(),
+            lp: withdrawal.lp.to_string(),
+            amount: withdrawal.amount,
+            xmr_address: withdrawal.xmr_address,
+            unichain_tx_hash: withdrawal.unichain_tx_hash.to_string(),
+            unichain_block: withdrawal.unichain_block,
+            status: proto::WithdrawalStatus::from(withdrawal.status).into(),
+            error: withdrawal.error,
+            attempts: withdrawal.attempts,
+            xmr_tx_hash: withdrawal.xmr_tx_hash.map(|hash| hash.to_string()),
+            xmr_tx_key: withdrawal.xmr_tx_key,
+            xmr_fee: withdrawal.xmr_fee,
+            xmr_amount: withdrawal.xmr_amount,
+        }
+    }
+}
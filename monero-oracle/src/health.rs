@@ -0,0 +1,267 @@
+//! Liveness and readiness checks for `/healthz` and `/readyz`
+//!
+//! Liveness only asks whether the poll loop is still turning, so an
+//! orchestrator restarts a wedged process but not one waiting out an RPC
+//! outage. Readiness also requires both RPC endpoints to answer, the oracle
+//! wallet to hold enough ETH for gas and the contract to be close to the
+//! Monero tip.
+
+use crate::api::OracleStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Thresholds for the health checks
+#[derive(Debug, Clone, Copy)]
+pub struct HealthConfig {
+    /// Longest time without a completed poll before the loop counts as stuck
+    pub max_poll_age: Duration,
+    /// Largest acceptable Monero tip minus contract tip
+    pub max_lag: u64,
+    /// Least ETH the oracle wallet needs (`BALANCE_PAUSE_ETH`); `None` skips
+    /// the check (signers never send transactions)
+    pub min_balance_eth: Option<f64>,
+}
+
+/// Oracle wallet balances that change how the oracle behaves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BalanceThresholds {
+    /// Below this, warn and alert but keep posting
+    pub warn_eth: f64,
+    /// Below this, stop sending transactions until the wallet is refunded
+    pub pause_eth: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceLevel {
+    Ok,
+    Low,
+    Paused,
+}
+
+impl BalanceThresholds {
+    pub fn level(&self, balance_eth: f64) -> BalanceLevel {
+        if balance_eth < self.pause_eth {
+            BalanceLevel::Paused
+        } else if balance_eth < self.warn_eth {
+            BalanceLevel::Low
+        } else {
+            BalanceLevel::Ok
+        }
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, ok: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// `/healthz` and `/readyz` body; served with 503 unless `healthy`
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub checks: Vec<Check>,
+}
+
+impl HealthReport {
+    fn new(checks: Vec<Check>) -> Self {
+        Self {
+            healthy: checks.iter().all(|check| check.ok),
+            checks,
+        }
+    }
+}
+
+/// Whether the poll loop completed a cycle recently; before the first one,
+/// the service gets `max_poll_age` from `started_at`
+pub fn liveness(
+    status: &OracleStatus,
+    started_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    config: &HealthConfig,
+) -> HealthReport {
+    HealthReport::new(vec![poll_check(status, started_at, now, config)])
+}
+
+/// Liveness plus RPC reachability, wallet balance and lag. `monero` and
+/// `unichain` are the results of probing each endpoint.
+pub fn readiness(
+    status: &OracleStatus,
+    started_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    config: &HealthConfig,
+    monero: anyhow::Result<()>,
+    unichain: anyhow::Result<()>,
+) -> HealthReport {
+    let mut checks = vec![
+        poll_check(status, started_at, now, config),
+        rpc_check("monero_rpc", monero),
+        rpc_check("unichain_rpc", unichain),
+    ];
+
+    if let Some(min_balance) = config.min_balance_eth {
+        checks.push(match status.balance_eth {
+            Some(balance) => Check::new(
+                "balance",
+                balance >= min_balance,
+                format!("{:.6} ETH (minimum {})", balance, min_balance),
+            ),
+            None => Check::new("balance", false, "not checked yet"),
+        });
+    }
+
+    checks.push(match (status.monero_tip, status.contract_latest_block) {
+        (Some(tip), Some(latest)) => {
+            let lag = tip.saturating_sub(latest);
+            Check::new(
+                "lag",
+                lag <= config.max_lag,
+                format!("{} blocks (maximum {})", lag, config.max_lag),
+            )
+        }
+        _ => Check::new("lag", false, "not polled yet"),
+    });
+
+    HealthReport::new(checks)
+}
+
+fn poll_check(
+    status: &OracleStatus,
+    started_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    config: &HealthConfig,
+) -> Check {
+    let since = status.last_poll_at.unwrap_or(started_at);
+    let age = (now - since).to_std().unwrap_or_default();
+    let detail = match status.last_poll_at {
+        Some(_) => format!("last poll {}s ago", age.as_secs()),
+        None => format!("started {}s ago, no poll yet", age.as_secs()),
+    };
+    Check::new("poll_loop", age <= config.max_poll_age, detail)
+}
+
+fn rpc_check(name: &'static str, result: anyhow::Result<()>) -> Check {
+    match result {
+        Ok(()) => Check::new(name, true, "reachable"),
+        Err(e) => Check::new(name, false, format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> HealthConfig {
+        HealthConfig {
+            max_poll_age: Duration::from_secs(600),
+            max_lag: 30,
+            min_balance_eth: Some(0.001),
+        }
+    }
+
+    fn polled(at: DateTime<Utc>) -> OracleStatus {
+        OracleStatus {
+            monero_tip: Some(3_100_020),
+            contract_latest_block: Some(3_100_005),
+            balance_eth: Some(0.05),
+            last_poll_at: Some(at),
+            ..OracleStatus::default()
+        }
+    }
+
+    #[test]
+    fn test_balance_levels() {
+        let thresholds = BalanceThresholds {
+            warn_eth: 0.01,
+            pause_eth: 0.001,
+        };
+        assert_eq!(thresholds.level(0.5), BalanceLevel::Ok);
+        assert_eq!(thresholds.level(0.01), BalanceLevel::Ok);
+        assert_eq!(thresholds.level(0.005), BalanceLevel::Low);
+        assert_eq!(thresholds.level(0.0009), BalanceLevel::Paused);
+        assert_eq!(thresholds.level(0.0), BalanceLevel::Paused);
+    }
+
+    #[test]
+    fn test_liveness_tracks_poll_age() {
+        let started = Utc::now();
+        let fresh = OracleStatus::default();
+
+        let report = liveness(
+            &fresh,
+            started,
+            started + chrono::Duration::seconds(60),
+            &config(),
+        );
+        assert!(report.healthy);
+
+        let report = liveness(
+            &fresh,
+            started,
+            started + chrono::Duration::hours(1),
+            &config(),
+        );
+        assert!(!report.healthy);
+
+        let status = polled(started + chrono::Duration::minutes(55));
+        let report = liveness(
+            &status,
+            started,
+            started + chrono::Duration::hours(1),
+            &config(),
+        );
+        assert!(report.healthy);
+    }
+
+    #[test]
+    fn test_readiness_checks() {
+        let now = Utc::now();
+        let status = polled(now);
+
+        let report = readiness(&status, now, now, &config(), Ok(()), Ok(()));
+        assert!(report.healthy);
+        assert_eq!(report.checks.len(), 5);
+
+        let report = readiness(
+            &status,
+            now,
+            now,
+            &config(),
+            Ok(()),
+            Err(anyhow::anyhow!("connection refused")),
+        );
+        assert!(!report.healthy);
+        assert!(report
+            .checks
+            .iter()
+            .any(|c| c.name == "unichain_rpc" && !c.ok));
+
+        let mut broke = status.clone();
+        broke.balance_eth = Some(0.0001);
+        assert!(!readiness(&broke, now, now, &config(), Ok(()), Ok(())).healthy);
+
+        // Signers don't need gas
+        let signer = HealthConfig {
+            min_balance_eth: None,
+            ..config()
+        };
+        assert!(readiness(&broke, now, now, &signer, Ok(()), Ok(())).healthy);
+
+        let mut lagging = status;
+        lagging.contract_latest_block = Some(3_099_900);
+        assert!(!readiness(&lagging, now, now, &config(), Ok(()), Ok(())).healthy);
+    }
+}
@@ -0,0 +1,37 @@
+//! On-chain heartbeat proving the oracle is alive between postings
+//!
+//! While the bridge is idle, the oracle may post nothing for hours (see
+//! [`crate::cadence`]), and an outage looks the same as a quiet chain. With
+//! `HEARTBEAT_INTERVAL_SECS`, the oracle calls the contract's `heartbeat()`
+//! whenever that long has passed without it posting a block or sending a
+//! heartbeat. The contract's `lastOracleActivity()` returns the later of the
+//! two, so a downstream contract (or anyone reading the chain) can tell the
+//! oracle is down once it is older than the interval plus some slack,
+//! without trusting the oracle's own reports.
+//!
+//! The check reads `lastOracleActivity()` rather than local state, so it
+//! holds across restarts and among redundant instances.
+
+use std::time::Duration;
+
+/// Whether a heartbeat is due at `now`, given the contract's
+/// `lastOracleActivity()`; both are Unix times in seconds
+pub fn is_due(last_activity: u64, now: u64, interval: Duration) -> bool {
+    now.saturating_sub(last_activity) >= interval.as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let hour = Duration::from_secs(3600);
+        assert!(!is_due(1_700_000_000, 1_700_000_000 + 3599, hour));
+        assert!(is_due(1_700_000_000, 1_700_000_000 + 3600, hour));
+        // Never active
+        assert!(is_due(0, 1_700_000_000, hour));
+        // A Unichain clock ahead of ours
+        assert!(!is_due(1_700_000_100, 1_700_000_000, hour));
+    }
+}
@@ -0,0 +1,129 @@
+//! Timeouts and connection pooling for the HTTP clients
+//!
+//! reqwest's defaults never time out: a node that accepts a connection and
+//! then goes quiet holds the request, and the poll waiting on it, forever.
+//! The Monero and Unichain clients are each built from their own
+//! [`HttpSettings`] (`MONERO_RPC_*` and `UNICHAIN_RPC_*`), with defaults
+//! that fail a dead connection in well under a poll.
+
+use anyhow::Result;
+use reqwest::{Client, ClientBuilder};
+use std::{fmt, time::Duration};
+
+/// Settings for one HTTP client; `None` keeps reqwest's default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HttpSettings {
+    /// Time to establish a connection, including TLS
+    pub connect_timeout: Option<Duration>,
+    /// Longest wait for the next bytes of a response; a large response that
+    /// keeps streaming isn't cut off
+    pub read_timeout: Option<Duration>,
+    /// Idle connections kept open per host
+    pub pool_max_idle: Option<usize>,
+    /// How long an idle connection is kept before it is closed
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keep-alive probes on open connections, so a peer that vanished
+    /// without closing them is noticed
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl HttpSettings {
+    /// monerod: `get_blocks.bin` answers for a catch-up can take a while to
+    /// start on a busy node
+    pub const MONERO: Self = Self {
+        connect_timeout: Some(Duration::from_secs(10)),
+        read_timeout: Some(Duration::from_secs(120)),
+        pool_max_idle: None,
+        pool_idle_timeout: Some(Duration::from_secs(90)),
+        tcp_keepalive: Some(Duration::from_secs(60)),
+    };
+
+    /// Unichain: small JSON-RPC calls
+    pub const UNICHAIN: Self = Self {
+        connect_timeout: Some(Duration::from_secs(10)),
+        read_timeout: Some(Duration::from_secs(30)),
+        pool_max_idle: None,
+        pool_idle_timeout: Some(Duration::from_secs(90)),
+        tcp_keepalive: Some(Duration::from_secs(60)),
+    };
+
+    /// Apply these settings to `builder`
+    pub fn apply(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        builder.tcp_keepalive(self.tcp_keepalive)
+    }
+
+    pub fn client(&self) -> Result<Client> {
+        Ok(self.apply(Client::builder()).build()?)
+    }
+}
+
+impl fmt::Display for HttpSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = |duration: Option<Duration>| match duration {
+            Some(duration) => format!("{}s", duration.as_secs()),
+            None => "none".to_string(),
+        };
+        write!(
+            f,
+            "connect {}, read {}, keep-alive {}, idle connections closed after {}",
+            secs(self.connect_timeout),
+            secs(self.read_timeout),
+            secs(self.tcp_keepalive),
+            secs(self.pool_idle_timeout)
+        )?;
+        if let Some(max) = self.pool_max_idle {
+            write!(f, ", at most {} idle per host", max)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Read, net::TcpListener, time::Instant};
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        // Accepts the request and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/json_rpc", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf);
+            std::thread::sleep(Duration::from_secs(5));
+        });
+
+        let client = HttpSettings {
+            read_timeout: Some(Duration::from_millis(200)),
+            ..HttpSettings::MONERO
+        }
+        .client()
+        .unwrap();
+        let started = Instant::now();
+        let error = client.post(url).send().await.unwrap_err();
+        assert!(error.is_timeout());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            HttpSettings::UNICHAIN.to_string(),
+            "connect 10s, read 30s, keep-alive 60s, idle connections closed after 90s"
+        );
+    }
+}
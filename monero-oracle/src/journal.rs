@@ -0,0 +1,253 @@
+//! Operation journal: idempotency keys for actions with on-chain effects
+//!
+//! Before the oracle posts a block, mints a deposit, confirms a withdrawal or
+//! pays one out on Monero, it writes an intent to the `operations` table
+//! under a key derived only from what the action does, then records every
+//! transaction broadcast for it and marks it complete once one is confirmed.
+//! Retrying the same action always lands on the same key. An intent left
+//! open by a crash is found again on restart, and whichever transaction of
+//! an earlier attempt made it on-chain is used instead of sending another.
+
+use alloy::primitives::{Address, B256};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// An action the oracle journals before taking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Intent {
+    /// `postMoneroBlock` (or its signed variant) to `contract`
+    PostBlock {
+        contract: Address,
+        height: u64,
+        block_hash: B256,
+    },
+    /// `mintDeposit` for a deposit output
+    Mint { tx_hash: B256, output_index: u64 },
+    /// `confirmWithdrawal` for a burn
+    ConfirmWithdrawal { burn_id: u64 },
+    /// One Monero transaction paying these burns, in burn order
+    Payout { burn_ids: Vec<u64> },
+}
+
+impl Intent {
+    /// Payout of `burn_ids`, whatever order they are paid in
+    pub fn payout(burn_ids: impl IntoIterator<Item = u64>) -> Self {
+        let mut burn_ids: Vec<u64> = burn_ids.into_iter().collect();
+        burn_ids.sort_unstable();
+        Self::Payout { burn_ids }
+    }
+
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Self::PostBlock { .. } => OperationKind::PostBlock,
+            Self::Mint { .. } => OperationKind::Mint,
+            Self::ConfirmWithdrawal { .. } => OperationKind::ConfirmWithdrawal,
+            Self::Payout { .. } => OperationKind::Payout,
+        }
+    }
+
+    /// Idempotency key: the same action always gets the same key
+    pub fn key(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Intent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PostBlock {
+                contract,
+                height,
+                block_hash,
+            } => write!(f, "post:{}:{}:{}", contract, height, block_hash),
+            Self::Mint {
+                tx_hash,
+                output_index,
+            } => write!(f, "mint:{}:{}", tx_hash, output_index),
+            Self::ConfirmWithdrawal { burn_id } => write!(f, "confirm-withdrawal:{}", burn_id),
+            Self::Payout { burn_ids } => {
+                let ids: Vec<String> = burn_ids.iter().map(u64::to_string).collect();
+                write!(f, "payout:{}", ids.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for Intent {
+    type Err = anyhow::Error;
+
+    fn from_str(key: &str) -> Result<Self> {
+        let (kind, rest) = key
+            .split_once(':')
+            .with_context(|| format!("Malformed operation key '{}'", key))?;
+        let parts: Vec<&str> = rest.split(':').collect();
+        let intent = match (kind.parse()?, parts.as_slice()) {
+            (OperationKind::PostBlock, [contract, height, block_hash]) => Self::PostBlock {
+                contract: contract.parse()?,
+                height: height.parse()?,
+                block_hash: block_hash.parse()?,
+            },
+            (OperationKind::Mint, [tx_hash, output_index]) => Self::Mint {
+                tx_hash: tx_hash.parse()?,
+                output_index: output_index.parse()?,
+            },
+            (OperationKind::ConfirmWithdrawal, [burn_id]) => Self::ConfirmWithdrawal {
+                burn_id: burn_id.parse()?,
+            },
+            (OperationKind::Payout, [burn_ids]) => Self::Payout {
+                burn_ids: burn_ids
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?,
+            },
+            _ => anyhow::bail!("Malformed operation key '{}'", key),
+        };
+        Ok(intent)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationKind {
+    #[serde(rename = "post")]
+    PostBlock,
+    Mint,
+    ConfirmWithdrawal,
+    Payout,
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PostBlock => "post",
+            Self::Mint => "mint",
+            Self::ConfirmWithdrawal => "confirm-withdrawal",
+            Self::Payout => "payout",
+        })
+    }
+}
+
+impl FromStr for OperationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "post" => Ok(Self::PostBlock),
+            "mint" => Ok(Self::Mint),
+            "confirm-withdrawal" => Ok(Self::ConfirmWithdrawal),
+            "payout" => Ok(Self::Payout),
+            other => anyhow::bail!("Unknown operation kind '{}'", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    /// Intent written; it may or may not have taken effect yet
+    Pending,
+    /// Known to have taken effect
+    Completed,
+    /// Known not to have taken effect; taking it again reopens it
+    Abandoned,
+}
+
+impl fmt::Display for OperationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pending => "pending",
+            Self::Completed => "completed",
+            Self::Abandoned => "abandoned",
+        })
+    }
+}
+
+impl FromStr for OperationStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "completed" => Ok(Self::Completed),
+            "abandoned" => Ok(Self::Abandoned),
+            other => anyhow::bail!("Unknown operation status '{}'", other),
+        }
+    }
+}
+
+/// A journal entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    pub key: String,
+    pub kind: OperationKind,
+    pub status: OperationStatus,
+    /// Transaction that carried the action out, once completed (a Monero tx
+    /// hash for payouts). `None` for actions found already taken.
+    pub tx_hash: Option<B256>,
+    /// Every transaction broadcast for it, oldest first
+    pub sent: Vec<B256>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Operation {
+    pub fn intent(&self) -> Result<Intent> {
+        self.key.parse()
+    }
+
+    /// Transactions that may have carried the action out, newest first
+    pub fn candidate_txs(&self) -> Vec<B256> {
+        self.tx_hash
+            .into_iter()
+            .chain(self.sent.iter().rev().copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_round_trip() {
+        let intents = [
+            Intent::PostBlock {
+                contract: Address::repeat_byte(0x11),
+                height: 3_100_000,
+                block_hash: B256::repeat_byte(0x22),
+            },
+            Intent::Mint {
+                tx_hash: B256::repeat_byte(0x33),
+                output_index: 1,
+            },
+            Intent::ConfirmWithdrawal { burn_id: 42 },
+            Intent::Payout {
+                burn_ids: vec![4, 7, 9],
+            },
+        ];
+        for intent in intents {
+            let key = intent.key();
+            assert!(key.starts_with(&format!("{}:", intent.kind())));
+            assert_eq!(key.parse::<Intent>().unwrap(), intent);
+        }
+        assert_eq!(Intent::payout([7, 4]).key(), "payout:4,7");
+    }
+
+    #[test]
+    fn test_rejects_malformed_keys() {
+        for key in [
+            "post:3100000",
+            "mint:0x00:1",
+            "refund:1",
+            "payout:",
+            "confirm-withdrawal:1:2",
+            "nonsense",
+        ] {
+            assert!(key.parse::<Intent>().is_err(), "{}", key);
+        }
+    }
+}
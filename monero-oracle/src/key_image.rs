@@ -0,0 +1,83 @@
+//! Key-image tracking for the bridge wallet's own outputs
+//!
+//! An output's key image is revealed when it is spent, but computing it needs
+//! the spend key, so the oracle learns the key images of the bridge's outputs
+//! from monero-wallet-rpc (`incoming_transfers`). Every scanned block's inputs
+//! are then matched against them. A bridge output spent by anything other than
+//! a recorded withdrawal payout means funds moved outside the oracle's control.
+
+use crate::monero_rpc::{parse_hex_to_b256, TransactionJson};
+use alloy::primitives::B256;
+use anyhow::Result;
+use serde::Serialize;
+
+/// An output owned by the bridge wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeOutput {
+    pub key_image: B256,
+    pub tx_hash: B256,
+    /// Amount in piconero
+    pub amount: u64,
+    /// Where the output was spent, if it has been
+    pub spent: Option<KeyImageSpend>,
+}
+
+/// A key image revealed by a transaction input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyImageSpend {
+    pub key_image: B256,
+    pub block_height: u64,
+    pub tx_hash: B256,
+}
+
+/// Every key image spent by the transactions of a block
+pub fn block_key_images(
+    block_height: u64,
+    transactions: &[(String, TransactionJson)],
+) -> Result<Vec<KeyImageSpend>> {
+    let mut spends = Vec::new();
+    for (tx_hash, tx) in transactions {
+        let tx_hash = parse_hex_to_b256(tx_hash)?;
+        for key_image in tx.key_images() {
+            spends.push(KeyImageSpend {
+                key_image: parse_hex_to_b256(key_image)?,
+                block_height,
+                tx_hash,
+            });
+        }
+    }
+    Ok(spends)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_key_images() {
+        let tx: TransactionJson = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "vin": [
+                { "key": { "amount": 0, "key_offsets": [1, 2], "k_image": "aa".repeat(32) } },
+                { "key": { "amount": 0, "key_offsets": [3, 4], "k_image": "bb".repeat(32) } }
+            ],
+            "vout": [],
+            "extra": []
+        }))
+        .unwrap();
+        let miner_tx: TransactionJson = serde_json::from_value(serde_json::json!({
+            "vin": [{ "gen": { "height": 100 } }],
+            "vout": []
+        }))
+        .unwrap();
+
+        let spends =
+            block_key_images(100, &[("cc".repeat(32), tx), ("dd".repeat(32), miner_tx)]).unwrap();
+        assert_eq!(spends.len(), 2);
+        assert_eq!(spends[1].key_image, B256::repeat_byte(0xbb));
+        assert_eq!(spends[1].tx_hash, B256::repeat_byte(0xcc));
+        assert_eq!(spends[1].block_height, 100);
+    }
+}
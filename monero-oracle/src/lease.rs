@@ -0,0 +1,209 @@
+//! Leader election among redundant oracle instances
+//!
+//! Several instances can run against one contract with the same key, each
+//! with its own Monero node and database, and `LEASE_INSTANCE_ID` set to a
+//! name unique to it. They take turns through a lease on the contract:
+//! `acquireLease(holder, duration)` succeeds for the instance holding it, or
+//! for any once it has run out, so one instance at a time gets it. Only the
+//! holder posts, rolls back, mints and pays withdrawals; the others index
+//! the blocks it posts, so their databases are current when they take over.
+//!
+//! The leader renews the lease at the first poll after half of it has gone.
+//! It stops sending once the lease is about to run out by its own clock,
+//! whether or not a renewal went through, so two instances never send at
+//! once. A standby polls again as the lease runs out and takes it if it
+//! wasn't renewed: a leader that dies is replaced within `LEASE_DURATION_SECS`.
+//! Draining releases the lease, for an immediate handover.
+
+use alloy::primitives::{keccak256, B256};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The lease is treated as lost this long before it runs out, leaving a
+/// renewal time to land and covering a lagging block timestamp
+pub const MARGIN: Duration = Duration::from_secs(15);
+
+/// Shortest `LEASE_DURATION_SECS` accepted
+pub const MIN_DURATION: Duration = Duration::from_secs(60);
+
+/// Longest lease the contract grants (`MAX_LEASE_DURATION`)
+pub const MAX_DURATION: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaseConfig {
+    /// `LEASE_INSTANCE_ID`, unique to each instance
+    pub instance: String,
+    pub duration: Duration,
+}
+
+impl LeaseConfig {
+    /// This instance's lease holder ID on the contract
+    pub fn holder(&self) -> B256 {
+        keccak256(self.instance.as_bytes())
+    }
+}
+
+/// What to do about the lease at a poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// This instance holds the lease with over half of it left
+    Lead,
+    /// Take the lease, or renew it; `takeover` if another instance held it
+    /// last
+    Acquire { takeover: bool },
+    /// Another instance holds the lease for `remaining` more
+    Standby { holder: B256, remaining: Duration },
+}
+
+/// This instance's view of the lease, given what `getLease` returned
+pub struct Lease {
+    config: LeaseConfig,
+    holder: B256,
+    /// When this instance's lease runs out, by the local clock
+    held_until: Mutex<Option<Instant>>,
+    /// When another instance's lease runs out
+    standby_until: Mutex<Option<Instant>>,
+}
+
+impl Lease {
+    pub fn new(config: LeaseConfig) -> Self {
+        Self {
+            holder: config.holder(),
+            config,
+            held_until: Mutex::default(),
+            standby_until: Mutex::default(),
+        }
+    }
+
+    pub fn holder(&self) -> B256 {
+        self.holder
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.config.instance
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.config.duration
+    }
+
+    /// Decide on the lease `holder` has for `remaining` more
+    pub fn decide(&self, holder: B256, remaining: Duration) -> Decision {
+        if holder == self.holder {
+            if remaining > self.config.duration / 2 {
+                Decision::Lead
+            } else {
+                Decision::Acquire { takeover: false }
+            }
+        } else if remaining.is_zero() {
+            Decision::Acquire {
+                takeover: holder != B256::ZERO,
+            }
+        } else {
+            Decision::Standby { holder, remaining }
+        }
+    }
+
+    /// Record the lease as held for `remaining` from `since`
+    pub fn hold(&self, since: Instant, remaining: Duration) {
+        *self.held_until.lock().expect("lease poisoned") = Some(since + remaining);
+        *self.standby_until.lock().expect("lease poisoned") = None;
+    }
+
+    /// Record another instance as holding the lease for `remaining`
+    pub fn stand_by(&self, remaining: Duration) {
+        *self.held_until.lock().expect("lease poisoned") = None;
+        *self.standby_until.lock().expect("lease poisoned") = Some(Instant::now() + remaining);
+    }
+
+    /// Whether this instance may send, with [`MARGIN`] left on its lease
+    pub fn is_held(&self) -> bool {
+        self.held_until
+            .lock()
+            .expect("lease poisoned")
+            .is_some_and(|until| Instant::now() + MARGIN < until)
+    }
+
+    pub fn ensure_held(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.is_held(),
+            "Not sending: instance '{}' doesn't hold the lease",
+            self.config.instance
+        );
+        Ok(())
+    }
+
+    /// When a standby should poll to take over, if the leader doesn't renew
+    pub fn takeover_at(&self) -> Option<Instant> {
+        *self.standby_until.lock().expect("lease poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lease(instance: &str) -> Lease {
+        Lease::new(LeaseConfig {
+            instance: instance.to_string(),
+            duration: Duration::from_secs(240),
+        })
+    }
+
+    #[test]
+    fn test_decide() {
+        let primary = lease("primary");
+        let standby = lease("standby");
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+
+        // Nobody has held it yet
+        assert_eq!(
+            primary.decide(B256::ZERO, Duration::ZERO),
+            Decision::Acquire { takeover: false }
+        );
+        // Renewed once half of it has gone
+        assert_eq!(primary.decide(primary.holder(), minutes(3)), Decision::Lead);
+        assert_eq!(
+            primary.decide(primary.holder(), minutes(1)),
+            Decision::Acquire { takeover: false }
+        );
+        // Restarted after it ran out, with the same database
+        assert_eq!(
+            primary.decide(primary.holder(), Duration::ZERO),
+            Decision::Acquire { takeover: false }
+        );
+
+        assert_eq!(
+            standby.decide(primary.holder(), minutes(1)),
+            Decision::Standby {
+                holder: primary.holder(),
+                remaining: minutes(1)
+            }
+        );
+        assert_eq!(
+            standby.decide(primary.holder(), Duration::ZERO),
+            Decision::Acquire { takeover: true }
+        );
+    }
+
+    #[test]
+    fn test_held_until_margin() {
+        let lease = lease("primary");
+        assert!(!lease.is_held());
+        assert!(lease.ensure_held().is_err());
+
+        lease.hold(Instant::now(), Duration::from_secs(240));
+        assert!(lease.is_held());
+        assert_eq!(lease.takeover_at(), None);
+
+        // Too close to running out to send
+        lease.hold(Instant::now(), MARGIN);
+        assert!(!lease.is_held());
+
+        lease.stand_by(Duration::from_secs(30));
+        assert!(!lease.is_held());
+        assert!(lease.takeover_at().is_some());
+    }
+}
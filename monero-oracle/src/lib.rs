@@ -0,0 +1,131 @@
+//! Monero Oracle for zeroXMR on Unichain
+//!
+//! The `monero-oracle` binary is a thin wrapper around this crate; the Monero
+//! RPC client, Merkle tree code and contract bindings are public so other
+//! tooling (claim CLIs, indexers, integration tests) can reuse them.
+//!
+//! - [`config`] - oracle configuration
+//! - [`settings`] - environment variables layered over `config.toml`
+//! - [`reload`] - applies config file changes without a restart
+//! - [`monero_rpc`] - monerod RPC client and block/transaction types
+//! - [`monero_tx`] - transaction decoding from raw blobs
+//! - [`fork_choice`] - heaviest-chain selection across monerod nodes
+//! - [`zmq`] - new-block notifications from monerod's ZMQ publisher
+//! - [`digest_auth`] - digest authentication for monerod's `--rpc-login`
+//! - [`epee`] - monerod's binary serialization, used by the `.bin` endpoints
+//! - [`fixtures`] - recorded monerod responses for offline regression tests
+//! - [`cadence`] - which blocks to post while the bridge is idle
+//! - [`merkle`] - tx and output Merkle roots posted for each block
+//! - [`proof`] - inclusion proofs against those roots
+//! - [`audit`] - reconciliation of the chain, the contract and the local database
+//! - [`snapshot`] - signed exports of the posted chain for bootstrapping an instance
+//! - [`block_export`] - canonical JSON of every processed block, for independent verification
+//! - [`archive`] - object storage for block exports and database backups, with retention
+//! - [`s3`] - S3-compatible and Google Cloud Storage buckets
+//! - [`pow`] - optional RandomX proof-of-work check of posted blocks
+//! - [`scanner`] - view-key detection of deposits to the bridge
+//! - [`tx_extra`] - tx public keys, payment IDs and other `tx_extra` fields
+//! - [`subaddress`] - per-user deposit subaddresses
+//! - [`deposit_policy`] - deposit limits that hold deposits for review instead of minting
+//! - [`price`] - XMR/USD rate from Chainlink or an HTTP source
+//! - [`bridge_fee`] - fees taken from mints and payouts, swept to a treasury
+//! - [`withdrawal`] - burns queued for payout on Monero
+//! - [`monero_wallet`] - monero-wallet-rpc client that pays withdrawals out
+//! - [`multisig`] - M-of-N multisig bridge wallet and its signing rounds
+//! - [`watch_only`] - view-only wallet files and output / key image exports for official tooling
+//! - [`fees`] - XMR fee quotes for withdrawal payouts
+//! - [`decoys`] - wallet2-style ring member selection for withdrawal transactions
+//! - [`key_image`] - spend tracking for the bridge wallet's own outputs
+//! - [`error`] - error classes that retries, alerts and metrics act on
+//! - [`retry`] - backoff for transient Monero and Unichain RPC failures
+//! - [`rate_limit`] - request rate and concurrency limits for public monerod nodes
+//! - [`http`] - timeouts and connection pooling for the Monero and Unichain clients
+//! - [`attestation`] - EIP-712 block attestations for multi-signature mode
+//! - [`frost`] - FROST threshold signatures over block attestations
+//! - [`gossip`] - attestations gossiped among multi-signature operators over libp2p
+//! - [`eth`] - WrappedMonero binding and fee-managed transaction sending
+//! - [`revert`] - contract revert reasons and the recovery each calls for
+//! - [`receipts`] - posting receipts re-checked after Unichain reorgs
+//! - [`costs`] - gas and XMR fee accounting behind the `report` command
+//! - [`calldata`] - contract calls written out for a multisig or manual send
+//! - [`safe`] - block postings proposed to a Safe multisig holding the oracle role
+//! - [`events`] - contract event subscription over WebSocket
+//! - [`admin`] - authenticated control actions for a running oracle
+//! - [`grpc`] - typed, streaming query API (with the `grpc` feature)
+//! - [`feed`] - live bridge events streamed by the HTTP API
+//! - [`health`] - liveness and readiness checks for the HTTP API
+//! - [`journal`] - idempotency keys for on-chain actions, kept across restarts
+//! - [`migrations`] - versioned schema migrations for the database
+//! - [`lease`] - leader election among redundant oracle instances
+//! - [`heartbeat`] - on-chain liveness signal while no block needs posting
+//! - [`nonce`] - nonce tracking and replacement of stuck oracle transactions
+//! - [`signer`] - oracle key, local or held in AWS / Google Cloud KMS
+//! - [`oracle`] - the polling service that keeps the contract in sync
+//! - [`alerts`] - webhook notifications for events that need an operator
+//! - [`systemd`] - readiness and watchdog notifications under systemd
+
+pub mod admin;
+pub mod alerts;
+pub mod api;
+pub mod archive;
+pub mod attestation;
+pub mod audit;
+pub mod block_export;
+pub mod bridge_fee;
+pub mod cadence;
+pub mod calldata;
+pub mod config;
+pub mod costs;
+pub mod decoys;
+pub mod deposit_policy;
+pub mod digest_auth;
+pub mod epee;
+pub mod error;
+pub mod eth;
+pub mod events;
+pub mod feed;
+pub mod fees;
+pub mod fixtures;
+pub mod fork_choice;
+pub mod frost;
+pub mod gas;
+pub mod gossip;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod heartbeat;
+pub mod http;
+pub mod journal;
+pub mod key_image;
+pub mod lease;
+pub mod merkle;
+pub mod metrics;
+pub mod migrations;
+pub mod monero_rpc;
+pub mod monero_tx;
+pub mod monero_wallet;
+pub mod multisig;
+pub mod nonce;
+pub mod oracle;
+pub mod pow;
+pub mod price;
+pub mod proof;
+pub mod rate_limit;
+pub mod receipts;
+pub mod reload;
+pub mod reorg;
+pub mod retry;
+pub mod revert;
+pub mod s3;
+pub mod safe;
+pub mod scanner;
+pub mod settings;
+pub mod signer;
+pub mod snapshot;
+pub mod storage;
+pub mod subaddress;
+pub mod systemd;
+pub mod tx_extra;
+pub mod watch_only;
+pub mod withdrawal;
+pub mod zmq;
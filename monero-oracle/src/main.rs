@@ -5,799 +5,1130 @@
 //!
 //! # Usage
 //! ```bash
-//! cargo run --release
+//! cargo run --release            # same as `run`
+//! cargo run --release -- run
+//! cargo run --release -- --help
+//!
+//! # Operator commands
+//! cargo run --release -- status
+//! cargo run --release -- post-block <height>
+//! cargo run --release -- backfill <from> <to>
+//! cargo run --release -- calldata <from> [to] [--out calls.jsonl]
+//! cargo run --release -- watch [--from <height>]
+//! cargo run --release -- rescan --from <height> [--to <height>]
+//! cargo run --release -- export-snapshot snapshot.json
+//! cargo run --release -- import-snapshot snapshot.json --signer <address>
+//! cargo run --release -- db status
+//! cargo run --release -- db migrate
+//! cargo run --release -- backup-db
+//! cargo run --release -- restore-db [--key <key>] [--list]
+//! cargo run --release -- fee-report --month 2025-01
+//! cargo run --release -- sweep-fees [--dry-run]
+//!
+//! # Merkle proofs for minting
+//! cargo run --release -- prove-tx <tx_hash>
+//! cargo run --release -- prove-output <tx_hash> <output_index>
+//! cargo run --release -- prove-tx <tx_hash> | cargo run --release -- verify-proof
+//! cargo run --release -- verify-export exports/0003100000.json
+//!
+//! # Multisig bridge wallet setup, on every operator's wallet
+//! cargo run --release -- multisig-prepare
+//! cargo run --release -- multisig-make --threshold 2 <info> <info>
+//! cargo run --release -- multisig-exchange <info> <info>   # until it prints no info
+//! cargo run --release -- multisig-status
+//!
+//! # Threshold key for signing block attestations, generated once by all operators
+//! cargo run --release -- frost-dkg-start --identifier 1 --threshold 2 --participants 3
+//! cargo run --release -- frost-dkg-shares --identifier 1 frost-dkg-round1-2.json frost-dkg-round1-3.json
+//! cargo run --release -- frost-dkg-finish --identifier 1 frost-dkg-share-2-to-1.json frost-dkg-share-3-to-1.json
+//!
+//! # Watch-only wallet for cross-checking balances
+//! cargo run --release -- export-outputs outputs
+//! cargo run --release -- export-key-images key-images.json
+//! cargo run --release -- watch-only-wallet --wallet-rpc <url> --filename bridge-watch [--restore-height <height>] [--key-images key-images.json]
+//!
+//! # Regression fixtures
+//! cargo run --release -- record-fixture <height>
+//!
+//! # Per-user deposit subaddresses
+//! cargo run --release -- subaddress <minor_index>
+//! cargo run --release -- deposits <minor_index>
+//! cargo run --release -- register <minor_index> <unichain_address>
 //! ```
 //!
-//! # Environment Variables
-//! - `ORACLE_PRIVATE_KEY` - Private key of oracle account
+//! # Configuration
+//! Every setting below can also be written in a TOML file (`--config`,
+//! `CONFIG_FILE`, or `config.toml` in the working directory); see
+//! `config.example.toml`. Environment variables take precedence. The poll
+//! interval, log level, alert and gas settings are re-read from the file
+//! while the oracle runs.
+//!
+//! - `CONFIG_FILE` - TOML config file (default: config.toml if present)
+//! - `PRIVATE_KEY` - Private key of oracle account (local signer)
+//! - `ORACLE_SIGNER` - local, keystore, aws-kms or gcp-kms; see README "KMS signer" (default: local, or keystore if `KEYSTORE_PATH` is set)
+//! - `KEYSTORE_PATH` - Encrypted JSON keystore holding the oracle key (keystore)
+//! - `KEYSTORE_PASSWORD_FILE` - File containing the keystore password (default: prompt)
+//! - `STANDBY_PRIVATE_KEY` / `STANDBY_KEYSTORE_PATH` - Key to switch to if the oracle role moves to it (default: none)
+//! - `STANDBY_KEYSTORE_PASSWORD_FILE` - File containing the standby keystore password (default: prompt)
+//! - `SAFE_ADDRESS` - Safe holding the oracle role; postings are proposed to it instead of sent (default: none)
+//! - `SAFE_TX_SERVICE_URL` - Safe Transaction Service the proposals go to (required with `SAFE_ADDRESS`)
+//! - `AWS_KMS_KEY_ID` / `AWS_KMS_REGION` / `AWS_KMS_PROFILE` - AWS KMS key and credentials (aws-kms)
+//! - `GCP_KMS_PROJECT` / `GCP_KMS_LOCATION` / `GCP_KMS_KEY_RING` / `GCP_KMS_KEY` / `GCP_KMS_KEY_VERSION` - Google Cloud KMS key (gcp-kms)
 //! - `BRIDGE_ADDRESS` - Address of WrappedMonero contract
+//! - `EXTRA_BRIDGE_ADDRESSES` - Further contracts every block is also posted to, comma-separated (default: none)
 //! - `UNICHAIN_RPC_URL` - Unichain RPC URL (default: https://mainnet.unichain.org)
-//! - `MONERO_RPC_URL` - Monero RPC URL (default: http://xmr.privex.io:18081)
+//! - `UNICHAIN_RPC_CONNECT_TIMEOUT_SECS`, `UNICHAIN_RPC_READ_TIMEOUT_SECS` - Unichain connect and read timeouts; 0 waits forever (default: 10, 30)
+//! - `UNICHAIN_RPC_POOL_MAX_IDLE`, `UNICHAIN_RPC_POOL_IDLE_SECS`, `UNICHAIN_RPC_KEEPALIVE_SECS` - Unichain connection pooling and TCP keep-alive (default: unlimited, 90, 60)
+//! - `UNICHAIN_WS_URL` - Unichain WebSocket URL for following contract events live (default: none)
+//! - `MONERO_RPC_URL` - Monero RPC URL (default: http://xmr.privex.io:18081 on mainnet, local monerod otherwise)
+//! - `MONERO_RPC_FALLBACK_URLS` - Comma-separated extra monerod nodes; polls follow the heaviest chain (default: none)
+//! - `MONERO_QUORUM` - Nodes that must agree on a block's hash before it is posted (default: 1)
+//! - `MONERO_ZMQ_URL` - monerod `--zmq-pub` endpoint; new blocks trigger a poll immediately (default: none)
+//! - `MONERO_RPC_PROXY` - Proxy for monerod, socks5h:// for onion nodes (default: none)
+//! - `MONERO_RPC_CA_CERT` - PEM CA certificate trusted for an https:// monerod (default: system roots)
+//! - `MONERO_RPC_LOGIN` - user:password for a monerod started with `--rpc-login` (default: none)
+//! - `MONERO_RPC_RECORD_DIR` - Save every monerod response in this directory (default: none)
+//! - `MONERO_RPC_REPLAY_DIR` - Answer monerod requests from responses saved there, offline (default: none)
+//! - `RPC_MAX_RETRIES` - Retries for Monero and Unichain RPC calls that fail transiently (default: 3)
+//! - `RPC_RETRY_BASE_MS` - Initial retry backoff in milliseconds, doubled per retry (default: 500)
+//! - `MONERO_RPC_MAX_RPS` - Requests per second to each monerod node, bursting up to one second's worth (default: unlimited)
+//! - `MONERO_RPC_MAX_CONCURRENT` - Requests in flight to each monerod node (default: unlimited)
+//! - `MONERO_RPC_DELAY_MS` - Minimum gap between requests to each monerod node (default: 0)
+//! - `MONERO_RPC_CONNECT_TIMEOUT_SECS`, `MONERO_RPC_READ_TIMEOUT_SECS` - monerod connect and read timeouts; 0 waits forever (default: 10, 120)
+//! - `MONERO_RPC_POOL_MAX_IDLE`, `MONERO_RPC_POOL_IDLE_SECS`, `MONERO_RPC_KEEPALIVE_SECS` - monerod connection pooling and TCP keep-alive (default: unlimited, 90, 60)
 //! - `POLL_INTERVAL_SECS` - Polling interval in seconds (default: 120)
-
-use alloy::{
-    network::EthereumWallet,
-    primitives::{Address, B256, U256},
-    providers::{Provider, ProviderBuilder},
-    signers::local::PrivateKeySigner,
-    sol,
-};
+//! - `LEASE_INSTANCE_ID` - Name unique to this instance; enables leader election among redundant instances (default: disabled)
+//! - `LEASE_DURATION_SECS` - Leader lease length (default: 3 x POLL_INTERVAL_SECS + 30)
+//! - `HEARTBEAT_INTERVAL_SECS` - Call the contract's `heartbeat()` after this long without a posting (default: disabled)
+//! - `POLL_TIMEOUT_SECS` - Cancel a poll still running after this long and reconnect to monerod; 0 disables (default: 600)
+//! - `LOG_LEVEL` - error, warn, info, debug or trace for the oracle's logs; `RUST_LOG` filters dependencies (default: info)
+//! - `MIN_CONFIRMATIONS` - Blocks required on top of a block before it is posted (default: 10)
+//! - `FETCH_CONCURRENCY` - Blocks fetched from monerod in parallel when catching up (default: 4)
+//! - `VERIFY_POW` - Check each block's RandomX proof of work before posting; needs the randomx feature (default: false)
+//! - `BRIDGE_VIEW_KEY` / `BRIDGE_SPEND_PUBLIC_KEY` - Bridge wallet keys for deposit scanning (default: disabled)
+//! - `SUBADDRESS_ACCOUNT` - Account whose subaddresses are assigned to users (default: 0)
+//! - `SUBADDRESS_LOOKAHEAD` - Subaddresses of that account watched for deposits (default: 1000)
+//! - `WATCH_WITHDRAWALS` - Queue withdrawals for burn events (default: false)
+//! - `WITHDRAWAL_START_BLOCK` - Unichain block to read burns from on first start (default: head)
+//! - `WITHDRAWAL_CONFIRMATIONS` - Unichain confirmations before a burn is queued (default: 10)
+//! - `MONERO_WALLET_RPC_URL` - monero-wallet-rpc that pays out withdrawals (default: disabled)
+//! - `WITHDRAWAL_MAX_ATTEMPTS` - Retries of a failing payout step before giving up (default: 5)
+//! - `WITHDRAWAL_FEE_PRIORITY` - unimportant, normal, elevated or priority for payouts (default: normal)
+//! - `WITHDRAWAL_DEDUCT_FEE` - Pay withdrawals net of the quoted XMR fee (default: true)
+//! - `WITHDRAWAL_BATCH_SIZE` - Most withdrawals paid by one Monero transaction, 1 to 15 (default: 15)
+//! - `WITHDRAWAL_BATCH_INTERVAL_SECS` - Time a partial batch waits after the previous payout (default: 0)
+//! - `MULTISIG_API_KEY` - Key shared by the operators of a multisig bridge wallet; enables co-signing (default: disabled)
+//! - `MULTISIG_PEERS` - Co-signers' HTTP API URLs, on the oracle paying withdrawals (default: none)
+//! - `ORACLE_MODE` - single, signer or submitter; see README "Multi-signature mode" (default: single)
+//! - `ATTESTATION_PEERS` - Comma-separated signer API URLs polled by the submitter (default: none)
+//! - `GOSSIP_LISTEN` - libp2p multiaddr to gossip attestations on; see README "Attestation gossip" (default: disabled)
+//! - `GOSSIP_PEERS` - Operators' multiaddrs to join the gossip network through (default: none)
+//! - `GOSSIP_KEY` - This operator's libp2p key, created on first start (default: gossip.key)
+//! - `FROST_KEY_SHARE` - This operator's threshold key share from `frost-dkg-finish`; see README "Threshold signing" (default: disabled)
+//! - `FROST_API_KEY` - Key shared by the threshold signers, required with `FROST_KEY_SHARE`
+//! - `FROST_PEERS` - Threshold signers' HTTP API URLs, on the submitter (default: none)
+//! - `MONERO_NETWORK` - mainnet, testnet or stagenet; checked against monerod and the wallet (default: mainnet)
+//! - `WATCH_MEMPOOL` - Report deposits still in the txpool as pending (default: false)
+//! - `IDLE_POST_INTERVAL_BLOCKS` - Post only every this many blocks while no deposits are pending (default: 1)
+//! - `CHECKPOINT_INTERVAL_BLOCKS` - Post only every this many blocks and blocks with deposits, filling skipped ones on request (default: off)
+//! - `MAX_CATCH_UP_BLOCKS` - Further behind than this, wait for `run --force-backfill` or `POST /admin/approve-catch-up` (default: no limit)
+//! - `AUTO_MINT` - Mint detected deposits to their registered recipient (default: false)
+//! - `MIN_DEPOSIT_XMR` / `MAX_DEPOSIT_XMR` - Hold smaller or larger deposits for review instead of minting them (default: no limit)
+//! - `MAX_DEPOSIT_PER_USER_XMR` - Hold deposits that take a recipient's mints over this in 24 hours (default: no limit)
+//! - `BRIDGE_FEE_BPS` - Basis points of each mint and withdrawal payout taken as a fee, at most 1000 (default: 0)
+//! - `BRIDGE_FEE_USD` - Dollars' worth of XMR taken from each mint and withdrawal payout (default: none)
+//! - `TREASURY_XMR_ADDRESS` - Monero address `sweep-fees` sends collected bridge fees to
+//! - `PRICE_FEED_ADDRESS` - Chainlink XMR/USD aggregator on Unichain the bridge fee is converted at
+//! - `PRICE_FEED_URL` / `PRICE_FEED_JSON_POINTER` - JSON endpoint and pointer to read the rate from instead (default pointer: /monero/usd)
+//! - `PRICE_MAX_AGE_SECS` - Refuse rates older than this (default: 86400)
+//! - `OUTPUT_MERKLE_HASHER` - keccak256, sha256 for legacy contracts, or v2 for version 2 output trees (default: keccak256)
+//! - `TX_MERKLE_HASHER` - keccak256, or v2 for domain-separated tx trees (default: keccak256)
+//! - `INDEX_OUTPUTS` - Store every output of posted blocks for proofs and lookups (default: true)
+//! - `REORG_TRACKED_BLOCKS` - Number of posted blocks kept for reorg checks (default: 64)
+//! - `REORG_CONFIRMATION_DEPTH` - Confirmations before a posted block is re-checked (default: 10)
+//! - `UNICHAIN_CONFIRMATION_DEPTH` - Unichain blocks before a posting's receipt is re-checked and reorged-out postings resubmitted; 0 disables (default: 60)
+//! - `DATABASE_PATH` - SQLite database recording posted blocks (default: oracle.db)
+//! - `DB_AUTO_MIGRATE` - Apply pending schema migrations at startup instead of refusing to run (default: true)
+//! - `BLOCK_EXPORT_TARGET` - Directory, `s3://bucket/prefix` or `gs://bucket/prefix` each processed block is written to as JSON (default: disabled)
+//! - `BLOCK_EXPORT_RETAIN_BLOCKS` - Delete exported blocks further below the latest than this (default: keep all)
+//! - `DB_BACKUP_TARGET` - Directory, `s3://` or `gs://` location the database is backed up to (default: disabled)
+//! - `DB_BACKUP_INTERVAL_SECS` / `DB_BACKUP_KEEP` - Time between backups and how many are kept (default: 86400 / 7)
+//! - `S3_REGION` / `S3_ENDPOINT` - Bucket region and S3-compatible endpoint (default: us-east-1, AWS's regional endpoint)
+//! - `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` - Access key for `s3://` targets
+//! - `GCS_HMAC_ACCESS_ID` / `GCS_HMAC_SECRET` - Google Cloud Storage HMAC key for `gs://` targets
+//! - `ORACLE_HTTP_PORT` - Serve the read-only HTTP API on this port (default: disabled)
+//! - `GRPC_PORT` - Serve the gRPC API on this port, in builds with the `grpc` feature (default: disabled)
+//! - `ADMIN_API_KEY` - Bearer key enabling the `/admin` endpoints of the HTTP API (default: disabled)
+//! - `METRICS_PORT` - Serve Prometheus metrics on this port (default: disabled)
+//! - `HEALTH_MAX_POLL_AGE_SECS` - Time without a completed poll before `/healthz` fails (default: 1800)
+//! - `HEALTH_MAX_LAG_BLOCKS` - Contract lag above which `/readyz` fails (default: 30)
+//! - `BALANCE_WARN_ETH` - Oracle balance below which it warns and alerts (default: 0.01)
+//! - `BALANCE_PAUSE_ETH` - Oracle balance below which block posting pauses (default: 0.001)
+//! - `ALERT_WEBHOOK_URL` - Webhook notified of low balance, failing polls, reorgs, lag and role mismatch (default: log only)
+//! - `ALERT_WEBHOOK_FORMAT` - generic, discord or telegram (default: detected from the URL)
+//! - `ALERT_TELEGRAM_CHAT_ID` - Chat for Telegram alerts
+//! - `ALERT_COOLDOWN_SECS` - Minimum time between repeats of an alert (default: 3600)
+//! - `ALERT_POLL_FAILURES` - Consecutive failed polls before alerting (default: 3)
+//! - `GAS_STRATEGY` - economy, normal or aggressive (default: normal)
+//! - `MAX_FEE_PER_GAS_GWEI` - Hard cap on max fee per gas (default: none)
+//! - `PRIORITY_FEE_GWEI` - Fixed priority fee instead of the fee history (default: none)
+//! - `FEE_BUMP_PERCENT` - Fee increase for each replacement transaction (default: 15)
+//! - `STUCK_TX_TIMEOUT_SECS` - Time before an unconfirmed transaction is replaced (default: 180)
+//! - `MAX_FEE_BUMPS` - Replacements attempted before giving up (default: 5)
+//! - `GAS_BUDGET_DAILY_ETH` / `GAS_BUDGET_WEEKLY_ETH` - Most ETH to spend on gas in any 24 hours / 7 days; posting pauses once used up (default: no limit)
+
+use alloy::primitives::{Address, B64};
 use anyhow::{Context, Result};
-use chrono::Utc;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-use sha3::Keccak256;
-use std::{env, time::Duration};
-use tokio::time::interval;
-use tracing::{error, info, warn};
-
-// ════════════════════════════════════════════════════════════════════════════
-// CONTRACT ABI
-// ════════════════════════════════════════════════════════════════════════════
-
-sol! {
-    #[sol(rpc)]
-    contract WrappedMonero {
-        address public oracle;
-        uint256 public latestMoneroBlock;
-
-        function postMoneroBlock(
-            uint256 blockHeight,
-            bytes32 blockHash,
-            bytes32 txMerkleRoot,
-            bytes32 outputMerkleRoot
-        ) external;
-
-        function transferOracle(address newOracle) external;
-    }
-}
-
-// ════════════════════════════════════════════════════════════════════════════
-// CONFIGURATION
-// ════════════════════════════════════════════════════════════════════════════
-
-#[derive(Debug, Clone)]
-struct Config {
-    oracle_private_key: String,
-    bridge_address: Address,
-    unichain_rpc_url: String,
-    monero_rpc_url: String,
-    poll_interval_secs: u64,
-}
-
-impl Config {
-    fn from_env() -> Result<Self> {
-        Ok(Self {
-            oracle_private_key: env::var("PRIVATE_KEY")
-                .context("PRIVATE_KEY not set (used for both deployment and oracle)")?,
-            bridge_address: env::var("BRIDGE_ADDRESS")
-                .context("BRIDGE_ADDRESS not set")?
-                .parse()
-                .context("Invalid BRIDGE_ADDRESS")?,
-            unichain_rpc_url: env::var("UNICHAIN_RPC_URL")
-                .unwrap_or_else(|_| "https://mainnet.unichain.org".to_string()),
-            monero_rpc_url: env::var("MONERO_RPC_URL")
-                .unwrap_or_else(|_| "http://xmr.privex.io:18081".to_string()),
-            poll_interval_secs: env::var("POLL_INTERVAL_SECS")
-                .unwrap_or_else(|_| "120".to_string())
-                .parse()
-                .unwrap_or(120),
-        })
-    }
-}
-
-// ════════════════════════════════════════════════════════════════════════════
-// MONERO RPC TYPES
-// ════════════════════════════════════════════════════════════════════════════
-
-#[derive(Debug, Serialize)]
-struct JsonRpcRequest<T> {
-    jsonrpc: &'static str,
-    id: &'static str,
-    method: &'static str,
-    params: T,
-}
-
-#[derive(Debug, Deserialize)]
-struct JsonRpcResponse<T> {
-    result: Option<T>,
-    error: Option<JsonRpcError>,
-}
-
-#[derive(Debug, Deserialize)]
-struct JsonRpcError {
-    message: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct BlockHeaderResponse {
-    block_header: BlockHeader,
-}
-
-#[derive(Debug, Deserialize)]
-struct BlockHeader {
-    height: u64,
-    hash: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct GetBlockResponse {
-    block_header: BlockHeader,
-    json: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct BlockJson {
-    tx_hashes: Option<Vec<String>>,
-}
-
-#[derive(Debug, Serialize)]
-struct GetTransactionsRequest {
-    txs_hashes: Vec<String>,
-    decode_as_json: bool,
-}
-
-#[derive(Debug, Deserialize)]
-struct GetTransactionsResponse {
-    status: String,
-    txs: Option<Vec<TransactionInfo>>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TransactionInfo {
-    tx_hash: String,
-    as_json: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct TransactionJson {
-    vout: Option<Vec<TxOutput>>,
-    rct_signatures: Option<RctSignatures>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TxOutput {
-    target: Option<OutputTarget>,
-}
-
-#[derive(Debug, Deserialize)]
-struct OutputTarget {
-    key: Option<String>,
-    tagged_key: Option<TaggedKey>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TaggedKey {
-    key: String,
+use clap::{Parser, Subcommand, ValueEnum};
+use monero_oracle::{
+    archive::DbBackups,
+    block_export::BlockArtifact,
+    bridge_fee::FeeReport,
+    config,
+    costs::{CostReport, Month},
+    decoys, fees, fixtures, frost, migrations,
+    monero_rpc::MoneroRpcClient,
+    monero_wallet::MoneroWalletClient,
+    oracle::OracleService,
+    proof::{self, InclusionProof},
+    scanner::format_xmr,
+    settings,
+    signer::OracleSigner,
+    snapshot::{Snapshot, SnapshotBlock},
+    storage::Database,
+    subaddress::SubaddressIndex,
+    watch_only,
+};
+use std::{path::PathBuf, sync::Arc};
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+#[derive(Parser)]
+#[command(
+    name = "monero-oracle",
+    version,
+    about = "Monero oracle for zeroXMR on Unichain"
+)]
+struct Cli {
+    /// TOML config file; environment variables override it
+    /// [default: $CONFIG_FILE, or config.toml if present]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Deserialize)]
-struct RctSignatures {
-    #[serde(rename = "ecdhInfo")]
-    ecdh_info: Option<Vec<EcdhInfo>>,
-    #[serde(rename = "outPk")]
-    out_pk: Option<Vec<String>>,
+#[derive(Subcommand)]
+enum Command {
+    /// Poll Monero and keep the contract in sync (the default)
+    Run {
+        /// Post every missing block even when more than MAX_CATCH_UP_BLOCKS
+        /// are missing
+        #[arg(long)]
+        force_backfill: bool,
+    },
+    /// Print Monero, contract and database sync state as JSON
+    Status,
+    /// Post one confirmed block to the contract
+    PostBlock { height: u64 },
+    /// Post confirmed blocks FROM to TO; ones already posted are only rescanned
+    Backfill { from: u64, to: u64 },
+    /// Post blocks FROM to TO (default: FROM) that checkpoint mode skipped,
+    /// below the contract's latest block
+    Fill { from: u64, to: Option<u64> },
+    /// Print postMoneroBlock calldata for confirmed blocks FROM to TO (default:
+    /// FROM) instead of sending, as JSON lines; needs no oracle key
+    Calldata {
+        from: u64,
+        to: Option<u64>,
+        /// Write to FILE instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Recompute blocks FROM to TO and compare them with the contract and
+    /// the database; prints a JSON report and fails on any discrepancy
+    Audit {
+        #[arg(long)]
+        from: u64,
+        #[arg(long)]
+        to: u64,
+        /// Write the report to FILE instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Check every block the contract stores against monerod as it is
+    /// posted, and alert on differences; needs no oracle key and sends nothing
+    Watch {
+        /// First block to check [default: the contract's latest block]
+        #[arg(long)]
+        from: Option<u64>,
+    },
+    /// Scan posted blocks FROM to TO (default: the contract's latest block)
+    /// again for deposits missed when they were posted, and hold each one
+    /// found for review; prints them as JSON and needs no oracle key
+    Rescan {
+        #[arg(long)]
+        from: u64,
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Merkle proof of a transaction, for verifyTxInBlock
+    ProveTx { tx_hash: String },
+    /// Merkle proof of an output, for verifyMerkleProof
+    ProveOutput { tx_hash: String, output_index: u64 },
+    /// Check a proof printed by prove-tx or prove-output (FILE, or stdin)
+    VerifyProof { file: Option<PathBuf> },
+    /// Recompute a block exported to BLOCK_EXPORT_TARGET from its own data
+    /// (FILE, or stdin) and print the roots to compare with the contract's
+    VerifyExport { file: Option<PathBuf> },
+    /// Pick decoys for spending the RingCT output with global index INDEX and
+    /// print the ring as JSON
+    Decoys { index: u64 },
+    /// Print the withdrawal payout fee at each priority as JSON
+    WithdrawalFees,
+    /// Print journaled operations not yet known to have taken effect as JSON
+    Operations,
+    /// Write every posted block and its roots to FILE, signed by the oracle
+    /// key, for import-snapshot on another instance
+    ExportSnapshot { file: PathBuf },
+    /// Load the blocks of a snapshot signed by SIGNER into the database
+    ImportSnapshot {
+        file: PathBuf,
+        /// Address the snapshot must be signed by, e.g. the contract's oracle
+        #[arg(long)]
+        signer: Address,
+    },
+    /// Database schema version and migrations
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Copy the database to DB_BACKUP_TARGET now, deleting backups past
+    /// DB_BACKUP_KEEP
+    BackupDb,
+    /// Fetch the newest backup (or KEY) from DB_BACKUP_TARGET to
+    /// DATABASE_PATH, which must not exist yet
+    RestoreDb {
+        #[arg(long)]
+        key: Option<String>,
+        /// Only list the backups, oldest first
+        #[arg(long)]
+        list: bool,
+    },
+    /// Sum the gas, ETH and XMR fees the oracle paid in MONTH (YYYY-MM, UTC)
+    /// by transaction kind
+    Report {
+        #[arg(long)]
+        month: Month,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+        /// Write the report to FILE instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Sum the bridge fees collected in MONTH (YYYY-MM, UTC) by operation kind
+    FeeReport {
+        #[arg(long)]
+        month: Month,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+        /// Write the report to FILE instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Send collected bridge fees not yet swept to TREASURY_XMR_ADDRESS
+    /// from the bridge wallet; needs no oracle key
+    SweepFees {
+        /// Only print the amount that would be swept
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Start making the wallet at MONERO_WALLET_RPC_URL multisig: print the
+    /// info the other operators pass to multisig-make
+    MultisigPrepare,
+    /// Make the wallet a THRESHOLD-of-N multisig wallet from the other
+    /// operators' multisig-prepare info; prints the info for the next round
+    MultisigMake {
+        #[arg(long)]
+        threshold: u32,
+        #[arg(required = true)]
+        info: Vec<String>,
+    },
+    /// One round of multisig key exchange from the other operators' info of
+    /// the previous round; repeat until no info is printed
+    MultisigExchange {
+        #[arg(required = true)]
+        info: Vec<String>,
+    },
+    /// Print the wallet's multisig threshold and the latest signing rounds
+    /// as JSON
+    MultisigStatus,
+    /// Start generating a threshold key with the other operators, any
+    /// THRESHOLD of the PARTICIPANTS signing block attestations: write this
+    /// operator's secret frost-dkg-state-N.json and the
+    /// frost-dkg-round1-N.json to send every other operator
+    FrostDkgStart {
+        /// This operator's number, from 1 to PARTICIPANTS
+        #[arg(long)]
+        identifier: u16,
+        #[arg(long)]
+        threshold: u16,
+        #[arg(long)]
+        participants: u16,
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Check the other operators' frost-dkg-round1 files and write the
+    /// frost-dkg-share-N-to-M.json to send, privately, to each operator M
+    FrostDkgShares {
+        #[arg(long)]
+        identifier: u16,
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        #[arg(required = true)]
+        round1: Vec<PathBuf>,
+    },
+    /// Check the shares the other operators sent this one, write its
+    /// frost-share-N.json and print the key for setThresholdKey
+    FrostDkgFinish {
+        #[arg(long)]
+        identifier: u16,
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        #[arg(required = true)]
+        shares: Vec<PathBuf>,
+    },
+    /// Write the bridge wallet's outputs from MONERO_WALLET_RPC_URL to FILE,
+    /// in monero-wallet-cli's export_outputs format
+    ExportOutputs { file: PathBuf },
+    /// Write the bridge wallet's signed key images from
+    /// MONERO_WALLET_RPC_URL to FILE as JSON, for watch-only-wallet
+    ExportKeyImages { file: PathBuf },
+    /// Create a standard view-only wallet for the bridge's primary address
+    /// in another monero-wallet-rpc and print its balance as JSON
+    WatchOnlyWallet {
+        /// monero-wallet-rpc run with --wallet-dir; its open wallet is
+        /// replaced, so it can't be MONERO_WALLET_RPC_URL
+        #[arg(long)]
+        wallet_rpc: String,
+        /// Wallet file to create in its --wallet-dir
+        #[arg(long)]
+        filename: String,
+        /// Height to scan the chain from
+        #[arg(long, default_value_t = 0)]
+        restore_height: u64,
+        /// Key images written by export-key-images, so spends are counted
+        #[arg(long)]
+        key_images: Option<PathBuf>,
+    },
+    /// Save monerod's responses for block HEIGHT and the roots computed from
+    /// them, for the replay tests
+    RecordFixture {
+        height: u64,
+        /// Fixture directory; the block goes in DIR/block-HEIGHT, or
+        /// DIR/synthetic-HEIGHT when made up
+        #[arg(long, default_value = "tests/fixtures")]
+        dir: PathBuf,
+        /// Write a made-up block with TXS transactions instead of fetching
+        /// one from MONERO_RPC_URL (with the `synthetic-fixtures` feature)
+        #[cfg(feature = "synthetic-fixtures")]
+        #[arg(long, value_name = "TXS")]
+        synthetic: Option<usize>,
+    },
+    /// Deposit address of subaddress SUBADDRESS_ACCOUNT/MINOR
+    Subaddress { minor: u32 },
+    /// Deposits made to subaddress SUBADDRESS_ACCOUNT/MINOR
+    Deposits { minor: u32 },
+    /// Mint deposits to subaddress SUBADDRESS_ACCOUNT/MINOR to ADDRESS (AUTO_MINT)
+    Register { minor: u32, address: Address },
+    /// Integrated address for PAYMENT_ID (16 hex chars; random if omitted)
+    IntegratedAddress { payment_id: Option<B64> },
+    /// Deposits made with PAYMENT_ID
+    PaymentIdDeposits { payment_id: B64 },
+    /// Mint deposits made with PAYMENT_ID to ADDRESS (AUTO_MINT)
+    RegisterPaymentId { payment_id: B64, address: Address },
 }
 
-#[derive(Debug, Deserialize)]
-struct EcdhInfo {
-    amount: String,
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Print the schema version, pending migrations and migration history
+    /// as JSON
+    Status,
+    /// Apply pending migrations, after copying the database to
+    /// DATABASE_PATH.v{version}.bak
+    Migrate,
 }
 
-// Extracted output data
-#[derive(Debug, Clone)]
-struct MoneroOutput {
-    tx_hash: B256,
-    output_index: u64,
-    ecdh_amount: B256,
-    output_pub_key: B256,
-    commitment: B256,
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Json,
+    Csv,
 }
 
-// ════════════════════════════════════════════════════════════════════════════
-// MONERO RPC CLIENT
-// ════════════════════════════════════════════════════════════════════════════
-
-struct MoneroRpcClient {
-    client: Client,
-    rpc_url: String,
-}
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
 
-impl MoneroRpcClient {
-    fn new(rpc_url: String) -> Self {
-        Self {
-            client: Client::new(),
-            rpc_url,
-        }
+    // Load .env file from project root
+    // Try parent directory first (when running from monero-oracle/)
+    if dotenvy::from_filename("../.env").is_err() {
+        // Fall back to current directory
+        dotenvy::dotenv().ok();
     }
-
-    async fn get_last_block_header(&self) -> Result<BlockHeader> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0",
-            id: "0",
-            method: "get_last_block_header",
-            params: serde_json::json!({}),
-        };
-
-        let response: JsonRpcResponse<BlockHeaderResponse> = self
-            .client
-            .post(format!("{}/json_rpc", self.rpc_url))
-            .json(&request)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        if let Some(error) = response.error {
-            anyhow::bail!("Monero RPC error: {}", error.message);
+    settings::load(cli.config.as_deref())?;
+
+    // Initialize logging (stderr, so proof output on stdout stays machine-readable)
+    let logging = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(log_filter(config::log_level_from_env()?)?)
+        .with_filter_reloading();
+    let log_handle = logging.reload_handle();
+    logging.init();
+
+    match cli.command.unwrap_or(Command::Run {
+        force_backfill: false,
+    }) {
+        Command::Run { force_backfill } => {
+            let service = service()?;
+            let service = if force_backfill {
+                service.with_catch_up_approved()
+            } else {
+                service
+            };
+            service
+                .with_log_filter(Box::new(move |level| {
+                    log_handle.reload(log_filter(level)?)?;
+                    Ok(())
+                }))
+                .run()
+                .await
         }
-
-        Ok(response
-            .result
-            .context("No result in response")?
-            .block_header)
-    }
-
-    async fn get_block(&self, height: u64) -> Result<GetBlockResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0",
-            id: "0",
-            method: "get_block",
-            params: serde_json::json!({ "height": height }),
-        };
-
-        let response: JsonRpcResponse<GetBlockResponse> = self
-            .client
-            .post(format!("{}/json_rpc", self.rpc_url))
-            .json(&request)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        if let Some(error) = response.error {
-            anyhow::bail!("Monero RPC error: {}", error.message);
+        Command::Status => {
+            let status = service()?.status().await?;
+            println!("{}", serde_json::to_string_pretty(&status)?);
+            Ok(())
         }
-
-        response.result.context("No result in response")
-    }
-
-    async fn get_transactions(&self, tx_hashes: Vec<String>) -> Result<Vec<TransactionInfo>> {
-        if tx_hashes.is_empty() {
-            return Ok(vec![]);
+        Command::PostBlock { height } => service()?.backfill(height, height).await,
+        Command::Backfill { from, to } => service()?.backfill(from, to).await,
+        Command::Fill { from, to } => service()?.fill(from, to.unwrap_or(from)).await,
+        Command::Calldata { from, to, out } => {
+            let service = OracleService::new(config::Config::from_env_unsigned()?)?;
+            let mut out: Box<dyn std::io::Write> = match &out {
+                Some(path) => Box::new(
+                    std::fs::File::create(path)
+                        .with_context(|| format!("Failed to create {}", path.display()))?,
+                ),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            service
+                .export_calldata(from, to.unwrap_or(from), &mut out)
+                .await?;
+            Ok(())
         }
-
-        let request = GetTransactionsRequest {
-            txs_hashes: tx_hashes,
-            decode_as_json: true,
-        };
-
-        let response: GetTransactionsResponse = self
-            .client
-            .post(format!("{}/get_transactions", self.rpc_url))
-            .json(&request)
-            .send()
-            .await?
-            .json()
+        Command::Audit { from, to, out } => {
+            let service = OracleService::new(config::Config::from_env_unsigned()?)?;
+            let report = service.audit(from, to).await?;
+            let json = serde_json::to_string_pretty(&report)?;
+            match &out {
+                Some(path) => std::fs::write(path, json + "\n")
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => println!("{}", json),
+            }
+            if !report.is_clean() {
+                anyhow::bail!(
+                    "{} discrepancy(ies) in blocks {} to {}",
+                    report.discrepancies.len(),
+                    from,
+                    to
+                );
+            }
+            Ok(())
+        }
+        Command::Watch { from } => {
+            OracleService::new(config::Config::from_env_unsigned()?)?
+                .watch(from)
+                .await
+        }
+        Command::Rescan { from, to } => {
+            let found = OracleService::new(config::Config::from_env_unsigned()?)?
+                .rescan(from, to)
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&found)?);
+            Ok(())
+        }
+        Command::ProveTx { tx_hash } => {
+            let proof = proof::prove_tx(
+                &monero_client()?,
+                None,
+                &tx_hash,
+                config::tx_merkle_hasher_from_env()?,
+            )
             .await?;
-
-        if response.status != "OK" {
-            anyhow::bail!("Failed to get transactions: {}", response.status);
+            println!("{}", serde_json::to_string_pretty(&proof)?);
+            Ok(())
         }
-
-        Ok(response.txs.unwrap_or_default())
-    }
-
-    async fn extract_outputs_from_block(&self, height: u64) -> Result<Vec<MoneroOutput>> {
-        let block_data = self.get_block(height).await?;
-        let block_json: BlockJson = serde_json::from_str(&block_data.json)?;
-
-        let tx_hashes = block_json.tx_hashes.unwrap_or_default();
-        if tx_hashes.is_empty() {
-            info!("   No transactions in block {}", height);
-            return Ok(vec![]);
+        Command::ProveOutput {
+            tx_hash,
+            output_index,
+        } => {
+            let proof = proof::prove_output(
+                &monero_client()?,
+                &tx_hash,
+                output_index,
+                config::output_merkle_hasher_from_env()?,
+            )
+            .await?;
+            println!("{}", serde_json::to_string_pretty(&proof)?);
+            Ok(())
         }
-
-        info!(
-            "   Fetching {} transaction(s) from block...",
-            tx_hashes.len()
-        );
-
-        let transactions = self.get_transactions(tx_hashes).await?;
-        let mut all_outputs = Vec::new();
-
-        for tx in transactions {
-            let tx_json: TransactionJson = match serde_json::from_str(&tx.as_json) {
-                Ok(j) => j,
-                Err(e) => {
-                    warn!("   Failed to parse transaction JSON: {}", e);
-                    continue;
-                }
+        Command::VerifyProof { file } => {
+            let json = match &file {
+                Some(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                None => std::io::read_to_string(std::io::stdin())?,
             };
-
-            let vout = match tx_json.vout {
-                Some(v) => v,
-                None => continue,
+            let proof: InclusionProof =
+                serde_json::from_str(&json).context("Not a transaction or output proof")?;
+            let (what, root) = match &proof {
+                InclusionProof::Tx(proof) => (
+                    format!("Transaction {} at index {}", proof.tx_hash, proof.tx_index),
+                    format!("txMerkleRoot {}", proof.tx_merkle_root),
+                ),
+                InclusionProof::Output(proof) => (
+                    format!(
+                        "Output {} of {} at leaf {}",
+                        proof.output.output_index, proof.output.tx_hash, proof.leaf_index
+                    ),
+                    format!("outputMerkleRoot {}", proof.output_merkle_root),
+                ),
             };
-
-            let rct_sigs = match tx_json.rct_signatures {
-                Some(r) => r,
-                None => continue,
+            if !proof.verify() {
+                anyhow::bail!("Invalid proof: {} does not lead to {}", what, root);
+            }
+            println!("Valid: {} is included in {}", what, root);
+            Ok(())
+        }
+        Command::VerifyExport { file } => {
+            let json = match &file {
+                Some(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                None => std::io::read_to_string(std::io::stdin())?,
             };
-
-            let ecdh_info = rct_sigs.ecdh_info.unwrap_or_default();
-            let out_pk = rct_sigs.out_pk.unwrap_or_default();
-
-            for (i, output) in vout.iter().enumerate() {
-                let output_pub_key = match &output.target {
-                    Some(target) => {
-                        if let Some(key) = &target.key {
-                            key.clone()
-                        } else if let Some(tagged_key) = &target.tagged_key {
-                            tagged_key.key.clone()
-                        } else {
-                            continue;
-                        }
+            let artifact: BlockArtifact =
+                serde_json::from_str(&json).context("Not an exported block")?;
+            artifact
+                .verify()
+                .with_context(|| format!("Invalid export of block {}", artifact.header.height))?;
+            println!(
+                "Valid: block {} ({}) with {} transactions and {} outputs",
+                artifact.header.height,
+                artifact.header.hash,
+                artifact.tx_hashes.len() + 1,
+                artifact.outputs.len()
+            );
+            println!("   txMerkleRoot     {}", artifact.tx_tree.root);
+            println!("   outputMerkleRoot {}", artifact.output_tree.root);
+            Ok(())
+        }
+        Command::Decoys { index } => {
+            let ring = decoys::build_ring(&monero_client()?, index).await?;
+            println!("{}", serde_json::to_string_pretty(&ring)?);
+            Ok(())
+        }
+        Command::WithdrawalFees => {
+            let estimate = monero_client()?.get_fee_estimate().await?;
+            let quotes: Vec<_> = fees::FeePriority::ALL
+                .into_iter()
+                .map(|priority| fees::quote(&estimate, priority))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&quotes)?);
+            Ok(())
+        }
+        Command::Operations => {
+            let pending = open_database()?.pending_operations()?;
+            println!("{}", serde_json::to_string_pretty(&pending)?);
+            Ok(())
+        }
+        Command::ExportSnapshot { file } => {
+            let blocks: Vec<SnapshotBlock> = open_database()?
+                .posted_chain()?
+                .iter()
+                .map(|(record, cumulative_difficulty)| {
+                    SnapshotBlock::new(record, *cumulative_difficulty)
+                })
+                .collect();
+            let signer = OracleSigner::connect(&config::signer_config_from_env()?).await?;
+            let snapshot = Snapshot::sign(
+                &signer,
+                config::monero_network_from_env()?,
+                config::bridge_address_from_env()?,
+                blocks,
+            )
+            .await?;
+            std::fs::write(&file, serde_json::to_string_pretty(&snapshot)? + "\n")
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            let header = snapshot.header()?;
+            println!(
+                "Exported {} blocks ({} to {}) to {}, signed by {}",
+                snapshot.blocks.len(),
+                header.firstHeight,
+                header.lastHeight,
+                file.display(),
+                snapshot.signer
+            );
+            Ok(())
+        }
+        Command::ImportSnapshot { file, signer } => {
+            let json = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let snapshot: Snapshot = serde_json::from_str(&json).context("Not a snapshot file")?;
+            snapshot.verify(
+                signer,
+                config::monero_network_from_env()?,
+                config::bridge_address_from_env()?,
+            )?;
+            let blocks = snapshot
+                .blocks
+                .iter()
+                .map(|block| Ok((block.record()?, block.cumulative_difficulty)))
+                .collect::<Result<Vec<_>>>()?;
+            let imported = open_database()?.import_posted_blocks(&blocks)?;
+            println!(
+                "Imported {} of {} blocks signed by {} ({} already in the database)",
+                imported,
+                blocks.len(),
+                signer,
+                blocks.len() - imported
+            );
+            Ok(())
+        }
+        Command::Db { command } => {
+            let path = PathBuf::from(config::database_path_from_env());
+            anyhow::ensure!(path.exists(), "No database at {}", path.display());
+            let before = migrations::status_at(&path)?;
+            match command {
+                DbCommand::Status => {
+                    println!("{}", serde_json::to_string_pretty(&before)?);
+                }
+                DbCommand::Migrate => {
+                    let after = Database::open_checked(&path, true)?.schema_status()?;
+                    if after.version == before.version {
+                        println!(
+                            "{} is at version {}, the latest",
+                            path.display(),
+                            after.version
+                        );
+                    } else {
+                        println!(
+                            "Migrated {} from version {} to {} ({})",
+                            path.display(),
+                            before.version,
+                            after.version,
+                            before.pending.join(", ")
+                        );
                     }
-                    None => continue,
-                };
-
-                let ecdh = match ecdh_info.get(i) {
-                    Some(e) => &e.amount,
-                    None => continue,
-                };
-
-                let commitment = match out_pk.get(i) {
-                    Some(c) => c,
-                    None => continue,
-                };
-
-                // Parse hex strings to B256
-                let tx_hash = parse_hex_to_b256(&tx.tx_hash)?;
-                let ecdh_amount = parse_hex_to_b256_padded(ecdh)?;
-                let output_pub_key_bytes = parse_hex_to_b256(&output_pub_key)?;
-                let commitment_bytes = parse_hex_to_b256(commitment)?;
-
-                all_outputs.push(MoneroOutput {
-                    tx_hash,
-                    output_index: i as u64,
-                    ecdh_amount,
-                    output_pub_key: output_pub_key_bytes,
-                    commitment: commitment_bytes,
-                });
+                }
             }
+            Ok(())
         }
-
-        info!(
-            "   Extracted {} outputs from block {}",
-            all_outputs.len(),
-            height
-        );
-        Ok(all_outputs)
-    }
-}
-
-// ════════════════════════════════════════════════════════════════════════════
-// MERKLE TREE
-// ════════════════════════════════════════════════════════════════════════════
-
-fn compute_tx_merkle_root(tx_hashes: &[String]) -> B256 {
-    if tx_hashes.is_empty() {
-        return B256::ZERO;
-    }
-
-    if tx_hashes.len() == 1 {
-        return parse_hex_to_b256(&tx_hashes[0]).unwrap_or(B256::ZERO);
-    }
-
-    // DEBUG: Log first and last TX
-    if tx_hashes.len() > 0 {
-        info!("   TX Merkle: {} transactions", tx_hashes.len());
-        info!("   First TX: {}", &tx_hashes[0]);
-        if tx_hashes.len() > 1 {
-            info!("   Last TX: {}", &tx_hashes[tx_hashes.len() - 1]);
+        Command::BackupDb => {
+            let backups = db_backups()?;
+            let path = config::database_path_from_env();
+            let key = backups
+                .backup(
+                    Arc::new(open_database()?),
+                    &PathBuf::from(format!("{}.backup", path)),
+                )
+                .await?;
+            let pruned = backups.prune().await?;
+            println!(
+                "Backed up {} as {} ({} old backups deleted)",
+                path, key, pruned
+            );
+            Ok(())
         }
-    }
-
-    let mut level: Vec<[u8; 32]> = tx_hashes
-        .iter()
-        .filter_map(|h| {
-            let bytes = hex::decode(h).ok()?;
-            if bytes.len() == 32 {
-                let mut arr = [0u8; 32];
-                arr.copy_from_slice(&bytes);
-                Some(arr)
-            } else {
-                None
+        Command::RestoreDb { key, list } => {
+            let backups = db_backups()?;
+            if list {
+                for key in backups.list().await? {
+                    println!("{}", key);
+                }
+                return Ok(());
             }
-        })
-        .collect();
-
-    while level.len() > 1 {
-        let mut next_level = Vec::new();
-
-        for chunk in level.chunks(2) {
-            // Use alloy keccak256 to match contract verification
-            use alloy::primitives::keccak256;
-            
-            let mut data = Vec::new();
-            data.extend_from_slice(&chunk[0]);
-            
-            if chunk.len() > 1 {
-                data.extend_from_slice(&chunk[1]);
-            } else {
-                // Duplicate last hash for odd number
-                data.extend_from_slice(&chunk[0]);
+            let path = config::database_path_from_env();
+            let (key, held) = backups.restore(key, path.as_ref()).await?;
+            println!("Restored {} from {}", path, key);
+            if held > 0 {
+                println!(
+                    "{} queued withdrawal(s) marked failed: check whether each was paid \
+                     after the backup before requeueing it",
+                    held
+                );
             }
-
-            let hash = keccak256(&data);
-            next_level.push(hash.0);
+            Ok(())
         }
-
-        level = next_level;
-    }
-
-    B256::from_slice(&level[0])
-}
-
-fn compute_output_merkle_root(outputs: &[MoneroOutput]) -> B256 {
-    if outputs.is_empty() {
-        return B256::ZERO;
-    }
-
-    // Create leaves: keccak256(abi.encodePacked(txHash, outputIndex, ecdhAmount, outputPubKey, commitment))
-    let leaves: Vec<[u8; 32]> = outputs
-        .iter()
-        .map(|output| {
-            use alloy::primitives::keccak256;
-
-            // Pack the data similar to Solidity's abi.encodePacked
-            let mut data = Vec::new();
-            data.extend_from_slice(output.tx_hash.as_slice());
-            data.extend_from_slice(&U256::from(output.output_index).to_be_bytes::<32>());
-            data.extend_from_slice(output.ecdh_amount.as_slice());
-            data.extend_from_slice(output.output_pub_key.as_slice());
-            data.extend_from_slice(output.commitment.as_slice());
-
-            keccak256(&data).0
-        })
-        .collect();
-
-    if leaves.len() == 1 {
-        return B256::from_slice(&leaves[0]);
-    }
-
-    let mut level = leaves;
-
-    while level.len() > 1 {
-        let mut next_level = Vec::new();
-
-        for chunk in level.chunks(2) {
-            let mut hasher = Sha256::new();
-            hasher.update(&chunk[0]);
-
-            if chunk.len() > 1 {
-                hasher.update(&chunk[1]);
-            } else {
-                hasher.update(&chunk[0]);
+        Command::Report { month, format, out } => {
+            let costs = open_database()?.tx_costs(month.start(), month.end())?;
+            let report = CostReport::new(month, &costs);
+            let text = match format {
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+                ReportFormat::Csv => report.to_csv(),
+            };
+            match &out {
+                Some(path) => std::fs::write(path, text)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => print!("{}", text),
             }
-
-            let result = hasher.finalize();
-            let mut arr = [0u8; 32];
-            arr.copy_from_slice(&result);
-            next_level.push(arr);
+            Ok(())
         }
-
-        level = next_level;
-    }
-
-    B256::from_slice(&level[0])
-}
-
-// ════════════════════════════════════════════════════════════════════════════
-// HELPERS
-// ════════════════════════════════════════════════════════════════════════════
-
-fn parse_hex_to_b256(hex_str: &str) -> Result<B256> {
-    let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
-    let bytes = hex::decode(hex_str)?;
-
-    if bytes.len() != 32 {
-        anyhow::bail!("Expected 32 bytes, got {}", bytes.len());
-    }
-
-    Ok(B256::from_slice(&bytes))
-}
-
-fn parse_hex_to_b256_padded(hex_str: &str) -> Result<B256> {
-    let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
-    let bytes = hex::decode(hex_str)?;
-
-    let mut padded = [0u8; 32];
-    let start = 32 - bytes.len().min(32);
-    padded[start..].copy_from_slice(&bytes[..bytes.len().min(32)]);
-
-    Ok(B256::from_slice(&padded))
-}
-
-// ════════════════════════════════════════════════════════════════════════════
-// ORACLE SERVICE
-// ════════════════════════════════════════════════════════════════════════════
-
-struct OracleService {
-    config: Config,
-    monero_client: MoneroRpcClient,
-}
-
-impl OracleService {
-    fn new(config: Config) -> Self {
-        let monero_client = MoneroRpcClient::new(config.monero_rpc_url.clone());
-        Self {
-            config,
-            monero_client,
+        Command::FeeReport { month, format, out } => {
+            let db = open_database()?;
+            let collected = db.collected_fees(month.start(), month.end())?;
+            let sweeps = db.fee_sweeps(month.start(), month.end())?;
+            let report = FeeReport::new(month, &collected, &sweeps);
+            let text = match format {
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+                ReportFormat::Csv => report.to_csv(),
+            };
+            match &out {
+                Some(path) => std::fs::write(path, text)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => print!("{}", text),
+            }
+            Ok(())
         }
-    }
-
-    async fn run(&self) -> Result<()> {
-        info!("🔮 Monero Oracle Service Starting...\n");
-        info!("Configuration:");
-        info!("   Monero RPC: {}", self.config.monero_rpc_url);
-        info!("   Unichain RPC: {}", self.config.unichain_rpc_url);
-        info!("   WrappedMonero: {}", self.config.bridge_address);
-        info!(
-            "   Interval: {}s ({} min)",
-            self.config.poll_interval_secs,
-            self.config.poll_interval_secs / 60
-        );
-
-        // Set up wallet and provider
-        let signer: PrivateKeySigner = self.config.oracle_private_key.parse()?;
-        let wallet_address = signer.address();
-        let wallet = EthereumWallet::from(signer);
-
-        info!("\n👤 Oracle address: {}", wallet_address);
-
-        let provider = ProviderBuilder::new()
-            .with_recommended_fillers()
-            .wallet(wallet)
-            .on_builtin(&self.config.unichain_rpc_url)
-            .await?;
-
-        // Check balance
-        let balance = provider.get_balance(wallet_address).await?;
-        info!("   Balance: {} ETH", format_ether(balance));
-
-        if balance.is_zero() {
-            anyhow::bail!("Oracle has no ETH for gas! Please fund the oracle address.");
+        Command::SweepFees { dry_run } => {
+            let sweep = OracleService::new(config::Config::from_env_unsigned()?)?
+                .sweep_fees(dry_run)
+                .await?;
+            if let Some(sweep) = sweep {
+                println!("{}", serde_json::to_string_pretty(&sweep)?);
+            }
+            Ok(())
         }
-
-        // Connect to contract
-        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
-
-        // Verify oracle role
-        let contract_oracle = contract.oracle().call().await?.oracle;
-        if contract_oracle != wallet_address {
-            anyhow::bail!(
-                "Wallet is not the oracle!\n   Contract oracle: {}\n   Wallet address: {}",
-                contract_oracle,
-                wallet_address
+        Command::MultisigPrepare => {
+            println!("{}", payout_wallet()?.prepare_multisig().await?);
+            Ok(())
+        }
+        Command::MultisigMake { threshold, info } => {
+            let keys = payout_wallet()?
+                .make_multisig(&info, threshold, &wallet_password()?)
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&keys)?);
+            Ok(())
+        }
+        Command::MultisigExchange { info } => {
+            let keys = payout_wallet()?
+                .exchange_multisig_keys(&info, &wallet_password()?)
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&keys)?);
+            Ok(())
+        }
+        Command::MultisigStatus => {
+            let status = serde_json::json!({
+                "wallet": payout_wallet()?.is_multisig().await?,
+                "rounds": open_database()?.multisig_rounds(20)?,
+            });
+            println!("{}", serde_json::to_string_pretty(&status)?);
+            Ok(())
+        }
+        Command::FrostDkgStart {
+            identifier,
+            threshold,
+            participants,
+            dir,
+        } => {
+            let (state, round1) = frost::DkgState::start(identifier, threshold, participants)?;
+            let state_path = dir.join(format!("frost-dkg-state-{}.json", identifier));
+            let round1_path = dir.join(format!("frost-dkg-round1-{}.json", identifier));
+            // Never replace the state of a DKG under way
+            anyhow::ensure!(
+                !state_path.exists(),
+                "{} exists; delete it to start again",
+                state_path.display()
             );
+            round1.write(&round1_path)?;
+            state.write(&state_path)?;
+            println!("Send {} to every other operator", round1_path.display());
+            Ok(())
         }
-
-        info!("\n✅ Oracle verified and ready!\n");
-        info!("{}", "═".repeat(70));
-
-        // Main polling loop
-        let mut poll_interval = interval(Duration::from_secs(self.config.poll_interval_secs));
-
-        loop {
-            poll_interval.tick().await;
-
-            if let Err(e) = self.poll(&contract).await {
-                error!("❌ Error in oracle loop: {}", e);
+        Command::FrostDkgShares {
+            identifier,
+            dir,
+            round1,
+        } => {
+            let state_path = dir.join(format!("frost-dkg-state-{}.json", identifier));
+            let mut state = frost::DkgState::read(&state_path)?;
+            let round1 = round1
+                .iter()
+                .map(|path| frost::Round1Package::read(path))
+                .collect::<Result<Vec<_>>>()?;
+            let shares = state.shares(&round1)?;
+            state.write(&state_path)?;
+            for share in &shares {
+                let path = dir.join(format!(
+                    "frost-dkg-share-{}-to-{}.json",
+                    share.from, share.to
+                ));
+                share.write(&path)?;
+                println!("Send {} to operator {} only", path.display(), share.to);
             }
+            Ok(())
         }
-    }
-
-    async fn poll<T, P>(&self, contract: &WrappedMonero::WrappedMoneroInstance<T, P>) -> Result<()>
-    where
-        T: alloy::transports::Transport + Clone,
-        P: Provider<T> + Clone,
-    {
-        info!(
-            "\n[{}] 🔍 Checking Monero blockchain...",
-            Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
-        );
-
-        // Get latest Monero block header
-        let header = self.monero_client.get_last_block_header().await?;
-        let block_height = header.height;
-
-        info!("   Latest Monero block: {}", block_height);
-        info!("   Hash: 0x{}", header.hash);
-
-        // Get last posted block from contract
-        let latest_posted = contract.latestMoneroBlock().call().await?.latestMoneroBlock;
-        let latest_posted_u64: u64 = latest_posted.try_into().unwrap_or(0);
-
-        info!("   Last posted block: {}", latest_posted_u64);
-
-        // Post all missing blocks
-        if block_height > latest_posted_u64 {
-            let blocks_to_post = block_height - latest_posted_u64;
-            info!("   📊 {} new block(s) detected!", blocks_to_post);
-
-            for height in (latest_posted_u64 + 1)..=block_height {
-                info!("\n   📦 Processing block {}...", height);
-
-                // Get full block with transactions
-                let block_data = self.monero_client.get_block(height).await?;
-                let block_json: BlockJson = serde_json::from_str(&block_data.json)?;
-                let tx_hashes = block_json.tx_hashes.unwrap_or_default();
-                let block_hash = parse_hex_to_b256(&block_data.block_header.hash)?;
-
-                info!("      Transactions: {}", tx_hashes.len());
-
-                // Compute TX Merkle root
-                let tx_merkle_root = compute_tx_merkle_root(&tx_hashes);
-                info!("      TX Merkle root: {}", tx_merkle_root);
-
-                // Extract outputs from block
-                let outputs = self.monero_client.extract_outputs_from_block(height).await?;
-                info!("      Outputs: {}", outputs.len());
-
-                // Compute output Merkle root
-                let output_merkle_root = compute_output_merkle_root(&outputs);
-                info!("      Output Merkle root: {}", output_merkle_root);
-
-                // Post to contract
-                self.post_block(contract, height, block_hash, tx_merkle_root, output_merkle_root)
-                    .await?;
-            }
-        } else {
-            info!("   ✅ Already up to date");
+        Command::FrostDkgFinish {
+            identifier,
+            dir,
+            shares,
+        } => {
+            let state_path = dir.join(format!("frost-dkg-state-{}.json", identifier));
+            let state = frost::DkgState::read(&state_path)?;
+            let shares = shares
+                .iter()
+                .map(|path| frost::Round2Package::read(path))
+                .collect::<Result<Vec<_>>>()?;
+            let share = state.finish(&shares)?;
+            let path = dir.join(format!("frost-share-{}.json", identifier));
+            share.write(&path)?;
+            // Its polynomial is what the other operators' shares of this one
+            // are made of
+            std::fs::remove_file(&state_path)
+                .with_context(|| format!("Failed to delete {}", state_path.display()))?;
+            let group = &share.group;
+            let summary = serde_json::json!({
+                "threshold": group.threshold,
+                "participants": group.verifying_shares.len(),
+                "publicKey": group.public_key,
+                "setThresholdKey": group.contract_key()?,
+                "share": path.display().to_string(),
+            });
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+            Ok(())
         }
-
-        Ok(())
-    }
-
-    async fn post_block<T, P>(
-        &self,
-        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
-        block_height: u64,
-        block_hash: B256,
-        tx_merkle_root: B256,
-        output_merkle_root: B256,
-    ) -> Result<()>
-    where
-        T: alloy::transports::Transport + Clone,
-        P: Provider<T> + Clone,
-    {
-        info!("\n📤 Posting block {} to contract...", block_height);
-        info!("   Hash: {}", block_hash);
-        info!("   TX Merkle Root: {}", tx_merkle_root);
-        info!("   Output Merkle Root: {}", output_merkle_root);
-
-        // Try swapping blockHash and blockHeight to match struct order
-        let tx = contract
-            .postMoneroBlock(
-                U256::from(block_height),
-                block_hash,
-                tx_merkle_root,
-                output_merkle_root,
-            )
-            .send()
-            .await;
-
-        match tx {
-            Ok(pending_tx) => {
-                info!("   TX: {}", pending_tx.tx_hash());
-                info!("   ⏳ Waiting for confirmation...");
-
-                let receipt = pending_tx.get_receipt().await?;
-
-                info!(
-                    "   ✅ Confirmed in block {}",
-                    receipt.block_number.unwrap_or(0)
+        Command::ExportOutputs { file } => {
+            let outputs = payout_wallet()?.export_outputs(true).await?;
+            std::fs::write(&file, outputs)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            println!("Wrote the bridge wallet's outputs to {}", file.display());
+            Ok(())
+        }
+        Command::ExportKeyImages { file } => {
+            let key_images = payout_wallet()?.export_key_images(true).await?;
+            watch_only::write_key_images(&file, &key_images)?;
+            println!(
+                "Wrote {} key images to {}",
+                key_images.signed_key_images.len(),
+                file.display()
+            );
+            Ok(())
+        }
+        Command::WatchOnlyWallet {
+            wallet_rpc,
+            filename,
+            restore_height,
+            key_images,
+        } => {
+            let same_url =
+                |url: &str| url.trim_end_matches('/') == wallet_rpc.trim_end_matches('/');
+            if config::wallet_rpc_url_from_env().is_some_and(|url| same_url(&url)) {
+                anyhow::bail!(
+                    "--wallet-rpc is MONERO_WALLET_RPC_URL; creating a wallet would close the payout wallet"
                 );
-                info!("   Gas used: {}", receipt.gas_used);
             }
-            Err(e) => {
-                let error_str = e.to_string();
-                if error_str.contains("Block already posted") || error_str.contains("Block exists")
-                {
-                    warn!("   ⚠️  Block {} already posted", block_height);
-                } else {
-                    return Err(e.into());
+            let keys = config::view_keys_from_env()?
+                .context("BRIDGE_VIEW_KEY and BRIDGE_SPEND_PUBLIC_KEY must be set")?;
+            let key_images = key_images
+                .as_deref()
+                .map(watch_only::read_key_images)
+                .transpose()?;
+            let wallet =
+                MoneroWalletClient::new(wallet_rpc).with_retry(config::rpc_retry_from_env()?);
+            let created = watch_only::create(
+                &wallet,
+                &keys,
+                config::monero_network_from_env()?,
+                &filename,
+                restore_height,
+                key_images.as_ref(),
+            )
+            .await?;
+            println!("{}", serde_json::to_string_pretty(&created)?);
+            Ok(())
+        }
+        Command::RecordFixture {
+            height,
+            dir,
+            #[cfg(feature = "synthetic-fixtures")]
+            synthetic,
+        } => {
+            #[cfg(feature = "synthetic-fixtures")]
+            let made_up = synthetic.is_some();
+            #[cfg(not(feature = "synthetic-fixtures"))]
+            let made_up = false;
+            // Made-up blocks are named apart from recorded ones, whose
+            // heights they may share
+            let kind = if made_up { "synthetic" } else { "block" };
+            let dir = dir.join(format!("{}-{}", kind, height));
+            let tx_hasher = config::tx_merkle_hasher_from_env()?;
+            let hasher = config::output_merkle_hasher_from_env()?;
+            #[cfg(feature = "synthetic-fixtures")]
+            let synthetic = match synthetic {
+                Some(tx_count) => Some(
+                    fixtures::synthetic::synthesize_block(
+                        height, tx_count, tx_hasher, hasher, &dir,
+                    )
+                    .await?,
+                ),
+                None => None,
+            };
+            #[cfg(not(feature = "synthetic-fixtures"))]
+            let synthetic = None;
+            let block = match synthetic {
+                Some(block) => block,
+                None => {
+                    fixtures::record_block(monero_client()?, height, tx_hasher, hasher, &dir)
+                        .await?
                 }
+            };
+            println!(
+                "Recorded block {} ({} outputs) in {}",
+                block.height,
+                block.output_count,
+                dir.display()
+            );
+            Ok(())
+        }
+        Command::Subaddress { minor } => {
+            let index = subaddress(minor)?;
+            let keys = config::view_keys_from_env()?
+                .context("BRIDGE_VIEW_KEY and BRIDGE_SPEND_PUBLIC_KEY must be set")?;
+            if !keys.tracks(index) {
+                anyhow::bail!(
+                    "Subaddress {} is beyond the watched range; raise SUBADDRESS_LOOKAHEAD",
+                    index
+                );
+            }
+            println!(
+                "{}",
+                keys.address(index, config::monero_network_from_env()?)
+            );
+            Ok(())
+        }
+        Command::Deposits { minor } => {
+            for deposit in open_database()?.deposits_for_subaddress(subaddress(minor)?)? {
+                println!(
+                    "{}  {} XMR  {}:{}",
+                    deposit.block_height,
+                    format_xmr(deposit.amount),
+                    deposit.tx_hash,
+                    deposit.output_index
+                );
             }
+            Ok(())
+        }
+        Command::Register { minor, address } => {
+            let index = subaddress(minor)?;
+            open_database()?.register_recipient(index, address)?;
+            println!(
+                "Deposits to subaddress {} will be minted to {}",
+                index, address
+            );
+            Ok(())
+        }
+        Command::IntegratedAddress { payment_id } => {
+            let keys = config::view_keys_from_env()?
+                .context("BRIDGE_VIEW_KEY and BRIDGE_SPEND_PUBLIC_KEY must be set")?;
+            let payment_id = payment_id.unwrap_or_else(|| B64::from(rand::random::<[u8; 8]>()));
+            println!("Payment ID: {}", payment_id);
+            println!(
+                "{}",
+                keys.integrated_address(payment_id, config::monero_network_from_env()?)
+            );
+            Ok(())
+        }
+        Command::PaymentIdDeposits { payment_id } => {
+            for deposit in open_database()?.deposits_for_payment_id(payment_id)? {
+                println!(
+                    "{}  {} XMR  {}:{}",
+                    deposit.block_height,
+                    format_xmr(deposit.amount),
+                    deposit.tx_hash,
+                    deposit.output_index
+                );
+            }
+            Ok(())
+        }
+        Command::RegisterPaymentId {
+            payment_id,
+            address,
+        } => {
+            open_database()?.register_payment_id_recipient(payment_id, address)?;
+            println!(
+                "Deposits with payment ID {} will be minted to {}",
+                payment_id, address
+            );
+            Ok(())
         }
-
-        Ok(())
     }
 }
 
-fn format_ether(wei: U256) -> String {
-    let wei_u128: u128 = wei.try_into().unwrap_or(u128::MAX);
-    let ether = wei_u128 as f64 / 1e18;
-    format!("{:.6}", ether)
+/// `RUST_LOG`, with `level` for the oracle's own logs
+fn log_filter(level: LevelFilter) -> Result<EnvFilter> {
+    Ok(EnvFilter::from_default_env().add_directive(format!("monero_oracle={}", level).parse()?))
 }
 
-// ════════════════════════════════════════════════════════════════════════════
-// MAIN
-// ════════════════════════════════════════════════════════════════════════════
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("monero_oracle=info".parse()?),
-        )
-        .init();
-
-    // Load .env file from project root
-    // Try parent directory first (when running from monero-oracle/)
-    if let Err(_) = dotenvy::from_filename("../.env") {
-        // Fall back to current directory
-        dotenvy::dotenv().ok();
-    }
-
-    // Load configuration
-    let config = Config::from_env()?;
-
-    // Run oracle service
-    let service = OracleService::new(config);
-    service.run().await
+/// The oracle service, configured from the environment
+fn service() -> Result<OracleService> {
+    OracleService::new(config::Config::from_env()?)
 }
 
-// ════════════════════════════════════════════════════════════════════════════
-// TESTS
-// ════════════════════════════════════════════════════════════════════════════
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_compute_tx_merkle_root_empty() {
-        let result = compute_tx_merkle_root(&[]);
-        assert_eq!(result, B256::ZERO);
-    }
+/// monerod client for `MONERO_RPC_URL`; the proof commands need nothing else
+fn monero_client() -> Result<MoneroRpcClient> {
+    let rpc_url = config::monero_rpc_url_from_env(config::monero_network_from_env()?);
+    let connection = config::monero_connection_from_env([rpc_url.as_str()])?;
+    let client = MoneroRpcClient::new(rpc_url)
+        .with_client(connection.client()?)
+        .with_retry(config::rpc_retry_from_env()?)
+        .with_rate_limit(config::monero_rate_limit_from_env()?);
+    let client = match config::monero_rpc_login_from_env()? {
+        Some(login) => client.with_login(login),
+        None => client,
+    };
+    Ok(match config::monero_fixtures_from_env()? {
+        Some(fixtures) => client.with_fixtures(fixtures),
+        None => client,
+    })
+}
 
-    #[test]
-    fn test_compute_tx_merkle_root_single() {
-        let hashes = vec!["a".repeat(64)];
-        let result = compute_tx_merkle_root(&hashes);
-        assert_ne!(result, B256::ZERO);
-    }
+/// wallet-rpc client for the bridge wallet at `MONERO_WALLET_RPC_URL`
+fn payout_wallet() -> Result<MoneroWalletClient> {
+    let url = config::wallet_rpc_url_from_env().context("MONERO_WALLET_RPC_URL must be set")?;
+    Ok(MoneroWalletClient::new(url).with_retry(config::rpc_retry_from_env()?))
+}
 
-    #[test]
-    fn test_compute_tx_merkle_root_multiple() {
-        let hashes = vec!["a".repeat(64), "b".repeat(64), "c".repeat(64)];
-        let result = compute_tx_merkle_root(&hashes);
-        assert_ne!(result, B256::ZERO);
-    }
+/// Password of the wallet open in wallet-rpc, read from the terminal
+fn wallet_password() -> Result<String> {
+    rpassword::prompt_password("Wallet password: ")
+        .context("No terminal to prompt for the wallet password")
+}
 
-    #[test]
-    fn test_parse_hex_to_b256() {
-        let hex = "a".repeat(64);
-        let result = parse_hex_to_b256(&hex).unwrap();
-        assert_eq!(result.as_slice(), &[0xaa; 32]);
-    }
+/// The oracle's database at `DATABASE_PATH`
+fn open_database() -> Result<Database> {
+    Database::open_checked(
+        config::database_path_from_env(),
+        config::db_auto_migrate_from_env()?,
+    )
+}
 
-    #[test]
-    fn test_parse_hex_to_b256_with_prefix() {
-        let hex = format!("0x{}", "b".repeat(64));
-        let result = parse_hex_to_b256(&hex).unwrap();
-        assert_eq!(result.as_slice(), &[0xbb; 32]);
-    }
+/// Backups at `DB_BACKUP_TARGET`
+fn db_backups() -> Result<DbBackups> {
+    let config = config::db_backup_from_env()?.context("DB_BACKUP_TARGET is not set")?;
+    DbBackups::new(config, reqwest::Client::new())
+}
 
-    #[test]
-    fn test_compute_output_merkle_root_empty() {
-        let result = compute_output_merkle_root(&[]);
-        assert_eq!(result, B256::ZERO);
-    }
+/// Subaddress `SUBADDRESS_ACCOUNT/<minor>`
+fn subaddress(minor: u32) -> Result<SubaddressIndex> {
+    Ok(SubaddressIndex::new(
+        config::subaddress_account_from_env()?,
+        minor,
+    ))
 }
@@ -0,0 +1,889 @@
+//! Merkle roots committed to the contract for each Monero block
+//!
+//! Transactions: keccak256 tree over the block's tx hashes (`verifyTxInBlock`),
+//! version 1 ([`MerkleHasher::Keccak256`]) or 2 ([`MerkleHasher::V2`]).
+//! Outputs: built as the [`MerkleHasher`] says. keccak256 (`verifyMerkleProof`)
+//! is the default, version 1: [`output_leaf`] leaves in the order the outputs
+//! are given, no domain separation. Version 3 (`verifyMerkleProofSHA256`) is
+//! the same tree with SHA-256 nodes, kept for contracts deployed before the
+//! switch. Odd levels duplicate their last node.
+//!
+//! Version 2 ([`MerkleHasher::V2`], `verifyOutputProofV2`) is the canonical
+//! encoding:
+//!
+//! - Order: outputs sorted by tx index (position of the transaction in
+//!   the block, miner tx first) and then output index; see [`canonical_order`]
+//! - Leaf: `keccak256(0x00 ‖ txHash ‖ uint256(outputIndex) ‖ ecdhAmount ‖
+//!   outputPubKey ‖ commitment)`
+//! - Node: `keccak256(0x01 ‖ left ‖ right)`
+//! - Root: `keccak256(0x02 ‖ uint8(2) ‖ top node)`, zero for an empty tree
+//!
+//! Version 2 tx trees hash each tx hash into a leaf, `keccak256(0x00 ‖
+//! txHash)`, in block order, with the same nodes and root. A version 1 tx
+//! tree's leaves are the raw tx hashes, the same size as a node: anyone can
+//! pass an internal node (the hash of two children) off as a transaction of
+//! the block, with the path above it as proof.
+//!
+//! The prefixes keep a leaf from passing as a node and a node from passing as
+//! a root, and the version byte in the root means a proof built for one
+//! version never verifies against another's root. A later encoding gets the
+//! next version; the contract records the version each block was posted with,
+//! so blocks posted before a switch stay provable.
+//!
+//! [`MerkleTree`] keeps every level of a tree, so proofs for a posted block
+//! are read off it instead of rebuilt from the block, and it is stored with
+//! the block's outputs (`INDEX_OUTPUTS`) in that compact form.
+//!
+//! [`monero_tree_hash`] is Monero's own tx tree. It isn't posted, but checking
+//! it against the block hash proves the tx list the other roots are built from.
+//!
+//! Levels and leaf sets of [`PARALLEL_MIN_NODES`] or more are hashed on the
+//! rayon thread pool; busy blocks have thousands of outputs, and backfills
+//! hash them back to back. Smaller ones stay on the calling thread, where
+//! splitting the work costs more than it saves.
+
+use crate::{
+    error::OracleError,
+    monero_rpc::{parse_hex_to_b256, BlockHeader, MoneroOutput},
+};
+use alloy::primitives::{keccak256, B256, U256};
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{borrow::Cow, collections::HashMap, fmt, str::FromStr};
+use tracing::info;
+
+/// Nodes in a level (or leaves to hash) from which work is split across threads
+pub const PARALLEL_MIN_NODES: usize = 256;
+
+/// Domain-separation prefixes of version 2 output trees
+pub const LEAF_PREFIX: u8 = 0x00;
+pub const NODE_PREFIX: u8 = 0x01;
+pub const ROOT_PREFIX: u8 = 0x02;
+
+/// How internal tree nodes are hashed, and for output trees the encoding
+/// version that goes with it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MerkleHasher {
+    #[default]
+    Keccak256,
+    /// Legacy output tree hashing, version 3, verified by
+    /// `verifyMerkleProofSHA256`
+    Sha256,
+    /// Canonical version 2 output trees, verified by `verifyOutputProofV2`
+    V2,
+}
+
+impl MerkleHasher {
+    pub fn hash_pair(self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        match self {
+            Self::Keccak256 => hash_pair_keccak(left, right),
+            Self::Sha256 => hash_pair_sha256(left, right),
+            Self::V2 => hash_node_v2(left, right),
+        }
+    }
+
+    /// Contract function that verifies proofs built with this hasher
+    pub fn verifier(self) -> &'static str {
+        match self {
+            Self::Keccak256 => "verifyMerkleProof",
+            Self::Sha256 => "verifyMerkleProofSHA256",
+            Self::V2 => "verifyOutputProofV2",
+        }
+    }
+
+    /// Output tree version, as the contract's `outputTreeVersion` holds it
+    pub fn version(self) -> u8 {
+        match self {
+            Self::Keccak256 => 1,
+            Self::V2 => 2,
+            Self::Sha256 => 3,
+        }
+    }
+
+    /// Hasher that builds the output tree of a block posted with output tree
+    /// `version` (the contract's `outputTreeVersionOf`, 1 for unversioned
+    /// blocks), whatever `OUTPUT_MERKLE_HASHER` new blocks are posted with
+    pub fn for_output_version(version: u8) -> Result<Self> {
+        match version {
+            1 => Ok(Self::Keccak256),
+            2 => Ok(Self::V2),
+            3 => Ok(Self::Sha256),
+            other => anyhow::bail!("Unknown output tree version {}", other),
+        }
+    }
+
+    /// Leaf of `tx_hash` in a tx tree of this version
+    pub fn tx_leaf(self, tx_hash: &[u8; 32]) -> [u8; 32] {
+        match self {
+            Self::Keccak256 | Self::Sha256 => *tx_hash,
+            Self::V2 => {
+                let mut data = [0u8; 33];
+                data[0] = LEAF_PREFIX;
+                data[1..].copy_from_slice(tx_hash);
+                keccak256(data).0
+            }
+        }
+    }
+
+    /// Leaf of `output` in an output tree of this version
+    pub fn output_leaf(self, output: &MoneroOutput) -> [u8; 32] {
+        match self {
+            Self::Keccak256 | Self::Sha256 => output_leaf(output),
+            Self::V2 => output_leaf_v2(output),
+        }
+    }
+
+    /// `outputs` of a block whose transactions are `tx_order` (miner tx
+    /// first), in the order of an output tree's leaves: as given for version
+    /// 1, [`canonical_order`] for version 2
+    pub fn output_order<'a>(
+        self,
+        outputs: &'a [MoneroOutput],
+        tx_order: &[B256],
+    ) -> Cow<'a, [MoneroOutput]> {
+        match self {
+            Self::Keccak256 | Self::Sha256 => Cow::Borrowed(outputs),
+            Self::V2 => Cow::Owned(canonical_order(outputs, tx_order)),
+        }
+    }
+
+    /// Root as posted, from a non-empty tree's top node
+    fn commit_root(self, top: [u8; 32]) -> B256 {
+        match self {
+            Self::Keccak256 | Self::Sha256 => B256::from(top),
+            Self::V2 => {
+                let mut data = [0u8; 34];
+                data[0] = ROOT_PREFIX;
+                data[1] = self.version();
+                data[2..].copy_from_slice(&top);
+                keccak256(data)
+            }
+        }
+    }
+}
+
+impl FromStr for MerkleHasher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "keccak256" | "keccak" => Ok(Self::Keccak256),
+            "sha256" | "legacy" => Ok(Self::Sha256),
+            "v2" => Ok(Self::V2),
+            other => anyhow::bail!(
+                "Unknown Merkle hasher '{}' (expected keccak256, sha256 or v2)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for MerkleHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Keccak256 => "keccak256",
+            Self::Sha256 => "sha256",
+            Self::V2 => "v2",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Root over `leaves`, or zero for an empty tree. A single leaf is its own
+/// root, except in version 2 trees, whose root always commits to the version.
+pub fn merkle_root(leaves: Vec<[u8; 32]>, hasher: MerkleHasher) -> B256 {
+    MerkleTree::new(leaves, hasher).root()
+}
+
+/// Which of a block's two trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeKind {
+    Tx,
+    Output,
+}
+
+impl fmt::Display for TreeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tx => "tx",
+            Self::Output => "output",
+        })
+    }
+}
+
+impl FromStr for TreeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tx" => Ok(Self::Tx),
+            "output" => Ok(Self::Output),
+            other => anyhow::bail!("Unknown Merkle tree '{}'", other),
+        }
+    }
+}
+
+/// Every level of a Merkle tree, leaves first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    hasher: MerkleHasher,
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+/// Sibling path from a leaf to the root, as the contract's verifiers take it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub leaf_index: u64,
+    pub leaf: B256,
+    pub siblings: Vec<B256>,
+}
+
+impl MerkleProof {
+    /// Fold the path back up to a root, mirroring the contract's verifier
+    pub fn compute_root(&self, hasher: MerkleHasher) -> B256 {
+        let mut index = self.leaf_index;
+        let mut computed = self.leaf.0;
+        for sibling in &self.siblings {
+            computed = if index & 1 == 0 {
+                hasher.hash_pair(&computed, &sibling.0)
+            } else {
+                hasher.hash_pair(&sibling.0, &computed)
+            };
+            index /= 2;
+        }
+        hasher.commit_root(computed)
+    }
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<[u8; 32]>, hasher: MerkleHasher) -> Self {
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = hash_level(level, |left, right| hasher.hash_pair(left, right));
+            levels.push(next);
+        }
+        Self { hasher, levels }
+    }
+
+    pub fn hasher(&self) -> MerkleHasher {
+        self.hasher
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.levels[0]
+    }
+
+    /// Root of the tree, or zero when empty
+    pub fn root(&self) -> B256 {
+        match self
+            .levels
+            .last()
+            .expect("a tree has a leaf level")
+            .as_slice()
+        {
+            [top] => self.hasher.commit_root(*top),
+            _ => B256::ZERO,
+        }
+    }
+
+    /// Add a leaf, rehashing only the last node of each level
+    pub fn push(&mut self, leaf: [u8; 32]) {
+        self.levels[0].push(leaf);
+        let mut depth = 0;
+        while self.levels[depth].len() > 1 {
+            let level = &self.levels[depth];
+            let parent = (level.len() - 1) / 2;
+            let left = &level[2 * parent];
+            let node = self
+                .hasher
+                .hash_pair(left, level.get(2 * parent + 1).unwrap_or(left));
+
+            if depth + 1 == self.levels.len() {
+                self.levels.push(vec![]);
+            }
+            let next = &mut self.levels[depth + 1];
+            match next.get_mut(parent) {
+                Some(stale) => *stale = node,
+                None => next.push(node),
+            }
+            depth += 1;
+        }
+    }
+
+    /// Sibling path for the leaf at `leaf_index`, or `None` past the end
+    pub fn proof(&self, leaf_index: usize) -> Option<MerkleProof> {
+        let leaf = *self.leaves().get(leaf_index)?;
+        let mut index = leaf_index;
+        let mut siblings = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = level.get(index ^ 1).unwrap_or(&level[index]);
+            siblings.push(B256::from(*sibling));
+            index /= 2;
+        }
+        Some(MerkleProof {
+            leaf_index: leaf_index as u64,
+            leaf: B256::from(leaf),
+            siblings,
+        })
+    }
+
+    /// Whether `proof` leads to this tree's root
+    pub fn verify(&self, proof: &MerkleProof) -> bool {
+        !self.is_empty() && proof.compute_root(self.hasher) == self.root()
+    }
+
+    /// Every node, level by level from the leaves, for storage
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.levels.iter().flatten().flatten().copied().collect()
+    }
+
+    /// Read back [`Self::to_bytes`] of a tree with `leaf_count` leaves
+    pub fn from_bytes(leaf_count: usize, hasher: MerkleHasher, bytes: &[u8]) -> Result<Self> {
+        let mut sizes = vec![leaf_count];
+        while let Some(&size) = sizes.last().filter(|&&size| size > 1) {
+            sizes.push(size.div_ceil(2));
+        }
+        let nodes: usize = sizes.iter().sum();
+        anyhow::ensure!(
+            bytes.len() == nodes * 32,
+            "Stored tree has {} bytes, expected {} for {} leaves",
+            bytes.len(),
+            nodes * 32,
+            leaf_count
+        );
+
+        let mut chunks = bytes
+            .chunks_exact(32)
+            .map(|node| <[u8; 32]>::try_from(node).expect("32-byte chunk"));
+        let levels = sizes
+            .iter()
+            .map(|&size| chunks.by_ref().take(size).collect())
+            .collect();
+        Ok(Self { hasher, levels })
+    }
+}
+
+/// Root of the block's tx tree, as posted in `txMerkleRoot`
+pub fn compute_tx_merkle_root(tx_hashes: &[String], hasher: MerkleHasher) -> B256 {
+    if tx_hashes.len() > 1 {
+        info!("   TX Merkle: {} transactions", tx_hashes.len());
+        info!("   First TX: {}", &tx_hashes[0]);
+        info!("   Last TX: {}", &tx_hashes[tx_hashes.len() - 1]);
+    }
+
+    tx_tree(tx_hashes, hasher).root()
+}
+
+/// Root of the block's output tree, as posted in `outputMerkleRoot`, from
+/// `outputs` in [`MerkleHasher::output_order`]
+pub fn compute_output_merkle_root(outputs: &[MoneroOutput], hasher: MerkleHasher) -> B256 {
+    output_tree(outputs, hasher).root()
+}
+
+/// The block's tx tree, with [`MerkleHasher::tx_leaf`] leaves in block order
+pub fn tx_tree(tx_hashes: &[String], hasher: MerkleHasher) -> MerkleTree {
+    let leaves = tx_leaves(tx_hashes)
+        .iter()
+        .map(|tx_hash| hasher.tx_leaf(tx_hash))
+        .collect();
+    MerkleTree::new(leaves, hasher)
+}
+
+/// The block's output tree over `outputs`, which are already in
+/// [`MerkleHasher::output_order`]; a leaf's index is its output's position
+pub fn output_tree(outputs: &[MoneroOutput], hasher: MerkleHasher) -> MerkleTree {
+    let leaf = |output: &MoneroOutput| hasher.output_leaf(output);
+    let leaves = if outputs.len() >= PARALLEL_MIN_NODES {
+        outputs.par_iter().map(leaf).collect()
+    } else {
+        outputs.iter().map(leaf).collect()
+    };
+    MerkleTree::new(leaves, hasher)
+}
+
+/// Version 2 leaf order: sorted by tx index, the transaction's position in
+/// `tx_order` (the block's miner tx, then its other transactions), then by
+/// output index. The order `outputs` come in doesn't matter. Outputs of a
+/// transaction missing from `tx_order` go last, as given.
+pub fn canonical_order(outputs: &[MoneroOutput], tx_order: &[B256]) -> Vec<MoneroOutput> {
+    let tx_index: HashMap<B256, usize> = tx_order
+        .iter()
+        .enumerate()
+        .map(|(index, tx_hash)| (*tx_hash, index))
+        .collect();
+    let mut sorted = outputs.to_vec();
+    sorted.sort_by_key(|output| {
+        (
+            tx_index.get(&output.tx_hash).copied().unwrap_or(usize::MAX),
+            output.output_index,
+        )
+    });
+    sorted
+}
+
+/// Pair off `nodes` into the next level up, duplicating an odd last node
+fn hash_level<F>(nodes: &[[u8; 32]], hash_pair: F) -> Vec<[u8; 32]>
+where
+    F: Fn(&[u8; 32], &[u8; 32]) -> [u8; 32] + Sync,
+{
+    let pair = |chunk: &[[u8; 32]]| hash_pair(&chunk[0], chunk.get(1).unwrap_or(&chunk[0]));
+    if nodes.len() >= PARALLEL_MIN_NODES {
+        nodes.par_chunks(2).map(pair).collect()
+    } else {
+        nodes.chunks(2).map(pair).collect()
+    }
+}
+
+/// Monero's `tree_hash` (cryptonote `tree-hash.c`) over a block's tx hashes,
+/// miner tx first. Unlike [`merkle_root`], leaves that don't fit the largest
+/// power of two below the count are paired off first instead of duplicated.
+pub fn monero_tree_hash(hashes: &[[u8; 32]]) -> [u8; 32] {
+    match hashes.len() {
+        0 => [0u8; 32],
+        1 => hashes[0],
+        2 => hash_pair_keccak(&hashes[0], &hashes[1]),
+        count => {
+            let mut cnt = count.next_power_of_two() / 2;
+            let untouched = 2 * cnt - count;
+
+            // Always an even number left over, so nothing is duplicated
+            let mut ints: Vec<[u8; 32]> = hashes[..untouched].to_vec();
+            ints.extend(hash_level(&hashes[untouched..], hash_pair_keccak));
+
+            while cnt > 2 {
+                cnt /= 2;
+                ints = hash_level(&ints, hash_pair_keccak);
+            }
+
+            hash_pair_keccak(&ints[0], &ints[1])
+        }
+    }
+}
+
+/// Monero block hashing blob: header, tree root and tx count including the
+/// miner tx. This is what the proof of work hashes.
+pub fn block_hashing_blob(
+    header: &BlockHeader,
+    tree_root: &[u8; 32],
+    tx_count: u64,
+) -> Result<Vec<u8>> {
+    let mut blob = Vec::with_capacity(96);
+    write_varint(&mut blob, header.major_version as u64);
+    write_varint(&mut blob, header.minor_version as u64);
+    write_varint(&mut blob, header.timestamp);
+    blob.extend_from_slice(parse_hex_to_b256(&header.prev_hash)?.as_slice());
+    blob.extend_from_slice(&header.nonce.to_le_bytes());
+    blob.extend_from_slice(tree_root);
+    write_varint(&mut blob, tx_count);
+    Ok(blob)
+}
+
+/// Monero block id: keccak256 of the length-prefixed hashing blob
+pub fn compute_block_id(header: &BlockHeader, tree_root: &[u8; 32], tx_count: u64) -> Result<B256> {
+    let blob = block_hashing_blob(header, tree_root, tx_count)?;
+    let mut data = Vec::with_capacity(blob.len() + 2);
+    write_varint(&mut data, blob.len() as u64);
+    data.extend_from_slice(&blob);
+    Ok(keccak256(&data))
+}
+
+/// Check a block's tx list against its header: the block id rebuilt from
+/// Monero's tree root must equal the block hash. Returns the tree root.
+pub fn verify_block_tx_hashes(
+    header: &BlockHeader,
+    miner_tx_hash: &str,
+    tx_hashes: &[String],
+) -> Result<B256> {
+    let mut leaves = vec![parse_hex_to_b256(miner_tx_hash)?.0];
+    for tx_hash in tx_hashes {
+        leaves.push(parse_hex_to_b256(tx_hash)?.0);
+    }
+
+    let tree_root = monero_tree_hash(&leaves);
+    let block_id = compute_block_id(header, &tree_root, leaves.len() as u64)?;
+    let block_hash = parse_hex_to_b256(&header.hash)?;
+
+    if block_id != block_hash {
+        return Err(OracleError::Consensus(format!(
+            "Block {} tx list does not match its hash (computed {}, header {})",
+            header.height, block_id, block_hash
+        ))
+        .into());
+    }
+
+    Ok(B256::from(tree_root))
+}
+
+/// Monero varint: 7 bits per byte, least significant first
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Decode tx hashes into tree leaves, skipping anything that isn't 32 bytes
+pub fn tx_leaves(tx_hashes: &[String]) -> Vec<[u8; 32]> {
+    let leaf = |h: &String| {
+        let bytes = hex::decode(h).ok()?;
+        if bytes.len() == 32 {
+            let mut arr = [0u8; 32];
+            arr.copy_from_slice(&bytes);
+            Some(arr)
+        } else {
+            None
+        }
+    };
+    if tx_hashes.len() >= PARALLEL_MIN_NODES {
+        tx_hashes.par_iter().filter_map(leaf).collect()
+    } else {
+        tx_hashes.iter().filter_map(leaf).collect()
+    }
+}
+
+/// Output leaf: keccak256(abi.encodePacked(txHash, outputIndex, ecdhAmount, outputPubKey, commitment))
+pub fn output_leaf(output: &MoneroOutput) -> [u8; 32] {
+    // Pack the data similar to Solidity's abi.encodePacked
+    let mut data = Vec::new();
+    data.extend_from_slice(output.tx_hash.as_slice());
+    data.extend_from_slice(&U256::from(output.output_index).to_be_bytes::<32>());
+    data.extend_from_slice(output.ecdh_amount.as_slice());
+    data.extend_from_slice(output.output_pub_key.as_slice());
+    data.extend_from_slice(output.commitment.as_slice());
+
+    keccak256(&data).0
+}
+
+/// Version 2 output leaf, as `outputLeafV2` computes it:
+/// keccak256(0x00 ‖ txHash ‖ uint256(outputIndex) ‖ ecdhAmount ‖ outputPubKey ‖ commitment)
+pub fn output_leaf_v2(output: &MoneroOutput) -> [u8; 32] {
+    let mut data = Vec::with_capacity(161);
+    data.push(LEAF_PREFIX);
+    data.extend_from_slice(output.tx_hash.as_slice());
+    data.extend_from_slice(&U256::from(output.output_index).to_be_bytes::<32>());
+    data.extend_from_slice(output.ecdh_amount.as_slice());
+    data.extend_from_slice(output.output_pub_key.as_slice());
+    data.extend_from_slice(output.commitment.as_slice());
+
+    keccak256(&data).0
+}
+
+/// Version 2 internal node hash matching `verifyOutputProofV2`
+pub fn hash_node_v2(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 65];
+    data[0] = NODE_PREFIX;
+    data[1..33].copy_from_slice(left);
+    data[33..].copy_from_slice(right);
+    keccak256(data).0
+}
+
+/// Internal node hash matching `verifyTxInBlock` and `verifyMerkleProof`
+pub fn hash_pair_keccak(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(left);
+    data[32..].copy_from_slice(right);
+    keccak256(data).0
+}
+
+/// Internal node hash matching `verifyMerkleProofSHA256`
+pub fn hash_pair_sha256(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_tx_merkle_root_empty() {
+        let result = compute_tx_merkle_root(&[], MerkleHasher::Keccak256);
+        assert_eq!(result, B256::ZERO);
+    }
+
+    #[test]
+    fn test_compute_tx_merkle_root_single() {
+        let hashes = vec!["a".repeat(64)];
+        let result = compute_tx_merkle_root(&hashes, MerkleHasher::Keccak256);
+        assert_eq!(result, B256::repeat_byte(0xaa));
+    }
+
+    #[test]
+    fn test_compute_tx_merkle_root_multiple() {
+        let hashes = vec!["a".repeat(64), "b".repeat(64), "c".repeat(64)];
+        let result = compute_tx_merkle_root(&hashes, MerkleHasher::Keccak256);
+        assert_ne!(result, B256::ZERO);
+    }
+
+    #[test]
+    fn test_v2_tx_tree_rejects_internal_nodes_as_leaves() {
+        let hashes: Vec<String> = (1..=4u8).map(|i| hex::encode([i; 32])).collect();
+
+        // Version 1: the node over the first two transactions, presented as
+        // a transaction with the rest of its path, reaches the root
+        let v1 = tx_tree(&hashes, MerkleHasher::Keccak256);
+        let forged = MerkleProof {
+            leaf_index: 0,
+            leaf: B256::from(hash_pair_keccak(&[1; 32], &[2; 32])),
+            siblings: v1.proof(0).unwrap().siblings[1..].to_vec(),
+        };
+        assert!(v1.verify(&forged));
+
+        // Version 2: leaves are hashed with their own prefix, so a node
+        // can't be one, whatever is claimed as the tx hash
+        let v2 = tx_tree(&hashes, MerkleHasher::V2);
+        let node = hash_node_v2(&v2.leaves()[0], &v2.leaves()[1]);
+        let forged = MerkleProof {
+            leaf_index: 0,
+            leaf: B256::from(MerkleHasher::V2.tx_leaf(&node)),
+            siblings: v2.proof(0).unwrap().siblings[1..].to_vec(),
+        };
+        assert!(!v2.verify(&forged));
+        assert_eq!(v2.leaves()[2], MerkleHasher::V2.tx_leaf(&[3; 32]));
+        assert!(v2.verify(&v2.proof(2).unwrap()));
+        assert_eq!(compute_tx_merkle_root(&hashes, MerkleHasher::V2), v2.root());
+        assert_ne!(v2.root(), v1.root());
+    }
+
+    #[test]
+    fn test_compute_output_merkle_root_empty() {
+        let result = compute_output_merkle_root(&[], MerkleHasher::Keccak256);
+        assert_eq!(result, B256::ZERO);
+    }
+
+    #[test]
+    fn test_output_root_depends_on_hasher() {
+        let leaves = vec![[1u8; 32], [2u8; 32], [3u8; 32]];
+        let keccak = merkle_root(leaves.clone(), MerkleHasher::Keccak256);
+        let sha256 = merkle_root(leaves, MerkleHasher::Sha256);
+
+        let left = hash_pair_keccak(&[1u8; 32], &[2u8; 32]);
+        let right = hash_pair_keccak(&[3u8; 32], &[3u8; 32]);
+        assert_eq!(keccak, B256::from(hash_pair_keccak(&left, &right)));
+        assert_ne!(keccak, sha256);
+    }
+
+    #[test]
+    fn test_parallel_levels_match_sequential() {
+        let leaves: Vec<[u8; 32]> = (0..3 * PARALLEL_MIN_NODES as u32 + 1)
+            .map(|i| keccak256(i.to_be_bytes()).0)
+            .collect();
+
+        let mut level = leaves.clone();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|chunk| hash_pair_keccak(&chunk[0], chunk.get(1).unwrap_or(&chunk[0])))
+                .collect();
+        }
+        assert_eq!(
+            merkle_root(leaves.clone(), MerkleHasher::Keccak256),
+            B256::from(level[0])
+        );
+
+        let tx_hashes: Vec<String> = leaves.iter().map(hex::encode).collect();
+        assert_eq!(tx_leaves(&tx_hashes), leaves);
+    }
+
+    #[test]
+    fn test_tree_push_matches_rebuild() {
+        for hasher in [
+            MerkleHasher::Keccak256,
+            MerkleHasher::Sha256,
+            MerkleHasher::V2,
+        ] {
+            let leaves: Vec<[u8; 32]> = (0..20u8).map(|i| [i; 32]).collect();
+            let mut tree = MerkleTree::new(vec![], hasher);
+            assert_eq!(tree.root(), B256::ZERO);
+            assert!(tree.proof(0).is_none());
+
+            for (count, leaf) in leaves.iter().enumerate() {
+                tree.push(*leaf);
+                let rebuilt = MerkleTree::new(leaves[..=count].to_vec(), hasher);
+                assert_eq!(tree, rebuilt, "{} leaves", count + 1);
+            }
+            for index in 0..leaves.len() {
+                assert!(tree.verify(&tree.proof(index).unwrap()));
+            }
+
+            let mut wrong = tree.proof(3).unwrap();
+            wrong.leaf_index = 4;
+            assert!(!tree.verify(&wrong));
+        }
+    }
+
+    #[test]
+    fn test_tree_bytes_round_trip() {
+        for count in [0, 1, 2, 5, 8] {
+            let tree = MerkleTree::new((0..count).map(|i| [i; 32]).collect(), MerkleHasher::Sha256);
+            let bytes = tree.to_bytes();
+            let read =
+                MerkleTree::from_bytes(count as usize, MerkleHasher::Sha256, &bytes).unwrap();
+            assert_eq!(read, tree);
+            assert!(
+                MerkleTree::from_bytes(count as usize + 1, MerkleHasher::Sha256, &bytes).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_monero_tree_hash_shape() {
+        let h: Vec<[u8; 32]> = (1..=5u8).map(|i| [i; 32]).collect();
+        let pair = |a: &[u8; 32], b: &[u8; 32]| hash_pair_keccak(a, b);
+
+        assert_eq!(monero_tree_hash(&h[..1]), h[0]);
+        // Three leaves: the first is carried up, the last two are paired
+        assert_eq!(monero_tree_hash(&h[..3]), pair(&h[0], &pair(&h[1], &h[2])));
+        assert_eq!(
+            monero_tree_hash(&h[..4]),
+            pair(&pair(&h[0], &h[1]), &pair(&h[2], &h[3]))
+        );
+        // Five leaves: three carried up, the last two paired
+        assert_eq!(
+            monero_tree_hash(&h),
+            pair(&pair(&h[0], &h[1]), &pair(&h[2], &pair(&h[3], &h[4])))
+        );
+    }
+
+    #[test]
+    fn test_verify_genesis_block() {
+        let header = BlockHeader {
+            height: 0,
+            hash: "418015bb9ae982a1975da7d79277c2705727a56894ba0fb246adaabb1f4632e3".to_string(),
+            major_version: 1,
+            minor_version: 0,
+            timestamp: 0,
+            prev_hash: "0".repeat(64),
+            nonce: 10000,
+            difficulty: 1,
+            wide_difficulty: None,
+            cumulative_difficulty: 1,
+            wide_cumulative_difficulty: None,
+        };
+        let miner_tx = "c88ce9783b4f11190d7b9c17a69c1c52200f9faaee8e98dd07e6811175177139";
+
+        let root = verify_block_tx_hashes(&header, miner_tx, &[]).unwrap();
+        assert_eq!(root, parse_hex_to_b256(miner_tx).unwrap());
+        assert!(verify_block_tx_hashes(&header, miner_tx, &["a".repeat(64)]).is_err());
+    }
+
+    #[test]
+    fn test_parse_hasher() {
+        assert_eq!(
+            "SHA256".parse::<MerkleHasher>().unwrap(),
+            MerkleHasher::Sha256
+        );
+        assert_eq!(MerkleHasher::default().to_string(), "keccak256");
+        assert_eq!("v2".parse::<MerkleHasher>().unwrap(), MerkleHasher::V2);
+        assert!("blake2b".parse::<MerkleHasher>().is_err());
+    }
+
+    #[test]
+    fn test_hasher_for_recorded_output_version() {
+        use MerkleHasher::*;
+        // A block keeps the tree it was posted with after a switch
+        for hasher in [Keccak256, Sha256, V2] {
+            assert_eq!(
+                MerkleHasher::for_output_version(hasher.version()).unwrap(),
+                hasher
+            );
+        }
+        assert_ne!(Sha256.version(), Keccak256.version());
+        assert!(MerkleHasher::for_output_version(4).is_err());
+    }
+
+    fn output(tx: u8, index: u64) -> MoneroOutput {
+        MoneroOutput {
+            tx_hash: B256::from([tx; 32]),
+            output_index: index,
+            ecdh_amount: B256::from([0xaa; 32]),
+            output_pub_key: B256::from([tx.wrapping_add(index as u8); 32]),
+            commitment: B256::from([0xcc; 32]),
+        }
+    }
+
+    #[test]
+    fn test_canonical_order_follows_the_block() {
+        // Transaction 9 comes first in the block despite its larger hash,
+        // and its outputs arrive after transaction 2's
+        let tx_order = [B256::from([9; 32]), B256::from([2; 32])];
+        let outputs = vec![output(2, 0), output(9, 1), output(9, 0), output(9, 2)];
+        let sorted = canonical_order(&outputs, &tx_order);
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|o| (o.tx_hash[0], o.output_index))
+                .collect::<Vec<_>>(),
+            [(9, 0), (9, 1), (9, 2), (2, 0)]
+        );
+        assert_eq!(canonical_order(&sorted, &tx_order), sorted);
+        assert_eq!(
+            MerkleHasher::V2.output_order(&outputs, &tx_order)[..],
+            sorted[..]
+        );
+
+        // Version 1 keeps the given order
+        assert_eq!(
+            MerkleHasher::Keccak256.output_order(&outputs, &tx_order)[..],
+            outputs[..]
+        );
+
+        // Outputs of a transaction outside the block go last
+        let stray = canonical_order(&[output(7, 0), output(2, 0)], &tx_order);
+        assert_eq!(stray[0].tx_hash, tx_order[1]);
+    }
+
+    #[test]
+    fn test_v2_encoding() {
+        let outputs = vec![output(1, 0), output(1, 1), output(2, 0)];
+        let leaves: Vec<[u8; 32]> = outputs.iter().map(output_leaf_v2).collect();
+
+        let mut packed = vec![LEAF_PREFIX];
+        packed.extend_from_slice(outputs[0].tx_hash.as_slice());
+        packed.extend_from_slice(&U256::ZERO.to_be_bytes::<32>());
+        packed.extend_from_slice(&[0xaa; 32]);
+        packed.extend_from_slice(&[1; 32]);
+        packed.extend_from_slice(&[0xcc; 32]);
+        assert_eq!(leaves[0], keccak256(&packed).0);
+        assert_ne!(leaves[0], output_leaf(&outputs[0]));
+
+        let node = |l: &[u8; 32], r: &[u8; 32]| keccak256([&[NODE_PREFIX][..], l, r].concat()).0;
+        let top = node(&node(&leaves[0], &leaves[1]), &node(&leaves[2], &leaves[2]));
+        let root = keccak256([&[ROOT_PREFIX, 2][..], &top].concat());
+        assert_eq!(compute_output_merkle_root(&outputs, MerkleHasher::V2), root);
+
+        // A single output is still wrapped, and the root names its version
+        let single = output_tree(&outputs[..1], MerkleHasher::V2);
+        assert_eq!(
+            single.root(),
+            keccak256([&[ROOT_PREFIX, 2][..], &leaves[0]].concat())
+        );
+        assert_eq!(MerkleHasher::V2.version(), 2);
+        assert_eq!(MerkleHasher::Sha256.version(), 3);
+
+        // A version 2 path folded as version 1 misses the root
+        let tree = output_tree(&outputs, MerkleHasher::V2);
+        let proof = tree.proof(2).unwrap();
+        assert!(tree.verify(&proof));
+        assert_eq!(proof.compute_root(MerkleHasher::V2), root);
+        assert_ne!(proof.compute_root(MerkleHasher::Keccak256), root);
+        assert_eq!(
+            compute_output_merkle_root(&[], MerkleHasher::V2),
+            B256::ZERO
+        );
+    }
+}
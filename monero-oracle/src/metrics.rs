@@ -0,0 +1,480 @@
+//! Prometheus metrics
+//!
+//! Enabled by setting `METRICS_PORT`; metrics are served in the Prometheus text
+//! format at `GET /metrics`. Metrics are always recorded so that enabling the
+//! endpoint never changes oracle behavior.
+
+use anyhow::Result;
+use axum::{routing, Router};
+use prometheus::{
+    Counter, Encoder, Gauge, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::OnceLock;
+use tracing::info;
+
+pub struct Metrics {
+    registry: Registry,
+    pub blocks_posted: IntCounter,
+    pub root_mismatches: IntCounter,
+    pub postings_confirmed: IntCounter,
+    pub postings_reorged: IntCounter,
+    pub heartbeats_sent: IntCounter,
+    pub blocks_exported: IntCounter,
+    pub block_export_failures: IntCounter,
+    pub db_backups: IntCounter,
+    pub db_backup_failures: IntCounter,
+    pub archive_objects_pruned: IntCounter,
+    pub watched_block: IntGauge,
+    pub mismatched_blocks: IntGauge,
+    pub monero_tip_height: IntGauge,
+    pub contract_latest_block: IntGauge,
+    pub lag_blocks: IntGauge,
+    pub extra_contract_latest_block: IntGaugeVec,
+    pub extra_contract_lag_blocks: IntGaugeVec,
+    pub extra_contract_failures: IntCounterVec,
+    pub contract_reverts: IntCounterVec,
+    pub rpc_errors: IntCounterVec,
+    pub rpc_retries: IntCounterVec,
+    pub gas_used: IntCounter,
+    pub transactions_replaced: IntCounter,
+    pub gas_spent_eth: Counter,
+    pub wallet_balance_eth: Gauge,
+    pub posting_paused: IntGauge,
+    pub contract_paused: IntGauge,
+    pub read_only: IntGauge,
+    pub lease_held: IntGauge,
+    pub poll_duration_seconds: Histogram,
+    pub poll_errors: IntCounterVec,
+    pub poll_stalls: IntCounter,
+    pub deposits_detected: IntCounter,
+    pub deposits_rejected: IntCounter,
+    pub pending_deposits: IntGauge,
+    pub deposits_minted: IntCounter,
+    pub deposits_held: IntCounter,
+    pub withdrawals_queued: IntCounterVec,
+    pub withdrawals_paid: IntCounter,
+    pub withdrawals_confirmed: IntCounter,
+    pub withdrawals_failed: IntCounter,
+    pub bridge_balance_xmr: Gauge,
+    pub bridge_unspent_outputs: IntGauge,
+    pub bridge_unexpected_spends: IntCounter,
+    pub attestations_signed: IntCounter,
+    pub attestations_collected: IntCounter,
+    pub attestations_gossiped: IntCounter,
+    pub gossip_peers: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("oracle".to_string()), None)?;
+
+        let metrics = Self {
+            blocks_posted: IntCounter::new(
+                "blocks_posted_total",
+                "Monero blocks posted to the contract",
+            )?,
+            root_mismatches: IntCounter::new(
+                "root_mismatches_total",
+                "Posted blocks whose stored hash or roots differ from the computed ones",
+            )?,
+            postings_confirmed: IntCounter::new(
+                "postings_confirmed_total",
+                "Block postings still on-chain when re-checked at UNICHAIN_CONFIRMATION_DEPTH",
+            )?,
+            postings_reorged: IntCounter::new(
+                "postings_reorged_total",
+                "Block postings whose transactions a Unichain reorg dropped",
+            )?,
+            heartbeats_sent: IntCounter::new(
+                "heartbeats_sent_total",
+                "heartbeat() calls sent with HEARTBEAT_INTERVAL_SECS",
+            )?,
+            blocks_exported: IntCounter::new(
+                "blocks_exported_total",
+                "Processed blocks written to BLOCK_EXPORT_TARGET",
+            )?,
+            block_export_failures: IntCounter::new(
+                "block_export_failures_total",
+                "Processed blocks that couldn't be written to BLOCK_EXPORT_TARGET",
+            )?,
+            db_backups: IntCounter::new(
+                "db_backups_total",
+                "Database backups written to DB_BACKUP_TARGET",
+            )?,
+            db_backup_failures: IntCounter::new(
+                "db_backup_failures_total",
+                "Database backups that failed",
+            )?,
+            archive_objects_pruned: IntCounter::new(
+                "archive_objects_pruned_total",
+                "Block exports and database backups deleted by their retention limits",
+            )?,
+            watched_block: IntGauge::new(
+                "watched_block",
+                "Highest posted block checked in watch mode",
+            )?,
+            mismatched_blocks: IntGauge::new(
+                "mismatched_blocks",
+                "Posted blocks that currently differ from the computed ones, in watch mode",
+            )?,
+            monero_tip_height: IntGauge::new("monero_tip_height", "Latest Monero block height")?,
+            contract_latest_block: IntGauge::new(
+                "contract_latest_block",
+                "Latest Monero block stored in the contract",
+            )?,
+            lag_blocks: IntGauge::new(
+                "lag_blocks",
+                "Blocks between the Monero tip and the contract",
+            )?,
+            extra_contract_latest_block: IntGaugeVec::new(
+                Opts::new(
+                    "extra_contract_latest_block",
+                    "Latest Monero block stored in each EXTRA_BRIDGE_ADDRESSES contract",
+                ),
+                &["contract"],
+            )?,
+            extra_contract_lag_blocks: IntGaugeVec::new(
+                Opts::new(
+                    "extra_contract_lag_blocks",
+                    "Blocks between the Monero tip and each extra contract",
+                ),
+                &["contract"],
+            )?,
+            extra_contract_failures: IntCounterVec::new(
+                Opts::new(
+                    "extra_contract_failures_total",
+                    "Polls that failed to bring an extra contract up to date",
+                ),
+                &["contract"],
+            )?,
+            contract_reverts: IntCounterVec::new(
+                Opts::new(
+                    "contract_reverts_total",
+                    "Block postings the contract reverted, by recovery action",
+                ),
+                &["recovery"],
+            )?,
+            rpc_errors: IntCounterVec::new(
+                Opts::new("rpc_errors_total", "Failed RPC calls"),
+                &["rpc", "method"],
+            )?,
+            rpc_retries: IntCounterVec::new(
+                Opts::new(
+                    "rpc_retries_total",
+                    "RPC calls retried after a transient failure",
+                ),
+                &["rpc", "method"],
+            )?,
+            gas_used: IntCounter::new("gas_used_total", "Gas used by oracle transactions")?,
+            transactions_replaced: IntCounter::new(
+                "transactions_replaced_total",
+                "Pending oracle transactions replaced with the same nonce",
+            )?,
+            gas_spent_eth: Counter::new(
+                "gas_spent_eth_total",
+                "ETH spent on gas by oracle transactions",
+            )?,
+            wallet_balance_eth: Gauge::new("wallet_balance_eth", "Oracle wallet balance in ETH")?,
+            posting_paused: IntGauge::new(
+                "posting_paused",
+                "1 while block posting is paused for a low oracle balance",
+            )?,
+            contract_paused: IntGauge::new(
+                "contract_paused",
+                "1 while the bridge contract is paused",
+            )?,
+            read_only: IntGauge::new(
+                "read_only",
+                "1 while no configured key holds the contract's oracle role",
+            )?,
+            lease_held: IntGauge::new(
+                "lease_held",
+                "1 while this instance holds the leader lease",
+            )?,
+            poll_duration_seconds: Histogram::with_opts(
+                HistogramOpts::new("poll_duration_seconds", "Duration of one poll cycle")
+                    .buckets(vec![0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0]),
+            )?,
+            poll_errors: IntCounterVec::new(
+                Opts::new(
+                    "poll_errors_total",
+                    "Poll cycles that failed, by error class",
+                ),
+                &["kind"],
+            )?,
+            poll_stalls: IntCounter::new(
+                "poll_stalls_total",
+                "Polls cancelled for running past POLL_TIMEOUT_SECS",
+            )?,
+            deposits_detected: IntCounter::new(
+                "deposits_detected_total",
+                "Deposits to the bridge found by view-key scanning",
+            )?,
+            deposits_rejected: IntCounter::new(
+                "deposits_rejected_total",
+                "Outputs to the bridge whose decrypted amount doesn't open their commitment",
+            )?,
+            pending_deposits: IntGauge::new(
+                "pending_deposits",
+                "Deposits seen in the txpool that aren't in a posted block yet",
+            )?,
+            deposits_minted: IntCounter::new(
+                "deposits_minted_total",
+                "Detected deposits minted to their registered recipient",
+            )?,
+            deposits_held: IntCounter::new(
+                "deposits_held_total",
+                "Deposits held for review instead of minted, for falling outside the deposit limits",
+            )?,
+            withdrawals_queued: IntCounterVec::new(
+                Opts::new(
+                    "withdrawals_queued_total",
+                    "Burn events read from the contract, by initial status",
+                ),
+                &["status"],
+            )?,
+            withdrawals_paid: IntCounter::new(
+                "withdrawals_paid_total",
+                "Withdrawals relayed to the Monero network",
+            )?,
+            withdrawals_confirmed: IntCounter::new(
+                "withdrawals_confirmed_total",
+                "Withdrawal payments published with confirmWithdrawal",
+            )?,
+            withdrawals_failed: IntCounter::new(
+                "withdrawals_failed_total",
+                "Withdrawals given up on after repeated failures",
+            )?,
+            bridge_balance_xmr: Gauge::new(
+                "bridge_balance_xmr",
+                "Unlocked balance of the bridge wallet account in XMR",
+            )?,
+            bridge_unspent_outputs: IntGauge::new(
+                "bridge_unspent_outputs",
+                "Bridge wallet outputs whose key image hasn't been seen on-chain",
+            )?,
+            bridge_unexpected_spends: IntCounter::new(
+                "bridge_unexpected_spends_total",
+                "Bridge outputs spent by a transaction that isn't a withdrawal payout",
+            )?,
+            attestations_signed: IntCounter::new(
+                "attestations_signed_total",
+                "Block attestations signed by this oracle",
+            )?,
+            attestations_collected: IntCounter::new(
+                "attestations_collected_total",
+                "Valid attestations fetched from peer signers",
+            )?,
+            attestations_gossiped: IntCounter::new(
+                "attestations_gossiped_total",
+                "New valid attestations received by gossip",
+            )?,
+            gossip_peers: IntGauge::new(
+                "gossip_peers",
+                "Connected gossip peers authenticated as signers",
+            )?,
+            registry,
+        };
+
+        metrics
+            .registry
+            .register(Box::new(metrics.blocks_posted.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.root_mismatches.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.postings_confirmed.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.postings_reorged.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.heartbeats_sent.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.blocks_exported.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.block_export_failures.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.db_backups.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.db_backup_failures.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.archive_objects_pruned.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.watched_block.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.mismatched_blocks.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.monero_tip_height.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.contract_latest_block.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.lag_blocks.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.extra_contract_latest_block.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.extra_contract_lag_blocks.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.extra_contract_failures.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.contract_reverts.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.rpc_errors.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.rpc_retries.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.gas_used.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.transactions_replaced.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.gas_spent_eth.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.wallet_balance_eth.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.posting_paused.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.contract_paused.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.read_only.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.lease_held.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.poll_duration_seconds.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.poll_errors.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.poll_stalls.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.deposits_detected.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.deposits_rejected.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.pending_deposits.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.deposits_minted.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.deposits_held.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.withdrawals_queued.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.withdrawals_paid.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.withdrawals_confirmed.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.withdrawals_failed.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.bridge_balance_xmr.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.bridge_unspent_outputs.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.bridge_unexpected_spends.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.attestations_signed.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.attestations_collected.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.attestations_gossiped.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.gossip_peers.clone()))?;
+
+        Ok(metrics)
+    }
+
+    /// Pass `result` through, counting it as an RPC error if it failed
+    pub fn observe_rpc<T, E>(
+        &self,
+        rpc: &str,
+        method: &str,
+        result: std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        if result.is_err() {
+            self.rpc_errors.with_label_values(&[rpc, method]).inc();
+        }
+        result
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            return format!("# failed to encode metrics: {}\n", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Process-wide metrics
+pub fn get() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| Metrics::new().expect("metric definitions are valid"))
+}
+
+/// Serve `/metrics` until the process exits
+pub async fn serve(port: u16) -> Result<()> {
+    let app = Router::new().route("/metrics", routing::get(|| async { get().render() }));
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("📈 Metrics listening on {}/metrics", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_rpc_errors() {
+        let metrics = get();
+        let failed: std::result::Result<(), &str> = Err("boom");
+        assert!(metrics.observe_rpc("monero", "get_block", failed).is_err());
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("oracle_rpc_errors_total{method=\"get_block\",rpc=\"monero\"}"));
+        assert!(rendered.contains("oracle_blocks_posted_total"));
+    }
+}
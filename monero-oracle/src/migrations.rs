@@ -0,0 +1,280 @@
+//! Versioned schema migrations for the oracle database
+//!
+//! The schema is built by the numbered SQL files in `migrations/`, compiled
+//! into the binary. A database records the last one applied in SQLite's
+//! `user_version`, and each applied migration with its name and time in
+//! `schema_migrations`. Opening a database applies the ones it is missing,
+//! in order, each in its own transaction together with the version bump, so
+//! a failure leaves the database at the previous version rather than half
+//! migrated.
+//!
+//! A database at a version this build doesn't know was written by a newer
+//! release and is never opened: running an older binary against it could
+//! misread or overwrite what it doesn't understand. With
+//! `DB_AUTO_MIGRATE=false`, the oracle also refuses a database with pending
+//! migrations, leaving the upgrade to `db migrate`. Before the oracle or
+//! `db migrate` upgrades a database, it copies it to
+//! `DATABASE_PATH.v{version}.bak`.
+//!
+//! Migrations are append-only: a released one is never edited, and a schema
+//! change is a new file with the next number, added to [`MIGRATIONS`].
+//! Databases from before versioning are at version 0 and take the
+//! `0001_baseline` migration, which is idempotent.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
+use std::path::Path;
+
+/// One schema change
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every migration, in version order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        sql: include_str!("../migrations/0001_baseline.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "posting_confirmations",
+        sql: include_str!("../migrations/0002_posting_confirmations.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "deposit_output_keys",
+        sql: include_str!("../migrations/0003_deposit_output_keys.sql"),
+    },
+];
+
+const HISTORY: &str = "
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version             INTEGER PRIMARY KEY,
+    name                TEXT NOT NULL,
+    applied_at          TEXT NOT NULL
+);
+";
+
+/// Schema version of this build
+pub fn latest() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Schema version of the database, 0 before any migration
+pub fn version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Whether the database has no tables yet, as when just created
+pub fn is_empty(conn: &Connection) -> Result<bool> {
+    Ok(!conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get::<_, bool>(0),
+    )?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub applied_at: String,
+}
+
+/// Where a database stands against this build, for `db status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaStatus {
+    pub version: u32,
+    pub latest: u32,
+    /// Names of the migrations not applied yet
+    pub pending: Vec<String>,
+    pub applied: Vec<AppliedMigration>,
+}
+
+impl SchemaStatus {
+    pub fn is_current(&self) -> bool {
+        self.version == self.latest
+    }
+}
+
+/// Status of the database at `path`, opened read-only
+pub fn status_at(path: &Path) -> Result<SchemaStatus> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open database at {}", path.display()))?;
+    status(&conn)
+}
+
+pub fn status(conn: &Connection) -> Result<SchemaStatus> {
+    let version = version(conn)?;
+    let has_history: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations')",
+        [],
+        |row| row.get(0),
+    )?;
+    let applied = if has_history {
+        let mut stmt = conn
+            .prepare("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")?;
+        let applied = stmt
+            .query_map([], |row| {
+                Ok(AppliedMigration {
+                    version: row.get(0)?,
+                    name: row.get(1)?,
+                    applied_at: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        applied
+    } else {
+        Vec::new()
+    };
+    Ok(SchemaStatus {
+        version,
+        latest: latest(),
+        pending: MIGRATIONS
+            .iter()
+            .filter(|migration| migration.version > version)
+            .map(|migration| format!("{:04}_{}", migration.version, migration.name))
+            .collect(),
+        applied,
+    })
+}
+
+/// Refuse a database written by a newer release
+pub fn ensure_known(version: u32) -> Result<()> {
+    anyhow::ensure!(
+        version <= latest(),
+        "Database schema is at version {}, newer than this release's {}: \
+         run the release that wrote it, or restore a backup",
+        version,
+        latest()
+    );
+    Ok(())
+}
+
+/// Apply the migrations `conn` is missing; returns the versions applied
+pub fn migrate(conn: &mut Connection) -> Result<Vec<u32>> {
+    ensure_known(version(conn)?)?;
+    apply(conn, MIGRATIONS)
+}
+
+fn apply(conn: &mut Connection, migrations: &[Migration]) -> Result<Vec<u32>> {
+    let current = version(conn)?;
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(HISTORY)?;
+        tx.execute_batch(migration.sql).with_context(|| {
+            format!(
+                "Migration {:04}_{} failed; the database is still at version {}",
+                migration.version,
+                migration.name,
+                applied.last().copied().unwrap_or(current)
+            )
+        })?;
+        tx.execute(
+            "INSERT OR REPLACE INTO schema_migrations (version, name, applied_at)
+             VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.name, Utc::now().to_rfc3339()],
+        )?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: &[Migration] = &[
+        Migration {
+            version: 1,
+            name: "widgets",
+            sql: "CREATE TABLE widgets (id INTEGER PRIMARY KEY);",
+        },
+        Migration {
+            version: 2,
+            name: "widget_names",
+            sql: "ALTER TABLE widgets ADD COLUMN name TEXT NOT NULL DEFAULT '';",
+        },
+        Migration {
+            version: 3,
+            name: "broken",
+            sql: "CREATE TABLE gadgets (id INTEGER); INSERT INTO nowhere VALUES (1);",
+        },
+    ];
+
+    #[test]
+    fn test_migrations_apply_in_order_and_stop_at_a_failure() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(apply(&mut conn, &STEPS[..1]).unwrap(), vec![1]);
+        assert_eq!(apply(&mut conn, &STEPS[..2]).unwrap(), vec![2]);
+        assert!(apply(&mut conn, &STEPS[..2]).unwrap().is_empty());
+        conn.execute("INSERT INTO widgets (name) VALUES ('a')", [])
+            .unwrap();
+
+        // The failed migration is rolled back whole
+        assert!(apply(&mut conn, STEPS).is_err());
+        assert_eq!(version(&conn).unwrap(), 2);
+        let gadgets: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'gadgets')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!gadgets);
+
+        let status = status(&conn).unwrap();
+        assert_eq!(
+            status
+                .applied
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>(),
+            ["widgets", "widget_names"]
+        );
+    }
+
+    #[test]
+    fn test_baseline_takes_unversioned_databases() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // A database from before versioning, with a row to keep
+        conn.execute_batch(
+            "CREATE TABLE sync_state (name TEXT PRIMARY KEY, value INTEGER NOT NULL);
+             INSERT INTO sync_state VALUES ('unichain_burn_events', 42);",
+        )
+        .unwrap();
+        assert_eq!(
+            status(&conn).unwrap().pending,
+            [
+                "0001_baseline",
+                "0002_posting_confirmations",
+                "0003_deposit_output_keys"
+            ]
+        );
+
+        assert_eq!(migrate(&mut conn).unwrap(), vec![1, 2, 3]);
+        let status = status(&conn).unwrap();
+        assert!(status.is_current());
+        assert!(status.pending.is_empty());
+        let cursor: i64 = conn
+            .query_row("SELECT value FROM sync_state", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cursor, 42);
+
+        conn.pragma_update(None, "user_version", latest() + 1)
+            .unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
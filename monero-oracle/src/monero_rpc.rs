@@ -0,0 +1,1507 @@
+//! Monero daemon RPC client and response types
+
+use crate::{
+    digest_auth::{self, Challenge, Credentials},
+    epee::{self, Section, SectionExt, Value},
+    error::OracleError,
+    fixtures::Fixtures,
+    http::HttpSettings,
+    metrics, monero_tx,
+    rate_limit::{RateLimit, RateLimiter},
+    retry::RetryPolicy,
+    scanner::zero_commit,
+    subaddress::Network,
+};
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use reqwest::{header, Certificate, Client, Proxy, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tracing::{info, warn};
+
+/// JSON-RPC error code monerod returns for methods it doesn't serve
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Serialize)]
+pub(crate) struct JsonRpcRequest<T> {
+    pub jsonrpc: &'static str,
+    pub id: &'static str,
+    pub method: &'static str,
+    pub params: T,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JsonRpcResponse<T> {
+    pub result: Option<T>,
+    pub error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JsonRpcError {
+    #[serde(default)]
+    pub code: i64,
+    pub message: String,
+}
+
+/// monerod refused `method`: it runs with `--restricted-rpc` (or is too old
+/// to have it), or a proxy in front of it blocks the call. Restricted nodes
+/// answer with "Method not found" or HTTP 404 rather than saying why.
+#[derive(Debug, thiserror::Error)]
+#[error("monerod doesn't serve {method} (restricted RPC?)")]
+pub struct MethodUnavailable {
+    pub method: &'static str,
+}
+
+/// Whether `error` is a [`MethodUnavailable`]
+pub fn is_method_unavailable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<MethodUnavailable>())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockHeaderResponse {
+    pub block_header: BlockHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockHeadersRangeResponse {
+    headers: Vec<BlockHeader>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub hash: String,
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub timestamp: u64,
+    pub prev_hash: String,
+    pub nonce: u32,
+    /// Low 64 bits of the difficulty
+    #[serde(default)]
+    pub difficulty: u64,
+    /// Full difficulty as a `0x` hex string (monerod v0.15+)
+    #[serde(default)]
+    pub wide_difficulty: Option<String>,
+    /// Low 64 bits of the chain's total difficulty up to this block
+    #[serde(default)]
+    pub cumulative_difficulty: u64,
+    #[serde(default)]
+    pub wide_cumulative_difficulty: Option<String>,
+}
+
+impl BlockHeader {
+    /// Difficulty the block was mined at
+    pub fn difficulty(&self) -> Result<u128> {
+        wide_value(self.wide_difficulty.as_deref(), self.difficulty)
+    }
+
+    /// Total difficulty of the chain up to this block, which decides forks
+    pub fn cumulative_difficulty(&self) -> Result<u128> {
+        wide_value(
+            self.wide_cumulative_difficulty.as_deref(),
+            self.cumulative_difficulty,
+        )
+    }
+}
+
+/// A `wide_*` 128-bit hex field, or its 64-bit counterpart from older daemons
+fn wide_value(wide: Option<&str>, low: u64) -> Result<u128> {
+    match wide {
+        Some(wide) => u128::from_str_radix(wide.trim_start_matches("0x"), 16)
+            .with_context(|| format!("Invalid difficulty {}", wide)),
+        None => Ok(low.into()),
+    }
+}
+
+/// Subset of monerod's `get_info`
+#[derive(Debug, Deserialize)]
+pub struct DaemonInfo {
+    pub height: u64,
+    #[serde(default)]
+    pub testnet: bool,
+    #[serde(default)]
+    pub stagenet: bool,
+    /// Started with `--restricted-rpc`; public nodes usually are
+    #[serde(default)]
+    pub restricted: bool,
+}
+
+impl DaemonInfo {
+    /// Network the daemon runs on
+    pub fn network(&self) -> Network {
+        if self.testnet {
+            Network::Testnet
+        } else if self.stagenet {
+            Network::Stagenet
+        } else {
+            Network::Mainnet
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetBlockResponse {
+    pub block_header: BlockHeader,
+    /// Hex-encoded block blob
+    #[serde(default)]
+    pub blob: String,
+    pub json: String,
+    /// Only returned by monerod v0.13 and later
+    #[serde(default)]
+    pub miner_tx_hash: Option<String>,
+}
+
+impl GetBlockResponse {
+    /// The block decoded from its blob, with the miner transaction checked
+    /// against `miner_tx_hash`. The node's `json` rendering isn't used: it
+    /// is covered by no hash, so a node could change the miner tx's outputs
+    /// there and nothing would notice.
+    pub fn decode(&self) -> Result<monero_tx::DecodedBlock> {
+        let height = self.block_header.height;
+        let blob = hex::decode(&self.blob)
+            .with_context(|| format!("Invalid blob for block {}", height))?;
+        let block = monero_tx::decode_block(&blob)
+            .with_context(|| format!("Failed to decode block {}", height))?;
+        let miner_tx_hash = self
+            .miner_tx_hash
+            .as_deref()
+            .context("Monero node did not return miner_tx_hash (requires monerod v0.13+)")?;
+        if parse_hex_to_b256(miner_tx_hash)? != block.miner_tx.hash {
+            return Err(OracleError::Consensus(format!(
+                "Block {} miner tx hashes to {}, monerod says {}",
+                height, block.miner_tx.hash, miner_tx_hash
+            ))
+            .into());
+        }
+        Ok(block)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockJson {
+    pub miner_tx: Option<TransactionJson>,
+    pub tx_hashes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GetTransactionsRequest {
+    pub txs_hashes: Vec<String>,
+    pub decode_as_json: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GetTransactionsResponse {
+    pub status: String,
+    pub txs: Option<Vec<TransactionInfo>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetTransactionPoolResponse {
+    status: String,
+    /// Omitted when the pool is empty
+    #[serde(default)]
+    transactions: Vec<PoolTransaction>,
+}
+
+/// A transaction waiting in monerod's txpool
+#[derive(Debug, Deserialize)]
+pub struct PoolTransaction {
+    pub id_hash: String,
+    /// Hex-encoded transaction blob
+    pub tx_blob: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionInfo {
+    pub tx_hash: String,
+    /// Hex-encoded transaction blob
+    #[serde(default)]
+    pub as_hex: String,
+    pub block_height: Option<u64>,
+    #[serde(default)]
+    pub in_pool: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionJson {
+    #[serde(default)]
+    pub vin: Vec<TxInput>,
+    pub vout: Option<Vec<TxOutput>>,
+    #[serde(default)]
+    pub extra: Vec<u8>,
+    pub rct_signatures: Option<RctSignatures>,
+}
+
+impl TransactionJson {
+    /// Key images of the outputs this transaction spends
+    pub fn key_images(&self) -> impl Iterator<Item = &str> {
+        self.vin
+            .iter()
+            .filter_map(|input| input.key.as_ref())
+            .map(|key| key.k_image.as_str())
+    }
+
+    /// A miner transaction, whose outputs carry cleartext amounts
+    pub fn is_coinbase(&self) -> bool {
+        self.vin.iter().any(|input| input.gen.is_some())
+    }
+}
+
+/// A transaction input; miner transactions have `gen` instead of `key`
+#[derive(Debug, Deserialize)]
+pub struct TxInput {
+    pub key: Option<InputKey>,
+    pub gen: Option<GenInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenInput {
+    pub height: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InputKey {
+    pub k_image: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TxOutput {
+    /// Zero for RingCT outputs, whose amount is in `ecdhInfo`
+    #[serde(default)]
+    pub amount: u64,
+    pub target: Option<OutputTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutputTarget {
+    pub key: Option<String>,
+    pub tagged_key: Option<TaggedKey>,
+}
+
+impl OutputTarget {
+    /// One-time output key, from either the pre- or post-view-tag format
+    pub fn public_key(&self) -> Option<&str> {
+        self.key
+            .as_deref()
+            .or(self.tagged_key.as_ref().map(|t| t.key.as_str()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaggedKey {
+    pub key: String,
+    pub view_tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RctSignatures {
+    #[serde(rename = "ecdhInfo")]
+    pub ecdh_info: Option<Vec<EcdhInfo>>,
+    #[serde(rename = "outPk")]
+    pub out_pk: Option<Vec<String>>,
+}
+
+/// Encrypted amount: 8 bytes since Bulletproofs 2 (the mask is then
+/// derived from the shared secret), 32 bytes with a 32-byte mask before
+#[derive(Debug, Deserialize)]
+pub struct EcdhInfo {
+    pub amount: String,
+    pub mask: Option<String>,
+}
+
+/// Output data committed to by a block's output Merkle root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoneroOutput {
+    pub tx_hash: B256,
+    pub output_index: u64,
+    pub ecdh_amount: B256,
+    pub output_pub_key: B256,
+    pub commitment: B256,
+}
+
+/// How to reach monerod when it isn't on the local network
+#[derive(Debug, Clone, Default)]
+pub struct Connection {
+    /// `socks5h://` (Tor, onion nodes), `socks5://` or `http://` proxy
+    pub proxy: Option<String>,
+    /// PEM certificate to trust for an `https://` node, e.g. a self-signed one
+    pub ca_cert: Option<PathBuf>,
+    /// Timeouts and connection pooling
+    pub http: HttpSettings,
+}
+
+impl Connection {
+    /// HTTP client that goes through the proxy, trusts the extra CA and
+    /// gives up on unresponsive nodes
+    pub fn client(&self) -> Result<Client> {
+        let mut builder = self.http.apply(Client::builder());
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy).context("Invalid MONERO_RPC_PROXY")?);
+        }
+        if let Some(path) = &self.ca_cert {
+            let pem =
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let certificate = Certificate::from_pem(&pem).context("Invalid MONERO_RPC_CA_CERT")?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Client for a monerod JSON-RPC endpoint
+#[derive(Clone)]
+pub struct MoneroRpcClient {
+    /// Shared by clones, so [`Self::reconnect`] reaches all of them
+    client: Arc<Mutex<Client>>,
+    rpc_url: String,
+    retry: RetryPolicy,
+    limiter: RateLimiter,
+    login: Option<Credentials>,
+    /// Last digest challenge and the number of requests that have used it
+    challenge: Arc<Mutex<Option<(Challenge, u32)>>>,
+    fixtures: Option<Fixtures>,
+}
+
+impl MoneroRpcClient {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(Client::new())),
+            rpc_url,
+            retry: RetryPolicy::default(),
+            limiter: RateLimiter::default(),
+            login: None,
+            challenge: Arc::default(),
+            fixtures: None,
+        }
+    }
+
+    /// Endpoint this client talks to
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// Backoff used for transient failures (default: [`RetryPolicy::default`])
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limits to keep to for a public node (default: none)
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.limiter = RateLimiter::new(limit);
+        self
+    }
+
+    /// HTTP client to send requests with, e.g. from [`Connection::client`]
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Arc::new(Mutex::new(client));
+        self
+    }
+
+    /// Send later requests with `client`, dropping this client's pooled
+    /// connections. Requests already in flight keep their connection.
+    pub fn reconnect(&self, client: Client) {
+        *self.client.lock().expect("client lock poisoned") = client;
+    }
+
+    /// Log in to a monerod started with `--rpc-login`
+    pub fn with_login(mut self, login: Credentials) -> Self {
+        self.login = Some(login);
+        self
+    }
+
+    /// Record responses to disk, or answer requests from a recording
+    /// instead of the node
+    pub fn with_fixtures(mut self, fixtures: Fixtures) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    async fn post_json<B: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        method: &'static str,
+        body: &B,
+    ) -> Result<Response> {
+        self.post(path, method, &serde_json::to_vec(body)?, "application/json")
+            .await
+    }
+
+    /// POST `body` to `path`, answering monerod's digest challenge when a
+    /// login is set. Refused methods become [`MethodUnavailable`].
+    async fn post(
+        &self,
+        path: &str,
+        method: &'static str,
+        body: &[u8],
+        content_type: &'static str,
+    ) -> Result<Response> {
+        if let Some(recorded) = self
+            .fixtures
+            .as_ref()
+            .map(|fixtures| fixtures.load(method, path, body))
+            .transpose()?
+            .flatten()
+        {
+            return Ok(http::Response::new(recorded).into());
+        }
+
+        let url = Url::parse(&format!("{}{}", self.rpc_url, path))?;
+        let mut response = self
+            .send(&url, body, content_type)
+            .await
+            .map_err(|e| OracleError::monero(method, e))?;
+
+        // First request, or monerod expired the nonce: take the new challenge
+        if response.status() == StatusCode::UNAUTHORIZED && self.login.is_some() {
+            let challenge = Challenge::from_headers(
+                response
+                    .headers()
+                    .get_all(header::WWW_AUTHENTICATE)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok()),
+            )?;
+            *self.challenge.lock().expect("digest state poisoned") = Some((challenge, 0));
+            response = self
+                .send(&url, body, content_type)
+                .await
+                .map_err(|e| OracleError::monero(method, e))?;
+        }
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED if self.login.is_some() => {
+                return Err(OracleError::Config(
+                    "monerod rejected the RPC login (check MONERO_RPC_LOGIN)".to_string(),
+                )
+                .into())
+            }
+            StatusCode::UNAUTHORIZED => {
+                return Err(OracleError::Config(
+                    "monerod requires an RPC login (set MONERO_RPC_LOGIN)".to_string(),
+                )
+                .into())
+            }
+            StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+                return Err(MethodUnavailable { method }.into())
+            }
+            _ => {
+                response = response
+                    .error_for_status()
+                    .map_err(|e| OracleError::monero(method, e))?
+            }
+        }
+
+        match &self.fixtures {
+            Some(fixtures @ Fixtures::Record(_)) => {
+                let status = response.status();
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|e| OracleError::monero(method, e))?;
+                fixtures.save(method, path, body, &bytes)?;
+                let mut recorded = http::Response::new(bytes);
+                *recorded.status_mut() = status;
+                Ok(recorded.into())
+            }
+            _ => Ok(response),
+        }
+    }
+
+    async fn send(
+        &self,
+        url: &Url,
+        body: &[u8],
+        content_type: &'static str,
+    ) -> reqwest::Result<Response> {
+        let client = self.client.lock().expect("client lock poisoned").clone();
+        let mut request = client
+            .post(url.clone())
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body.to_vec());
+        if let Some(authorization) = self.authorization(url.path()) {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        request.send().await
+    }
+
+    /// Digest `Authorization` header for the next request, once monerod has
+    /// sent a challenge
+    fn authorization(&self, uri: &str) -> Option<String> {
+        let login = self.login.as_ref()?;
+        let mut challenge = self.challenge.lock().expect("digest state poisoned");
+        let (challenge, uses) = challenge.as_mut()?;
+        *uses += 1;
+        Some(challenge.authorization(login, "POST", uri, *uses, &digest_auth::cnonce()))
+    }
+
+    /// Call a `/json_rpc` method, retrying transient failures and counting
+    /// the ones that still fail in the RPC error metric
+    async fn json_rpc<P, R>(&self, method: &'static str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method,
+            params,
+        };
+
+        let result = self
+            .retry
+            .retry("monero", method, || async {
+                let _permit = self.limiter.acquire().await;
+                let response: JsonRpcResponse<R> = self
+                    .post_json("/json_rpc", method, &request)
+                    .await?
+                    .json()
+                    .await
+                    .map_err(|e| OracleError::monero(method, e))?;
+
+                if let Some(error) = response.error {
+                    if error.code == METHOD_NOT_FOUND {
+                        return Err(MethodUnavailable { method }.into());
+                    }
+                    return Err(OracleError::monero_status(method, &error.message).into());
+                }
+
+                response
+                    .result
+                    .ok_or_else(|| OracleError::Parse(format!("No result in {} response", method)))
+                    .map_err(Into::into)
+            })
+            .await;
+
+        metrics::get().observe_rpc("monero", method, result)
+    }
+
+    /// Call a binary (`.bin`) endpoint with an epee request, retrying like
+    /// the JSON calls
+    async fn bin_rpc(&self, path: &str, method: &'static str, request: Section) -> Result<Section> {
+        let body = epee::to_bytes(&request);
+        let result = self
+            .retry
+            .retry("monero", method, || async {
+                let _permit = self.limiter.acquire().await;
+                let bytes = self
+                    .post(path, method, &body, "application/octet-stream")
+                    .await?
+                    .bytes()
+                    .await
+                    .map_err(|e| OracleError::monero(method, e))?;
+                let response = epee::from_bytes(&bytes).map_err(OracleError::parse)?;
+
+                let status = response.bytes("status").map_err(OracleError::parse)?;
+                if status != b"OK" {
+                    let status = String::from_utf8_lossy(status);
+                    return Err(OracleError::monero_status(method, &status).into());
+                }
+                Ok(response)
+            })
+            .await;
+
+        metrics::get().observe_rpc("monero", method, result)
+    }
+
+    /// Consecutive blocks from `start_height` as raw blobs, with their
+    /// transactions' global output indices. monerod decides how many it
+    /// returns (at most 1000), so callers loop until they have what they need.
+    pub async fn get_blocks_bin(&self, start_height: u64) -> Result<BinaryBlocks> {
+        // The known-chain list must end at genesis; with only genesis in it,
+        // monerod starts at `start_height`
+        let genesis = parse_hex_to_b256(&self.get_block_header_by_height(0).await?.hash)?;
+        let request = Section::from([
+            ("block_ids".to_string(), Value::String(genesis.to_vec())),
+            ("start_height".to_string(), Value::U64(start_height)),
+            ("prune".to_string(), Value::Bool(false)),
+            ("no_miner_tx".to_string(), Value::Bool(false)),
+        ]);
+        let response = self
+            .bin_rpc("/get_blocks.bin", "get_blocks.bin", request)
+            .await?;
+
+        let blocks = response
+            .array("blocks")?
+            .iter()
+            .map(BlockBlobs::from_epee)
+            .collect::<Result<_>>()?;
+        let output_indices = response
+            .array("output_indices")?
+            .iter()
+            .map(|block| {
+                let block = block.as_object().context("Malformed output indices")?;
+                block
+                    .array("indices")?
+                    .iter()
+                    .map(|tx| {
+                        let tx = tx.as_object().context("Malformed output indices")?;
+                        u64_array(tx.array("indices")?)
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(BinaryBlocks {
+            start_height: response.u64("start_height")?,
+            current_height: response.u64("current_height")?,
+            blocks,
+            output_indices,
+        })
+    }
+
+    /// Raw blobs of the blocks at `heights`, in the same order
+    pub async fn get_blocks_by_height_bin(&self, heights: &[u64]) -> Result<Vec<BlockBlobs>> {
+        let request = Section::from([(
+            "heights".to_string(),
+            Value::Array(heights.iter().copied().map(Value::U64).collect()),
+        )]);
+        let response = self
+            .bin_rpc(
+                "/get_blocks_by_height.bin",
+                "get_blocks_by_height.bin",
+                request,
+            )
+            .await?;
+
+        let blocks = response
+            .array("blocks")?
+            .iter()
+            .map(BlockBlobs::from_epee)
+            .collect::<Result<Vec<_>>>()?;
+        anyhow::ensure!(
+            blocks.len() == heights.len(),
+            "Asked for {} blocks, got {}",
+            heights.len(),
+            blocks.len()
+        );
+        Ok(blocks)
+    }
+
+    /// One-time keys and commitments of the RingCT outputs with the given
+    /// global indices
+    pub async fn get_outs_bin(&self, indices: &[u64]) -> Result<Vec<OutputKey>> {
+        let outputs = indices
+            .iter()
+            .map(|&index| {
+                Value::Object(Section::from([
+                    ("amount".to_string(), Value::U64(0)),
+                    ("index".to_string(), Value::U64(index)),
+                ]))
+            })
+            .collect();
+        let request = Section::from([
+            ("outputs".to_string(), Value::Array(outputs)),
+            ("get_txid".to_string(), Value::Bool(true)),
+        ]);
+        let response = self
+            .bin_rpc("/get_outs.bin", "get_outs.bin", request)
+            .await?;
+
+        response
+            .array("outs")?
+            .iter()
+            .map(|out| {
+                let out = out.as_object().context("Malformed output entry")?;
+                Ok(OutputKey {
+                    key: B256::try_from(out.bytes("key")?)?,
+                    mask: B256::try_from(out.bytes("mask")?)?,
+                    unlocked: out.field("unlocked")?.as_bool().unwrap_or(false),
+                    height: out.u64("height")?,
+                    txid: B256::try_from(out.bytes("txid")?)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Number of RingCT outputs created in each block from `from_height` to
+    /// `to_height` (the tip when zero)
+    pub async fn get_output_distribution(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<OutputDistribution> {
+        let response: OutputDistributionResponse = self
+            .json_rpc(
+                "get_output_distribution",
+                serde_json::json!({
+                    "amounts": [0],
+                    "from_height": from_height,
+                    "to_height": to_height,
+                    "cumulative": false,
+                    "binary": false,
+                }),
+            )
+            .await?;
+        response
+            .distributions
+            .into_iter()
+            .find(|distribution| distribution.amount == 0)
+            .context("No RingCT output distribution in response")
+    }
+
+    /// Current per-byte fees, in piconero
+    pub async fn get_fee_estimate(&self) -> Result<FeeEstimate> {
+        self.json_rpc("get_fee_estimate", serde_json::json!({}))
+            .await
+    }
+
+    pub async fn get_info(&self) -> Result<DaemonInfo> {
+        self.json_rpc("get_info", serde_json::json!({})).await
+    }
+
+    pub async fn get_last_block_header(&self) -> Result<BlockHeader> {
+        let response: BlockHeaderResponse = self
+            .json_rpc("get_last_block_header", serde_json::json!({}))
+            .await?;
+        Ok(response.block_header)
+    }
+
+    pub async fn get_block_header_by_height(&self, height: u64) -> Result<BlockHeader> {
+        let response: BlockHeaderResponse = self
+            .json_rpc(
+                "get_block_header_by_height",
+                serde_json::json!({ "height": height }),
+            )
+            .await?;
+        Ok(response.block_header)
+    }
+
+    /// Headers of blocks `start..=end`, in order, in one call
+    pub async fn get_block_headers_range(&self, start: u64, end: u64) -> Result<Vec<BlockHeader>> {
+        let response: BlockHeadersRangeResponse = self
+            .json_rpc(
+                "get_block_headers_range",
+                serde_json::json!({ "start_height": start, "end_height": end }),
+            )
+            .await?;
+        let expected = end - start + 1;
+        anyhow::ensure!(
+            response.headers.len() as u64 == expected
+                && response
+                    .headers
+                    .iter()
+                    .zip(start..)
+                    .all(|(header, height)| header.height == height),
+            "Asked for headers {} to {}, got {} others",
+            start,
+            end,
+            response.headers.len()
+        );
+        Ok(response.headers)
+    }
+
+    pub async fn get_block(&self, height: u64) -> Result<GetBlockResponse> {
+        self.json_rpc("get_block", serde_json::json!({ "height": height }))
+            .await
+    }
+
+    pub async fn get_transactions(&self, tx_hashes: Vec<String>) -> Result<Vec<TransactionInfo>> {
+        if tx_hashes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let request = GetTransactionsRequest {
+            txs_hashes: tx_hashes,
+            decode_as_json: false,
+        };
+
+        let result = self
+            .retry
+            .retry("monero", "get_transactions", || async {
+                let _permit = self.limiter.acquire().await;
+                let response: GetTransactionsResponse = self
+                    .post_json("/get_transactions", "get_transactions", &request)
+                    .await?
+                    .json()
+                    .await
+                    .map_err(|e| OracleError::monero("get_transactions", e))?;
+
+                if response.status != "OK" {
+                    return Err(
+                        OracleError::monero_status("get_transactions", &response.status).into(),
+                    );
+                }
+
+                Ok(response.txs.unwrap_or_default())
+            })
+            .await;
+
+        metrics::get().observe_rpc("monero", "get_transactions", result)
+    }
+
+    /// Decoded transactions currently in the txpool, paired with their
+    /// hashes. Transactions that fail to decode are skipped.
+    pub async fn get_transaction_pool(&self) -> Result<Vec<(String, TransactionJson)>> {
+        let result = self
+            .retry
+            .retry("monero", "get_transaction_pool", || async {
+                let _permit = self.limiter.acquire().await;
+                let response: GetTransactionPoolResponse = self
+                    .post_json(
+                        "/get_transaction_pool",
+                        "get_transaction_pool",
+                        &serde_json::json!({}),
+                    )
+                    .await?
+                    .json()
+                    .await
+                    .map_err(|e| OracleError::monero("get_transaction_pool", e))?;
+
+                if response.status != "OK" {
+                    return Err(OracleError::monero_status(
+                        "get_transaction_pool",
+                        &response.status,
+                    )
+                    .into());
+                }
+
+                Ok(response.transactions)
+            })
+            .await;
+
+        let transactions = metrics::get().observe_rpc("monero", "get_transaction_pool", result)?;
+        Ok(decode_pool_transactions(transactions))
+    }
+
+    /// Height of the block containing `tx_hash`
+    pub async fn get_transaction_height(&self, tx_hash: &str) -> Result<u64> {
+        let tx = self
+            .get_transactions(vec![tx_hash.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .with_context(|| format!("Transaction {} not found", tx_hash))?;
+
+        if tx.in_pool {
+            anyhow::bail!("Transaction {} is still in the mempool", tx_hash);
+        }
+
+        tx.block_height
+            .with_context(|| format!("No block height for transaction {}", tx_hash))
+    }
+
+    /// Block hash and ordered tx hashes (excluding the miner tx) at `height`
+    pub async fn get_block_tx_hashes(&self, height: u64) -> Result<(B256, Vec<String>)> {
+        let block_data = self.get_block(height).await?;
+        let block_json: BlockJson = serde_json::from_str(&block_data.json)?;
+        let block_hash = parse_hex_to_b256(&block_data.block_header.hash)?;
+        Ok((block_hash, block_json.tx_hashes.unwrap_or_default()))
+    }
+
+    /// Decoded transactions of the block at `height`, miner tx first, paired
+    /// with their hashes. Each non-miner blob is checked against its hash.
+    pub async fn get_block_transactions(
+        &self,
+        height: u64,
+    ) -> Result<Vec<(String, TransactionJson)>> {
+        Ok(self.fetch_block(height).await?.transactions)
+    }
+
+    /// The block at `height` with its decoded transactions
+    pub async fn fetch_block(&self, height: u64) -> Result<FetchedBlock> {
+        let block_data = self.get_block(height).await?;
+        let block = block_data.decode()?;
+
+        let tx_hashes: Vec<String> = block.tx_hashes.iter().map(hex::encode).collect();
+        if tx_hashes.is_empty() {
+            info!("   No transactions in block {}", height);
+        } else {
+            info!(
+                "   Fetching {} transaction(s) from block...",
+                tx_hashes.len()
+            );
+        }
+        let transactions = self.get_transactions(tx_hashes).await?;
+        FetchedBlock::from_rpc(block_data.block_header, block, transactions)
+    }
+
+    /// Blocks `from..=to` with their decoded transactions, in two calls:
+    /// `get_block_headers_range` for the headers and
+    /// `get_blocks_by_height.bin` for the block and transaction blobs. Each
+    /// blob is checked against the hash the block lists for it.
+    pub async fn fetch_blocks(&self, from: u64, to: u64) -> Result<Vec<FetchedBlock>> {
+        let heights: Vec<u64> = (from..=to).collect();
+        let (headers, blobs) = futures::try_join!(
+            self.get_block_headers_range(from, to),
+            self.get_blocks_by_height_bin(&heights)
+        )?;
+
+        headers
+            .into_iter()
+            .zip(blobs)
+            .map(|(header, blobs)| {
+                let height = header.height;
+                let block = monero_tx::decode_block(&blobs.block)
+                    .with_context(|| format!("Failed to decode block {}", height))?;
+                anyhow::ensure!(
+                    blobs.txs.len() == block.tx_hashes.len(),
+                    "Block {} has {} transactions, monerod returned {}",
+                    height,
+                    block.tx_hashes.len(),
+                    blobs.txs.len()
+                );
+
+                let mut transactions = vec![(hex::encode(block.miner_tx.hash), block.miner_tx.tx)];
+                for (tx_hash, blob) in block.tx_hashes.iter().zip(&blobs.txs) {
+                    let decoded = monero_tx::decode(blob)
+                        .with_context(|| format!("Failed to decode transaction {}", tx_hash))?;
+                    if decoded.hash != *tx_hash {
+                        return Err(OracleError::Consensus(format!(
+                            "Blob hashes to {}, expected {}",
+                            decoded.hash, tx_hash
+                        ))
+                        .into());
+                    }
+                    transactions.push((hex::encode(tx_hash), decoded.tx));
+                }
+
+                Ok(FetchedBlock {
+                    header,
+                    tx_hashes: block.tx_hashes.iter().map(hex::encode).collect(),
+                    transactions,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn extract_outputs_from_block(&self, height: u64) -> Result<Vec<MoneroOutput>> {
+        let transactions = self.get_block_transactions(height).await?;
+        let all_outputs = outputs_from_transactions(&transactions)?;
+
+        info!(
+            "   Extracted {} outputs from block {}",
+            all_outputs.len(),
+            height
+        );
+        Ok(all_outputs)
+    }
+}
+
+/// A block's header and its decoded transactions
+#[derive(Debug)]
+pub struct FetchedBlock {
+    pub header: BlockHeader,
+    /// Hashes of every transaction but the miner tx, in block order
+    pub tx_hashes: Vec<String>,
+    /// Decoded transactions, miner tx first, paired with their hashes
+    pub transactions: Vec<(String, TransactionJson)>,
+}
+
+impl FetchedBlock {
+    /// The block with header `header`, decoded from its `get_block` blob
+    /// (see [`GetBlockResponse::decode`]), and the `get_transactions`
+    /// response for the hashes it lists. Each transaction blob is decoded
+    /// and checked against its hash.
+    pub(crate) fn from_rpc(
+        header: BlockHeader,
+        block: monero_tx::DecodedBlock,
+        transactions: Vec<TransactionInfo>,
+    ) -> Result<Self> {
+        let height = header.height;
+        let mut decoded = vec![(hex::encode(block.miner_tx.hash), block.miner_tx.tx)];
+
+        let tx_hashes: Vec<String> = block.tx_hashes.iter().map(hex::encode).collect();
+        anyhow::ensure!(
+            transactions.len() == tx_hashes.len(),
+            "Block {} has {} transactions, monerod returned {}",
+            height,
+            tx_hashes.len(),
+            transactions.len()
+        );
+        // A missing transaction would shift every later output in the
+        // Merkle tree, so a bad blob fails the block rather than being skipped
+        for (expected, tx) in tx_hashes.iter().zip(transactions) {
+            if tx.tx_hash != *expected {
+                return Err(OracleError::Consensus(format!(
+                    "Asked for transaction {}, monerod returned {}",
+                    expected, tx.tx_hash
+                ))
+                .into());
+            }
+            let tx_json = decode_transaction(&tx.tx_hash, &tx.as_hex)
+                .with_context(|| format!("Failed to decode transaction {}", tx.tx_hash))?;
+            decoded.push((tx.tx_hash, tx_json));
+        }
+
+        Ok(Self {
+            header,
+            tx_hashes,
+            transactions: decoded,
+        })
+    }
+
+    pub fn miner_tx_hash(&self) -> &str {
+        &self.transactions[0].0
+    }
+}
+
+/// A block and its transactions as serialized on chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockBlobs {
+    pub block: Vec<u8>,
+    /// Excludes the miner transaction, which is part of the block blob
+    pub txs: Vec<Vec<u8>>,
+}
+
+impl BlockBlobs {
+    fn from_epee(value: &Value) -> Result<Self> {
+        let entry = value.as_object().context("Malformed block entry")?;
+        let txs = entry
+            .array("txs")?
+            .iter()
+            .map(|tx| match tx {
+                Value::String(blob) => Ok(blob.clone()),
+                // Pruned responses wrap each blob in an object
+                Value::Object(tx) => Ok(tx.bytes("blob")?.to_vec()),
+                _ => anyhow::bail!("Malformed transaction entry"),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            block: entry.bytes("block")?.to_vec(),
+            txs,
+        })
+    }
+}
+
+/// `/get_blocks.bin` response
+#[derive(Debug, Clone)]
+pub struct BinaryBlocks {
+    /// Height of the first block in `blocks`
+    pub start_height: u64,
+    /// Daemon's chain height
+    pub current_height: u64,
+    pub blocks: Vec<BlockBlobs>,
+    /// Per block, per transaction (miner transaction first), the global
+    /// index of each output
+    pub output_indices: Vec<Vec<Vec<u64>>>,
+}
+
+/// `/get_outs.bin` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputKey {
+    pub key: B256,
+    /// Pedersen commitment (`outPk`)
+    pub mask: B256,
+    pub unlocked: bool,
+    pub height: u64,
+    pub txid: B256,
+}
+
+/// `get_output_distribution` result for one amount
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OutputDistribution {
+    pub amount: u64,
+    /// Height of the first block counted
+    pub start_height: u64,
+    /// Outputs created before `start_height`
+    pub base: u64,
+    /// Outputs created in each block from `start_height` on
+    pub distribution: Vec<u64>,
+}
+
+/// `get_fee_estimate` result, in piconero per byte of weight
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FeeEstimate {
+    /// Base fee
+    pub fee: u64,
+    /// Fee at each priority from unimportant to priority; empty before the
+    /// 2021 fee scaling
+    #[serde(default)]
+    pub fees: Vec<u64>,
+    /// Fees are rounded up to a multiple of this; absent on old daemons
+    #[serde(default)]
+    pub quantization_mask: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputDistributionResponse {
+    distributions: Vec<OutputDistribution>,
+}
+
+fn u64_array(values: &[Value]) -> Result<Vec<u64>> {
+    values
+        .iter()
+        .map(|value| value.as_u64().context("Expected an integer"))
+        .collect()
+}
+
+/// Hashes of `transactions` (miner tx first) in block order, as
+/// [`MerkleHasher::output_order`](crate::merkle::MerkleHasher::output_order)
+/// takes them
+pub fn transaction_order(transactions: &[(String, TransactionJson)]) -> Result<Vec<B256>> {
+    transactions
+        .iter()
+        .map(|(tx_hash, _)| parse_hex_to_b256(tx_hash))
+        .collect()
+}
+
+/// Outputs committed to by the output Merkle tree, in block order.
+///
+/// Miner transaction outputs have no `ecdhInfo` or `outPk`: their leaf takes
+/// the cleartext amount (8 bytes little-endian, padded like a compact ECDH
+/// amount) and the commitment `G + amount·H` RingCT gives such amounts.
+pub fn outputs_from_transactions(
+    transactions: &[(String, TransactionJson)],
+) -> Result<Vec<MoneroOutput>> {
+    let mut all_outputs = Vec::new();
+
+    for (tx_hash, tx_json) in transactions {
+        let vout = match &tx_json.vout {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let coinbase = tx_json.is_coinbase();
+        let rct_sigs = match &tx_json.rct_signatures {
+            Some(r) => Some(r),
+            None if coinbase => None,
+            None => continue,
+        };
+
+        let ecdh_info = rct_sigs
+            .and_then(|r| r.ecdh_info.as_deref())
+            .unwrap_or_default();
+        let out_pk = rct_sigs
+            .and_then(|r| r.out_pk.as_deref())
+            .unwrap_or_default();
+
+        for (i, output) in vout.iter().enumerate() {
+            let output_pub_key = match output.target.as_ref().and_then(OutputTarget::public_key) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let (ecdh_amount, commitment_bytes) = if coinbase {
+                let mut amount = [0u8; 32];
+                amount[24..].copy_from_slice(&output.amount.to_le_bytes());
+                (B256::from(amount), B256::from(zero_commit(output.amount)))
+            } else {
+                let ecdh = match ecdh_info.get(i) {
+                    Some(e) => &e.amount,
+                    None => continue,
+                };
+
+                let commitment = match out_pk.get(i) {
+                    Some(c) => c,
+                    None => continue,
+                };
+
+                (
+                    parse_hex_to_b256_padded(ecdh)?,
+                    parse_hex_to_b256(commitment)?,
+                )
+            };
+
+            // Parse hex strings to B256
+            let tx_hash = parse_hex_to_b256(tx_hash)?;
+            let output_pub_key_bytes = parse_hex_to_b256(output_pub_key)?;
+
+            all_outputs.push(MoneroOutput {
+                tx_hash,
+                output_index: i as u64,
+                ecdh_amount,
+                output_pub_key: output_pub_key_bytes,
+                commitment: commitment_bytes,
+            });
+        }
+    }
+
+    Ok(all_outputs)
+}
+
+/// Decode a hex transaction blob, checking it hashes to `tx_hash`
+pub fn decode_transaction(tx_hash: &str, blob_hex: &str) -> Result<TransactionJson> {
+    let blob = hex::decode(blob_hex).context("Invalid transaction hex")?;
+    let decoded = monero_tx::decode(&blob)?;
+    let expected = parse_hex_to_b256(tx_hash)?;
+    if decoded.hash != expected {
+        return Err(OracleError::Consensus(format!(
+            "Blob hashes to {}, expected {}",
+            decoded.hash, expected
+        ))
+        .into());
+    }
+    Ok(decoded.tx)
+}
+
+fn decode_pool_transactions(transactions: Vec<PoolTransaction>) -> Vec<(String, TransactionJson)> {
+    let mut decoded = Vec::with_capacity(transactions.len());
+    for tx in transactions {
+        match decode_transaction(&tx.id_hash, &tx.tx_blob) {
+            Ok(tx_json) => decoded.push((tx.id_hash, tx_json)),
+            Err(e) => warn!(
+                "   Failed to decode txpool transaction {}: {}",
+                tx.id_hash, e
+            ),
+        }
+    }
+    decoded
+}
+
+/// Parse a (optionally 0x-prefixed) 32-byte hex string
+pub fn parse_hex_to_b256(hex_str: &str) -> Result<B256> {
+    let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+    let bytes = hex::decode(hex_str)?;
+
+    if bytes.len() != 32 {
+        return Err(OracleError::Parse(format!("Expected 32 bytes, got {}", bytes.len())).into());
+    }
+
+    Ok(B256::from_slice(&bytes))
+}
+
+/// Parse up to 32 bytes of hex, left-padded with zeros
+pub fn parse_hex_to_b256_padded(hex_str: &str) -> Result<B256> {
+    let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+    let bytes = hex::decode(hex_str)?;
+
+    let mut padded = [0u8; 32];
+    let start = 32 - bytes.len().min(32);
+    padded[start..].copy_from_slice(&bytes[..bytes.len().min(32)]);
+
+    Ok(B256::from_slice(&padded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_blobs_from_epee() {
+        let full = Value::Object(Section::from([
+            ("block".to_string(), Value::String(vec![1, 2, 3])),
+            (
+                "txs".to_string(),
+                Value::Array(vec![Value::String(vec![4]), Value::String(vec![5, 6])]),
+            ),
+        ]));
+        let blobs = BlockBlobs::from_epee(&full).unwrap();
+        assert_eq!(blobs.block, vec![1, 2, 3]);
+        assert_eq!(blobs.txs, vec![vec![4], vec![5, 6]]);
+
+        // Pruned entries, and blocks without transactions
+        let pruned = Value::Object(Section::from([
+            ("block".to_string(), Value::String(vec![1])),
+            (
+                "txs".to_string(),
+                Value::Array(vec![Value::Object(Section::from([
+                    ("blob".to_string(), Value::String(vec![7])),
+                    ("prunable_hash".to_string(), Value::String(vec![0; 32])),
+                ]))]),
+            ),
+        ]));
+        assert_eq!(BlockBlobs::from_epee(&pruned).unwrap().txs, vec![vec![7]]);
+
+        let empty = Value::Object(Section::from([(
+            "block".to_string(),
+            Value::String(vec![1]),
+        )]));
+        assert!(BlockBlobs::from_epee(&empty).unwrap().txs.is_empty());
+    }
+
+    #[test]
+    fn test_connection_client() {
+        assert!(Connection::default().client().is_ok());
+
+        let proxied = Connection {
+            proxy: Some("http://127.0.0.1:8118".to_string()),
+            ca_cert: None,
+            http: HttpSettings::MONERO,
+        };
+        assert!(proxied.client().is_ok());
+
+        let missing_cert = Connection {
+            proxy: None,
+            ca_cert: Some(PathBuf::from("/nonexistent/monerod.pem")),
+            http: HttpSettings::default(),
+        };
+        assert!(missing_cert.client().is_err());
+    }
+
+    #[test]
+    fn test_parse_hex_to_b256() {
+        let hex = "a".repeat(64);
+        let result = parse_hex_to_b256(&hex).unwrap();
+        assert_eq!(result.as_slice(), &[0xaa; 32]);
+    }
+
+    #[test]
+    fn test_parse_hex_to_b256_with_prefix() {
+        let hex = format!("0x{}", "b".repeat(64));
+        let result = parse_hex_to_b256(&hex).unwrap();
+        assert_eq!(result.as_slice(), &[0xbb; 32]);
+    }
+
+    #[test]
+    fn test_header_difficulty() {
+        let header: BlockHeader = serde_json::from_value(serde_json::json!({
+            "height": 3_100_000,
+            "hash": "aa".repeat(32),
+            "major_version": 16,
+            "minor_version": 16,
+            "timestamp": 1_710_000_000u64,
+            "prev_hash": "bb".repeat(32),
+            "nonce": 7,
+            "difficulty": 300_000_000_000u64,
+            "wide_difficulty": "0x45d964b800",
+            "cumulative_difficulty": 1u64,
+            "wide_cumulative_difficulty": "0x10000000000000001"
+        }))
+        .unwrap();
+        assert_eq!(header.difficulty().unwrap(), 300_000_000_000);
+        assert_eq!(header.cumulative_difficulty().unwrap(), (1u128 << 64) + 1);
+
+        let old = BlockHeader {
+            wide_cumulative_difficulty: None,
+            cumulative_difficulty: 42,
+            ..header
+        };
+        assert_eq!(old.cumulative_difficulty().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_daemon_network() {
+        let info: DaemonInfo = serde_json::from_value(serde_json::json!({
+            "height": 1_500_000,
+            "mainnet": false,
+            "nettype": "stagenet",
+            "stagenet": true,
+            "testnet": false
+        }))
+        .unwrap();
+        assert_eq!(info.network(), Network::Stagenet);
+
+        let info: DaemonInfo = serde_json::from_value(serde_json::json!({
+            "height": 3_100_000,
+            "mainnet": true
+        }))
+        .unwrap();
+        assert_eq!(info.network(), Network::Mainnet);
+    }
+
+    #[test]
+    fn test_get_block_decodes_the_blob() {
+        let blob = monero_tx::tests::block_blob(&[B256::repeat_byte(0x66)]);
+        let miner_tx_hash = monero_tx::decode_block(&blob).unwrap().miner_tx.hash;
+        let response = |miner_tx_hash: B256| -> GetBlockResponse {
+            serde_json::from_value(serde_json::json!({
+                "block_header": {
+                    "height": 7,
+                    "hash": "11".repeat(32),
+                    "major_version": 16,
+                    "minor_version": 16,
+                    "timestamp": 1_700_000_000,
+                    "prev_hash": "22".repeat(32),
+                    "nonce": 0,
+                },
+                "blob": hex::encode(&blob),
+                // The JSON rendering is ignored, whatever it claims
+                "json": r#"{"miner_tx":{"vout":[]},"tx_hashes":[]}"#,
+                "miner_tx_hash": hex::encode(miner_tx_hash),
+            }))
+            .unwrap()
+        };
+
+        let block = response(miner_tx_hash).decode().unwrap();
+        assert_eq!(block.miner_tx.tx.vout.as_ref().map(Vec::len), Some(1));
+        assert_eq!(block.tx_hashes, vec![B256::repeat_byte(0x66)]);
+
+        let error = response(B256::repeat_byte(0x33)).decode().unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<OracleError>(),
+            Some(OracleError::Consensus(_))
+        ));
+    }
+
+    #[test]
+    fn test_miner_tx_outputs() {
+        let block: BlockJson = serde_json::from_value(serde_json::json!({
+            "major_version": 16,
+            "miner_tx": {
+                "version": 2,
+                "unlock_time": 3_100_060,
+                "vin": [{ "gen": { "height": 3_100_000 } }],
+                "vout": [{
+                    "amount": 600_000_000_000u64,
+                    "target": { "tagged_key": { "key": "aa".repeat(32), "view_tag": "8a" } }
+                }],
+                "extra": [1],
+                "rct_signatures": { "type": 0 }
+            },
+            "tx_hashes": ["cc".repeat(32)]
+        }))
+        .unwrap();
+        let miner_tx = block.miner_tx.unwrap();
+        assert!(miner_tx.is_coinbase());
+
+        let tx: TransactionJson = serde_json::from_value(serde_json::json!({
+            "vin": [{ "key": { "amount": 0, "key_offsets": [1], "k_image": "dd".repeat(32) } }],
+            "vout": [{ "amount": 0, "target": { "key": "ee".repeat(32) } }],
+            "rct_signatures": {
+                "type": 6,
+                "ecdhInfo": [{ "amount": "0102030405060708" }],
+                "outPk": ["ff".repeat(32)]
+            }
+        }))
+        .unwrap();
+        assert!(!tx.is_coinbase());
+
+        let outputs =
+            outputs_from_transactions(&[("bb".repeat(32), miner_tx), ("cc".repeat(32), tx)])
+                .unwrap();
+        assert_eq!(outputs.len(), 2);
+
+        let coinbase = &outputs[0];
+        assert_eq!(coinbase.tx_hash, B256::repeat_byte(0xbb));
+        assert_eq!(coinbase.output_index, 0);
+        assert_eq!(coinbase.output_pub_key, B256::repeat_byte(0xaa));
+        assert_eq!(
+            &coinbase.ecdh_amount[24..],
+            &600_000_000_000u64.to_le_bytes()
+        );
+        assert_eq!(
+            coinbase.commitment,
+            B256::from(zero_commit(600_000_000_000))
+        );
+
+        assert_eq!(outputs[1].tx_hash, B256::repeat_byte(0xcc));
+        assert_eq!(outputs[1].commitment, B256::repeat_byte(0xff));
+    }
+
+    #[test]
+    fn test_decode_pool_transactions() {
+        let empty: GetTransactionPoolResponse =
+            serde_json::from_value(serde_json::json!({ "status": "OK", "untrusted": false }))
+                .unwrap();
+        assert!(empty.transactions.is_empty());
+
+        // v2, no inputs or outputs, extra [1, 2], RCTTypeNull
+        let blob = [2, 0, 0, 0, 2, 1, 2, 0];
+        let tx_hash = hex::encode(monero_tx::decode(&blob).unwrap().hash);
+        let response: GetTransactionPoolResponse = serde_json::from_value(serde_json::json!({
+            "status": "OK",
+            "transactions": [
+                {
+                    "id_hash": tx_hash,
+                    "tx_blob": hex::encode(blob),
+                    "receive_time": 1_700_000_000u64,
+                    "relayed": true
+                },
+                { "id_hash": "d".repeat(64), "tx_blob": hex::encode(blob) },
+                { "id_hash": "e".repeat(64), "tx_blob": "not hex" }
+            ]
+        }))
+        .unwrap();
+
+        let decoded = decode_pool_transactions(response.transactions);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].0, tx_hash);
+        assert_eq!(decoded[0].1.extra, vec![1, 2]);
+    }
+}
@@ -0,0 +1,513 @@
+//! Monero transaction deserialization from raw blobs
+//!
+//! monerod's `as_json` rendering changes shape across daemon versions (view
+//! tags, compact ecdhInfo) and a field it doesn't know how to print is simply
+//! missing. Decoding the consensus serialization instead reads every field
+//! the oracle needs, and recomputing the transaction hash from the blob proves
+//! the daemon handed over the transaction it named.
+//!
+//! Only the prefix and the RingCT base are decoded; the prunable part (range
+//! proofs, ring signatures) is hashed but not parsed. The result is the same
+//! [`TransactionJson`] the JSON path produces, so scanning and Merkle code
+//! don't care where a transaction came from.
+//!
+//! Block blobs, fetched in bulk during catch-up, decode to their miner
+//! transaction and the hashes of the others; the header fields are left to
+//! the block id check, which fails if they don't match the header.
+
+use crate::{
+    error::OracleError,
+    monero_rpc::{
+        EcdhInfo, GenInput, InputKey, OutputTarget, RctSignatures, TaggedKey, TransactionJson,
+        TxInput, TxOutput,
+    },
+};
+use alloy::primitives::{keccak256, B256};
+use anyhow::{Context, Result};
+
+pub(crate) const TXIN_GEN: u8 = 0xff;
+pub(crate) const TXIN_TO_KEY: u8 = 0x02;
+const TXOUT_TO_KEY: u8 = 0x02;
+pub(crate) const TXOUT_TO_TAGGED_KEY: u8 = 0x03;
+
+/// RingCT types whose base carries pseudo outputs
+pub(crate) const RCT_TYPE_NULL: u8 = 0;
+const RCT_TYPE_SIMPLE: u8 = 2;
+/// From Bulletproof2 on, ecdhInfo is just an 8-byte amount
+const RCT_TYPE_BULLETPROOF2: u8 = 4;
+pub(crate) const RCT_TYPE_BULLETPROOF_PLUS: u8 = 6;
+
+/// A transaction decoded from its blob
+#[derive(Debug)]
+pub struct DecodedTransaction {
+    pub hash: B256,
+    pub version: u64,
+    pub unlock_time: u64,
+    pub tx: TransactionJson,
+}
+
+/// A block decoded from its blob
+#[derive(Debug)]
+pub struct DecodedBlock {
+    pub miner_tx: DecodedTransaction,
+    /// Every other transaction, in block order
+    pub tx_hashes: Vec<B256>,
+}
+
+/// Decode a full (unpruned) transaction blob
+pub fn decode(blob: &[u8]) -> Result<DecodedTransaction> {
+    read_transaction(blob, false)
+        .map(|(decoded, _)| decoded)
+        .map_err(|e| OracleError::parse(e).into())
+}
+
+/// Decode a block blob: header, miner transaction, then the hashes of the
+/// block's other transactions
+pub fn decode_block(blob: &[u8]) -> Result<DecodedBlock> {
+    read_block(blob).map_err(|e| OracleError::parse(e).into())
+}
+
+fn read_block(blob: &[u8]) -> Result<DecodedBlock> {
+    let mut reader = Reader {
+        bytes: blob,
+        pos: 0,
+    };
+    reader.varint()?; // major_version
+    reader.varint()?; // minor_version
+    reader.varint()?; // timestamp
+    reader.take(32)?; // prev_id
+    reader.take(4)?; // nonce
+
+    let (miner_tx, miner_tx_len) = read_transaction(&blob[reader.pos..], true)?;
+    reader.take(miner_tx_len)?;
+    anyhow::ensure!(
+        miner_tx.tx.is_coinbase(),
+        "Block starts with a non-miner transaction"
+    );
+
+    let tx_count = reader.len()?;
+    let tx_hashes = (0..tx_count)
+        .map(|_| Ok(B256::from_slice(reader.take(32)?)))
+        .collect::<Result<_>>()?;
+    anyhow::ensure!(reader.pos == blob.len(), "Trailing bytes after block");
+    Ok(DecodedBlock {
+        miner_tx,
+        tx_hashes,
+    })
+}
+
+/// Decode the transaction at the start of `blob`, returning its length. A
+/// miner transaction `embedded` in a block blob carries no signatures, so it
+/// ends with its prefix (v1) or its null RingCT base (v2).
+fn read_transaction(blob: &[u8], embedded: bool) -> Result<(DecodedTransaction, usize)> {
+    let mut reader = Reader {
+        bytes: blob,
+        pos: 0,
+    };
+
+    let version = reader.varint()?;
+    anyhow::ensure!(
+        version == 1 || version == 2,
+        "Unsupported transaction version {}",
+        version
+    );
+    let unlock_time = reader.varint()?;
+
+    let input_count = reader.len()?;
+    let mut vin = Vec::with_capacity(input_count);
+    for _ in 0..input_count {
+        vin.push(match reader.byte()? {
+            TXIN_GEN => TxInput {
+                key: None,
+                gen: Some(GenInput {
+                    height: reader.varint()?,
+                }),
+            },
+            TXIN_TO_KEY => {
+                reader.varint()?; // amount
+                for _ in 0..reader.len()? {
+                    reader.varint()?; // key offset
+                }
+                TxInput {
+                    key: Some(InputKey {
+                        k_image: hex::encode(reader.take(32)?),
+                    }),
+                    gen: None,
+                }
+            }
+            tag => anyhow::bail!("Unsupported input type {:#04x}", tag),
+        });
+    }
+
+    let output_count = reader.len()?;
+    let mut vout = Vec::with_capacity(output_count);
+    for _ in 0..output_count {
+        let amount = reader.varint()?;
+        let target = match reader.byte()? {
+            TXOUT_TO_KEY => OutputTarget {
+                key: Some(hex::encode(reader.take(32)?)),
+                tagged_key: None,
+            },
+            TXOUT_TO_TAGGED_KEY => OutputTarget {
+                key: None,
+                tagged_key: Some(TaggedKey {
+                    key: hex::encode(reader.take(32)?),
+                    view_tag: Some(hex::encode(reader.take(1)?)),
+                }),
+            },
+            tag => anyhow::bail!("Unsupported output type {:#04x}", tag),
+        };
+        vout.push(TxOutput {
+            amount,
+            target: Some(target),
+        });
+    }
+
+    let extra_len = reader.len()?;
+    let extra = reader.take(extra_len)?.to_vec();
+    let prefix_end = reader.pos;
+
+    let (hash, rct_signatures, end) = if version == 1 {
+        // Ring signatures follow; a v1 hash covers the whole blob
+        let end = if embedded { prefix_end } else { blob.len() };
+        (keccak256(&blob[..end]), None, end)
+    } else {
+        let rct = rct_base(&mut reader, vin.len(), vout.len())?;
+        let base_end = reader.pos;
+        let prunable_hash = if rct.0 == RCT_TYPE_NULL {
+            anyhow::ensure!(
+                embedded || base_end == blob.len(),
+                "Trailing bytes after transaction"
+            );
+            B256::ZERO
+        } else {
+            anyhow::ensure!(!embedded, "Miner transaction with RingCT signatures");
+            keccak256(&blob[base_end..])
+        };
+        let end = if embedded { base_end } else { blob.len() };
+
+        let mut hashes = Vec::with_capacity(96);
+        hashes.extend_from_slice(keccak256(&blob[..prefix_end]).as_slice());
+        hashes.extend_from_slice(keccak256(&blob[prefix_end..base_end]).as_slice());
+        hashes.extend_from_slice(prunable_hash.as_slice());
+        (keccak256(&hashes), Some(rct.1), end)
+    };
+
+    let decoded = DecodedTransaction {
+        hash,
+        version,
+        unlock_time,
+        tx: TransactionJson {
+            vin,
+            vout: Some(vout),
+            extra,
+            rct_signatures,
+        },
+    };
+    Ok((decoded, end))
+}
+
+/// RingCT type and the base fields (`ecdhInfo`, `outPk`) for `inputs` and
+/// `outputs`
+fn rct_base(reader: &mut Reader, inputs: usize, outputs: usize) -> Result<(u8, RctSignatures)> {
+    let rct_type = reader.byte()?;
+    if rct_type == RCT_TYPE_NULL {
+        return Ok((
+            rct_type,
+            RctSignatures {
+                ecdh_info: None,
+                out_pk: None,
+            },
+        ));
+    }
+    anyhow::ensure!(
+        rct_type <= RCT_TYPE_BULLETPROOF_PLUS,
+        "Unsupported RingCT type {}",
+        rct_type
+    );
+
+    reader.varint()?; // fee
+    if rct_type == RCT_TYPE_SIMPLE {
+        reader.take(32 * inputs)?; // pseudoOuts
+    }
+
+    let ecdh_info = (0..outputs)
+        .map(|_| {
+            if rct_type >= RCT_TYPE_BULLETPROOF2 {
+                Ok(EcdhInfo {
+                    amount: hex::encode(reader.take(8)?),
+                    mask: None,
+                })
+            } else {
+                let mask = hex::encode(reader.take(32)?);
+                Ok(EcdhInfo {
+                    amount: hex::encode(reader.take(32)?),
+                    mask: Some(mask),
+                })
+            }
+        })
+        .collect::<Result<_>>()?;
+    let out_pk = (0..outputs)
+        .map(|_| Ok(hex::encode(reader.take(32)?)))
+        .collect::<Result<_>>()?;
+
+    Ok((
+        rct_type,
+        RctSignatures {
+            ecdh_info: Some(ecdh_info),
+            out_pk: Some(out_pk),
+        },
+    ))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .context("Truncated transaction")?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Monero varint: 7 bits per byte, least significant first
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7f);
+            anyhow::ensure!(shift < 63 || bits <= 1, "Varint overflows u64");
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                anyhow::ensure!(byte != 0 || shift == 0, "Non-canonical varint");
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("Varint overflows u64")
+    }
+
+    /// An element count, bounded by the bytes left so a corrupt blob can't
+    /// make us allocate gigabytes
+    fn len(&mut self) -> Result<usize> {
+        let len = self.varint()?;
+        anyhow::ensure!(
+            len <= (self.bytes.len() - self.pos) as u64,
+            "Count {} exceeds transaction size",
+            len
+        );
+        Ok(len as usize)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::merkle::write_varint;
+
+    /// A two-output CLSAG-style transaction: prefix, RingCT base, then
+    /// `prunable` standing in for the proofs
+    fn clsag_blob(prunable: &[u8]) -> (Vec<u8>, usize, usize) {
+        let mut blob = Vec::new();
+        write_varint(&mut blob, 2); // version
+        write_varint(&mut blob, 0); // unlock_time
+
+        write_varint(&mut blob, 1); // inputs
+        blob.push(TXIN_TO_KEY);
+        write_varint(&mut blob, 0);
+        write_varint(&mut blob, 2);
+        write_varint(&mut blob, 1_000_000);
+        write_varint(&mut blob, 300);
+        blob.extend_from_slice(&[0x11; 32]); // key image
+
+        write_varint(&mut blob, 2); // outputs
+        write_varint(&mut blob, 0);
+        blob.push(TXOUT_TO_TAGGED_KEY);
+        blob.extend_from_slice(&[0x22; 32]);
+        blob.push(0x4f);
+        write_varint(&mut blob, 0);
+        blob.push(TXOUT_TO_KEY);
+        blob.extend_from_slice(&[0x33; 32]);
+
+        write_varint(&mut blob, 3); // extra
+        blob.extend_from_slice(&[1, 2, 3]);
+        let prefix_end = blob.len();
+
+        blob.push(5); // RCTTypeCLSAG
+        write_varint(&mut blob, 30_000_000); // fee
+        blob.extend_from_slice(&[0xaa; 8]);
+        blob.extend_from_slice(&[0xbb; 8]);
+        blob.extend_from_slice(&[0xcc; 32]);
+        blob.extend_from_slice(&[0xdd; 32]);
+        let base_end = blob.len();
+
+        blob.extend_from_slice(prunable);
+        (blob, prefix_end, base_end)
+    }
+
+    #[test]
+    fn test_decode_rct_transaction() {
+        let (blob, prefix_end, base_end) = clsag_blob(&[0x99; 100]);
+        let decoded = decode(&blob).unwrap();
+        assert_eq!(decoded.version, 2);
+
+        let tx = &decoded.tx;
+        assert_eq!(tx.key_images().collect::<Vec<_>>(), vec!["11".repeat(32)]);
+        let vout = tx.vout.as_ref().unwrap();
+        let tagged = vout[0]
+            .target
+            .as_ref()
+            .unwrap()
+            .tagged_key
+            .as_ref()
+            .unwrap();
+        assert_eq!(tagged.key, "22".repeat(32));
+        assert_eq!(tagged.view_tag.as_deref(), Some("4f"));
+        assert_eq!(
+            vout[1].target.as_ref().unwrap().public_key(),
+            Some("33".repeat(32).as_str())
+        );
+        assert_eq!(tx.extra, vec![1, 2, 3]);
+
+        let rct = tx.rct_signatures.as_ref().unwrap();
+        let ecdh = rct.ecdh_info.as_ref().unwrap();
+        assert_eq!(ecdh[0].amount, "aa".repeat(8));
+        assert_eq!(ecdh[1].amount, "bb".repeat(8));
+        assert_eq!(
+            rct.out_pk.as_ref().unwrap(),
+            &vec!["cc".repeat(32), "dd".repeat(32)]
+        );
+
+        let mut hashes = Vec::new();
+        hashes.extend_from_slice(keccak256(&blob[..prefix_end]).as_slice());
+        hashes.extend_from_slice(keccak256(&blob[prefix_end..base_end]).as_slice());
+        hashes.extend_from_slice(keccak256(&blob[base_end..]).as_slice());
+        assert_eq!(decoded.hash, keccak256(&hashes));
+    }
+
+    #[test]
+    fn test_decode_miner_transaction() {
+        let mut blob = Vec::new();
+        write_varint(&mut blob, 2);
+        write_varint(&mut blob, 3_100_060); // unlock_time
+        write_varint(&mut blob, 1);
+        blob.push(TXIN_GEN);
+        write_varint(&mut blob, 3_100_000);
+        write_varint(&mut blob, 1);
+        write_varint(&mut blob, 600_000_000_000);
+        blob.push(TXOUT_TO_TAGGED_KEY);
+        blob.extend_from_slice(&[0x44; 32]);
+        blob.push(0x01);
+        write_varint(&mut blob, 0);
+        let prefix_end = blob.len();
+        blob.push(RCT_TYPE_NULL);
+
+        let decoded = decode(&blob).unwrap();
+        assert_eq!(decoded.tx.vout.as_ref().unwrap()[0].amount, 600_000_000_000);
+        assert_eq!(decoded.unlock_time, 3_100_060);
+        assert!(decoded.tx.is_coinbase());
+        assert_eq!(decoded.tx.vin[0].gen.as_ref().unwrap().height, 3_100_000);
+        assert!(decoded
+            .tx
+            .rct_signatures
+            .as_ref()
+            .unwrap()
+            .ecdh_info
+            .is_none());
+
+        let mut hashes = Vec::new();
+        hashes.extend_from_slice(keccak256(&blob[..prefix_end]).as_slice());
+        hashes.extend_from_slice(keccak256([RCT_TYPE_NULL]).as_slice());
+        hashes.extend_from_slice(B256::ZERO.as_slice());
+        assert_eq!(decoded.hash, keccak256(&hashes));
+    }
+
+    #[test]
+    fn test_decode_block() {
+        let blob = block_blob(&[B256::repeat_byte(0x66), B256::repeat_byte(0x77)]);
+        let block = decode_block(&blob).unwrap();
+        assert_eq!(block.miner_tx.hash, decode(&miner_tx_blob()).unwrap().hash);
+        assert_eq!(
+            block.tx_hashes,
+            vec![B256::repeat_byte(0x66), B256::repeat_byte(0x77)]
+        );
+
+        assert!(decode_block(&blob[..blob.len() - 1]).is_err());
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert!(decode_block(&trailing).is_err());
+    }
+
+    fn miner_tx_blob() -> Vec<u8> {
+        let mut miner_tx = Vec::new();
+        write_varint(&mut miner_tx, 2);
+        write_varint(&mut miner_tx, 60);
+        write_varint(&mut miner_tx, 1);
+        miner_tx.push(TXIN_GEN);
+        write_varint(&mut miner_tx, 3_100_000);
+        write_varint(&mut miner_tx, 1);
+        write_varint(&mut miner_tx, 600_000_000_000);
+        miner_tx.push(TXOUT_TO_KEY);
+        miner_tx.extend_from_slice(&[0x44; 32]);
+        write_varint(&mut miner_tx, 0);
+        miner_tx.push(RCT_TYPE_NULL);
+        miner_tx
+    }
+
+    /// A block blob paying one coinbase output and listing `tx_hashes`
+    pub(crate) fn block_blob(tx_hashes: &[B256]) -> Vec<u8> {
+        let mut blob = Vec::new();
+        write_varint(&mut blob, 16); // major_version
+        write_varint(&mut blob, 16); // minor_version
+        write_varint(&mut blob, 1_700_000_000);
+        blob.extend_from_slice(&[0x55; 32]); // prev_id
+        blob.extend_from_slice(&7u32.to_le_bytes());
+        blob.extend_from_slice(&miner_tx_blob());
+        write_varint(&mut blob, tx_hashes.len() as u64);
+        for hash in tx_hashes {
+            blob.extend_from_slice(hash.as_slice());
+        }
+        blob
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        let (blob, _, base_end) = clsag_blob(&[]);
+        assert!(decode(&blob[..base_end - 1]).is_err());
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[3, 0]).is_err());
+
+        // Overlong and non-canonical varints
+        assert!(Reader {
+            bytes: &[0xff; 11],
+            pos: 0
+        }
+        .varint()
+        .is_err());
+        assert!(Reader {
+            bytes: &[0x80, 0x00],
+            pos: 0
+        }
+        .varint()
+        .is_err());
+        assert_eq!(
+            Reader {
+                bytes: &[0xac, 0x02],
+                pos: 0
+            }
+            .varint()
+            .unwrap(),
+            300
+        );
+    }
+}
@@ -0,0 +1,626 @@
+//! monero-wallet-rpc client for paying out withdrawals
+//!
+//! Transfers are created with `do_not_relay` so the tx hash and tx key can be
+//! stored before anything reaches the network; [`MoneroWalletClient::relay_tx`]
+//! then broadcasts the signed transaction. A crash between the two steps
+//! leaves a recorded transaction that can be relayed again, never a second
+//! payment.
+//!
+//! A multisig bridge wallet signs through the `*_multisig` methods instead:
+//! the `transfer` returns a txset that co-signers add their signatures to
+//! (see [`crate::multisig`]), and the complete set is broadcast with
+//! `submit_multisig`.
+//!
+//! wallet-rpc must run with `--disable-rpc-login` (or behind a proxy that adds
+//! auth); digest authentication is not supported.
+
+use crate::{
+    error::OracleError,
+    fees::FeePriority,
+    metrics,
+    monero_rpc::{parse_hex_to_b256, JsonRpcRequest, JsonRpcResponse},
+    retry::RetryPolicy,
+};
+use alloy::primitives::B256;
+use anyhow::Result;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Balance of one wallet account, in piconero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Balance {
+    pub balance: u64,
+    pub unlocked_balance: u64,
+}
+
+/// A signed but not yet relayed transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransfer {
+    pub tx_hash: B256,
+    /// Secret tx key; with the destination address it proves the payment
+    pub tx_key: String,
+    /// Total sent to the destinations
+    pub amount: u64,
+    pub fee: u64,
+    /// Opaque blob accepted by `relay_tx`
+    pub tx_metadata: String,
+}
+
+/// An output received by the wallet, from `incoming_transfers`
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncomingTransfer {
+    pub amount: u64,
+    /// Empty for view-only wallets, which can't compute key images
+    #[serde(default)]
+    pub key_image: String,
+    pub tx_hash: String,
+    pub spent: bool,
+}
+
+/// Key image of a wallet output, with the spend key's signature over it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedKeyImage {
+    pub key_image: String,
+    pub signature: String,
+}
+
+/// Key images as `export_key_images` returns them and `import_key_images`
+/// takes them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyImages {
+    /// Index of the wallet output the first key image belongs to
+    #[serde(default)]
+    pub offset: u32,
+    #[serde(default)]
+    pub signed_key_images: Vec<SignedKeyImage>,
+}
+
+impl KeyImages {
+    /// Reject key images or signatures that aren't 32 / 64 bytes of hex
+    pub fn validate(&self) -> Result<()> {
+        for (i, signed) in self.signed_key_images.iter().enumerate() {
+            let is_hex =
+                |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+            anyhow::ensure!(
+                is_hex(&signed.key_image, 64) && is_hex(&signed.signature, 128),
+                "Key image {} is malformed",
+                self.offset as usize + i
+            );
+        }
+        Ok(())
+    }
+}
+
+/// What a wallet made of imported key images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyImageImport {
+    /// Height of the newest spend found
+    pub height: u64,
+    /// Piconero found spent
+    pub spent: u64,
+    pub unspent: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetAddressResponse {
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IncomingTransfersResponse {
+    #[serde(default)]
+    transfers: Vec<IncomingTransfer>,
+}
+
+/// One recipient of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Destination<'a> {
+    pub amount: u64,
+    pub address: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct TransferRequest<'a> {
+    destinations: &'a [Destination<'a>],
+    account_index: u32,
+    priority: u32,
+    get_tx_key: bool,
+    get_tx_metadata: bool,
+    do_not_relay: bool,
+}
+
+/// A transfer from a multisig wallet, signed only by this wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigTransfer {
+    /// Passed to the co-signers' `sign_multisig`
+    pub txset: String,
+    /// Secret tx key, which only the wallet creating the transfer knows
+    pub tx_key: String,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+/// `is_multisig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigStatus {
+    pub multisig: bool,
+    /// Key exchange is complete and the wallet can sign
+    pub ready: bool,
+    pub threshold: u32,
+    pub total: u32,
+}
+
+/// Outcome of one step of multisig key exchange
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigKeys {
+    /// Wallet address, final once the exchange is done
+    pub address: String,
+    /// Passed to the other participants' next step; empty once done
+    #[serde(default)]
+    pub multisig_info: String,
+}
+
+/// Where a transaction pays, from `describe_transfer`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TransferDescription {
+    #[serde(default)]
+    pub recipients: Vec<Recipient>,
+    #[serde(default)]
+    pub change_amount: u64,
+    #[serde(default)]
+    pub change_address: String,
+    pub fee: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Recipient {
+    pub address: String,
+    pub amount: u64,
+}
+
+/// A multisig txset with one more signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedMultisig {
+    pub tx_data_hex: String,
+    /// Hashes of the transactions, once they have every signature needed
+    #[serde(default)]
+    pub tx_hash_list: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransferResponse {
+    #[serde(default)]
+    tx_hash: String,
+    #[serde(default)]
+    tx_key: String,
+    amount: u64,
+    fee: u64,
+    #[serde(default)]
+    tx_metadata: String,
+    #[serde(default)]
+    multisig_txset: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MultisigInfoResponse {
+    #[serde(alias = "info")]
+    multisig_info: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DescribeTransferResponse {
+    #[serde(default)]
+    desc: Vec<TransferDescription>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubmitMultisigResponse {
+    tx_hash_list: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelayTxResponse {
+    tx_hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportOutputsResponse {
+    outputs_data_hex: String,
+}
+
+/// Client for a monero-wallet-rpc JSON-RPC endpoint
+#[derive(Clone)]
+pub struct MoneroWalletClient {
+    client: Client,
+    rpc_url: String,
+    retry: RetryPolicy,
+}
+
+impl MoneroWalletClient {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            client: Client::new(),
+            rpc_url,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Backoff used for transient failures (default: [`RetryPolicy::default`])
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Call a `/json_rpc` method, retrying transient failures and counting
+    /// the ones that still fail in the RPC error metric. Retrying `transfer`
+    /// only signs again, and `relay_tx` rebroadcasts the same transaction, so
+    /// a retry can't pay twice.
+    async fn json_rpc<P, R>(&self, method: &'static str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method,
+            params,
+        };
+
+        let result = self
+            .retry
+            .retry("monero_wallet", method, || async {
+                let response: JsonRpcResponse<R> = self
+                    .client
+                    .post(format!("{}/json_rpc", self.rpc_url))
+                    .json(&request)
+                    .send()
+                    .await
+                    .map_err(|e| OracleError::monero(method, e))?
+                    .json()
+                    .await
+                    .map_err(|e| OracleError::monero(method, e))?;
+
+                if let Some(error) = response.error {
+                    return Err(OracleError::monero_status(method, &error.message).into());
+                }
+
+                response
+                    .result
+                    .ok_or_else(|| OracleError::Parse(format!("No result in {} response", method)))
+                    .map_err(Into::into)
+            })
+            .await;
+
+        metrics::get().observe_rpc("monero_wallet", method, result)
+    }
+
+    pub async fn get_balance(&self, account: u32) -> Result<Balance> {
+        self.json_rpc(
+            "get_balance",
+            serde_json::json!({ "account_index": account }),
+        )
+        .await
+    }
+
+    /// Primary address of `account`
+    pub async fn get_address(&self, account: u32) -> Result<String> {
+        let response: GetAddressResponse = self
+            .json_rpc(
+                "get_address",
+                serde_json::json!({ "account_index": account }),
+            )
+            .await?;
+        Ok(response.address)
+    }
+
+    /// Every output the wallet has received in `account`, spent or not
+    pub async fn incoming_transfers(&self, account: u32) -> Result<Vec<IncomingTransfer>> {
+        let response: IncomingTransfersResponse = self
+            .json_rpc(
+                "incoming_transfers",
+                serde_json::json!({ "transfer_type": "all", "account_index": account }),
+            )
+            .await?;
+        Ok(response.transfers)
+    }
+
+    /// Create and sign one transaction paying every destination from
+    /// `account` at `priority`, without broadcasting it. The returned amount
+    /// is the total sent.
+    pub async fn transfer(
+        &self,
+        destinations: &[Destination<'_>],
+        account: u32,
+        priority: FeePriority,
+    ) -> Result<SignedTransfer> {
+        self.unrelayed_transfer(destinations, account, priority)
+            .await?
+            .try_into()
+    }
+
+    /// Create a transfer like [`Self::transfer`] from a multisig wallet,
+    /// which signs it only with its own keys
+    pub async fn transfer_multisig(
+        &self,
+        destinations: &[Destination<'_>],
+        account: u32,
+        priority: FeePriority,
+    ) -> Result<MultisigTransfer> {
+        let response = self
+            .unrelayed_transfer(destinations, account, priority)
+            .await?;
+        anyhow::ensure!(
+            !response.multisig_txset.is_empty(),
+            "Wallet returned no multisig txset; is it a multisig wallet?"
+        );
+        Ok(MultisigTransfer {
+            txset: response.multisig_txset,
+            tx_key: response.tx_key,
+            amount: response.amount,
+            fee: response.fee,
+        })
+    }
+
+    async fn unrelayed_transfer(
+        &self,
+        destinations: &[Destination<'_>],
+        account: u32,
+        priority: FeePriority,
+    ) -> Result<TransferResponse> {
+        self.json_rpc(
+            "transfer",
+            TransferRequest {
+                destinations,
+                account_index: account,
+                priority: priority.wallet_priority(),
+                get_tx_key: true,
+                get_tx_metadata: true,
+                do_not_relay: true,
+            },
+        )
+        .await
+    }
+
+    /// Broadcast a transfer signed by [`Self::transfer`]
+    pub async fn relay_tx(&self, tx_metadata: &str) -> Result<B256> {
+        let response: RelayTxResponse = self
+            .json_rpc("relay_tx", serde_json::json!({ "hex": tx_metadata }))
+            .await?;
+        parse_hex_to_b256(&response.tx_hash)
+    }
+
+    /// Create a view-only wallet file `filename` for `address` from its
+    /// private view key, and open it in place of the current wallet.
+    /// wallet-rpc must run with `--wallet-dir`.
+    pub async fn generate_from_keys(
+        &self,
+        filename: &str,
+        address: &str,
+        view_key: &str,
+        restore_height: u64,
+    ) -> Result<()> {
+        let _: serde_json::Value = self
+            .json_rpc(
+                "generate_from_keys",
+                serde_json::json!({
+                    "filename": filename,
+                    "address": address,
+                    "viewkey": view_key,
+                    "restore_height": restore_height,
+                    "password": "",
+                    "autosave_current": true,
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Scan the chain up to the daemon's height, from `start_height` for a
+    /// freshly created wallet
+    pub async fn refresh(&self, start_height: u64) -> Result<()> {
+        let _: serde_json::Value = self
+            .json_rpc(
+                "refresh",
+                serde_json::json!({ "start_height": start_height }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The wallet's outputs (or, unless `all`, those added since the last
+    /// export) in the file format of monero-wallet-cli's `export_outputs`
+    pub async fn export_outputs(&self, all: bool) -> Result<Vec<u8>> {
+        let response: ExportOutputsResponse = self
+            .json_rpc("export_outputs", serde_json::json!({ "all": all }))
+            .await?;
+        hex::decode(&response.outputs_data_hex)
+            .map_err(|e| OracleError::Parse(format!("Invalid outputs_data_hex: {}", e)).into())
+    }
+
+    /// Signed key images of the wallet's outputs, or unless `all` of those
+    /// added since the last export. Needs the spend key.
+    pub async fn export_key_images(&self, all: bool) -> Result<KeyImages> {
+        self.json_rpc("export_key_images", serde_json::json!({ "all": all }))
+            .await
+    }
+
+    /// Mark the outputs `key_images` belong to as spent or unspent
+    pub async fn import_key_images(&self, key_images: &KeyImages) -> Result<KeyImageImport> {
+        self.json_rpc("import_key_images", key_images).await
+    }
+
+    pub async fn is_multisig(&self) -> Result<MultisigStatus> {
+        self.json_rpc("is_multisig", serde_json::json!({})).await
+    }
+
+    /// First step of making this wallet multisig: the info every other
+    /// participant passes to [`Self::make_multisig`]
+    pub async fn prepare_multisig(&self) -> Result<String> {
+        let response: MultisigInfoResponse = self
+            .json_rpc("prepare_multisig", serde_json::json!({}))
+            .await?;
+        Ok(response.multisig_info)
+    }
+
+    /// Turn this wallet into an M-of-N multisig wallet from the other
+    /// participants' `prepare_multisig` info
+    pub async fn make_multisig(
+        &self,
+        multisig_info: &[String],
+        threshold: u32,
+        password: &str,
+    ) -> Result<MultisigKeys> {
+        self.json_rpc(
+            "make_multisig",
+            serde_json::json!({
+                "multisig_info": multisig_info,
+                "threshold": threshold,
+                "password": password,
+            }),
+        )
+        .await
+    }
+
+    /// One further round of key exchange, from the other participants'
+    /// info of the previous step
+    pub async fn exchange_multisig_keys(
+        &self,
+        multisig_info: &[String],
+        password: &str,
+    ) -> Result<MultisigKeys> {
+        self.json_rpc(
+            "exchange_multisig_keys",
+            serde_json::json!({
+                "multisig_info": multisig_info,
+                "password": password,
+            }),
+        )
+        .await
+    }
+
+    /// Partial key images and signing nonces for the other participants,
+    /// who must import them before they can sign with this wallet
+    pub async fn export_multisig_info(&self) -> Result<String> {
+        let response: MultisigInfoResponse = self
+            .json_rpc("export_multisig_info", serde_json::json!({}))
+            .await?;
+        Ok(response.multisig_info)
+    }
+
+    /// Import the other participants' [`Self::export_multisig_info`]; returns
+    /// the number of outputs it completed
+    pub async fn import_multisig_info(&self, info: &[String]) -> Result<u64> {
+        #[derive(Deserialize)]
+        struct Response {
+            n_outputs: u64,
+        }
+        let response: Response = self
+            .json_rpc("import_multisig_info", serde_json::json!({ "info": info }))
+            .await?;
+        Ok(response.n_outputs)
+    }
+
+    /// Destinations and fee of the transactions in a multisig txset
+    pub async fn describe_transfer(&self, txset: &str) -> Result<Vec<TransferDescription>> {
+        let response: DescribeTransferResponse = self
+            .json_rpc(
+                "describe_transfer",
+                serde_json::json!({ "multisig_txset": txset }),
+            )
+            .await?;
+        Ok(response.desc)
+    }
+
+    /// Add this wallet's signature to a multisig txset
+    pub async fn sign_multisig(&self, txset: &str) -> Result<SignedMultisig> {
+        self.json_rpc("sign_multisig", serde_json::json!({ "tx_data_hex": txset }))
+            .await
+    }
+
+    /// Broadcast a multisig txset carrying enough signatures. Submitting it
+    /// again sends the same transaction.
+    pub async fn submit_multisig(&self, txset: &str) -> Result<B256> {
+        let response: SubmitMultisigResponse = self
+            .json_rpc(
+                "submit_multisig",
+                serde_json::json!({ "tx_data_hex": txset }),
+            )
+            .await?;
+        let tx_hash = response
+            .tx_hash_list
+            .first()
+            .ok_or_else(|| OracleError::Parse("submit_multisig sent nothing".to_string()))?;
+        parse_hex_to_b256(tx_hash)
+    }
+}
+
+impl TryFrom<TransferResponse> for SignedTransfer {
+    type Error = anyhow::Error;
+
+    fn try_from(response: TransferResponse) -> Result<Self> {
+        anyhow::ensure!(
+            !response.tx_metadata.is_empty(),
+            "Wallet returned no tx metadata"
+        );
+        Ok(Self {
+            tx_hash: parse_hex_to_b256(&response.tx_hash)?,
+            tx_key: response.tx_key,
+            amount: response.amount,
+            fee: response.fee,
+            tx_metadata: response.tx_metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transfer_response() {
+        let response: TransferResponse = serde_json::from_value(serde_json::json!({
+            "amount": 1_000_000_000_000u64,
+            "fee": 30_620_000u64,
+            "multisig_txset": "",
+            "tx_blob": "",
+            "tx_hash": "b".repeat(64),
+            "tx_key": "c".repeat(64),
+            "tx_metadata": "0200",
+            "unsigned_txset": ""
+        }))
+        .unwrap();
+
+        let signed = SignedTransfer::try_from(response).unwrap();
+        assert_eq!(signed.tx_hash, B256::repeat_byte(0xbb));
+        assert_eq!(signed.tx_key, "c".repeat(64));
+        assert_eq!(signed.fee, 30_620_000);
+        assert_eq!(signed.tx_metadata, "0200");
+    }
+
+    #[test]
+    fn test_parse_key_images() {
+        let exported: KeyImages = serde_json::from_value(serde_json::json!({
+            "offset": 0,
+            "signed_key_images": [{
+                "key_image": "a".repeat(64),
+                "signature": "b".repeat(128)
+            }]
+        }))
+        .unwrap();
+        assert_eq!(exported.signed_key_images.len(), 1);
+        assert!(exported.validate().is_ok());
+
+        // A wallet with no outputs leaves the list out
+        let empty: KeyImages = serde_json::from_value(serde_json::json!({ "offset": 0 })).unwrap();
+        assert!(empty.signed_key_images.is_empty());
+
+        let mut truncated = exported.clone();
+        truncated.offset = 3;
+        truncated.signed_key_images[0].signature.truncate(64);
+        assert_eq!(
+            truncated.validate().unwrap_err().to_string(),
+            "Key image 3 is malformed"
+        );
+    }
+}
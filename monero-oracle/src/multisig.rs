@@ -0,0 +1,589 @@
+//! M-of-N Monero multisig for withdrawal payouts
+//!
+//! With `MULTISIG_PEERS` set, the bridge wallet is a Monero multisig wallet
+//! shared by N operators, each with their own monero-wallet-rpc, and no
+//! payout leaves it without M signatures. The oracle paying withdrawals
+//! coordinates each signing round:
+//!
+//! 1. It fetches every co-signer's multisig info (`GET /multisig/info`) and
+//!    imports it, so the wallets share fresh signing nonces.
+//! 2. It creates the payout transfer, signed by its own wallet, and records
+//!    a round in the `multisig_rounds` table.
+//! 3. It passes the txset to co-signers (`POST /multisig/sign`) one at a time,
+//!    each adding a signature, until it has M, recording each in the round.
+//! 4. The complete txset is stored like any signed payout and broadcast with
+//!    `submit_multisig`.
+//!
+//! Co-signers are oracles with the multisig wallet as `MONERO_WALLET_RPC_URL`
+//! and `WATCH_WITHDRAWALS` on; they never pay out themselves. Before signing,
+//! a co-signer checks the transaction against burns it read from the
+//! contract itself: every destination must be one of the round's burns, paid
+//! no more than was burned, and change must return to the bridge wallet. A
+//! fee sweep may only pay the co-signer's own `TREASURY_XMR_ADDRESS`.
+//!
+//! Exporting multisig info replaces a wallet's signing nonces, so a txset
+//! from an earlier round can't be signed by a wallet that has taken part in
+//! a later one. A round left unfinished by a restart is marked failed and
+//! its withdrawals go into a new round. Operators authenticate each other
+//! with the shared `MULTISIG_API_KEY`.
+
+use crate::{
+    admin::BearerAuth,
+    fees::FeePriority,
+    journal::Intent,
+    monero_rpc::parse_hex_to_b256,
+    monero_wallet::{
+        Destination, MoneroWalletClient, MultisigStatus, SignedMultisig, SignedTransfer,
+        TransferDescription,
+    },
+    scanner::format_xmr,
+    storage::Database,
+};
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, time::Duration};
+use tracing::warn;
+
+/// Time allowed for a co-signer to import multisig info and sign
+const PEER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `MULTISIG_API_KEY` and `MULTISIG_PEERS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigConfig {
+    /// Shared by every operator; authenticates the `/multisig` endpoints
+    pub api_key: String,
+    /// Co-signers' HTTP APIs; empty on a co-signer
+    pub peers: Vec<String>,
+}
+
+impl MultisigConfig {
+    /// Whether this oracle pays withdrawals out, rather than only co-signing
+    pub fn is_coordinator(&self) -> bool {
+        !self.peers.is_empty()
+    }
+}
+
+/// What a signing round's transaction is for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Purpose {
+    /// Withdrawal payout for these burns
+    Payout { burn_ids: Vec<u64> },
+    /// Collected bridge fees to the treasury
+    FeeSweep,
+}
+
+impl fmt::Display for Purpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Payout { burn_ids } => write!(f, "{}", Intent::payout(burn_ids.iter().copied())),
+            Self::FeeSweep => f.write_str("fee-sweep"),
+        }
+    }
+}
+
+/// Which side of a round this oracle was on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundRole {
+    Coordinator,
+    Cosigner,
+}
+
+impl fmt::Display for RoundRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Coordinator => "coordinator",
+            Self::Cosigner => "cosigner",
+        })
+    }
+}
+
+impl FromStr for RoundRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "coordinator" => Ok(Self::Coordinator),
+            "cosigner" => Ok(Self::Cosigner),
+            other => anyhow::bail!("Unknown round role '{}'", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundStatus {
+    /// Collecting signatures
+    Signing,
+    /// Signed by this wallet, and by enough others if coordinating
+    Signed,
+    /// Given up; the txset can't be completed
+    Failed,
+}
+
+impl fmt::Display for RoundStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Signing => "signing",
+            Self::Signed => "signed",
+            Self::Failed => "failed",
+        })
+    }
+}
+
+impl FromStr for RoundStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "signing" => Ok(Self::Signing),
+            "signed" => Ok(Self::Signed),
+            "failed" => Ok(Self::Failed),
+            other => anyhow::bail!("Unknown round status '{}'", other),
+        }
+    }
+}
+
+/// A signing round as kept in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningRound {
+    pub id: i64,
+    /// [`Purpose`] of the round, e.g. `payout:4,5`
+    pub key: String,
+    pub role: RoundRole,
+    pub status: RoundStatus,
+    /// Co-signers that have signed, in order
+    pub signers: Vec<String>,
+    /// Latest txset, with every signature so far
+    #[serde(skip)]
+    pub tx_data_hex: String,
+    /// Once the transaction has every signature it needs
+    pub tx_hash: Option<B256>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `GET /multisig/info` body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigInfo {
+    pub multisig_info: String,
+}
+
+/// `POST /multisig/sign` body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignRequest {
+    pub purpose: Purpose,
+    /// Multisig info of every other wallet in the round
+    pub multisig_info: Vec<String>,
+    pub tx_data_hex: String,
+}
+
+/// Check that `descriptions` pay only what a round allows: each recipient
+/// is a separate entry of `allowed` (address and the most it may get), and
+/// change, if any, goes to `change_address`
+pub fn check_recipients(
+    descriptions: &[TransferDescription],
+    allowed: &[(String, u64)],
+    change_address: &str,
+) -> Result<()> {
+    let [description] = descriptions else {
+        anyhow::bail!("Expected one transaction, got {}", descriptions.len());
+    };
+    // Largest payouts first, each against the smallest allowance it fits,
+    // so no payout takes an allowance a larger one needed
+    let mut recipients: Vec<_> = description.recipients.iter().collect();
+    recipients.sort_by_key(|recipient| std::cmp::Reverse(recipient.amount));
+    let mut unused: Vec<&(String, u64)> = allowed.iter().collect();
+    for recipient in recipients {
+        let Some(i) = unused
+            .iter()
+            .enumerate()
+            .filter(|(_, (address, most))| {
+                *address == recipient.address && recipient.amount <= *most
+            })
+            .min_by_key(|(_, (_, most))| *most)
+            .map(|(i, _)| i)
+        else {
+            anyhow::bail!(
+                "Transaction pays {} XMR to {}, which the round doesn't ask for",
+                format_xmr(recipient.amount),
+                recipient.address
+            );
+        };
+        unused.swap_remove(i);
+    }
+    anyhow::ensure!(
+        description.change_amount == 0 || description.change_address == change_address,
+        "Transaction sends {} XMR of change to {}, not the bridge wallet",
+        format_xmr(description.change_amount),
+        description.change_address
+    );
+    Ok(())
+}
+
+/// Payout side: has co-signers sign the bridge wallet's transfers
+#[derive(Clone)]
+pub struct Coordinator {
+    config: MultisigConfig,
+    client: reqwest::Client,
+}
+
+impl Coordinator {
+    pub fn new(config: MultisigConfig, client: reqwest::Client) -> Self {
+        Self { config, client }
+    }
+
+    /// Refuse to pay out from a wallet that isn't a finished multisig wallet,
+    /// or with too few co-signers to reach its threshold
+    pub async fn check(&self, wallet: &MoneroWalletClient) -> Result<MultisigStatus> {
+        let status = wallet.is_multisig().await?;
+        anyhow::ensure!(
+            status.multisig && status.ready,
+            "MULTISIG_PEERS is set but the bridge wallet isn't a finished multisig wallet"
+        );
+        anyhow::ensure!(
+            status.threshold >= 2,
+            "A {}-of-{} wallet needs no co-signers; unset MULTISIG_PEERS",
+            status.threshold,
+            status.total
+        );
+        anyhow::ensure!(
+            self.config.peers.len() >= status.threshold as usize - 1,
+            "A {}-of-{} wallet needs {} co-signers but MULTISIG_PEERS has {}",
+            status.threshold,
+            status.total,
+            status.threshold - 1,
+            self.config.peers.len()
+        );
+        Ok(status)
+    }
+
+    /// Create a transfer to `destinations` and have enough co-signers sign
+    /// it. The round is recorded in `db` as it goes; the returned transfer's
+    /// metadata is the complete txset, for `submit_multisig`.
+    pub async fn transfer(
+        &self,
+        wallet: &MoneroWalletClient,
+        db: &Database,
+        purpose: &Purpose,
+        destinations: &[Destination<'_>],
+        account: u32,
+        priority: FeePriority,
+    ) -> Result<SignedTransfer> {
+        let status = self.check(wallet).await?;
+        let needed = status.threshold as usize - 1;
+
+        let own_info = wallet.export_multisig_info().await?;
+        let mut infos = Vec::new();
+        for peer in &self.config.peers {
+            match self.peer_info(peer).await {
+                Ok(info) => infos.push((peer.as_str(), info)),
+                Err(e) => warn!("   ⚠️  Co-signer {} unavailable: {:#}", peer, e),
+            }
+        }
+        anyhow::ensure!(
+            infos.len() >= needed,
+            "Only {} of the {} co-signers needed are available",
+            infos.len(),
+            needed
+        );
+        let peer_infos: Vec<String> = infos.iter().map(|(_, info)| info.clone()).collect();
+        wallet.import_multisig_info(&peer_infos).await?;
+
+        let unsigned = wallet
+            .transfer_multisig(destinations, account, priority)
+            .await?;
+        let round = db.begin_multisig_round(
+            &purpose.to_string(),
+            RoundRole::Coordinator,
+            &unsigned.txset,
+        )?;
+        match self
+            .collect(
+                db,
+                round,
+                purpose,
+                &own_info,
+                &infos,
+                unsigned.txset,
+                needed,
+            )
+            .await
+        {
+            Ok((txset, tx_hash)) => {
+                db.close_multisig_round(round, RoundStatus::Signed, Some(tx_hash), None)?;
+                Ok(SignedTransfer {
+                    tx_hash,
+                    tx_key: unsigned.tx_key,
+                    amount: unsigned.amount,
+                    fee: unsigned.fee,
+                    tx_metadata: txset,
+                })
+            }
+            Err(e) => {
+                db.close_multisig_round(
+                    round,
+                    RoundStatus::Failed,
+                    None,
+                    Some(&format!("{:#}", e)),
+                )?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Pass `txset` along `infos`' co-signers until `needed` have signed;
+    /// returns the complete txset and its transaction hash
+    #[allow(clippy::too_many_arguments)]
+    async fn collect(
+        &self,
+        db: &Database,
+        round: i64,
+        purpose: &Purpose,
+        own_info: &str,
+        infos: &[(&str, String)],
+        mut txset: String,
+        needed: usize,
+    ) -> Result<(String, B256)> {
+        let mut signed = 0;
+        let mut tx_hashes = Vec::new();
+        for (peer, _) in infos {
+            if signed == needed {
+                break;
+            }
+            let multisig_info = std::iter::once(own_info.to_string())
+                .chain(
+                    infos
+                        .iter()
+                        .filter(|(other, _)| other != peer)
+                        .map(|(_, info)| info.clone()),
+                )
+                .collect();
+            let request = SignRequest {
+                purpose: purpose.clone(),
+                multisig_info,
+                tx_data_hex: txset.clone(),
+            };
+            match self.request_signature(peer, &request).await {
+                Ok(response) => {
+                    txset = response.tx_data_hex;
+                    tx_hashes = response.tx_hash_list;
+                    db.record_multisig_signature(round, peer, &txset)?;
+                    signed += 1;
+                }
+                Err(e) => warn!("   ⚠️  Co-signer {} didn't sign: {:#}", peer, e),
+            }
+        }
+        anyhow::ensure!(
+            signed == needed,
+            "Only {} of the {} co-signatures needed were given",
+            signed,
+            needed
+        );
+        let tx_hash = tx_hashes
+            .first()
+            .context("Co-signers returned no transaction hash")?;
+        Ok((txset, parse_hex_to_b256(tx_hash)?))
+    }
+
+    async fn peer_info(&self, peer: &str) -> Result<String> {
+        let response = self
+            .client
+            .get(format!("{}/multisig/info", peer))
+            .bearer_auth(&self.config.api_key)
+            .timeout(PEER_TIMEOUT)
+            .send()
+            .await?;
+        let info: MultisigInfo = peer_response(response).await?;
+        Ok(info.multisig_info)
+    }
+
+    async fn request_signature(&self, peer: &str, request: &SignRequest) -> Result<SignedMultisig> {
+        let response = self
+            .client
+            .post(format!("{}/multisig/sign", peer))
+            .bearer_auth(&self.config.api_key)
+            .timeout(PEER_TIMEOUT)
+            .json(request)
+            .send()
+            .await?;
+        peer_response(response).await
+    }
+}
+
+/// A co-signer's JSON answer, or the error it gave
+pub(crate) async fn peer_response<T: serde::de::DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T> {
+    let status = response.status();
+    if !status.is_success() {
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        anyhow::bail!(
+            "{}: {}",
+            status,
+            body["error"].as_str().unwrap_or("no error message")
+        );
+    }
+    Ok(response.json().await?)
+}
+
+/// Co-signing side: checks and signs the coordinator's transfers
+#[derive(Clone)]
+pub struct Cosigner {
+    wallet: MoneroWalletClient,
+    api_key: String,
+    /// Account change must return to
+    account: u32,
+    treasury: Option<String>,
+}
+
+/// Peers present `MULTISIG_API_KEY`
+impl BearerAuth for Cosigner {
+    fn api_key(&self) -> Option<&str> {
+        Some(&self.api_key)
+    }
+}
+
+impl Cosigner {
+    pub fn new(
+        wallet: MoneroWalletClient,
+        api_key: String,
+        account: u32,
+        treasury: Option<String>,
+    ) -> Self {
+        Self {
+            wallet,
+            api_key,
+            account,
+            treasury,
+        }
+    }
+
+    /// This wallet's multisig info, with fresh signing nonces
+    pub async fn info(&self) -> Result<MultisigInfo> {
+        Ok(MultisigInfo {
+            multisig_info: self.wallet.export_multisig_info().await?,
+        })
+    }
+
+    /// Sign `request`'s txset if it pays only what its purpose allows,
+    /// recording the round in `db`
+    pub async fn sign(&self, db: &Database, request: &SignRequest) -> Result<SignedMultisig> {
+        self.wallet
+            .import_multisig_info(&request.multisig_info)
+            .await
+            .context("Failed to import the round's multisig info")?;
+
+        let allowed = match &request.purpose {
+            Purpose::Payout { burn_ids } => {
+                let mut allowed = Vec::with_capacity(burn_ids.len());
+                for &burn_id in burn_ids {
+                    let withdrawal = db.withdrawal(burn_id)?.with_context(|| {
+                        format!(
+                            "Burn #{} is unknown here; is WATCH_WITHDRAWALS on?",
+                            burn_id
+                        )
+                    })?;
+                    allowed.push((withdrawal.xmr_address, withdrawal.amount));
+                }
+                allowed
+            }
+            Purpose::FeeSweep => {
+                let treasury = self
+                    .treasury
+                    .clone()
+                    .context("No TREASURY_XMR_ADDRESS to check a fee sweep against")?;
+                vec![(treasury, u64::MAX)]
+            }
+        };
+        let descriptions = self.wallet.describe_transfer(&request.tx_data_hex).await?;
+        let change_address = self.wallet.get_address(self.account).await?;
+        check_recipients(&descriptions, &allowed, &change_address)?;
+
+        let signed = self.wallet.sign_multisig(&request.tx_data_hex).await?;
+        let round = db.begin_multisig_round(
+            &request.purpose.to_string(),
+            RoundRole::Cosigner,
+            &signed.tx_data_hex,
+        )?;
+        let tx_hash = signed
+            .tx_hash_list
+            .first()
+            .map(|hash| parse_hex_to_b256(hash))
+            .transpose()?;
+        db.close_multisig_round(round, RoundStatus::Signed, tx_hash, None)?;
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XMR: u64 = 1_000_000_000_000;
+
+    fn description(recipients: &[(&str, u64)], change: (&str, u64)) -> TransferDescription {
+        serde_json::from_value(serde_json::json!({
+            "recipients": recipients
+                .iter()
+                .map(|(address, amount)| serde_json::json!({ "address": address, "amount": amount }))
+                .collect::<Vec<_>>(),
+            "change_address": change.0,
+            "change_amount": change.1,
+            "fee": 30_000_000u64,
+            "ring_size": 16
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_recipients() {
+        let allowed = vec![("alice".to_string(), 2 * XMR), ("alice".to_string(), XMR)];
+
+        // Net of fees, each payout to its own burn
+        let paid = description(
+            &[("alice", XMR - 1), ("alice", 2 * XMR - 1)],
+            ("bridge", 5 * XMR),
+        );
+        assert!(check_recipients(&[paid], &allowed, "bridge").is_ok());
+
+        // Two payouts can't both claim the larger burn
+        let twice = description(&[("alice", 2 * XMR), ("alice", 2 * XMR)], ("bridge", 0));
+        assert!(check_recipients(&[twice], &allowed, "bridge").is_err());
+
+        let stranger = description(&[("mallory", XMR)], ("bridge", 0));
+        assert_eq!(
+            check_recipients(&[stranger], &allowed, "bridge")
+                .unwrap_err()
+                .to_string(),
+            "Transaction pays 1.000000000000 XMR to mallory, which the round doesn't ask for"
+        );
+
+        let change = description(&[("alice", XMR)], ("mallory", XMR));
+        assert!(check_recipients(&[change], &allowed, "bridge").is_err());
+
+        assert!(check_recipients(&[], &allowed, "bridge").is_err());
+    }
+
+    #[test]
+    fn test_purpose_key() {
+        let payout = Purpose::Payout {
+            burn_ids: vec![7, 3],
+        };
+        assert_eq!(payout.to_string(), "payout:3,7");
+        let json = serde_json::to_value(&payout).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "payout", "burn_ids": [7, 3] })
+        );
+        assert_eq!(
+            serde_json::from_value::<Purpose>(serde_json::json!({ "kind": "fee_sweep" })).unwrap(),
+            Purpose::FeeSweep
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Nonce tracking for oracle transactions
+//!
+//! Every oracle transaction uses the account's lowest unmined nonce. When a
+//! send gives up on a transaction that is still pending (fee cap reached, or
+//! out of bumps), the tracker remembers its nonce and fees, so the next send
+//! replaces it instead of queueing behind it with fees the node rejects as
+//! underpriced. A pending transaction the tracker doesn't know about - left by
+//! an earlier run, or sent with the same key elsewhere - is replaced too.
+
+use crate::gas::Fees;
+
+/// A transaction sent by this process that hasn't been seen mined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InFlight {
+    nonce: u64,
+    fees: Fees,
+}
+
+/// Nonce to use for the next transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NextNonce {
+    pub nonce: u64,
+    /// Another transaction is pending with this nonce and has to be replaced
+    pub stuck: bool,
+    /// Fees of that transaction, if this process sent it; a replacement must
+    /// pay more than both
+    pub stuck_fees: Option<Fees>,
+}
+
+#[derive(Debug, Default)]
+pub struct NonceTracker {
+    in_flight: Option<InFlight>,
+}
+
+impl NonceTracker {
+    /// Pick the nonce for the next transaction from the account's mined
+    /// (`latest`) and mempool (`pending`) transaction counts
+    pub fn next(&mut self, latest: u64, pending: u64) -> NextNonce {
+        if self
+            .in_flight
+            .is_some_and(|in_flight| in_flight.nonce < latest)
+        {
+            self.in_flight = None;
+        }
+
+        let stuck_fees = self
+            .in_flight
+            .filter(|in_flight| in_flight.nonce == latest)
+            .map(|in_flight| in_flight.fees);
+
+        NextNonce {
+            nonce: latest,
+            stuck: pending > latest || stuck_fees.is_some(),
+            stuck_fees,
+        }
+    }
+
+    /// Record a transaction (or replacement) broadcast with `nonce`
+    pub fn sent(&mut self, nonce: u64, fees: Fees) {
+        self.in_flight = Some(InFlight { nonce, fees });
+    }
+
+    /// Record that the transaction with `nonce` was mined
+    pub fn mined(&mut self, nonce: u64) {
+        if self
+            .in_flight
+            .is_some_and(|in_flight| in_flight.nonce <= nonce)
+        {
+            self.in_flight = None;
+        }
+    }
+
+    /// Nonce of the transaction this process is still waiting on, if any
+    pub fn pending_nonce(&self) -> Option<u64> {
+        self.in_flight.map(|in_flight| in_flight.nonce)
+    }
+}
+
+/// Whether a send was rejected because the nonce is taken by a pending
+/// transaction paying at least as much
+pub fn is_underpriced(error: &str) -> bool {
+    let error = error.to_ascii_lowercase();
+    error.contains("underpriced") || error.contains("already known")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(max_fee_per_gas: u128) -> Fees {
+        Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas: max_fee_per_gas / 10,
+        }
+    }
+
+    #[test]
+    fn test_fresh_nonce() {
+        let mut tracker = NonceTracker::default();
+        let next = tracker.next(7, 7);
+        assert_eq!(next.nonce, 7);
+        assert!(!next.stuck);
+        assert_eq!(next.stuck_fees, None);
+    }
+
+    #[test]
+    fn test_replaces_own_stuck_transaction() {
+        let mut tracker = NonceTracker::default();
+        tracker.sent(7, fees(1_000));
+
+        // Still pending: the next send reuses the nonce and must outbid it
+        let next = tracker.next(7, 8);
+        assert_eq!(next.nonce, 7);
+        assert!(next.stuck);
+        assert_eq!(next.stuck_fees, Some(fees(1_000)));
+
+        // Dropped from the mempool: same nonce, still outbid to be safe
+        assert!(tracker.next(7, 7).stuck);
+
+        // Mined in the meantime: the record is cleared
+        let next = tracker.next(8, 8);
+        assert_eq!(next.nonce, 8);
+        assert!(!next.stuck);
+        assert_eq!(tracker.pending_nonce(), None);
+    }
+
+    #[test]
+    fn test_unknown_pending_transaction() {
+        let mut tracker = NonceTracker::default();
+        let next = tracker.next(3, 5);
+        assert_eq!(next.nonce, 3);
+        assert!(next.stuck);
+        assert_eq!(next.stuck_fees, None);
+
+        tracker.sent(3, fees(500));
+        tracker.mined(3);
+        assert_eq!(tracker.pending_nonce(), None);
+    }
+
+    #[test]
+    fn test_is_underpriced() {
+        assert!(is_underpriced(
+            "server returned an error response: error code -32000: replacement transaction underpriced"
+        ));
+        assert!(is_underpriced("already known"));
+        assert!(!is_underpriced("nonce too low"));
+    }
+}
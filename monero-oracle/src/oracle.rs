@@ -0,0 +1,5350 @@
+//! The oracle service: polls Monero and posts confirmed blocks to the contract
+
+use crate::{
+    admin::{self, AdminCommand, AdminRequest},
+    alerts::{Alert, Alerter},
+    api::{self, ApiState, ContractStatus, OracleStatus, SharedStatus, StatusResponse},
+    archive::DbBackups,
+    attestation::{self, Attestation, BlockSignatures, OracleMode},
+    audit::AuditReport,
+    block_export::{BlockArtifact, BlockExporter},
+    bridge_fee::{self, FeeCharge, FeeKind, FeeSweep},
+    calldata::ExportedCall,
+    config::{Config, Tunables},
+    costs::{CostKind, TxCost},
+    deposit_policy::{self, ReviewStatus},
+    error,
+    eth::{self, format_ether, wei_to_ether, AggregatorV3, Safe, StoredBlock, WrappedMonero},
+    events::{self, ContractEvent},
+    feed::{BridgeEvent, DepositEvent, Feed},
+    fees,
+    fork_choice::{self, Candidate},
+    frost::{self, KeyShare, Participant},
+    gas::{BudgetExceeded, GasConfig},
+    gossip::Gossip,
+    health::{BalanceLevel, HealthConfig},
+    heartbeat,
+    journal::Intent,
+    key_image::{self, KeyImageSpend},
+    lease::{Decision, Lease},
+    merkle::{
+        block_hashing_blob, output_tree, tx_tree, verify_block_tx_hashes, MerkleHasher, MerkleTree,
+        TreeKind,
+    },
+    metrics,
+    monero_rpc::{
+        is_method_unavailable, outputs_from_transactions, parse_hex_to_b256, transaction_order,
+        BlockHeader, FeeEstimate, FetchedBlock, MoneroOutput, MoneroRpcClient,
+    },
+    monero_wallet::{Destination, MoneroWalletClient, SignedTransfer},
+    multisig::{Coordinator, Cosigner, MultisigConfig, Purpose},
+    nonce::NonceTracker,
+    pow::{self, PowVerifier},
+    price::{self, PriceSource, Rate},
+    proof::{self, OutputInclusionProof},
+    receipts::{self, ReceiptCheck},
+    reload::{self, LogFilter},
+    reorg::{self, ReorgDetector},
+    revert::{Recovery, Revert},
+    safe::{Proposal, SafeClient, SafeTx},
+    scanner::{self, format_xmr, Deposit, ReusedKey, ViewKeys},
+    settings::{self, FileSettings},
+    signer::{OracleSigner, SignerConfig},
+    storage::{Database, PendingMint, PostedBlockRecord, SafeProposal},
+    subaddress::MoneroAddress,
+    systemd::Notifier,
+    withdrawal::{BurnEvent, Withdrawal, WithdrawalStatus},
+    zmq,
+};
+use alloy::{
+    contract::{CallBuilder, CallDecoder},
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::{client::RpcClient, types::TransactionReceipt},
+    sol_types::Eip712Domain,
+    transports::http::Http,
+};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::{future, stream, Stream, StreamExt};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    future::Future,
+    io::Write,
+    path::PathBuf,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Notify},
+    time::{interval, interval_at, timeout, Instant},
+};
+use tracing::{error, info, warn};
+
+/// Most Unichain blocks requested in one `eth_getLogs` call
+const MAX_LOG_RANGE: u64 = 1_000;
+
+/// Most Monero blocks fetched in one bulk request when catching up; a busy
+/// block's transactions run to a few hundred kB
+const BULK_FETCH_BLOCKS: u64 = 25;
+
+/// Most block postings left awaiting Safe confirmations at once; a long
+/// catch-up is proposed in batches rather than all at once
+const MAX_PENDING_SAFE_PROPOSALS: usize = 16;
+
+/// Most posting receipts re-checked in one poll, so a backlog (such as the
+/// postings a standby indexed before taking over) is spread out
+const MAX_RECEIPT_CHECKS: usize = 100;
+
+/// Block data as posted to `postMoneroBlock`
+#[derive(Debug, Clone)]
+struct ProcessedBlock {
+    height: u64,
+    block_hash: B256,
+    prev_hash: B256,
+    cumulative_difficulty: u128,
+    tx_merkle_root: B256,
+    output_merkle_root: B256,
+    /// Kept with the outputs (`INDEX_OUTPUTS`) for proofs
+    tx_tree: MerkleTree,
+    output_tree: MerkleTree,
+    /// Every output, in the leaf order of the output tree
+    outputs: Vec<MoneroOutput>,
+    /// Outputs to the bridge, if view-key scanning is enabled
+    deposits: Vec<Deposit>,
+    /// Key images spent in the block, matched against the bridge's outputs
+    spends: Vec<KeyImageSpend>,
+}
+
+/// What [`OracleService`] does once connected to the contract
+enum Task {
+    /// Poll forever
+    Run,
+    /// Post blocks `from..=to` once, then exit
+    Backfill { from: u64, to: u64 },
+    /// Fill in skipped blocks `from..=to` once, then exit
+    Fill { from: u64, to: u64 },
+}
+
+/// Signing key and contract domain for block attestations
+struct Attester {
+    signer: OracleSigner,
+    domain: Eip712Domain,
+    /// This operator's share of the threshold key, with `FROST_KEY_SHARE`
+    participant: Option<Arc<Participant>>,
+    /// Runs threshold signing rounds, on the submitter
+    coordinator: Option<frost::Coordinator>,
+    /// Shares attestations on the operators' network, with `GOSSIP_LISTEN`
+    gossip: Option<Arc<Gossip>>,
+}
+
+impl Attester {
+    async fn sign(&self, block: &ProcessedBlock) -> Result<Attestation> {
+        let attestation = Attestation::unsigned(
+            self.signer.address(),
+            block.height,
+            block.block_hash,
+            block.tx_merkle_root,
+            block.output_merkle_root,
+        );
+        let signature = self
+            .signer
+            .sign_hash(&attestation.signing_hash(&self.domain))
+            .await?;
+        Ok(attestation.with_signature(signature))
+    }
+}
+
+/// Oracle key and Safe for proposing postings, with `SAFE_ADDRESS`
+struct SafeProposer {
+    signer: OracleSigner,
+    client: SafeClient,
+    chain_id: u64,
+}
+
+/// Polls Monero and keeps the WrappedMonero contract in sync
+pub struct OracleService {
+    config: Config,
+    /// `MONERO_RPC_URL` followed by the fallback nodes
+    monero_nodes: Vec<MoneroRpcClient>,
+    /// Index of the node on the heaviest chain at the last poll
+    active_node: Mutex<usize>,
+    /// Pays out withdrawals, if `MONERO_WALLET_RPC_URL` is set
+    wallet: Option<MoneroWalletClient>,
+    /// Has co-signers sign the wallet's transfers, with `MULTISIG_PEERS`
+    multisig: Option<Coordinator>,
+    /// Fetches attestations from `ATTESTATION_PEERS` in submitter mode
+    peer_client: reqwest::Client,
+    reorg_detector: Mutex<ReorgDetector>,
+    /// RandomX verifier, if `VERIFY_POW` is set
+    pow: Option<Arc<PowVerifier>>,
+    /// Writes each processed block out, if `BLOCK_EXPORT_TARGET` is set
+    exporter: Option<BlockExporter>,
+    alerts: Alerter,
+    /// `GAS_STRATEGY` and friends, replaced when the config file changes
+    gas: RwLock<GasConfig>,
+    /// Applies `LOG_LEVEL` changes, if the binary installed one
+    log_filter: Option<LogFilter>,
+    /// Nonce of the oracle transaction left pending, if any
+    nonces: Mutex<NonceTracker>,
+    /// The oracle key followed by the standby key, once connected
+    oracle_keys: OnceLock<Vec<Address>>,
+    /// Last block indexed in read-only mode
+    indexed_through: Mutex<Option<u64>>,
+    /// When the last withdrawal payout was signed, for batching
+    last_payout: Mutex<Option<Instant>>,
+    /// Set once connected if the oracle role is held by a Safe
+    safe: OnceLock<SafeProposer>,
+    /// Leader lease among redundant instances, with `LEASE_INSTANCE_ID`
+    lease: Option<Lease>,
+    /// An operator let a catch-up past `MAX_CATCH_UP_BLOCKS` go ahead;
+    /// cleared once back within it
+    catch_up_approved: AtomicBool,
+    /// Readiness and watchdog heartbeats, when run by systemd
+    systemd: Notifier,
+    db: Arc<Database>,
+    status: SharedStatus,
+    /// Live events for `/events`
+    feed: Feed,
+}
+
+impl OracleService {
+    pub fn new(config: Config) -> Result<Self> {
+        let http = config.monero_connection.client()?;
+        let monero_nodes = std::iter::once(&config.monero_rpc_url)
+            .chain(&config.monero_fallback_rpc_urls)
+            .map(|url| {
+                let client = MoneroRpcClient::new(url.clone())
+                    .with_client(http.clone())
+                    .with_retry(config.rpc_retry)
+                    .with_rate_limit(config.monero_rate_limit);
+                let client = match config.monero_rpc_login.clone() {
+                    Some(login) => client.with_login(login),
+                    None => client,
+                };
+                match config.monero_fixtures.clone() {
+                    Some(fixtures) => client.with_fixtures(fixtures),
+                    None => client,
+                }
+            })
+            .collect();
+        let reorg_detector = Mutex::new(ReorgDetector::new(
+            config.reorg_tracked_blocks,
+            config.reorg_confirmation_depth,
+        ));
+        let db = Arc::new(Database::open_checked(
+            &config.database_path,
+            config.db_auto_migrate,
+        )?);
+        let wallet = config
+            .wallet_rpc_url
+            .clone()
+            .map(|url| MoneroWalletClient::new(url).with_retry(config.rpc_retry));
+        let peer_client = reqwest::Client::new();
+        let multisig = config
+            .multisig
+            .clone()
+            .filter(MultisigConfig::is_coordinator)
+            .map(|multisig| Coordinator::new(multisig, peer_client.clone()));
+        let pow = config
+            .verify_pow
+            .then(PowVerifier::new)
+            .transpose()?
+            .map(Arc::new);
+        let exporter = config
+            .block_export
+            .clone()
+            .map(|target| {
+                BlockExporter::new(target, config.block_export_retain, peer_client.clone())
+            })
+            .transpose()?;
+        let alerts = Alerter::new(config.alerts.clone());
+        let gas = RwLock::new(config.gas.clone());
+        let lease = config.lease.clone().map(Lease::new);
+        Ok(Self {
+            config,
+            monero_nodes,
+            active_node: Mutex::new(0),
+            wallet,
+            multisig,
+            peer_client,
+            reorg_detector,
+            pow,
+            exporter,
+            alerts,
+            gas,
+            log_filter: None,
+            nonces: Mutex::default(),
+            oracle_keys: OnceLock::new(),
+            indexed_through: Mutex::default(),
+            last_payout: Mutex::default(),
+            safe: OnceLock::new(),
+            lease,
+            catch_up_approved: AtomicBool::new(false),
+            systemd: Notifier::from_env(),
+            db,
+            status: Arc::new(RwLock::new(OracleStatus::default())),
+            feed: Feed::new(),
+        })
+    }
+
+    /// Approve a catch-up past `MAX_CATCH_UP_BLOCKS` from the start
+    /// (`run --force-backfill`)
+    pub fn with_catch_up_approved(self) -> Self {
+        self.catch_up_approved.store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// Let config file changes to `LOG_LEVEL` reach the log subscriber
+    pub fn with_log_filter(mut self, log_filter: LogFilter) -> Self {
+        self.log_filter = Some(log_filter);
+        self
+    }
+
+    fn signer_config(&self) -> Result<&SignerConfig> {
+        self.config
+            .signer
+            .as_ref()
+            .context("No oracle key configured (PRIVATE_KEY, KEYSTORE_PATH or ORACLE_SIGNER)")
+    }
+
+    /// JSON-RPC client for `UNICHAIN_RPC_URL`
+    fn unichain(&self) -> Result<RpcClient<Http<reqwest::Client>>> {
+        eth::rpc_client(&self.config.unichain_rpc_url, &self.config.unichain_http)
+    }
+
+    /// Client for the node currently followed
+    fn monero(&self) -> MoneroRpcClient {
+        let active = *self.active_node.lock().expect("active node poisoned");
+        self.monero_nodes[active].clone()
+    }
+
+    /// Ask every node for its tip and follow the one on the heaviest chain.
+    /// Unreachable nodes are skipped; returns the chosen tip.
+    async fn select_node(&self) -> Result<BlockHeader> {
+        if self.monero_nodes.len() == 1 {
+            return self.monero_nodes[0].get_last_block_header().await;
+        }
+
+        let tips = future::join_all(
+            self.monero_nodes
+                .iter()
+                .map(|node| node.get_last_block_header()),
+        )
+        .await;
+        let mut reachable = Vec::new();
+        for (node, tip) in tips.into_iter().enumerate() {
+            match tip.and_then(|header| Ok((header.cumulative_difficulty()?, header))) {
+                Ok((cumulative_difficulty, header)) => reachable.push((
+                    Candidate {
+                        node,
+                        height: header.height,
+                        cumulative_difficulty,
+                    },
+                    header,
+                )),
+                Err(e) => warn!(
+                    "   ⚠️  Monero node {} unavailable: {:#}",
+                    self.monero_nodes[node].rpc_url(),
+                    e
+                ),
+            }
+        }
+        let candidates: Vec<Candidate> = reachable.iter().map(|(c, _)| *c).collect();
+
+        let mut active = self.active_node.lock().expect("active node poisoned");
+        let best =
+            *fork_choice::heaviest(&candidates, *active).context("No Monero node is reachable")?;
+        if best.node != *active {
+            warn!(
+                "   🔀 Following {} (height {}, cumulative difficulty {})",
+                self.monero_nodes[best.node].rpc_url(),
+                best.height,
+                best.cumulative_difficulty
+            );
+            *active = best.node;
+        }
+        drop(active);
+
+        if let Some((height, posted)) = self.db.latest_cumulative_difficulty()? {
+            if best.cumulative_difficulty < posted {
+                warn!(
+                    "   ⚠️  Heaviest node's chain ({}) is lighter than posted block {} ({}); nodes may be behind or on a fork",
+                    best.cumulative_difficulty, height, posted
+                );
+            }
+        }
+
+        Ok(reachable
+            .into_iter()
+            .find(|(candidate, _)| candidate.node == best.node)
+            .map(|(_, header)| header)
+            .expect("heaviest is a reachable node"))
+    }
+
+    /// Poll Monero and keep the contract in sync until stopped
+    pub async fn run(&self) -> Result<()> {
+        self.start(Task::Run).await
+    }
+
+    /// Post confirmed blocks `from..=to` in order and exit. Blocks the
+    /// contract already has are only rescanned into the local database.
+    pub async fn backfill(&self, from: u64, to: u64) -> Result<()> {
+        self.start(Task::Backfill { from, to }).await
+    }
+
+    /// Post blocks `from..=to` skipped in checkpoint mode, then exit
+    pub async fn fill(&self, from: u64, to: u64) -> Result<()> {
+        self.start(Task::Fill { from, to }).await
+    }
+
+    /// Write `postMoneroBlock` calls for confirmed blocks `from..=to` to `out`
+    /// instead of sending them, one JSON line each, for a multisig or manual
+    /// broadcast. Needs no oracle key and leaves the database alone. Blocks
+    /// the contract already has are skipped; returns how many were written.
+    pub async fn export_calldata(&self, from: u64, to: u64, out: &mut dyn Write) -> Result<u64> {
+        anyhow::ensure!(
+            self.config.mode == OracleMode::Single,
+            "Calldata export covers ORACLE_MODE=single; signed postings need attestations"
+        );
+        anyhow::ensure!(from <= to, "Empty block range {}..{}", from, to);
+        let provider = ProviderBuilder::new().on_client(self.unichain()?);
+        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
+
+        let tip_height = self.select_node().await?.height;
+        let confirmed_height = tip_height.saturating_sub(self.config.min_confirmations);
+        anyhow::ensure!(
+            to <= confirmed_height,
+            "Block {} has fewer than {} confirmations (tip {})",
+            to,
+            self.config.min_confirmations,
+            tip_height
+        );
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        let from = from.max(latest_posted + 1);
+        if from > to {
+            info!("✅ Contract already has blocks up to {}", latest_posted);
+            return Ok(0);
+        }
+
+        // The calls only succeed in order, each on top of the one before
+        let mut parent = eth::get_block(&contract, from - 1)
+            .await?
+            .map(|stored| stored.block_hash);
+        let mut written = 0;
+        let mut blocks = pin!(self.process_blocks(from, to));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            reorg::check_parent(block.height, block.prev_hash, parent)?;
+            let call = WrappedMonero::postMoneroBlockCall {
+                blockHeight: U256::from(block.height),
+                blockHash: block.block_hash,
+                txMerkleRoot: block.tx_merkle_root,
+                outputMerkleRoot: block.output_merkle_root,
+            };
+            ExportedCall::new(
+                self.config.bridge_address,
+                &call,
+                format!("Post Monero block {} ({})", block.height, block.block_hash),
+            )
+            .write_line(out)?;
+            written += 1;
+            parent = Some(block.block_hash);
+        }
+        info!(
+            "✅ Wrote calldata for blocks {} to {}; send it in order",
+            from, to
+        );
+        Ok(written)
+    }
+
+    /// Recompute blocks `from` to `to` from monerod and compare them with
+    /// the contract and the local database. Needs no oracle key.
+    pub async fn audit(&self, from: u64, to: u64) -> Result<AuditReport> {
+        anyhow::ensure!(from <= to, "Empty block range {}..{}", from, to);
+        let provider = ProviderBuilder::new().on_client(self.unichain()?);
+        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
+
+        let tip_height = self.select_node().await?.height;
+        anyhow::ensure!(
+            to <= tip_height,
+            "Block {} is beyond the Monero tip ({})",
+            to,
+            tip_height
+        );
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        info!(
+            "\n🔎 Auditing blocks {} to {} (contract at {})",
+            from, to, latest_posted
+        );
+
+        let mut report = AuditReport::new(from, to, self.config.bridge_address, latest_posted);
+        let mut blocks = pin!(self.process_blocks(from, to));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            let computed = StoredBlock {
+                block_hash: block.block_hash,
+                tx_merkle_root: block.tx_merkle_root,
+                output_merkle_root: block.output_merkle_root,
+            };
+            let stored = eth::get_block(&contract, block.height).await?;
+            let recorded = self
+                .db
+                .posted_block(block.height)?
+                .map(|record| StoredBlock {
+                    block_hash: record.block_hash,
+                    tx_merkle_root: record.tx_merkle_root,
+                    output_merkle_root: record.output_merkle_root,
+                });
+            let before = report.discrepancies.len();
+            report.check(block.height, &computed, stored.as_ref(), recorded.as_ref());
+            for discrepancy in &report.discrepancies[before..] {
+                warn!(
+                    "   ⚠️  Block {}: {:?} {:?} {}",
+                    discrepancy.height,
+                    discrepancy.source,
+                    discrepancy.issue,
+                    discrepancy.fields.join(", ")
+                );
+            }
+        }
+
+        info!(
+            "✅ Audited {} block(s): {} discrepancy(ies)",
+            report.blocks_checked,
+            report.discrepancies.len()
+        );
+        Ok(report)
+    }
+
+    /// Sync state of Monero, the contract and the local database, without
+    /// sending anything
+    pub async fn status(&self) -> Result<StatusResponse> {
+        let signer = OracleSigner::connect(self.signer_config()?).await?;
+        let provider = ProviderBuilder::new().on_client(self.unichain()?);
+        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
+
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        let tip_height = self.monero().get_last_block_header().await?.height;
+
+        let status = OracleStatus {
+            oracle_address: Some(signer.address()),
+            bridge_address: Some(self.config.bridge_address),
+            monero_tip: Some(tip_height),
+            monero_confirmed_height: Some(tip_height.saturating_sub(self.config.min_confirmations)),
+            contract_latest_block: Some(latest_posted),
+            ..OracleStatus::default()
+        };
+        Ok(StatusResponse::new(status, self.db.checkpoint()?))
+    }
+
+    async fn start(&self, task: Task) -> Result<()> {
+        info!("🔮 Monero Oracle Service Starting...\n");
+        info!("Configuration:");
+        info!("   Monero RPC: {}", self.config.monero_rpc_url);
+        for url in &self.config.monero_fallback_rpc_urls {
+            info!("   Monero RPC (fallback): {}", url);
+        }
+        if let Some(url) = &self.config.monero_zmq_url {
+            info!("   Monero ZMQ: {}", url);
+        }
+        if !self.config.monero_rate_limit.is_unlimited() {
+            info!("   Monero RPC limits: {}", self.config.monero_rate_limit);
+        }
+        info!("   Monero HTTP: {}", self.config.monero_connection.http);
+        info!("   Monero network: {}", self.config.network);
+        info!("   Unichain RPC: {}", self.config.unichain_rpc_url);
+        info!("   Unichain HTTP: {}", self.config.unichain_http);
+        info!("   WrappedMonero: {}", self.config.bridge_address);
+        info!(
+            "   Interval: {}s ({} min)",
+            self.config.poll_interval_secs,
+            self.config.poll_interval_secs / 60
+        );
+        info!("   Min confirmations: {}", self.config.min_confirmations);
+        info!("   Fetch concurrency: {}", self.config.fetch_concurrency);
+        info!("   Mode: {}", self.config.mode);
+        if self.config.mode == OracleMode::Submitter {
+            info!(
+                "   Attestation peers: {}",
+                self.config.attestation_peers.len()
+            );
+        }
+        info!(
+            "   Deposit scanning: {}",
+            if self.config.view_keys.is_some() {
+                "enabled"
+            } else {
+                "disabled (no view key)"
+            }
+        );
+        info!(
+            "   Mempool watching: {}",
+            match (self.config.watch_mempool, &self.config.view_keys) {
+                (true, Some(_)) => "enabled",
+                (true, None) => "disabled (no view key)",
+                (false, _) => "disabled",
+            }
+        );
+        info!(
+            "   Auto-mint: {}",
+            if self.config.auto_mint {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        if self.config.auto_mint && !self.config.deposit_policy.is_unlimited() {
+            info!("   Deposit limits: {}", self.config.deposit_policy);
+        }
+        if !self.config.bridge_fee.is_free() {
+            info!("   Bridge fee: {}", self.config.bridge_fee);
+        }
+        info!(
+            "   Withdrawal listener: {}",
+            if self.config.watch_withdrawals {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        info!(
+            "   Withdrawal payouts: {}",
+            match &self.config.wallet_rpc_url {
+                Some(url) => format!("{} (account {})", url, self.config.subaddress_account),
+                None => "disabled (no wallet RPC)".to_string(),
+            }
+        );
+        info!(
+            "   Output Merkle hasher: {}",
+            self.config.output_merkle_hasher
+        );
+        info!("   Tx Merkle hasher: {}", self.config.tx_merkle_hasher);
+        info!(
+            "   Gas strategy: {} (stuck after {}s, up to {} bumps)",
+            self.config.gas.strategy,
+            self.config.gas.stuck_timeout.as_secs(),
+            self.config.gas.max_bumps
+        );
+        info!(
+            "   Reorg checks: last {} blocks, {} confirmations",
+            self.config.reorg_tracked_blocks, self.config.reorg_confirmation_depth
+        );
+        if let Some(interval) = self.config.heartbeat_interval {
+            info!(
+                "   Heartbeat: after {}s without a posting",
+                interval.as_secs()
+            );
+        }
+
+        self.check_monero_network().await?;
+        self.check_multisig_wallet().await?;
+
+        // Set up wallet and provider
+        let signer = OracleSigner::connect(self.signer_config()?).await?;
+        let wallet_address = signer.address();
+        let mut wallet = signer.wallet();
+
+        info!("\n👤 Oracle address: {}", wallet_address);
+        let mut oracle_keys = vec![wallet_address];
+        if let Some(config) = &self.config.standby_signer {
+            let standby = OracleSigner::connect(config).await?;
+            info!("   Standby address: {}", standby.address());
+            standby.register_with(&mut wallet);
+            oracle_keys.push(standby.address());
+        }
+        let _ = self.oracle_keys.set(oracle_keys);
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(wallet)
+            .on_client(self.unichain()?);
+
+        // Check balance; an empty wallet starts paused rather than failing
+        let balance = provider.get_balance(wallet_address).await?;
+        info!("   Balance: {} ETH", format_ether(balance));
+        self.update_balance(wallet_address, wei_to_ether(balance))
+            .await;
+
+        // Transactions a previous run left in the mempool get replaced by
+        // the first send rather than blocking it
+        let mined_nonce = provider
+            .get_transaction_count(wallet_address)
+            .latest()
+            .await?;
+        let pending_nonce = provider
+            .get_transaction_count(wallet_address)
+            .pending()
+            .await?;
+        if pending_nonce > mined_nonce {
+            warn!(
+                "   ⚠️  {} oracle transaction(s) pending from nonce {}; the next transaction replaces it",
+                pending_nonce - mined_nonce,
+                mined_nonce
+            );
+        }
+
+        // Connect to contract
+        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
+
+        // Verify oracle role; every poll follows it from here on
+        let contract_oracle = contract.oracle().call().await?.oracle;
+        if let Some(safe) = &self.config.safe {
+            if contract_oracle != safe.address {
+                warn!(
+                    "   ⚠️  Safe {} is not the oracle ({}); starting read-only",
+                    safe.address, contract_oracle
+                );
+            }
+        } else if contract_oracle != wallet_address && self.config.mode != OracleMode::Signer {
+            if self.oracle_keys().contains(&contract_oracle) {
+                info!("   Standby key {} holds the oracle role", contract_oracle);
+            } else {
+                match self.config.mode {
+                    OracleMode::Single => warn!(
+                        "   ⚠️  Wallet is not the oracle ({}); starting read-only",
+                        contract_oracle
+                    ),
+                    // Block posting is permissionless; rollbacks, mints and
+                    // withdrawal confirmations still need the oracle role
+                    _ => warn!(
+                        "   ⚠️  Wallet is not the oracle ({}); reorg rollbacks, mints and withdrawal confirmations are skipped",
+                        contract_oracle
+                    ),
+                }
+            }
+        }
+
+        let attester = match self.config.mode {
+            OracleMode::Single => {
+                if let Some(config) = &self.config.safe {
+                    let safe = Safe::new(config.address, &provider);
+                    let owners = safe.getOwners().call().await?._0;
+                    let threshold = safe.getThreshold().call().await?._0;
+                    info!(
+                        "   Safe: {} ({} of {} owners confirm postings)",
+                        config.address,
+                        threshold,
+                        owners.len()
+                    );
+                    if !owners.contains(&wallet_address) {
+                        warn!(
+                            "   ⚠️  {} is not a Safe owner; proposals fail unless it is a delegate",
+                            wallet_address
+                        );
+                    }
+                    let _ = self.safe.set(SafeProposer {
+                        signer,
+                        client: SafeClient::new(config.clone()),
+                        chain_id: provider.get_chain_id().await?,
+                    });
+                }
+                None
+            }
+            OracleMode::Signer | OracleMode::Submitter => {
+                let chain_id = provider.get_chain_id().await?;
+                let domain = attestation::domain(chain_id, self.config.bridge_address);
+                let participant = match &self.config.frost {
+                    Some(config) => {
+                        let share = KeyShare::read(&config.key_share)?;
+                        let key = share.group.contract_key()?;
+                        let contract_key = (
+                            contract.thresholdKeyX().call().await?.thresholdKeyX,
+                            contract
+                                .thresholdKeyParity()
+                                .call()
+                                .await?
+                                .thresholdKeyParity,
+                        );
+                        if contract_key != (key.x, key.parity) {
+                            anyhow::bail!(
+                                "The contract's threshold key is not this key share's group key; \
+                                 set it with setThresholdKey({}, {})",
+                                key.x,
+                                key.parity
+                            );
+                        }
+                        info!(
+                            "   Threshold key: participant {} of {}, {} sign",
+                            share.identifier,
+                            share.group.verifying_shares.len(),
+                            share.group.threshold
+                        );
+                        Some(Arc::new(Participant::new(
+                            share,
+                            config.api_key.clone(),
+                            domain.clone(),
+                        )))
+                    }
+                    None => {
+                        let threshold = contract.signerThreshold().call().await?.signerThreshold;
+                        if threshold.is_zero() {
+                            anyhow::bail!(
+                                "No attestation signers configured on the contract (setSigners)"
+                            );
+                        }
+                        if !contract.isSigner(wallet_address).call().await?._0 {
+                            warn!(
+                                "   ⚠️  {} is not in the contract's signer set",
+                                wallet_address
+                            );
+                        }
+                        info!("   Signer threshold: {}", threshold);
+                        None
+                    }
+                };
+                let gossip = match &self.config.gossip {
+                    Some(config) => Some(
+                        Gossip::start(
+                            config,
+                            domain.clone(),
+                            &signer,
+                            contract.getSigners().call().await?._0,
+                            self.db.clone(),
+                        )
+                        .await?,
+                    ),
+                    None => None,
+                };
+                let coordinator = match (&self.config.frost, &participant) {
+                    (Some(config), Some(participant))
+                        if self.config.mode == OracleMode::Submitter =>
+                    {
+                        Some(frost::Coordinator::new(
+                            participant.clone(),
+                            config.peers.clone(),
+                            self.peer_client.clone(),
+                        ))
+                    }
+                    _ => None,
+                };
+                Some(Attester {
+                    signer,
+                    domain,
+                    participant,
+                    coordinator,
+                    gossip,
+                })
+            }
+        };
+
+        self.resume_from_checkpoint(&contract).await?;
+        self.resume_operations(&provider).await?;
+
+        {
+            let mut status = self.status.write().expect("status lock poisoned");
+            status.oracle_address = Some(wallet_address);
+            status.bridge_address = Some(self.config.bridge_address);
+            status.extra_contracts = self
+                .config
+                .extra_bridge_addresses
+                .iter()
+                .map(|&address| ContractStatus {
+                    address,
+                    ..ContractStatus::default()
+                })
+                .collect();
+        }
+        for &address in &self.config.extra_bridge_addresses {
+            let oracle = WrappedMonero::new(address, &provider)
+                .oracle()
+                .call()
+                .await?
+                .oracle;
+            if oracle == wallet_address {
+                info!("   Also posting to {}", address);
+            } else {
+                warn!(
+                    "   ⚠️  Also posting to {}, but its oracle is {}; it is skipped until that changes",
+                    address, oracle
+                );
+            }
+        }
+
+        match task {
+            Task::Run => {}
+            Task::Backfill { from, to } => {
+                return self
+                    .backfill_blocks(&contract, attester.as_ref(), from, to)
+                    .await;
+            }
+            Task::Fill { from, to } => {
+                return self.fill_blocks(&contract, from, to).await.map(|_| ());
+            }
+        }
+
+        if let Some(port) = self.config.metrics_port {
+            tokio::spawn(async move {
+                if let Err(e) = metrics::serve(port).await {
+                    error!("❌ Metrics server stopped: {}", e);
+                }
+            });
+        }
+        if let Some(backup) = self.config.db_backup.clone() {
+            let backups = DbBackups::new(backup, self.peer_client.clone())?;
+            let scratch = PathBuf::from(format!("{}.backup", self.config.database_path));
+            tokio::spawn(backups.run(self.db.clone(), scratch));
+        }
+
+        let (state, mut admin_requests) = self.api_state(attester.as_ref());
+        if let Some(port) = self.config.http_port {
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = api::serve(port, state).await {
+                    error!("❌ HTTP API stopped: {}", e);
+                }
+            });
+        }
+        #[cfg(feature = "grpc")]
+        if let Some(port) = self.config.grpc_port {
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = crate::grpc::serve(port, state).await {
+                    error!("❌ gRPC API stopped: {}", e);
+                }
+            });
+        }
+
+        info!("\n✅ Oracle verified and ready!\n");
+        info!("{}", "═".repeat(70));
+        self.systemd.ready("Polling");
+        if let Some(watchdog) = self.systemd.watchdog_timeout() {
+            let period = Duration::from_secs(self.config.poll_interval_secs);
+            info!("🐕 systemd watchdog: {}s", watchdog.as_secs());
+            // A healthy oracle only checks in once per poll
+            if watchdog < period * 2 {
+                warn!(
+                    "⚠️  WatchdogSec ({}s) is under twice POLL_INTERVAL_SECS ({}s); systemd may restart a healthy oracle",
+                    watchdog.as_secs(),
+                    period.as_secs()
+                );
+            }
+            if let Some(limit) = self.config.poll_timeout.filter(|&limit| watchdog <= limit) {
+                warn!(
+                    "⚠️  WatchdogSec ({}s) isn't above POLL_TIMEOUT_SECS ({}s); systemd restarts the oracle before it can cancel a stalled poll",
+                    watchdog.as_secs(),
+                    limit.as_secs()
+                );
+            }
+        }
+
+        // Main polling loop, woken early by ZMQ block notifications and
+        // contract events
+        let mut poll_interval = interval(Duration::from_secs(self.config.poll_interval_secs));
+        let new_block = Arc::new(Notify::new());
+        if let Some(url) = &self.config.monero_zmq_url {
+            zmq::spawn(url.clone(), new_block.clone());
+        }
+        let (event_tx, mut contract_events) = mpsc::channel(64);
+        if let Some(url) = &self.config.unichain_ws_url {
+            events::spawn(url.clone(), self.config.bridge_address, event_tx);
+        }
+        let (_watcher, mut config_changes) = match settings::path().map(|path| {
+            info!("👀 Watching {} for changes", path.display());
+            reload::watch(&path)
+        }) {
+            Some(Ok((watcher, changes))) => (Some(watcher), changes),
+            Some(Err(e)) => {
+                warn!("⚠️  Config file changes won't be applied: {:#}", e);
+                (None, mpsc::channel(1).1)
+            }
+            None => (None, mpsc::channel(1).1),
+        };
+        let mut tunables = self.config.tunables();
+        let mut consecutive_failures = 0;
+
+        loop {
+            tokio::select! {
+                _ = poll_interval.tick() => {}
+                _ = new_block.notified() => poll_interval.reset(),
+                Some(event) = contract_events.recv() => {
+                    if !self.on_contract_event(event) {
+                        continue;
+                    }
+                    poll_interval.reset();
+                }
+                Some(()) = config_changes.recv() => {
+                    tokio::time::sleep(reload::DEBOUNCE).await;
+                    while config_changes.try_recv().is_ok() {}
+                    if let Some(period) = self.reload_config(&mut tunables) {
+                        poll_interval = interval_at(Instant::now() + period, period);
+                    }
+                    continue;
+                }
+                Some(AdminRequest { command, reply }) = admin_requests.recv() => {
+                    if command == AdminCommand::Drain {
+                        info!("🔑 Draining: no poll is running, exiting");
+                        self.systemd.stopping();
+                        self.release_lease(&contract).await;
+                        let _ = reply.send(Ok("Drained; the oracle is exiting".to_string()));
+                        return Ok(());
+                    }
+                    let outcome = self
+                        .run_admin_command(command, &contract, attester.as_ref())
+                        .await;
+                    if let Err(e) = &outcome {
+                        warn!("⚠️  Admin request failed: {:#}", e);
+                    }
+                    let _ = reply.send(outcome);
+                    continue;
+                }
+            }
+
+            let result = self
+                .timed_poll(self.poll(&contract, attester.as_ref()))
+                .await;
+            self.finish_poll(result, &mut consecutive_failures).await;
+
+            // A standby polls again as the leader's lease runs out, to take
+            // over if it wasn't renewed
+            if let Some(at) = self.lease.as_ref().and_then(Lease::takeover_at) {
+                let now = std::time::Instant::now();
+                let period = poll_interval.period();
+                if at > now && at - now < period {
+                    poll_interval.reset_at(Instant::from_std(at + Duration::from_secs(1)));
+                }
+            }
+        }
+    }
+
+    /// Scan posted blocks `from` to `to` (default: the contract's latest
+    /// block) again with the current view keys and subaddresses, for
+    /// deposits missed when they were posted. Each one found is recorded and
+    /// held for review, so it is only minted once an operator approves it.
+    /// Needs no oracle key; returns the deposits found.
+    pub async fn rescan(&self, from: u64, to: Option<u64>) -> Result<Vec<Deposit>> {
+        anyhow::ensure!(
+            self.config.view_keys.is_some(),
+            "rescan needs BRIDGE_VIEW_KEY and BRIDGE_SPEND_PUBLIC_KEY"
+        );
+        let provider = ProviderBuilder::new().on_client(self.unichain()?);
+        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        let to = to.unwrap_or(latest_posted);
+        anyhow::ensure!(from <= to, "Empty block range {}..{}", from, to);
+        anyhow::ensure!(
+            to <= latest_posted,
+            "Block {} is not posted yet (contract at {}); deposits are only minted from posted blocks",
+            to,
+            latest_posted
+        );
+        info!(
+            "\n🔁 Rescanning blocks {} to {} for missed deposits",
+            from, to
+        );
+
+        let mut found = Vec::new();
+        let mut blocks = pin!(self.process_blocks(from, to));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            if block.deposits.is_empty() {
+                continue;
+            }
+            // A deposit in a block the contract doesn't have can't be proven
+            let stored = eth::get_block(&contract, block.height).await?;
+            if stored.map(|stored| stored.block_hash) != Some(block.block_hash) {
+                warn!(
+                    "   ⚠️  Block {} on the contract isn't {}; skipping its deposits",
+                    block.height, block.block_hash
+                );
+                continue;
+            }
+            for deposit in block.deposits {
+                if self.db.has_deposit(deposit.tx_hash, deposit.output_index)? {
+                    continue;
+                }
+                // Held before it is recorded, so a running oracle never
+                // mints it unreviewed
+                let reason = format!("Found by rescan from block {}", from);
+                self.hold_deposit(&deposit, reason).await?;
+                self.record_deposits(std::slice::from_ref(&deposit)).await?;
+                found.push(deposit);
+            }
+        }
+
+        info!(
+            "✅ Rescanned {} block(s): {} missed deposit(s) held for review",
+            to - from + 1,
+            found.len()
+        );
+        Ok(found)
+    }
+
+    /// Send the bridge fees collected and not yet swept to
+    /// `TREASURY_XMR_ADDRESS` from the bridge wallet, which pays the network
+    /// fee. The sweep is recorded before it is relayed, and forgotten again
+    /// if relaying fails and monerod never saw it. With `dry_run`, or nothing
+    /// to sweep, only reports the amount.
+    pub async fn sweep_fees(&self, dry_run: bool) -> Result<Option<FeeSweep>> {
+        let treasury = self
+            .config
+            .treasury_xmr_address
+            .as_deref()
+            .context("sweep-fees needs TREASURY_XMR_ADDRESS")?;
+        let wallet = self
+            .wallet
+            .as_ref()
+            .context("sweep-fees needs MONERO_WALLET_RPC_URL")?;
+        let unswept = self.db.unswept_fees()?;
+        info!(
+            "\n🏦 {} XMR of collected bridge fees not yet swept to {}",
+            format_xmr(unswept),
+            treasury
+        );
+        if unswept == 0 || dry_run {
+            return Ok(None);
+        }
+
+        let transfer = self
+            .sign_transfer(
+                wallet,
+                &[Destination {
+                    amount: unswept,
+                    address: treasury,
+                }],
+                &Purpose::FeeSweep,
+            )
+            .await?;
+        let sweep = FeeSweep {
+            tx_hash: transfer.tx_hash,
+            amount: transfer.amount,
+            network_fee: transfer.fee,
+            swept_at: Utc::now(),
+        };
+        self.db.record_fee_sweep(&sweep)?;
+        if let Err(e) = self.relay_signed(wallet, &transfer.tx_metadata).await {
+            let known = self
+                .monero()
+                .get_transactions(vec![hex::encode(transfer.tx_hash)])
+                .await;
+            if !matches!(known, Ok(txs) if !txs.is_empty()) {
+                self.db.remove_fee_sweep(transfer.tx_hash)?;
+                return Err(e.context("Failed to relay the sweep"));
+            }
+        }
+        info!(
+            "   ✅ Swept in {} (fee {} XMR)",
+            transfer.tx_hash,
+            format_xmr(transfer.fee)
+        );
+        Ok(Some(sweep))
+    }
+
+    /// Check the contract's blocks against blocks computed from monerod as
+    /// they are posted, from `from` (default: the contract's latest block),
+    /// until stopped. Needs no oracle key and never sends a transaction;
+    /// differences are logged, counted and alerted on.
+    pub async fn watch(&self, from: Option<u64>) -> Result<()> {
+        info!("👁️  Monero Oracle Watcher Starting...\n");
+        info!("Configuration:");
+        info!("   Monero RPC: {}", self.config.monero_rpc_url);
+        for url in &self.config.monero_fallback_rpc_urls {
+            info!("   Monero RPC (fallback): {}", url);
+        }
+        info!("   Monero network: {}", self.config.network);
+        info!("   Unichain RPC: {}", self.config.unichain_rpc_url);
+        info!("   WrappedMonero: {}", self.config.bridge_address);
+        info!("   Interval: {}s", self.config.poll_interval_secs);
+        info!("   Min confirmations: {}", self.config.min_confirmations);
+        info!(
+            "   Output Merkle hasher: {}",
+            self.config.output_merkle_hasher
+        );
+        info!("   Tx Merkle hasher: {}", self.config.tx_merkle_hasher);
+
+        self.check_monero_network().await?;
+
+        let provider = ProviderBuilder::new().on_client(self.unichain()?);
+        let contract = WrappedMonero::new(self.config.bridge_address, &provider);
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        let mut next = from.or((latest_posted > 0).then_some(latest_posted));
+        let mut mismatched = BTreeSet::new();
+        self.status
+            .write()
+            .expect("status lock poisoned")
+            .bridge_address = Some(self.config.bridge_address);
+
+        if let Some(port) = self.config.metrics_port {
+            tokio::spawn(async move {
+                if let Err(e) = metrics::serve(port).await {
+                    error!("❌ Metrics server stopped: {}", e);
+                }
+            });
+        }
+
+        match next {
+            Some(from) => info!(
+                "\n✅ Watching from block {} (contract at {})\n",
+                from, latest_posted
+            ),
+            None => info!("\n✅ Watching from the first block posted\n"),
+        }
+        info!("{}", "═".repeat(70));
+        self.systemd.ready("Watching");
+
+        let mut poll_interval = interval(Duration::from_secs(self.config.poll_interval_secs));
+        let new_block = Arc::new(Notify::new());
+        if let Some(url) = &self.config.monero_zmq_url {
+            zmq::spawn(url.clone(), new_block.clone());
+        }
+        let mut consecutive_failures = 0;
+        loop {
+            tokio::select! {
+                _ = poll_interval.tick() => {}
+                _ = new_block.notified() => poll_interval.reset(),
+            }
+            let result = self
+                .timed_poll(self.watch_poll(&contract, &mut next, &mut mismatched))
+                .await;
+            self.finish_poll(result, &mut consecutive_failures).await;
+        }
+    }
+
+    /// Run one poll, cancelling it after `POLL_TIMEOUT_SECS`
+    async fn timed_poll(&self, poll: impl Future<Output = Result<()>>) -> Result<()> {
+        let timer = metrics::get().poll_duration_seconds.start_timer();
+        let result = match self.config.poll_timeout {
+            Some(limit) => match timeout(limit, poll).await {
+                Ok(result) => result,
+                Err(_) => Err(self.recover_from_stall(limit)),
+            },
+            None => poll.await,
+        };
+        timer.observe_duration();
+        result
+    }
+
+    /// Record the outcome of a poll: status, metrics, systemd heartbeat,
+    /// and an alert once failures need someone's attention
+    async fn finish_poll(&self, result: Result<()>, consecutive_failures: &mut u32) {
+        let error = result.err().map(|e| (error::classify(&e).0, e.to_string()));
+        let last_error = error.as_ref().map(|(_, error)| error.clone());
+        {
+            let mut status = self.status.write().expect("status lock poisoned");
+            status.last_poll_at = Some(Utc::now());
+            status.last_error = last_error;
+        }
+
+        match error {
+            None => {
+                *consecutive_failures = 0;
+                self.alerts.clear("poll_failures");
+                self.systemd.heartbeat(&self.systemd_status());
+            }
+            Some((kind, error)) => {
+                error!("❌ Error in oracle loop ({}): {}", kind, error);
+                // No heartbeat: a poll that keeps failing is left to
+                // alerts, one that never returns to the watchdog
+                self.systemd
+                    .status(&format!("Poll failed ({} error): {}", kind, error));
+                metrics::get()
+                    .poll_errors
+                    .with_label_values(&[&kind.to_string()])
+                    .inc();
+                *consecutive_failures += 1;
+                // Retrying won't fix a bad config, a corrupt database or
+                // chain data that doesn't add up, so don't wait for more
+                // failures before telling someone
+                if kind.needs_operator()
+                    || *consecutive_failures >= self.alerts.config().poll_failures
+                {
+                    self.alerts
+                        .notify(Alert::PollFailures {
+                            count: *consecutive_failures,
+                            kind,
+                            error,
+                        })
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// A poll ran past `POLL_TIMEOUT_SECS` and was cancelled, most likely
+    /// stuck on a request that will never be answered. Its actions are
+    /// journaled, so the next poll picks up where it stopped; connections
+    /// to monerod are replaced in case the stuck one was pooled.
+    fn recover_from_stall(&self, limit: Duration) -> anyhow::Error {
+        metrics::get().poll_stalls.inc();
+        warn!(
+            "⏱️  Poll still running after {}s; cancelled it",
+            limit.as_secs()
+        );
+        match self.config.monero_connection.client() {
+            Ok(http) => {
+                for node in &self.monero_nodes {
+                    node.reconnect(http.clone());
+                }
+                info!("   🔌 Reconnected to the Monero nodes");
+            }
+            Err(e) => warn!("   ⚠️  Failed to reconnect to the Monero nodes: {:#}", e),
+        }
+        anyhow::anyhow!("Poll stalled: cancelled after {}s", limit.as_secs())
+    }
+
+    /// One-line summary for `systemctl status`
+    fn systemd_status(&self) -> String {
+        let status = self.status.read().expect("status lock poisoned");
+        match (status.contract_latest_block, status.monero_tip) {
+            (Some(posted), Some(tip)) => {
+                format!("Contract at block {}, Monero tip {}", posted, tip)
+            }
+            _ => "Polling".to_string(),
+        }
+    }
+
+    /// The state the HTTP and gRPC APIs serve, and the admin requests they
+    /// forward to the polling loop
+    fn api_state(&self, attester: Option<&Attester>) -> (ApiState, mpsc::Receiver<AdminRequest>) {
+        let (admin, admin_requests) = match &self.config.admin_api_key {
+            Some(key) => {
+                let (handle, requests) = admin::channel(key);
+                (Some(handle), requests)
+            }
+            None => (None, mpsc::channel(1).1),
+        };
+        (
+            ApiState {
+                monero_client: self.monero(),
+                db: self.db.clone(),
+                status: self.status.clone(),
+                output_merkle_hasher: self.config.output_merkle_hasher,
+                tx_merkle_hasher: self.config.tx_merkle_hasher,
+                view_keys: self.config.view_keys.clone(),
+                network: self.config.network,
+                attester: attester.map(|a| a.signer.address()),
+                frost: attester.and_then(|a| a.participant.clone()),
+                cosigner: match (&self.config.multisig, &self.wallet) {
+                    (Some(multisig), Some(wallet)) => Some(Cosigner::new(
+                        wallet.clone(),
+                        multisig.api_key.clone(),
+                        self.config.subaddress_account,
+                        self.config.treasury_xmr_address.clone(),
+                    )),
+                    _ => None,
+                },
+                unichain_rpc_url: self.config.unichain_rpc_url.clone(),
+                http_client: self.peer_client.clone(),
+                health: HealthConfig {
+                    // Signers and Safe proposers never spend gas
+                    min_balance_eth: self.config.health.min_balance_eth.filter(|_| {
+                        self.config.mode != OracleMode::Signer && self.config.safe.is_none()
+                    }),
+                    ..self.config.health
+                },
+                started_at: Utc::now(),
+                admin,
+                fill_on_demand: self.config.cadence.checkpoints_only,
+                feed: self.feed.clone(),
+            },
+            admin_requests,
+        )
+    }
+
+    /// Carry out an admin action between polls; returns what it did
+    async fn run_admin_command<T, P>(
+        &self,
+        command: AdminCommand,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        attester: Option<&Attester>,
+    ) -> Result<String>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        match command {
+            AdminCommand::Pause | AdminCommand::Resume => {
+                let pause = command == AdminCommand::Pause;
+                self.status
+                    .write()
+                    .expect("status lock poisoned")
+                    .paused_by_operator = pause;
+                let message = if pause {
+                    "Paused: blocks are tracked, no transactions are sent"
+                } else {
+                    "Resumed"
+                };
+                info!("🔑 {}", message);
+                Ok(message.to_string())
+            }
+            AdminCommand::Backfill { from, to } => {
+                info!("🔑 Backfilling blocks {} to {}", from, to);
+                self.backfill_blocks(contract, attester, from, to).await?;
+                Ok(format!("Backfilled blocks {} to {}", from, to))
+            }
+            AdminCommand::Fill { from, to } => {
+                info!("🔑 Filling blocks {} to {}", from, to);
+                let filled = self.fill_blocks(contract, from, to).await?;
+                Ok(format!(
+                    "Filled {} block(s) between {} and {}",
+                    filled, from, to
+                ))
+            }
+            AdminCommand::ApproveCatchUp => {
+                self.catch_up_approved.store(true, Ordering::Relaxed);
+                let message = match self.config.max_catch_up {
+                    Some(maximum) => format!(
+                        "Catch-up approved: blocks are posted past the {}-block limit until caught up",
+                        maximum
+                    ),
+                    None => "Catch-up approved; MAX_CATCH_UP_BLOCKS isn't set, so nothing waits on it"
+                        .to_string(),
+                };
+                info!("🔑 {}", message);
+                Ok(message)
+            }
+            AdminCommand::RotateNode { url } => {
+                anyhow::ensure!(
+                    self.monero_nodes.len() > 1,
+                    "Only one Monero node is configured"
+                );
+                let mut active = self.active_node.lock().expect("active node poisoned");
+                let next = match url {
+                    Some(url) => self
+                        .monero_nodes
+                        .iter()
+                        .position(|node| {
+                            node.rpc_url().trim_end_matches('/') == url.trim_end_matches('/')
+                        })
+                        .with_context(|| format!("{} is not a configured Monero node", url))?,
+                    None => (*active + 1) % self.monero_nodes.len(),
+                };
+                *active = next;
+                let url = self.monero_nodes[next].rpc_url();
+                info!("🔑 Following Monero node {}", url);
+                Ok(format!(
+                    "Following {} while its chain is as heavy as the others",
+                    url
+                ))
+            }
+            AdminCommand::Drain => anyhow::bail!("Drain is handled by the poll loop"),
+        }
+    }
+
+    /// Re-read the config file and apply the settings that can change at
+    /// runtime. Returns the new poll interval if it changed.
+    fn reload_config(&self, current: &mut Tunables) -> Option<Duration> {
+        let path = settings::path()?;
+        let file = match FileSettings::read(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("⚠️  Ignoring config file change: {:#}", e);
+                return None;
+            }
+        };
+        let previous = settings::replace(file.clone()).unwrap_or_default();
+        let changed = previous.changed(&file);
+        if changed.is_empty() {
+            return None;
+        }
+        let tunables = match Tunables::from_env() {
+            Ok(tunables) => tunables,
+            Err(e) => {
+                settings::replace(previous);
+                warn!("⚠️  Ignoring config file change: {:#}", e);
+                return None;
+            }
+        };
+
+        let restart: Vec<_> = changed
+            .iter()
+            .filter(|key| !reload::is_reloadable(key))
+            .map(String::as_str)
+            .collect();
+        if !restart.is_empty() {
+            warn!(
+                "⚠️  Config file changed {}; restart the oracle to apply",
+                restart.join(", ")
+            );
+        }
+
+        if tunables.log_level != current.log_level {
+            match &self.log_filter {
+                Some(log_filter) => match log_filter(tunables.log_level) {
+                    Ok(()) => info!("🔧 Log level now {}", tunables.log_level),
+                    Err(e) => warn!("⚠️  Failed to change log level: {:#}", e),
+                },
+                None => warn!("⚠️  LOG_LEVEL changed; restart the oracle to apply"),
+            }
+        }
+        if tunables.alerts != current.alerts {
+            self.alerts.set_config(tunables.alerts.clone());
+            info!("🔧 Alert settings updated");
+        }
+        if tunables.gas != current.gas {
+            *self.gas.write().expect("gas config poisoned") = tunables.gas.clone();
+            info!(
+                "🔧 Gas strategy now {} (stuck after {}s, up to {} bumps)",
+                tunables.gas.strategy,
+                tunables.gas.stuck_timeout.as_secs(),
+                tunables.gas.max_bumps
+            );
+        }
+        let period = (tunables.poll_interval_secs != current.poll_interval_secs).then(|| {
+            info!("🔧 Poll interval now {}s", tunables.poll_interval_secs);
+            Duration::from_secs(tunables.poll_interval_secs)
+        });
+        *current = tunables;
+        period
+    }
+
+    /// Apply a contract event; returns whether to poll right away
+    fn on_contract_event(&self, event: ContractEvent) -> bool {
+        match event {
+            ContractEvent::BlockPosted { height, block_hash } => {
+                let mut status = self.status.write().expect("status lock poisoned");
+                if status.contract_latest_block < Some(height) {
+                    status.contract_latest_block = Some(height);
+                    metrics::get().contract_latest_block.set(height as i64);
+                }
+                info!("⛓️  Block {} ({}) posted on-chain", height, block_hash);
+                false
+            }
+            ContractEvent::BlocksRolledBack { from_height } => {
+                warn!(
+                    "⚠️  Contract rolled back from block {}; re-syncing",
+                    from_height
+                );
+                let latest = from_height.saturating_sub(1);
+                self.status
+                    .write()
+                    .expect("status lock poisoned")
+                    .contract_latest_block = Some(latest);
+                metrics::get().contract_latest_block.set(latest as i64);
+                true
+            }
+            // The poll switches keys or goes read-only as needed
+            ContractEvent::OracleTransferred { previous, new } => {
+                info!("👤 Oracle role transferred from {} to {}", previous, new);
+                true
+            }
+            // The poll reads `paused()` and holds or resumes posting
+            ContractEvent::Paused { by } => {
+                warn!("⏸️  Bridge contract paused by {}", by);
+                true
+            }
+            ContractEvent::Unpaused { by } => {
+                info!("▶️  Bridge contract unpaused by {}", by);
+                true
+            }
+        }
+    }
+
+    /// With `MULTISIG_PEERS`, refuse to start unless the bridge wallet is a
+    /// finished multisig wallet with enough co-signers, and give up signing
+    /// rounds a previous run left unfinished: their withdrawals were never
+    /// signed, and go into new rounds
+    async fn check_multisig_wallet(&self) -> Result<()> {
+        let (Some(coordinator), Some(wallet)) = (&self.multisig, &self.wallet) else {
+            if self.config.multisig.is_some() {
+                info!("   Multisig: co-signing at /multisig/sign; payouts are left to the coordinator");
+            }
+            return Ok(());
+        };
+        let status = coordinator.check(wallet).await?;
+        info!(
+            "   Multisig: {}-of-{} bridge wallet, co-signers {}",
+            status.threshold,
+            status.total,
+            self.config
+                .multisig
+                .as_ref()
+                .map(|multisig| multisig.peers.join(", "))
+                .unwrap_or_default()
+        );
+        let interrupted = self
+            .db
+            .fail_open_multisig_rounds("Interrupted by a restart")?;
+        if interrupted > 0 {
+            warn!(
+                "   ⚠️  Gave up {} unfinished multisig signing round(s)",
+                interrupted
+            );
+        }
+        Ok(())
+    }
+
+    /// Refuse to start against a monerod or wallet on a different network
+    /// than `MONERO_NETWORK`: addresses and withdrawals would be wrong
+    async fn check_monero_network(&self) -> Result<()> {
+        for (index, node) in self.monero_nodes.iter().enumerate() {
+            let info = match node.get_info().await {
+                Ok(info) => info,
+                // A fallback that is down now may be back by the next poll
+                Err(e) if index > 0 => {
+                    warn!("   ⚠️  Monero node {} unavailable: {:#}", node.rpc_url(), e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let daemon = info.network();
+            if daemon != self.config.network {
+                anyhow::bail!(
+                    "monerod at {} is on {}, but MONERO_NETWORK is {}",
+                    node.rpc_url(),
+                    daemon,
+                    self.config.network
+                );
+            }
+            if info.restricted {
+                info!(
+                    "   monerod RPC at {} is restricted; refused calls are skipped where possible",
+                    node.rpc_url()
+                );
+            }
+        }
+
+        if let Some(wallet) = &self.wallet {
+            let address = wallet.get_address(self.config.subaddress_account).await?;
+            let network = MoneroAddress::parse(&address)
+                .context("monero-wallet-rpc returned an invalid address")?
+                .network;
+            if network != self.config.network {
+                anyhow::bail!(
+                    "monero-wallet-rpc has a {} wallet open, but MONERO_NETWORK is {}",
+                    network,
+                    self.config.network
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Settle operations a previous run journaled but never saw through. An
+    /// Unichain action with a successful receipt for one of its transactions
+    /// is complete; one without is left pending and its next attempt finds
+    /// its transactions again. A payout is complete once its withdrawals are
+    /// broadcast, and abandoned once none of them is left signed and waiting to
+    /// be relayed.
+    async fn resume_operations<T, P>(&self, provider: &P) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T>,
+    {
+        let pending = self.db.pending_operations()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        info!("\n📒 Resuming {} journaled operation(s)...", pending.len());
+
+        let mut completed = 0;
+        let mut abandoned = 0;
+        for operation in pending {
+            match operation.intent()? {
+                Intent::Payout { burn_ids } => {
+                    let mut statuses = Vec::new();
+                    for &burn_id in &burn_ids {
+                        let withdrawal = self
+                            .db
+                            .withdrawal(burn_id)?
+                            .with_context(|| format!("No withdrawal for burn #{}", burn_id))?;
+                        statuses.push(withdrawal.status);
+                    }
+                    if statuses.iter().all(|status| {
+                        matches!(
+                            status,
+                            WithdrawalStatus::Broadcast
+                                | WithdrawalStatus::ConfirmedXmr
+                                | WithdrawalStatus::ConfirmedEvm
+                        )
+                    }) {
+                        self.db
+                            .complete_operation(&operation.key, operation.sent.last().copied())?;
+                        completed += 1;
+                    } else if !statuses.contains(&WithdrawalStatus::Signed) {
+                        self.db
+                            .abandon_operation(&operation.key, "Payout never relayed")?;
+                        abandoned += 1;
+                    }
+                }
+                _ => {
+                    for tx_hash in operation.candidate_txs() {
+                        let receipt = eth::find_receipt(provider, &[tx_hash]).await?;
+                        if receipt.is_some_and(|receipt| receipt.status()) {
+                            self.db.complete_operation(&operation.key, Some(tx_hash))?;
+                            completed += 1;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        info!(
+            "   {} completed, {} abandoned, {} left for their next attempt",
+            completed,
+            abandoned,
+            self.db.pending_operations()?.len()
+        );
+        Ok(())
+    }
+
+    /// Reload the reorg window from the local database and cross-check the
+    /// stored checkpoint against the contract
+    async fn resume_from_checkpoint<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        info!("\n💾 Database: {}", self.config.database_path);
+
+        let checkpoint = match self.db.checkpoint()? {
+            Some(height) => height,
+            None => {
+                info!("   No stored checkpoint, starting fresh");
+                return Ok(());
+            }
+        };
+
+        let recent = self.db.recent_blocks(self.config.reorg_tracked_blocks)?;
+        {
+            let mut detector = self.reorg_detector.lock().expect("reorg detector poisoned");
+            for record in &recent {
+                detector.record(record.height, record.block_hash);
+            }
+        }
+
+        info!("   Stored checkpoint: {}", checkpoint);
+        info!("   Resumed reorg tracking for {} block(s)", recent.len());
+
+        let latest_posted = contract.latestMoneroBlock().call().await?.latestMoneroBlock;
+        let latest_posted_u64: u64 = latest_posted.try_into().unwrap_or(0);
+
+        if checkpoint > latest_posted_u64 {
+            warn!(
+                "   ⚠️  Stored checkpoint {} is ahead of the contract ({}); marking the difference orphaned",
+                checkpoint, latest_posted_u64
+            );
+            self.db.mark_orphaned(latest_posted_u64 + 1)?;
+            self.reorg_detector
+                .lock()
+                .expect("reorg detector poisoned")
+                .rollback(latest_posted_u64 + 1);
+        } else if checkpoint < latest_posted_u64 {
+            warn!(
+                "   ⚠️  Contract is at block {} but the database stops at {}; blocks in between were posted elsewhere",
+                latest_posted_u64, checkpoint
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn poll<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        attester: Option<&Attester>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        info!(
+            "\n[{}] 🔍 Checking Monero blockchain...",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        );
+
+        // Get latest Monero block header
+        let header = self.select_node().await?;
+        let tip_height = header.height;
+
+        // Only blocks with at least MIN_CONFIRMATIONS blocks on top are posted
+        let confirmed_height = tip_height.saturating_sub(self.config.min_confirmations);
+
+        info!("   Latest Monero block: {}", tip_height);
+        info!("   Hash: 0x{}", header.hash);
+        info!(
+            "   Confirmed block: {} ({} confirmations)",
+            confirmed_height, self.config.min_confirmations
+        );
+        {
+            let mut status = self.status.write().expect("status lock poisoned");
+            status.monero_tip = Some(tip_height);
+            status.monero_confirmed_height = Some(confirmed_height);
+        }
+        metrics::get().monero_tip_height.set(tip_height as i64);
+
+        let is_oracle = self.check_oracle_role(contract).await?;
+        // A standby instance sends nothing and only indexes the blocks the
+        // leader posts, like an oracle without the role
+        let leading = (is_oracle || self.config.mode == OracleMode::Submitter)
+            && self.hold_lease(contract).await?;
+        let is_oracle = is_oracle && leading;
+        // Proposals to a Safe cost the oracle key no gas
+        let low_balance = self.refresh_wallet_balance(contract).await && self.config.safe.is_none();
+        let paused_by_operator = self
+            .status
+            .read()
+            .expect("status lock poisoned")
+            .paused_by_operator;
+        let over_gas_budget = self.check_gas_budget().await?;
+        let contract_paused = self.check_contract_paused(contract).await?;
+        self.check_tree_versions(contract).await?;
+        // Extra contracts are paused or not on their own, as the old one
+        // in a migration may be
+        let sends_paused = low_balance || paused_by_operator || over_gas_budget;
+        let paused = sends_paused || contract_paused;
+
+        // Re-check recently posted blocks before extending the chain; a
+        // rollback needs gas and the oracle role, so this waits while paused
+        if !paused && is_oracle {
+            self.check_for_reorg(contract, tip_height).await?;
+            self.reverify_postings(contract, attester).await?;
+        }
+
+        // Get last posted block from contract
+        let latest_posted = metrics::get()
+            .observe_rpc(
+                "unichain",
+                "latestMoneroBlock",
+                self.config
+                    .rpc_retry
+                    .retry("unichain", "latestMoneroBlock", || async {
+                        Ok(contract.latestMoneroBlock().call().await?)
+                    })
+                    .await,
+            )?
+            .latestMoneroBlock;
+        let latest_posted_u64: u64 = latest_posted.try_into().unwrap_or(0);
+
+        info!("   Last posted block: {}", latest_posted_u64);
+        self.status
+            .write()
+            .expect("status lock poisoned")
+            .contract_latest_block = Some(latest_posted_u64);
+        metrics::get()
+            .contract_latest_block
+            .set(latest_posted_u64 as i64);
+        let lag = tip_height.saturating_sub(latest_posted_u64);
+        metrics::get().lag_blocks.set(lag as i64);
+        if lag > self.config.health.max_lag {
+            self.alerts
+                .notify(Alert::Lag {
+                    lag,
+                    maximum: self.config.health.max_lag,
+                })
+                .await;
+        } else {
+            self.alerts.clear("lag");
+        }
+
+        if let Some(gossip) = attester.and_then(|attester| attester.gossip.as_ref()) {
+            gossip.set_signers(contract.getSigners().call().await?._0);
+        }
+        if let (OracleMode::Signer, Some(attester)) = (self.config.mode, attester) {
+            return self
+                .attest_blocks(attester, latest_posted_u64 + 1, confirmed_height)
+                .await;
+        }
+
+        if is_oracle && self.safe.get().is_some() {
+            self.sync_safe_proposals(contract, latest_posted_u64)
+                .await?;
+        }
+
+        // Post missing confirmed blocks, all of them while deposits are
+        // pending. Posting is permissionless in submitter mode; a single
+        // oracle without the role only indexes.
+        let cadence = self.config.cadence;
+        let active = cadence.is_active(!self.db.pending_deposits()?.is_empty());
+        let target = cadence.target(confirmed_height, active);
+        let behind = confirmed_height.saturating_sub(latest_posted_u64);
+        let catch_up_blocked = self.catch_up_blocked(behind);
+        self.status
+            .write()
+            .expect("status lock poisoned")
+            .catch_up_blocked = catch_up_blocked.then_some(behind);
+        if !catch_up_blocked {
+            self.alerts.clear("catch_up_blocked");
+        }
+        if !leading || (!is_oracle && self.config.mode == OracleMode::Single) {
+            self.index_posted_blocks(contract, latest_posted_u64.min(confirmed_height))
+                .await?;
+        } else if paused {
+            warn!(
+                "   ⏸️  Posting paused {}; {} confirmed block(s) waiting",
+                if paused_by_operator {
+                    "by an operator"
+                } else if over_gas_budget {
+                    "over the gas budget"
+                } else if contract_paused {
+                    "while the contract is paused"
+                } else {
+                    "for low balance"
+                },
+                confirmed_height.saturating_sub(latest_posted_u64)
+            );
+        } else if let (true, Some(maximum)) = (catch_up_blocked, self.config.max_catch_up) {
+            warn!(
+                "   🛑 {} confirmed block(s) behind, more than MAX_CATCH_UP_BLOCKS ({}); \
+                 check the contract, then approve with `run --force-backfill` or \
+                 POST /admin/approve-catch-up",
+                behind, maximum
+            );
+            self.alerts
+                .notify(Alert::CatchUpBlocked { behind, maximum })
+                .await;
+        } else if target > latest_posted_u64 {
+            let blocks_to_post = target - latest_posted_u64;
+            info!("   📊 {} new confirmed block(s) detected!", blocks_to_post);
+
+            let mut parent = eth::get_block(contract, latest_posted_u64)
+                .await?
+                .map(|stored| stored.block_hash);
+            let mut blocks = pin!(self.process_blocks(latest_posted_u64 + 1, target));
+            while let Some(block) = blocks.next().await {
+                let block = block?;
+                reorg::check_parent(block.height, block.prev_hash, parent)?;
+                parent = Some(block.block_hash);
+
+                if !cadence.should_post(block.height, target, !block.deposits.is_empty(), active) {
+                    self.record_spends(&block.spends)?;
+                    continue;
+                }
+
+                let signatures = match attester {
+                    Some(attester) => {
+                        match self.collect_signatures(contract, attester, &block).await? {
+                            Some(signatures) => Some(signatures),
+                            // Later blocks can't be posted before this one
+                            None => break,
+                        }
+                    }
+                    None => None,
+                };
+
+                if self.safe.get().is_some() {
+                    let pending = self.db.safe_proposals()?;
+                    if pending.len() >= MAX_PENDING_SAFE_PROPOSALS
+                        && !pending.iter().any(|p| p.height == block.height)
+                    {
+                        info!(
+                            "   ⏳ {} posting(s) awaiting Safe confirmations; proposing more once they execute",
+                            pending.len()
+                        );
+                        break;
+                    }
+                }
+
+                // Post to contract
+                self.post_block(
+                    contract,
+                    block.height,
+                    block.block_hash,
+                    block.tx_merkle_root,
+                    block.output_merkle_root,
+                    signatures,
+                )
+                .await?;
+                self.db.record_cumulative_difficulty(
+                    block.height,
+                    block.block_hash,
+                    block.cumulative_difficulty,
+                )?;
+
+                self.index_outputs(&block)?;
+                self.record_deposits(&block.deposits).await?;
+                self.record_spends(&block.spends)?;
+            }
+        } else if confirmed_height > latest_posted_u64 {
+            info!(
+                "   💤 No deposits pending; posting again at block {}",
+                target + cadence.idle_interval
+            );
+        } else if tip_height > latest_posted_u64 {
+            info!(
+                "   ⏳ {} block(s) awaiting confirmations",
+                tip_height - latest_posted_u64
+            );
+        } else {
+            info!("   ✅ Already up to date");
+        }
+
+        // A submitter's key is a signer, which may send heartbeats too
+        if let (Some(interval), true, false) = (
+            self.config.heartbeat_interval,
+            leading && (is_oracle || self.config.mode == OracleMode::Submitter),
+            paused,
+        ) {
+            if let Err(e) = self.send_heartbeat(contract, interval, tip_height).await {
+                warn!("   ⚠️  Heartbeat failed: {:#}", e);
+            }
+        }
+
+        if !sends_paused && leading && !self.config.extra_bridge_addresses.is_empty() {
+            self.sync_extra_contracts(contract, tip_height, confirmed_height)
+                .await;
+        }
+
+        if let (true, Some(keys)) = (self.config.watch_mempool, &self.config.view_keys) {
+            match self.scan_mempool(keys).await {
+                Err(e) if is_method_unavailable(&e) => {
+                    warn!("   ⚠️  Skipping mempool scan: {:#}", e)
+                }
+                result => result?,
+            }
+        }
+
+        if self.config.auto_mint && !paused && is_oracle {
+            self.mint_deposits(contract).await?;
+        }
+
+        if self.config.watch_withdrawals {
+            self.sync_withdrawals(contract).await?;
+        }
+
+        // Payouts wait with the confirmations, so the new oracle can't
+        // pay a burn this one already paid without recording it
+        // A co-signer's wallet only signs the coordinator's payouts. A
+        // paused contract holds payouts too: the pause may be over the
+        // burns themselves.
+        let pays_out = self
+            .config
+            .multisig
+            .as_ref()
+            .is_none_or(MultisigConfig::is_coordinator);
+        if let (Some(wallet), true, true, false) = (
+            &self.wallet,
+            pays_out,
+            is_oracle,
+            paused_by_operator || contract_paused,
+        ) {
+            self.pay_withdrawals(wallet).await?;
+            self.check_payout_confirmations(wallet, tip_height).await?;
+            if !paused {
+                self.confirm_withdrawals(contract).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// One watcher poll: check blocks the contract stored since the last
+    /// one, from `next` (the contract's first block if `None`) up to the
+    /// confirmed height, and recheck blocks that differed before. `mismatched` holds those; a difference is alerted
+    /// on once, and logged as resolved when the block is rolled back or
+    /// corrected.
+    async fn watch_poll<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        next: &mut Option<u64>,
+        mismatched: &mut BTreeSet<u64>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        info!(
+            "\n[{}] 👁️  Checking posted blocks...",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        );
+
+        let tip_height = self.select_node().await?.height;
+        let confirmed_height = tip_height.saturating_sub(self.config.min_confirmations);
+        let latest_posted: u64 = metrics::get()
+            .observe_rpc(
+                "unichain",
+                "latestMoneroBlock",
+                self.config
+                    .rpc_retry
+                    .retry("unichain", "latestMoneroBlock", || async {
+                        Ok(contract.latestMoneroBlock().call().await?)
+                    })
+                    .await,
+            )?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        info!(
+            "   Monero tip {}, contract at {}",
+            tip_height, latest_posted
+        );
+        {
+            let mut status = self.status.write().expect("status lock poisoned");
+            status.monero_tip = Some(tip_height);
+            status.monero_confirmed_height = Some(confirmed_height);
+            status.contract_latest_block = Some(latest_posted);
+        }
+        metrics::get().monero_tip_height.set(tip_height as i64);
+        metrics::get()
+            .contract_latest_block
+            .set(latest_posted as i64);
+        metrics::get()
+            .lag_blocks
+            .set(tip_height.saturating_sub(latest_posted) as i64);
+
+        if latest_posted == 0 {
+            info!("   💤 Nothing posted yet");
+            return Ok(());
+        }
+        let next = next.get_or_insert(latest_posted);
+        // Blocks reposted after a rollback are checked again
+        if latest_posted + 1 < *next {
+            info!(
+                "   ↩️  Contract rolled back to {}; checking again from there",
+                latest_posted
+            );
+            *next = latest_posted + 1;
+        }
+
+        for height in mismatched.clone() {
+            let block = self.process_block(height).await?;
+            if self.contract_mismatches(contract, &block).await?.is_empty() {
+                info!("   ✅ Contract data for block {} matches again", height);
+                mismatched.remove(&height);
+            }
+        }
+
+        // Unconfirmed blocks may still be reorganized away on the node
+        let to = latest_posted.min(confirmed_height);
+        if *next > to {
+            info!("   ✅ No newly posted blocks to check");
+        } else {
+            let mut blocks = pin!(self.process_blocks(*next, to));
+            while let Some(block) = blocks.next().await {
+                let block = block?;
+                let fields = self.contract_mismatches(contract, &block).await?;
+                if !fields.is_empty() && mismatched.insert(block.height) {
+                    error!(
+                        "🚨 Contract data for block {} differs from the computed block: {}",
+                        block.height,
+                        fields.join(", ")
+                    );
+                    metrics::get().root_mismatches.inc();
+                    self.alerts
+                        .notify(Alert::RootMismatch {
+                            height: block.height,
+                            fields: fields.join(", "),
+                        })
+                        .await;
+                }
+                *next = block.height + 1;
+                metrics::get().watched_block.set(block.height as i64);
+            }
+            info!("   ✅ Checked blocks up to {}", to);
+        }
+
+        metrics::get()
+            .mismatched_blocks
+            .set(mismatched.len() as i64);
+        if !mismatched.is_empty() {
+            warn!(
+                "   ⚠️  {} posted block(s) differ from the chain: {:?}",
+                mismatched.len(),
+                mismatched
+            );
+        }
+        Ok(())
+    }
+
+    /// Fields of `block` the contract stores differently; none if it
+    /// doesn't store the block at all (skipped in checkpoint mode, or
+    /// rolled back)
+    async fn contract_mismatches<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        block: &ProcessedBlock,
+    ) -> Result<Vec<&'static str>>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let computed = StoredBlock {
+            block_hash: block.block_hash,
+            tx_merkle_root: block.tx_merkle_root,
+            output_merkle_root: block.output_merkle_root,
+        };
+        Ok(eth::get_block(contract, block.height)
+            .await?
+            .map(|stored| stored.mismatches(&computed))
+            .unwrap_or_default())
+    }
+
+    /// Keys this oracle can send with, the primary first
+    fn oracle_keys(&self) -> &[Address] {
+        self.oracle_keys.get().map_or(&[], Vec::as_slice)
+    }
+
+    /// Follow the contract's oracle role: switch to whichever configured key
+    /// holds it, or drop to read-only when none does. Returns whether this
+    /// oracle can act for the contract.
+    async fn check_oracle_role<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<bool>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        if self.config.mode == OracleMode::Signer {
+            return Ok(true);
+        }
+
+        let contract_oracle = metrics::get()
+            .observe_rpc(
+                "unichain",
+                "oracle",
+                self.config
+                    .rpc_retry
+                    .retry("unichain", "oracle", || async {
+                        Ok(contract.oracle().call().await?)
+                    })
+                    .await,
+            )?
+            .oracle;
+
+        if let Some(safe) = &self.config.safe {
+            return Ok(self.follow_safe_role(contract_oracle, safe.address).await);
+        }
+
+        let (active, was_read_only) = {
+            let status = self.status.read().expect("status lock poisoned");
+            (status.oracle_address, status.read_only)
+        };
+        let Some(active) = active else {
+            return Ok(true);
+        };
+
+        if contract_oracle != active && self.oracle_keys().contains(&contract_oracle) {
+            warn!(
+                "   👤 Oracle role moved from {} to {}; sending with that key",
+                active, contract_oracle
+            );
+            self.status
+                .write()
+                .expect("status lock poisoned")
+                .oracle_address = Some(contract_oracle);
+            // Pending nonces belong to the previous key
+            *self.nonces.lock().expect("nonce tracker poisoned") = NonceTracker::default();
+        } else if contract_oracle != active {
+            if !was_read_only {
+                error!(
+                    "❌ Oracle role moved to {}, which isn't a configured key; read-only until it returns",
+                    contract_oracle
+                );
+                self.set_read_only(true);
+                self.alerts
+                    .notify(Alert::RoleMismatch {
+                        contract_oracle,
+                        wallet: active,
+                    })
+                    .await;
+            }
+            return Ok(false);
+        }
+
+        if was_read_only {
+            info!("   👤 {} holds the oracle role again", contract_oracle);
+        }
+        self.set_read_only(false);
+        self.alerts.clear("role_mismatch");
+        Ok(true)
+    }
+
+    /// With `SAFE_ADDRESS`, act only while the Safe holds the oracle role
+    async fn follow_safe_role(&self, contract_oracle: Address, safe: Address) -> bool {
+        let was_read_only = self.status.read().expect("status lock poisoned").read_only;
+        if contract_oracle != safe {
+            if !was_read_only {
+                error!(
+                    "❌ Oracle role moved from the Safe to {}; read-only until it returns",
+                    contract_oracle
+                );
+                self.set_read_only(true);
+                self.alerts
+                    .notify(Alert::RoleMismatch {
+                        contract_oracle,
+                        wallet: safe,
+                    })
+                    .await;
+            }
+            return false;
+        }
+
+        if was_read_only {
+            info!("   👤 Safe {} holds the oracle role again", safe);
+        }
+        self.set_read_only(false);
+        self.alerts.clear("role_mismatch");
+        true
+    }
+
+    fn set_read_only(&self, read_only: bool) {
+        self.status.write().expect("status lock poisoned").read_only = read_only;
+        metrics::get().read_only.set(read_only as i64);
+    }
+
+    /// With `LEASE_INSTANCE_ID`, take or renew the leader lease as due, and
+    /// return whether this instance may send; always true without one
+    async fn hold_lease<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<bool>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let Some(lease) = &self.lease else {
+            return Ok(true);
+        };
+        let was_leading = lease.is_held();
+        let read_at = std::time::Instant::now();
+        let current = contract.getLease().call().await?;
+        let remaining = Duration::from_secs(current.remaining.try_into().unwrap_or(u64::MAX));
+
+        match lease.decide(current.holder, remaining) {
+            Decision::Lead => lease.hold(read_at, remaining),
+            Decision::Standby { holder, remaining } => {
+                if was_leading {
+                    warn!("   👑 Lost the lease to {}", holder);
+                }
+                info!(
+                    "   💤 Standby: another instance holds the lease for {}s",
+                    remaining.as_secs()
+                );
+                lease.stand_by(remaining);
+            }
+            Decision::Acquire { takeover } => {
+                let sent_at = std::time::Instant::now();
+                let call =
+                    contract.acquireLease(lease.holder(), U256::from(lease.duration().as_secs()));
+                let result = self
+                    .send_tx(contract.provider(), call, CostKind::Lease, |_| {})
+                    .await
+                    .and_then(|receipt| {
+                        anyhow::ensure!(
+                            receipt.status(),
+                            "acquireLease reverted in {}",
+                            receipt.transaction_hash
+                        );
+                        Ok(())
+                    });
+                match result {
+                    Ok(()) => {
+                        lease.hold(sent_at, lease.duration());
+                        if takeover {
+                            self.take_over(lease).await?;
+                        } else if !was_leading {
+                            info!("   👑 Instance '{}' holds the lease", lease.instance());
+                        }
+                    }
+                    Err(e) => {
+                        warn!("   ⚠️  Couldn't take the lease: {:#}", e);
+                        // A renewal that failed leaves what is left of the lease
+                        if current.holder == lease.holder() {
+                            lease.hold(read_at, remaining);
+                        } else {
+                            lease.stand_by(remaining);
+                        }
+                    }
+                }
+            }
+        }
+
+        let leading = lease.is_held();
+        self.status.write().expect("status lock poisoned").standby = !leading;
+        metrics::get().lease_held.set(leading as i64);
+        Ok(leading)
+    }
+
+    /// After taking the lease from another instance, hold the withdrawals
+    /// queued here for review: whether the previous leader paid them is in
+    /// its database, not this one
+    async fn take_over(&self, lease: &Lease) -> Result<()> {
+        let mut withdrawals_held = 0;
+        if self.wallet.is_some() {
+            for withdrawal in self.db.withdrawals_with_status(WithdrawalStatus::Queued)? {
+                self.db.set_withdrawal_status(
+                    withdrawal.burn_id,
+                    WithdrawalStatus::Failed,
+                    Some("Queued before a leader takeover; requeue once the previous leader is known not to have paid it"),
+                )?;
+                withdrawals_held += 1;
+            }
+        }
+        warn!(
+            "   👑 Instance '{}' took over the lease; {} queued withdrawal(s) held for review",
+            lease.instance(),
+            withdrawals_held
+        );
+        self.alerts
+            .notify(Alert::LeaseTakeover {
+                instance: lease.instance().to_string(),
+                withdrawals_held,
+            })
+            .await;
+        Ok(())
+    }
+
+    /// Give up the lease on the way out, so a standby takes over at its
+    /// next poll rather than when the lease runs out
+    async fn release_lease<T, P>(&self, contract: &WrappedMonero::WrappedMoneroInstance<T, P>)
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let Some(lease) = self.lease.as_ref().filter(|lease| lease.is_held()) else {
+            return;
+        };
+        let call = contract.releaseLease(lease.holder());
+        match self
+            .send_tx(contract.provider(), call, CostKind::Lease, |_| {})
+            .await
+        {
+            Ok(receipt) if receipt.status() => info!("👑 Released the lease"),
+            Ok(receipt) => warn!("⚠️  releaseLease reverted in {}", receipt.transaction_hash),
+            Err(e) => warn!("⚠️  Couldn't release the lease: {:#}", e),
+        }
+    }
+
+    /// Read-only mode: rescan blocks the current oracle posted, up to `to`,
+    /// into the local database and check each against the computed roots
+    async fn index_posted_blocks<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        to: u64,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let indexed = *self
+            .indexed_through
+            .lock()
+            .expect("indexed height poisoned");
+        let from = match indexed {
+            Some(height) => height + 1,
+            None => self.db.checkpoint()?.map_or(to + 1, |height| height + 1),
+        };
+        if from > to {
+            info!("   👁️  Read-only: no newly posted blocks to index");
+            return Ok(());
+        }
+
+        info!(
+            "   👁️  Read-only: indexing posted blocks {} to {}",
+            from, to
+        );
+        let mut blocks = pin!(self.process_blocks(from, to));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            let expected = StoredBlock {
+                block_hash: block.block_hash,
+                tx_merkle_root: block.tx_merkle_root,
+                output_merkle_root: block.output_merkle_root,
+            };
+            self.verify_stored_block(contract, block.height, &expected)
+                .await;
+            self.index_outputs(&block)?;
+            self.record_deposits(&block.deposits).await?;
+            self.record_spends(&block.spends)?;
+            *self
+                .indexed_through
+                .lock()
+                .expect("indexed height poisoned") = Some(block.height);
+        }
+        Ok(())
+    }
+
+    /// Pay queued withdrawals, oldest first, from the bridge wallet, up to
+    /// `WITHDRAWAL_BATCH_SIZE` of them per Monero transaction. Each transfer
+    /// is recorded before it is relayed, so a crash in between leaves its
+    /// withdrawals `signed` and it is relayed again on the next poll.
+    async fn pay_withdrawals(&self, wallet: &MoneroWalletClient) -> Result<()> {
+        if let Some(lease) = &self.lease {
+            lease.ensure_held()?;
+        }
+        // Withdrawals paid together share a transaction; relay it once
+        let mut relayed = HashSet::new();
+        for withdrawal in self.db.withdrawals_due(WithdrawalStatus::Signed)? {
+            let Some(tx_hash) = withdrawal.xmr_tx_hash else {
+                anyhow::bail!(
+                    "Signed withdrawal #{} has no recorded transaction",
+                    withdrawal.burn_id
+                );
+            };
+            if relayed.insert(tx_hash) {
+                self.relay_payout(wallet, tx_hash).await?;
+            }
+        }
+
+        let tracked_unspent = self.refresh_bridge_outputs(wallet).await?;
+
+        let queued = self.db.withdrawals_due(WithdrawalStatus::Queued)?;
+        let account = self.config.subaddress_account;
+        let balance = wallet.get_balance(account).await?;
+        metrics::get()
+            .bridge_balance_xmr
+            .set(balance.unlocked_balance as f64 / 1e12);
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        // Read once per round, so every batch paid in it is quoted from the
+        // same estimate
+        let estimate = if self.config.withdrawal_deduct_fee {
+            Some(self.monero().get_fee_estimate().await?)
+        } else {
+            None
+        };
+        // Read when the first bridge fee of the round is quoted
+        let mut rate = None;
+
+        // Outputs the wallet thinks are unspent may already be spent on-chain
+        // if it's lagging; never plan payouts beyond what is provably unspent
+        let mut available = match tracked_unspent {
+            Some(unspent) => balance.unlocked_balance.min(unspent),
+            None => balance.unlocked_balance,
+        };
+        let batch_size = self.config.withdrawal_batch_size as usize;
+        let mut queued = queued.into_iter().peekable();
+        while let Some(next) = queued.peek() {
+            if next.amount > available {
+                warn!(
+                    "   ⚠️  Burn #{} needs {} XMR but only {} XMR is unlocked; waiting",
+                    next.burn_id,
+                    format_xmr(next.amount),
+                    format_xmr(available)
+                );
+                break;
+            }
+
+            let mut batch = Vec::new();
+            let mut total = 0;
+            while let Some(withdrawal) =
+                queued.next_if(|w| batch.len() < batch_size && total + w.amount <= available)
+            {
+                total += withdrawal.amount;
+                batch.push(withdrawal);
+            }
+
+            // A partial batch waits for more withdrawals to join it
+            let last_payout = *self.last_payout.lock().expect("last payout poisoned");
+            if batch.len() < batch_size
+                && last_payout
+                    .is_some_and(|at| at.elapsed() < self.config.withdrawal_batch_interval)
+            {
+                info!(
+                    "   ⏳ Holding {} withdrawal(s) for the next batch",
+                    batch.len()
+                );
+                break;
+            }
+
+            let spent = self
+                .pay_batch(wallet, batch, estimate.as_ref(), &mut rate)
+                .await?;
+            available = available.saturating_sub(spent);
+        }
+
+        Ok(())
+    }
+
+    /// Sign one transaction paying every withdrawal in `batch` and relay it;
+    /// returns what it took from the wallet. With a fee estimate, each
+    /// recipient is paid net of its share of the quoted fee, and withdrawals
+    /// too small to cover it are left out. The bridge fee, if any, comes off
+    /// each payout as well.
+    async fn pay_batch(
+        &self,
+        wallet: &MoneroWalletClient,
+        batch: Vec<Withdrawal>,
+        estimate: Option<&FeeEstimate>,
+        rate: &mut Option<Rate>,
+    ) -> Result<u64> {
+        let mut charges = HashMap::new();
+        let mut payable = Vec::with_capacity(batch.len());
+        for withdrawal in batch {
+            let reference = bridge_fee::withdrawal_reference(withdrawal.burn_id);
+            match self
+                .bridge_fee(FeeKind::Withdrawal, reference, withdrawal.amount, rate)
+                .await?
+            {
+                Some(charge) if charge.net().is_none() => {
+                    let e = anyhow::anyhow!(
+                        "{} XMR doesn't cover the {} bridge fee",
+                        format_xmr(withdrawal.amount),
+                        charge
+                    );
+                    self.withdrawal_failed(withdrawal.burn_id, "Bridge fee", &e)?;
+                    continue;
+                }
+                Some(charge) => {
+                    charges.insert(withdrawal.burn_id, charge);
+                }
+                None => {}
+            }
+            payable.push(withdrawal);
+        }
+        let mut batch = payable;
+        let bridge_fee = |withdrawal: &Withdrawal| {
+            charges
+                .get(&withdrawal.burn_id)
+                .map_or(0, |charge| charge.fee)
+        };
+
+        let priority = self.config.withdrawal_fee_priority;
+        let mut fee = 0;
+        if let Some(estimate) = estimate {
+            // Leaving one out raises everyone else's share, so quote again
+            // until every remaining withdrawal covers its share
+            loop {
+                if batch.is_empty() {
+                    return Ok(0);
+                }
+                let quote = fees::quote_batch(estimate, priority, batch.len() as u64);
+                let (covered, short): (Vec<_>, Vec<_>) = batch.into_iter().partition(|w| {
+                    quote
+                        .payout(w.amount)
+                        .and_then(|payout| payout.checked_sub(bridge_fee(w)))
+                        .is_some_and(|payout| payout > 0)
+                });
+                batch = covered;
+                if short.is_empty() {
+                    fee = quote.fee;
+                    break;
+                }
+                for withdrawal in short {
+                    let e = match charges.get(&withdrawal.burn_id) {
+                        Some(charge) => anyhow::anyhow!(
+                            "{} XMR doesn't cover the {} XMR network fee and the {} bridge fee",
+                            format_xmr(withdrawal.amount),
+                            format_xmr(quote.fee),
+                            charge
+                        ),
+                        None => anyhow::anyhow!(
+                            "{} XMR doesn't cover the {} XMR network fee",
+                            format_xmr(withdrawal.amount),
+                            format_xmr(quote.fee)
+                        ),
+                    };
+                    self.withdrawal_failed(withdrawal.burn_id, "Fee quote", &e)?;
+                }
+            }
+        }
+
+        let payouts: Vec<(u64, u64)> = batch
+            .iter()
+            .map(|withdrawal| {
+                (
+                    withdrawal.burn_id,
+                    withdrawal.amount - fee - bridge_fee(withdrawal),
+                )
+            })
+            .collect();
+        info!(
+            "\n💸 Paying {} withdrawal(s), {} XMR fee deducted from each",
+            batch.len(),
+            format_xmr(fee)
+        );
+        for (withdrawal, (_, amount)) in batch.iter().zip(&payouts) {
+            info!(
+                "   Burn #{}: {} XMR to {}",
+                withdrawal.burn_id,
+                format_xmr(*amount),
+                withdrawal.xmr_address
+            );
+            if let Some(charge) = charges.get(&withdrawal.burn_id) {
+                info!("      bridge fee {}", charge);
+                self.db.record_fee_charge(charge)?;
+            }
+        }
+
+        let intent = Intent::payout(batch.iter().map(|withdrawal| withdrawal.burn_id));
+        self.db.begin_operation(&intent)?;
+        let destinations: Vec<Destination<'_>> = batch
+            .iter()
+            .zip(&payouts)
+            .map(|(withdrawal, &(_, amount))| Destination {
+                amount,
+                address: &withdrawal.xmr_address,
+            })
+            .collect();
+        let purpose = Purpose::Payout {
+            burn_ids: batch.iter().map(|withdrawal| withdrawal.burn_id).collect(),
+        };
+        let transfer = match self.sign_transfer(wallet, &destinations, &purpose).await {
+            Ok(transfer) => transfer,
+            Err(e) => {
+                self.db
+                    .abandon_operation(&intent.key(), &format!("Transfer failed: {:#}", e))?;
+                for withdrawal in &batch {
+                    self.withdrawal_failed(withdrawal.burn_id, "Transfer", &e)?;
+                }
+                return Ok(0);
+            }
+        };
+        info!(
+            "   Signed {} (fee {} XMR)",
+            transfer.tx_hash,
+            format_xmr(transfer.fee)
+        );
+
+        self.db.record_withdrawals_signed(&payouts, &transfer)?;
+        for charge in batch
+            .iter()
+            .filter_map(|withdrawal| charges.get(&withdrawal.burn_id))
+        {
+            self.db
+                .collect_fee(FeeKind::Withdrawal, &charge.reference)?;
+        }
+        self.db
+            .record_operation_tx(&intent.key(), transfer.tx_hash)?;
+        *self.last_payout.lock().expect("last payout poisoned") = Some(Instant::now());
+        self.relay_payout(wallet, transfer.tx_hash).await?;
+        Ok(transfer.amount + transfer.fee)
+    }
+
+    /// Create and sign a transfer from the bridge wallet at
+    /// `WITHDRAWAL_FEE_PRIORITY` without broadcasting it. A multisig wallet
+    /// has its co-signers sign it first.
+    async fn sign_transfer(
+        &self,
+        wallet: &MoneroWalletClient,
+        destinations: &[Destination<'_>],
+        purpose: &Purpose,
+    ) -> Result<SignedTransfer> {
+        let account = self.config.subaddress_account;
+        let priority = self.config.withdrawal_fee_priority;
+        match &self.multisig {
+            Some(coordinator) => {
+                coordinator
+                    .transfer(wallet, &self.db, purpose, destinations, account, priority)
+                    .await
+            }
+            None => wallet.transfer(destinations, account, priority).await,
+        }
+    }
+
+    /// Broadcast a transfer signed by [`Self::sign_transfer`]
+    async fn relay_signed(&self, wallet: &MoneroWalletClient, tx_metadata: &str) -> Result<B256> {
+        match &self.multisig {
+            Some(_) => wallet.submit_multisig(tx_metadata).await,
+            None => wallet.relay_tx(tx_metadata).await,
+        }
+    }
+
+    /// Track key images of the wallet's outputs and return the value of the
+    /// ones not yet seen spent on-chain (`None` for a view-only wallet)
+    async fn refresh_bridge_outputs(&self, wallet: &MoneroWalletClient) -> Result<Option<u64>> {
+        let transfers = wallet
+            .incoming_transfers(self.config.subaddress_account)
+            .await?;
+        if transfers.iter().all(|t| t.key_image.is_empty()) && !transfers.is_empty() {
+            return Ok(None);
+        }
+
+        // Outputs the wallet already considers spent were spent before we
+        // started tracking them; only unspent ones are worth watching
+        for transfer in transfers
+            .iter()
+            .filter(|t| !t.spent && !t.key_image.is_empty())
+        {
+            self.db.track_bridge_output(
+                parse_hex_to_b256(&transfer.key_image)?,
+                parse_hex_to_b256(&transfer.tx_hash)?,
+                transfer.amount,
+            )?;
+        }
+
+        let unspent = self.db.unspent_bridge_outputs()?;
+        metrics::get()
+            .bridge_unspent_outputs
+            .set(unspent.len() as i64);
+        Ok(Some(unspent.iter().map(|o| o.amount).sum()))
+    }
+
+    /// Broadcast the signed payout `tx_hash` and mark the withdrawals it pays
+    /// broadcast. If relaying fails but the daemon already knows the transaction,
+    /// an earlier relay went through.
+    async fn relay_payout(&self, wallet: &MoneroWalletClient, tx_hash: B256) -> Result<()> {
+        let batch: Vec<Withdrawal> = self
+            .db
+            .withdrawals_in_payout(tx_hash)?
+            .into_iter()
+            .filter(|withdrawal| withdrawal.status == WithdrawalStatus::Signed)
+            .collect();
+        let Some(first) = batch.first() else {
+            return Ok(());
+        };
+        let metadata = self
+            .db
+            .withdrawal_tx_metadata(first.burn_id)?
+            .with_context(|| format!("No signed transfer for burn #{}", first.burn_id))?;
+
+        if let Err(e) = self.relay_signed(wallet, &metadata).await {
+            let known = self
+                .monero()
+                .get_transactions(vec![hex::encode(tx_hash)])
+                .await;
+            if !matches!(known, Ok(txs) if !txs.is_empty()) {
+                for withdrawal in &batch {
+                    self.withdrawal_failed(withdrawal.burn_id, "Relay", &e)?;
+                }
+                return Ok(());
+            }
+        }
+
+        for withdrawal in &batch {
+            self.db
+                .advance_withdrawal(withdrawal.burn_id, WithdrawalStatus::Signed)?;
+            metrics::get().withdrawals_paid.inc();
+            self.feed.publish(BridgeEvent::WithdrawalSent {
+                burn_id: withdrawal.burn_id,
+                xmr_address: withdrawal.xmr_address.clone(),
+                amount: withdrawal.xmr_amount.unwrap_or(withdrawal.amount),
+                xmr_tx_hash: tx_hash,
+            });
+            info!("   ✅ Burn #{} paid out", withdrawal.burn_id);
+        }
+        let fee = self
+            .db
+            .withdrawals_in_payout(tx_hash)?
+            .iter()
+            .filter_map(|withdrawal| withdrawal.xmr_fee)
+            .sum();
+        self.db.record_tx_cost(&TxCost::payout(tx_hash, fee))?;
+
+        // Payouts signed before the journal existed have no entry
+        let key = Intent::payout(batch.iter().map(|withdrawal| withdrawal.burn_id)).key();
+        if self.db.operation(&key)?.is_some() {
+            self.db.complete_operation(&key, Some(tx_hash))?;
+        }
+        Ok(())
+    }
+
+    /// Move broadcast withdrawals to `confirmed_xmr` once their Monero
+    /// transaction has `MIN_CONFIRMATIONS` blocks on top. A transaction the
+    /// daemon doesn't know (dropped from the mempool, or lost by a crash
+    /// right after relaying) is rebroadcast from the stored signed transfer:
+    /// it is the same transaction, so it can't pay twice.
+    async fn check_payout_confirmations(
+        &self,
+        wallet: &MoneroWalletClient,
+        tip_height: u64,
+    ) -> Result<()> {
+        // Withdrawals paid together share a transaction; look it up once
+        let mut payouts: BTreeMap<B256, Vec<u64>> = BTreeMap::new();
+        for withdrawal in self.db.withdrawals_due(WithdrawalStatus::Broadcast)? {
+            let Some(tx_hash) = withdrawal.xmr_tx_hash else {
+                anyhow::bail!(
+                    "Broadcast withdrawal #{} has no recorded transaction",
+                    withdrawal.burn_id
+                );
+            };
+            payouts.entry(tx_hash).or_default().push(withdrawal.burn_id);
+        }
+
+        for (tx_hash, burn_ids) in payouts {
+            let tx = self
+                .monero()
+                .get_transactions(vec![hex::encode(tx_hash)])
+                .await?
+                .into_iter()
+                .next();
+            let Some(tx) = tx else {
+                let relayed = match self.db.withdrawal_tx_metadata(burn_ids[0])? {
+                    Some(metadata) => self.relay_signed(wallet, &metadata).await.map(|_| ()),
+                    None => Err(anyhow::anyhow!("No signed transfer stored")),
+                }
+                .with_context(|| format!("Transaction {} not found on Monero", tx_hash));
+                match relayed {
+                    Ok(()) => warn!(
+                        "   📡 Payout {} was missing on Monero, rebroadcast",
+                        tx_hash
+                    ),
+                    Err(e) => {
+                        for &burn_id in &burn_ids {
+                            self.withdrawal_failed(burn_id, "Rebroadcast", &e)?;
+                        }
+                    }
+                }
+                continue;
+            };
+            let confirmations = match tx.block_height {
+                Some(height) if !tx.in_pool => tip_height.saturating_sub(height),
+                _ => 0,
+            };
+            if confirmations < self.config.min_confirmations {
+                continue;
+            }
+
+            for burn_id in burn_ids {
+                self.db
+                    .advance_withdrawal(burn_id, WithdrawalStatus::Broadcast)?;
+                info!(
+                    "   ✅ Burn #{} payout confirmed on Monero ({} confirmations)",
+                    burn_id, confirmations
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publish withdrawals confirmed on Monero with `confirmWithdrawal`
+    async fn confirm_withdrawals<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        for withdrawal in self.db.withdrawals_due(WithdrawalStatus::ConfirmedXmr)? {
+            let burn_id = withdrawal.burn_id;
+            let (Some(tx_hash), Some(tx_key)) = (withdrawal.xmr_tx_hash, &withdrawal.xmr_tx_key)
+            else {
+                anyhow::bail!(
+                    "Confirmed withdrawal #{} has no recorded transaction",
+                    burn_id
+                );
+            };
+
+            info!("\n🧾 Confirming burn #{} on Unichain...", burn_id);
+            let tx_key = match parse_hex_to_b256(tx_key) {
+                Ok(key) => key,
+                Err(e) => {
+                    // Not retryable: the key won't change
+                    let error = format!("Tx key is not 32 bytes: {:#}", e);
+                    self.db.set_withdrawal_status(
+                        burn_id,
+                        WithdrawalStatus::Failed,
+                        Some(&error),
+                    )?;
+                    metrics::get().withdrawals_failed.inc();
+                    error!("   ❌ Burn #{}: {}", burn_id, error);
+                    continue;
+                }
+            };
+
+            let call = contract.confirmWithdrawal(U256::from(burn_id), tx_hash, tx_key);
+            let intent = Intent::ConfirmWithdrawal { burn_id };
+            match self.send(contract.provider(), call, Some(&intent)).await {
+                Ok(receipt) => info!(
+                    "   ✅ Confirmed in block {}",
+                    receipt.block_number.unwrap_or(0)
+                ),
+                Err(e) if format!("{:#}", e).contains("Already processed") => {
+                    warn!("   ⚠️  Burn #{} already settled on-chain", burn_id);
+                    self.db.complete_operation(&intent.key(), None)?;
+                }
+                Err(e) => {
+                    self.withdrawal_failed(burn_id, "Confirmation", &e)?;
+                    continue;
+                }
+            }
+
+            self.db
+                .advance_withdrawal(burn_id, WithdrawalStatus::ConfirmedXmr)?;
+            metrics::get().withdrawals_confirmed.inc();
+        }
+
+        Ok(())
+    }
+
+    /// Count a failed withdrawal step; after `WITHDRAWAL_MAX_ATTEMPTS` the
+    /// withdrawal is marked failed and left for an operator
+    fn withdrawal_failed(&self, burn_id: u64, step: &str, e: &anyhow::Error) -> Result<()> {
+        let error = format!("{} failed: {:#}", step, e);
+        let status = self.db.record_withdrawal_failure(
+            burn_id,
+            &error,
+            self.config.withdrawal_max_attempts,
+        )?;
+
+        if status == WithdrawalStatus::Failed {
+            metrics::get().withdrawals_failed.inc();
+            error!("   ❌ Burn #{} gave up: {}", burn_id, error);
+        } else {
+            warn!("   ⚠️  Burn #{}: {}, will retry", burn_id, error);
+        }
+        Ok(())
+    }
+
+    /// Queue withdrawals for `BurnRequested` events in Unichain blocks with
+    /// enough confirmations, resuming from the stored cursor
+    async fn sync_withdrawals<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let head = metrics::get().observe_rpc(
+            "unichain",
+            "blockNumber",
+            self.config
+                .rpc_retry
+                .retry("unichain", "blockNumber", || async {
+                    Ok(contract.provider().get_block_number().await?)
+                })
+                .await,
+        )?;
+        let confirmed = head.saturating_sub(self.config.withdrawal_confirmations);
+
+        // Without a cursor, start at WITHDRAWAL_START_BLOCK or only watch new burns
+        let mut from = match self.db.unichain_cursor()? {
+            Some(cursor) => cursor + 1,
+            None => self.config.withdrawal_start_block.unwrap_or(confirmed),
+        };
+
+        while from <= confirmed {
+            let to = confirmed.min(from + MAX_LOG_RANGE - 1);
+            let events = metrics::get().observe_rpc(
+                "unichain",
+                "getLogs",
+                self.config
+                    .rpc_retry
+                    .retry("unichain", "getLogs", || async {
+                        Ok(contract
+                            .BurnRequested_filter()
+                            .from_block(from)
+                            .to_block(to)
+                            .query()
+                            .await?)
+                    })
+                    .await,
+            )?;
+
+            for (event, log) in events {
+                let withdrawal = Withdrawal::from_burn(
+                    BurnEvent {
+                        burn_id: event.burnId,
+                        user: event.user,
+                        lp: event.lp,
+                        amount: event.amount,
+                        xmr_address: event.xmrAddress,
+                        unichain_tx_hash: log.transaction_hash.unwrap_or_default(),
+                        unichain_block: log.block_number.unwrap_or(to),
+                    },
+                    self.config.network,
+                )?;
+
+                if !self.db.queue_withdrawal(&withdrawal)? {
+                    continue;
+                }
+                metrics::get()
+                    .withdrawals_queued
+                    .with_label_values(&[&withdrawal.status.to_string()])
+                    .inc();
+
+                match withdrawal.status {
+                    WithdrawalStatus::Rejected => warn!(
+                        "   ⚠️  Burn #{} rejected: {}",
+                        withdrawal.burn_id,
+                        withdrawal.error.as_deref().unwrap_or_default()
+                    ),
+                    _ => info!(
+                        "   🔥 Burn #{}: {} XMR to {} queued",
+                        withdrawal.burn_id,
+                        format_xmr(withdrawal.amount),
+                        withdrawal.xmr_address
+                    ),
+                }
+            }
+
+            self.db.set_unichain_cursor(to)?;
+            from = to + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Mint every stored deposit that has a registered recipient and hasn't
+    /// been minted yet. A failed mint is left pending and retried next poll.
+    async fn mint_deposits<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        // Read when the first bridge fee of the round is quoted
+        let mut rate = None;
+        for PendingMint { deposit, recipient } in self.db.pending_mints()? {
+            info!(
+                "\n🪙 Minting {} XMR to {} for {}:{}",
+                format_xmr(deposit.amount),
+                recipient,
+                deposit.tx_hash,
+                deposit.output_index
+            );
+
+            // The contract is the source of truth: the deposit may have been
+            // minted by an earlier run whose database was lost
+            let output_id = eth::output_id(deposit.tx_hash, deposit.output_index);
+            let used = metrics::get()
+                .observe_rpc(
+                    "unichain",
+                    "usedOutputs",
+                    self.config
+                        .rpc_retry
+                        .retry("unichain", "usedOutputs", || async {
+                            Ok(contract.usedOutputs(output_id).call().await?)
+                        })
+                        .await,
+                )?
+                ._0;
+            let intent = Intent::Mint {
+                tx_hash: deposit.tx_hash,
+                output_index: deposit.output_index,
+            };
+            if used {
+                warn!("   ⚠️  Output already minted on-chain, skipping");
+                if self.db.operation(&intent.key())?.is_some() {
+                    self.db.complete_operation(&intent.key(), None)?;
+                }
+                self.db.record_mint(&deposit, recipient, B256::ZERO)?;
+                // Minted net of any fee quoted before the earlier run stopped
+                self.db.collect_fee(
+                    FeeKind::Mint,
+                    &bridge_fee::mint_reference(deposit.tx_hash, deposit.output_index),
+                )?;
+                continue;
+            }
+
+            let approved = self
+                .db
+                .deposit_review(deposit.tx_hash, deposit.output_index)?
+                == Some(ReviewStatus::Approved);
+            let policy = self.config.deposit_policy;
+            if !policy.is_unlimited() && !approved {
+                let since = Utc::now() - chrono::Duration::from_std(deposit_policy::USER_WINDOW)?;
+                let minted = self.db.minted_since(recipient, since)?;
+                if let Some(violation) = policy.check(deposit.amount, minted) {
+                    self.hold_deposit(&deposit, violation.to_string()).await?;
+                    continue;
+                }
+            }
+
+            let mut amount = deposit.amount;
+            let reference = bridge_fee::mint_reference(deposit.tx_hash, deposit.output_index);
+            if let Some(charge) = self
+                .bridge_fee(FeeKind::Mint, reference.clone(), deposit.amount, &mut rate)
+                .await?
+            {
+                match charge.net() {
+                    Some(net) => {
+                        info!("   Bridge fee {}", charge);
+                        self.db.record_fee_charge(&charge)?;
+                        amount = net;
+                    }
+                    // Approving a deposit too small for the fee waives it
+                    None if approved => info!("   Bridge fee {} waived on approval", charge),
+                    None => {
+                        let reason = format!(
+                            "{} XMR doesn't cover the {} bridge fee",
+                            format_xmr(deposit.amount),
+                            charge
+                        );
+                        self.hold_deposit(&deposit, reason).await?;
+                        continue;
+                    }
+                }
+            }
+
+            let proof = match self.deposit_proof(contract, &deposit).await {
+                Ok(proof) => proof,
+                Err(e) => {
+                    warn!("   ⚠️  Couldn't prove the output, will retry: {:#}", e);
+                    continue;
+                }
+            };
+            let call = contract.mintDeposit(
+                recipient,
+                U256::from(amount),
+                (&proof.output).into(),
+                U256::from(proof.block_height),
+                proof.proof,
+                U256::from(proof.leaf_index),
+            );
+            match self.send(contract.provider(), call, Some(&intent)).await {
+                Ok(receipt) => {
+                    info!(
+                        "   ✅ Minted in block {}",
+                        receipt.block_number.unwrap_or(0)
+                    );
+                    self.db
+                        .record_mint(&deposit, recipient, receipt.transaction_hash)?;
+                    self.db.collect_fee(FeeKind::Mint, &reference)?;
+                    metrics::get().deposits_minted.inc();
+                }
+                Err(e) => warn!("   ⚠️  Mint failed, will retry: {:#}", e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Proof of a deposit's output in its block's output tree, which
+    /// `mintDeposit` checks against the posted root. The tree is built in
+    /// the version the contract recorded for the block, which needn't be
+    /// the one `OUTPUT_MERKLE_HASHER` posts new blocks with. Indexed blocks
+    /// need no round trip to monerod.
+    async fn deposit_proof<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        deposit: &Deposit,
+    ) -> Result<OutputInclusionProof>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let tx_hash = hex::encode(deposit.tx_hash);
+        let version = metrics::get().observe_rpc(
+            "unichain",
+            "outputTreeVersionOf",
+            self.config
+                .rpc_retry
+                .retry("unichain", "outputTreeVersionOf", || {
+                    eth::block_output_tree_version(contract, deposit.block_height)
+                })
+                .await,
+        )?;
+        let hasher = MerkleHasher::for_output_version(version)?;
+        if let Some(proof) =
+            proof::prove_indexed_output(&self.db, &tx_hash, deposit.output_index, hasher)?
+        {
+            return Ok(proof);
+        }
+        proof::prove_output(&self.monero(), &tx_hash, deposit.output_index, hasher).await
+    }
+
+    /// Hold a deposit for review, alerting when it is first held
+    async fn hold_deposit(&self, deposit: &Deposit, reason: String) -> Result<()> {
+        warn!("   ✋ Held for review: {}", reason);
+        if self.db.hold_deposit(deposit, &reason)? {
+            metrics::get().deposits_held.inc();
+            self.alerts
+                .notify(Alert::DepositHeld {
+                    tx_hash: deposit.tx_hash,
+                    output_index: deposit.output_index,
+                    reason,
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Bridge fee on an operation: the quote recorded when it was first
+    /// attempted, or a new one. A dollar fee is converted at `rate`, which is
+    /// read from the price feed once and shared by the rest of the round.
+    /// `None` without a bridge fee.
+    async fn bridge_fee(
+        &self,
+        kind: FeeKind,
+        reference: String,
+        amount: u64,
+        rate: &mut Option<Rate>,
+    ) -> Result<Option<FeeCharge>> {
+        let schedule = self.config.bridge_fee;
+        if schedule.is_free() {
+            return Ok(None);
+        }
+        if let Some(charge) = self.db.fee_charge(kind, &reference)? {
+            return Ok(Some(charge));
+        }
+        let rate = match (schedule.usd, *rate) {
+            (None, _) => None,
+            (Some(_), Some(rate)) => Some(rate),
+            (Some(_), None) => Some(*rate.insert(self.xmr_usd().await?)),
+        };
+        Ok(Some(FeeCharge::quote(
+            kind,
+            reference,
+            amount,
+            &schedule,
+            rate.as_ref(),
+        )))
+    }
+
+    /// Current XMR/USD rate from `PRICE_FEED_*`
+    async fn xmr_usd(&self) -> Result<Rate> {
+        let feed = self
+            .config
+            .price_feed
+            .as_ref()
+            .context("BRIDGE_FEE_USD needs a price feed")?;
+        let address = match &feed.source {
+            PriceSource::Http { .. } => return feed.fetch_http(&self.peer_client).await,
+            PriceSource::Chainlink(address) => *address,
+        };
+        let aggregator =
+            AggregatorV3::new(address, ProviderBuilder::new().on_client(self.unichain()?));
+        let (decimals, round) = metrics::get().observe_rpc(
+            "unichain",
+            "latestRoundData",
+            self.config
+                .rpc_retry
+                .retry("unichain", "latestRoundData", || async {
+                    Ok((
+                        aggregator.decimals().call().await?._0,
+                        aggregator.latestRoundData().call().await?,
+                    ))
+                })
+                .await,
+        )?;
+        let rate = price::chainlink_rate(
+            i128::try_from(round.answer).context("Chainlink answer out of range")?,
+            decimals,
+            u64::try_from(round.updatedAt).context("Chainlink update time out of range")?,
+        )?;
+        feed.check(rate, Utc::now())
+    }
+
+    /// Re-read the wallet balance and return whether block posting is
+    /// paused. Failures are only counted, never fatal: the last known state
+    /// is kept.
+    async fn refresh_wallet_balance<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> bool
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let (oracle_address, paused) = {
+            let status = self.status.read().expect("status lock poisoned");
+            (status.oracle_address, status.posting_paused)
+        };
+        let Some(oracle_address) = oracle_address else {
+            return paused;
+        };
+
+        let balance = metrics::get().observe_rpc(
+            "unichain",
+            "getBalance",
+            self.config
+                .rpc_retry
+                .retry("unichain", "getBalance", || async {
+                    Ok(contract.provider().get_balance(oracle_address).await?)
+                })
+                .await,
+        );
+        match balance {
+            Ok(balance) => {
+                self.update_balance(oracle_address, wei_to_ether(balance))
+                    .await
+            }
+            Err(_) => paused,
+        }
+    }
+
+    /// Record the wallet balance, warning below `BALANCE_WARN_ETH` and
+    /// pausing block posting below `BALANCE_PAUSE_ETH`. Returns whether
+    /// posting is paused.
+    async fn update_balance(&self, address: Address, balance_eth: f64) -> bool {
+        metrics::get().wallet_balance_eth.set(balance_eth);
+
+        // Signers never spend gas
+        let level = match self.config.mode {
+            OracleMode::Signer => BalanceLevel::Ok,
+            _ => self.config.balance.level(balance_eth),
+        };
+        let paused = level == BalanceLevel::Paused;
+        let was_paused = {
+            let mut status = self.status.write().expect("status lock poisoned");
+            status.balance_eth = Some(balance_eth);
+            std::mem::replace(&mut status.posting_paused, paused)
+        };
+        metrics::get().posting_paused.set(paused as i64);
+
+        match level {
+            BalanceLevel::Paused => {
+                if !was_paused {
+                    error!(
+                        "⏸️  Oracle balance {:.6} ETH is below {} ETH; pausing block posting until refunded",
+                        balance_eth, self.config.balance.pause_eth
+                    );
+                }
+                self.alerts
+                    .notify(Alert::PostingPaused {
+                        address,
+                        balance_eth,
+                        minimum_eth: self.config.balance.pause_eth,
+                    })
+                    .await;
+            }
+            BalanceLevel::Low => {
+                warn!(
+                    "   ⚠️  Oracle balance {:.6} ETH is below {} ETH; refund soon",
+                    balance_eth, self.config.balance.warn_eth
+                );
+                self.alerts
+                    .notify(Alert::LowBalance {
+                        address,
+                        balance_eth,
+                        minimum_eth: self.config.balance.warn_eth,
+                    })
+                    .await;
+            }
+            BalanceLevel::Ok => self.alerts.clear("low_balance"),
+        }
+        if !paused {
+            self.alerts.clear("posting_paused");
+            if was_paused {
+                info!(
+                    "▶️  Oracle balance restored ({:.6} ETH); resuming block posting",
+                    balance_eth
+                );
+            }
+        }
+        paused
+    }
+
+    /// The gas budget used up, if any, by transactions recorded in its
+    /// period
+    fn gas_budget_exceeded(&self) -> Result<Option<BudgetExceeded>> {
+        let budget = self.gas.read().expect("gas config poisoned").budget;
+        let Some(lookback) = budget.lookback() else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        let costs = self.db.tx_costs(now - lookback, now)?;
+        Ok(budget.exceeded(&costs, now))
+    }
+
+    /// Check the gas budget, pausing block posting while it is used up.
+    /// Returns whether it is.
+    async fn check_gas_budget(&self) -> Result<bool> {
+        let exceeded = self.gas_budget_exceeded()?;
+        let was_exceeded = std::mem::replace(
+            &mut self
+                .status
+                .write()
+                .expect("status lock poisoned")
+                .over_gas_budget,
+            exceeded.is_some(),
+        );
+        match exceeded {
+            Some(exceeded) => {
+                if !was_exceeded {
+                    error!(
+                        "⏸️  Gas budget used up ({}); pausing block posting until spending falls back under it",
+                        exceeded
+                    );
+                }
+                self.alerts.notify(Alert::GasBudgetExceeded(exceeded)).await;
+            }
+            None => {
+                self.alerts.clear("gas_budget_exceeded");
+                if was_exceeded {
+                    info!("▶️  Gas spending back under budget; resuming block posting");
+                }
+            }
+        }
+        Ok(exceeded.is_some())
+    }
+
+    /// Call `heartbeat()` if the contract has seen no posting or heartbeat
+    /// for `HEARTBEAT_INTERVAL_SECS` (see [`crate::heartbeat`])
+    async fn send_heartbeat<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        interval: Duration,
+        tip_height: u64,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let last_activity: u64 = contract
+            .lastOracleActivity()
+            .call()
+            .await
+            .context("Failed to read lastOracleActivity() from the contract")?
+            ._0
+            .try_into()
+            .unwrap_or(u64::MAX);
+        let now = Utc::now().timestamp().try_into().unwrap_or(0);
+        if !heartbeat::is_due(last_activity, now, interval) {
+            return Ok(());
+        }
+
+        if let Some(lease) = &self.lease {
+            lease.ensure_held()?;
+        }
+        let call = contract.heartbeat(U256::from(tip_height));
+        let receipt = self
+            .send_tx(contract.provider(), call, CostKind::Heartbeat, |_| {})
+            .await?;
+        anyhow::ensure!(
+            receipt.status(),
+            "heartbeat reverted in {}",
+            receipt.transaction_hash
+        );
+        metrics::get().heartbeats_sent.inc();
+        info!(
+            "   💓 Heartbeat sent in Unichain block {}",
+            receipt.block_number.unwrap_or(0)
+        );
+        Ok(())
+    }
+
+    /// Fail the poll while the contract records new blocks under other tree
+    /// versions than `OUTPUT_MERKLE_HASHER` and `TX_MERKLE_HASHER` build:
+    /// each block posted meanwhile would be unmintable for good
+    async fn check_tree_versions<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let output_version = metrics::get().observe_rpc(
+            "unichain",
+            "outputTreeVersion",
+            self.config
+                .rpc_retry
+                .retry("unichain", "outputTreeVersion", || {
+                    eth::output_tree_version(contract)
+                })
+                .await,
+        )?;
+        let hasher = self.config.output_merkle_hasher;
+        anyhow::ensure!(
+            output_version == hasher.version(),
+            "Contract takes version {} output trees, but OUTPUT_MERKLE_HASHER={} builds version {}",
+            output_version,
+            hasher,
+            hasher.version()
+        );
+
+        let tx_version = metrics::get().observe_rpc(
+            "unichain",
+            "txTreeVersion",
+            self.config
+                .rpc_retry
+                .retry("unichain", "txTreeVersion", || {
+                    eth::tx_tree_version(contract)
+                })
+                .await,
+        )?;
+        let hasher = self.config.tx_merkle_hasher;
+        anyhow::ensure!(
+            tx_version == hasher.version(),
+            "Contract takes version {} tx trees, but TX_MERKLE_HASHER={} builds version {}",
+            tx_version,
+            hasher,
+            hasher.version()
+        );
+        Ok(())
+    }
+
+    /// Read the contract's `paused()`, reporting changes. Once it is
+    /// unpaused, the blocks that piled up meanwhile are approved past
+    /// `MAX_CATCH_UP_BLOCKS`: the gap is the pause's, not a surprise.
+    async fn check_contract_paused<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<bool>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let paused = metrics::get().observe_rpc(
+            "unichain",
+            "paused",
+            self.config
+                .rpc_retry
+                .retry("unichain", "paused", || eth::is_paused(contract))
+                .await,
+        )?;
+        let was_paused = std::mem::replace(
+            &mut self
+                .status
+                .write()
+                .expect("status lock poisoned")
+                .contract_paused,
+            paused,
+        );
+        metrics::get().contract_paused.set(paused as i64);
+        if paused {
+            if !was_paused {
+                warn!("⏸️  Bridge contract is paused; holding block posting until it is unpaused");
+            }
+            self.alerts.notify(Alert::ContractPaused).await;
+        } else {
+            self.alerts.clear("contract_paused");
+            if was_paused {
+                info!("▶️  Bridge contract unpaused; resuming block posting from where it stopped");
+                self.catch_up_approved.store(true, Ordering::Relaxed);
+            }
+        }
+        Ok(paused)
+    }
+
+    /// Whether posting `behind` blocks must wait for an operator: more than
+    /// `MAX_CATCH_UP_BLOCKS` and not approved. Back within the limit, an
+    /// earlier approval lapses.
+    fn catch_up_blocked(&self, behind: u64) -> bool {
+        match self.config.max_catch_up {
+            Some(maximum) if behind > maximum => !self.catch_up_approved.load(Ordering::Relaxed),
+            _ => {
+                self.catch_up_approved.store(false, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    /// Process blocks `from..=to`, fetching them in batches of up to
+    /// `BULK_FETCH_BLOCKS`, up to `FETCH_CONCURRENCY` batches and blocks at
+    /// once, but yielding them in height order, since they must be posted
+    /// sequentially. Dropping the stream cancels fetches still in flight.
+    fn process_blocks(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> impl Stream<Item = Result<ProcessedBlock>> + '_ {
+        let batches = (from..=to)
+            .step_by(BULK_FETCH_BLOCKS as usize)
+            .map(move |start| (start, to.min(start + BULK_FETCH_BLOCKS - 1)));
+        stream::iter(batches)
+            .map(move |(start, end)| self.fetch_blocks(start, end))
+            .buffered(self.config.fetch_concurrency)
+            .flat_map(|fetched| {
+                let blocks: Vec<Result<FetchedBlock>> = match fetched {
+                    Ok(blocks) => blocks.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(blocks)
+            })
+            .map(move |block| async move { self.process_fetched(block?).await })
+            .buffered(self.config.fetch_concurrency)
+    }
+
+    /// Blocks `from..=to` with their transactions: a range in two bulk
+    /// calls, or one block at a time from nodes that don't serve them
+    async fn fetch_blocks(&self, from: u64, to: u64) -> Result<Vec<FetchedBlock>> {
+        let monero = self.monero();
+        if from < to {
+            match monero.fetch_blocks(from, to).await {
+                Ok(blocks) => {
+                    info!("\n   📥 Fetched blocks {} to {}", from, to);
+                    return Ok(blocks);
+                }
+                Err(e) if is_method_unavailable(&e) => {
+                    warn!("   ⚠️  {}; fetching blocks one at a time", e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let mut blocks = Vec::with_capacity((to - from + 1) as usize);
+        for height in from..=to {
+            blocks.push(monero.fetch_block(height).await?);
+        }
+        Ok(blocks)
+    }
+
+    /// Fetch a block and compute the roots the contract stores for it
+    async fn process_block(&self, height: u64) -> Result<ProcessedBlock> {
+        let block = self.monero().fetch_block(height).await?;
+        self.process_fetched(block).await
+    }
+
+    /// Compute the roots the contract stores for a fetched block
+    async fn process_fetched(&self, block: FetchedBlock) -> Result<ProcessedBlock> {
+        let height = block.header.height;
+        info!("\n   📦 Processing block {}...", height);
+
+        let FetchedBlock {
+            header,
+            tx_hashes,
+            transactions,
+        } = block;
+        let block_hash = parse_hex_to_b256(&header.hash)?;
+        let prev_hash = parse_hex_to_b256(&header.prev_hash)?;
+        let cumulative_difficulty = header.cumulative_difficulty()?;
+        self.check_quorum(height, block_hash).await?;
+
+        info!("      Transactions: {}", tx_hashes.len());
+
+        // Don't trust the node's tx list: it must reproduce the block hash
+        let miner_tx_hash = &transactions[0].0;
+        let tree_root = verify_block_tx_hashes(&header, miner_tx_hash, &tx_hashes)?;
+        info!("      Monero tree root: {} (matches block hash)", tree_root);
+
+        if let Some(verifier) = &self.pow {
+            let blob = block_hashing_blob(&header, &tree_root.0, tx_hashes.len() as u64 + 1)?;
+            let seed = self
+                .monero()
+                .get_block_header_by_height(pow::seed_height(height))
+                .await?;
+            let seed_hash = parse_hex_to_b256(&seed.hash)?;
+            let verifier = Arc::clone(verifier);
+            let header = header.clone();
+            tokio::task::spawn_blocking(move || verifier.verify(&header, &blob, seed_hash))
+                .await??;
+            info!("      Proof of work: valid");
+        }
+
+        // Compute TX Merkle root
+        let tx_tree = tx_tree(&tx_hashes, self.config.tx_merkle_hasher);
+        let tx_merkle_root = tx_tree.root();
+        info!("      TX Merkle root: {}", tx_merkle_root);
+
+        // Extract outputs from block, in the output tree's leaf order so the
+        // output index records each one's position in the tree
+        let outputs = self
+            .config
+            .output_merkle_hasher
+            .output_order(
+                &outputs_from_transactions(&transactions)?,
+                &transaction_order(&transactions)?,
+            )
+            .into_owned();
+        info!("      Outputs: {}", outputs.len());
+
+        // Compute output Merkle root
+        let output_tree = output_tree(&outputs, self.config.output_merkle_hasher);
+        let output_merkle_root = output_tree.root();
+        info!("      Output Merkle root: {}", output_merkle_root);
+
+        let deposits = match &self.config.view_keys {
+            Some(keys) => scanner::scan_block(keys, height, &transactions)?,
+            None => vec![],
+        };
+        if !deposits.is_empty() {
+            info!("      Deposits to bridge: {}", deposits.len());
+        }
+
+        let spends = key_image::block_key_images(height, &transactions)?;
+
+        if let Some(exporter) = &self.exporter {
+            let exported = async {
+                let artifact = BlockArtifact::new(
+                    &header,
+                    parse_hex_to_b256(miner_tx_hash)?,
+                    tree_root,
+                    &tx_hashes,
+                    &tx_tree,
+                    &outputs,
+                    &output_tree,
+                )?;
+                exporter.export(&artifact).await
+            }
+            .await;
+            // The export is for outside verifiers; it never holds up posting
+            match exported {
+                Ok(()) => metrics::get().blocks_exported.inc(),
+                Err(e) => {
+                    metrics::get().block_export_failures.inc();
+                    warn!(
+                        "   ⚠️  Failed to export block {} to {}: {:#}",
+                        height, exporter, e
+                    );
+                }
+            }
+        }
+
+        Ok(ProcessedBlock {
+            height,
+            block_hash,
+            prev_hash,
+            cumulative_difficulty,
+            tx_merkle_root,
+            output_merkle_root,
+            tx_tree,
+            output_tree,
+            outputs,
+            deposits,
+            spends,
+        })
+    }
+
+    /// With `MONERO_QUORUM` above 1, require that many nodes to report
+    /// `block_hash` at `height`; unreachable nodes count against it
+    async fn check_quorum(&self, height: u64, block_hash: B256) -> Result<()> {
+        let quorum = self.config.monero_quorum;
+        if quorum <= 1 {
+            return Ok(());
+        }
+
+        let reported: Vec<Option<B256>> = future::join_all(
+            self.monero_nodes
+                .iter()
+                .map(|node| node.get_block_header_by_height(height)),
+        )
+        .await
+        .into_iter()
+        .zip(&self.monero_nodes)
+        .map(
+            |(header, node)| match header.and_then(|header| parse_hex_to_b256(&header.hash)) {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    warn!("   ⚠️  Monero node {} unavailable: {:#}", node.rpc_url(), e);
+                    None
+                }
+            },
+        )
+        .collect();
+
+        let agreeing = fork_choice::agreeing(&reported, &block_hash);
+        anyhow::ensure!(
+            agreeing >= quorum,
+            "Only {} of {} Monero nodes report {} at height {} (quorum {}); not processing it",
+            agreeing,
+            reported.len(),
+            block_hash,
+            height,
+            quorum
+        );
+        info!("      Quorum: {}/{} nodes agree", agreeing, reported.len());
+        Ok(())
+    }
+
+    /// Store a posted block's outputs and trees for proofs and lookups, if
+    /// enabled
+    fn index_outputs(&self, block: &ProcessedBlock) -> Result<()> {
+        if self.config.index_outputs {
+            self.db
+                .index_block_outputs(block.height, block.block_hash, &block.outputs)?;
+            for (kind, tree) in [
+                (TreeKind::Tx, &block.tx_tree),
+                (TreeKind::Output, &block.output_tree),
+            ] {
+                self.db
+                    .store_merkle_tree(block.height, block.block_hash, kind, tree)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Store deposits from a posted block. One whose one-time key was
+    /// already credited to another output is held for review instead.
+    async fn record_deposits(&self, deposits: &[Deposit]) -> Result<()> {
+        let (deposits, reused) = scanner::split_reused_keys(deposits.to_vec(), |key| {
+            self.db.deposit_with_output_key(key)
+        })?;
+        for ReusedKey { deposit, credited } in reused {
+            let reason = format!(
+                "Output key {} already credited to {}:{} (burnt output)",
+                deposit.output_pub_key, credited.0, credited.1
+            );
+            self.hold_deposit(&deposit, reason).await?;
+        }
+        for deposit in &deposits {
+            if self.db.record_deposit(deposit)? {
+                metrics::get().deposits_detected.inc();
+                self.feed
+                    .publish(BridgeEvent::DepositConfirmed(deposit.into()));
+            }
+            match deposit.payment_id {
+                Some(payment_id) => info!(
+                    "   💰 Deposit: {} XMR with payment ID {} in {}:{} (block {})",
+                    format_xmr(deposit.amount),
+                    payment_id,
+                    deposit.tx_hash,
+                    deposit.output_index,
+                    deposit.block_height
+                ),
+                None => info!(
+                    "   💰 Deposit: {} XMR to subaddress {} in {}:{} (block {})",
+                    format_xmr(deposit.amount),
+                    deposit.subaddress,
+                    deposit.tx_hash,
+                    deposit.output_index,
+                    deposit.block_height
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Record deposits waiting in the txpool as pending, and follow earlier
+    /// ones that have since left it: mined ones stay pending until their block
+    /// is posted, dropped ones are forgotten
+    async fn scan_mempool(&self, keys: &ViewKeys) -> Result<()> {
+        let pool = self.monero().get_transaction_pool().await?;
+        let mut in_pool = HashSet::new();
+
+        for (tx_hash, tx) in &pool {
+            in_pool.insert(parse_hex_to_b256(tx_hash)?);
+            for deposit in scanner::scan_transaction(keys, 0, tx_hash, tx)? {
+                if self.db.record_pending_deposit(&deposit)? {
+                    self.feed
+                        .publish(BridgeEvent::DepositDetected(DepositEvent::pending(
+                            &deposit,
+                        )));
+                    info!(
+                        "   👀 Pending deposit: {} XMR to subaddress {} in {}:{}",
+                        format_xmr(deposit.amount),
+                        deposit.subaddress,
+                        deposit.tx_hash,
+                        deposit.output_index
+                    );
+                }
+            }
+        }
+
+        let mut checked = HashSet::new();
+        for pending in self.db.pending_deposits()? {
+            if pending.block_height.is_some()
+                || in_pool.contains(&pending.tx_hash)
+                || !checked.insert(pending.tx_hash)
+            {
+                continue;
+            }
+
+            let tx = self
+                .monero()
+                .get_transactions(vec![hex::encode(pending.tx_hash)])
+                .await?
+                .into_iter()
+                .next();
+            match tx {
+                Some(tx) if tx.in_pool => {}
+                Some(tx) => {
+                    if let Some(height) = tx.block_height {
+                        self.db.set_pending_deposit_mined(pending.tx_hash, height)?;
+                    }
+                }
+                None => {
+                    self.db.remove_pending_deposits(pending.tx_hash)?;
+                    warn!(
+                        "   ⚠️  Pending deposit tx {} dropped from the txpool",
+                        pending.tx_hash
+                    );
+                }
+            }
+        }
+
+        metrics::get()
+            .pending_deposits
+            .set(self.db.pending_deposits()?.len() as i64);
+        Ok(())
+    }
+
+    /// Mark bridge outputs spent in a posted block. A spend that isn't one of
+    /// our withdrawal payouts means someone else holds the bridge spend key.
+    fn record_spends(&self, spends: &[KeyImageSpend]) -> Result<()> {
+        for spend in self.db.record_key_image_spends(spends)? {
+            if self.db.is_withdrawal_payout(spend.tx_hash)? {
+                info!(
+                    "   🔑 Bridge output spent by payout {} (block {})",
+                    spend.tx_hash, spend.block_height
+                );
+            } else {
+                metrics::get().bridge_unexpected_spends.inc();
+                error!(
+                    "   🚨 Bridge output with key image {} spent by {} (block {}), which is not a withdrawal payout!",
+                    spend.key_image, spend.tx_hash, spend.block_height
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn check_for_reorg<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        tip_height: u64,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        // Nothing can be posted over an unfinished rollback; with a Safe,
+        // its continueRollback calls were proposed with it
+        if self.safe.get().is_none() {
+            self.finish_rollback(contract).await?;
+        }
+
+        let (due, oldest_tracked) = {
+            let detector = self.reorg_detector.lock().expect("reorg detector poisoned");
+            (detector.due_for_check(tip_height), detector.oldest_height())
+        };
+
+        for posted in due {
+            let header = self
+                .monero()
+                .get_block_header_by_height(posted.height)
+                .await?;
+            let canonical_hash = parse_hex_to_b256(&header.hash)?;
+
+            if canonical_hash == posted.hash {
+                continue;
+            }
+
+            error!("🚨 Monero reorg detected at block {}!", posted.height);
+            error!("   Posted hash:    {}", posted.hash);
+            error!("   Canonical hash: {}", canonical_hash);
+            self.alerts
+                .notify(Alert::Reorg {
+                    height: posted.height,
+                })
+                .await;
+
+            if Some(posted.height) == oldest_tracked {
+                warn!(
+                    "   ⚠️  Fork is at the edge of the tracked window; it may be deeper than block {}",
+                    posted.height
+                );
+            }
+
+            return self.rollback_blocks(contract, posted.height).await;
+        }
+
+        Ok(())
+    }
+
+    async fn rollback_blocks<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        from_height: u64,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        warn!(
+            "\n⏪ Rolling contract back to block {}...",
+            from_height.saturating_sub(1)
+        );
+
+        let call = contract.rollbackMoneroBlocks(U256::from(from_height));
+        if let Some(proposer) = self.safe.get() {
+            // Postings resume on top of the rollback once the owners execute
+            // it and the continueRollback calls a deep one takes
+            let calls = eth::rollback_calls(contract, from_height).await?;
+            self.propose_to_safe(contract, proposer, call.calldata().clone())
+                .await
+                .context("Failed to propose rollback to the Safe")?;
+            for _ in 1..calls {
+                let call = contract.continueRollback();
+                self.propose_to_safe(contract, proposer, call.calldata().clone())
+                    .await
+                    .context("Failed to propose continueRollback to the Safe")?;
+            }
+        } else {
+            let receipt = self
+                .send(contract.provider(), call, None)
+                .await
+                .context("Failed to send rollback transaction")?;
+
+            info!(
+                "   ✅ Rolled back in block {}",
+                receipt.block_number.unwrap_or(0)
+            );
+            self.finish_rollback(contract).await?;
+        }
+
+        self.reorg_detector
+            .lock()
+            .expect("reorg detector poisoned")
+            .rollback(from_height);
+        let orphaned = self.db.mark_orphaned(from_height)?;
+        info!("   Marked {} stored block(s) as orphaned", orphaned);
+
+        Ok(())
+    }
+
+    /// Clear what's left of a rollback deeper than the contract's
+    /// `MAX_ROLLBACK_BLOCKS`, one `continueRollback` per batch
+    async fn finish_rollback<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        while let Some(cursor) = eth::rollback_cursor(contract).await? {
+            info!("   ⏪ Clearing orphaned blocks from {}...", cursor);
+            self.send(contract.provider(), contract.continueRollback(), None)
+                .await
+                .context("Failed to send continueRollback transaction")?;
+        }
+        Ok(())
+    }
+
+    /// Re-check the receipts of postings mined `UNICHAIN_CONFIRMATION_DEPTH`
+    /// blocks ago, and post again any a Unichain reorg dropped that the
+    /// contract doesn't hold otherwise (see [`crate::receipts`])
+    async fn reverify_postings<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        attester: Option<&Attester>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let Some(depth) = self.config.unichain_confirmation_depth else {
+            return Ok(());
+        };
+        let head = metrics::get().observe_rpc(
+            "unichain",
+            "blockNumber",
+            self.config
+                .rpc_retry
+                .retry("unichain", "blockNumber", || async {
+                    Ok(contract.provider().get_block_number().await?)
+                })
+                .await,
+        )?;
+        let Some(settled) = receipts::settled_block(head, depth) else {
+            return Ok(());
+        };
+
+        let mut dropped = Vec::new();
+        for posting in self.db.unconfirmed_postings(settled, MAX_RECEIPT_CHECKS)? {
+            let receipt = contract
+                .provider()
+                .get_transaction_receipt(posting.unichain_tx_hash)
+                .await?;
+            let found = receipt.and_then(|receipt| Some((receipt.block_number?, receipt.status())));
+            match receipts::check(posting.unichain_block, found) {
+                ReceiptCheck::Confirmed => {
+                    self.db.confirm_posting(posting.unichain_tx_hash)?;
+                    metrics::get().postings_confirmed.inc();
+                }
+                ReceiptCheck::Moved { unichain_block } => {
+                    info!(
+                        "   🔀 Posting of block {} was mined again in Unichain block {}",
+                        posting.height, unichain_block
+                    );
+                    self.db
+                        .move_posting(posting.unichain_tx_hash, unichain_block)?;
+                }
+                ReceiptCheck::Dropped => dropped.push(posting),
+            }
+        }
+        if dropped.is_empty() {
+            return Ok(());
+        }
+
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        for posting in dropped {
+            let height = posting.height;
+            metrics::get().postings_reorged.inc();
+            error!(
+                "🚨 Unichain reorg dropped the posting of block {} ({})",
+                height, posting.unichain_tx_hash
+            );
+
+            // Stored since by another transaction: a re-post this poll
+            // already made, or another submitter's
+            if let Some(stored) = eth::get_block(contract, height).await? {
+                let superseded = self
+                    .db
+                    .posted_block(height)?
+                    .is_some_and(|live| live.unichain_tx_hash != posting.unichain_tx_hash);
+                if stored.block_hash == posting.block_hash && !superseded {
+                    info!("   ✅ The contract holds block {} all the same", height);
+                    self.db.confirm_posting(posting.unichain_tx_hash)?;
+                } else {
+                    self.db.drop_posting(posting.unichain_tx_hash)?;
+                }
+                continue;
+            }
+
+            self.db.drop_posting(posting.unichain_tx_hash)?;
+            if height <= latest_posted {
+                warn!(
+                    "   ⚠️  The contract has moved past block {}; not posting it again",
+                    height
+                );
+                continue;
+            }
+            self.alerts
+                .notify(Alert::PostingReorged {
+                    height,
+                    unichain_tx_hash: posting.unichain_tx_hash,
+                })
+                .await;
+
+            // The rest are posted again by the poll, on top of the contract
+            let signatures = match attester {
+                Some(attester) => {
+                    let block = self.process_block(height).await?;
+                    if block.block_hash != posting.block_hash {
+                        break;
+                    }
+                    match self.collect_signatures(contract, attester, &block).await? {
+                        Some(signatures) => Some(signatures),
+                        None => break,
+                    }
+                }
+                None => None,
+            };
+            self.post_block(
+                contract,
+                height,
+                posting.block_hash,
+                posting.tx_merkle_root,
+                posting.output_merkle_root,
+                signatures,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn post_block<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        block_height: u64,
+        block_hash: B256,
+        tx_merkle_root: B256,
+        output_merkle_root: B256,
+        signatures: Option<BlockSignatures>,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        info!("\n📤 Posting block {} to contract...", block_height);
+        info!("   Hash: {}", block_hash);
+        info!("   TX Merkle Root: {}", tx_merkle_root);
+        info!("   Output Merkle Root: {}", output_merkle_root);
+
+        if let Some(proposer) = self.safe.get() {
+            let call = contract.postMoneroBlock(
+                U256::from(block_height),
+                block_hash,
+                tx_merkle_root,
+                output_merkle_root,
+            );
+            return self
+                .propose_block(
+                    contract,
+                    proposer,
+                    block_height,
+                    block_hash,
+                    call.calldata(),
+                )
+                .await;
+        }
+
+        let intent = Intent::PostBlock {
+            contract: *contract.address(),
+            height: block_height,
+            block_hash,
+        };
+        let result = match signatures {
+            // Try swapping blockHash and blockHeight to match struct order
+            None => {
+                let call = contract.postMoneroBlock(
+                    U256::from(block_height),
+                    block_hash,
+                    tx_merkle_root,
+                    output_merkle_root,
+                );
+                self.send(contract.provider(), call, Some(&intent)).await
+            }
+            Some(BlockSignatures::Signers(signatures)) => {
+                info!("   Signatures: {}", signatures.len());
+                let call = contract.postMoneroBlockWithSignatures(
+                    U256::from(block_height),
+                    block_hash,
+                    tx_merkle_root,
+                    output_merkle_root,
+                    signatures,
+                );
+                self.send(contract.provider(), call, Some(&intent)).await
+            }
+            Some(BlockSignatures::Threshold(signature)) => {
+                info!("   Threshold signature nonce: {}", signature.nonce_address);
+                let call = contract.postMoneroBlockWithThresholdSignature(
+                    U256::from(block_height),
+                    block_hash,
+                    tx_merkle_root,
+                    output_merkle_root,
+                    signature.s,
+                    signature.nonce_address,
+                );
+                self.send(contract.provider(), call, Some(&intent)).await
+            }
+        };
+
+        match result {
+            Ok(receipt) => {
+                info!(
+                    "   ✅ Confirmed in block {}",
+                    receipt.block_number.unwrap_or(0)
+                );
+                info!("   Gas used: {}", receipt.gas_used);
+
+                let gas_used: u64 = receipt.gas_used.try_into().unwrap_or(u64::MAX);
+                metrics::get().blocks_posted.inc();
+
+                self.reorg_detector
+                    .lock()
+                    .expect("reorg detector poisoned")
+                    .record(block_height, block_hash);
+
+                self.db.record_posted_block(&PostedBlockRecord {
+                    height: block_height,
+                    block_hash,
+                    tx_merkle_root,
+                    output_merkle_root,
+                    unichain_tx_hash: receipt.transaction_hash,
+                    unichain_block: receipt.block_number,
+                    gas_used,
+                    posted_at: Utc::now(),
+                })?;
+                self.feed.publish(BridgeEvent::BlockPosted {
+                    contract: *contract.address(),
+                    height: block_height,
+                    block_hash,
+                    unichain_tx_hash: receipt.transaction_hash,
+                });
+            }
+            Err(e) => {
+                self.handle_revert(e, &format!("Posting block {}", block_height))
+                    .await?;
+                self.db.complete_operation(&intent.key(), None)?;
+            }
+        }
+
+        let expected = StoredBlock {
+            block_hash,
+            tx_merkle_root,
+            output_merkle_root,
+        };
+        self.verify_stored_block(contract, block_height, &expected)
+            .await;
+
+        Ok(())
+    }
+
+    /// Bring every `EXTRA_BRIDGE_ADDRESSES` contract up to `confirmed_height`.
+    /// Each is synced on its own: one that fails is reported and retried at
+    /// the next poll without holding up the others or the primary contract.
+    async fn sync_extra_contracts<T, P>(
+        &self,
+        primary: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        tip_height: u64,
+        confirmed_height: u64,
+    ) where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let mut failing = false;
+        for &address in &self.config.extra_bridge_addresses {
+            let contract = WrappedMonero::new(address, primary.provider());
+            let label = address.to_string();
+            let result = self.sync_extra_contract(&contract, confirmed_height).await;
+            if let Err(e) = &result {
+                error!("❌ Failed to sync extra contract {}: {:#}", address, e);
+                metrics::get()
+                    .extra_contract_failures
+                    .with_label_values(&[&label])
+                    .inc();
+                self.alerts
+                    .notify(Alert::ExtraContractFailing {
+                        contract: address,
+                        error: format!("{:#}", e),
+                    })
+                    .await;
+                failing = true;
+            }
+
+            let mut status = self.status.write().expect("status lock poisoned");
+            let Some(entry) = status
+                .extra_contracts
+                .iter_mut()
+                .find(|entry| entry.address == address)
+            else {
+                continue;
+            };
+            match result {
+                Ok(latest) => {
+                    let lag = tip_height.saturating_sub(latest);
+                    entry.latest_block = Some(latest);
+                    entry.lag = Some(lag);
+                    entry.last_synced_at = Some(Utc::now());
+                    entry.last_error = None;
+                    metrics::get()
+                        .extra_contract_latest_block
+                        .with_label_values(&[&label])
+                        .set(latest as i64);
+                    metrics::get()
+                        .extra_contract_lag_blocks
+                        .with_label_values(&[&label])
+                        .set(lag as i64);
+                }
+                Err(e) => entry.last_error = Some(format!("{:#}", e)),
+            }
+        }
+        if !failing {
+            self.alerts.clear("extra_contract_failing");
+        }
+    }
+
+    /// Post the confirmed blocks one extra contract is missing, first
+    /// rolling back any of its recent blocks that left the canonical chain.
+    /// Returns its latest block afterwards.
+    async fn sync_extra_contract<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        confirmed_height: u64,
+    ) -> Result<u64>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let active = self
+            .status
+            .read()
+            .expect("status lock poisoned")
+            .oracle_address;
+        let oracle = contract.oracle().call().await?.oracle;
+        anyhow::ensure!(
+            Some(oracle) == active,
+            "its oracle is {}, not this oracle's key",
+            oracle
+        );
+
+        let mut latest = self.rewind_extra_contract(contract).await?;
+        if latest >= confirmed_height {
+            return Ok(latest);
+        }
+        info!(
+            "   📊 Extra contract {}: posting blocks {} to {}",
+            contract.address(),
+            latest + 1,
+            confirmed_height
+        );
+
+        let mut parent = eth::get_block(contract, latest)
+            .await?
+            .map(|stored| stored.block_hash);
+        let mut blocks = pin!(self.process_blocks(latest + 1, confirmed_height));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            reorg::check_parent(block.height, block.prev_hash, parent)?;
+            let call = contract.postMoneroBlock(
+                U256::from(block.height),
+                block.block_hash,
+                block.tx_merkle_root,
+                block.output_merkle_root,
+            );
+            let intent = Intent::PostBlock {
+                contract: *contract.address(),
+                height: block.height,
+                block_hash: block.block_hash,
+            };
+            match self.send(contract.provider(), call, Some(&intent)).await {
+                Ok(receipt) => info!(
+                    "   ✅ Block {} confirmed in {} at block {}",
+                    block.height,
+                    contract.address(),
+                    receipt.block_number.unwrap_or(0)
+                ),
+                Err(e) => {
+                    let action =
+                        format!("Posting block {} to {}", block.height, contract.address());
+                    self.handle_revert(e, &action)
+                        .await
+                        .with_context(|| format!("Failed to post block {}", block.height))?;
+                    self.db.complete_operation(&intent.key(), None)?;
+                }
+            }
+            parent = Some(block.block_hash);
+            latest = block.height;
+        }
+        Ok(latest)
+    }
+
+    /// Roll an extra contract back past its latest blocks that are no longer
+    /// on the canonical chain, looking back up to `REORG_TRACKED_BLOCKS`.
+    /// Returns its latest block afterwards.
+    async fn rewind_extra_contract<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+    ) -> Result<u64>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        self.finish_rollback(contract).await?;
+        let latest: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        let floor = latest.saturating_sub(self.config.reorg_tracked_blocks as u64);
+
+        let mut fork = None;
+        for height in (floor + 1..=latest).rev() {
+            let Some(stored) = eth::get_block(contract, height).await? else {
+                continue;
+            };
+            let header = self.monero().get_block_header_by_height(height).await?;
+            if stored.block_hash == parse_hex_to_b256(&header.hash)? {
+                break;
+            }
+            fork = Some(height);
+        }
+        let Some(from_height) = fork else {
+            return Ok(latest);
+        };
+
+        warn!(
+            "\n⏪ Extra contract {} has orphaned blocks from {}; rolling back",
+            contract.address(),
+            from_height
+        );
+        self.alerts
+            .notify(Alert::Reorg {
+                height: from_height,
+            })
+            .await;
+        let receipt = self
+            .send(
+                contract.provider(),
+                contract.rollbackMoneroBlocks(U256::from(from_height)),
+                None,
+            )
+            .await
+            .context("Failed to send rollback transaction")?;
+        info!(
+            "   ✅ Rolled back in block {}",
+            receipt.block_number.unwrap_or(0)
+        );
+        self.finish_rollback(contract).await?;
+
+        Ok(contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0))
+    }
+
+    /// Propose a posting to the Safe, unless one for the same block is
+    /// already awaiting confirmations. The posting is recorded once the
+    /// Safe executes it.
+    async fn propose_block<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        proposer: &SafeProposer,
+        block_height: u64,
+        block_hash: B256,
+        calldata: &Bytes,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let existing = self
+            .db
+            .safe_proposals()?
+            .into_iter()
+            .find(|proposal| proposal.height == block_height);
+        match existing {
+            Some(existing) if existing.block_hash == block_hash => {
+                info!(
+                    "   ⏳ Already proposed to the Safe at nonce {}; awaiting confirmations",
+                    existing.nonce
+                );
+                return Ok(());
+            }
+            Some(existing) => warn!(
+                "   ⚠️  Block {} changed since it was proposed; Safe owners should reject {}",
+                block_height, existing.safe_tx_hash
+            ),
+            None => {}
+        }
+
+        let (safe_tx_hash, nonce) = self
+            .propose_to_safe(contract, proposer, calldata.clone())
+            .await
+            .with_context(|| format!("Failed to propose block {} to the Safe", block_height))?;
+        self.db.record_safe_proposal(&SafeProposal {
+            height: block_height,
+            block_hash,
+            safe_tx_hash,
+            nonce,
+        })?;
+        Ok(())
+    }
+
+    /// Sign a call from the Safe to the bridge and propose it at the Safe's
+    /// next free nonce. Returns the Safe transaction hash and nonce.
+    async fn propose_to_safe<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        proposer: &SafeProposer,
+        data: Bytes,
+    ) -> Result<(B256, u64)>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let executed_nonce = self.safe_nonce(contract, proposer).await?;
+        // Proposals still awaiting confirmations hold the nonces before it
+        let nonce = self
+            .db
+            .safe_nonce()?
+            .map_or(executed_nonce, |next| next.max(executed_nonce));
+
+        let tx = SafeTx::call(self.config.bridge_address, data, nonce);
+        let safe_tx_hash = proposer.client.signing_hash(proposer.chain_id, &tx);
+        let signature = proposer.signer.sign_hash(&safe_tx_hash).await?;
+        proposer
+            .client
+            .propose(&Proposal::new(
+                &tx,
+                safe_tx_hash,
+                proposer.signer.address(),
+                &signature,
+            ))
+            .await?;
+        self.db.set_safe_nonce(nonce + 1)?;
+
+        info!(
+            "   🔐 Proposed to Safe {} at nonce {}: {}",
+            proposer.client.address(),
+            nonce,
+            safe_tx_hash
+        );
+        Ok((safe_tx_hash, nonce))
+    }
+
+    /// Nonce of the Safe's next transaction to execute
+    async fn safe_nonce<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        proposer: &SafeProposer,
+    ) -> Result<u64>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let safe = Safe::new(proposer.client.address(), contract.provider());
+        let nonce = metrics::get()
+            .observe_rpc(
+                "unichain",
+                "safeNonce",
+                self.config
+                    .rpc_retry
+                    .retry("unichain", "safeNonce", || async {
+                        Ok(safe.nonce().call().await?)
+                    })
+                    .await,
+            )?
+            ._0;
+        Ok(nonce.try_into().unwrap_or(u64::MAX))
+    }
+
+    /// Settle proposals whose nonce the Safe has moved past: record the
+    /// postings it executed, and drop the ones replaced by another
+    /// transaction at that nonce so they are proposed again
+    async fn sync_safe_proposals<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        latest_posted: u64,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let Some(proposer) = self.safe.get() else {
+            return Ok(());
+        };
+        let proposals = self.db.safe_proposals()?;
+        if proposals.is_empty() {
+            return Ok(());
+        }
+
+        let executed_nonce = self.safe_nonce(contract, proposer).await?;
+        for proposal in proposals {
+            if proposal.nonce >= executed_nonce {
+                continue;
+            }
+
+            let executed = proposer
+                .client
+                .transaction(proposal.safe_tx_hash)
+                .await?
+                .filter(|tx| tx.is_executed && tx.is_successful == Some(true));
+            let stored = eth::get_block(contract, proposal.height).await?;
+            match (executed, stored) {
+                (Some(tx), Some(stored)) if stored.block_hash == proposal.block_hash => {
+                    info!(
+                        "   ✅ Safe executed the posting of block {} in Unichain block {}",
+                        proposal.height,
+                        tx.block_number.unwrap_or(0)
+                    );
+                    metrics::get().blocks_posted.inc();
+                    self.reorg_detector
+                        .lock()
+                        .expect("reorg detector poisoned")
+                        .record(proposal.height, proposal.block_hash);
+                    self.db.record_posted_block(&PostedBlockRecord {
+                        height: proposal.height,
+                        block_hash: proposal.block_hash,
+                        tx_merkle_root: stored.tx_merkle_root,
+                        output_merkle_root: stored.output_merkle_root,
+                        unichain_tx_hash: tx.transaction_hash.unwrap_or_default(),
+                        unichain_block: tx.block_number,
+                        gas_used: tx.gas_used.unwrap_or(0),
+                        posted_at: Utc::now(),
+                    })?;
+                    self.feed.publish(BridgeEvent::BlockPosted {
+                        contract: *contract.address(),
+                        height: proposal.height,
+                        block_hash: proposal.block_hash,
+                        unichain_tx_hash: tx.transaction_hash.unwrap_or_default(),
+                    });
+                }
+                _ if proposal.height > latest_posted => warn!(
+                    "   ⚠️  Safe nonce {} went to another transaction; block {} will be proposed again",
+                    proposal.nonce, proposal.height
+                ),
+                _ => warn!(
+                    "   ⚠️  Safe nonce {} went to another transaction and block {} was posted elsewhere",
+                    proposal.nonce, proposal.height
+                ),
+            }
+            self.db.delete_safe_proposal(proposal.height)?;
+        }
+        Ok(())
+    }
+
+    /// Read the block back from the contract and alert if what it stores
+    /// differs from what was computed, e.g. from an encoding mismatch
+    async fn verify_stored_block<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        block_height: u64,
+        expected: &StoredBlock,
+    ) where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let stored = match eth::get_block(contract, block_height).await {
+            Ok(Some(stored)) => stored,
+            Ok(None) => {
+                warn!(
+                    "   ⚠️  Block {} is not stored in the contract after posting",
+                    block_height
+                );
+                return;
+            }
+            Err(e) => {
+                warn!("   ⚠️  Could not verify block {}: {:#}", block_height, e);
+                return;
+            }
+        };
+
+        let mismatches = stored.mismatches(expected);
+        if mismatches.is_empty() {
+            return;
+        }
+
+        error!(
+            "🚨 Contract data for block {} differs from the computed block",
+            block_height
+        );
+        error!("   Computed: {:?}", expected);
+        error!("   Stored:   {:?}", stored);
+        metrics::get().root_mismatches.inc();
+        self.alerts
+            .notify(Alert::RootMismatch {
+                height: block_height,
+                fields: mismatches.join(", "),
+            })
+            .await;
+    }
+
+    async fn backfill_blocks<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        attester: Option<&Attester>,
+        from: u64,
+        to: u64,
+    ) -> Result<()>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        if self.config.mode == OracleMode::Signer {
+            anyhow::bail!("Signers don't post blocks; run the backfill on the submitter");
+        }
+        if !self.check_oracle_role(contract).await? && self.config.mode == OracleMode::Single {
+            anyhow::bail!("No configured key holds the oracle role; can't post blocks");
+        }
+        anyhow::ensure!(from <= to, "Empty block range {}..{}", from, to);
+
+        let tip_height = self.select_node().await?.height;
+        let confirmed_height = tip_height.saturating_sub(self.config.min_confirmations);
+        anyhow::ensure!(
+            to <= confirmed_height,
+            "Block {} has fewer than {} confirmations (tip {})",
+            to,
+            self.config.min_confirmations,
+            tip_height
+        );
+
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        info!(
+            "\n📚 Backfilling blocks {} to {} (contract at {})",
+            from, to, latest_posted
+        );
+
+        // Hash of the last block this backfill posted
+        let mut posted_parent = None;
+        let mut blocks = pin!(self.process_blocks(from, to));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            let height = block.height;
+
+            if height <= latest_posted {
+                info!("   Block {} is already posted; rescanning only", height);
+            } else {
+                let parent = match posted_parent {
+                    Some(hash) => Some(hash),
+                    None => eth::get_block(contract, height - 1)
+                        .await?
+                        .map(|stored| stored.block_hash),
+                };
+                reorg::check_parent(height, block.prev_hash, parent)?;
+
+                let signatures = match attester {
+                    Some(attester) => Some(
+                        self.collect_signatures(contract, attester, &block)
+                            .await?
+                            .with_context(|| {
+                                format!("Not enough attestations for block {}", height)
+                            })?,
+                    ),
+                    None => None,
+                };
+                self.post_block(
+                    contract,
+                    block.height,
+                    block.block_hash,
+                    block.tx_merkle_root,
+                    block.output_merkle_root,
+                    signatures,
+                )
+                .await?;
+                self.db.record_cumulative_difficulty(
+                    height,
+                    block.block_hash,
+                    block.cumulative_difficulty,
+                )?;
+                posted_parent = Some(block.block_hash);
+            }
+
+            self.index_outputs(&block)?;
+            self.record_deposits(&block.deposits).await?;
+            self.record_spends(&block.spends)?;
+        }
+
+        info!("\n✅ Backfill complete");
+        Ok(())
+    }
+
+    /// Post blocks `from..=to` that checkpoint mode skipped, with
+    /// `fillMoneroBlock`, so transactions in them can be proven. They must
+    /// lie below the contract's latest block; ones it already has are left
+    /// alone. Returns how many were posted.
+    async fn fill_blocks<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        from: u64,
+        to: u64,
+    ) -> Result<u64>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        anyhow::ensure!(
+            self.config.mode == OracleMode::Single && self.safe.get().is_none(),
+            "Filling blocks needs ORACLE_MODE=single without SAFE_ADDRESS"
+        );
+        if !self.check_oracle_role(contract).await? {
+            anyhow::bail!("No configured key holds the oracle role; can't fill blocks");
+        }
+        anyhow::ensure!(
+            !self
+                .status
+                .read()
+                .expect("status lock poisoned")
+                .paused_by_operator,
+            "Paused by an operator"
+        );
+        anyhow::ensure!(from <= to, "Empty block range {}..{}", from, to);
+
+        let latest_posted: u64 = contract
+            .latestMoneroBlock()
+            .call()
+            .await?
+            .latestMoneroBlock
+            .try_into()
+            .unwrap_or(0);
+        anyhow::ensure!(
+            to < latest_posted,
+            "Block {} is not below the contract's latest block {}; it is posted as usual",
+            to,
+            latest_posted
+        );
+        info!("\n🧩 Filling skipped blocks {} to {}", from, to);
+
+        let mut filled = 0;
+        let mut blocks = pin!(self.process_blocks(from, to));
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            if eth::get_block(contract, block.height).await?.is_some() {
+                info!("   Block {} is already posted", block.height);
+                continue;
+            }
+
+            let intent = Intent::PostBlock {
+                contract: *contract.address(),
+                height: block.height,
+                block_hash: block.block_hash,
+            };
+            let call = contract.fillMoneroBlock(
+                U256::from(block.height),
+                block.block_hash,
+                block.tx_merkle_root,
+                block.output_merkle_root,
+            );
+            let receipt = match self.send(contract.provider(), call, Some(&intent)).await {
+                Ok(receipt) => receipt,
+                Err(e) => {
+                    self.handle_revert(e, &format!("Filling block {}", block.height))
+                        .await
+                        .with_context(|| format!("Failed to fill block {}", block.height))?;
+                    self.db.complete_operation(&intent.key(), None)?;
+                    continue;
+                }
+            };
+            info!(
+                "   ✅ Filled block {} in Unichain block {}",
+                block.height,
+                receipt.block_number.unwrap_or(0)
+            );
+            metrics::get().blocks_posted.inc();
+
+            // Left out of the reorg window: it only tracks the chain tip
+            self.db.record_posted_block(&PostedBlockRecord {
+                height: block.height,
+                block_hash: block.block_hash,
+                tx_merkle_root: block.tx_merkle_root,
+                output_merkle_root: block.output_merkle_root,
+                unichain_tx_hash: receipt.transaction_hash,
+                unichain_block: receipt.block_number,
+                gas_used: receipt.gas_used.try_into().unwrap_or(u64::MAX),
+                posted_at: Utc::now(),
+            })?;
+            self.feed.publish(BridgeEvent::BlockPosted {
+                contract: *contract.address(),
+                height: block.height,
+                block_hash: block.block_hash,
+                unichain_tx_hash: receipt.transaction_hash,
+            });
+            self.index_outputs(&block)?;
+            self.record_deposits(&block.deposits).await?;
+            filled += 1;
+        }
+        Ok(filled)
+    }
+
+    /// Sign every confirmed block from `from` to `to` that this signer hasn't
+    /// attested yet; the attestations are served at `/attestation/:height`
+    /// and pushed to any gossip peers
+    async fn attest_blocks(&self, attester: &Attester, from: u64, to: u64) -> Result<()> {
+        let signer = attester.signer.address();
+        let mut signed = Vec::new();
+        for height in from..=to {
+            let header = self.monero().get_block_header_by_height(height).await?;
+            let block_hash = parse_hex_to_b256(&header.hash)?;
+            if let Some(existing) = self.db.attestation(height, signer)? {
+                if existing.block_hash == block_hash {
+                    continue;
+                }
+                warn!(
+                    "   ⚠️  Block {} changed since it was attested; re-signing",
+                    height
+                );
+            }
+
+            let block = self.process_block(height).await?;
+            let attestation = attester.sign(&block).await?;
+            self.db.record_attestation(&attestation)?;
+            metrics::get().attestations_signed.inc();
+            info!("   ✍️  Attested block {}", height);
+            signed.push(attestation);
+        }
+        if let Some(gossip) = &attester.gossip {
+            gossip.broadcast(&signed);
+        }
+        Ok(())
+    }
+
+    /// Sign `block` and gather peer attestations for it, or with a
+    /// threshold key have the participants sign it. Returns the signatures
+    /// to post once the threshold is met, `None` while still waiting on
+    /// peers.
+    async fn collect_signatures<T, P>(
+        &self,
+        contract: &WrappedMonero::WrappedMoneroInstance<T, P>,
+        attester: &Attester,
+        block: &ProcessedBlock,
+    ) -> Result<Option<BlockSignatures>>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+    {
+        let own = attester.sign(block).await?;
+        self.db.record_attestation(&own)?;
+        metrics::get().attestations_signed.inc();
+
+        if let Some(coordinator) = &attester.coordinator {
+            let signature = coordinator
+                .sign(&own)
+                .await
+                .with_context(|| format!("Threshold signing of block {} failed", block.height))?;
+            return Ok(signature.map(BlockSignatures::Threshold));
+        }
+
+        let threshold = contract.signerThreshold().call().await?.signerThreshold;
+        let threshold: usize = threshold.try_into().unwrap_or(usize::MAX);
+
+        let mut attestations = Vec::new();
+        if contract.isSigner(own.signer).call().await?._0 {
+            attestations.push(own.clone());
+        }
+
+        // Gossiped attestations are already stored, pulling from peers
+        // only when short of the threshold; then those of ATTESTATION_PEERS
+        let mut candidates: Vec<(Option<&str>, Attestation)> = Vec::new();
+        if let Some(gossip) = &attester.gossip {
+            gossip.broadcast(std::slice::from_ref(&own));
+            let gossiped = || -> Result<Vec<Attestation>> {
+                Ok(self
+                    .db
+                    .attestations(block.height)?
+                    .into_iter()
+                    .filter(|attestation| attestation.signer != own.signer)
+                    .collect())
+            };
+            let mut stored = gossiped()?;
+            if attestations.len() + stored.len() < threshold
+                && gossip.pull(&self.db, block.height).await? > 0
+            {
+                stored = gossiped()?;
+            }
+            candidates.extend(stored.into_iter().map(|attestation| (None, attestation)));
+        }
+        for peer in &self.config.attestation_peers {
+            match self.fetch_attestation(peer, block.height).await {
+                Ok(Some(attestation)) => candidates.push((Some(peer.as_str()), attestation)),
+                Ok(None) => {}
+                Err(e) => warn!("   ⚠️  Attestation peer {} failed: {:#}", peer, e),
+            }
+        }
+
+        for (peer, attestation) in candidates {
+            if attestations
+                .iter()
+                .any(|collected| collected.signer == attestation.signer)
+            {
+                continue;
+            }
+            if !attestation.same_block(&own) {
+                warn!(
+                    "   ⚠️  {} attested different data for block {}",
+                    attestation.signer, block.height
+                );
+                continue;
+            }
+            if let Err(e) = attestation.verify(&attester.domain) {
+                warn!(
+                    "   ⚠️  Invalid attestation from {}: {:#}",
+                    peer.unwrap_or("gossip"),
+                    e
+                );
+                continue;
+            }
+            if !contract.isSigner(attestation.signer).call().await?._0 {
+                warn!("   ⚠️  {} is not a registered signer", attestation.signer);
+                continue;
+            }
+
+            if peer.is_some() {
+                self.db.record_attestation(&attestation)?;
+                metrics::get().attestations_collected.inc();
+            }
+            attestations.push(attestation);
+        }
+
+        let signatures = attestation::aggregate(&attestations, threshold);
+        if signatures.is_none() {
+            info!(
+                "   ⏳ Block {} has {}/{} attestations; waiting for signers",
+                block.height,
+                attestations.len(),
+                threshold
+            );
+        }
+        Ok(signatures.map(BlockSignatures::Signers))
+    }
+
+    /// A peer's attestation for `height`, `None` if it hasn't signed it yet
+    async fn fetch_attestation(&self, peer: &str, height: u64) -> Result<Option<Attestation>> {
+        let response = self
+            .peer_client
+            .get(format!("{}/attestation/{}", peer, height))
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Send an oracle transaction with the configured gas strategy.
+    ///
+    /// With an `intent`, the send is journaled under its idempotency key:
+    /// every broadcast is recorded, and if an earlier attempt (possibly
+    /// before a restart) already has a successful receipt, that receipt is
+    /// returned instead of sending again. Sends without one are rollbacks.
+    ///
+    /// A transaction that is mined but reverts is an error, carrying the
+    /// reason [`eth::reverted_receipt`] finds, so callers only see receipts
+    /// of calls that took effect and hand the rest to
+    /// [`handle_revert`](Self::handle_revert) like a send that failed its
+    /// gas estimate.
+    ///
+    /// The gas paid is recorded for cost reports, whether or not the
+    /// transaction succeeded. Nothing is sent once the gas budget is used
+    /// up, or with `LEASE_INSTANCE_ID` once the lease is about to run out,
+    /// even partway through a poll.
+    async fn send<'a, T, P, D>(
+        &self,
+        provider: &'a P,
+        call: CallBuilder<T, &'a P, D>,
+        intent: Option<&Intent>,
+    ) -> Result<TransactionReceipt>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+        D: CallDecoder + Clone,
+    {
+        if let Some(lease) = &self.lease {
+            lease.ensure_held()?;
+        }
+        let key = match intent {
+            Some(intent) => {
+                if let Some(receipt) = self.find_operation_receipt(provider, intent).await? {
+                    info!(
+                        "   ♻️  Already sent as {}, not sending again",
+                        receipt.transaction_hash
+                    );
+                    return Ok(receipt);
+                }
+                Some(intent.key())
+            }
+            None => None,
+        };
+        let on_sent = |tx_hash| {
+            if let Some(key) = &key {
+                if let Err(e) = self.db.record_operation_tx(key, tx_hash) {
+                    warn!("   ⚠️  Failed to journal {} for {}: {:#}", tx_hash, key, e);
+                }
+            }
+        };
+        let kind = intent.map_or(CostKind::Rollback, |intent| intent.kind().into());
+        let receipt = self.send_tx(provider, call.clone(), kind, on_sent).await?;
+        if !receipt.status() {
+            return Err(eth::reverted_receipt(call, &receipt).await);
+        }
+        if let Some(key) = &key {
+            self.db
+                .complete_operation(key, Some(receipt.transaction_hash))?;
+        }
+        Ok(receipt)
+    }
+
+    /// Send `call` from the oracle key, unjournaled, and record its cost
+    /// as `kind`
+    async fn send_tx<'a, T, P, D>(
+        &self,
+        provider: &'a P,
+        call: CallBuilder<T, &'a P, D>,
+        kind: CostKind,
+        on_sent: impl Fn(B256),
+    ) -> Result<TransactionReceipt>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T> + Clone,
+        D: CallDecoder + Clone,
+    {
+        let oracle_address = self
+            .status
+            .read()
+            .expect("status lock poisoned")
+            .oracle_address
+            .context("Oracle address not initialized")?;
+        if let Some(exceeded) = self.gas_budget_exceeded()? {
+            anyhow::bail!("Not sending: {}", exceeded);
+        }
+        let gas = self.gas.read().expect("gas config poisoned").clone();
+        let receipt = eth::send_with_fee_bumps(
+            provider,
+            oracle_address,
+            &gas,
+            &self.nonces,
+            call.from(oracle_address),
+            on_sent,
+        )
+        .await?;
+        let cost = TxCost::gas(
+            kind,
+            receipt.transaction_hash,
+            receipt.gas_used.try_into().unwrap_or(u64::MAX),
+            receipt.effective_gas_price,
+        );
+        if let Err(e) = self.db.record_tx_cost(&cost) {
+            warn!(
+                "   ⚠️  Failed to record the cost of {}: {:#}",
+                receipt.transaction_hash, e
+            );
+        }
+        Ok(receipt)
+    }
+
+    /// Act on a failed send by its revert reason. Returns `Ok` when the
+    /// contract already has what the call was for; otherwise returns the
+    /// error, after alerting or halting sends as the reason calls for.
+    /// Failures other than reverts are returned as they are.
+    async fn handle_revert(&self, error: anyhow::Error, action: &str) -> Result<()> {
+        let Some(revert) = Revert::from_error(&error) else {
+            return Err(error);
+        };
+        let recovery = revert.recovery();
+        metrics::get()
+            .contract_reverts
+            .with_label_values(&[&recovery.to_string()])
+            .inc();
+        match recovery {
+            Recovery::Skip => {
+                warn!("   ⚠️  {} reverted with {}; nothing to do", action, revert);
+                return Ok(());
+            }
+            Recovery::Retry => {}
+            Recovery::Alert => {
+                self.alerts
+                    .notify(Alert::ContractRevert {
+                        action: action.to_string(),
+                        reason: revert.to_string(),
+                        halted: false,
+                    })
+                    .await;
+            }
+            Recovery::Halt => {
+                self.status
+                    .write()
+                    .expect("status lock poisoned")
+                    .paused_by_operator = true;
+                error!(
+                    "🛑 {} reverted with {}; no transactions are sent until resumed \
+                     (POST /admin/resume or a restart)",
+                    action, revert
+                );
+                self.alerts
+                    .notify(Alert::ContractRevert {
+                        action: action.to_string(),
+                        reason: revert.to_string(),
+                        halted: true,
+                    })
+                    .await;
+            }
+        }
+        Err(error.context(format!(
+            "{} reverted with {} ({})",
+            action, revert, recovery
+        )))
+    }
+
+    /// Journal `intent` and return the successful receipt of a transaction
+    /// an earlier attempt sent for it, if there is one
+    async fn find_operation_receipt<T, P>(
+        &self,
+        provider: &P,
+        intent: &Intent,
+    ) -> Result<Option<TransactionReceipt>>
+    where
+        T: alloy::transports::Transport + Clone,
+        P: Provider<T>,
+    {
+        let operation = self.db.begin_operation(intent)?;
+        for tx_hash in operation.candidate_txs() {
+            let receipt = eth::find_receipt(provider, &[tx_hash]).await?;
+            if let Some(receipt) = receipt.filter(|receipt| receipt.status()) {
+                self.db.complete_operation(&operation.key, Some(tx_hash))?;
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests;
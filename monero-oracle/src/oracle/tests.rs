@@ -0,0 +1,1356 @@
+//! Behavior tests of [`OracleService`] against a mock monerod,
+//! monero-wallet-rpc and Unichain node. A restart is a new service on the
+//! same database.
+
+use super::*;
+use crate::{
+    fixtures::{synthetic, Fixtures, RecordedBlock},
+    journal::OperationStatus,
+    monero_rpc::JsonRpcRequest,
+};
+use alloy::{
+    consensus::{Transaction as _, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    primitives::keccak256,
+    sol_types::{Revert as RequireFailed, SolCall, SolError, SolInterface},
+};
+use axum::{body::Bytes as Body, extract::State, routing::post, Json, Router};
+use chrono::TimeDelta;
+use serde_json::{json, Value};
+use std::fs;
+
+/// First and last block of the mock Monero chain
+const FIRST: u64 = 100;
+const LAST: u64 = 112;
+
+/// Anvil's first key, the contract's oracle
+const ORACLE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+const BRIDGE: Address = Address::repeat_byte(0xcc);
+
+/// Piconero each test withdrawal pays
+const AMOUNT: u64 = 1_000_000_000_000;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("monero-oracle-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// monerod serving the synthetic chain up to `tip`, and the bridge's
+/// monero-wallet-rpc, at one address
+struct MoneroNode {
+    dir: PathBuf,
+    state: Mutex<Monero>,
+}
+
+#[derive(Default)]
+struct Monero {
+    tip: u64,
+    /// Payouts monerod knows, by hash, with the block that mined them
+    payouts: HashMap<String, Option<u64>>,
+    transfers: u64,
+    relays: u64,
+}
+
+impl MoneroNode {
+    /// The recorded `get_block` response for `height`
+    fn block(&self, height: u64) -> Value {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method: "get_block",
+            params: json!({ "height": height }),
+        };
+        let response = Fixtures::Replay(self.dir.clone())
+            .load(
+                "get_block",
+                "/json_rpc",
+                &serde_json::to_vec(&request).unwrap(),
+            )
+            .unwrap()
+            .unwrap();
+        serde_json::from_slice(&response).unwrap()
+    }
+
+    fn json_rpc(&self, method: &str, params: &Value) -> Result<Value, Value> {
+        let mut state = self.state.lock().unwrap();
+        let height = params["height"].as_u64().unwrap_or(state.tip);
+        if height > state.tip {
+            return Err(json!({ "code": -2, "message": "Requested block height too big" }));
+        }
+        Ok(match method {
+            "get_last_block_header" | "get_block_header_by_height" => json!({
+                "block_header": self.block(height)["result"]["block_header"],
+                "status": "OK",
+            }),
+            "get_block" => self.block(height)["result"].clone(),
+            "get_balance" => json!({ "balance": 10 * AMOUNT, "unlocked_balance": 10 * AMOUNT }),
+            "incoming_transfers" => json!({ "transfers": [{
+                "amount": 10 * AMOUNT,
+                "key_image": hex::encode(keccak256("bridge output")),
+                "tx_hash": hex::encode(keccak256("bridge deposit")),
+                "spent": false,
+            }]}),
+            "transfer" => {
+                state.transfers += 1;
+                let tx_hash = hex::encode(keccak256(format!("payout {}", state.transfers)));
+                let amount: u64 = params["destinations"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|destination| destination["amount"].as_u64().unwrap())
+                    .sum();
+                json!({
+                    "tx_hash": tx_hash,
+                    "tx_key": "ab".repeat(32),
+                    "amount": amount,
+                    "fee": 1000,
+                    "tx_metadata": tx_hash,
+                })
+            }
+            "relay_tx" => {
+                // The metadata of a mock transfer is its hash
+                let tx_hash = params["hex"].as_str().unwrap().to_string();
+                state.relays += 1;
+                state.payouts.entry(tx_hash.clone()).or_insert(None);
+                json!({ "tx_hash": tx_hash })
+            }
+            _ => return Err(json!({ "code": -32601, "message": "Method not found" })),
+        })
+    }
+
+    /// A block's transactions from its fixture, else the payouts monerod
+    /// knows
+    fn get_transactions(&self, body: &[u8]) -> Value {
+        let file = Fixtures::file_name("get_transactions", "/get_transactions", body);
+        if let Ok(recorded) = fs::read(self.dir.join(file)) {
+            return serde_json::from_slice(&recorded).unwrap();
+        }
+        let request: Value = serde_json::from_slice(body).unwrap();
+        let state = self.state.lock().unwrap();
+        let txs: Vec<Value> = request["txs_hashes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|hash| {
+                let mined = state.payouts.get(hash.as_str()?)?;
+                Some(json!({
+                    "tx_hash": hash,
+                    "as_hex": "",
+                    "block_height": mined,
+                    "in_pool": mined.is_none(),
+                }))
+            })
+            .collect();
+        json!({ "status": "OK", "txs": txs })
+    }
+}
+
+async fn monero_json_rpc(
+    State(node): State<Arc<MoneroNode>>,
+    Json(request): Json<Value>,
+) -> Json<Value> {
+    let method = request["method"].as_str().unwrap_or_default();
+    Json(match node.json_rpc(method, &request["params"]) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+    })
+}
+
+async fn monero_get_transactions(State(node): State<Arc<MoneroNode>>, body: Body) -> Json<Value> {
+    Json(node.get_transactions(&body))
+}
+
+/// What the mock bridge contract holds
+#[derive(Clone, Default)]
+struct Contract {
+    oracle: Address,
+    latest: u64,
+    blocks: BTreeMap<u64, StoredBlock>,
+    paused: bool,
+    /// Lease holder and when the lease runs out
+    lease: (B256, u64),
+    output_version: u8,
+    tx_version: u8,
+    confirmed_burns: Vec<u64>,
+}
+
+impl Contract {
+    /// Run `input` from `from` at time `now`, returning its output or the
+    /// `require` message it reverts with
+    fn execute(&mut self, from: Address, input: &[u8], now: u64) -> Result<Vec<u8>, String> {
+        use WrappedMonero::{WrappedMoneroCalls as Call, *};
+
+        let Ok(call) = Call::abi_decode(input, false) else {
+            return Ok(vec![]);
+        };
+        let only_oracle = |contract: &Self| match from == contract.oracle {
+            true => Ok(()),
+            false => Err("Only oracle".to_string()),
+        };
+        let not_paused = |contract: &Self| match contract.paused {
+            true => Err("EnforcedPause".to_string()),
+            false => Ok(()),
+        };
+        Ok(match call {
+            Call::oracle(_) => oracleCall::abi_encode_returns(&(self.oracle,)),
+            Call::latestMoneroBlock(_) => {
+                latestMoneroBlockCall::abi_encode_returns(&(U256::from(self.latest),))
+            }
+            Call::moneroBlocks(call) => {
+                let stored = self.blocks.get(&call.blockHeight.to::<u64>());
+                let block = stored.copied().unwrap_or(StoredBlock {
+                    block_hash: B256::ZERO,
+                    tx_merkle_root: B256::ZERO,
+                    output_merkle_root: B256::ZERO,
+                });
+                moneroBlocksCall::abi_encode_returns(&(
+                    block.block_hash,
+                    block.tx_merkle_root,
+                    block.output_merkle_root,
+                    U256::ZERO,
+                    stored.is_some(),
+                ))
+            }
+            Call::paused(_) => pausedCall::abi_encode_returns(&(self.paused,)),
+            Call::outputTreeVersion(_) => {
+                outputTreeVersionCall::abi_encode_returns(&(self.output_version,))
+            }
+            Call::txTreeVersion(_) => txTreeVersionCall::abi_encode_returns(&(self.tx_version,)),
+            Call::getLease(_) => {
+                let (holder, until) = self.lease;
+                getLeaseCall::abi_encode_returns(&(holder, U256::from(until.saturating_sub(now))))
+            }
+            Call::postMoneroBlock(call) => {
+                only_oracle(self)?;
+                not_paused(self)?;
+                let height = call.blockHeight.to::<u64>();
+                if height <= self.latest {
+                    return Err("Height must increase".to_string());
+                }
+                self.store(
+                    height,
+                    call.blockHash,
+                    call.txMerkleRoot,
+                    call.outputMerkleRoot,
+                );
+                self.latest = height;
+                vec![]
+            }
+            Call::fillMoneroBlock(call) => {
+                only_oracle(self)?;
+                not_paused(self)?;
+                let height = call.blockHeight.to::<u64>();
+                if height >= self.latest {
+                    return Err("Not below latest block".to_string());
+                }
+                if self.blocks.contains_key(&height) {
+                    return Err("Block exists".to_string());
+                }
+                self.store(
+                    height,
+                    call.blockHash,
+                    call.txMerkleRoot,
+                    call.outputMerkleRoot,
+                );
+                vec![]
+            }
+            Call::acquireLease(call) => {
+                only_oracle(self)?;
+                let (holder, until) = self.lease;
+                if holder != call.holder && until > now {
+                    return Err("Lease held".to_string());
+                }
+                self.lease = (call.holder, now + call.duration.to::<u64>());
+                vec![]
+            }
+            Call::releaseLease(call) => {
+                if self.lease.0 == call.holder {
+                    self.lease = (B256::ZERO, 0);
+                }
+                vec![]
+            }
+            Call::confirmWithdrawal(call) => {
+                only_oracle(self)?;
+                let burn_id = call.burnId.to::<u64>();
+                if self.confirmed_burns.contains(&burn_id) {
+                    return Err("Already processed".to_string());
+                }
+                self.confirmed_burns.push(burn_id);
+                vec![]
+            }
+            _ => vec![],
+        })
+    }
+
+    fn store(&mut self, height: u64, block_hash: B256, tx_root: B256, output_root: B256) {
+        self.blocks.insert(
+            height,
+            StoredBlock {
+                block_hash,
+                tx_merkle_root: tx_root,
+                output_merkle_root: output_root,
+            },
+        );
+    }
+}
+
+/// A transaction the mock node accepted
+#[derive(Debug, Clone)]
+struct Sent {
+    hash: B256,
+    from: Address,
+    nonce: u64,
+    max_fee_per_gas: u128,
+    input: Vec<u8>,
+}
+
+impl Sent {
+    fn calls<C: SolCall>(&self) -> bool {
+        self.input.starts_with(&C::SELECTOR)
+    }
+}
+
+/// A Unichain node mining each transaction into its own block as it
+/// arrives, unless told to hold some in the mempool
+#[derive(Default)]
+struct Unichain {
+    contract: Contract,
+    /// Contract time, in seconds
+    now: u64,
+    block_number: u64,
+    /// The oracle's next nonce
+    nonce: u64,
+    /// Held in the mempool
+    pending: Option<Sent>,
+    /// Transactions still to hold rather than mine
+    hold: usize,
+    sent: Vec<Sent>,
+    /// Receipts, with the contract as it was before the transaction
+    receipts: HashMap<B256, (Value, Contract)>,
+}
+
+fn revert(reason: &str) -> Value {
+    json!({
+        "code": 3,
+        "message": format!("execution reverted: {}", reason),
+        "data": format!("0x{}", hex::encode(RequireFailed::from(reason).abi_encode())),
+    })
+}
+
+fn quantity(value: impl Into<u128>) -> String {
+    format!("{:#x}", value.into())
+}
+
+impl Unichain {
+    fn rpc(&mut self, method: &str, params: &Value) -> Result<Value, Value> {
+        Ok(match method {
+            "eth_chainId" => json!("0x7a69"),
+            "eth_blockNumber" => json!(quantity(self.block_number)),
+            "eth_getBalance" => json!(quantity(10u128 * 10u128.pow(18))),
+            "eth_getTransactionCount" => {
+                let pending = params[1] == "pending" && self.pending.is_some();
+                json!(quantity(self.nonce + pending as u64))
+            }
+            "eth_feeHistory" => json!({
+                "oldestBlock": quantity(self.block_number),
+                "baseFeePerGas": vec![quantity(1_000_000_000u64); 11],
+                "gasUsedRatio": vec![0.5; 10],
+                "reward": vec![vec![quantity(1_000_000_000u64)]; 10],
+            }),
+            "eth_estimateGas" | "eth_call" => {
+                let request = &params[0];
+                let from = request["from"]
+                    .as_str()
+                    .map_or(Address::ZERO, |from| from.parse().unwrap());
+                let input = request["input"].as_str().or(request["data"].as_str());
+                let input = hex::decode(input.unwrap_or("0x").trim_start_matches("0x")).unwrap();
+                let output = self
+                    .contract
+                    .clone()
+                    .execute(from, &input, self.now)
+                    .map_err(|reason| revert(&reason))?;
+                match method {
+                    "eth_call" => json!(format!("0x{}", hex::encode(output))),
+                    _ => json!(quantity(0x30000u64)),
+                }
+            }
+            "eth_sendRawTransaction" => {
+                let raw =
+                    hex::decode(params[0].as_str().unwrap().trim_start_matches("0x")).unwrap();
+                let tx = TxEnvelope::decode_2718(&mut raw.as_slice()).unwrap();
+                let sent = Sent {
+                    hash: *tx.tx_hash(),
+                    from: tx.recover_signer().unwrap(),
+                    nonce: tx.nonce(),
+                    max_fee_per_gas: tx.max_fee_per_gas(),
+                    input: tx.input().to_vec(),
+                };
+                if sent.nonce < self.nonce {
+                    return Err(json!({ "code": -32000, "message": "nonce too low" }));
+                }
+                if let Some(pending) = self.pending.as_ref().filter(|p| p.nonce == sent.nonce) {
+                    if sent.max_fee_per_gas * 100 < pending.max_fee_per_gas * 110 {
+                        return Err(json!({
+                            "code": -32000,
+                            "message": "replacement transaction underpriced",
+                        }));
+                    }
+                }
+                self.sent.push(sent.clone());
+                if self.hold > 0 {
+                    self.hold -= 1;
+                    self.pending = Some(sent.clone());
+                } else {
+                    self.mine(sent.clone());
+                }
+                json!(sent.hash)
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: B256 = serde_json::from_value(params[0].clone()).unwrap();
+                self.receipts
+                    .get(&hash)
+                    .map_or(Value::Null, |(receipt, _)| receipt.clone())
+            }
+            "eth_getBlockByNumber" => Value::Null,
+            _ => return Err(json!({ "code": -32601, "message": "Method not found" })),
+        })
+    }
+
+    fn mine(&mut self, sent: Sent) {
+        self.pending = None;
+        let before = self.contract.clone();
+        let mut after = self.contract.clone();
+        let succeeded = after.execute(sent.from, &sent.input, self.now).is_ok();
+        if succeeded {
+            self.contract = after;
+        }
+        self.block_number += 1;
+        self.nonce = sent.nonce + 1;
+        let receipt = json!({
+            "type": "0x2",
+            "status": if succeeded { "0x1" } else { "0x0" },
+            "cumulativeGasUsed": "0x30000",
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "transactionHash": sent.hash,
+            "transactionIndex": "0x0",
+            "blockHash": keccak256(self.block_number.to_be_bytes()),
+            "blockNumber": quantity(self.block_number),
+            "gasUsed": "0x30000",
+            "effectiveGasPrice": quantity(2_000_000_000u64),
+            "from": sent.from,
+            "to": BRIDGE,
+            "contractAddress": null,
+        });
+        self.receipts.insert(sent.hash, (receipt, before));
+    }
+
+    /// Mine the transaction held in the mempool
+    fn mine_held(&mut self) {
+        let held = self.pending.clone().expect("no transaction held");
+        self.mine(held);
+    }
+
+    /// Reorg `hash` out of the chain, back into the mempool
+    fn reorg_out(&mut self, hash: B256) {
+        let (_, before) = self.receipts.remove(&hash).expect("not mined");
+        let sent = self.sent.iter().find(|sent| sent.hash == hash).unwrap();
+        self.contract = before;
+        self.nonce = sent.nonce;
+        self.pending = Some(sent.clone());
+        self.block_number += 1;
+    }
+
+    /// Sent transactions calling `C`
+    fn sent<C: SolCall>(&self) -> Vec<Sent> {
+        self.sent
+            .iter()
+            .filter(|s| s.calls::<C>())
+            .cloned()
+            .collect()
+    }
+}
+
+async fn unichain_rpc(
+    State(chain): State<Arc<Mutex<Unichain>>>,
+    Json(request): Json<Value>,
+) -> Json<Value> {
+    let method = request["method"].as_str().unwrap_or_default();
+    let result = chain.lock().unwrap().rpc(method, &request["params"]);
+    Json(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+    })
+}
+
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    url
+}
+
+/// Mock nodes with the synthetic chain `FIRST..=LAST` on Monero and the
+/// contract at `FIRST - 1`, and settings for oracles against them
+struct Harness {
+    dir: PathBuf,
+    blocks: Vec<RecordedBlock>,
+    monero: Arc<MoneroNode>,
+    unichain: Arc<Mutex<Unichain>>,
+    monero_url: String,
+    unichain_url: String,
+}
+
+impl Harness {
+    /// Nodes with the Monero tip at `FIRST + 2`, so `FIRST` is confirmed
+    async fn new(name: &str) -> Self {
+        let dir = scratch_dir(name);
+        let hasher = MerkleHasher::default();
+        let blocks = synthetic::synthesize_chain(FIRST, LAST, 1, hasher, hasher, &dir)
+            .await
+            .unwrap();
+        let monero = Arc::new(MoneroNode {
+            dir: dir.clone(),
+            state: Mutex::new(Monero {
+                tip: FIRST + 2,
+                ..Monero::default()
+            }),
+        });
+        let parent = monero.block(FIRST)["result"]["block_header"]["prev_hash"].clone();
+        let mut contract = Contract {
+            oracle: ORACLE_KEY
+                .parse::<alloy::signers::local::PrivateKeySigner>()
+                .unwrap()
+                .address(),
+            latest: FIRST - 1,
+            output_version: hasher.version(),
+            tx_version: hasher.version(),
+            ..Contract::default()
+        };
+        contract.store(
+            FIRST - 1,
+            serde_json::from_value::<B256>(json!(format!("0x{}", parent.as_str().unwrap())))
+                .unwrap(),
+            B256::ZERO,
+            B256::ZERO,
+        );
+        let unichain = Arc::new(Mutex::new(Unichain {
+            contract,
+            now: 1_700_000_000,
+            ..Unichain::default()
+        }));
+
+        let monero_url = serve(
+            Router::new()
+                .route("/json_rpc", post(monero_json_rpc))
+                .route("/get_transactions", post(monero_get_transactions))
+                .with_state(monero.clone()),
+        )
+        .await;
+        let unichain_url = serve(
+            Router::new()
+                .route("/", post(unichain_rpc))
+                .with_state(unichain.clone()),
+        )
+        .await;
+        Self {
+            dir,
+            blocks,
+            monero,
+            unichain,
+            monero_url,
+            unichain_url,
+        }
+    }
+
+    /// An oracle with its database in `oracle.db` and `settings` (TOML)
+    /// on top of the harness's
+    fn oracle(&self, settings: &str) -> OracleService {
+        self.oracle_on("oracle.db", settings)
+    }
+
+    fn oracle_on(&self, database: &str, settings: &str) -> OracleService {
+        settings::set_for_test(&format!(
+            "bridge_address = \"{}\"\n\
+             private_key = \"{}\"\n\
+             unichain_rpc_url = \"{}\"\n\
+             monero_rpc_url = \"{}\"\n\
+             monero_wallet_rpc_url = \"{}\"\n\
+             database_path = \"{}\"\n\
+             min_confirmations = 2\n\
+             rpc_max_retries = 0\n\
+             withdrawal_deduct_fee = false\n\
+             {}",
+            BRIDGE,
+            ORACLE_KEY,
+            self.unichain_url,
+            self.monero_url,
+            self.monero_url,
+            self.dir.join(database).display(),
+            settings
+        ));
+        OracleService::new(Config::from_env().unwrap()).unwrap()
+    }
+
+    fn chain(&self) -> std::sync::MutexGuard<'_, Unichain> {
+        self.unichain.lock().unwrap()
+    }
+
+    fn monero(&self) -> std::sync::MutexGuard<'_, Monero> {
+        self.monero.state.lock().unwrap()
+    }
+
+    /// Payouts the mock wallet signed and relayed
+    fn payouts_made(&self) -> (u64, u64) {
+        let monero = self.monero();
+        (monero.transfers, monero.relays)
+    }
+
+    fn block(&self, height: u64) -> &RecordedBlock {
+        &self.blocks[(height - FIRST) as usize]
+    }
+
+    /// Journal key of posting `height`
+    fn posting(&self, height: u64) -> String {
+        Intent::PostBlock {
+            contract: BRIDGE,
+            height,
+            block_hash: self.block(height).block_hash,
+        }
+        .key()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Connect `service` to the mock Unichain as [`OracleService::start`] does,
+/// resuming what the database journaled
+async fn connect(service: &OracleService) -> impl Provider<Http<reqwest::Client>> + Clone {
+    let signer = OracleSigner::connect(service.signer_config().unwrap())
+        .await
+        .unwrap();
+    let address = signer.address();
+    let _ = service.oracle_keys.set(vec![address]);
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(signer.wallet())
+        .on_client(service.unichain().unwrap());
+
+    service
+        .resume_from_checkpoint(&WrappedMonero::new(BRIDGE, &provider))
+        .await
+        .unwrap();
+    service.resume_operations(&provider).await.unwrap();
+    let mut status = service.status.write().unwrap();
+    status.oracle_address = Some(address);
+    status.bridge_address = Some(BRIDGE);
+    drop(status);
+    provider
+}
+
+async fn poll<P>(service: &OracleService, provider: &P) -> Result<()>
+where
+    P: Provider<Http<reqwest::Client>> + Clone,
+{
+    service
+        .poll(&WrappedMonero::new(BRIDGE, provider), None)
+        .await
+}
+
+fn withdrawal(burn_id: u64) -> Withdrawal {
+    Withdrawal {
+        burn_id,
+        user: Address::repeat_byte(0x11),
+        lp: Address::repeat_byte(0x22),
+        amount: AMOUNT,
+        xmr_address: "4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx".to_string(),
+        unichain_tx_hash: B256::repeat_byte(0x33),
+        unichain_block: 1,
+        status: WithdrawalStatus::Queued,
+        error: None,
+        attempts: 0,
+        xmr_tx_hash: None,
+        xmr_tx_key: None,
+        xmr_fee: None,
+        xmr_amount: None,
+    }
+}
+
+/// Persist burn `burn_id` as paid up to `status`, the way an oracle that
+/// crashed there left it: its payout signed and journaled, the journal
+/// entry not yet completed. Returns the payout's hash.
+fn seed_payout(db: &Database, burn_id: u64, status: WithdrawalStatus) -> B256 {
+    db.queue_withdrawal(&withdrawal(burn_id)).unwrap();
+    let intent = Intent::payout([burn_id]);
+    db.begin_operation(&intent).unwrap();
+    let tx_hash = keccak256(format!("seeded payout {}", burn_id));
+    let transfer = SignedTransfer {
+        tx_hash,
+        tx_key: "ab".repeat(32),
+        amount: AMOUNT,
+        fee: 1000,
+        tx_metadata: hex::encode(tx_hash),
+    };
+    db.record_withdrawals_signed(&[(burn_id, AMOUNT)], &transfer)
+        .unwrap();
+    db.record_operation_tx(&intent.key(), tx_hash).unwrap();
+    let mut at = WithdrawalStatus::Signed;
+    while at != status {
+        at = db.advance_withdrawal(burn_id, at).unwrap();
+    }
+    tx_hash
+}
+
+fn status_of(service: &OracleService, burn_id: u64) -> WithdrawalStatus {
+    service.db.withdrawal(burn_id).unwrap().unwrap().status
+}
+
+#[tokio::test]
+async fn test_restart_after_paying_does_not_pay_again() {
+    let harness = Harness::new("oracle-restart-queued").await;
+    let service = harness.oracle("");
+    service.db.queue_withdrawal(&withdrawal(1)).unwrap();
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::Broadcast);
+    assert_eq!(harness.payouts_made(), (1, 1));
+    drop(service);
+
+    let service = harness.oracle("");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::Broadcast);
+    assert_eq!(harness.payouts_made(), (1, 1));
+}
+
+#[tokio::test]
+async fn test_restart_relays_a_signed_payout_without_signing_again() {
+    let harness = Harness::new("oracle-restart-signed").await;
+    let service = harness.oracle("");
+    let tx_hash = seed_payout(&service.db, 1, WithdrawalStatus::Signed);
+    drop(service);
+
+    let service = harness.oracle("");
+    let provider = connect(&service).await;
+    // Still to relay
+    let key = Intent::payout([1]).key();
+    assert_eq!(
+        service.db.operation(&key).unwrap().unwrap().status,
+        OperationStatus::Pending
+    );
+    poll(&service, &provider).await.unwrap();
+
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::Broadcast);
+    assert_eq!(harness.payouts_made(), (0, 1));
+    let operation = service.db.operation(&key).unwrap().unwrap();
+    assert_eq!(operation.status, OperationStatus::Completed);
+    assert_eq!(operation.tx_hash, Some(tx_hash));
+}
+
+#[tokio::test]
+async fn test_restart_rebroadcasts_a_lost_payout_and_confirms_it() {
+    let harness = Harness::new("oracle-restart-broadcast").await;
+    let service = harness.oracle("");
+    let tx_hash = seed_payout(&service.db, 1, WithdrawalStatus::Broadcast);
+    drop(service);
+
+    let service = harness.oracle("");
+    let provider = connect(&service).await;
+    let key = Intent::payout([1]).key();
+    assert_eq!(
+        service.db.operation(&key).unwrap().unwrap().status,
+        OperationStatus::Completed
+    );
+    // monerod never saw it
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::Broadcast);
+    assert_eq!(harness.payouts_made(), (0, 1));
+
+    harness
+        .monero()
+        .payouts
+        .insert(hex::encode(tx_hash), Some(FIRST));
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::ConfirmedEvm);
+    assert_eq!(harness.chain().contract.confirmed_burns, [1]);
+}
+
+#[tokio::test]
+async fn test_restart_reuses_a_confirmation_mined_after_the_crash() {
+    let harness = Harness::new("oracle-restart-confirmed-xmr").await;
+    // Nothing to post, so the confirmation is the only transaction
+    harness.monero().tip = FIRST + 1;
+    harness.chain().hold = 1;
+    let service = harness.oracle("stuck_tx_timeout_secs = 1\nmax_fee_bumps = 0\n");
+    seed_payout(&service.db, 1, WithdrawalStatus::ConfirmedXmr);
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    let withdrawal = service.db.withdrawal(1).unwrap().unwrap();
+    assert_eq!(withdrawal.status, WithdrawalStatus::ConfirmedXmr);
+    assert_eq!(withdrawal.attempts, 1);
+
+    // Mined while the oracle was down; the retry is due at once
+    harness.chain().mine_held();
+    service
+        .db
+        .set_withdrawal_status(1, WithdrawalStatus::ConfirmedXmr, None)
+        .unwrap();
+    drop(service);
+
+    let service = harness.oracle("");
+    let provider = connect(&service).await;
+    let key = Intent::ConfirmWithdrawal { burn_id: 1 }.key();
+    assert_eq!(
+        service.db.operation(&key).unwrap().unwrap().status,
+        OperationStatus::Completed
+    );
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::ConfirmedEvm);
+    let chain = harness.chain();
+    assert_eq!(
+        chain.sent::<WrappedMonero::confirmWithdrawalCall>().len(),
+        1
+    );
+    assert_eq!(chain.contract.confirmed_burns, [1]);
+}
+
+#[tokio::test]
+async fn test_restart_leaves_a_settled_withdrawal_alone() {
+    let harness = Harness::new("oracle-restart-confirmed-evm").await;
+    let service = harness.oracle("");
+    seed_payout(&service.db, 1, WithdrawalStatus::ConfirmedEvm);
+    drop(service);
+
+    let service = harness.oracle("");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::ConfirmedEvm);
+    assert_eq!(harness.payouts_made(), (0, 0));
+    assert!(harness
+        .chain()
+        .sent::<WrappedMonero::confirmWithdrawalCall>()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_restart_replaces_a_posting_left_pending() {
+    let harness = Harness::new("oracle-restart-posting").await;
+    harness.chain().hold = 1;
+    let service = harness.oracle("stuck_tx_timeout_secs = 1\nmax_fee_bumps = 0\n");
+    let provider = connect(&service).await;
+    assert!(poll(&service, &provider).await.is_err());
+    assert_eq!(harness.chain().contract.latest, FIRST - 1);
+    let key = harness.posting(FIRST);
+    assert_eq!(service.db.operation(&key).unwrap().unwrap().sent.len(), 1);
+    drop(service);
+
+    // The pending transaction takes the first nonce: the new run replaces
+    // it, paying more than it once the node turns a same-fee resend down
+    let service = harness.oracle("");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    let chain = harness.chain();
+    assert_eq!(chain.contract.latest, FIRST);
+    let sent = chain.sent::<WrappedMonero::postMoneroBlockCall>();
+    assert_eq!(sent.len(), 2);
+    assert_eq!((sent[0].nonce, sent[1].nonce), (0, 0));
+    assert!(sent[1].max_fee_per_gas > sent[0].max_fee_per_gas);
+
+    let operation = service.db.operation(&key).unwrap().unwrap();
+    assert_eq!(operation.status, OperationStatus::Completed);
+    assert_eq!(operation.tx_hash, Some(sent[1].hash));
+    let posted = service.db.posted_block(FIRST).unwrap().unwrap();
+    assert_eq!(posted.unichain_tx_hash, sent[1].hash);
+}
+
+/// Heights the mock contract holds, past the harness's `FIRST - 1`
+fn posted_heights(harness: &Harness) -> Vec<u64> {
+    harness
+        .chain()
+        .contract
+        .blocks
+        .keys()
+        .copied()
+        .filter(|&height| height >= FIRST)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_idle_cadence_posts_on_time() {
+    let harness = Harness::new("oracle-cadence-on-time").await;
+    harness.monero().tip = FIRST + 7;
+    let service = harness.oracle("idle_post_interval_blocks = 5\n");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    // Block 105 is the newest confirmed multiple of the interval
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 5]);
+    assert_eq!(harness.chain().contract.latest, FIRST + 5);
+}
+
+#[tokio::test]
+async fn test_idle_cadence_skips_blocks_between_postings() {
+    let harness = Harness::new("oracle-cadence-skipped").await;
+    let service = harness.oracle("idle_post_interval_blocks = 5\n");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(posted_heights(&harness), [FIRST]);
+
+    // 104 is confirmed but not due; nothing is sent until 105 is
+    harness.monero().tip = FIRST + 6;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(posted_heights(&harness), [FIRST]);
+    assert_eq!(
+        harness
+            .chain()
+            .sent::<WrappedMonero::postMoneroBlockCall>()
+            .len(),
+        1
+    );
+
+    harness.monero().tip = FIRST + 7;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 5]);
+}
+
+#[tokio::test]
+async fn test_idle_cadence_posts_late_checkpoints_together() {
+    let harness = Harness::new("oracle-cadence-late").await;
+    // Down past two due postings: the next poll makes them up along with
+    // the newest, and nothing between them
+    harness.monero().tip = LAST;
+    let service = harness.oracle("idle_post_interval_blocks = 5\n");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 5, FIRST + 10]);
+    let chain = harness.chain();
+    assert_eq!(chain.sent::<WrappedMonero::postMoneroBlockCall>().len(), 3);
+    assert_eq!(chain.contract.latest, FIRST + 10);
+}
+
+/// An oracle in checkpoint mode, with `settings` besides, that has posted
+/// `FIRST`, `FIRST + 5` and `FIRST + 10`, skipping the blocks between
+async fn checkpoints_posted(
+    harness: &Harness,
+    settings: &str,
+) -> (OracleService, impl Provider<Http<reqwest::Client>> + Clone) {
+    harness.monero().tip = LAST;
+    let service = harness.oracle(&format!("checkpoint_interval_blocks = 5\n{}", settings));
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(posted_heights(harness), [FIRST, FIRST + 5, FIRST + 10]);
+    (service, provider)
+}
+
+#[tokio::test]
+async fn test_checkpoint_fill_posts_a_gap() {
+    let harness = Harness::new("oracle-fill-gap").await;
+    let (service, provider) = checkpoints_posted(&harness, "").await;
+    let contract = WrappedMonero::new(BRIDGE, &provider);
+
+    let filled = service.fill_blocks(&contract, FIRST + 1, FIRST + 4).await;
+    assert_eq!(filled.unwrap(), 4);
+    assert_eq!(
+        posted_heights(&harness),
+        [
+            FIRST,
+            FIRST + 1,
+            FIRST + 2,
+            FIRST + 3,
+            FIRST + 4,
+            FIRST + 5,
+            FIRST + 10
+        ]
+    );
+    for height in FIRST + 1..=FIRST + 4 {
+        let stored = harness.chain().contract.blocks[&height];
+        assert_eq!(stored.block_hash, harness.block(height).block_hash);
+        let posted = service.db.posted_block(height).unwrap().unwrap();
+        assert_eq!(posted.block_hash, stored.block_hash);
+    }
+    assert_eq!(harness.chain().contract.latest, FIRST + 10);
+
+    // Posted blocks in the range are skipped, not sent again
+    let filled = service.fill_blocks(&contract, FIRST, FIRST + 6).await;
+    assert_eq!(filled.unwrap(), 1);
+    assert_eq!(
+        harness
+            .chain()
+            .sent::<WrappedMonero::fillMoneroBlockCall>()
+            .len(),
+        5
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint_fill_reaches_up_to_the_latest_checkpoint() {
+    let harness = Harness::new("oracle-fill-checkpoint").await;
+    let (service, provider) = checkpoints_posted(&harness, "").await;
+    let contract = WrappedMonero::new(BRIDGE, &provider);
+
+    // The latest checkpoint itself was posted as usual
+    let error = service
+        .fill_blocks(&contract, FIRST + 6, FIRST + 10)
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("is not below the contract's latest block"));
+    assert!(harness
+        .chain()
+        .sent::<WrappedMonero::fillMoneroBlockCall>()
+        .is_empty());
+
+    let filled = service.fill_blocks(&contract, FIRST + 6, FIRST + 9).await;
+    assert_eq!(filled.unwrap(), 4);
+    let heights = posted_heights(&harness);
+    assert_eq!(
+        heights[2..],
+        [FIRST + 6, FIRST + 7, FIRST + 8, FIRST + 9, FIRST + 10]
+    );
+    assert_eq!(harness.chain().contract.latest, FIRST + 10);
+}
+
+const ADMIN_KEY: &str = "0123456789abcdef";
+
+/// Serve the oracle's HTTP API, as [`OracleService::start`] does
+async fn serve_api(service: &OracleService) -> (String, mpsc::Receiver<AdminRequest>) {
+    let (state, admin_requests) = service.api_state(None);
+    (serve(api::router(state)).await, admin_requests)
+}
+
+/// Answer the next admin request the way the polling loop does
+async fn answer_admin<P>(
+    service: &OracleService,
+    provider: &P,
+    admin_requests: &mut mpsc::Receiver<AdminRequest>,
+) where
+    P: Provider<Http<reqwest::Client>> + Clone,
+{
+    let AdminRequest { command, reply } = admin_requests.recv().await.unwrap();
+    let contract = WrappedMonero::new(BRIDGE, provider);
+    let _ = reply.send(service.run_admin_command(command, &contract, None).await);
+}
+
+#[tokio::test]
+async fn test_fill_on_demand_posts_the_block_for_an_admin() {
+    let harness = Harness::new("oracle-fill-authorized").await;
+    let settings = format!("admin_api_key = \"{}\"\noracle_http_port = 1\n", ADMIN_KEY);
+    let (service, provider) = checkpoints_posted(&harness, &settings).await;
+    let (url, mut admin_requests) = serve_api(&service).await;
+    let client = reqwest::Client::new();
+
+    let request = client
+        .post(format!("{}/fill/{}", url, FIRST + 3))
+        .bearer_auth(ADMIN_KEY)
+        .send();
+    let (response, ()) = tokio::join!(
+        request,
+        answer_admin(&service, &provider, &mut admin_requests)
+    );
+    let response = response.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["message"], "Filled 1 block(s) between 103 and 103");
+    assert_eq!(
+        posted_heights(&harness),
+        [FIRST, FIRST + 3, FIRST + 5, FIRST + 10]
+    );
+    assert_eq!(
+        harness.chain().contract.blocks[&(FIRST + 3)].block_hash,
+        harness.block(FIRST + 3).block_hash
+    );
+
+    // The newest checkpoint isn't a skipped block
+    let request = client
+        .post(format!("{}/fill/{}", url, FIRST + 10))
+        .bearer_auth(ADMIN_KEY)
+        .send();
+    let (response, ()) = tokio::join!(
+        request,
+        answer_admin(&service, &provider, &mut admin_requests)
+    );
+    assert_eq!(response.unwrap().status(), reqwest::StatusCode::CONFLICT);
+    assert_eq!(
+        harness
+            .chain()
+            .sent::<WrappedMonero::fillMoneroBlockCall>()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_fill_on_demand_refuses_without_the_admin_key() {
+    let harness = Harness::new("oracle-fill-unauthorized").await;
+    let settings = format!("admin_api_key = \"{}\"\noracle_http_port = 1\n", ADMIN_KEY);
+    let (service, _provider) = checkpoints_posted(&harness, &settings).await;
+    let (url, mut admin_requests) = serve_api(&service).await;
+    let client = reqwest::Client::new();
+
+    for key in [None, Some("fedcba9876543210")] {
+        let mut request = client.post(format!("{}/fill/{}", url, FIRST + 3));
+        if let Some(key) = key {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+    assert!(admin_requests.try_recv().is_err());
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 5, FIRST + 10]);
+    assert!(harness
+        .chain()
+        .sent::<WrappedMonero::fillMoneroBlockCall>()
+        .is_empty());
+}
+
+/// `postMoneroBlock` transactions sent to the mock Unichain
+fn postings_sent(harness: &Harness) -> usize {
+    harness
+        .chain()
+        .sent::<WrappedMonero::postMoneroBlockCall>()
+        .len()
+}
+
+#[tokio::test]
+async fn test_gas_budget_stops_posting_at_the_limit() {
+    let harness = Harness::new("oracle-gas-budget-limit").await;
+    harness.monero().tip = FIRST + 4;
+    let service = harness.oracle("gas_budget_daily_eth = 0.0007\n");
+    let provider = connect(&service).await;
+
+    // Each posting the mock mines costs 0.000393216 ETH (196,608 gas at
+    // 2 gwei): the second uses the budget up, so the third isn't sent
+    let error = poll(&service, &provider).await.unwrap_err();
+    assert!(format!("{:#}", error).contains("Not sending: 0.000786 ETH spent on gas, daily budget"));
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 1]);
+    assert_eq!(postings_sent(&harness), 2);
+
+    // Later polls track blocks without posting them
+    harness.monero().tip = FIRST + 5;
+    poll(&service, &provider).await.unwrap();
+    assert!(service.status.read().unwrap().over_gas_budget);
+    assert_eq!(postings_sent(&harness), 2);
+    assert_eq!(harness.chain().contract.latest, FIRST + 1);
+}
+
+#[tokio::test]
+async fn test_gas_budget_resumes_posting_once_spending_ages_out() {
+    let harness = Harness::new("oracle-gas-budget-reset").await;
+    let service = harness.oracle("gas_budget_daily_eth = 0.0007\n");
+    // Spent almost a day ago, and enough to use the budget up
+    service
+        .db
+        .record_tx_cost(&TxCost {
+            recorded_at: Utc::now() - TimeDelta::days(1) + TimeDelta::seconds(2),
+            ..TxCost::gas(
+                CostKind::PostBlock,
+                B256::repeat_byte(1),
+                400_000,
+                2_000_000_000,
+            )
+        })
+        .unwrap();
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert!(service.status.read().unwrap().over_gas_budget);
+    assert_eq!(postings_sent(&harness), 0);
+
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    poll(&service, &provider).await.unwrap();
+    assert!(!service.status.read().unwrap().over_gas_budget);
+    assert_eq!(posted_heights(&harness), [FIRST]);
+}
+
+/// Instance `instance` of a redundant pair sharing the oracle key, with
+/// its own database
+fn lease_instance(harness: &Harness, instance: &str) -> OracleService {
+    harness.oracle_on(
+        &format!("{}.db", instance),
+        &format!(
+            "lease_instance_id = \"{}\"\nlease_duration_secs = 60\npoll_interval_secs = 10\n",
+            instance
+        ),
+    )
+}
+
+fn lease_holder(instance: &str) -> B256 {
+    keccak256(instance.as_bytes())
+}
+
+#[tokio::test]
+async fn test_lease_lets_one_of_two_oracles_post_and_renew() {
+    let harness = Harness::new("oracle-lease-renew").await;
+    let a = lease_instance(&harness, "a");
+    let b = lease_instance(&harness, "b");
+    let provider_a = connect(&a).await;
+    let provider_b = connect(&b).await;
+
+    poll(&a, &provider_a).await.unwrap();
+    poll(&b, &provider_b).await.unwrap();
+    assert_eq!(
+        harness.chain().contract.lease,
+        (lease_holder("a"), 1_700_000_060)
+    );
+    assert!(!a.status.read().unwrap().standby);
+    assert!(b.status.read().unwrap().standby);
+    assert!(b.lease.as_ref().unwrap().ensure_held().is_err());
+    // Only the leader sends
+    assert_eq!(postings_sent(&harness), 1);
+    assert_eq!(
+        harness
+            .chain()
+            .sent::<WrappedMonero::acquireLeaseCall>()
+            .len(),
+        1
+    );
+
+    // Past half of it, the leader renews; the standby keeps waiting
+    harness.chain().now += 35;
+    harness.monero().tip = FIRST + 3;
+    poll(&a, &provider_a).await.unwrap();
+    poll(&b, &provider_b).await.unwrap();
+    assert_eq!(
+        harness.chain().contract.lease,
+        (lease_holder("a"), 1_700_000_095)
+    );
+    assert!(b.status.read().unwrap().standby);
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 1]);
+    let chain = harness.chain();
+    let acquired = chain.sent::<WrappedMonero::acquireLeaseCall>();
+    assert_eq!(acquired.len(), 2);
+    assert!(acquired
+        .iter()
+        .all(|sent| sent.input[4..36] == lease_holder("a")[..]));
+}
+
+#[tokio::test]
+async fn test_lease_passes_to_the_standby_when_the_leader_stops_renewing() {
+    let harness = Harness::new("oracle-lease-takeover").await;
+    let a = lease_instance(&harness, "a");
+    let b = lease_instance(&harness, "b");
+    b.db.queue_withdrawal(&withdrawal(1)).unwrap();
+    let provider_a = connect(&a).await;
+    let provider_b = connect(&b).await;
+    poll(&a, &provider_a).await.unwrap();
+    poll(&b, &provider_b).await.unwrap();
+    assert!(b.status.read().unwrap().standby);
+
+    // A stalls past its lease; B takes it over and posts
+    harness.chain().now += 61;
+    harness.monero().tip = FIRST + 3;
+    poll(&b, &provider_b).await.unwrap();
+    assert_eq!(
+        harness.chain().contract.lease,
+        (lease_holder("b"), 1_700_000_121)
+    );
+    assert!(!b.status.read().unwrap().standby);
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 1]);
+    // Whether A paid what B had queued is in A's database
+    assert_eq!(status_of(&b, 1), WithdrawalStatus::Failed);
+    assert_eq!(harness.payouts_made(), (0, 0));
+
+    // A finds the lease lost: it stops sending and only indexes
+    harness.monero().tip = FIRST + 4;
+    poll(&a, &provider_a).await.unwrap();
+    assert!(a.status.read().unwrap().standby);
+    assert!(a.lease.as_ref().unwrap().ensure_held().is_err());
+    assert_eq!(posted_heights(&harness), [FIRST, FIRST + 1]);
+    assert_eq!(*a.indexed_through.lock().unwrap(), Some(FIRST + 1));
+    let chain = harness.chain();
+    let acquired = chain.sent::<WrappedMonero::acquireLeaseCall>();
+    assert_eq!(acquired.len(), 2);
+    assert_eq!(acquired[1].input[4..36], lease_holder("b")[..]);
+}
+
+#[tokio::test]
+async fn test_reposts_a_posting_a_reorg_dropped_with_a_higher_fee() {
+    let harness = Harness::new("oracle-repost").await;
+    let service = harness.oracle("unichain_confirmation_depth = 2\n");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    let posted = harness.chain().sent::<WrappedMonero::postMoneroBlockCall>()[0].clone();
+    assert_eq!(
+        service
+            .db
+            .posted_block(FIRST)
+            .unwrap()
+            .unwrap()
+            .unichain_tx_hash,
+        posted.hash
+    );
+
+    // Reorged out before it settled; the node holds it in its mempool
+    harness.chain().reorg_out(posted.hash);
+    harness.chain().block_number += 2;
+    assert_eq!(harness.chain().contract.latest, FIRST - 1);
+    poll(&service, &provider).await.unwrap();
+
+    let chain = harness.chain();
+    let sent = chain.sent::<WrappedMonero::postMoneroBlockCall>();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1].input, posted.input);
+    assert_eq!(sent[1].nonce, posted.nonce);
+    assert!(sent[1].max_fee_per_gas * 100 >= posted.max_fee_per_gas * 110);
+    assert_eq!(chain.contract.latest, FIRST);
+    assert_eq!(
+        chain.contract.blocks[&FIRST].block_hash,
+        harness.block(FIRST).block_hash
+    );
+    drop(chain);
+    assert_eq!(
+        service
+            .db
+            .posted_block(FIRST)
+            .unwrap()
+            .unwrap()
+            .unichain_tx_hash,
+        sent[1].hash
+    );
+}
+
+#[tokio::test]
+async fn test_paused_contract_holds_postings_and_payouts() {
+    let harness = Harness::new("oracle-contract-paused").await;
+    harness.chain().contract.paused = true;
+    let service = harness.oracle("");
+    service.db.queue_withdrawal(&withdrawal(1)).unwrap();
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+
+    assert!(service.status.read().unwrap().contract_paused);
+    assert_eq!(postings_sent(&harness), 0);
+    assert_eq!(status_of(&service, 1), WithdrawalStatus::Queued);
+    assert_eq!(harness.payouts_made(), (0, 0));
+}
+
+#[tokio::test]
+async fn test_unpaused_contract_resumes_posting_where_it_stopped() {
+    let harness = Harness::new("oracle-contract-resumed").await;
+    let service = harness.oracle("max_catch_up_blocks = 2\n");
+    let provider = connect(&service).await;
+    poll(&service, &provider).await.unwrap();
+    assert_eq!(posted_heights(&harness), [FIRST]);
+
+    harness.chain().contract.paused = true;
+    harness.monero().tip = LAST;
+    poll(&service, &provider).await.unwrap();
+    assert!(service.status.read().unwrap().contract_paused);
+    assert_eq!(postings_sent(&harness), 1);
+
+    // Unpausing approves the backlog the pause built up, past
+    // MAX_CATCH_UP_BLOCKS
+    harness.chain().contract.paused = false;
+    poll(&service, &provider).await.unwrap();
+    let status = service.status.read().unwrap();
+    assert!(!status.contract_paused);
+    assert_eq!(status.catch_up_blocked, None);
+    drop(status);
+    assert_eq!(harness.chain().contract.latest, FIRST + 10);
+    assert_eq!(postings_sent(&harness), 11);
+}
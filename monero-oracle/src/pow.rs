@@ -0,0 +1,141 @@
+//! Proof-of-work check for Monero block headers (`VERIFY_POW`)
+//!
+//! The header check proves a block's transactions match its hash, but not that
+//! anyone mined it. With `VERIFY_POW=true` each block's hashing blob is also
+//! hashed with RandomX and the result checked against the block's difficulty,
+//! so an RPC node can't feed the oracle fabricated blocks.
+//!
+//! RandomX keys its hash with the hash of a seed block that changes every 2048
+//! blocks. The light-mode cache built from it takes about 256 MiB and a second
+//! or so to build; after that a hash costs a few milliseconds. RandomX is a C++
+//! library, so it is only linked into builds with the `randomx` feature.
+
+use crate::{error::OracleError, monero_rpc::BlockHeader};
+use alloy::primitives::{B256, U256};
+use anyhow::Result;
+
+/// First block major version hashed with RandomX
+pub const RANDOMX_MAJOR_VERSION: u8 = 12;
+const SEEDHASH_EPOCH_BLOCKS: u64 = 2048;
+const SEEDHASH_EPOCH_LAG: u64 = 64;
+
+/// Height of the block whose hash seeds RandomX at `height` (`rx_seedheight`)
+pub fn seed_height(height: u64) -> u64 {
+    if height <= SEEDHASH_EPOCH_BLOCKS + SEEDHASH_EPOCH_LAG {
+        0
+    } else {
+        (height - SEEDHASH_EPOCH_LAG - 1) & !(SEEDHASH_EPOCH_BLOCKS - 1)
+    }
+}
+
+/// Monero's `check_hash`: the PoW hash, read as a little-endian 256-bit
+/// number, times the difficulty must not overflow 256 bits
+pub fn check_hash(hash: &B256, difficulty: u128) -> bool {
+    U256::from_le_bytes(hash.0)
+        .checked_mul(U256::from(difficulty))
+        .is_some()
+}
+
+/// RandomX hasher, keeping the cache for the current seed
+pub struct PowVerifier {
+    #[cfg(feature = "randomx")]
+    cache: std::sync::Mutex<Option<(B256, randomx_rs::RandomXCache)>>,
+}
+
+impl PowVerifier {
+    #[cfg(feature = "randomx")]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            cache: Default::default(),
+        })
+    }
+
+    #[cfg(not(feature = "randomx"))]
+    pub fn new() -> Result<Self> {
+        anyhow::bail!("VERIFY_POW needs a build with the randomx feature")
+    }
+
+    /// Check `header`'s proof of work. `hashing_blob` is the block hashing
+    /// blob and `seed_hash` the hash of the block at [`seed_height`]. CPU
+    /// bound, and slow whenever the seed changes; call it off the runtime.
+    pub fn verify(&self, header: &BlockHeader, hashing_blob: &[u8], seed_hash: B256) -> Result<()> {
+        anyhow::ensure!(
+            header.major_version >= RANDOMX_MAJOR_VERSION,
+            "Block {} (v{}) predates RandomX; only RandomX proof of work is checked",
+            header.height,
+            header.major_version
+        );
+        let difficulty = header.difficulty()?;
+        anyhow::ensure!(
+            difficulty > 0,
+            "Block {} has zero difficulty",
+            header.height
+        );
+
+        let pow_hash = self.randomx_hash(seed_hash, hashing_blob)?;
+        if !check_hash(&pow_hash, difficulty) {
+            return Err(OracleError::Consensus(format!(
+                "Block {} fails its proof of work: RandomX hash {} doesn't meet difficulty {}",
+                header.height, pow_hash, difficulty
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "randomx")]
+    fn randomx_hash(&self, seed_hash: B256, input: &[u8]) -> Result<B256> {
+        use randomx_rs::{RandomXCache, RandomXFlag, RandomXVM};
+
+        let flags = RandomXFlag::get_recommended_flags();
+        let cache = {
+            let mut cached = self.cache.lock().expect("RandomX cache poisoned");
+            match &*cached {
+                Some((seed, cache)) if *seed == seed_hash => cache.clone(),
+                _ => {
+                    let cache = RandomXCache::new(flags, seed_hash.as_slice())?;
+                    *cached = Some((seed_hash, cache.clone()));
+                    cache
+                }
+            }
+        };
+        let vm = RandomXVM::new(flags, Some(cache), None)?;
+        Ok(B256::from_slice(&vm.calculate_hash(input)?))
+    }
+
+    #[cfg(not(feature = "randomx"))]
+    fn randomx_hash(&self, _seed_hash: B256, _input: &[u8]) -> Result<B256> {
+        anyhow::bail!("VERIFY_POW needs a build with the randomx feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_height() {
+        assert_eq!(seed_height(0), 0);
+        assert_eq!(seed_height(2112), 0);
+        assert_eq!(seed_height(2113), 2048);
+        assert_eq!(seed_height(4160), 2048);
+        assert_eq!(seed_height(4161), 4096);
+        // Mainnet block 3,100,000 is seeded by 3,098,624
+        assert_eq!(seed_height(3_100_000), 3_098_624);
+    }
+
+    #[test]
+    fn test_check_hash() {
+        let mut hash = B256::ZERO;
+        assert!(check_hash(&hash, u128::MAX));
+
+        // 2^255 fails difficulty 2 but just passes difficulty 1
+        hash.0[31] = 0x80;
+        assert!(check_hash(&hash, 1));
+        assert!(!check_hash(&hash, 2));
+
+        hash.0[31] = 0x7f;
+        assert!(check_hash(&hash, 2));
+        assert!(!check_hash(&B256::repeat_byte(0xff), 2));
+    }
+}
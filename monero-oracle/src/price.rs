@@ -0,0 +1,195 @@
+//! XMR/USD rate for quoting bridge fees in dollars
+//!
+//! The rate comes from a Chainlink aggregator on Unichain
+//! (`PRICE_FEED_ADDRESS`) or from any HTTP endpoint returning JSON
+//! (`PRICE_FEED_URL`, with `PRICE_FEED_JSON_POINTER` locating the price in
+//! the response). A rate older than `PRICE_MAX_AGE_SECS` is refused rather
+//! than used: a fee quoted from a stale price is charged for good.
+
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::{fmt, time::Duration};
+
+/// Time allowed for an HTTP price source to answer
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Piconero in one XMR
+const PICONERO: f64 = 1e12;
+
+/// Where the rate is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriceSource {
+    /// Chainlink XMR/USD aggregator on Unichain
+    Chainlink(Address),
+    /// JSON endpoint, with the RFC 6901 pointer to the price in its response
+    Http { url: String, pointer: String },
+}
+
+impl fmt::Display for PriceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Chainlink(address) => write!(f, "chainlink:{}", address),
+            Self::Http { url, .. } => f.write_str(url),
+        }
+    }
+}
+
+/// `PRICE_FEED_*` settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceFeed {
+    pub source: PriceSource,
+    /// Oldest rate accepted
+    pub max_age: Duration,
+}
+
+/// An XMR/USD rate and when the source last updated it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub usd_per_xmr: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Rate {
+    /// Piconero worth `usd` dollars, rounded up
+    pub fn piconero(&self, usd: f64) -> u64 {
+        (usd * PICONERO / self.usd_per_xmr).ceil() as u64
+    }
+}
+
+impl PriceFeed {
+    /// Reject a rate the source stopped updating more than `max_age` before
+    /// `now`
+    pub fn check(&self, rate: Rate, now: DateTime<Utc>) -> Result<Rate> {
+        anyhow::ensure!(
+            rate.usd_per_xmr.is_finite() && rate.usd_per_xmr > 0.0,
+            "{} returned an invalid XMR/USD rate {}",
+            self.source,
+            rate.usd_per_xmr
+        );
+        let age = (now - rate.updated_at).to_std().unwrap_or_default();
+        anyhow::ensure!(
+            age <= self.max_age,
+            "XMR/USD rate from {} is stale: last updated {} ({}s ago, PRICE_MAX_AGE_SECS is {})",
+            self.source,
+            rate.updated_at,
+            age.as_secs(),
+            self.max_age.as_secs()
+        );
+        Ok(rate)
+    }
+
+    /// Read the rate from an HTTP source, which is taken to be current.
+    /// Chainlink feeds are read by the oracle, which holds the provider.
+    pub async fn fetch_http(&self, client: &reqwest::Client) -> Result<Rate> {
+        let PriceSource::Http { url, pointer } = &self.source else {
+            anyhow::bail!("{} is not an HTTP price source", self.source);
+        };
+        let response: Value = client
+            .get(url)
+            .timeout(HTTP_TIMEOUT)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to query price feed {}", url))?
+            .json()
+            .await
+            .with_context(|| format!("Price feed {} didn't return JSON", url))?;
+        let rate = Rate {
+            usd_per_xmr: price_at(&response, pointer)?,
+            updated_at: Utc::now(),
+        };
+        self.check(rate, Utc::now())
+    }
+}
+
+/// The price at `pointer` in a JSON response, as a number or a numeric
+/// string
+pub fn price_at(response: &Value, pointer: &str) -> Result<f64> {
+    let value = response
+        .pointer(pointer)
+        .with_context(|| format!("No price at {} in the price feed response", pointer))?;
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+    .with_context(|| format!("Price at {} is not a number: {}", pointer, value))
+}
+
+/// Rate from a Chainlink `latestRoundData` answer with `decimals` decimals,
+/// updated at `updated_at` (Unix seconds)
+pub fn chainlink_rate(answer: i128, decimals: u8, updated_at: u64) -> Result<Rate> {
+    anyhow::ensure!(
+        answer > 0,
+        "Chainlink returned a non-positive answer {}",
+        answer
+    );
+    let updated_at = Utc
+        .timestamp_opt(updated_at as i64, 0)
+        .single()
+        .context("Chainlink returned an invalid update time")?;
+    Ok(Rate {
+        usd_per_xmr: answer as f64 / 10f64.powi(decimals as i32),
+        updated_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_at() {
+        let coingecko = serde_json::json!({ "monero": { "usd": 162.37 } });
+        assert_eq!(price_at(&coingecko, "/monero/usd").unwrap(), 162.37);
+
+        let kraken = serde_json::json!({ "result": { "XXMRZUSD": { "c": ["161.90", "0.5"] } } });
+        assert_eq!(price_at(&kraken, "/result/XXMRZUSD/c/0").unwrap(), 161.90);
+
+        assert!(price_at(&coingecko, "/monero/eur").is_err());
+        assert!(price_at(&coingecko, "/monero").is_err());
+    }
+
+    #[test]
+    fn test_rate_conversion() {
+        let rate = chainlink_rate(16_000_000_000, 8, 1_700_000_000).unwrap();
+        assert_eq!(rate.usd_per_xmr, 160.0);
+        assert_eq!(rate.updated_at.timestamp(), 1_700_000_000);
+        // $2 at $160 is 0.0125 XMR
+        assert_eq!(rate.piconero(2.0), 12_500_000_000);
+        // Rounded up, never down
+        assert_eq!(rate.piconero(1.0 / 3.0), 2_083_333_334);
+
+        assert!(chainlink_rate(0, 8, 1_700_000_000).is_err());
+        assert!(chainlink_rate(-1, 8, 1_700_000_000).is_err());
+    }
+
+    #[test]
+    fn test_stale_rate_refused() {
+        let feed = PriceFeed {
+            source: PriceSource::Http {
+                url: "https://prices.example".to_string(),
+                pointer: "/monero/usd".to_string(),
+            },
+            max_age: Duration::from_secs(3600),
+        };
+        let now = Utc::now();
+        let rate = |age: i64| Rate {
+            usd_per_xmr: 160.0,
+            updated_at: now - chrono::Duration::seconds(age),
+        };
+        assert!(feed.check(rate(3600), now).is_ok());
+        assert!(feed.check(rate(3601), now).is_err());
+        assert!(feed
+            .check(
+                Rate {
+                    usd_per_xmr: f64::NAN,
+                    updated_at: now
+                },
+                now
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! Merkle inclusion proofs for posted blocks
+//!
+//! Reads the sibling path the contract expects off a block's transaction or
+//! output tree: `verifyTxInBlock` for transactions, and `verifyMerkleProof`
+//! (or the legacy `verifyMerkleProofSHA256`, or `verifyOutputProofV2`) for
+//! outputs, depending on the output tree hasher. Trees stored when the block was posted are used as
+//! they are; otherwise the tree is rebuilt exactly as the oracle built it.
+//! Proofs read back from JSON can be checked locally the same way the
+//! contract will check them.
+
+use crate::{
+    merkle::{output_tree, tx_tree, MerkleHasher, MerkleProof, MerkleTree, TreeKind},
+    monero_rpc::{
+        outputs_from_transactions, parse_hex_to_b256, transaction_order, MoneroOutput,
+        MoneroRpcClient,
+    },
+    storage::Database,
+};
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Proof that a transaction is included in a posted block's `txMerkleRoot`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxInclusionProof {
+    pub block_height: u64,
+    pub block_hash: B256,
+    pub tx_hash: B256,
+    /// Position of the transaction in the block, passed as `txIndex`
+    pub tx_index: u64,
+    pub tx_merkle_root: B256,
+    pub proof: Vec<B256>,
+    /// Tx tree version: `keccak256` (1) or `v2`; absent in proofs from
+    /// before versioning, which are all version 1
+    #[serde(default)]
+    pub hasher: MerkleHasher,
+}
+
+/// Proof that an output is included in a posted block's `outputMerkleRoot`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputInclusionProof {
+    pub block_height: u64,
+    pub block_hash: B256,
+    pub output: MoneroOutput,
+    /// Position of the output's leaf in the block's output tree, passed as
+    /// `outputIndex`
+    pub leaf_index: u64,
+    pub leaf: B256,
+    pub output_merkle_root: B256,
+    pub proof: Vec<B256>,
+    /// Internal node hash; `verifier` names the contract function to call
+    pub hasher: MerkleHasher,
+    pub verifier: String,
+}
+
+/// Either proof, as printed by `prove-tx` or `prove-output`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum InclusionProof {
+    Output(OutputInclusionProof),
+    Tx(TxInclusionProof),
+}
+
+impl InclusionProof {
+    /// Check the proof the way the contract will
+    pub fn verify(&self) -> bool {
+        match self {
+            Self::Tx(proof) => verify_tx_proof(proof),
+            Self::Output(proof) => verify_output_proof(proof),
+        }
+    }
+}
+
+/// Root and sibling path for the leaf at `index`
+fn sibling_path(tree: &MerkleTree, index: usize) -> Option<(B256, Vec<B256>)> {
+    tree.proof(index).map(|proof| (tree.root(), proof.siblings))
+}
+
+/// Sibling path for the transaction at `tx_index` in a block's tx list
+pub fn tx_merkle_proof(
+    tx_hashes: &[String],
+    tx_index: usize,
+    hasher: MerkleHasher,
+) -> Option<(B256, Vec<B256>)> {
+    sibling_path(&tx_tree(tx_hashes, hasher), tx_index)
+}
+
+/// Sibling path for the output at `leaf_index` in the block's output tree,
+/// whose leaves are in [`MerkleHasher::output_order`]
+pub fn output_merkle_proof(
+    outputs: &[MoneroOutput],
+    leaf_index: usize,
+    hasher: MerkleHasher,
+) -> Option<(B256, Vec<B256>)> {
+    sibling_path(&output_tree(outputs, hasher), leaf_index)
+}
+
+/// Check a transaction proof the same way `verifyTxInBlock` does
+pub fn verify_tx_proof(proof: &TxInclusionProof) -> bool {
+    let path = MerkleProof {
+        leaf_index: proof.tx_index,
+        leaf: B256::from(proof.hasher.tx_leaf(&proof.tx_hash.0)),
+        siblings: proof.proof.clone(),
+    };
+    path.compute_root(proof.hasher) == proof.tx_merkle_root
+}
+
+/// Check an output proof the same way its `verifier` does
+pub fn verify_output_proof(proof: &OutputInclusionProof) -> bool {
+    let path = MerkleProof {
+        leaf_index: proof.leaf_index,
+        leaf: proof.leaf,
+        siblings: proof.proof.clone(),
+    };
+    B256::from(proof.hasher.output_leaf(&proof.output)) == proof.leaf
+        && path.compute_root(proof.hasher) == proof.output_merkle_root
+}
+
+/// Locate `tx_hash` on chain and prove its inclusion in the block's tx tree,
+/// from the tree stored in `db` when the block's is there
+pub async fn prove_tx(
+    client: &MoneroRpcClient,
+    db: Option<&Database>,
+    tx_hash: &str,
+    hasher: MerkleHasher,
+) -> Result<TxInclusionProof> {
+    let tx_hash_b256 = parse_hex_to_b256(tx_hash)?;
+    let block_height = client.get_transaction_height(tx_hash).await?;
+
+    let stored = match db {
+        Some(db) => db
+            .merkle_tree(block_height, TreeKind::Tx)?
+            .filter(|(_, tree)| tree.hasher() == hasher),
+        None => None,
+    };
+    let (block_hash, tree) = match stored {
+        Some(stored) => stored,
+        None => {
+            let (block_hash, tx_hashes) = client.get_block_tx_hashes(block_height).await?;
+            (block_hash, tx_tree(&tx_hashes, hasher))
+        }
+    };
+    let leaf = hasher.tx_leaf(&tx_hash_b256.0);
+    let tx_index = tree
+        .leaves()
+        .iter()
+        .position(|stored| *stored == leaf)
+        .with_context(|| format!("Transaction not found in block {}", block_height))?;
+
+    let (tx_merkle_root, proof) =
+        sibling_path(&tree, tx_index).context("Transaction index out of range")?;
+
+    Ok(TxInclusionProof {
+        block_height,
+        block_hash,
+        tx_hash: tx_hash_b256,
+        tx_index: tx_index as u64,
+        tx_merkle_root,
+        proof,
+        hasher,
+    })
+}
+
+/// Locate output `output_index` of `tx_hash` and prove its inclusion in the
+/// block's output tree
+pub async fn prove_output(
+    client: &MoneroRpcClient,
+    tx_hash: &str,
+    output_index: u64,
+    hasher: MerkleHasher,
+) -> Result<OutputInclusionProof> {
+    let tx_hash_b256 = parse_hex_to_b256(tx_hash)?;
+    let block_height = client.get_transaction_height(tx_hash).await?;
+
+    let block = client.fetch_block(block_height).await?;
+    let block_hash = parse_hex_to_b256(&block.header.hash)?;
+    let outputs = outputs_from_transactions(&block.transactions)?;
+    let tx_order = transaction_order(&block.transactions)?;
+    output_proof(
+        block_height,
+        block_hash,
+        &hasher.output_order(&outputs, &tx_order),
+        tx_hash_b256,
+        output_index,
+        hasher,
+    )
+}
+
+/// Like [`prove_output`], from the output index instead of monerod. `None`
+/// if the output's block hasn't been indexed.
+pub fn prove_indexed_output(
+    db: &Database,
+    tx_hash: &str,
+    output_index: u64,
+    hasher: MerkleHasher,
+) -> Result<Option<OutputInclusionProof>> {
+    let tx_hash = parse_hex_to_b256(tx_hash)?;
+    let Some(indexed) = db.indexed_output(tx_hash, output_index)? else {
+        return Ok(None);
+    };
+
+    let stored = db
+        .merkle_tree(indexed.block_height, TreeKind::Output)?
+        .filter(|(_, tree)| tree.hasher() == hasher);
+    if let Some((block_hash, tree)) = stored {
+        let leaf = hasher.output_leaf(&indexed.output);
+        let leaf_index = tree
+            .leaves()
+            .iter()
+            .position(|stored| *stored == leaf)
+            .context("Stored output tree is missing the output")?;
+        let (output_merkle_root, proof) =
+            sibling_path(&tree, leaf_index).context("Output index out of range")?;
+        return Ok(Some(OutputInclusionProof {
+            block_height: indexed.block_height,
+            block_hash,
+            leaf_index: leaf_index as u64,
+            leaf: B256::from(leaf),
+            output: indexed.output,
+            output_merkle_root,
+            proof,
+            hasher,
+            verifier: hasher.verifier().to_string(),
+        }));
+    }
+
+    let block = db
+        .indexed_block(indexed.block_height)?
+        .context("Indexed output without its block")?;
+    output_proof(
+        block.height,
+        block.block_hash,
+        &block.outputs,
+        tx_hash,
+        output_index,
+        hasher,
+    )
+    .map(Some)
+}
+
+/// Proof for an output of a block whose `outputs` are in the output tree's
+/// leaf order
+fn output_proof(
+    block_height: u64,
+    block_hash: B256,
+    outputs: &[MoneroOutput],
+    tx_hash: B256,
+    output_index: u64,
+    hasher: MerkleHasher,
+) -> Result<OutputInclusionProof> {
+    let leaf_index = outputs
+        .iter()
+        .position(|o| o.tx_hash == tx_hash && o.output_index == output_index)
+        .with_context(|| {
+            format!(
+                "Output {} of {} not found in block {}",
+                output_index, tx_hash, block_height
+            )
+        })?;
+
+    let (output_merkle_root, proof) =
+        output_merkle_proof(outputs, leaf_index, hasher).context("Output index out of range")?;
+    let output = outputs[leaf_index].clone();
+    let leaf = B256::from(hasher.output_leaf(&output));
+
+    Ok(OutputInclusionProof {
+        block_height,
+        block_hash,
+        output,
+        leaf_index: leaf_index as u64,
+        leaf,
+        output_merkle_root,
+        proof,
+        hasher,
+        verifier: hasher.verifier().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{compute_output_merkle_root, compute_tx_merkle_root};
+
+    fn output(byte: u8, index: u64) -> MoneroOutput {
+        MoneroOutput {
+            tx_hash: B256::from([byte; 32]),
+            output_index: index,
+            ecdh_amount: B256::from([byte.wrapping_add(1); 32]),
+            output_pub_key: B256::from([byte.wrapping_add(2); 32]),
+            commitment: B256::from([byte.wrapping_add(3); 32]),
+        }
+    }
+
+    #[test]
+    fn test_tx_proofs_match_posted_root() {
+        for (count, hasher) in
+            (1..=7u8).flat_map(|c| [(c, MerkleHasher::Keccak256), (c, MerkleHasher::V2)])
+        {
+            let hashes: Vec<String> = (0..count).map(|i| hex::encode([i + 1; 32])).collect();
+            let root = compute_tx_merkle_root(&hashes, hasher);
+
+            for index in 0..hashes.len() {
+                let (proof_root, path) = tx_merkle_proof(&hashes, index, hasher).unwrap();
+                assert_eq!(proof_root, root);
+
+                let proof = TxInclusionProof {
+                    block_height: 1,
+                    block_hash: B256::ZERO,
+                    tx_hash: parse_hex_to_b256(&hashes[index]).unwrap(),
+                    tx_index: index as u64,
+                    tx_merkle_root: root,
+                    proof: path,
+                    hasher,
+                };
+                assert!(verify_tx_proof(&proof));
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_proofs_match_posted_root() {
+        for (count, hasher) in (1..=6u8).flat_map(|c| {
+            [
+                MerkleHasher::Keccak256,
+                MerkleHasher::Sha256,
+                MerkleHasher::V2,
+            ]
+            .map(|h| (c, h))
+        }) {
+            let outputs: Vec<MoneroOutput> = (0..count).map(|i| output(i, i as u64)).collect();
+            let root = compute_output_merkle_root(&outputs, hasher);
+
+            for index in 0..outputs.len() {
+                let (proof_root, path) = output_merkle_proof(&outputs, index, hasher).unwrap();
+                assert_eq!(proof_root, root);
+
+                let proof = OutputInclusionProof {
+                    block_height: 1,
+                    block_hash: B256::ZERO,
+                    output: outputs[index].clone(),
+                    leaf_index: index as u64,
+                    leaf: B256::from(hasher.output_leaf(&outputs[index])),
+                    output_merkle_root: root,
+                    proof: path,
+                    hasher,
+                    verifier: hasher.verifier().to_string(),
+                };
+                assert!(verify_output_proof(&proof));
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_rejects_wrong_index() {
+        let hashes: Vec<String> = (0..4u8).map(|i| hex::encode([i + 1; 32])).collect();
+        let (root, path) = tx_merkle_proof(&hashes, 1, MerkleHasher::Keccak256).unwrap();
+
+        let proof = TxInclusionProof {
+            block_height: 1,
+            block_hash: B256::ZERO,
+            tx_hash: parse_hex_to_b256(&hashes[1]).unwrap(),
+            tx_index: 2,
+            tx_merkle_root: root,
+            proof: path,
+            hasher: MerkleHasher::Keccak256,
+        };
+        assert!(!verify_tx_proof(&proof));
+        assert!(tx_merkle_proof(&hashes, 4, MerkleHasher::Keccak256).is_none());
+    }
+
+    #[test]
+    fn test_verify_proofs_read_back_from_json() {
+        let hashes: Vec<String> = (0..3u8).map(|i| hex::encode([i + 1; 32])).collect();
+        let (root, path) = tx_merkle_proof(&hashes, 2, MerkleHasher::V2).unwrap();
+        let tx_proof = TxInclusionProof {
+            block_height: 1,
+            block_hash: B256::ZERO,
+            tx_hash: parse_hex_to_b256(&hashes[2]).unwrap(),
+            tx_index: 2,
+            tx_merkle_root: root,
+            proof: path,
+            hasher: MerkleHasher::V2,
+        };
+        let json = serde_json::to_string(&tx_proof).unwrap();
+        let read: InclusionProof = serde_json::from_str(&json).unwrap();
+        assert!(matches!(read, InclusionProof::Tx(_)));
+        assert!(read.verify());
+
+        // Proofs from before tx tree versions read as version 1
+        let (root, path) = tx_merkle_proof(&hashes, 2, MerkleHasher::Keccak256).unwrap();
+        let mut legacy = serde_json::to_value(TxInclusionProof {
+            tx_merkle_root: root,
+            proof: path,
+            hasher: MerkleHasher::Keccak256,
+            ..tx_proof
+        })
+        .unwrap();
+        legacy.as_object_mut().unwrap().remove("hasher");
+        let read: InclusionProof = serde_json::from_value(legacy).unwrap();
+        assert!(read.verify());
+
+        let outputs: Vec<MoneroOutput> = (0..3u8).map(|i| output(i, 0)).collect();
+        let hasher = MerkleHasher::Sha256;
+        let proof = output_proof(7, B256::ZERO, &outputs, B256::from([1; 32]), 0, hasher).unwrap();
+        let mut json = serde_json::to_value(&proof).unwrap();
+        let read: InclusionProof = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(read, InclusionProof::Output(_)));
+        assert!(read.verify());
+
+        // A tampered amount no longer matches the leaf
+        json["output"]["ecdhAmount"] = serde_json::json!(B256::from([0xee; 32]));
+        let read: InclusionProof = serde_json::from_value(json).unwrap();
+        assert!(!read.verify());
+    }
+
+    #[test]
+    fn test_v2_proof_uses_canonical_position() {
+        // Outputs out of order, as a node might list them; transaction 2
+        // comes first in the block
+        let outputs = vec![output(1, 1), output(1, 0), output(2, 0)];
+        let tx_order = [B256::from([2; 32]), B256::from([1; 32])];
+        let hasher = MerkleHasher::V2;
+        let ordered = hasher.output_order(&outputs, &tx_order);
+        let proof = output_proof(7, B256::ZERO, &ordered, B256::from([1; 32]), 0, hasher).unwrap();
+        assert_eq!(proof.leaf_index, 1);
+        assert_eq!(proof.verifier, "verifyOutputProofV2");
+        assert_eq!(
+            proof.output_merkle_root,
+            compute_output_merkle_root(&ordered, hasher)
+        );
+        assert!(verify_output_proof(&proof));
+
+        // Relabelled as version 1, neither the leaf nor the root holds
+        let mut v1 = proof.clone();
+        v1.hasher = MerkleHasher::Keccak256;
+        assert!(!verify_output_proof(&v1));
+    }
+
+    #[test]
+    fn test_indexed_output_proof() {
+        let db = Database::open_in_memory().unwrap();
+        let outputs: Vec<MoneroOutput> = (0..5u8).map(|i| output(i, 0)).collect();
+        let hasher = MerkleHasher::Keccak256;
+        let tx_hash = hex::encode([3u8; 32]);
+
+        assert!(prove_indexed_output(&db, &tx_hash, 0, hasher)
+            .unwrap()
+            .is_none());
+
+        db.index_block_outputs(42, B256::from([0xbb; 32]), &outputs)
+            .unwrap();
+        let proof = prove_indexed_output(&db, &tx_hash, 0, hasher)
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof.block_height, 42);
+        assert_eq!(proof.leaf_index, 3);
+        assert_eq!(
+            proof.output_merkle_root,
+            compute_output_merkle_root(&outputs, hasher)
+        );
+        assert!(verify_output_proof(&proof));
+
+        // The stored tree gives the same proof; one built with another
+        // hasher is passed over
+        db.store_merkle_tree(
+            42,
+            B256::from([0xbb; 32]),
+            TreeKind::Output,
+            &output_tree(&outputs, hasher),
+        )
+        .unwrap();
+        let stored = prove_indexed_output(&db, &tx_hash, 0, hasher)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&stored).unwrap(),
+            serde_json::to_value(&proof).unwrap()
+        );
+        let legacy = prove_indexed_output(&db, &tx_hash, 0, MerkleHasher::Sha256)
+            .unwrap()
+            .unwrap();
+        assert!(verify_output_proof(&legacy));
+        assert_ne!(legacy.output_merkle_root, proof.output_merkle_root);
+    }
+}
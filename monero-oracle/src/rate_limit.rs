@@ -0,0 +1,195 @@
+//! Politeness limits for public monerod nodes
+//!
+//! Public nodes ban addresses that hammer them, and a backfill of thousands
+//! of blocks does exactly that. Each node's client can be held to a request
+//! rate (`MONERO_RPC_MAX_RPS`), a cap on requests in flight
+//! (`MONERO_RPC_MAX_CONCURRENT`) and a minimum gap between requests
+//! (`MONERO_RPC_DELAY_MS`). Retries count against the same limits. All three
+//! are off by default, which suits a node of your own.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimit {
+    /// Requests per second, allowing bursts of up to one second's worth
+    pub max_per_sec: Option<f64>,
+    /// Requests in flight at once
+    pub max_concurrent: Option<usize>,
+    /// Minimum time between the starts of two requests
+    pub delay: Duration,
+}
+
+impl RateLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_per_sec.is_none() && self.max_concurrent.is_none() && self.delay.is_zero()
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limits = Vec::new();
+        if let Some(rate) = self.max_per_sec {
+            limits.push(format!("{} req/s", rate));
+        }
+        if let Some(max) = self.max_concurrent {
+            limits.push(format!("{} in flight", max));
+        }
+        if !self.delay.is_zero() {
+            limits.push(format!("{}ms apart", self.delay.as_millis()));
+        }
+        if limits.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&limits.join(", "))
+        }
+    }
+}
+
+/// Start times handed out so far
+#[derive(Debug, Default)]
+struct Schedule {
+    /// Theoretical arrival time of the next request at the steady rate
+    next_at_rate: Option<Instant>,
+    last_start: Option<Instant>,
+}
+
+impl Schedule {
+    /// Reserve the earliest start at or after `now` that keeps to `limit`
+    fn reserve(&mut self, limit: &RateLimit, now: Instant) -> Instant {
+        let mut start = now;
+        if let Some(last_start) = self.last_start {
+            start = start.max(last_start + limit.delay);
+        }
+        if let Some(rate) = limit.max_per_sec {
+            let interval = Duration::from_secs_f64(1.0 / rate);
+            let burst = interval.mul_f64((rate.ceil() - 1.0).max(0.0));
+            let next = self.next_at_rate.unwrap_or(start);
+            if let Some(earliest) = next.checked_sub(burst) {
+                start = start.max(earliest);
+            }
+            self.next_at_rate = Some(next.max(start) + interval);
+        }
+        self.last_start = Some(start);
+        start
+    }
+}
+
+#[derive(Debug)]
+struct Limits {
+    limit: RateLimit,
+    permits: Option<Semaphore>,
+    schedule: Mutex<Schedule>,
+}
+
+/// Shared by all clones of one node's client
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    limits: Option<Arc<Limits>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let limits = (!limit.is_unlimited()).then(|| {
+            Arc::new(Limits {
+                limit,
+                permits: limit.max_concurrent.map(Semaphore::new),
+                schedule: Mutex::default(),
+            })
+        });
+        Self { limits }
+    }
+
+    /// Wait for a request slot. Hold the returned permit until the response
+    /// has been read.
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let limits = self.limits.as_ref()?;
+        let permit = match &limits.permits {
+            Some(permits) => Some(permits.acquire().await.expect("semaphore never closed")),
+            None => None,
+        };
+        let start = limits
+            .schedule
+            .lock()
+            .expect("rate limit state poisoned")
+            .reserve(&limits.limit, Instant::now());
+        tokio::time::sleep_until(start.into()).await;
+        permit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(limit: RateLimit, requests: usize, now: Instant) -> Vec<Duration> {
+        let mut schedule = Schedule::default();
+        (0..requests)
+            .map(|_| schedule.reserve(&limit, now) - now)
+            .collect()
+    }
+
+    #[test]
+    fn test_rate_allows_one_second_burst() {
+        let limit = RateLimit {
+            max_per_sec: Some(4.0),
+            ..RateLimit::default()
+        };
+        let ms = Duration::from_millis;
+        assert_eq!(
+            starts(limit, 6, Instant::now()),
+            [ms(0), ms(0), ms(0), ms(0), ms(250), ms(500)]
+        );
+
+        // Below one request per second there is no burst
+        let limit = RateLimit {
+            max_per_sec: Some(0.5),
+            ..RateLimit::default()
+        };
+        assert_eq!(
+            starts(limit, 3, Instant::now()),
+            [ms(0), ms(2000), ms(4000)]
+        );
+    }
+
+    #[test]
+    fn test_delay_spaces_requests() {
+        let ms = Duration::from_millis;
+        let limit = RateLimit {
+            max_per_sec: Some(10.0),
+            delay: ms(300),
+            ..RateLimit::default()
+        };
+        assert_eq!(starts(limit, 3, Instant::now()), [ms(0), ms(300), ms(600)]);
+
+        // A quiet spell doesn't bank requests beyond the burst
+        let mut schedule = Schedule::default();
+        let limit = RateLimit {
+            max_per_sec: Some(1.0),
+            ..RateLimit::default()
+        };
+        let now = Instant::now();
+        schedule.reserve(&limit, now);
+        let later = now + Duration::from_secs(60);
+        assert_eq!(schedule.reserve(&limit, later), later);
+        assert_eq!(
+            schedule.reserve(&limit, later),
+            later + Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_unlimited_by_default() {
+        assert!(RateLimit::default().is_unlimited());
+        assert!(RateLimiter::new(RateLimit::default()).limits.is_none());
+        assert!(!RateLimit {
+            max_concurrent: Some(2),
+            ..RateLimit::default()
+        }
+        .is_unlimited());
+    }
+}
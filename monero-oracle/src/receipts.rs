@@ -0,0 +1,67 @@
+//! Re-verification of block postings after Unichain reorgs
+//!
+//! A posting counts as made once its transaction is mined, but a Unichain
+//! reorg can still drop it, taking the block out of the contract. Once the
+//! block it was mined in is `UNICHAIN_CONFIRMATION_DEPTH` blocks deep, the
+//! oracle fetches the receipt again. A posting still mined where it was is
+//! marked confirmed and not checked again; one mined again in another block
+//! waits out the depth from there. One whose receipt is gone, or reverted
+//! when mined again, was reorged out: its record is dropped and, unless the
+//! contract already holds the block, it is posted again.
+
+/// What a posting's receipt shows once it is due for re-checking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptCheck {
+    /// Still mined where it was recorded, deep enough to stay
+    Confirmed,
+    /// Mined again in another block, which must get as deep
+    Moved { unichain_block: u64 },
+    /// No longer on-chain, or reverted when mined again
+    Dropped,
+}
+
+/// Judge a posting recorded as mined in `recorded`, from the block and
+/// status of the receipt found for it now, if any
+pub fn check(recorded: Option<u64>, receipt: Option<(u64, bool)>) -> ReceiptCheck {
+    match receipt {
+        Some((_, false)) | None => ReceiptCheck::Dropped,
+        Some((block, true)) if Some(block) == recorded => ReceiptCheck::Confirmed,
+        Some((block, true)) => ReceiptCheck::Moved {
+            unichain_block: block,
+        },
+    }
+}
+
+/// Newest Unichain block a posting can have been mined in and be due for
+/// re-checking at `head`; `None` before the chain is that long
+pub fn settled_block(head: u64, depth: u64) -> Option<u64> {
+    head.checked_sub(depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(Some(100), Some((100, true))), ReceiptCheck::Confirmed);
+        assert_eq!(
+            check(Some(100), Some((104, true))),
+            ReceiptCheck::Moved {
+                unichain_block: 104
+            }
+        );
+        // Recorded before the block number was known
+        assert_eq!(
+            check(None, Some((100, true))),
+            ReceiptCheck::Moved {
+                unichain_block: 100
+            }
+        );
+        assert_eq!(check(Some(100), None), ReceiptCheck::Dropped);
+        assert_eq!(check(Some(100), Some((104, false))), ReceiptCheck::Dropped);
+
+        assert_eq!(settled_block(1_000, 60), Some(940));
+        assert_eq!(settled_block(10, 60), None);
+    }
+}
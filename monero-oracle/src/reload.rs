@@ -0,0 +1,96 @@
+//! Hot reload of the config file
+//!
+//! While running, the oracle watches the file it was started with and
+//! re-reads it on every change. Settings that only tune how it runs are
+//! applied right away: `POLL_INTERVAL_SECS`, `LOG_LEVEL`, the `ALERT_*`
+//! webhook and the gas settings, budgets included. Changes to anything else (nodes, keys, the
+//! contract) are logged as needing a restart. A file that fails to parse or
+//! validate is ignored and the previous settings stay in effect.
+//!
+//! Environment variables still win over the file, so a setting also given in
+//! the environment can't be changed this way.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{path::Path, time::Duration};
+use tokio::sync::mpsc;
+use tracing::level_filters::LevelFilter;
+
+/// Editors write a file in several steps; changes are applied once it has
+/// been quiet this long
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Applies a new `LOG_LEVEL` to the installed subscriber
+pub type LogFilter = Box<dyn Fn(LevelFilter) -> Result<()> + Send + Sync>;
+
+/// Settings applied without a restart
+const RELOADABLE: &[&str] = &[
+    "POLL_INTERVAL_SECS",
+    "LOG_LEVEL",
+    "GAS_STRATEGY",
+    "MAX_FEE_PER_GAS_GWEI",
+    "PRIORITY_FEE_GWEI",
+    "FEE_BUMP_PERCENT",
+    "STUCK_TX_TIMEOUT_SECS",
+    "MAX_FEE_BUMPS",
+    "GAS_BUDGET_DAILY_ETH",
+    "GAS_BUDGET_WEEKLY_ETH",
+];
+
+/// Whether a change to `key` takes effect without a restart
+pub fn is_reloadable(key: &str) -> bool {
+    key.starts_with("ALERT_") || RELOADABLE.contains(&key)
+}
+
+/// Watch `path` for changes. The watcher stops when dropped.
+///
+/// The directory is watched rather than the file, since editors and config
+/// management often replace the file instead of writing to it.
+pub fn watch(path: &Path) -> Result<(RecommendedWatcher, mpsc::Receiver<()>)> {
+    let name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file", path.display()))?
+        .to_os_string();
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let (tx, rx) = mpsc::channel(1);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        if event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == Some(name.as_os_str()))
+        {
+            // A change already queued covers this one
+            let _ = tx.try_send(());
+        }
+    })?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    Ok((watcher, rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reloadable_settings() {
+        assert!(is_reloadable("POLL_INTERVAL_SECS"));
+        assert!(is_reloadable("LOG_LEVEL"));
+        assert!(is_reloadable("ALERT_WEBHOOK_URL"));
+        assert!(is_reloadable("GAS_STRATEGY"));
+        assert!(!is_reloadable("MONERO_RPC_URL"));
+        assert!(!is_reloadable("BRIDGE_ADDRESS"));
+        assert!(!is_reloadable("PRIVATE_KEY"));
+    }
+}
@@ -0,0 +1,155 @@
+//! Reorg detection for posted Monero blocks
+//!
+//! Keeps a sliding window of the block hashes the oracle has posted. Once a
+//! posted block is buried under enough confirmations it is re-checked against
+//! the Monero node; a hash mismatch means the contract is following an
+//! orphaned chain and must be rolled back.
+//!
+//! Before a block is posted, its `prev_hash` must also match the hash the
+//! contract stores for the height below, so a node on another fork (or one
+//! returning inconsistent data) can't extend the contract's chain.
+
+use crate::error::OracleError;
+use alloy::primitives::B256;
+use anyhow::Result;
+use std::collections::VecDeque;
+
+/// A block the oracle has posted to the contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostedBlock {
+    pub height: u64,
+    pub hash: B256,
+}
+
+/// Tracks the last N posted block hashes
+#[derive(Debug)]
+pub struct ReorgDetector {
+    blocks: VecDeque<PostedBlock>,
+    max_tracked: usize,
+    confirmation_depth: u64,
+}
+
+impl ReorgDetector {
+    pub fn new(max_tracked: usize, confirmation_depth: u64) -> Self {
+        Self {
+            blocks: VecDeque::with_capacity(max_tracked),
+            max_tracked,
+            confirmation_depth,
+        }
+    }
+
+    /// Record a freshly posted block, evicting the oldest entry when full
+    pub fn record(&mut self, height: u64, hash: B256) {
+        // A re-post after a rollback replaces anything at or above this height
+        self.rollback(height);
+
+        self.blocks.push_back(PostedBlock { height, hash });
+        while self.blocks.len() > self.max_tracked {
+            self.blocks.pop_front();
+        }
+    }
+
+    /// Tracked blocks with at least `confirmation_depth` blocks on top of them,
+    /// lowest height first
+    pub fn due_for_check(&self, tip_height: u64) -> Vec<PostedBlock> {
+        self.blocks
+            .iter()
+            .filter(|b| b.height + self.confirmation_depth <= tip_height)
+            .copied()
+            .collect()
+    }
+
+    /// Forget every tracked block at or above `from_height`
+    pub fn rollback(&mut self, from_height: u64) {
+        self.blocks.retain(|b| b.height < from_height);
+    }
+
+    /// Lowest tracked height, if any
+    pub fn oldest_height(&self) -> Option<u64> {
+        self.blocks.front().map(|b| b.height)
+    }
+}
+
+/// Check that a block at `height` links to `parent`, the hash the contract
+/// holds for `height - 1`. Without a stored parent there is nothing to check.
+pub fn check_parent(height: u64, prev_hash: B256, parent: Option<B256>) -> Result<()> {
+    match parent {
+        Some(parent) if parent != prev_hash => Err(OracleError::Consensus(format!(
+            "Block {} doesn't link to the contract's block {}: prev_hash {}, contract has {}; refusing to post",
+            height,
+            height.saturating_sub(1),
+            prev_hash,
+            parent
+        ))
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> B256 {
+        B256::from([byte; 32])
+    }
+
+    #[test]
+    fn test_record_evicts_oldest() {
+        let mut detector = ReorgDetector::new(3, 0);
+        for height in 1..=5 {
+            detector.record(height, hash(height as u8));
+        }
+
+        let tracked: Vec<u64> = detector.due_for_check(5).iter().map(|b| b.height).collect();
+        assert_eq!(tracked, vec![3, 4, 5]);
+        assert_eq!(detector.oldest_height(), Some(3));
+    }
+
+    #[test]
+    fn test_due_for_check_respects_depth() {
+        let mut detector = ReorgDetector::new(10, 10);
+        for height in 100..105 {
+            detector.record(height, hash(1));
+        }
+
+        assert!(detector.due_for_check(109).is_empty());
+
+        let due: Vec<u64> = detector
+            .due_for_check(112)
+            .iter()
+            .map(|b| b.height)
+            .collect();
+        assert_eq!(due, vec![100, 101, 102]);
+    }
+
+    #[test]
+    fn test_check_parent() {
+        assert!(check_parent(101, hash(1), Some(hash(1))).is_ok());
+        assert!(check_parent(101, hash(1), None).is_ok());
+
+        let err = check_parent(101, hash(1), Some(hash(2))).unwrap_err();
+        assert!(err.to_string().contains("block 100"));
+    }
+
+    #[test]
+    fn test_rollback_and_repost() {
+        let mut detector = ReorgDetector::new(10, 0);
+        for height in 1..=4 {
+            detector.record(height, hash(1));
+        }
+
+        detector.rollback(3);
+        detector.record(3, hash(2));
+
+        let due = detector.due_for_check(3);
+        assert_eq!(due.len(), 3);
+        assert_eq!(
+            due[2],
+            PostedBlock {
+                height: 3,
+                hash: hash(2)
+            }
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Retries with exponential backoff for Monero and Unichain RPC calls
+//!
+//! A single dropped connection used to abort the whole poll cycle, leaving
+//! the rest of its work (mints, withdrawals, wallet checks) for the next one.
+//! Calls are now retried when the failure looks transient: network errors,
+//! timeouts and 5xx responses back off exponentially with jitter, rate limits
+//! (HTTP 429) start from a longer delay, and anything else - RPC error
+//! responses, reverts, malformed data - fails immediately. Failures are told
+//! apart by type, see [`error`](crate::error).
+
+use crate::{error, metrics};
+use anyhow::Result;
+use rand::Rng;
+use std::{fmt, future::Future, time::Duration};
+use tracing::warn;
+
+/// Rate-limited calls start backing off at `2^RATE_LIMIT_SHIFT` times the
+/// base delay
+const RATE_LIMIT_SHIFT: u32 = 2;
+
+/// How a failed call should be treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Connection failures, timeouts and 5xx responses
+    Transient,
+    /// HTTP 429 or a provider's rate limit message
+    RateLimited,
+    /// Won't succeed on retry
+    Permanent,
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Transient => "transient",
+            Self::RateLimited => "rate limited",
+            Self::Permanent => "permanent",
+        })
+    }
+}
+
+/// How to treat `error`; errors of no known class aren't retried
+pub fn classify(error: &anyhow::Error) -> ErrorClass {
+    error::classify(error).1
+}
+
+/// Backoff settings shared by the RPC clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (starting at 0): a random point in
+    /// the upper half of the exponential backoff, so clients that failed
+    /// together don't retry together
+    pub fn delay(&self, attempt: u32, class: ErrorClass) -> Duration {
+        let shift = match class {
+            ErrorClass::RateLimited => attempt + RATE_LIMIT_SHIFT,
+            _ => attempt,
+        };
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1u32 << shift.min(16))
+            .min(self.max_delay);
+        let ceiling_ms = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(ceiling_ms / 2..=ceiling_ms))
+    }
+
+    /// Run `call` until it succeeds, fails permanently or runs out of retries.
+    /// `rpc` and `method` label the retry metric and log lines.
+    pub async fn retry<T, F, Fut>(&self, rpc: &str, method: &str, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match call().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            let class = classify(&error);
+            if class == ErrorClass::Permanent || attempt >= self.max_retries {
+                return Err(error);
+            }
+
+            let delay = self.delay(attempt, class);
+            warn!(
+                "   ⚠️  {} {} failed ({}), retry {}/{} in {:?}: {:#}",
+                rpc,
+                method,
+                class,
+                attempt + 1,
+                self.max_retries,
+                delay,
+                error
+            );
+            metrics::get()
+                .rpc_retries
+                .with_label_values(&[rpc, method])
+                .inc();
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{status_class, OracleError};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn monero_error(message: &str, retry: ErrorClass) -> anyhow::Error {
+        OracleError::MoneroRpc {
+            method: "get_block",
+            message: message.to_string(),
+            retry,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(&monero_error("slow down", ErrorClass::RateLimited)),
+            ErrorClass::RateLimited
+        );
+        assert_eq!(
+            classify(&monero_error("Invalid height", ErrorClass::Permanent)),
+            ErrorClass::Permanent
+        );
+        assert_eq!(
+            classify(&anyhow::anyhow!("HTTP error 503 with body: ")),
+            ErrorClass::Permanent
+        );
+        assert_eq!(status_class(502), ErrorClass::Transient);
+        assert_eq!(status_class(404), ErrorClass::Permanent);
+    }
+
+    #[test]
+    fn test_delay_backs_off_within_bounds() {
+        let policy = RetryPolicy::default();
+
+        for attempt in 0..4 {
+            let ceiling = policy.base_delay * (1 << attempt);
+            let delay = policy.delay(attempt, ErrorClass::Transient);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+        }
+
+        // Rate limits start four times higher, and nothing exceeds the cap
+        let delay = policy.delay(0, ErrorClass::RateLimited);
+        assert!(delay >= policy.base_delay * 2 && delay <= policy.base_delay * 4);
+        assert!(policy.delay(30, ErrorClass::RateLimited) <= policy.max_delay);
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors_only() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        };
+
+        let calls = AtomicU32::new(0);
+        let result = policy
+            .retry("monero", "get_block", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(monero_error("connection reset", ErrorClass::Transient)),
+                    _ => Ok(7),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = policy
+            .retry("monero", "get_block", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(monero_error("Invalid height", ErrorClass::Permanent))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = policy
+            .retry("monero", "get_block", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(monero_error("operation timed out", ErrorClass::Transient))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,216 @@
+//! Contract reverts decoded, and what the oracle does about each
+//!
+//! A call that would revert fails its gas estimate, and the node returns the
+//! revert data with the error. It is decoded against the contract ABI -
+//! WrappedMonero's `require` strings, the custom errors of the OpenZeppelin
+//! libraries it uses, and Solidity panics - instead of matching on error
+//! text, which differs between nodes. Each reason maps to a [`Recovery`].
+
+use alloy::{
+    contract,
+    primitives::Bytes,
+    sol,
+    sol_types::{ContractError, Panic, SolInterface},
+};
+use std::fmt;
+
+sol! {
+    #![sol(all_derives)]
+
+    /// Custom errors WrappedMonero can revert with, all from OpenZeppelin;
+    /// its own checks revert with `require` strings
+    interface OpenZeppelin {
+        error ECDSAInvalidSignature();
+        error ECDSAInvalidSignatureLength(uint256 length);
+        error ECDSAInvalidSignatureS(bytes32 s);
+        error ReentrancyGuardReentrantCall();
+        error EnforcedPause();
+    }
+}
+
+use OpenZeppelin::OpenZeppelinErrors as CustomError;
+
+/// Why a contract call reverted
+#[derive(Debug, PartialEq)]
+pub enum Revert {
+    /// `require(condition, reason)` failed
+    Require(String),
+    Custom(CustomError),
+    /// `assert`, arithmetic overflow and other Solidity panics
+    Panic(Panic),
+    /// Revert data matching nothing in the ABI, or none at all
+    Unknown(Bytes),
+}
+
+/// What to do after a revert
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// The contract already has what the call was for; carry on
+    Skip,
+    /// Expected to pass once the contract or the oracle catches up; try
+    /// again next poll
+    Retry,
+    /// Stop sending transactions until an operator resumes the oracle
+    Halt,
+    /// Try again next poll, but tell an operator
+    Alert,
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Skip => "skip",
+            Self::Retry => "retry",
+            Self::Halt => "halt",
+            Self::Alert => "alert",
+        })
+    }
+}
+
+impl Revert {
+    /// Decode revert data returned by the node
+    pub fn decode(data: &[u8]) -> Self {
+        match ContractError::<CustomError>::abi_decode(data, true) {
+            Ok(ContractError::Revert(revert)) => Self::Require(revert.reason),
+            Ok(ContractError::CustomError(error)) => Self::Custom(error),
+            Ok(ContractError::Panic(panic)) => Self::Panic(panic),
+            Err(_) => Self::Unknown(Bytes::copy_from_slice(data)),
+        }
+    }
+
+    /// The revert behind a failed send, if it failed because the call
+    /// reverts rather than for a transport or signing problem
+    pub fn from_error(error: &anyhow::Error) -> Option<Self> {
+        let payload =
+            error
+                .chain()
+                .find_map(|cause| match cause.downcast_ref::<contract::Error>()? {
+                    contract::Error::TransportError(error) => error.as_error_resp(),
+                    _ => None,
+                })?;
+        match payload.as_revert_data() {
+            Some(data) if !data.is_empty() => Some(Self::decode(&data)),
+            // Some nodes only give the reason in the message
+            _ => Self::from_message(&payload.message),
+        }
+    }
+
+    /// A revert reported as `execution reverted[: reason]` without data
+    fn from_message(message: &str) -> Option<Self> {
+        let rest = message.strip_prefix("execution reverted")?;
+        Some(match rest.strip_prefix(": ") {
+            Some(reason) if !reason.is_empty() => Self::Require(reason.to_string()),
+            _ => Self::Unknown(Bytes::new()),
+        })
+    }
+
+    /// What to do about this revert from a block posting, fill or rollback
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            Self::Require(reason) => match reason.as_str() {
+                // Posted by an earlier attempt or another submitter, or the
+                // contract has moved past this height
+                "Block exists" | "Height must increase" | "Not below latest block" => {
+                    Recovery::Skip
+                }
+                // The role check at the next poll switches keys or goes
+                // read-only; signers may still be catching up
+                "Only oracle" | "Not enough signatures" => Recovery::Retry,
+                // Paused between the poll's check and the send
+                "Pausable: paused" => Recovery::Retry,
+                _ => Recovery::Alert,
+            },
+            Self::Custom(
+                CustomError::ReentrancyGuardReentrantCall(_) | CustomError::EnforcedPause(_),
+            ) => Recovery::Retry,
+            // A signer produced a malformed signature
+            Self::Custom(_) => Recovery::Alert,
+            // The contract is broken, or isn't the one this ABI describes
+            Self::Panic(_) | Self::Unknown(_) => Recovery::Halt,
+        }
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Require(reason) => write!(f, "\"{}\"", reason),
+            Self::Custom(error) => match error {
+                CustomError::ECDSAInvalidSignature(_) => f.write_str("ECDSAInvalidSignature()"),
+                CustomError::ECDSAInvalidSignatureLength(error) => {
+                    write!(f, "ECDSAInvalidSignatureLength({})", error.length)
+                }
+                CustomError::ECDSAInvalidSignatureS(error) => {
+                    write!(f, "ECDSAInvalidSignatureS({})", error.s)
+                }
+                CustomError::ReentrancyGuardReentrantCall(_) => {
+                    f.write_str("ReentrancyGuardReentrantCall()")
+                }
+                CustomError::EnforcedPause(_) => f.write_str("EnforcedPause()"),
+            },
+            Self::Panic(panic) => write!(f, "{}", panic),
+            Self::Unknown(data) if data.is_empty() => f.write_str("no reason given"),
+            Self::Unknown(data) => write!(f, "unknown revert data {}", data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        primitives::U256,
+        sol_types::{PanicKind, Revert as RequireFailed, SolError},
+    };
+
+    #[test]
+    fn test_decode_reverts() {
+        let require = RequireFailed::from("Block exists").abi_encode();
+        assert_eq!(
+            Revert::decode(&require),
+            Revert::Require("Block exists".to_string())
+        );
+        assert_eq!(Revert::decode(&require).recovery(), Recovery::Skip);
+
+        let custom = OpenZeppelin::ECDSAInvalidSignatureLength {
+            length: U256::from(64),
+        }
+        .abi_encode();
+        let decoded = Revert::decode(&custom);
+        assert_eq!(decoded.to_string(), "ECDSAInvalidSignatureLength(64)");
+        assert_eq!(decoded.recovery(), Recovery::Alert);
+
+        let panic = Panic::from(PanicKind::UnderOverflow).abi_encode();
+        assert_eq!(Revert::decode(&panic).recovery(), Recovery::Halt);
+
+        let unknown = Revert::decode(&[0xde, 0xad, 0xbe, 0xef]);
+        assert!(matches!(unknown, Revert::Unknown(_)));
+        assert_eq!(unknown.recovery(), Recovery::Halt);
+    }
+
+    #[test]
+    fn test_recovery_by_reason() {
+        let recovery = |reason: &str| Revert::Require(reason.to_string()).recovery();
+        assert_eq!(recovery("Height must increase"), Recovery::Skip);
+        assert_eq!(recovery("Not enough signatures"), Recovery::Retry);
+        assert_eq!(recovery("Pausable: paused"), Recovery::Retry);
+        assert_eq!(
+            Revert::decode(&OpenZeppelin::EnforcedPause {}.abi_encode()).recovery(),
+            Recovery::Retry
+        );
+        assert_eq!(recovery("Not a signer"), Recovery::Alert);
+    }
+
+    #[test]
+    fn test_reason_from_message() {
+        assert_eq!(
+            Revert::from_message("execution reverted: Only oracle"),
+            Some(Revert::Require("Only oracle".to_string()))
+        );
+        assert_eq!(
+            Revert::from_message("execution reverted"),
+            Some(Revert::Unknown(Bytes::new()))
+        );
+        assert_eq!(Revert::from_message("nonce too low"), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! Block postings proposed to a Safe multisig instead of sent directly
+//!
+//! When the contract's oracle role is held by a Safe (`SAFE_ADDRESS`), the
+//! oracle key can't post blocks itself. Instead each posting becomes a Safe
+//! transaction: the oracle signs its EIP-712 `SafeTx` hash as one owner (or
+//! delegate) and proposes it to the Safe Transaction Service
+//! (`SAFE_TX_SERVICE_URL`), where the other owners confirm and execute it.
+//! Postings are proposed at consecutive Safe nonces, so they execute in
+//! block order.
+
+use alloy::{
+    primitives::{Address, Bytes, PrimitiveSignature, B256, U256},
+    sol,
+    sol_types::{eip712_domain, Eip712Domain, SolStruct},
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize, Serializer};
+
+sol! {
+    /// A Safe transaction; mirrors the Safe contract's `SAFE_TX_TYPEHASH`
+    struct SafeTx {
+        address to;
+        uint256 value;
+        bytes data;
+        uint8 operation;
+        uint256 safeTxGas;
+        uint256 baseGas;
+        uint256 gasPrice;
+        address gasToken;
+        address refundReceiver;
+        uint256 nonce;
+    }
+}
+
+/// The Safe holding the oracle role and the service its owners use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafeConfig {
+    pub address: Address,
+    /// Safe Transaction Service base URL, without a trailing slash
+    pub service_url: String,
+}
+
+/// EIP-712 domain of a Safe (v1.3.0 and later)
+pub fn domain(chain_id: u64, safe: Address) -> Eip712Domain {
+    eip712_domain! {
+        chain_id: chain_id,
+        verifying_contract: safe,
+    }
+}
+
+impl SafeTx {
+    /// A plain `CALL` from the Safe with no gas refund, so whoever executes
+    /// it pays the gas
+    pub fn call(to: Address, data: Bytes, nonce: u64) -> Self {
+        Self {
+            to,
+            value: U256::ZERO,
+            data,
+            operation: 0,
+            safeTxGas: U256::ZERO,
+            baseGas: U256::ZERO,
+            gasPrice: U256::ZERO,
+            gasToken: Address::ZERO,
+            refundReceiver: Address::ZERO,
+            nonce: U256::from(nonce),
+        }
+    }
+}
+
+/// Body of a Transaction Service proposal: the transaction, its hash and
+/// the proposer's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proposal {
+    #[serde(serialize_with = "checksummed")]
+    pub to: Address,
+    pub value: String,
+    pub data: Bytes,
+    pub operation: u8,
+    pub safe_tx_gas: String,
+    pub base_gas: String,
+    pub gas_price: String,
+    #[serde(serialize_with = "checksummed")]
+    pub gas_token: Address,
+    #[serde(serialize_with = "checksummed")]
+    pub refund_receiver: Address,
+    pub nonce: String,
+    pub contract_transaction_hash: B256,
+    #[serde(serialize_with = "checksummed")]
+    pub sender: Address,
+    pub signature: Bytes,
+    pub origin: String,
+}
+
+impl Proposal {
+    /// `tx` as signed by `sender`, an owner or delegate of the Safe
+    pub fn new(
+        tx: &SafeTx,
+        safe_tx_hash: B256,
+        sender: Address,
+        signature: &PrimitiveSignature,
+    ) -> Self {
+        Self {
+            to: tx.to,
+            value: tx.value.to_string(),
+            data: tx.data.clone(),
+            operation: tx.operation,
+            safe_tx_gas: tx.safeTxGas.to_string(),
+            base_gas: tx.baseGas.to_string(),
+            gas_price: tx.gasPrice.to_string(),
+            gas_token: tx.gasToken,
+            refund_receiver: tx.refundReceiver,
+            nonce: tx.nonce.to_string(),
+            contract_transaction_hash: safe_tx_hash,
+            sender,
+            // r, s and v = 27/28: a plain ECDSA signature over the hash
+            signature: signature.as_bytes().into(),
+            origin: "monero-oracle".to_string(),
+        }
+    }
+}
+
+/// The service rejects addresses that aren't EIP-55 checksummed
+fn checksummed<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&address.to_checksum(None))
+}
+
+/// A proposed transaction as the Transaction Service reports it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceTransaction {
+    pub is_executed: bool,
+    /// `None` until executed
+    pub is_successful: Option<bool>,
+    pub transaction_hash: Option<B256>,
+    pub block_number: Option<u64>,
+    pub gas_used: Option<u64>,
+}
+
+/// Safe Transaction Service API client for one Safe
+#[derive(Debug, Clone)]
+pub struct SafeClient {
+    config: SafeConfig,
+    http: reqwest::Client,
+}
+
+impl SafeClient {
+    pub fn new(config: SafeConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.config.address
+    }
+
+    /// Hash the owners sign for `tx` on chain `chain_id`
+    pub fn signing_hash(&self, chain_id: u64, tx: &SafeTx) -> B256 {
+        tx.eip712_signing_hash(&domain(chain_id, self.config.address))
+    }
+
+    /// Submit a signed transaction for the other owners to confirm
+    pub async fn propose(&self, proposal: &Proposal) -> Result<()> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/api/v1/safes/{}/multisig-transactions/",
+                self.config.service_url,
+                self.config.address.to_checksum(None)
+            ))
+            .json(proposal)
+            .send()
+            .await
+            .context("Failed to reach the Safe Transaction Service")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Safe Transaction Service rejected the proposal ({}): {}",
+                status,
+                body
+            );
+        }
+        Ok(())
+    }
+
+    /// A proposed transaction's state, `None` if the service doesn't know it
+    pub async fn transaction(&self, safe_tx_hash: B256) -> Result<Option<ServiceTransaction>> {
+        let response = self
+            .http
+            .get(format!(
+                "{}/api/v1/multisig-transactions/{}/",
+                self.config.service_url, safe_tx_hash
+            ))
+            .send()
+            .await
+            .context("Failed to reach the Safe Transaction Service")?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        primitives::{address, b256, keccak256},
+        signers::{local::PrivateKeySigner, SignerSync},
+    };
+
+    #[test]
+    fn test_safe_tx_type_hashes() {
+        let tx = SafeTx::call(Address::ZERO, Bytes::new(), 0);
+        // SAFE_TX_TYPEHASH and DOMAIN_SEPARATOR_TYPEHASH from Safe v1.3.0
+        assert_eq!(
+            tx.eip712_type_hash(),
+            b256!("bb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8")
+        );
+        assert_eq!(
+            keccak256(domain(1, Address::ZERO).encode_type()),
+            b256!("47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218")
+        );
+    }
+
+    #[test]
+    fn test_proposal_payload() {
+        let safe = address!("1111111111111111111111111111111111111111");
+        let bridge = address!("00000000000000000000000000000000000000aa");
+        let client = SafeClient::new(SafeConfig {
+            address: safe,
+            service_url: "http://localhost:8000".to_string(),
+        });
+        let tx = SafeTx::call(bridge, Bytes::from(vec![0xde, 0xad]), 7);
+        let hash = client.signing_hash(130, &tx);
+        assert_ne!(hash, client.signing_hash(1, &tx));
+        assert_ne!(
+            hash,
+            client.signing_hash(130, &SafeTx::call(bridge, Bytes::new(), 7))
+        );
+
+        let signer = PrivateKeySigner::random();
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        let proposal = Proposal::new(&tx, hash, signer.address(), &signature);
+        let json = serde_json::to_value(&proposal).unwrap();
+
+        assert_eq!(json["to"], bridge.to_checksum(None));
+        assert_eq!(json["data"], "0xdead");
+        assert_eq!(json["nonce"], "7");
+        assert_eq!(json["safeTxGas"], "0");
+        assert_eq!(json["refundReceiver"], Address::ZERO.to_checksum(None));
+        assert_eq!(json["contractTransactionHash"], hash.to_string());
+
+        // The service recovers the sender from the signature over the hash
+        let bytes = &proposal.signature;
+        assert_eq!(bytes.len(), 65);
+        assert!(bytes[64] == 27 || bytes[64] == 28);
+        let recovered = PrimitiveSignature::try_from(&bytes[..])
+            .unwrap()
+            .recover_address_from_prehash(&hash)
+            .unwrap();
+        assert_eq!(recovered, signer.address());
+    }
+}
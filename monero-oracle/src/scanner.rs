@@ -0,0 +1,825 @@
+//! View-key scanning for deposits to the bridge
+//!
+//! With the bridge's private view key and public spend key, every output of a
+//! block can be tested for ownership: the shared secret `8·a·R` with the tx
+//! public key yields the expected one-time key `Hs(8aR || i)·G + B`. Matching
+//! outputs have their ECDH amount decrypted and checked against the output's
+//! Pedersen commitment, so a sender can't claim more than they actually sent.
+//! Both ECDH formats are understood: the compact 8-byte amount XORed with
+//! `keccak("amount" || Hs(8aR || i))`, and the 32-byte amount and mask of
+//! pre-Bulletproofs 2 transactions, offset by `Hs(Hs(8aR || i))` and
+//! `Hs(8aR || i)`. Outputs without RingCT data (miner transactions) carry
+//! their amount in the clear.
+//!
+//! Subaddresses are matched the way wallets do it: subtracting `Hs(8aR || i)·G`
+//! from the output key leaves the recipient's spend key, which is looked up in
+//! a table of tracked subaddresses.
+//!
+//! Deposits to the primary address can instead be attributed by payment ID:
+//! an integrated address embeds 8 bytes that the sender's wallet encrypts into
+//! `tx_extra`, XORed with `keccak(8aR || 0x8d)`.
+
+use crate::{
+    merkle::write_varint,
+    metrics,
+    monero_rpc::{parse_hex_to_b256, EcdhInfo, TransactionJson},
+    subaddress::{
+        encode_address, encode_integrated_address, subaddress_spend_public, Network,
+        SubaddressIndex,
+    },
+    tx_extra::TxExtra,
+};
+use alloy::primitives::{keccak256, B256, B64};
+use anyhow::{Context, Result};
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use serde::Serialize;
+use std::{collections::HashMap, fmt};
+use tracing::warn;
+
+/// Monero's second Pedersen generator `H` (amounts are committed as `mask·G + amount·H`)
+const PEDERSEN_H: [u8; 32] = [
+    0x8b, 0x65, 0x59, 0x70, 0x15, 0x37, 0x99, 0xaf, 0x2a, 0xea, 0xdc, 0x9f, 0xf1, 0xad, 0xd0, 0xea,
+    0x6c, 0x72, 0x51, 0xd5, 0x41, 0x54, 0xcf, 0xa9, 0x2c, 0x17, 0x3a, 0x0d, 0xd3, 0x9c, 0x1f, 0x94,
+];
+
+/// Appended to the derivation when hashing the payment ID key
+const ENCRYPTED_PAYMENT_ID_TAIL: u8 = 0x8d;
+
+/// The bridge wallet's scanning keys and the subaddresses it watches
+#[derive(Clone)]
+pub struct ViewKeys {
+    view_secret: Scalar,
+    spend_public: EdwardsPoint,
+    /// Compressed subaddress spend key -> index
+    subaddresses: HashMap<[u8; 32], SubaddressIndex>,
+}
+
+impl ViewKeys {
+    /// Keys that only watch the primary address
+    pub fn new(view_secret: Scalar, spend_public: EdwardsPoint) -> Self {
+        let mut keys = Self {
+            view_secret,
+            spend_public,
+            subaddresses: HashMap::new(),
+        };
+        keys.track(SubaddressIndex::default());
+        keys
+    }
+
+    /// Also watch subaddresses `account/0` through `account/(lookahead - 1)`
+    pub fn with_subaddresses(mut self, account: u32, lookahead: u32) -> Self {
+        for minor in 0..lookahead {
+            self.track(SubaddressIndex::new(account, minor));
+        }
+        self
+    }
+
+    fn track(&mut self, index: SubaddressIndex) {
+        let spend = subaddress_spend_public(&self.view_secret, &self.spend_public, index);
+        self.subaddresses.insert(spend.compress().to_bytes(), index);
+    }
+
+    /// Whether outputs to `index` are detected
+    pub fn tracks(&self, index: SubaddressIndex) -> bool {
+        self.subaddresses.values().any(|i| *i == index)
+    }
+
+    /// Address string for subaddress `index`
+    pub fn address(&self, index: SubaddressIndex, network: Network) -> String {
+        let spend = subaddress_spend_public(&self.view_secret, &self.spend_public, index);
+        // A subaddress's view key is a·D, the primary address's a·G
+        let view = if index.is_primary() {
+            &self.view_secret * ED25519_BASEPOINT_TABLE
+        } else {
+            self.view_secret * spend
+        };
+        encode_address(network, index, &spend, &view)
+    }
+
+    /// Private view key as hex, for creating a view-only wallet
+    pub fn view_key_hex(&self) -> String {
+        hex::encode(self.view_secret.as_bytes())
+    }
+
+    /// Integrated address: the primary address with `payment_id` embedded
+    pub fn integrated_address(&self, payment_id: B64, network: Network) -> String {
+        encode_integrated_address(
+            network,
+            &self.spend_public,
+            &(&self.view_secret * ED25519_BASEPOINT_TABLE),
+            &payment_id.0,
+        )
+    }
+
+    /// Parse a private view key and public spend key as 64-char hex
+    pub fn from_hex(view_secret: &str, spend_public: &str) -> Result<Self> {
+        let view_bytes = parse_hex_to_b256(view_secret).context("Invalid view key")?;
+        let view_secret = Option::<Scalar>::from(Scalar::from_canonical_bytes(view_bytes.0))
+            .context("View key is not a canonical scalar")?;
+        let spend_public = decompress(&parse_hex_to_b256(spend_public)?.0)
+            .context("Spend public key is not a valid point")?;
+        Ok(Self::new(view_secret, spend_public))
+    }
+}
+
+impl fmt::Debug for ViewKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewKeys")
+            .field("view_secret", &"<redacted>")
+            .field(
+                "spend_public",
+                &hex::encode(self.spend_public.compress().as_bytes()),
+            )
+            .field("subaddresses", &self.subaddresses.len())
+            .finish()
+    }
+}
+
+/// An output addressed to the bridge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deposit {
+    pub block_height: u64,
+    pub tx_hash: B256,
+    /// Index within the transaction's outputs
+    pub output_index: u64,
+    /// Amount in piconero
+    pub amount: u64,
+    pub output_pub_key: B256,
+    /// Subaddress the output was sent to, identifying the depositing user
+    pub subaddress: SubaddressIndex,
+    /// Payment ID of the integrated address the output was sent to, which
+    /// identifies the depositing user instead for primary address deposits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<B64>,
+}
+
+/// Scan every transaction of a block for outputs owned by `keys`. Owned
+/// outputs whose amount fails its commitment check are counted in
+/// `deposits_rejected_total`.
+pub fn scan_block(
+    keys: &ViewKeys,
+    block_height: u64,
+    transactions: &[(String, TransactionJson)],
+) -> Result<Vec<Deposit>> {
+    let mut deposits = Vec::new();
+    for (tx_hash, tx) in transactions {
+        let (found, rejected) = scan(keys, block_height, tx_hash, tx)?;
+        deposits.extend(found);
+        metrics::get().deposits_rejected.inc_by(rejected);
+    }
+    Ok(deposits)
+}
+
+/// A deposit whose one-time output key was already credited
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReusedKey {
+    pub deposit: Deposit,
+    /// Transaction and output index the key was first credited to
+    pub credited: (B256, u64),
+}
+
+/// Set aside deposits whose one-time key was already credited, to an
+/// output found earlier (looked up with `credited`) or earlier in
+/// `deposits`. Transactions built with the same tx secret key give their
+/// outputs the same one-time key, and the key image spending one spends
+/// them all (Monero's "burning bug"): crediting each would mint tokens
+/// that only one output's XMR stands behind.
+pub fn split_reused_keys(
+    deposits: Vec<Deposit>,
+    mut credited: impl FnMut(B256) -> Result<Option<(B256, u64)>>,
+) -> Result<(Vec<Deposit>, Vec<ReusedKey>)> {
+    let mut fresh: Vec<Deposit> = Vec::new();
+    let mut reused = Vec::new();
+    for deposit in deposits {
+        let first = match fresh
+            .iter()
+            .find(|earlier| earlier.output_pub_key == deposit.output_pub_key)
+        {
+            Some(earlier) => Some((earlier.tx_hash, earlier.output_index)),
+            None => credited(deposit.output_pub_key)?,
+        };
+        match first {
+            // The same output seen again, e.g. re-mined after a reorg
+            Some(first) if first != (deposit.tx_hash, deposit.output_index) => {
+                reused.push(ReusedKey {
+                    deposit,
+                    credited: first,
+                })
+            }
+            _ => fresh.push(deposit),
+        }
+    }
+    Ok((fresh, reused))
+}
+
+/// Outputs of `tx` owned by `keys`, with decrypted and commitment-checked amounts
+pub fn scan_transaction(
+    keys: &ViewKeys,
+    block_height: u64,
+    tx_hash: &str,
+    tx: &TransactionJson,
+) -> Result<Vec<Deposit>> {
+    scan(keys, block_height, tx_hash, tx).map(|(deposits, _)| deposits)
+}
+
+/// Owned outputs of `tx`, and how many more were owned but rejected
+fn scan(
+    keys: &ViewKeys,
+    block_height: u64,
+    tx_hash: &str,
+    tx: &TransactionJson,
+) -> Result<(Vec<Deposit>, u64)> {
+    let Some(vout) = &tx.vout else {
+        return Ok((vec![], 0));
+    };
+    let rct = tx.rct_signatures.as_ref();
+    let ecdh_info = rct.and_then(|r| r.ecdh_info.as_deref()).unwrap_or_default();
+    let out_pk = rct.and_then(|r| r.out_pk.as_deref()).unwrap_or_default();
+
+    let extra = TxExtra::parse(&tx.extra);
+    let derivations: Vec<[u8; 32]> = extra
+        .pubkeys()
+        .filter_map(|r| key_derivation(&keys.view_secret, r))
+        .collect();
+
+    let mut deposits = Vec::new();
+    let mut rejected = 0;
+
+    for (i, output) in vout.iter().enumerate() {
+        let Some(target) = &output.target else {
+            continue;
+        };
+        let Some(output_key) = target.public_key() else {
+            continue;
+        };
+        let output_key = parse_hex_to_b256(output_key)?;
+        let view_tag = target
+            .tagged_key
+            .as_ref()
+            .and_then(|t| t.view_tag.as_deref())
+            .and_then(|t| u8::from_str_radix(t, 16).ok());
+
+        // Txs paying several subaddresses carry a per-output tx key in the additional list
+        let additional = extra
+            .additional_pubkeys()
+            .get(i)
+            .and_then(|r| key_derivation(&keys.view_secret, r));
+
+        for derivation in derivations.iter().chain(additional.iter()) {
+            if view_tag.is_some_and(|tag| tag != derive_view_tag(derivation, i as u64)) {
+                continue;
+            }
+
+            let shared = derivation_to_scalar(derivation, i as u64);
+            let Some(output_point) = decompress(&output_key.0) else {
+                break;
+            };
+            let recipient = output_point - &shared * ED25519_BASEPOINT_TABLE;
+            let Some(&subaddress) = keys.subaddresses.get(&recipient.compress().to_bytes()) else {
+                continue;
+            };
+
+            let amount = match (ecdh_info.get(i), out_pk.get(i)) {
+                (Some(ecdh), Some(commitment)) => decode_amount(&shared, ecdh, commitment),
+                _ if output.amount > 0 => Some(output.amount),
+                _ => {
+                    warn!("   Owned output {}:{} has no RingCT data", tx_hash, i);
+                    break;
+                }
+            };
+            match amount {
+                Some(amount) => deposits.push(Deposit {
+                    block_height,
+                    tx_hash: parse_hex_to_b256(tx_hash)?,
+                    output_index: i as u64,
+                    amount,
+                    output_pub_key: output_key,
+                    subaddress,
+                    // Integrated addresses only exist for the primary address
+                    payment_id: extra
+                        .encrypted_payment_id()
+                        .filter(|_| subaddress.is_primary())
+                        .map(|encrypted| decrypt_payment_id(derivation, encrypted))
+                        .filter(|payment_id| !payment_id.is_zero()),
+                }),
+                None => {
+                    warn!(
+                        "   ⚠️  Owned output {}:{} has an amount that doesn't match its commitment; ignoring",
+                        tx_hash, i
+                    );
+                    rejected += 1;
+                }
+            }
+            break;
+        }
+    }
+
+    Ok((deposits, rejected))
+}
+
+/// Format a piconero amount as XMR
+pub fn format_xmr(piconero: u64) -> String {
+    format!(
+        "{}.{:012}",
+        piconero / 1_000_000_000_000,
+        piconero % 1_000_000_000_000
+    )
+}
+
+/// Parse an XMR amount such as `0.05` into piconero
+pub fn parse_xmr(xmr: &str) -> Result<u64> {
+    let (whole, fraction) = xmr.trim().split_once('.').unwrap_or((xmr.trim(), ""));
+    anyhow::ensure!(
+        !(whole.is_empty() && fraction.is_empty())
+            && fraction.len() <= 12
+            && whole
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit()),
+        "Invalid XMR amount '{}'",
+        xmr
+    );
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u64 = format!("{:0<12}", fraction).parse()?;
+    whole
+        .checked_mul(1_000_000_000_000)
+        .and_then(|piconero| piconero.checked_add(fraction))
+        .with_context(|| format!("XMR amount '{}' is too large", xmr))
+}
+
+/// `encrypted XOR keccak(derivation || 0x8d)[..8]`; encryption is the same
+/// operation
+fn decrypt_payment_id(derivation: &[u8; 32], encrypted: [u8; 8]) -> B64 {
+    let mut data = derivation.to_vec();
+    data.push(ENCRYPTED_PAYMENT_ID_TAIL);
+    let key = keccak256(&data);
+
+    let mut payment_id = encrypted;
+    for (byte, k) in payment_id.iter_mut().zip(key.iter()) {
+        *byte ^= k;
+    }
+    B64::from(payment_id)
+}
+
+/// `8·a·R`, or `None` if `tx_pubkey` isn't a valid point
+fn key_derivation(view_secret: &Scalar, tx_pubkey: &[u8; 32]) -> Option<[u8; 32]> {
+    let r = decompress(tx_pubkey)?;
+    Some((view_secret * r).mul_by_cofactor().compress().to_bytes())
+}
+
+/// `Hs(derivation || varint(index))`
+fn derivation_to_scalar(derivation: &[u8; 32], index: u64) -> Scalar {
+    let mut data = derivation.to_vec();
+    write_varint(&mut data, index);
+    hash_to_scalar(&data)
+}
+
+/// First byte of `keccak256("view_tag" || derivation || varint(index))`
+fn derive_view_tag(derivation: &[u8; 32], index: u64) -> u8 {
+    let mut data = b"view_tag".to_vec();
+    data.extend_from_slice(derivation);
+    write_varint(&mut data, index);
+    keccak256(&data)[0]
+}
+
+/// Decrypt an ECDH amount and check it against `commitment`
+fn decode_amount(shared: &Scalar, ecdh: &EcdhInfo, commitment: &str) -> Option<u64> {
+    let encrypted = hex::decode(&ecdh.amount).ok()?;
+    let (mask, amount) = match encrypted.len() {
+        8 => (
+            commitment_mask(shared),
+            decrypt_compact_amount(shared, encrypted.try_into().ok()?),
+        ),
+        32 => {
+            let mask = parse_hex_to_b256(ecdh.mask.as_deref()?).ok()?;
+            decrypt_legacy_amount(shared, encrypted.try_into().ok()?, mask.0)?
+        }
+        _ => return None,
+    };
+
+    let commitment = parse_hex_to_b256(commitment).ok()?;
+    verify_commitment(&mask, amount, &commitment.0).then_some(amount)
+}
+
+/// Whether `mask·G + amount·H` is `commitment`, i.e. the decrypted amount is
+/// the one the sender committed to in `outPk`. Without this check a sender
+/// could encrypt any amount and have it minted.
+pub fn verify_commitment(mask: &Scalar, amount: u64, commitment: &[u8; 32]) -> bool {
+    commit(mask, amount).compress().to_bytes() == *commitment
+}
+
+/// `amount XOR keccak("amount" || shared)`, for 8-byte ECDH amounts
+fn decrypt_compact_amount(shared: &Scalar, encrypted: [u8; 8]) -> u64 {
+    let mut data = b"amount".to_vec();
+    data.extend_from_slice(shared.as_bytes());
+    let pad = keccak256(&data);
+
+    let mut amount_bytes = [0u8; 8];
+    for (i, byte) in amount_bytes.iter_mut().enumerate() {
+        *byte = encrypted[i] ^ pad[i];
+    }
+    u64::from_le_bytes(amount_bytes)
+}
+
+/// `mask - Hs(shared)` and `amount - Hs(Hs(shared))`, for 32-byte ECDH
+/// amounts. `None` if the amount doesn't fit in 64 bits, which an honest
+/// sender never produces.
+fn decrypt_legacy_amount(
+    shared: &Scalar,
+    encrypted_amount: [u8; 32],
+    encrypted_mask: [u8; 32],
+) -> Option<(Scalar, u64)> {
+    let mask_offset = hash_to_scalar(shared.as_bytes());
+    let amount_offset = hash_to_scalar(mask_offset.as_bytes());
+    let mask = Scalar::from_bytes_mod_order(encrypted_mask) - mask_offset;
+    let amount = Scalar::from_bytes_mod_order(encrypted_amount) - amount_offset;
+
+    let (low, high) = amount.as_bytes().split_at(8);
+    if high.iter().any(|&byte| byte != 0) {
+        return None;
+    }
+    Some((mask, u64::from_le_bytes(low.try_into().ok()?)))
+}
+
+/// `Hs("commitment_mask" || shared)`
+fn commitment_mask(shared: &Scalar) -> Scalar {
+    let mut data = b"commitment_mask".to_vec();
+    data.extend_from_slice(shared.as_bytes());
+    hash_to_scalar(&data)
+}
+
+/// Pedersen commitment `mask·G + amount·H`
+fn commit(mask: &Scalar, amount: u64) -> EdwardsPoint {
+    let h = decompress(&PEDERSEN_H).expect("H is a valid point");
+    mask * ED25519_BASEPOINT_TABLE + Scalar::from(amount) * h
+}
+
+/// Commitment `G + amount·H` that RingCT assigns to a cleartext (miner
+/// transaction) amount
+pub fn zero_commit(amount: u64) -> [u8; 32] {
+    commit(&Scalar::ONE, amount).compress().to_bytes()
+}
+
+fn hash_to_scalar(data: &[u8]) -> Scalar {
+    Scalar::from_bytes_mod_order(keccak256(data).0)
+}
+
+fn decompress(bytes: &[u8; 32]) -> Option<EdwardsPoint> {
+    CompressedEdwardsY(*bytes).decompress()
+}
+
+/// Decode a varint, returning the value and the number of bytes read
+pub(crate) fn read_varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_extra::{NONCE_ENCRYPTED_PAYMENT_ID, TX_EXTRA_NONCE, TX_EXTRA_PUBKEY};
+
+    fn scalar(byte: u8) -> Scalar {
+        Scalar::from_bytes_mod_order([byte; 32])
+    }
+
+    /// Build a one-output transaction from a sender's point of view. Payments
+    /// to a subaddress use `r·D` rather than `r·G` as the tx public key.
+    fn send_to(
+        view_public: &EdwardsPoint,
+        spend_public: &EdwardsPoint,
+        amount: u64,
+        claimed_amount: u64,
+        to_subaddress: bool,
+    ) -> TransactionJson {
+        let tx_secret = scalar(7);
+        let tx_public = if to_subaddress {
+            tx_secret * spend_public
+        } else {
+            &tx_secret * ED25519_BASEPOINT_TABLE
+        };
+        let derivation = (tx_secret * view_public)
+            .mul_by_cofactor()
+            .compress()
+            .to_bytes();
+        let shared = derivation_to_scalar(&derivation, 0);
+
+        let output_key = &shared * ED25519_BASEPOINT_TABLE + spend_public;
+        let commitment = commit(&commitment_mask(&shared), amount);
+
+        let mut data = b"amount".to_vec();
+        data.extend_from_slice(shared.as_bytes());
+        let pad = keccak256(&data);
+        let encrypted: Vec<u8> = claimed_amount
+            .to_le_bytes()
+            .iter()
+            .zip(pad.iter())
+            .map(|(a, p)| a ^ p)
+            .collect();
+
+        let mut extra = vec![TX_EXTRA_NONCE, 2, 0xaa, 0xbb, TX_EXTRA_PUBKEY];
+        extra.extend_from_slice(tx_public.compress().as_bytes());
+
+        serde_json::from_value(serde_json::json!({
+            "vout": [{
+                "target": {
+                    "tagged_key": {
+                        "key": hex::encode(output_key.compress().as_bytes()),
+                        "view_tag": format!("{:02x}", derive_view_tag(&derivation, 0)),
+                    }
+                }
+            }],
+            "extra": extra,
+            "rct_signatures": {
+                "ecdhInfo": [{ "amount": hex::encode(encrypted) }],
+                "outPk": [hex::encode(commitment.compress().as_bytes())],
+            }
+        }))
+        .unwrap()
+    }
+
+    fn bridge_keys() -> (ViewKeys, EdwardsPoint) {
+        let view_secret = scalar(3);
+        let spend_public = &scalar(5) * ED25519_BASEPOINT_TABLE;
+        let view_public = &view_secret * ED25519_BASEPOINT_TABLE;
+        (ViewKeys::new(view_secret, spend_public), view_public)
+    }
+
+    #[test]
+    fn test_addresses_match_a_real_wallet() {
+        // The Monero General Fund's published view key, primary address
+        // and donation subaddress (0/70)
+        let primary = "44AFFq5kSiGBoZ4NMDwYtN18obc8AemS33DBLWs3H7otXft3XjrpDtQGv7SqSsaBYBb98uNbr2VBBEt7f2wfn3RVGQBEP3A";
+        let subaddress = "888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H";
+        let parsed = crate::subaddress::MoneroAddress::parse(primary).unwrap();
+        let keys = ViewKeys::from_hex(
+            "f359631075708155cc3d92a32b75a7d02a5dcf27756707b47a2b31b21c389501",
+            &hex::encode(parsed.spend_public),
+        )
+        .unwrap();
+
+        assert_eq!(
+            keys.address(SubaddressIndex::default(), Network::Mainnet),
+            primary
+        );
+        assert_eq!(
+            keys.address(SubaddressIndex::new(0, 70), Network::Mainnet),
+            subaddress
+        );
+        // The primary address's view key is a·G
+        assert_eq!(
+            parsed.view_public,
+            (&keys.view_secret * ED25519_BASEPOINT_TABLE)
+                .compress()
+                .to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_detects_deposit_and_decrypts_amount() {
+        let (keys, view_public) = bridge_keys();
+        let tx = send_to(
+            &view_public,
+            &keys.spend_public,
+            1_500_000_000_000,
+            1_500_000_000_000,
+            false,
+        );
+
+        let deposits = scan_transaction(&keys, 42, &"ab".repeat(32), &tx).unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].amount, 1_500_000_000_000);
+        assert_eq!(deposits[0].block_height, 42);
+        assert_eq!(format_xmr(deposits[0].amount), "1.500000000000");
+        assert!(deposits[0].subaddress.is_primary());
+    }
+
+    #[test]
+    fn test_attributes_deposit_to_subaddress() {
+        let (keys, _) = bridge_keys();
+        let keys = keys.with_subaddresses(0, 10);
+        let user = SubaddressIndex::new(0, 7);
+
+        let spend = subaddress_spend_public(&keys.view_secret, &keys.spend_public, user);
+        let view = keys.view_secret * spend;
+        let tx = send_to(&view, &spend, 5_000, 5_000, true);
+
+        let deposits = scan_transaction(&keys, 1, &"ab".repeat(32), &tx).unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].subaddress, user);
+        assert!(keys.tracks(user));
+        assert!(!keys.tracks(SubaddressIndex::new(0, 10)));
+    }
+
+    #[test]
+    fn test_holds_outputs_reusing_a_credited_key() {
+        let (keys, view_public) = bridge_keys();
+        // Same tx secret key, so both outputs get the same one-time key
+        let first = send_to(&view_public, &keys.spend_public, 5_000, 5_000, false);
+        let second = send_to(&view_public, &keys.spend_public, 5_000, 5_000, false);
+        let deposits = scan_block(
+            &keys,
+            1,
+            &[("aa".repeat(32), first), ("bb".repeat(32), second)],
+        )
+        .unwrap();
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].output_pub_key, deposits[1].output_pub_key);
+
+        let (fresh, reused) = split_reused_keys(deposits.clone(), |_| Ok(None)).unwrap();
+        assert_eq!(fresh, vec![deposits[0].clone()]);
+        assert_eq!(
+            reused,
+            vec![ReusedKey {
+                deposit: deposits[1].clone(),
+                credited: (deposits[0].tx_hash, 0),
+            }]
+        );
+
+        // Credited in an earlier block: both are held, but the output that
+        // was credited passes when seen again
+        let earlier = (B256::repeat_byte(0xcc), 3);
+        let (fresh, reused) = split_reused_keys(deposits.clone(), |_| Ok(Some(earlier))).unwrap();
+        assert!(fresh.is_empty());
+        assert_eq!(reused.len(), 2);
+        let credited = (deposits[0].tx_hash, 0);
+        let (fresh, reused) = split_reused_keys(deposits.clone(), |_| Ok(Some(credited))).unwrap();
+        assert_eq!(fresh, vec![deposits[0].clone()]);
+        assert_eq!(reused[0].deposit, deposits[1]);
+    }
+
+    #[test]
+    fn test_ignores_outputs_for_other_wallets() {
+        let (keys, _) = bridge_keys();
+        let other_view = &scalar(9) * ED25519_BASEPOINT_TABLE;
+        let tx = send_to(&other_view, &keys.spend_public, 1_000, 1_000, false);
+
+        assert!(scan_transaction(&keys, 1, &"ab".repeat(32), &tx)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rejects_amount_not_matching_commitment() {
+        let (keys, view_public) = bridge_keys();
+        let tx = send_to(&view_public, &keys.spend_public, 1_000, 1_000_000, false);
+
+        assert!(scan_transaction(&keys, 1, &"ab".repeat(32), &tx)
+            .unwrap()
+            .is_empty());
+
+        let rejected = metrics::get().deposits_rejected.get();
+        assert!(scan_block(&keys, 1, &[("ab".repeat(32), tx)])
+            .unwrap()
+            .is_empty());
+        assert!(metrics::get().deposits_rejected.get() > rejected);
+    }
+
+    #[test]
+    fn test_verify_commitment() {
+        let mask = scalar(4);
+        let commitment = commit(&mask, 1_000).compress().to_bytes();
+        assert!(verify_commitment(&mask, 1_000, &commitment));
+        assert!(!verify_commitment(&mask, 1_001, &commitment));
+        assert!(!verify_commitment(&scalar(5), 1_000, &commitment));
+        assert!(verify_commitment(&Scalar::ONE, 7, &zero_commit(7)));
+    }
+
+    /// The shared secret `send_to` derives for output 0
+    fn sender_shared(view_public: &EdwardsPoint) -> Scalar {
+        let derivation = (scalar(7) * view_public)
+            .mul_by_cofactor()
+            .compress()
+            .to_bytes();
+        derivation_to_scalar(&derivation, 0)
+    }
+
+    #[test]
+    fn test_decrypts_legacy_ecdh_amount() {
+        let (keys, view_public) = bridge_keys();
+        let mut tx = send_to(&view_public, &keys.spend_public, 0, 0, false);
+        let shared = sender_shared(&view_public);
+
+        // Pre-Bulletproofs 2: a random mask and both scalars offset by hashes
+        // of the shared secret
+        let amount = 42_000_000_000u64;
+        let mask = scalar(11);
+        let mask_offset = hash_to_scalar(shared.as_bytes());
+        let amount_offset = hash_to_scalar(mask_offset.as_bytes());
+        let rct = tx.rct_signatures.as_mut().unwrap();
+        rct.ecdh_info = Some(vec![EcdhInfo {
+            amount: hex::encode((Scalar::from(amount) + amount_offset).as_bytes()),
+            mask: Some(hex::encode((mask + mask_offset).as_bytes())),
+        }]);
+        rct.out_pk = Some(vec![hex::encode(
+            commit(&mask, amount).compress().as_bytes(),
+        )]);
+
+        let deposits = scan_transaction(&keys, 1, &"ab".repeat(32), &tx).unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].amount, amount);
+
+        // A wrong mask no longer opens the commitment
+        let rct = tx.rct_signatures.as_mut().unwrap();
+        rct.ecdh_info.as_mut().unwrap()[0].mask = Some(hex::encode(mask.as_bytes()));
+        assert!(scan_transaction(&keys, 1, &"ab".repeat(32), &tx)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_reads_cleartext_amount_without_ringct() {
+        let (keys, view_public) = bridge_keys();
+        let mut tx = send_to(&view_public, &keys.spend_public, 0, 0, false);
+        tx.rct_signatures = None;
+        tx.vout.as_mut().unwrap()[0].amount = 600_000_000_000;
+
+        let deposits = scan_transaction(&keys, 1, &"ab".repeat(32), &tx).unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].amount, 600_000_000_000);
+    }
+
+    #[test]
+    fn test_decrypts_payment_id() {
+        let (keys, view_public) = bridge_keys();
+        let derivation = (scalar(7) * view_public)
+            .mul_by_cofactor()
+            .compress()
+            .to_bytes();
+        let with_payment_id = |payment_id: [u8; 8]| {
+            let mut tx = send_to(&view_public, &keys.spend_public, 1_000, 1_000, false);
+            let encrypted = decrypt_payment_id(&derivation, payment_id);
+            let mut extra = vec![TX_EXTRA_NONCE, 9, NONCE_ENCRYPTED_PAYMENT_ID];
+            extra.extend_from_slice(encrypted.as_slice());
+            extra.extend_from_slice(&tx.extra);
+            tx.extra = extra;
+            tx
+        };
+
+        let tx = with_payment_id([0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0]);
+        assert_eq!(
+            TxExtra::parse(&tx.extra)
+                .encrypted_payment_id()
+                .map(B64::from),
+            Some(decrypt_payment_id(
+                &derivation,
+                [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0]
+            ))
+        );
+        let deposits = scan_transaction(&keys, 1, &"ab".repeat(32), &tx).unwrap();
+        assert_eq!(
+            deposits[0].payment_id,
+            Some("0x123456789abcdef0".parse().unwrap())
+        );
+
+        // Wallets add an all-zero payment ID to hide whether one was used
+        let tx = with_payment_id([0; 8]);
+        let deposits = scan_transaction(&keys, 1, &"ab".repeat(32), &tx).unwrap();
+        assert_eq!(deposits[0].payment_id, None);
+
+        let address = keys.integrated_address(B64::repeat_byte(0x12), Network::Mainnet);
+        assert_eq!(address.len(), 106);
+    }
+
+    #[test]
+    fn test_zero_commit() {
+        // zeroCommit(0) is the basepoint G
+        assert_eq!(
+            hex::encode(zero_commit(0)),
+            "5866666666666666666666666666666666666666666666666666666666666666"
+        );
+        assert_ne!(zero_commit(1), zero_commit(0));
+    }
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            assert_eq!(read_varint(&buf), Some((value, buf.len())));
+        }
+    }
+
+    #[test]
+    fn test_parse_xmr() {
+        assert_eq!(parse_xmr("1.5").unwrap(), 1_500_000_000_000);
+        assert_eq!(parse_xmr("0.000000000001").unwrap(), 1);
+        assert_eq!(parse_xmr(".01").unwrap(), 10_000_000_000);
+        assert_eq!(parse_xmr("250").unwrap(), 250_000_000_000_000);
+        assert_eq!(parse_xmr(&format_xmr(123_456_789)).unwrap(), 123_456_789);
+        for invalid in ["", ".", "1.0000000000001", "-1", "1e3", "20000000"] {
+            assert!(parse_xmr(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
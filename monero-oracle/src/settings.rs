@@ -0,0 +1,561 @@
+//! Layered settings: environment variables over an optional TOML file
+//!
+//! Every setting has one name, the environment variable documented in the
+//! README (`MONERO_RPC_URL`). In the config file the same setting is written
+//! in lowercase, either flat or split at a table, so these are equivalent:
+//!
+//! ```toml
+//! monero_rpc_url = "http://127.0.0.1:18081"
+//!
+//! [monero]
+//! rpc_url = "http://127.0.0.1:18081"
+//! ```
+//!
+//! Arrays become the comma-separated lists the variables take. A variable set
+//! in the environment (or `.env`) always wins over the file, so one file can
+//! be shared between instances while keys and per-host values stay in the
+//! environment. An empty variable counts as unset. A key the file sets that
+//! is no setting, like a misspelled one, is an error rather than ignored.
+//!
+//! The running oracle re-reads the file when it changes; see [`crate::reload`].
+
+use crate::error::OracleError;
+use anyhow::{Context, Result};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::RwLock,
+};
+
+/// Config file read when neither `--config` nor `CONFIG_FILE` names one
+pub const DEFAULT_FILE: &str = "config.toml";
+
+/// Every setting, by variable name; [`FileSettings::parse`] rejects others
+const KNOWN: &[&str] = &[
+    "ADMIN_API_KEY",
+    "ALERT_COOLDOWN_SECS",
+    "ALERT_POLL_FAILURES",
+    "ALERT_TELEGRAM_CHAT_ID",
+    "ALERT_WEBHOOK_FORMAT",
+    "ALERT_WEBHOOK_URL",
+    "ATTESTATION_PEERS",
+    "AUTO_MINT",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_KMS_KEY_ID",
+    "AWS_KMS_PROFILE",
+    "AWS_KMS_REGION",
+    "AWS_SECRET_ACCESS_KEY",
+    "BALANCE_PAUSE_ETH",
+    "BALANCE_WARN_ETH",
+    "BLOCK_EXPORT_RETAIN_BLOCKS",
+    "BLOCK_EXPORT_TARGET",
+    "BRIDGE_ADDRESS",
+    "BRIDGE_FEE_BPS",
+    "BRIDGE_FEE_USD",
+    "BRIDGE_SPEND_PUBLIC_KEY",
+    "BRIDGE_VIEW_KEY",
+    "CHECKPOINT_INTERVAL_BLOCKS",
+    "DATABASE_PATH",
+    "DB_AUTO_MIGRATE",
+    "DB_BACKUP_INTERVAL_SECS",
+    "DB_BACKUP_KEEP",
+    "DB_BACKUP_TARGET",
+    "EXTRA_BRIDGE_ADDRESSES",
+    "FEE_BUMP_PERCENT",
+    "FETCH_CONCURRENCY",
+    "FROST_API_KEY",
+    "FROST_KEY_SHARE",
+    "FROST_PEERS",
+    "GAS_BUDGET_DAILY_ETH",
+    "GAS_BUDGET_WEEKLY_ETH",
+    "GAS_STRATEGY",
+    "GCP_KMS_KEY",
+    "GCP_KMS_KEY_RING",
+    "GCP_KMS_KEY_VERSION",
+    "GCP_KMS_LOCATION",
+    "GCP_KMS_PROJECT",
+    "GCS_HMAC_ACCESS_ID",
+    "GCS_HMAC_SECRET",
+    "GOSSIP_KEY",
+    "GOSSIP_LISTEN",
+    "GOSSIP_PEERS",
+    "GRPC_PORT",
+    "HEALTH_MAX_LAG_BLOCKS",
+    "HEALTH_MAX_POLL_AGE_SECS",
+    "HEARTBEAT_INTERVAL_SECS",
+    "IDLE_POST_INTERVAL_BLOCKS",
+    "INDEX_OUTPUTS",
+    "KEYSTORE_PASSWORD_FILE",
+    "KEYSTORE_PATH",
+    "LEASE_DURATION_SECS",
+    "LEASE_INSTANCE_ID",
+    "LOG_LEVEL",
+    "MAX_CATCH_UP_BLOCKS",
+    "MAX_DEPOSIT_PER_USER_XMR",
+    "MAX_DEPOSIT_XMR",
+    "MAX_FEE_BUMPS",
+    "MAX_FEE_PER_GAS_GWEI",
+    "METRICS_PORT",
+    "MIN_CONFIRMATIONS",
+    "MIN_DEPOSIT_XMR",
+    "MONERO_NETWORK",
+    "MONERO_QUORUM",
+    "MONERO_RPC_CA_CERT",
+    "MONERO_RPC_CONNECT_TIMEOUT_SECS",
+    "MONERO_RPC_DELAY_MS",
+    "MONERO_RPC_FALLBACK_URLS",
+    "MONERO_RPC_KEEPALIVE_SECS",
+    "MONERO_RPC_LOGIN",
+    "MONERO_RPC_MAX_CONCURRENT",
+    "MONERO_RPC_MAX_RPS",
+    "MONERO_RPC_POOL_IDLE_SECS",
+    "MONERO_RPC_POOL_MAX_IDLE",
+    "MONERO_RPC_PROXY",
+    "MONERO_RPC_READ_TIMEOUT_SECS",
+    "MONERO_RPC_RECORD_DIR",
+    "MONERO_RPC_REPLAY_DIR",
+    "MONERO_RPC_URL",
+    "MONERO_WALLET_RPC_URL",
+    "MONERO_ZMQ_URL",
+    "MULTISIG_API_KEY",
+    "MULTISIG_PEERS",
+    "ORACLE_HTTP_PORT",
+    "ORACLE_MODE",
+    "ORACLE_SIGNER",
+    "OUTPUT_MERKLE_HASHER",
+    "POLL_INTERVAL_SECS",
+    "POLL_TIMEOUT_SECS",
+    "PRICE_FEED_ADDRESS",
+    "PRICE_FEED_JSON_POINTER",
+    "PRICE_FEED_URL",
+    "PRICE_MAX_AGE_SECS",
+    "PRIORITY_FEE_GWEI",
+    "PRIVATE_KEY",
+    "REORG_CONFIRMATION_DEPTH",
+    "REORG_TRACKED_BLOCKS",
+    "RPC_MAX_RETRIES",
+    "RPC_RETRY_BASE_MS",
+    "S3_ENDPOINT",
+    "S3_REGION",
+    "SAFE_ADDRESS",
+    "SAFE_TX_SERVICE_URL",
+    "STANDBY_KEYSTORE_PASSWORD_FILE",
+    "STANDBY_KEYSTORE_PATH",
+    "STANDBY_PRIVATE_KEY",
+    "STUCK_TX_TIMEOUT_SECS",
+    "SUBADDRESS_ACCOUNT",
+    "SUBADDRESS_LOOKAHEAD",
+    "TREASURY_XMR_ADDRESS",
+    "TX_MERKLE_HASHER",
+    "UNICHAIN_CONFIRMATION_DEPTH",
+    "UNICHAIN_RPC_CONNECT_TIMEOUT_SECS",
+    "UNICHAIN_RPC_KEEPALIVE_SECS",
+    "UNICHAIN_RPC_POOL_IDLE_SECS",
+    "UNICHAIN_RPC_POOL_MAX_IDLE",
+    "UNICHAIN_RPC_READ_TIMEOUT_SECS",
+    "UNICHAIN_RPC_URL",
+    "UNICHAIN_WS_URL",
+    "VERIFY_POW",
+    "WATCH_MEMPOOL",
+    "WATCH_WITHDRAWALS",
+    "WITHDRAWAL_BATCH_INTERVAL_SECS",
+    "WITHDRAWAL_BATCH_SIZE",
+    "WITHDRAWAL_CONFIRMATIONS",
+    "WITHDRAWAL_DEDUCT_FEE",
+    "WITHDRAWAL_FEE_PRIORITY",
+    "WITHDRAWAL_MAX_ATTEMPTS",
+    "WITHDRAWAL_START_BLOCK",
+];
+
+static FILE: RwLock<Option<FileSettings>> = RwLock::new(None);
+static PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Settings from a config file, keyed by environment variable name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSettings {
+    values: BTreeMap<String, String>,
+}
+
+impl FileSettings {
+    pub fn parse(toml: &str) -> Result<Self> {
+        let table: toml::Table = toml.parse()?;
+        let mut values = BTreeMap::new();
+        flatten(None, "", &table, &mut values)?;
+        Ok(Self { values })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let toml = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&toml).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Settings added, removed or changed in `other`
+    pub fn changed(&self, other: &Self) -> Vec<String> {
+        self.values
+            .keys()
+            .chain(other.values.keys())
+            .filter(|key| self.get(key) != other.get(key))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Collect the settings in `table`, at `path` in the file (`monero.rpc`)
+/// and named `prefix` (`MONERO_RPC`) in the environment
+fn flatten(
+    prefix: Option<&str>,
+    path: &str,
+    table: &toml::Table,
+    values: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    for (key, value) in table {
+        let name = match prefix {
+            Some(prefix) => format!("{}_{}", prefix, key.to_ascii_uppercase()),
+            None => key.to_ascii_uppercase(),
+        };
+        let path = match path {
+            "" => key.clone(),
+            path => format!("{}.{}", path, key),
+        };
+        let value = match value {
+            toml::Value::Table(table) => {
+                flatten(Some(&name), &path, table, values)?;
+                continue;
+            }
+            toml::Value::Array(items) => items
+                .iter()
+                .map(scalar)
+                .collect::<Option<Vec<_>>>()
+                .map(|items| items.join(",")),
+            value => scalar(value),
+        }
+        .with_context(|| format!("{} can't hold nested arrays or tables", name))?;
+        anyhow::ensure!(
+            KNOWN.binary_search(&name.as_str()).is_ok(),
+            "Unknown setting {} ({})",
+            path,
+            name
+        );
+        anyhow::ensure!(
+            values.insert(name.clone(), value).is_none(),
+            "{} is set twice",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn scalar(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Datetime(dt) => Some(dt.to_string()),
+        toml::Value::Array(_) | toml::Value::Table(_) => None,
+    }
+}
+
+/// Load the config file: `path`, else `CONFIG_FILE`, else `config.toml` if
+/// it exists. Returns the file read, if any.
+pub fn load(path: Option<&Path>) -> Result<Option<PathBuf>> {
+    let path = match path
+        .map(Path::to_path_buf)
+        .or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from))
+    {
+        Some(path) => path,
+        None if Path::new(DEFAULT_FILE).exists() => PathBuf::from(DEFAULT_FILE),
+        None => return Ok(None),
+    };
+    let settings = FileSettings::read(&path)?;
+    replace(settings);
+    *PATH.write().expect("settings lock poisoned") = Some(path.clone());
+    Ok(Some(path))
+}
+
+/// The config file loaded, if any
+pub fn path() -> Option<PathBuf> {
+    PATH.read().expect("settings lock poisoned").clone()
+}
+
+/// Swap in re-read file settings, returning the ones they replace
+pub fn replace(settings: FileSettings) -> Option<FileSettings> {
+    FILE.write()
+        .expect("settings lock poisoned")
+        .replace(settings)
+}
+
+/// A setting from the environment, else the config file
+pub fn var(key: &str) -> Option<String> {
+    #[cfg(test)]
+    if let Some(value) = TEST_SETTINGS.with_borrow(|settings| {
+        settings.as_ref().map(|settings| {
+            settings
+                .get(key)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        })
+    }) {
+        return value;
+    }
+    lookup(FILE.read().expect("settings lock poisoned").as_ref(), key)
+}
+
+#[cfg(test)]
+thread_local! {
+    static TEST_SETTINGS: std::cell::RefCell<Option<FileSettings>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Read only `toml` on this thread, ignoring the environment and the
+/// config file, so tests can load a [`Config`](crate::config::Config) side
+/// by side
+#[cfg(test)]
+pub fn set_for_test(toml: &str) {
+    let settings = FileSettings::parse(toml).expect("invalid test settings");
+    TEST_SETTINGS.set(Some(settings));
+}
+
+fn lookup(file: Option<&FileSettings>, key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .or_else(|| file?.get(key).map(str::to_string))
+        .filter(|value| !value.is_empty())
+}
+
+/// A required setting
+pub fn require(key: &str) -> Result<String> {
+    var(key).ok_or_else(|| OracleError::Config(format!("{} not set", key)).into())
+}
+
+/// A setting parsed as `T`, `None` if unset
+pub fn parse<T>(key: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    var(key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| OracleError::Config(format!("Invalid {}: {}", key, e)).into())
+        })
+        .transpose()
+}
+
+/// A setting parsed as `T`, `default` if unset
+pub fn parse_or<T>(key: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    Ok(parse(key)?.unwrap_or(default))
+}
+
+/// A boolean setting: `true` or `1`, `false` or `0`; false if unset
+pub fn flag(key: &str) -> Result<bool> {
+    match var(key).as_deref() {
+        None | Some("false" | "0") => Ok(false),
+        Some("true" | "1") => Ok(true),
+        Some(value) => Err(OracleError::Config(format!(
+            "Invalid {}: {:?} isn't true, false, 1 or 0",
+            key, value
+        ))
+        .into()),
+    }
+}
+
+/// A comma-separated list setting
+pub fn list(key: &str) -> Vec<String> {
+    var(key)
+        .map(|value| {
+            value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_tables_and_arrays() {
+        let settings = FileSettings::parse(
+            r#"
+            poll_interval_secs = 60
+            verify_pow = true
+
+            [monero]
+            rpc_url = "http://127.0.0.1:18081"
+            rpc_fallback_urls = ["https://node1:443", "http://node2:18081"]
+
+            [balance]
+            pause_eth = 0.005
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.get("POLL_INTERVAL_SECS"), Some("60"));
+        assert_eq!(settings.get("VERIFY_POW"), Some("true"));
+        assert_eq!(
+            settings.get("MONERO_RPC_URL"),
+            Some("http://127.0.0.1:18081")
+        );
+        assert_eq!(
+            settings.get("MONERO_RPC_FALLBACK_URLS"),
+            Some("https://node1:443,http://node2:18081")
+        );
+        assert_eq!(settings.get("BALANCE_PAUSE_ETH"), Some("0.005"));
+        assert_eq!(settings.get("RPC_URL"), None);
+    }
+
+    #[test]
+    fn test_rejects_ambiguous_files() {
+        // The same setting written flat and in a table
+        assert!(
+            FileSettings::parse("monero_rpc_url = \"a\"\n[monero]\nrpc_url = \"b\"\n").is_err()
+        );
+        assert!(FileSettings::parse("attestation_peers = [[\"a\"]]\n").is_err());
+        assert!(FileSettings::parse("poll_interval_secs = \n").is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings() {
+        let error =
+            FileSettings::parse("[monero]\nrpc_ulr = \"http://127.0.0.1:18081\"\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown setting monero.rpc_ulr (MONERO_RPC_ULR)"
+        );
+        let error = FileSettings::parse("poll_interval = 60\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown setting poll_interval (POLL_INTERVAL)"
+        );
+        // A table that names no settings
+        assert!(FileSettings::parse("[moneroo]\nrpc_url = \"a\"\n").is_err());
+    }
+
+    #[test]
+    fn test_known_settings() {
+        assert!(KNOWN.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Every setting the config reads
+        let config = include_str!("config.rs");
+        let config = &config[..config.find("#[cfg(test)]").unwrap()];
+        let pieces: Vec<&str> = config.split('"').collect();
+        for pair in pieces.windows(2).step_by(2) {
+            let (before, literal) = (pair[0], pair[1]);
+            let Some(call) = before.strip_suffix('(') else {
+                continue;
+            };
+            // `parse::<u64>(`
+            let call = call
+                .rfind("::<")
+                .map_or(call, |turbofish| &call[..turbofish]);
+            let function = call
+                .rsplit(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap();
+            if ["var", "require", "parse", "parse_or", "flag", "list", "xmr"].contains(&function) {
+                assert!(KNOWN.contains(&literal), "{} isn't in KNOWN", literal);
+            }
+        }
+
+        // Every setting the example config shows, set or commented out
+        let mut table = String::new();
+        for line in include_str!("../config.example.toml").lines() {
+            let line = line.trim_start_matches(['#', ' ']);
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                table = format!("{}_", name.to_ascii_uppercase());
+            } else if let Some((key, _)) = line.split_once(" = ") {
+                if key
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                {
+                    let name = format!("{}{}", table, key.to_ascii_uppercase());
+                    assert!(KNOWN.contains(&name.as_str()), "{} isn't in KNOWN", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_flags() {
+        set_for_test("verify_pow = true\nauto_mint = 0\nwatch_mempool = \"1\"\n");
+        assert!(flag("VERIFY_POW").unwrap());
+        assert!(!flag("AUTO_MINT").unwrap());
+        assert!(flag("WATCH_MEMPOOL").unwrap());
+        assert!(!flag("WATCH_WITHDRAWALS").unwrap());
+
+        set_for_test("verify_pow = \"yes\"\n");
+        let error = flag("VERIFY_POW").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid VERIFY_POW: \"yes\" isn't true, false, 1 or 0"
+        );
+    }
+
+    #[test]
+    fn test_environment_overrides_file() {
+        // Names no setting has, so the environment is this test's alone
+        let file = FileSettings {
+            values: [
+                ("SETTINGS_TEST_OVERRIDDEN", "file"),
+                ("SETTINGS_TEST_FILE_ONLY", "file"),
+                ("SETTINGS_TEST_EMPTY", "file"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        };
+        env::set_var("SETTINGS_TEST_OVERRIDDEN", "env");
+        env::set_var("SETTINGS_TEST_EMPTY", "");
+
+        assert_eq!(
+            lookup(Some(&file), "SETTINGS_TEST_OVERRIDDEN").as_deref(),
+            Some("env")
+        );
+        assert_eq!(
+            lookup(Some(&file), "SETTINGS_TEST_FILE_ONLY").as_deref(),
+            Some("file")
+        );
+        assert_eq!(
+            lookup(Some(&file), "SETTINGS_TEST_EMPTY").as_deref(),
+            Some("file")
+        );
+        assert_eq!(lookup(None, "SETTINGS_TEST_FILE_ONLY"), None);
+    }
+
+    #[test]
+    fn test_changed_settings() {
+        let before = FileSettings::parse(
+            "poll_interval_secs = 60\nlog_level = \"info\"\n[gas]\nstrategy = \"normal\"\n",
+        )
+        .unwrap();
+        let after = FileSettings::parse(
+            "poll_interval_secs = 30\nlog_level = \"info\"\nmonero_rpc_url = \"http://node:18081\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            before.changed(&after),
+            ["GAS_STRATEGY", "MONERO_RPC_URL", "POLL_INTERVAL_SECS"]
+        );
+        assert!(before.changed(&before).is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+//! The oracle's Ethereum key: a local private key, an encrypted keystore
+//! file, or a key held in AWS KMS or Google Cloud KMS
+//!
+//! A keystore is the JSON file geth, `cast wallet` and most wallets export
+//! (Web3 Secret Storage v3). It is decrypted once at startup with the password
+//! from `KEYSTORE_PASSWORD_FILE`, or one typed at a prompt, so the key is never
+//! in the environment or on disk in plaintext.
+//!
+//! With a KMS backend the private key never leaves the KMS; transactions and
+//! attestations are signed remotely, one API call per signature. KMS support
+//! is compiled in with the `aws-kms` and `gcp-kms` features. Credentials come
+//! from each SDK's default chain (environment, profile or shared config file,
+//! instance/workload metadata).
+
+use alloy::{
+    network::EthereumWallet,
+    primitives::{Address, PrimitiveSignature, B256},
+    signers::{local::PrivateKeySigner, Signer},
+};
+use anyhow::{Context, Result};
+use std::{fmt, fs, path::PathBuf, str::FromStr};
+
+/// Where the oracle key lives, from `ORACLE_SIGNER`
+#[derive(Clone)]
+pub enum SignerConfig {
+    /// Hex private key from `PRIVATE_KEY`
+    Local(String),
+    /// Encrypted JSON keystore from `KEYSTORE_PATH`
+    Keystore {
+        path: PathBuf,
+        /// File holding the password; prompted for on the terminal if unset
+        password_file: Option<PathBuf>,
+    },
+    AwsKms {
+        key_id: String,
+        /// Overrides the region from the credential chain
+        region: Option<String>,
+        /// Named profile from the shared AWS config
+        profile: Option<String>,
+    },
+    GcpKms {
+        project_id: String,
+        location: String,
+        key_ring: String,
+        key: String,
+        version: u64,
+    },
+}
+
+/// Keeps `PRIVATE_KEY` out of `Config`'s debug output
+impl fmt::Debug for SignerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(_) => f.write_str("Local(<redacted>)"),
+            Self::Keystore {
+                path,
+                password_file,
+            } => f
+                .debug_struct("Keystore")
+                .field("path", path)
+                .field("password_file", password_file)
+                .finish(),
+            Self::AwsKms {
+                key_id,
+                region,
+                profile,
+            } => f
+                .debug_struct("AwsKms")
+                .field("key_id", key_id)
+                .field("region", region)
+                .field("profile", profile)
+                .finish(),
+            Self::GcpKms {
+                project_id,
+                location,
+                key_ring,
+                key,
+                version,
+            } => f
+                .debug_struct("GcpKms")
+                .field("project_id", project_id)
+                .field("location", location)
+                .field("key_ring", key_ring)
+                .field("key", key)
+                .field("version", version)
+                .finish(),
+        }
+    }
+}
+
+/// Backend named by `ORACLE_SIGNER`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerKind {
+    Local,
+    Keystore,
+    AwsKms,
+    GcpKms,
+}
+
+impl FromStr for SignerKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "keystore" => Ok(Self::Keystore),
+            "aws-kms" => Ok(Self::AwsKms),
+            "gcp-kms" => Ok(Self::GcpKms),
+            other => anyhow::bail!(
+                "Unknown signer '{}' (expected local, keystore, aws-kms or gcp-kms)",
+                other
+            ),
+        }
+    }
+}
+
+/// A connected signing key
+#[derive(Clone)]
+pub enum OracleSigner {
+    Local(PrivateKeySigner),
+    #[cfg(feature = "aws-kms")]
+    Aws(alloy::signers::aws::AwsSigner),
+    #[cfg(feature = "gcp-kms")]
+    Gcp(alloy::signers::gcp::GcpSigner),
+}
+
+impl OracleSigner {
+    /// Load or decrypt the key, or connect to the KMS and fetch its public key
+    pub async fn connect(config: &SignerConfig) -> Result<Self> {
+        match config {
+            SignerConfig::Local(private_key) => Ok(Self::Local(
+                private_key.parse().context("Invalid PRIVATE_KEY")?,
+            )),
+            SignerConfig::Keystore {
+                path,
+                password_file,
+            } => {
+                let path = path.clone();
+                let password_file = password_file.clone();
+                // scrypt is deliberately slow, and the prompt blocks
+                tokio::task::spawn_blocking(move || {
+                    let password = keystore_password(password_file.as_ref())?;
+                    let signer =
+                        PrivateKeySigner::decrypt_keystore(&path, password).with_context(|| {
+                            format!("Failed to decrypt keystore {}", path.display())
+                        })?;
+                    Ok(Self::Local(signer))
+                })
+                .await?
+            }
+            #[cfg(feature = "aws-kms")]
+            SignerConfig::AwsKms {
+                key_id,
+                region,
+                profile,
+            } => {
+                let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+                if let Some(region) = region {
+                    loader = loader.region(aws_config::Region::new(region.clone()));
+                }
+                if let Some(profile) = profile {
+                    loader = loader.profile_name(profile);
+                }
+                let client = aws_sdk_kms::Client::new(&loader.load().await);
+                let signer = alloy::signers::aws::AwsSigner::new(client, key_id.clone(), None)
+                    .await
+                    .with_context(|| format!("Failed to load AWS KMS key {}", key_id))?;
+                Ok(Self::Aws(signer))
+            }
+            #[cfg(feature = "gcp-kms")]
+            SignerConfig::GcpKms {
+                project_id,
+                location,
+                key_ring,
+                key,
+                version,
+            } => {
+                use alloy::signers::gcp::{GcpKeyRingRef, GcpSigner, KeySpecifier};
+                use gcloud_sdk::{
+                    google::cloud::kms::v1::key_management_service_client::KeyManagementServiceClient,
+                    GoogleApi,
+                };
+
+                let client = GoogleApi::from_function(
+                    KeyManagementServiceClient::new,
+                    "https://cloudkms.googleapis.com",
+                    None,
+                )
+                .await
+                .context("Failed to connect to Google Cloud KMS")?;
+                let key_ring = GcpKeyRingRef::new(project_id, location, key_ring);
+                let specifier = KeySpecifier::new(key_ring, key, *version);
+                let signer = GcpSigner::new(client, specifier, None)
+                    .await
+                    .with_context(|| format!("Failed to load Google Cloud KMS key {}", key))?;
+                Ok(Self::Gcp(signer))
+            }
+            #[allow(unreachable_patterns)]
+            _ => anyhow::bail!(
+                "ORACLE_SIGNER needs a build with the {} feature",
+                match config {
+                    SignerConfig::GcpKms { .. } => "gcp-kms",
+                    _ => "aws-kms",
+                }
+            ),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            #[cfg(feature = "aws-kms")]
+            Self::Aws(signer) => signer.address(),
+            #[cfg(feature = "gcp-kms")]
+            Self::Gcp(signer) => signer.address(),
+        }
+    }
+
+    /// Sign a 32-byte digest, e.g. an EIP-712 signing hash
+    pub async fn sign_hash(&self, hash: &B256) -> Result<PrimitiveSignature> {
+        Ok(match self {
+            Self::Local(signer) => signer.sign_hash(hash).await?,
+            #[cfg(feature = "aws-kms")]
+            Self::Aws(signer) => signer.sign_hash(hash).await?,
+            #[cfg(feature = "gcp-kms")]
+            Self::Gcp(signer) => signer.sign_hash(hash).await?,
+        })
+    }
+
+    /// Add this key to `wallet`, which then signs transactions sent from it
+    pub fn register_with(&self, wallet: &mut EthereumWallet) {
+        match self {
+            Self::Local(signer) => wallet.register_signer(signer.clone()),
+            #[cfg(feature = "aws-kms")]
+            Self::Aws(signer) => wallet.register_signer(signer.clone()),
+            #[cfg(feature = "gcp-kms")]
+            Self::Gcp(signer) => wallet.register_signer(signer.clone()),
+        }
+    }
+
+    /// Wallet that signs the oracle's transactions with this key
+    pub fn wallet(&self) -> EthereumWallet {
+        match self {
+            Self::Local(signer) => EthereumWallet::from(signer.clone()),
+            #[cfg(feature = "aws-kms")]
+            Self::Aws(signer) => EthereumWallet::from(signer.clone()),
+            #[cfg(feature = "gcp-kms")]
+            Self::Gcp(signer) => EthereumWallet::from(signer.clone()),
+        }
+    }
+}
+
+/// Contents of `password_file` without the trailing newline, or a password
+/// read from the terminal
+fn keystore_password(password_file: Option<&PathBuf>) -> Result<String> {
+    match password_file {
+        Some(file) => {
+            let password = fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            Ok(password.trim_end_matches(['\r', '\n']).to_string())
+        }
+        None => rpassword::prompt_password("Keystore password: ")
+            .context("No terminal to prompt for the keystore password; set KEYSTORE_PASSWORD_FILE"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kind() {
+        assert_eq!("AWS-KMS".parse::<SignerKind>().unwrap(), SignerKind::AwsKms);
+        assert_eq!("gcp-kms".parse::<SignerKind>().unwrap(), SignerKind::GcpKms);
+        assert_eq!(
+            "keystore".parse::<SignerKind>().unwrap(),
+            SignerKind::Keystore
+        );
+        assert!("hsm".parse::<SignerKind>().is_err());
+    }
+
+    #[test]
+    fn test_keystore_password_file() {
+        let file = std::env::temp_dir().join(format!("keystore-password-{}", std::process::id()));
+        fs::write(&file, "correct horse battery staple\n").unwrap();
+        assert_eq!(
+            keystore_password(Some(&file)).unwrap(),
+            "correct horse battery staple"
+        );
+        fs::remove_file(&file).unwrap();
+        assert!(keystore_password(Some(&file)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_signer() {
+        let key = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        let config = SignerConfig::Local(key.to_string());
+        assert!(!format!("{:?}", config).contains("4c0883"));
+
+        let signer = OracleSigner::connect(&config).await.unwrap();
+        let hash = B256::repeat_byte(0x42);
+        let signature = signer.sign_hash(&hash).await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&hash).unwrap(),
+            signer.address()
+        );
+
+        assert!(
+            OracleSigner::connect(&SignerConfig::Local("0x1234".to_string()))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Signed snapshots of the posted chain, for bootstrapping a new instance
+//!
+//! `export-snapshot` writes every live posting in the local database - block
+//! hash, both Merkle roots, the cumulative difficulty monerod reported and
+//! the Unichain transaction that posted it - to a JSON file signed by the
+//! oracle key. `import-snapshot` checks the signature against an address the
+//! operator trusts and loads the blocks into the local database, so a new
+//! instance, or an auditor, starts from the posted chain instead of fetching
+//! every block from monerod again.
+//!
+//! The signature is EIP-712 over a [`SnapshotHeader`] committing to every
+//! block in order, so no block can be dropped, reordered or altered without
+//! invalidating it. A snapshot only says what its signer posted; `audit`
+//! still checks the blocks against monerod and the contract.
+
+use crate::{signer::OracleSigner, storage::PostedBlockRecord, subaddress::Network};
+use alloy::{
+    primitives::{keccak256, Address, Bytes, PrimitiveSignature, B256},
+    sol,
+    sol_types::{eip712_domain, Eip712Domain, SolStruct},
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Format version written by this build
+pub const VERSION: u32 = 1;
+
+sol! {
+    /// What the snapshot signature covers
+    struct SnapshotHeader {
+        uint32 version;
+        string network;
+        address bridge;
+        uint64 firstHeight;
+        uint64 lastHeight;
+        // keccak256 of every block's struct hash, in height order
+        bytes32 blocksHash;
+    }
+
+    struct SnapshotEntry {
+        uint64 height;
+        bytes32 blockHash;
+        bytes32 txMerkleRoot;
+        bytes32 outputMerkleRoot;
+        // 0 if unknown
+        uint128 cumulativeDifficulty;
+        bytes32 unichainTxHash;
+        // 0 if unknown
+        uint64 unichainBlock;
+        uint64 gasUsed;
+        int64 postedAt;
+    }
+}
+
+fn domain() -> Eip712Domain {
+    eip712_domain! {
+        name: "monero-oracle snapshot",
+        version: "1",
+    }
+}
+
+/// One posted block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotBlock {
+    pub height: u64,
+    pub block_hash: B256,
+    pub tx_merkle_root: B256,
+    pub output_merkle_root: B256,
+    /// As monerod reported it when the block was posted, if recorded
+    pub cumulative_difficulty: Option<u128>,
+    pub unichain_tx_hash: B256,
+    pub unichain_block: Option<u64>,
+    pub gas_used: u64,
+    /// Unix seconds
+    pub posted_at: i64,
+}
+
+impl SnapshotBlock {
+    pub fn new(record: &PostedBlockRecord, cumulative_difficulty: Option<u128>) -> Self {
+        Self {
+            height: record.height,
+            block_hash: record.block_hash,
+            tx_merkle_root: record.tx_merkle_root,
+            output_merkle_root: record.output_merkle_root,
+            cumulative_difficulty,
+            unichain_tx_hash: record.unichain_tx_hash,
+            unichain_block: record.unichain_block,
+            gas_used: record.gas_used,
+            posted_at: record.posted_at.timestamp(),
+        }
+    }
+
+    /// The posting as the database keeps it
+    pub fn record(&self) -> Result<PostedBlockRecord> {
+        Ok(PostedBlockRecord {
+            height: self.height,
+            block_hash: self.block_hash,
+            tx_merkle_root: self.tx_merkle_root,
+            output_merkle_root: self.output_merkle_root,
+            unichain_tx_hash: self.unichain_tx_hash,
+            unichain_block: self.unichain_block,
+            gas_used: self.gas_used,
+            posted_at: DateTime::<Utc>::from_timestamp(self.posted_at, 0)
+                .with_context(|| format!("Block {} has an invalid postedAt", self.height))?,
+        })
+    }
+
+    fn struct_hash(&self) -> B256 {
+        SnapshotEntry {
+            height: self.height,
+            blockHash: self.block_hash,
+            txMerkleRoot: self.tx_merkle_root,
+            outputMerkleRoot: self.output_merkle_root,
+            cumulativeDifficulty: self.cumulative_difficulty.unwrap_or(0),
+            unichainTxHash: self.unichain_tx_hash,
+            unichainBlock: self.unichain_block.unwrap_or(0),
+            gasUsed: self.gas_used,
+            postedAt: self.posted_at,
+        }
+        .eip712_hash_struct()
+    }
+}
+
+/// A signed export of the posted chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub version: u32,
+    pub network: String,
+    pub bridge: Address,
+    pub created_at: DateTime<Utc>,
+    /// Live postings, lowest height first
+    pub blocks: Vec<SnapshotBlock>,
+    pub signer: Address,
+    /// 65-byte `r || s || v` signature over the EIP-712 digest
+    pub signature: Bytes,
+}
+
+impl Snapshot {
+    /// Sign `blocks` with the oracle key
+    pub async fn sign(
+        signer: &OracleSigner,
+        network: Network,
+        bridge: Address,
+        blocks: Vec<SnapshotBlock>,
+    ) -> Result<Self> {
+        anyhow::ensure!(!blocks.is_empty(), "No posted blocks to export");
+        let mut snapshot = Self {
+            version: VERSION,
+            network: network.to_string(),
+            bridge,
+            created_at: Utc::now(),
+            blocks,
+            signer: signer.address(),
+            signature: Bytes::new(),
+        };
+        let signature = signer.sign_hash(&snapshot.signing_hash()?).await?;
+        snapshot.signature = Bytes::copy_from_slice(&signature.as_bytes());
+        Ok(snapshot)
+    }
+
+    pub fn header(&self) -> Result<SnapshotHeader> {
+        let (first, last) = match (self.blocks.first(), self.blocks.last()) {
+            (Some(first), Some(last)) => (first.height, last.height),
+            _ => anyhow::bail!("Snapshot has no blocks"),
+        };
+        let mut hashes = Vec::with_capacity(self.blocks.len() * 32);
+        for block in &self.blocks {
+            hashes.extend_from_slice(block.struct_hash().as_slice());
+        }
+        Ok(SnapshotHeader {
+            version: self.version,
+            network: self.network.clone(),
+            bridge: self.bridge,
+            firstHeight: first,
+            lastHeight: last,
+            blocksHash: keccak256(&hashes),
+        })
+    }
+
+    pub fn signing_hash(&self) -> Result<B256> {
+        Ok(self.header()?.eip712_signing_hash(&domain()))
+    }
+
+    /// Check that `trusted` signed this snapshot for `network` and `bridge`,
+    /// and that its blocks are in strictly increasing height order
+    pub fn verify(&self, trusted: Address, network: Network, bridge: Address) -> Result<()> {
+        anyhow::ensure!(
+            self.version == VERSION,
+            "Unsupported snapshot version {} (this build reads {})",
+            self.version,
+            VERSION
+        );
+        let signature = PrimitiveSignature::try_from(self.signature.as_ref())
+            .context("Malformed snapshot signature")?;
+        let recovered = signature.recover_address_from_prehash(&self.signing_hash()?)?;
+        anyhow::ensure!(
+            recovered == self.signer,
+            "Snapshot signed by {}, claims {}",
+            recovered,
+            self.signer
+        );
+        anyhow::ensure!(
+            recovered == trusted,
+            "Snapshot signed by {}, not the trusted {}",
+            recovered,
+            trusted
+        );
+        anyhow::ensure!(
+            self.network == network.to_string(),
+            "Snapshot is for {}, but MONERO_NETWORK is {}",
+            self.network,
+            network
+        );
+        anyhow::ensure!(
+            self.bridge == bridge,
+            "Snapshot is for contract {}, but BRIDGE_ADDRESS is {}",
+            self.bridge,
+            bridge
+        );
+        if let Some(pair) = self
+            .blocks
+            .windows(2)
+            .find(|pair| pair[1].height <= pair[0].height)
+        {
+            anyhow::bail!(
+                "Snapshot block {} follows block {}",
+                pair[1].height,
+                pair[0].height
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn block(height: u64) -> SnapshotBlock {
+        SnapshotBlock {
+            height,
+            block_hash: B256::repeat_byte(height as u8),
+            tx_merkle_root: B256::repeat_byte(0x11),
+            output_merkle_root: B256::repeat_byte(0x22),
+            cumulative_difficulty: Some(u128::from(height) << 70),
+            unichain_tx_hash: B256::repeat_byte(0x33),
+            unichain_block: Some(9_000_000 + height),
+            gas_used: 120_000,
+            posted_at: 1_760_000_000 + height as i64,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_verify() {
+        let key = PrivateKeySigner::random();
+        let signer = OracleSigner::Local(key.clone());
+        let bridge = Address::repeat_byte(0xb1);
+        let snapshot = Snapshot::sign(
+            &signer,
+            Network::Mainnet,
+            bridge,
+            vec![block(3_100_000), block(3_100_001)],
+        )
+        .await
+        .unwrap();
+        snapshot
+            .verify(key.address(), Network::Mainnet, bridge)
+            .unwrap();
+
+        // Survives a round trip through the file format
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let read: Snapshot = serde_json::from_str(&json).unwrap();
+        read.verify(key.address(), Network::Mainnet, bridge)
+            .unwrap();
+        assert_eq!(read.blocks[1].record().unwrap().height, 3_100_001);
+
+        let stranger = PrivateKeySigner::random().address();
+        assert!(snapshot.verify(stranger, Network::Mainnet, bridge).is_err());
+        assert!(snapshot
+            .verify(key.address(), Network::Stagenet, bridge)
+            .is_err());
+
+        let mut altered = snapshot.clone();
+        altered.blocks[0].tx_merkle_root = B256::repeat_byte(0x44);
+        assert!(altered
+            .verify(key.address(), Network::Mainnet, bridge)
+            .is_err());
+
+        let mut dropped = snapshot.clone();
+        dropped.blocks.pop();
+        assert!(dropped
+            .verify(key.address(), Network::Mainnet, bridge)
+            .is_err());
+    }
+}
@@ -0,0 +1,3187 @@
+//! Local persistence for posted blocks and detected deposits
+//!
+//! Every block the oracle posts is written to a SQLite database together with
+//! the Unichain transaction that carried it, along with any deposits to the
+//! bridge found in it and the mints paid out for them. Burns read from the
+//! contract are queued here as withdrawals, and the bridge wallet's own
+//! outputs are tracked by key image. The database gives operators an
+//! audit trail (`sqlite3 oracle.db 'SELECT * FROM posted_blocks'`) and lets a
+//! restarted oracle resume its reorg window without replaying the chain.
+//!
+//! With `INDEX_OUTPUTS` on, every output committed to by a block's output
+//! Merkle root is kept too, in leaf order, so proofs and lookups of old
+//! blocks don't have to fetch and parse them from monerod again. Both of
+//! the block's Merkle trees are kept whole alongside them, so a proof is a
+//! lookup rather than a rebuild.
+//!
+//! Deposits outside the deposit limits (see [`crate::deposit_policy`]) are
+//! held here until an operator approves or rejects them, and the bridge fee
+//! charged on each mint and payout is kept with the rate it was converted at,
+//! along with the sweeps of collected fees to the treasury (see
+//! [`crate::bridge_fee`]).
+//!
+//! With a multisig bridge wallet, each signing round of a payout is recorded
+//! with the co-signers that signed it (see [`crate::multisig`]).
+//!
+//! With `SAFE_ADDRESS` set, postings proposed to the Safe are kept until
+//! they execute, so a proposal awaiting confirmations isn't made twice.
+//!
+//! The schema is built and upgraded by the versioned migrations in
+//! [`crate::migrations`].
+//!
+//! The operation journal (see [`crate::journal`]) lives here as well, so an
+//! intent is written in the same database as the state it changes, and so
+//! does the cost of every transaction the oracle pays for (see
+//! [`crate::costs`]).
+
+use crate::{
+    attestation::Attestation,
+    bridge_fee::{FeeCharge, FeeKind, FeeSweep},
+    costs::TxCost,
+    deposit_policy::{HeldDeposit, ReviewStatus},
+    journal::{Intent, Operation, OperationStatus},
+    key_image::{BridgeOutput, KeyImageSpend},
+    merkle::{MerkleTree, TreeKind},
+    migrations::{self, SchemaStatus},
+    monero_rpc::MoneroOutput,
+    monero_wallet::SignedTransfer,
+    multisig::{RoundRole, RoundStatus, SigningRound},
+    scanner::Deposit,
+    subaddress::SubaddressIndex,
+    withdrawal::{retry_delay, Withdrawal, WithdrawalStatus},
+};
+use alloy::primitives::{Address, Bytes, B256, B64};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{
+    params, types::Type, Connection, Error::FromSqlConversionFailure, OptionalExtension, Row,
+};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+const OPERATION_COLUMNS: &str = "key, kind, status, tx_hash, error, created_at, updated_at";
+
+const OUTPUT_COLUMNS: &str =
+    "block_height, leaf_index, tx_hash, output_index, ecdh_amount, output_pub_key, commitment";
+/// [`OUTPUT_COLUMNS`] plus the decrypted amount, for outputs that are deposits
+const INDEXED_OUTPUT_COLUMNS: &str =
+    "block_height, leaf_index, tx_hash, output_index, ecdh_amount, output_pub_key, commitment,
+    (SELECT amount FROM deposits d WHERE d.tx_hash = outputs.tx_hash
+        AND d.output_index = outputs.output_index AND d.orphaned = 0)";
+
+const UNICHAIN_CURSOR: &str = "unichain_burn_events";
+const SAFE_NONCE: &str = "safe_next_nonce";
+const WITHDRAWAL_COLUMNS: &str = "burn_id, user, lp, amount, xmr_address, unichain_tx_hash,
+    unichain_block, status, error, attempts, xmr_tx_hash, xmr_tx_key, xmr_fee,
+    (SELECT amount FROM withdrawal_payouts p WHERE p.burn_id = withdrawals.burn_id)";
+
+/// A block posting as recorded in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostedBlockRecord {
+    pub height: u64,
+    pub block_hash: B256,
+    pub tx_merkle_root: B256,
+    pub output_merkle_root: B256,
+    pub unichain_tx_hash: B256,
+    pub unichain_block: Option<u64>,
+    pub gas_used: u64,
+    pub posted_at: DateTime<Utc>,
+}
+
+/// A deposit seen in the txpool that hasn't reached a posted block yet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingDeposit {
+    pub tx_hash: B256,
+    pub output_index: u64,
+    /// Amount in piconero
+    pub amount: u64,
+    pub subaddress: SubaddressIndex,
+    /// Block it was mined in, `None` while still in the txpool
+    pub block_height: Option<u64>,
+    pub first_seen_at: DateTime<Utc>,
+}
+
+/// A detected deposit whose subaddress has a registered recipient but which
+/// has not been minted yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMint {
+    pub deposit: Deposit,
+    pub recipient: Address,
+}
+
+/// An output as indexed, with its place in the block's output tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedOutput {
+    pub block_height: u64,
+    /// Position among all outputs in the block, the proof's `outputIndex`
+    pub leaf_index: u64,
+    #[serde(flatten)]
+    pub output: MoneroOutput,
+    /// Amount in piconero, decrypted with the bridge view key when the output
+    /// is a deposit to the bridge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+}
+
+/// Every output of a block, in leaf order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedBlock {
+    pub height: u64,
+    pub block_hash: B256,
+    pub outputs: Vec<MoneroOutput>,
+}
+
+/// A block posting proposed to the Safe and not yet executed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeProposal {
+    pub height: u64,
+    pub block_hash: B256,
+    pub safe_tx_hash: B256,
+    pub nonce: u64,
+}
+
+/// SQLite-backed store of posted blocks
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    /// Open (or create) the database at `path`, applying any pending
+    /// [migrations]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::init(Self::connect(path)?)
+    }
+
+    /// Open the database at `path` for the oracle to run on. Pending
+    /// migrations are applied with `auto_migrate`, after copying the
+    /// database to `{path}.v{version}.bak`, and refused without it.
+    pub fn open_checked(path: impl AsRef<Path>, auto_migrate: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Self::connect(path)?;
+        let version = migrations::version(&conn)?;
+        migrations::ensure_known(version)?;
+        if version < migrations::latest() && !migrations::is_empty(&conn)? {
+            anyhow::ensure!(
+                auto_migrate,
+                "Database schema is at version {}, this release needs {}: \
+                 run `monero-oracle db migrate` or set DB_AUTO_MIGRATE=true",
+                version,
+                migrations::latest()
+            );
+            let backup = PathBuf::from(format!("{}.v{}.bak", path.display(), version));
+            if !backup.exists() {
+                conn.execute("VACUUM INTO ?1", params![backup.to_string_lossy()])
+                    .with_context(|| {
+                        format!("Failed to copy the database to {}", backup.display())
+                    })?;
+            }
+        }
+        Self::init(conn)
+    }
+
+    /// Open a throwaway in-memory database
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn connect(path: &Path) -> Result<Connection> {
+        Connection::open(path)
+            .with_context(|| format!("Failed to open database at {}", path.display()))
+    }
+
+    fn init(mut conn: Connection) -> Result<Self> {
+        migrations::migrate(&mut conn).context("Failed to migrate the database schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Schema version and migration history
+    pub fn schema_status(&self) -> Result<SchemaStatus> {
+        migrations::status(&self.conn())
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().expect("database lock poisoned")
+    }
+
+    /// Record a confirmed block posting
+    pub fn record_posted_block(&self, record: &PostedBlockRecord) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO posted_blocks (
+                height, block_hash, tx_merkle_root, output_merkle_root,
+                unichain_tx_hash, unichain_block, gas_used, posted_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.height as i64,
+                record.block_hash.to_string(),
+                record.tx_merkle_root.to_string(),
+                record.output_merkle_root.to_string(),
+                record.unichain_tx_hash.to_string(),
+                record.unichain_block.map(|b| b as i64),
+                record.gas_used as i64,
+                record.posted_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Live postings not re-checked yet that were mined in `settled_block` or
+    /// earlier, or in an unrecorded block; lowest height first, at most
+    /// `limit`
+    pub fn unconfirmed_postings(
+        &self,
+        settled_block: u64,
+        limit: usize,
+    ) -> Result<Vec<PostedBlockRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT height, block_hash, tx_merkle_root, output_merkle_root,
+                    unichain_tx_hash, unichain_block, gas_used, posted_at
+             FROM posted_blocks
+             WHERE orphaned = 0 AND confirmed_at IS NULL
+               AND (unichain_block IS NULL OR unichain_block <= ?1)
+             ORDER BY height, id LIMIT ?2",
+        )?;
+        let records = stmt
+            .query_map(params![settled_block as i64, limit as i64], record_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// Mark the posting sent as `unichain_tx_hash` as still on-chain at
+    /// `UNICHAIN_CONFIRMATION_DEPTH`
+    pub fn confirm_posting(&self, unichain_tx_hash: B256) -> Result<()> {
+        self.conn().execute(
+            "UPDATE posted_blocks SET confirmed_at = ?2
+             WHERE unichain_tx_hash = ?1 AND orphaned = 0",
+            params![unichain_tx_hash.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Record that the posting sent as `unichain_tx_hash` was mined again in
+    /// `unichain_block` after a Unichain reorg
+    pub fn move_posting(&self, unichain_tx_hash: B256, unichain_block: u64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE posted_blocks SET unichain_block = ?2
+             WHERE unichain_tx_hash = ?1 AND orphaned = 0",
+            params![unichain_tx_hash.to_string(), unichain_block as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Mark the posting sent as `unichain_tx_hash` orphaned, its transaction
+    /// having been reorged out of Unichain. Unlike [`Self::mark_orphaned`],
+    /// the Monero block and its deposits stand.
+    pub fn drop_posting(&self, unichain_tx_hash: B256) -> Result<()> {
+        self.conn().execute(
+            "UPDATE posted_blocks SET orphaned = 1
+             WHERE unichain_tx_hash = ?1 AND orphaned = 0",
+            params![unichain_tx_hash.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Mark every live posting (and its deposits) at or above `from_height` as
+    /// orphaned by a reorg. Rows are kept for the audit trail.
+    pub fn mark_orphaned(&self, from_height: u64) -> Result<usize> {
+        let conn = self.conn();
+        let updated = conn.execute(
+            "UPDATE posted_blocks SET orphaned = 1 WHERE height >= ?1 AND orphaned = 0",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "UPDATE deposits SET orphaned = 1 WHERE block_height >= ?1 AND orphaned = 0",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "UPDATE bridge_outputs SET spent_height = NULL, spent_tx_hash = NULL
+             WHERE spent_height >= ?1",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM chain_work WHERE height >= ?1",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM indexed_blocks WHERE height >= ?1",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM outputs WHERE block_height >= ?1",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM merkle_trees WHERE height >= ?1",
+            params![from_height as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM safe_proposals WHERE height >= ?1",
+            params![from_height as i64],
+        )?;
+        Ok(updated)
+    }
+
+    /// Store every output of block `height`, replacing any indexed before
+    pub fn index_block_outputs(
+        &self,
+        height: u64,
+        block_hash: B256,
+        outputs: &[MoneroOutput],
+    ) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM outputs WHERE block_height = ?1",
+            params![height as i64],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO indexed_blocks (height, block_hash, output_count)
+             VALUES (?1, ?2, ?3)",
+            params![height as i64, block_hash.to_string(), outputs.len() as i64],
+        )?;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT INTO outputs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                OUTPUT_COLUMNS
+            ))?;
+            for (leaf_index, output) in outputs.iter().enumerate() {
+                stmt.execute(params![
+                    height as i64,
+                    leaf_index as i64,
+                    output.tx_hash.to_string(),
+                    output.output_index as i64,
+                    output.ecdh_amount.to_string(),
+                    output.output_pub_key.to_string(),
+                    output.commitment.to_string(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Keep a block's tree, replacing any stored before
+    pub fn store_merkle_tree(
+        &self,
+        height: u64,
+        block_hash: B256,
+        kind: TreeKind,
+        tree: &MerkleTree,
+    ) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR REPLACE INTO merkle_trees
+                (height, kind, block_hash, hasher, leaf_count, nodes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                height as i64,
+                kind.to_string(),
+                block_hash.to_string(),
+                tree.hasher().to_string(),
+                tree.len() as i64,
+                tree.to_bytes(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Block `height`'s tree, with the block hash, if it was stored
+    pub fn merkle_tree(&self, height: u64, kind: TreeKind) -> Result<Option<(B256, MerkleTree)>> {
+        let row = self
+            .conn()
+            .query_row(
+                "SELECT block_hash, hasher, leaf_count, nodes FROM merkle_trees
+                 WHERE height = ?1 AND kind = ?2",
+                params![height as i64, kind.to_string()],
+                |row| {
+                    Ok((
+                        b256_column(row, 0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)? as usize,
+                        row.get::<_, Vec<u8>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((block_hash, hasher, leaf_count, nodes)) = row else {
+            return Ok(None);
+        };
+        let tree = MerkleTree::from_bytes(leaf_count, hasher.parse()?, &nodes)
+            .with_context(|| format!("Stored {} tree of block {} is corrupt", kind, height))?;
+        Ok(Some((block_hash, tree)))
+    }
+
+    /// Outputs of block `height`, if it has been indexed
+    pub fn indexed_block(&self, height: u64) -> Result<Option<IndexedBlock>> {
+        let conn = self.conn();
+        let Some(block_hash) = conn
+            .query_row(
+                "SELECT block_hash FROM indexed_blocks WHERE height = ?1",
+                params![height as i64],
+                |row| b256_column(row, 0),
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM outputs WHERE block_height = ?1 ORDER BY leaf_index",
+            INDEXED_OUTPUT_COLUMNS
+        ))?;
+        let outputs = stmt
+            .query_map(params![height as i64], |row| {
+                indexed_output_from_row(row).map(|indexed| indexed.output)
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(Some(IndexedBlock {
+            height,
+            block_hash,
+            outputs,
+        }))
+    }
+
+    /// Output `output_index` of `tx_hash`, if its block has been indexed
+    pub fn indexed_output(
+        &self,
+        tx_hash: B256,
+        output_index: u64,
+    ) -> Result<Option<IndexedOutput>> {
+        self.conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM outputs WHERE tx_hash = ?1 AND output_index = ?2",
+                    INDEXED_OUTPUT_COLUMNS
+                ),
+                params![tx_hash.to_string(), output_index as i64],
+                indexed_output_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Indexed outputs of `tx_hash`, in output order
+    pub fn indexed_tx_outputs(&self, tx_hash: B256) -> Result<Vec<IndexedOutput>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM outputs WHERE tx_hash = ?1 ORDER BY output_index",
+            INDEXED_OUTPUT_COLUMNS
+        ))?;
+        let outputs = stmt
+            .query_map(params![tx_hash.to_string()], indexed_output_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(outputs)
+    }
+
+    /// The indexed output with one-time public key `output_pub_key`
+    pub fn output_by_pub_key(&self, output_pub_key: B256) -> Result<Option<IndexedOutput>> {
+        self.conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM outputs WHERE output_pub_key = ?1
+                     ORDER BY block_height LIMIT 1",
+                    INDEXED_OUTPUT_COLUMNS
+                ),
+                params![output_pub_key.to_string()],
+                indexed_output_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Record the cumulative difficulty monerod reported for a posted block
+    pub fn record_cumulative_difficulty(
+        &self,
+        height: u64,
+        block_hash: B256,
+        cumulative_difficulty: u128,
+    ) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO chain_work (height, block_hash, cumulative_difficulty)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (height) DO UPDATE SET
+                block_hash = excluded.block_hash,
+                cumulative_difficulty = excluded.cumulative_difficulty",
+            params![
+                height as i64,
+                block_hash.to_string(),
+                // SQLite integers are 64-bit
+                cumulative_difficulty.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Height and cumulative difficulty of the highest posted block recorded
+    pub fn latest_cumulative_difficulty(&self) -> Result<Option<(u64, u128)>> {
+        self.conn()
+            .query_row(
+                "SELECT height, cumulative_difficulty FROM chain_work
+                 ORDER BY height DESC LIMIT 1",
+                [],
+                |row| {
+                    let difficulty: String = row.get(1)?;
+                    Ok((
+                        row.get::<_, i64>(0)? as u64,
+                        difficulty
+                            .parse()
+                            .map_err(|e| FromSqlConversionFailure(1, Type::Text, Box::new(e)))?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Record a detected deposit. A deposit seen again (e.g. re-mined after a
+    /// reorg) is moved to its new block and un-orphaned; returns whether it
+    /// was new.
+    pub fn record_deposit(&self, deposit: &Deposit) -> Result<bool> {
+        let conn = self.conn();
+        let existed: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM deposits WHERE tx_hash = ?1 AND output_index = ?2)",
+            params![deposit.tx_hash.to_string(), deposit.output_index as i64],
+            |row| row.get(0),
+        )?;
+
+        conn.execute(
+            "INSERT INTO deposits (
+                block_height, tx_hash, output_index, amount, output_pub_key,
+                subaddress_major, subaddress_minor, detected_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (tx_hash, output_index) DO UPDATE SET
+                block_height = excluded.block_height,
+                orphaned = 0",
+            params![
+                deposit.block_height as i64,
+                deposit.tx_hash.to_string(),
+                deposit.output_index as i64,
+                deposit.amount as i64,
+                deposit.output_pub_key.to_string(),
+                deposit.subaddress.major,
+                deposit.subaddress.minor,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        if let Some(payment_id) = deposit.payment_id {
+            conn.execute(
+                "INSERT OR REPLACE INTO deposit_payment_ids (tx_hash, output_index, payment_id)
+                 VALUES (?1, ?2, ?3)",
+                params![
+                    deposit.tx_hash.to_string(),
+                    deposit.output_index as i64,
+                    payment_id.to_string(),
+                ],
+            )?;
+        }
+        conn.execute(
+            "DELETE FROM pending_deposits WHERE tx_hash = ?1 AND output_index = ?2",
+            params![deposit.tx_hash.to_string(), deposit.output_index as i64],
+        )?;
+        Ok(!existed)
+    }
+
+    /// Transaction and index of the deposit recorded with one-time key
+    /// `output_pub_key`, orphaned or not
+    pub fn deposit_with_output_key(&self, output_pub_key: B256) -> Result<Option<(B256, u64)>> {
+        self.conn()
+            .query_row(
+                "SELECT tx_hash, output_index FROM deposits WHERE output_pub_key = ?1",
+                params![output_pub_key.to_string()],
+                |row| Ok((b256_column(row, 0)?, row.get::<_, i64>(1)? as u64)),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Whether a deposit output is already recorded, orphaned or not
+    pub fn has_deposit(&self, tx_hash: B256, output_index: u64) -> Result<bool> {
+        Ok(self.conn().query_row(
+            "SELECT EXISTS(SELECT 1 FROM deposits WHERE tx_hash = ?1 AND output_index = ?2)",
+            params![tx_hash.to_string(), output_index as i64],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Record a deposit seen in the txpool; returns whether it was new. One
+    /// already in a posted block is ignored, and one mined then returned to
+    /// the pool by a reorg is marked unmined again.
+    pub fn record_pending_deposit(&self, deposit: &Deposit) -> Result<bool> {
+        let conn = self.conn();
+        let key = params![deposit.tx_hash.to_string(), deposit.output_index as i64];
+        let posted: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM deposits WHERE tx_hash = ?1 AND output_index = ?2)",
+            key,
+            |row| row.get(0),
+        )?;
+        if posted {
+            return Ok(false);
+        }
+        let existed: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pending_deposits WHERE tx_hash = ?1 AND output_index = ?2)",
+            key,
+            |row| row.get(0),
+        )?;
+
+        conn.execute(
+            "INSERT INTO pending_deposits (
+                tx_hash, output_index, amount, subaddress_major, subaddress_minor, first_seen_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (tx_hash, output_index) DO UPDATE SET block_height = NULL",
+            params![
+                deposit.tx_hash.to_string(),
+                deposit.output_index as i64,
+                deposit.amount as i64,
+                deposit.subaddress.major,
+                deposit.subaddress.minor,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(!existed)
+    }
+
+    /// Every pending deposit, oldest first
+    pub fn pending_deposits(&self) -> Result<Vec<PendingDeposit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT tx_hash, output_index, amount, subaddress_major, subaddress_minor,
+                    block_height, first_seen_at
+             FROM pending_deposits
+             ORDER BY first_seen_at, tx_hash, output_index",
+        )?;
+
+        let deposits = stmt
+            .query_map([], pending_deposit_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(deposits)
+    }
+
+    /// Pending deposits to `index`, oldest first
+    pub fn pending_deposits_for_subaddress(
+        &self,
+        index: SubaddressIndex,
+    ) -> Result<Vec<PendingDeposit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT tx_hash, output_index, amount, subaddress_major, subaddress_minor,
+                    block_height, first_seen_at
+             FROM pending_deposits
+             WHERE subaddress_major = ?1 AND subaddress_minor = ?2
+             ORDER BY first_seen_at, tx_hash, output_index",
+        )?;
+
+        let deposits = stmt
+            .query_map(params![index.major, index.minor], pending_deposit_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(deposits)
+    }
+
+    /// Note that the pending deposits of `tx_hash` left the txpool in a block
+    pub fn set_pending_deposit_mined(&self, tx_hash: B256, block_height: u64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE pending_deposits SET block_height = ?2 WHERE tx_hash = ?1",
+            params![tx_hash.to_string(), block_height as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the pending deposits of a transaction dropped from the txpool
+    pub fn remove_pending_deposits(&self, tx_hash: B256) -> Result<usize> {
+        self.conn()
+            .execute(
+                "DELETE FROM pending_deposits WHERE tx_hash = ?1",
+                params![tx_hash.to_string()],
+            )
+            .map_err(Into::into)
+    }
+
+    /// Live deposits found in the block at `height`
+    pub fn deposits_in_block(&self, height: u64) -> Result<Vec<Deposit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT d.block_height, d.tx_hash, d.output_index, d.amount, d.output_pub_key,
+                    d.subaddress_major, d.subaddress_minor, p.payment_id
+             FROM deposits d
+             LEFT JOIN deposit_payment_ids p
+               ON p.tx_hash = d.tx_hash AND p.output_index = d.output_index
+             WHERE d.block_height = ?1 AND d.orphaned = 0
+             ORDER BY d.id",
+        )?;
+
+        let deposits = stmt
+            .query_map(params![height as i64], deposit_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(deposits)
+    }
+
+    /// Live deposits made to one user's subaddress, oldest first
+    pub fn deposits_for_subaddress(&self, index: SubaddressIndex) -> Result<Vec<Deposit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT d.block_height, d.tx_hash, d.output_index, d.amount, d.output_pub_key,
+                    d.subaddress_major, d.subaddress_minor, p.payment_id
+             FROM deposits d
+             LEFT JOIN deposit_payment_ids p
+               ON p.tx_hash = d.tx_hash AND p.output_index = d.output_index
+             WHERE d.subaddress_major = ?1 AND d.subaddress_minor = ?2 AND d.orphaned = 0
+             ORDER BY d.block_height, d.id",
+        )?;
+
+        let deposits = stmt
+            .query_map(params![index.major, index.minor], deposit_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(deposits)
+    }
+
+    /// Live deposits made to the integrated address with `payment_id`,
+    /// oldest first
+    pub fn deposits_for_payment_id(&self, payment_id: B64) -> Result<Vec<Deposit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT d.block_height, d.tx_hash, d.output_index, d.amount, d.output_pub_key,
+                    d.subaddress_major, d.subaddress_minor, p.payment_id
+             FROM deposits d
+             JOIN deposit_payment_ids p
+               ON p.tx_hash = d.tx_hash AND p.output_index = d.output_index
+             WHERE p.payment_id = ?1 AND d.orphaned = 0
+             ORDER BY d.block_height, d.id",
+        )?;
+
+        let deposits = stmt
+            .query_map(params![payment_id.to_string()], deposit_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(deposits)
+    }
+
+    /// Set the Unichain address that deposits carrying `payment_id` are
+    /// minted to. Takes precedence over the primary address's recipient.
+    pub fn register_payment_id_recipient(&self, payment_id: B64, recipient: Address) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO payment_id_recipients (payment_id, address, registered_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (payment_id) DO UPDATE SET
+                address = excluded.address,
+                registered_at = excluded.registered_at",
+            params![
+                payment_id.to_string(),
+                recipient.to_string(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Unichain address registered for `payment_id`, if any
+    pub fn payment_id_recipient(&self, payment_id: B64) -> Result<Option<Address>> {
+        self.conn()
+            .query_row(
+                "SELECT address FROM payment_id_recipients WHERE payment_id = ?1",
+                params![payment_id.to_string()],
+                |row| address_column(row, 0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Set the Unichain address that deposits to `index` are minted to
+    pub fn register_recipient(&self, index: SubaddressIndex, recipient: Address) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO recipients (subaddress_major, subaddress_minor, address, registered_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (subaddress_major, subaddress_minor) DO UPDATE SET
+                address = excluded.address,
+                registered_at = excluded.registered_at",
+            params![
+                index.major,
+                index.minor,
+                recipient.to_string(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Unichain address registered for `index`, if any
+    pub fn recipient(&self, index: SubaddressIndex) -> Result<Option<Address>> {
+        self.conn()
+            .query_row(
+                "SELECT address FROM recipients
+                 WHERE subaddress_major = ?1 AND subaddress_minor = ?2",
+                params![index.major, index.minor],
+                |row| address_column(row, 0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Live deposits with a registered recipient and no recorded mint, oldest
+    /// first. A deposit with a payment ID goes to that payment ID's recipient
+    /// and is left alone until one is registered.
+    pub fn pending_mints(&self) -> Result<Vec<PendingMint>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT d.block_height, d.tx_hash, d.output_index, d.amount, d.output_pub_key,
+                    d.subaddress_major, d.subaddress_minor, p.payment_id,
+                    COALESCE(pr.address, r.address)
+             FROM deposits d
+             LEFT JOIN deposit_payment_ids p
+               ON p.tx_hash = d.tx_hash AND p.output_index = d.output_index
+             LEFT JOIN payment_id_recipients pr ON pr.payment_id = p.payment_id
+             LEFT JOIN recipients r
+               ON r.subaddress_major = d.subaddress_major
+              AND r.subaddress_minor = d.subaddress_minor
+              AND p.payment_id IS NULL
+             LEFT JOIN mints m
+               ON m.tx_hash = d.tx_hash AND m.output_index = d.output_index
+             WHERE d.orphaned = 0 AND m.id IS NULL
+               AND COALESCE(pr.address, r.address) IS NOT NULL
+               AND NOT EXISTS (
+                   SELECT 1 FROM deposit_reviews v
+                   WHERE v.tx_hash = d.tx_hash AND v.output_index = d.output_index
+                     AND v.status != 'approved'
+               )
+             ORDER BY d.block_height, d.id",
+        )?;
+
+        let pending = stmt
+            .query_map([], |row| {
+                Ok(PendingMint {
+                    deposit: deposit_from_row(row)?,
+                    recipient: address_column(row, 8)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(pending)
+    }
+
+    /// Record that `deposit` was minted to `recipient` by `unichain_tx_hash`.
+    /// Returns false if the deposit had already been minted.
+    pub fn record_mint(
+        &self,
+        deposit: &Deposit,
+        recipient: Address,
+        unichain_tx_hash: B256,
+    ) -> Result<bool> {
+        let inserted = self.conn().execute(
+            "INSERT INTO mints (
+                tx_hash, output_index, recipient, amount, unichain_tx_hash, minted_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (tx_hash, output_index) DO NOTHING",
+            params![
+                deposit.tx_hash.to_string(),
+                deposit.output_index as i64,
+                recipient.to_string(),
+                deposit.amount as i64,
+                unichain_tx_hash.to_string(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Piconero minted to `recipient` since `since`
+    pub fn minted_since(&self, recipient: Address, since: DateTime<Utc>) -> Result<u64> {
+        let minted: i64 = self.conn().query_row(
+            "SELECT COALESCE(SUM(amount), 0) FROM mints
+             WHERE recipient = ?1 AND minted_at >= ?2",
+            params![recipient.to_string(), since.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+        Ok(minted as u64)
+    }
+
+    /// Keep `deposit` from being minted until an operator reviews it;
+    /// returns false if it was already held or reviewed
+    pub fn hold_deposit(&self, deposit: &Deposit, reason: &str) -> Result<bool> {
+        let inserted = self.conn().execute(
+            "INSERT INTO deposit_reviews (tx_hash, output_index, reason, status, held_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (tx_hash, output_index) DO NOTHING",
+            params![
+                deposit.tx_hash.to_string(),
+                deposit.output_index as i64,
+                reason,
+                ReviewStatus::Held.to_string(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Review state of a deposit, `None` if it was never held
+    pub fn deposit_review(&self, tx_hash: B256, output_index: u64) -> Result<Option<ReviewStatus>> {
+        self.conn()
+            .query_row(
+                "SELECT status FROM deposit_reviews WHERE tx_hash = ?1 AND output_index = ?2",
+                params![tx_hash.to_string(), output_index as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(|status| status.parse())
+            .transpose()
+    }
+
+    /// Live deposits held and not reviewed yet, oldest first
+    pub fn held_deposits(&self) -> Result<Vec<HeldDeposit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT d.block_height, d.tx_hash, d.output_index, d.amount, d.output_pub_key,
+                    d.subaddress_major, d.subaddress_minor, p.payment_id, v.reason, v.held_at
+             FROM deposit_reviews v
+             JOIN deposits d ON d.tx_hash = v.tx_hash AND d.output_index = v.output_index
+             LEFT JOIN deposit_payment_ids p
+               ON p.tx_hash = d.tx_hash AND p.output_index = d.output_index
+             WHERE v.status = ?1 AND d.orphaned = 0
+             ORDER BY v.held_at, d.id",
+        )?;
+        let held = stmt
+            .query_map(params![ReviewStatus::Held.to_string()], |row| {
+                let held_at: String = row.get(9)?;
+                Ok(HeldDeposit {
+                    deposit: deposit_from_row(row)?,
+                    reason: row.get(8)?,
+                    held_at: DateTime::parse_from_rfc3339(&held_at)
+                        .map_err(|e| FromSqlConversionFailure(9, Type::Text, Box::new(e)))?
+                        .with_timezone(&Utc),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(held)
+    }
+
+    /// Approve a held deposit for minting, or reject it for good
+    pub fn review_deposit(
+        &self,
+        tx_hash: B256,
+        output_index: u64,
+        status: ReviewStatus,
+    ) -> Result<()> {
+        anyhow::ensure!(status != ReviewStatus::Held, "A review approves or rejects");
+        let updated = self.conn().execute(
+            "UPDATE deposit_reviews SET status = ?3, reviewed_at = ?4
+             WHERE tx_hash = ?1 AND output_index = ?2 AND status = ?5",
+            params![
+                tx_hash.to_string(),
+                output_index as i64,
+                status.to_string(),
+                Utc::now().to_rfc3339(),
+                ReviewStatus::Held.to_string(),
+            ],
+        )?;
+        anyhow::ensure!(
+            updated == 1,
+            "Deposit {}:{} is not held for review",
+            tx_hash,
+            output_index
+        );
+        Ok(())
+    }
+
+    /// Record the bridge fee quoted for an operation; the first quote stands
+    pub fn record_fee_charge(&self, charge: &FeeCharge) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO bridge_fees (kind, reference, amount, fee, usd_per_xmr, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (kind, reference) DO NOTHING",
+            params![
+                charge.kind.to_string(),
+                charge.reference,
+                charge.amount as i64,
+                charge.fee as i64,
+                charge.usd_per_xmr,
+                charge.recorded_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Bridge fee already quoted for an operation
+    pub fn fee_charge(&self, kind: FeeKind, reference: &str) -> Result<Option<FeeCharge>> {
+        Ok(self
+            .conn()
+            .query_row(
+                "SELECT kind, reference, amount, fee, usd_per_xmr, recorded_at FROM bridge_fees
+                 WHERE kind = ?1 AND reference = ?2",
+                params![kind.to_string(), reference],
+                fee_charge_from_row,
+            )
+            .optional()?)
+    }
+
+    /// Mark the fee on an operation collected, once the mint or payout has
+    /// gone through
+    pub fn collect_fee(&self, kind: FeeKind, reference: &str) -> Result<()> {
+        self.conn().execute(
+            "UPDATE bridge_fees SET collected_at = ?3
+             WHERE kind = ?1 AND reference = ?2 AND collected_at IS NULL",
+            params![kind.to_string(), reference, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Fees collected in `[from, to)`, oldest first
+    pub fn collected_fees(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<FeeCharge>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT kind, reference, amount, fee, usd_per_xmr, recorded_at FROM bridge_fees
+             WHERE collected_at >= ?1 AND collected_at < ?2
+             ORDER BY collected_at, kind, reference",
+        )?;
+        let charges = stmt
+            .query_map(
+                params![from.to_rfc3339(), to.to_rfc3339()],
+                fee_charge_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(charges)
+    }
+
+    /// Fees collected and not yet swept to the treasury
+    pub fn unswept_fees(&self) -> Result<u64> {
+        let unswept: i64 = self.conn().query_row(
+            "SELECT (SELECT COALESCE(SUM(fee), 0) FROM bridge_fees WHERE collected_at IS NOT NULL)
+                  - (SELECT COALESCE(SUM(amount), 0) FROM fee_sweeps)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(unswept.max(0) as u64)
+    }
+
+    /// Record a sweep of collected fees before it is relayed
+    pub fn record_fee_sweep(&self, sweep: &FeeSweep) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO fee_sweeps (tx_hash, amount, network_fee, swept_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                sweep.tx_hash.to_string(),
+                sweep.amount as i64,
+                sweep.network_fee as i64,
+                sweep.swept_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Forget a sweep that was never relayed
+    pub fn remove_fee_sweep(&self, tx_hash: B256) -> Result<()> {
+        self.conn().execute(
+            "DELETE FROM fee_sweeps WHERE tx_hash = ?1",
+            params![tx_hash.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Sweeps made in `[from, to)`, oldest first
+    pub fn fee_sweeps(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<FeeSweep>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT tx_hash, amount, network_fee, swept_at FROM fee_sweeps
+             WHERE swept_at >= ?1 AND swept_at < ?2
+             ORDER BY swept_at",
+        )?;
+        let sweeps = stmt
+            .query_map(params![from.to_rfc3339(), to.to_rfc3339()], |row| {
+                Ok(FeeSweep {
+                    tx_hash: b256_column(row, 0)?,
+                    amount: row.get::<_, i64>(1)? as u64,
+                    network_fee: row.get::<_, i64>(2)? as u64,
+                    swept_at: datetime_column(row, 3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(sweeps)
+    }
+
+    /// Open a signing round for `key` around `tx_data_hex`; returns its ID
+    pub fn begin_multisig_round(
+        &self,
+        key: &str,
+        role: RoundRole,
+        tx_data_hex: &str,
+    ) -> Result<i64> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO multisig_rounds (key, role, status, tx_data_hex, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![
+                key,
+                role.to_string(),
+                RoundStatus::Signing.to_string(),
+                tx_data_hex,
+                now
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Add `signer`'s signature, carried by `tx_data_hex`, to a round
+    pub fn record_multisig_signature(
+        &self,
+        id: i64,
+        signer: &str,
+        tx_data_hex: &str,
+    ) -> Result<()> {
+        self.conn().execute(
+            "UPDATE multisig_rounds SET
+                signers = CASE signers WHEN '' THEN ?2 ELSE signers || ',' || ?2 END,
+                tx_data_hex = ?3, updated_at = ?4
+             WHERE id = ?1",
+            params![id, signer, tx_data_hex, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn close_multisig_round(
+        &self,
+        id: i64,
+        status: RoundStatus,
+        tx_hash: Option<B256>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        self.conn().execute(
+            "UPDATE multisig_rounds SET status = ?2, tx_hash = ?3, error = ?4, updated_at = ?5
+             WHERE id = ?1",
+            params![
+                id,
+                status.to_string(),
+                tx_hash.map(|hash| hash.to_string()),
+                error,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Mark every round still collecting signatures failed; returns how many
+    /// there were
+    pub fn fail_open_multisig_rounds(&self, error: &str) -> Result<usize> {
+        let failed = self.conn().execute(
+            "UPDATE multisig_rounds SET status = ?1, error = ?2, updated_at = ?3
+             WHERE status = ?4",
+            params![
+                RoundStatus::Failed.to_string(),
+                error,
+                Utc::now().to_rfc3339(),
+                RoundStatus::Signing.to_string()
+            ],
+        )?;
+        Ok(failed)
+    }
+
+    /// The `limit` most recent signing rounds, newest first
+    pub fn multisig_rounds(&self, limit: u32) -> Result<Vec<SigningRound>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, key, role, status, signers, tx_data_hex, tx_hash, error, created_at,
+                    updated_at
+             FROM multisig_rounds ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rounds = stmt
+            .query_map(params![limit], signing_round_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rounds)
+    }
+
+    /// Queue a withdrawal read from a burn event; returns false if the burn
+    /// was already known
+    pub fn queue_withdrawal(&self, withdrawal: &Withdrawal) -> Result<bool> {
+        let now = Utc::now().to_rfc3339();
+        let inserted = self.conn().execute(
+            "INSERT INTO withdrawals (
+                burn_id, user, lp, amount, xmr_address, unichain_tx_hash,
+                unichain_block, status, error, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+            ON CONFLICT (burn_id) DO NOTHING",
+            params![
+                withdrawal.burn_id as i64,
+                withdrawal.user.to_string(),
+                withdrawal.lp.to_string(),
+                withdrawal.amount as i64,
+                withdrawal.xmr_address,
+                withdrawal.unichain_tx_hash.to_string(),
+                withdrawal.unichain_block as i64,
+                withdrawal.status.to_string(),
+                withdrawal.error,
+                now,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn withdrawal(&self, burn_id: u64) -> Result<Option<Withdrawal>> {
+        self.conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM withdrawals WHERE burn_id = ?1",
+                    WITHDRAWAL_COLUMNS
+                ),
+                params![burn_id as i64],
+                withdrawal_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Withdrawals in `status`, oldest burn first
+    pub fn withdrawals_with_status(&self, status: WithdrawalStatus) -> Result<Vec<Withdrawal>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM withdrawals WHERE status = ?1 ORDER BY burn_id",
+            WITHDRAWAL_COLUMNS
+        ))?;
+
+        let withdrawals = stmt
+            .query_map(params![status.to_string()], withdrawal_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(withdrawals)
+    }
+
+    /// Withdrawals in `status` whose retry backoff (if any) has elapsed,
+    /// oldest burn first
+    pub fn withdrawals_due(&self, status: WithdrawalStatus) -> Result<Vec<Withdrawal>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM withdrawals
+             WHERE status = ?1 AND (next_attempt_at IS NULL OR next_attempt_at <= ?2)
+             ORDER BY burn_id",
+            WITHDRAWAL_COLUMNS
+        ))?;
+
+        let withdrawals = stmt
+            .query_map(
+                params![status.to_string(), Utc::now().to_rfc3339()],
+                withdrawal_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(withdrawals)
+    }
+
+    /// Count a failed attempt at a withdrawal's current step. It is retried
+    /// after a backoff, or moved to `failed` once `max_attempts` is reached;
+    /// returns the resulting status.
+    pub fn record_withdrawal_failure(
+        &self,
+        burn_id: u64,
+        error: &str,
+        max_attempts: u32,
+    ) -> Result<WithdrawalStatus> {
+        let conn = self.conn();
+        let (status, attempts): (String, u32) = conn.query_row(
+            "SELECT status, attempts FROM withdrawals WHERE burn_id = ?1",
+            params![burn_id as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let attempts = attempts + 1;
+        let status = if attempts >= max_attempts {
+            WithdrawalStatus::Failed
+        } else {
+            status.parse()?
+        };
+        let next_attempt_at = Utc::now() + retry_delay(attempts);
+
+        conn.execute(
+            "UPDATE withdrawals SET
+                status = ?2, error = ?3, attempts = ?4, next_attempt_at = ?5, updated_at = ?6
+             WHERE burn_id = ?1",
+            params![
+                burn_id as i64,
+                status.to_string(),
+                error,
+                attempts,
+                next_attempt_at.to_rfc3339(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(status)
+    }
+
+    /// Record a signed payout to one or more queued withdrawals, given as
+    /// `(burn_id, amount sent)` in destination order, moving them to
+    /// `signed`. Each is charged an equal share of the network fee, the first
+    /// one any remainder. Must happen before the transfer is relayed.
+    pub fn record_withdrawals_signed(
+        &self,
+        payouts: &[(u64, u64)],
+        transfer: &SignedTransfer,
+    ) -> Result<()> {
+        anyhow::ensure!(!payouts.is_empty(), "Payout has no withdrawals");
+        let share = transfer.fee / payouts.len() as u64;
+        let remainder = transfer.fee % payouts.len() as u64;
+        let now = Utc::now().to_rfc3339();
+
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        for (i, &(burn_id, amount)) in payouts.iter().enumerate() {
+            let fee = if i == 0 { share + remainder } else { share };
+            let updated = tx.execute(
+                "UPDATE withdrawals SET
+                    status = ?2, error = NULL, attempts = 0, next_attempt_at = NULL,
+                    xmr_tx_hash = ?3, xmr_tx_key = ?4, xmr_fee = ?5, xmr_tx_metadata = ?6,
+                    updated_at = ?7
+                 WHERE burn_id = ?1 AND status = ?8",
+                params![
+                    burn_id as i64,
+                    WithdrawalStatus::Signed.to_string(),
+                    transfer.tx_hash.to_string(),
+                    transfer.tx_key,
+                    fee as i64,
+                    transfer.tx_metadata,
+                    now,
+                    WithdrawalStatus::Queued.to_string(),
+                ],
+            )?;
+            anyhow::ensure!(updated == 1, "Withdrawal {} is not queued", burn_id);
+            tx.execute(
+                "INSERT OR REPLACE INTO withdrawal_payouts (burn_id, xmr_tx_hash, amount)
+                 VALUES (?1, ?2, ?3)",
+                params![burn_id as i64, transfer.tx_hash.to_string(), amount as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Withdrawals paid by the Monero transaction `tx_hash`, in burn order
+    pub fn withdrawals_in_payout(&self, tx_hash: B256) -> Result<Vec<Withdrawal>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM withdrawals WHERE xmr_tx_hash = ?1 ORDER BY burn_id",
+            WITHDRAWAL_COLUMNS
+        ))?;
+
+        let withdrawals = stmt
+            .query_map(params![tx_hash.to_string()], withdrawal_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(withdrawals)
+    }
+
+    /// Metadata `relay_tx` needs to broadcast a signed withdrawal
+    pub fn withdrawal_tx_metadata(&self, burn_id: u64) -> Result<Option<String>> {
+        self.conn()
+            .query_row(
+                "SELECT xmr_tx_metadata FROM withdrawals WHERE burn_id = ?1",
+                params![burn_id as i64],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(Into::into)
+    }
+
+    /// Move a withdrawal to `status`, recording (or clearing) its error and
+    /// resetting its retry count
+    pub fn set_withdrawal_status(
+        &self,
+        burn_id: u64,
+        status: WithdrawalStatus,
+        error: Option<&str>,
+    ) -> Result<()> {
+        self.conn().execute(
+            "UPDATE withdrawals SET
+                status = ?2, error = ?3, attempts = 0, next_attempt_at = NULL, updated_at = ?4
+             WHERE burn_id = ?1",
+            params![
+                burn_id as i64,
+                status.to_string(),
+                error,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Move a withdrawal from stage `from` to the one after it, only if it
+    /// is still at `from`: a stage is never completed twice, even by a
+    /// retry racing a restart. Clears the error and attempt count.
+    pub fn advance_withdrawal(
+        &self,
+        burn_id: u64,
+        from: WithdrawalStatus,
+    ) -> Result<WithdrawalStatus> {
+        let to = from
+            .next()
+            .with_context(|| format!("Withdrawal status {} is final", from))?;
+        let updated = self.conn().execute(
+            "UPDATE withdrawals SET
+                status = ?3, error = NULL, attempts = 0, next_attempt_at = NULL, updated_at = ?4
+             WHERE burn_id = ?1 AND status = ?2",
+            params![
+                burn_id as i64,
+                from.to_string(),
+                to.to_string(),
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        anyhow::ensure!(
+            updated == 1,
+            "Withdrawal #{} is no longer {}",
+            burn_id,
+            from
+        );
+        Ok(to)
+    }
+
+    /// Give a failed withdrawal another round of attempts. One that was
+    /// never signed goes back to `queued`; one with a signed transfer goes
+    /// back to `signed`, so that same transfer is relayed again rather than a
+    /// second one being signed. Returns the stage it resumes at.
+    pub fn requeue_withdrawal(&self, burn_id: u64) -> Result<WithdrawalStatus> {
+        let conn = self.conn();
+        let updated = conn.execute(
+            "UPDATE withdrawals SET
+                status = CASE WHEN xmr_tx_hash IS NULL THEN ?3 ELSE ?4 END,
+                error = NULL, attempts = 0, next_attempt_at = NULL, updated_at = ?5
+             WHERE burn_id = ?1 AND status = ?2",
+            params![
+                burn_id as i64,
+                WithdrawalStatus::Failed.to_string(),
+                WithdrawalStatus::Queued.to_string(),
+                WithdrawalStatus::Signed.to_string(),
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        anyhow::ensure!(updated == 1, "Withdrawal #{} has not failed", burn_id);
+        let status: String = conn.query_row(
+            "SELECT status FROM withdrawals WHERE burn_id = ?1",
+            params![burn_id as i64],
+            |row| row.get(0),
+        )?;
+        status.parse()
+    }
+
+    /// Start tracking a bridge wallet output by its key image; returns false
+    /// if it was already tracked
+    pub fn track_bridge_output(&self, key_image: B256, tx_hash: B256, amount: u64) -> Result<bool> {
+        let inserted = self.conn().execute(
+            "INSERT INTO bridge_outputs (key_image, tx_hash, amount, discovered_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (key_image) DO NOTHING",
+            params![
+                key_image.to_string(),
+                tx_hash.to_string(),
+                amount as i64,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Mark tracked bridge outputs spent by `spends`; returns the spends that
+    /// matched an unspent bridge output
+    pub fn record_key_image_spends(&self, spends: &[KeyImageSpend]) -> Result<Vec<KeyImageSpend>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "UPDATE bridge_outputs SET spent_height = ?2, spent_tx_hash = ?3
+             WHERE key_image = ?1 AND spent_height IS NULL",
+        )?;
+
+        let mut matched = Vec::new();
+        for spend in spends {
+            let updated = stmt.execute(params![
+                spend.key_image.to_string(),
+                spend.block_height as i64,
+                spend.tx_hash.to_string(),
+            ])?;
+            if updated > 0 {
+                matched.push(spend.clone());
+            }
+        }
+        Ok(matched)
+    }
+
+    /// Bridge outputs not yet seen spent on-chain
+    pub fn unspent_bridge_outputs(&self) -> Result<Vec<BridgeOutput>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT key_image, tx_hash, amount, spent_height, spent_tx_hash
+             FROM bridge_outputs
+             WHERE spent_height IS NULL
+             ORDER BY discovered_at, key_image",
+        )?;
+
+        let outputs = stmt
+            .query_map([], bridge_output_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(outputs)
+    }
+
+    /// Whether `tx_hash` is the Monero payout of a withdrawal
+    pub fn is_withdrawal_payout(&self, tx_hash: B256) -> Result<bool> {
+        self.conn()
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM withdrawals WHERE xmr_tx_hash = ?1)",
+                params![tx_hash.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Store a signer's attestation, replacing any earlier one for the same
+    /// height (the block there may have changed in a reorg)
+    pub fn record_attestation(&self, attestation: &Attestation) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO attestations (height, signer, block_hash, tx_merkle_root,
+                                       output_merkle_root, signature, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (height, signer) DO UPDATE SET
+                block_hash = excluded.block_hash,
+                tx_merkle_root = excluded.tx_merkle_root,
+                output_merkle_root = excluded.output_merkle_root,
+                signature = excluded.signature,
+                created_at = excluded.created_at",
+            params![
+                attestation.height as i64,
+                attestation.signer.to_string(),
+                attestation.block_hash.to_string(),
+                attestation.tx_merkle_root.to_string(),
+                attestation.output_merkle_root.to_string(),
+                attestation.signature.to_string(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// `signer`'s attestation for `height`, if stored
+    pub fn attestation(&self, height: u64, signer: Address) -> Result<Option<Attestation>> {
+        self.conn()
+            .query_row(
+                "SELECT height, block_hash, tx_merkle_root, output_merkle_root, signer, signature
+                 FROM attestations
+                 WHERE height = ?1 AND signer = ?2",
+                params![height as i64, signer.to_string()],
+                attestation_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Every stored attestation for `height`, this oracle's and gossiped
+    /// ones, by signer
+    pub fn attestations(&self, height: u64) -> Result<Vec<Attestation>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT height, block_hash, tx_merkle_root, output_merkle_root, signer, signature
+             FROM attestations
+             WHERE height = ?1",
+        )?;
+        let mut attestations = stmt
+            .query_map(params![height as i64], attestation_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        // Stored checksummed, so text order isn't address order
+        attestations.sort_by_key(|attestation| attestation.signer);
+        Ok(attestations)
+    }
+
+    /// Record a posting proposed to the Safe, replacing any earlier
+    /// proposal for the same height
+    pub fn record_safe_proposal(&self, proposal: &SafeProposal) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO safe_proposals (height, block_hash, safe_tx_hash, nonce, proposed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (height) DO UPDATE SET
+                block_hash = excluded.block_hash,
+                safe_tx_hash = excluded.safe_tx_hash,
+                nonce = excluded.nonce,
+                proposed_at = excluded.proposed_at",
+            params![
+                proposal.height as i64,
+                proposal.block_hash.to_string(),
+                proposal.safe_tx_hash.to_string(),
+                proposal.nonce as i64,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Postings proposed to the Safe and not yet executed, lowest first
+    pub fn safe_proposals(&self) -> Result<Vec<SafeProposal>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT height, block_hash, safe_tx_hash, nonce FROM safe_proposals ORDER BY height",
+        )?;
+        let proposals = stmt
+            .query_map([], |row| {
+                Ok(SafeProposal {
+                    height: row.get::<_, i64>(0)? as u64,
+                    block_hash: b256_column(row, 1)?,
+                    safe_tx_hash: b256_column(row, 2)?,
+                    nonce: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(proposals)
+    }
+
+    pub fn delete_safe_proposal(&self, height: u64) -> Result<()> {
+        self.conn().execute(
+            "DELETE FROM safe_proposals WHERE height = ?1",
+            params![height as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Journal the intent to take an action, before taking it, and return
+    /// its entry. An existing entry comes back as it stands, with the
+    /// transactions earlier attempts sent, so the caller can tell whether
+    /// the action was already taken; an abandoned one is reopened.
+    pub fn begin_operation(&self, intent: &Intent) -> Result<Operation> {
+        let key = intent.key();
+        let now = Utc::now().to_rfc3339();
+        self.conn().execute(
+            "INSERT INTO operations (key, kind, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)
+             ON CONFLICT (key) DO UPDATE SET
+                status = excluded.status, error = NULL, updated_at = excluded.updated_at
+             WHERE operations.status = ?5",
+            params![
+                key,
+                intent.kind().to_string(),
+                OperationStatus::Pending.to_string(),
+                now,
+                OperationStatus::Abandoned.to_string(),
+            ],
+        )?;
+        self.operation(&key)?
+            .with_context(|| format!("Operation {} was not recorded", key))
+    }
+
+    pub fn operation(&self, key: &str) -> Result<Option<Operation>> {
+        let conn = self.conn();
+        let operation = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM operations WHERE key = ?1",
+                    OPERATION_COLUMNS
+                ),
+                params![key],
+                operation_from_row,
+            )
+            .optional()?;
+        operation
+            .map(|operation| with_sent_transactions(&conn, operation))
+            .transpose()
+    }
+
+    /// Record a transaction broadcast for an operation
+    pub fn record_operation_tx(&self, key: &str, tx_hash: B256) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO operation_transactions (key, tx_hash, sent_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (key, tx_hash) DO NOTHING",
+            params![key, tx_hash.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Mark an operation as taken, by `tx_hash` if it is known
+    pub fn complete_operation(&self, key: &str, tx_hash: Option<B256>) -> Result<()> {
+        self.set_operation_status(key, OperationStatus::Completed, tx_hash, None)
+    }
+
+    /// Mark an operation as known not to have been taken
+    pub fn abandon_operation(&self, key: &str, error: &str) -> Result<()> {
+        self.set_operation_status(key, OperationStatus::Abandoned, None, Some(error))
+    }
+
+    fn set_operation_status(
+        &self,
+        key: &str,
+        status: OperationStatus,
+        tx_hash: Option<B256>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let updated = self.conn().execute(
+            "UPDATE operations SET status = ?2, tx_hash = ?3, error = ?4, updated_at = ?5
+             WHERE key = ?1",
+            params![
+                key,
+                status.to_string(),
+                tx_hash.map(|hash| hash.to_string()),
+                error,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        anyhow::ensure!(updated == 1, "No operation {}", key);
+        Ok(())
+    }
+
+    /// Operations not yet known to have been taken or not, oldest first
+    pub fn pending_operations(&self) -> Result<Vec<Operation>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM operations WHERE status = ?1 ORDER BY created_at, key",
+            OPERATION_COLUMNS
+        ))?;
+        let operations = stmt
+            .query_map(
+                params![OperationStatus::Pending.to_string()],
+                operation_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        operations
+            .into_iter()
+            .map(|operation| with_sent_transactions(&conn, operation))
+            .collect()
+    }
+
+    /// Record what a transaction cost; a transaction already recorded (its
+    /// receipt found again after a restart) is left as it is
+    pub fn record_tx_cost(&self, cost: &TxCost) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR IGNORE INTO tx_costs
+                (tx_hash, kind, gas_used, gas_price, xmr_fee, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                cost.tx_hash.to_string(),
+                cost.kind.to_string(),
+                cost.gas_used as i64,
+                cost.gas_price.to_string(),
+                cost.xmr_fee as i64,
+                cost.recorded_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Costs recorded in `[from, to)`, oldest first
+    pub fn tx_costs(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<TxCost>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT tx_hash, kind, gas_used, gas_price, xmr_fee, recorded_at
+             FROM tx_costs WHERE recorded_at >= ?1 AND recorded_at < ?2
+             ORDER BY recorded_at, tx_hash",
+        )?;
+        let costs = stmt
+            .query_map(
+                params![from.to_rfc3339(), to.to_rfc3339()],
+                tx_cost_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(costs)
+    }
+
+    /// Last Unichain block whose burn events have been queued
+    pub fn unichain_cursor(&self) -> Result<Option<u64>> {
+        self.conn()
+            .query_row(
+                "SELECT value FROM sync_state WHERE name = ?1",
+                params![UNICHAIN_CURSOR],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map(|value| value.map(|v| v as u64))
+            .map_err(Into::into)
+    }
+
+    pub fn set_unichain_cursor(&self, block: u64) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO sync_state (name, value) VALUES (?1, ?2)
+             ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+            params![UNICHAIN_CURSOR, block as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Safe nonce after the last transaction proposed to it
+    pub fn safe_nonce(&self) -> Result<Option<u64>> {
+        self.conn()
+            .query_row(
+                "SELECT value FROM sync_state WHERE name = ?1",
+                params![SAFE_NONCE],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map(|value| value.map(|v| v as u64))
+            .map_err(Into::into)
+    }
+
+    pub fn set_safe_nonce(&self, nonce: u64) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO sync_state (name, value) VALUES (?1, ?2)
+             ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+            params![SAFE_NONCE, nonce as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Highest height with a live (non-orphaned) posting
+    pub fn checkpoint(&self) -> Result<Option<u64>> {
+        let height: Option<i64> = self.conn().query_row(
+            "SELECT MAX(height) FROM posted_blocks WHERE orphaned = 0",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(height.map(|h| h as u64))
+    }
+
+    /// Live posting for `height`, if any
+    pub fn posted_block(&self, height: u64) -> Result<Option<PostedBlockRecord>> {
+        self.conn()
+            .query_row(
+                "SELECT height, block_hash, tx_merkle_root, output_merkle_root,
+                        unichain_tx_hash, unichain_block, gas_used, posted_at
+                 FROM posted_blocks
+                 WHERE height = ?1 AND orphaned = 0
+                 ORDER BY id DESC LIMIT 1",
+                params![height as i64],
+                record_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Every live posting with the cumulative difficulty recorded for it,
+    /// lowest height first
+    pub fn posted_chain(&self) -> Result<Vec<(PostedBlockRecord, Option<u128>)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT p.height, p.block_hash, p.tx_merkle_root, p.output_merkle_root,
+                    p.unichain_tx_hash, p.unichain_block, p.gas_used, p.posted_at,
+                    w.cumulative_difficulty
+             FROM posted_blocks p
+             LEFT JOIN chain_work w ON w.height = p.height AND w.block_hash = p.block_hash
+             WHERE p.orphaned = 0
+             ORDER BY p.height, p.id",
+        )?;
+        let chain = stmt
+            .query_map([], |row| {
+                let difficulty: Option<String> = row.get(8)?;
+                Ok((
+                    record_from_row(row)?,
+                    difficulty
+                        .map(|d| d.parse())
+                        .transpose()
+                        .map_err(|e| FromSqlConversionFailure(8, Type::Text, Box::new(e)))?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(chain)
+    }
+
+    /// Load postings made elsewhere, e.g. from a snapshot, in one
+    /// transaction. Heights already posted with the same hash only gain a
+    /// missing cumulative difficulty; any with a different hash fail the
+    /// whole import. Returns how many postings were added.
+    pub fn import_posted_blocks(
+        &self,
+        blocks: &[(PostedBlockRecord, Option<u128>)],
+    ) -> Result<usize> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let mut imported = 0;
+        for (record, cumulative_difficulty) in blocks {
+            let existing: Option<String> = tx
+                .query_row(
+                    "SELECT block_hash FROM posted_blocks
+                     WHERE height = ?1 AND orphaned = 0
+                     ORDER BY id DESC LIMIT 1",
+                    params![record.height as i64],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match existing {
+                Some(hash) if hash == record.block_hash.to_string() => {}
+                Some(hash) => anyhow::bail!(
+                    "Block {} is already posted here as {}, the snapshot has {}",
+                    record.height,
+                    hash,
+                    record.block_hash
+                ),
+                None => {
+                    // Taken as settled where they were exported
+                    tx.execute(
+                        "INSERT INTO posted_blocks (
+                            height, block_hash, tx_merkle_root, output_merkle_root,
+                            unichain_tx_hash, unichain_block, gas_used, posted_at,
+                            confirmed_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+                        params![
+                            record.height as i64,
+                            record.block_hash.to_string(),
+                            record.tx_merkle_root.to_string(),
+                            record.output_merkle_root.to_string(),
+                            record.unichain_tx_hash.to_string(),
+                            record.unichain_block.map(|b| b as i64),
+                            record.gas_used as i64,
+                            record.posted_at.to_rfc3339(),
+                        ],
+                    )?;
+                    imported += 1;
+                }
+            }
+            if let Some(difficulty) = cumulative_difficulty {
+                tx.execute(
+                    "INSERT OR IGNORE INTO chain_work (height, block_hash, cumulative_difficulty)
+                     VALUES (?1, ?2, ?3)",
+                    params![
+                        record.height as i64,
+                        record.block_hash.to_string(),
+                        difficulty.to_string(),
+                    ],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    }
+
+    /// The `limit` most recent live postings, lowest height first
+    pub fn recent_blocks(&self, limit: usize) -> Result<Vec<PostedBlockRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT height, block_hash, tx_merkle_root, output_merkle_root,
+                    unichain_tx_hash, unichain_block, gas_used, posted_at
+             FROM posted_blocks
+             WHERE orphaned = 0
+             ORDER BY height DESC LIMIT ?1",
+        )?;
+
+        let mut records = stmt
+            .query_map(params![limit as i64], record_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        records.reverse();
+        Ok(records)
+    }
+
+    /// Write a consistent copy of the whole database to `path`, which must
+    /// not exist yet. The oracle keeps running; writes wait for the copy.
+    pub fn backup_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        self.conn()
+            .execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+            .with_context(|| format!("Failed to back up the database to {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn record_from_row(row: &Row<'_>) -> rusqlite::Result<PostedBlockRecord> {
+    let posted_at: String = row.get(7)?;
+    Ok(PostedBlockRecord {
+        height: row.get::<_, i64>(0)? as u64,
+        block_hash: b256_column(row, 1)?,
+        tx_merkle_root: b256_column(row, 2)?,
+        output_merkle_root: b256_column(row, 3)?,
+        unichain_tx_hash: b256_column(row, 4)?,
+        unichain_block: row.get::<_, Option<i64>>(5)?.map(|b| b as u64),
+        gas_used: row.get::<_, i64>(6)? as u64,
+        posted_at: DateTime::parse_from_rfc3339(&posted_at)
+            .map_err(|e| FromSqlConversionFailure(7, Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
+
+fn indexed_output_from_row(row: &Row<'_>) -> rusqlite::Result<IndexedOutput> {
+    Ok(IndexedOutput {
+        block_height: row.get::<_, i64>(0)? as u64,
+        leaf_index: row.get::<_, i64>(1)? as u64,
+        output: MoneroOutput {
+            tx_hash: b256_column(row, 2)?,
+            output_index: row.get::<_, i64>(3)? as u64,
+            ecdh_amount: b256_column(row, 4)?,
+            output_pub_key: b256_column(row, 5)?,
+            commitment: b256_column(row, 6)?,
+        },
+        amount: row.get::<_, Option<i64>>(7)?.map(|amount| amount as u64),
+    })
+}
+
+fn deposit_from_row(row: &Row<'_>) -> rusqlite::Result<Deposit> {
+    Ok(Deposit {
+        block_height: row.get::<_, i64>(0)? as u64,
+        tx_hash: b256_column(row, 1)?,
+        output_index: row.get::<_, i64>(2)? as u64,
+        amount: row.get::<_, i64>(3)? as u64,
+        output_pub_key: b256_column(row, 4)?,
+        subaddress: SubaddressIndex::new(row.get(5)?, row.get(6)?),
+        payment_id: row
+            .get::<_, Option<String>>(7)?
+            .map(|payment_id| {
+                B64::from_str(&payment_id)
+                    .map_err(|e| FromSqlConversionFailure(7, Type::Text, Box::new(e)))
+            })
+            .transpose()?,
+    })
+}
+
+fn pending_deposit_from_row(row: &Row<'_>) -> rusqlite::Result<PendingDeposit> {
+    let first_seen_at: String = row.get(6)?;
+    Ok(PendingDeposit {
+        tx_hash: b256_column(row, 0)?,
+        output_index: row.get::<_, i64>(1)? as u64,
+        amount: row.get::<_, i64>(2)? as u64,
+        subaddress: SubaddressIndex::new(row.get(3)?, row.get(4)?),
+        block_height: row.get::<_, Option<i64>>(5)?.map(|h| h as u64),
+        first_seen_at: DateTime::parse_from_rfc3339(&first_seen_at)
+            .map_err(|e| FromSqlConversionFailure(6, Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
+
+fn withdrawal_from_row(row: &Row<'_>) -> rusqlite::Result<Withdrawal> {
+    let status: String = row.get(7)?;
+    Ok(Withdrawal {
+        burn_id: row.get::<_, i64>(0)? as u64,
+        user: address_column(row, 1)?,
+        lp: address_column(row, 2)?,
+        amount: row.get::<_, i64>(3)? as u64,
+        xmr_address: row.get(4)?,
+        unichain_tx_hash: b256_column(row, 5)?,
+        unichain_block: row.get::<_, i64>(6)? as u64,
+        status: status
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(7, Type::Text, e.into()))?,
+        error: row.get(8)?,
+        attempts: row.get(9)?,
+        xmr_tx_hash: row
+            .get::<_, Option<String>>(10)?
+            .map(|hash| {
+                B256::from_str(&hash)
+                    .map_err(|e| FromSqlConversionFailure(10, Type::Text, Box::new(e)))
+            })
+            .transpose()?,
+        xmr_tx_key: row.get(11)?,
+        xmr_fee: row.get::<_, Option<i64>>(12)?.map(|fee| fee as u64),
+        xmr_amount: row.get::<_, Option<i64>>(13)?.map(|amount| amount as u64),
+    })
+}
+
+fn bridge_output_from_row(row: &Row<'_>) -> rusqlite::Result<BridgeOutput> {
+    let key_image = b256_column(row, 0)?;
+    let spent = match row.get::<_, Option<i64>>(3)? {
+        Some(height) => Some(KeyImageSpend {
+            key_image,
+            block_height: height as u64,
+            tx_hash: b256_column(row, 4)?,
+        }),
+        None => None,
+    };
+    Ok(BridgeOutput {
+        key_image,
+        tx_hash: b256_column(row, 1)?,
+        amount: row.get::<_, i64>(2)? as u64,
+        spent,
+    })
+}
+
+fn attestation_from_row(row: &Row<'_>) -> rusqlite::Result<Attestation> {
+    let signature: String = row.get(5)?;
+    Ok(Attestation {
+        height: row.get::<_, i64>(0)? as u64,
+        block_hash: b256_column(row, 1)?,
+        tx_merkle_root: b256_column(row, 2)?,
+        output_merkle_root: b256_column(row, 3)?,
+        signer: address_column(row, 4)?,
+        signature: Bytes::from_str(&signature)
+            .map_err(|e| FromSqlConversionFailure(5, Type::Text, Box::new(e)))?,
+    })
+}
+
+fn fee_charge_from_row(row: &Row<'_>) -> rusqlite::Result<FeeCharge> {
+    let kind: String = row.get(0)?;
+    Ok(FeeCharge {
+        kind: kind
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(0, Type::Text, e.into()))?,
+        reference: row.get(1)?,
+        amount: row.get::<_, i64>(2)? as u64,
+        fee: row.get::<_, i64>(3)? as u64,
+        usd_per_xmr: row.get(4)?,
+        recorded_at: datetime_column(row, 5)?,
+    })
+}
+
+fn operation_from_row(row: &Row<'_>) -> rusqlite::Result<Operation> {
+    let kind: String = row.get(1)?;
+    let status: String = row.get(2)?;
+    Ok(Operation {
+        key: row.get(0)?,
+        kind: kind
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(1, Type::Text, e.into()))?,
+        status: status
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(2, Type::Text, e.into()))?,
+        tx_hash: row
+            .get::<_, Option<String>>(3)?
+            .map(|hash| {
+                B256::from_str(&hash)
+                    .map_err(|e| FromSqlConversionFailure(3, Type::Text, Box::new(e)))
+            })
+            .transpose()?,
+        sent: Vec::new(),
+        error: row.get(4)?,
+        created_at: datetime_column(row, 5)?,
+        updated_at: datetime_column(row, 6)?,
+    })
+}
+
+fn signing_round_from_row(row: &Row<'_>) -> rusqlite::Result<SigningRound> {
+    let role: String = row.get(2)?;
+    let status: String = row.get(3)?;
+    let signers: String = row.get(4)?;
+    Ok(SigningRound {
+        id: row.get(0)?,
+        key: row.get(1)?,
+        role: role
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(2, Type::Text, e.into()))?,
+        status: status
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(3, Type::Text, e.into()))?,
+        signers: signers
+            .split(',')
+            .filter(|signer| !signer.is_empty())
+            .map(str::to_string)
+            .collect(),
+        tx_data_hex: row.get(5)?,
+        tx_hash: row
+            .get::<_, Option<String>>(6)?
+            .map(|hash| {
+                B256::from_str(&hash)
+                    .map_err(|e| FromSqlConversionFailure(6, Type::Text, Box::new(e)))
+            })
+            .transpose()?,
+        error: row.get(7)?,
+        created_at: datetime_column(row, 8)?,
+        updated_at: datetime_column(row, 9)?,
+    })
+}
+
+fn tx_cost_from_row(row: &Row<'_>) -> rusqlite::Result<TxCost> {
+    let kind: String = row.get(1)?;
+    let gas_price: String = row.get(3)?;
+    Ok(TxCost {
+        tx_hash: b256_column(row, 0)?,
+        kind: kind
+            .parse()
+            .map_err(|e: anyhow::Error| FromSqlConversionFailure(1, Type::Text, e.into()))?,
+        gas_used: row.get::<_, i64>(2)? as u64,
+        gas_price: gas_price
+            .parse()
+            .map_err(|e| FromSqlConversionFailure(3, Type::Text, Box::new(e)))?,
+        xmr_fee: row.get::<_, i64>(4)? as u64,
+        recorded_at: datetime_column(row, 5)?,
+    })
+}
+
+/// Fill in the transactions broadcast for `operation`
+fn with_sent_transactions(conn: &Connection, mut operation: Operation) -> Result<Operation> {
+    let mut stmt = conn.prepare(
+        "SELECT tx_hash FROM operation_transactions WHERE key = ?1 ORDER BY sent_at, rowid",
+    )?;
+    operation.sent = stmt
+        .query_map(params![operation.key], |row| b256_column(row, 0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(operation)
+}
+
+fn datetime_column(row: &Row<'_>, idx: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(idx)?;
+    Ok(DateTime::parse_from_rfc3339(&value)
+        .map_err(|e| FromSqlConversionFailure(idx, Type::Text, Box::new(e)))?
+        .with_timezone(&Utc))
+}
+
+fn b256_column(row: &Row<'_>, idx: usize) -> rusqlite::Result<B256> {
+    let value: String = row.get(idx)?;
+    B256::from_str(&value).map_err(|e| FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn address_column(row: &Row<'_>, idx: usize) -> rusqlite::Result<Address> {
+    let value: String = row.get(idx)?;
+    Address::from_str(&value).map_err(|e| FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        costs::{CostKind, Month},
+        merkle::{output_tree, MerkleHasher},
+    };
+
+    fn record(height: u64, byte: u8) -> PostedBlockRecord {
+        PostedBlockRecord {
+            height,
+            block_hash: B256::from([byte; 32]),
+            tx_merkle_root: B256::from([byte.wrapping_add(1); 32]),
+            output_merkle_root: B256::from([byte.wrapping_add(2); 32]),
+            unichain_tx_hash: B256::from([byte.wrapping_add(3); 32]),
+            unichain_block: Some(1_000 + height),
+            gas_used: 85_000,
+            posted_at: DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_record_and_read_back() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.checkpoint().unwrap(), None);
+
+        db.record_posted_block(&record(100, 0x10)).unwrap();
+        db.record_posted_block(&record(101, 0x20)).unwrap();
+
+        assert_eq!(db.checkpoint().unwrap(), Some(101));
+        assert_eq!(db.posted_block(100).unwrap(), Some(record(100, 0x10)));
+        assert_eq!(db.posted_block(102).unwrap(), None);
+    }
+
+    #[test]
+    fn test_orphaned_blocks_are_replaced() {
+        let db = Database::open_in_memory().unwrap();
+        for height in 100..=103 {
+            db.record_posted_block(&record(height, height as u8))
+                .unwrap();
+        }
+
+        assert_eq!(db.mark_orphaned(102).unwrap(), 2);
+        assert_eq!(db.checkpoint().unwrap(), Some(101));
+
+        db.record_posted_block(&record(102, 0xee)).unwrap();
+        assert_eq!(db.posted_block(102).unwrap(), Some(record(102, 0xee)));
+
+        let heights: Vec<u64> = db
+            .recent_blocks(2)
+            .unwrap()
+            .iter()
+            .map(|r| r.height)
+            .collect();
+        assert_eq!(heights, vec![101, 102]);
+    }
+
+    #[test]
+    fn test_postings_rechecked_once_settled() {
+        let db = Database::open_in_memory().unwrap();
+        for height in 100..=102 {
+            db.record_posted_block(&record(height, height as u8))
+                .unwrap();
+        }
+        let due = |settled| -> Vec<u64> {
+            db.unconfirmed_postings(settled, 10)
+                .unwrap()
+                .iter()
+                .map(|r| r.height)
+                .collect()
+        };
+        // Mined in Unichain blocks 1100 to 1102
+        assert_eq!(due(1_101), vec![100, 101]);
+
+        db.confirm_posting(record(100, 100).unichain_tx_hash)
+            .unwrap();
+        db.move_posting(record(101, 101).unichain_tx_hash, 1_105)
+            .unwrap();
+        assert_eq!(due(1_102), vec![102]);
+
+        // A dropped posting leaves the height to its replacement
+        db.drop_posting(record(102, 102).unichain_tx_hash).unwrap();
+        assert_eq!(db.posted_block(102).unwrap(), None);
+        db.record_posted_block(&record(102, 0xee)).unwrap();
+        assert_eq!(db.posted_block(102).unwrap(), Some(record(102, 0xee)));
+        assert_eq!(due(1_105), vec![101, 102]);
+    }
+
+    #[test]
+    fn test_export_and_import_posted_chain() {
+        let source = Database::open_in_memory().unwrap();
+        source.record_posted_block(&record(100, 0x10)).unwrap();
+        source
+            .record_cumulative_difficulty(100, B256::from([0x10; 32]), 5_000)
+            .unwrap();
+        source.record_posted_block(&record(101, 0x20)).unwrap();
+        let chain = source.posted_chain().unwrap();
+        assert_eq!(
+            chain,
+            vec![(record(100, 0x10), Some(5_000)), (record(101, 0x20), None)]
+        );
+
+        let target = Database::open_in_memory().unwrap();
+        target.record_posted_block(&record(100, 0x10)).unwrap();
+        assert_eq!(target.import_posted_blocks(&chain).unwrap(), 1);
+        assert_eq!(target.checkpoint().unwrap(), Some(101));
+        assert_eq!(
+            target.latest_cumulative_difficulty().unwrap(),
+            Some((100, 5_000))
+        );
+
+        // A conflicting block rolls the whole import back
+        let conflicting = Database::open_in_memory().unwrap();
+        conflicting.record_posted_block(&record(101, 0x30)).unwrap();
+        assert!(conflicting.import_posted_blocks(&chain).is_err());
+        assert_eq!(conflicting.posted_block(100).unwrap(), None);
+    }
+
+    #[test]
+    fn test_open_checked_copies_before_migrating() {
+        let path =
+            std::env::temp_dir().join(format!("monero-oracle-migrate-{}.db", std::process::id()));
+        let backup = PathBuf::from(format!("{}.v0.bak", path.display()));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&backup);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE sync_state (name TEXT PRIMARY KEY, value INTEGER NOT NULL);",
+            )
+            .unwrap();
+
+        assert!(Database::open_checked(&path, false).is_err());
+        assert!(!backup.exists());
+        let db = Database::open_checked(&path, true).unwrap();
+        assert!(db.schema_status().unwrap().is_current());
+        assert_eq!(migrations::status_at(&backup).unwrap().version, 0);
+        drop(db);
+        // Current now: nothing to refuse
+        Database::open_checked(&path, false).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&backup).unwrap();
+    }
+
+    #[test]
+    fn test_backup_opens_as_a_database() {
+        let db = Database::open_in_memory().unwrap();
+        db.record_posted_block(&record(100, 0x10)).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("monero-oracle-backup-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        db.backup_to(&path).unwrap();
+        assert!(db.backup_to(&path).is_err());
+        let restored = Database::open(&path).unwrap();
+        assert_eq!(restored.posted_block(100).unwrap(), Some(record(100, 0x10)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_cumulative_difficulty_follows_reorgs() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.latest_cumulative_difficulty().unwrap(), None);
+
+        let work = u64::MAX as u128 * 1000;
+        db.record_cumulative_difficulty(100, B256::repeat_byte(1), work)
+            .unwrap();
+        db.record_cumulative_difficulty(101, B256::repeat_byte(2), work + 5)
+            .unwrap();
+        assert_eq!(
+            db.latest_cumulative_difficulty().unwrap(),
+            Some((101, work + 5))
+        );
+
+        db.mark_orphaned(101).unwrap();
+        assert_eq!(
+            db.latest_cumulative_difficulty().unwrap(),
+            Some((100, work))
+        );
+    }
+
+    #[test]
+    fn test_deposits_follow_reorgs() {
+        let db = Database::open_in_memory().unwrap();
+        let deposit = Deposit {
+            block_height: 100,
+            tx_hash: B256::from([0xab; 32]),
+            output_index: 1,
+            amount: 2_000_000_000_000,
+            output_pub_key: B256::from([0xcd; 32]),
+            subaddress: SubaddressIndex::new(0, 3),
+            payment_id: None,
+        };
+
+        assert!(db.record_deposit(&deposit).unwrap());
+        assert!(!db.record_deposit(&deposit).unwrap());
+        assert_eq!(db.deposits_in_block(100).unwrap(), vec![deposit.clone()]);
+
+        db.mark_orphaned(100).unwrap();
+        assert!(db.deposits_in_block(100).unwrap().is_empty());
+
+        // Re-mined in a later block
+        let remined = Deposit {
+            block_height: 101,
+            ..deposit
+        };
+        assert!(!db.record_deposit(&remined).unwrap());
+        assert_eq!(db.deposits_in_block(101).unwrap(), vec![remined.clone()]);
+        assert_eq!(
+            db.deposits_for_subaddress(SubaddressIndex::new(0, 3))
+                .unwrap(),
+            vec![remined]
+        );
+
+        // Another output with the same one-time key is never stored
+        assert_eq!(
+            db.deposit_with_output_key(deposit.output_pub_key).unwrap(),
+            Some((deposit.tx_hash, 1))
+        );
+        assert!(db
+            .record_deposit(&Deposit {
+                tx_hash: B256::from([0xef; 32]),
+                ..deposit.clone()
+            })
+            .is_err());
+        assert_eq!(db.deposit_with_output_key(B256::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_safe_proposals() {
+        let db = Database::open_in_memory().unwrap();
+        let proposal = |height: u64, byte: u8, nonce: u64| SafeProposal {
+            height,
+            block_hash: B256::from([byte; 32]),
+            safe_tx_hash: B256::from([byte.wrapping_add(1); 32]),
+            nonce,
+        };
+        db.record_safe_proposal(&proposal(101, 0x20, 4)).unwrap();
+        db.record_safe_proposal(&proposal(100, 0x10, 3)).unwrap();
+        assert_eq!(
+            db.safe_proposals().unwrap(),
+            [proposal(100, 0x10, 3), proposal(101, 0x20, 4)]
+        );
+
+        // A re-proposal replaces the old one
+        db.record_safe_proposal(&proposal(101, 0x30, 5)).unwrap();
+        db.delete_safe_proposal(100).unwrap();
+        assert_eq!(db.safe_proposals().unwrap(), [proposal(101, 0x30, 5)]);
+
+        db.mark_orphaned(101).unwrap();
+        assert!(db.safe_proposals().unwrap().is_empty());
+
+        assert_eq!(db.safe_nonce().unwrap(), None);
+        db.set_safe_nonce(6).unwrap();
+        assert_eq!(db.safe_nonce().unwrap(), Some(6));
+    }
+
+    #[test]
+    fn test_output_index() {
+        let db = Database::open_in_memory().unwrap();
+        let output = |tx: u8, index: u64| MoneroOutput {
+            tx_hash: B256::from([tx; 32]),
+            output_index: index,
+            ecdh_amount: B256::from([tx ^ 0x10; 32]),
+            output_pub_key: B256::from([tx.wrapping_add(index as u8 + 0x40); 32]),
+            commitment: B256::from([tx ^ 0x20; 32]),
+        };
+        let outputs = vec![output(1, 0), output(2, 0), output(2, 1)];
+        let block_hash = B256::from([0xbb; 32]);
+
+        assert_eq!(db.indexed_block(100).unwrap(), None);
+        db.index_block_outputs(100, block_hash, &outputs).unwrap();
+        // Indexing a block again replaces it rather than duplicating rows
+        db.index_block_outputs(100, block_hash, &outputs).unwrap();
+
+        assert_eq!(
+            db.indexed_block(100).unwrap(),
+            Some(IndexedBlock {
+                height: 100,
+                block_hash,
+                outputs: outputs.clone(),
+            })
+        );
+        let second = IndexedOutput {
+            block_height: 100,
+            leaf_index: 2,
+            output: output(2, 1),
+            amount: None,
+        };
+        assert_eq!(
+            db.indexed_output(B256::from([2; 32]), 1).unwrap(),
+            Some(second.clone())
+        );
+
+        let tree = output_tree(&outputs, MerkleHasher::Sha256);
+        assert_eq!(db.merkle_tree(100, TreeKind::Output).unwrap(), None);
+        db.store_merkle_tree(100, block_hash, TreeKind::Output, &tree)
+            .unwrap();
+        assert_eq!(
+            db.merkle_tree(100, TreeKind::Output).unwrap(),
+            Some((block_hash, tree))
+        );
+        assert_eq!(db.merkle_tree(100, TreeKind::Tx).unwrap(), None);
+        assert_eq!(db.indexed_tx_outputs(B256::from([2; 32])).unwrap().len(), 2);
+        assert_eq!(
+            db.output_by_pub_key(output(2, 1).output_pub_key).unwrap(),
+            Some(second.clone())
+        );
+
+        // Outputs found by deposit scanning carry their decrypted amount
+        db.record_deposit(&Deposit {
+            block_height: 100,
+            tx_hash: B256::from([2; 32]),
+            output_index: 1,
+            amount: 2_500_000_000_000,
+            output_pub_key: second.output.output_pub_key,
+            subaddress: SubaddressIndex::default(),
+            payment_id: None,
+        })
+        .unwrap();
+        assert_eq!(
+            db.indexed_output(B256::from([2; 32]), 1)
+                .unwrap()
+                .unwrap()
+                .amount,
+            Some(2_500_000_000_000)
+        );
+        assert_eq!(
+            db.indexed_tx_outputs(B256::from([2; 32]))
+                .unwrap()
+                .iter()
+                .map(|indexed| indexed.amount)
+                .collect::<Vec<_>>(),
+            [None, Some(2_500_000_000_000)]
+        );
+
+        // A reorg drops the block until it is indexed again
+        db.mark_orphaned(100).unwrap();
+        assert_eq!(db.indexed_block(100).unwrap(), None);
+        assert_eq!(db.indexed_output(B256::from([2; 32]), 1).unwrap(), None);
+        assert_eq!(db.merkle_tree(100, TreeKind::Output).unwrap(), None);
+
+        // A block is indexed even when it has no outputs to store
+        db.index_block_outputs(101, block_hash, &[]).unwrap();
+        assert_eq!(db.indexed_block(101).unwrap().unwrap().outputs, vec![]);
+    }
+
+    #[test]
+    fn test_pending_deposits_until_posted() {
+        let db = Database::open_in_memory().unwrap();
+        let user = SubaddressIndex::new(0, 4);
+        let deposit = Deposit {
+            block_height: 0,
+            tx_hash: B256::repeat_byte(0x31),
+            output_index: 0,
+            amount: 1_500_000_000_000,
+            output_pub_key: B256::repeat_byte(0x32),
+            subaddress: user,
+            payment_id: None,
+        };
+        let dropped = Deposit {
+            tx_hash: B256::repeat_byte(0x33),
+            ..deposit.clone()
+        };
+
+        assert!(db.record_pending_deposit(&deposit).unwrap());
+        assert!(!db.record_pending_deposit(&deposit).unwrap());
+        assert!(db.record_pending_deposit(&dropped).unwrap());
+
+        let pending = db.pending_deposits_for_subaddress(user).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].amount, 1_500_000_000_000);
+        assert_eq!(pending[0].block_height, None);
+        assert!(db
+            .pending_deposits_for_subaddress(SubaddressIndex::new(0, 5))
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(db.remove_pending_deposits(dropped.tx_hash).unwrap(), 1);
+        db.set_pending_deposit_mined(deposit.tx_hash, 200).unwrap();
+        assert_eq!(db.pending_deposits().unwrap()[0].block_height, Some(200));
+
+        // Posting the block turns it into a regular deposit
+        let mined = Deposit {
+            block_height: 200,
+            ..deposit.clone()
+        };
+        db.record_deposit(&mined).unwrap();
+        assert!(db.pending_deposits().unwrap().is_empty());
+        assert!(!db.record_pending_deposit(&deposit).unwrap());
+        assert!(db.pending_deposits().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_deposits_are_minted_once() {
+        let db = Database::open_in_memory().unwrap();
+        let user = SubaddressIndex::new(0, 5);
+        let deposit = Deposit {
+            block_height: 200,
+            tx_hash: B256::from([0x11; 32]),
+            output_index: 0,
+            amount: 500_000_000_000,
+            output_pub_key: B256::from([0x22; 32]),
+            subaddress: user,
+            payment_id: None,
+        };
+        db.record_deposit(&deposit).unwrap();
+
+        // Nothing to mint until the user registers a recipient
+        assert!(db.pending_mints().unwrap().is_empty());
+
+        let recipient = Address::repeat_byte(0x42);
+        db.register_recipient(user, recipient).unwrap();
+        assert_eq!(db.recipient(user).unwrap(), Some(recipient));
+        assert_eq!(
+            db.pending_mints().unwrap(),
+            vec![PendingMint {
+                deposit: deposit.clone(),
+                recipient
+            }]
+        );
+
+        let mint_tx = B256::from([0x33; 32]);
+        assert!(db.record_mint(&deposit, recipient, mint_tx).unwrap());
+        assert!(!db.record_mint(&deposit, recipient, mint_tx).unwrap());
+        assert!(db.pending_mints().unwrap().is_empty());
+
+        // A reorg that re-mines the deposit must not queue a second mint
+        db.mark_orphaned(200).unwrap();
+        db.record_deposit(&Deposit {
+            block_height: 201,
+            ..deposit
+        })
+        .unwrap();
+        assert!(db.pending_mints().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_held_deposits_wait_for_review() {
+        let db = Database::open_in_memory().unwrap();
+        let user = SubaddressIndex::new(0, 5);
+        let recipient = Address::repeat_byte(0x42);
+        db.register_recipient(user, recipient).unwrap();
+        let deposit = |byte: u8| Deposit {
+            block_height: 200,
+            tx_hash: B256::from([byte; 32]),
+            output_index: 0,
+            amount: 90_000_000_000_000,
+            output_pub_key: B256::from([byte.wrapping_add(1); 32]),
+            subaddress: user,
+            payment_id: None,
+        };
+        let (large, dust) = (deposit(0x11), deposit(0x22));
+        db.record_deposit(&large).unwrap();
+        db.record_deposit(&dust).unwrap();
+
+        assert!(db.hold_deposit(&large, "above MAX_DEPOSIT_XMR").unwrap());
+        assert!(db.hold_deposit(&dust, "below MIN_DEPOSIT_XMR").unwrap());
+        assert!(!db.hold_deposit(&dust, "below MIN_DEPOSIT_XMR").unwrap());
+        assert!(db.pending_mints().unwrap().is_empty());
+        let held = db.held_deposits().unwrap();
+        assert_eq!(held.len(), 2);
+        assert_eq!(held[0].deposit, large);
+        assert_eq!(held[0].reason, "above MAX_DEPOSIT_XMR");
+
+        db.review_deposit(large.tx_hash, 0, ReviewStatus::Approved)
+            .unwrap();
+        db.review_deposit(dust.tx_hash, 0, ReviewStatus::Rejected)
+            .unwrap();
+        // Reviews are final
+        assert!(db
+            .review_deposit(dust.tx_hash, 0, ReviewStatus::Approved)
+            .is_err());
+        assert!(db.held_deposits().unwrap().is_empty());
+        assert_eq!(
+            db.deposit_review(large.tx_hash, 0).unwrap(),
+            Some(ReviewStatus::Approved)
+        );
+        assert_eq!(
+            db.pending_mints().unwrap(),
+            [PendingMint {
+                deposit: large.clone(),
+                recipient
+            }]
+        );
+
+        let an_hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(db.minted_since(recipient, an_hour_ago).unwrap(), 0);
+        db.record_mint(&large, recipient, B256::from([0x33; 32]))
+            .unwrap();
+        assert_eq!(
+            db.minted_since(recipient, an_hour_ago).unwrap(),
+            large.amount
+        );
+        assert_eq!(db.minted_since(recipient, Utc::now()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_fee_charge_keeps_first_quote() {
+        let db = Database::open_in_memory().unwrap();
+        let charge = FeeCharge {
+            kind: FeeKind::Withdrawal,
+            reference: "12".to_string(),
+            amount: 1_000_000_000_000,
+            fee: 7_500_000_000,
+            usd_per_xmr: Some(200.0),
+            recorded_at: DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        };
+        assert_eq!(db.fee_charge(FeeKind::Withdrawal, "12").unwrap(), None);
+        db.record_fee_charge(&charge).unwrap();
+        // A retry quoted at a new rate doesn't replace it
+        db.record_fee_charge(&FeeCharge {
+            fee: 6_000_000_000,
+            usd_per_xmr: Some(250.0),
+            ..charge.clone()
+        })
+        .unwrap();
+        assert_eq!(
+            db.fee_charge(FeeKind::Withdrawal, "12").unwrap(),
+            Some(charge)
+        );
+        assert_eq!(db.fee_charge(FeeKind::Mint, "12").unwrap(), None);
+    }
+
+    #[test]
+    fn test_unswept_fees() {
+        let db = Database::open_in_memory().unwrap();
+        let charge = |kind, reference: &str, fee| FeeCharge {
+            kind,
+            reference: reference.to_string(),
+            amount: 1_000_000_000_000,
+            fee,
+            usd_per_xmr: None,
+            recorded_at: Utc::now(),
+        };
+        let minted = charge(FeeKind::Mint, "0xab:0", 2_500_000_000);
+        let paid = charge(FeeKind::Withdrawal, "3", 4_000_000_000);
+        db.record_fee_charge(&minted).unwrap();
+        db.record_fee_charge(&paid).unwrap();
+        db.record_fee_charge(&charge(FeeKind::Withdrawal, "4", 9_000_000_000))
+            .unwrap();
+
+        // Only fees on operations that went through count
+        db.collect_fee(FeeKind::Mint, "0xab:0").unwrap();
+        db.collect_fee(FeeKind::Withdrawal, "3").unwrap();
+        db.collect_fee(FeeKind::Withdrawal, "3").unwrap();
+        assert_eq!(db.unswept_fees().unwrap(), 6_500_000_000);
+        let (from, to) = (
+            Utc::now() - chrono::Duration::hours(1),
+            Utc::now() + chrono::Duration::hours(1),
+        );
+        assert_eq!(db.collected_fees(from, to).unwrap(), [minted, paid]);
+
+        let sweep = FeeSweep {
+            tx_hash: B256::repeat_byte(0x77),
+            amount: 6_000_000_000,
+            network_fee: 30_000_000,
+            swept_at: Utc::now(),
+        };
+        db.record_fee_sweep(&sweep).unwrap();
+        assert_eq!(db.unswept_fees().unwrap(), 500_000_000);
+        assert_eq!(
+            db.fee_sweeps(from, to).unwrap(),
+            std::slice::from_ref(&sweep)
+        );
+        db.remove_fee_sweep(sweep.tx_hash).unwrap();
+        assert_eq!(db.unswept_fees().unwrap(), 6_500_000_000);
+    }
+
+    #[test]
+    fn test_multisig_rounds() {
+        let db = Database::open_in_memory().unwrap();
+        let first = db
+            .begin_multisig_round("payout:1,2", RoundRole::Coordinator, "aa")
+            .unwrap();
+        db.record_multisig_signature(first, "http://cosigner-1:8080", "bb")
+            .unwrap();
+        db.record_multisig_signature(first, "http://cosigner-2:8080", "cc")
+            .unwrap();
+        let second = db
+            .begin_multisig_round("payout:3", RoundRole::Coordinator, "dd")
+            .unwrap();
+        db.close_multisig_round(first, RoundStatus::Signed, Some(B256::repeat_byte(1)), None)
+            .unwrap();
+
+        // Only the round still collecting signatures is interrupted
+        assert_eq!(db.fail_open_multisig_rounds("Interrupted").unwrap(), 1);
+        let rounds = db.multisig_rounds(10).unwrap();
+        assert_eq!(rounds[0].id, second);
+        assert_eq!(rounds[0].status, RoundStatus::Failed);
+        assert_eq!(rounds[0].error.as_deref(), Some("Interrupted"));
+        assert_eq!(rounds[1].status, RoundStatus::Signed);
+        assert_eq!(
+            rounds[1].signers,
+            ["http://cosigner-1:8080", "http://cosigner-2:8080"]
+        );
+        assert_eq!(rounds[1].tx_data_hex, "cc");
+        assert_eq!(rounds[1].tx_hash, Some(B256::repeat_byte(1)));
+    }
+
+    #[test]
+    fn test_payment_id_deposits_mint_to_payment_id_recipient() {
+        let db = Database::open_in_memory().unwrap();
+        let payment_id = B64::repeat_byte(0x5a);
+        let deposit = Deposit {
+            block_height: 300,
+            tx_hash: B256::from([0x44; 32]),
+            output_index: 1,
+            amount: 700_000_000_000,
+            output_pub_key: B256::from([0x55; 32]),
+            subaddress: SubaddressIndex::default(),
+            payment_id: Some(payment_id),
+        };
+        db.record_deposit(&deposit).unwrap();
+        assert_eq!(
+            db.deposits_for_payment_id(payment_id).unwrap(),
+            db.deposits_in_block(300).unwrap()
+        );
+        assert_eq!(db.deposits_in_block(300).unwrap()[0], deposit);
+
+        // The primary address's recipient doesn't claim payment ID deposits
+        db.register_recipient(SubaddressIndex::default(), Address::repeat_byte(0x01))
+            .unwrap();
+        assert!(db.pending_mints().unwrap().is_empty());
+
+        let recipient = Address::repeat_byte(0x42);
+        db.register_payment_id_recipient(payment_id, recipient)
+            .unwrap();
+        assert_eq!(
+            db.payment_id_recipient(payment_id).unwrap(),
+            Some(recipient)
+        );
+        assert_eq!(
+            db.pending_mints().unwrap(),
+            [PendingMint { deposit, recipient }]
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_queue() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.unichain_cursor().unwrap(), None);
+
+        let withdrawal = Withdrawal {
+            burn_id: 3,
+            user: Address::repeat_byte(0x01),
+            lp: Address::repeat_byte(0x02),
+            amount: 1_500_000_000_000,
+            xmr_address: "4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx".to_string(),
+            unichain_tx_hash: B256::repeat_byte(0x03),
+            unichain_block: 900,
+            status: WithdrawalStatus::Queued,
+            error: None,
+            attempts: 0,
+            xmr_tx_hash: None,
+            xmr_tx_key: None,
+            xmr_fee: None,
+            xmr_amount: None,
+        };
+        assert!(db.queue_withdrawal(&withdrawal).unwrap());
+        assert!(!db.queue_withdrawal(&withdrawal).unwrap());
+        db.set_unichain_cursor(900).unwrap();
+
+        assert_eq!(db.withdrawal(3).unwrap(), Some(withdrawal.clone()));
+        assert_eq!(
+            db.withdrawals_with_status(WithdrawalStatus::Queued)
+                .unwrap(),
+            vec![withdrawal]
+        );
+        assert!(db
+            .withdrawals_with_status(WithdrawalStatus::Rejected)
+            .unwrap()
+            .is_empty());
+        assert_eq!(db.unichain_cursor().unwrap(), Some(900));
+
+        let transfer = SignedTransfer {
+            tx_hash: B256::repeat_byte(0x04),
+            tx_key: "ab".repeat(32),
+            amount: 1_500_000_000_000,
+            fee: 30_000_000,
+            tx_metadata: "0200".to_string(),
+        };
+        db.record_withdrawals_signed(&[(3, 1_500_000_000_000)], &transfer)
+            .unwrap();
+        assert!(db
+            .record_withdrawals_signed(&[(3, 1_500_000_000_000)], &transfer)
+            .is_err());
+        assert_eq!(
+            db.withdrawal_tx_metadata(3).unwrap().as_deref(),
+            Some("0200")
+        );
+
+        assert_eq!(
+            db.advance_withdrawal(3, WithdrawalStatus::Signed).unwrap(),
+            WithdrawalStatus::Broadcast
+        );
+        // A stage completes once
+        assert!(db.advance_withdrawal(3, WithdrawalStatus::Signed).is_err());
+        let sent = db.withdrawal(3).unwrap().unwrap();
+        assert_eq!(sent.status, WithdrawalStatus::Broadcast);
+        assert_eq!(sent.xmr_tx_hash, Some(transfer.tx_hash));
+        assert_eq!(sent.xmr_tx_key, Some(transfer.tx_key));
+        assert_eq!(sent.xmr_fee, Some(30_000_000));
+        assert_eq!(sent.xmr_amount, Some(1_500_000_000_000));
+
+        // A failing step backs off, then gives up at the attempt limit
+        assert_eq!(
+            db.record_withdrawal_failure(3, "tx not found", 2).unwrap(),
+            WithdrawalStatus::Broadcast
+        );
+        assert!(db
+            .withdrawals_due(WithdrawalStatus::Broadcast)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            db.record_withdrawal_failure(3, "tx not found", 2).unwrap(),
+            WithdrawalStatus::Failed
+        );
+        let failed = db.withdrawal(3).unwrap().unwrap();
+        assert_eq!(failed.attempts, 2);
+        assert_eq!(failed.error.as_deref(), Some("tx not found"));
+
+        // Requeued with its signed transfer, never to be signed again
+        assert_eq!(db.requeue_withdrawal(3).unwrap(), WithdrawalStatus::Signed);
+        let requeued = db.withdrawal(3).unwrap().unwrap();
+        assert_eq!(requeued.attempts, 0);
+        assert_eq!(requeued.error, None);
+        assert_eq!(requeued.xmr_tx_hash, Some(transfer.tx_hash));
+        assert!(db.requeue_withdrawal(3).is_err());
+    }
+
+    #[test]
+    fn test_batched_payout() {
+        let db = Database::open_in_memory().unwrap();
+        let withdrawal = |burn_id| Withdrawal {
+            burn_id,
+            user: Address::repeat_byte(0x01),
+            lp: Address::repeat_byte(0x02),
+            amount: 1_000_000_000_000,
+            xmr_address: "4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx".to_string(),
+            unichain_tx_hash: B256::repeat_byte(burn_id as u8),
+            unichain_block: 900,
+            status: WithdrawalStatus::Queued,
+            error: None,
+            attempts: 0,
+            xmr_tx_hash: None,
+            xmr_tx_key: None,
+            xmr_fee: None,
+            xmr_amount: None,
+        };
+        for burn_id in 1..=4 {
+            db.queue_withdrawal(&withdrawal(burn_id)).unwrap();
+        }
+
+        let transfer = SignedTransfer {
+            tx_hash: B256::repeat_byte(0x04),
+            tx_key: "ab".repeat(32),
+            amount: 2_999_000_000_000,
+            fee: 100_000_001,
+            tx_metadata: "0200".to_string(),
+        };
+        let payouts = [
+            (1, 999_000_000_000),
+            (2, 1_000_000_000_000),
+            (3, 1_000_000_000_000),
+        ];
+        db.record_withdrawals_signed(&payouts, &transfer).unwrap();
+
+        let paid = db.withdrawals_in_payout(transfer.tx_hash).unwrap();
+        assert_eq!(
+            paid.iter()
+                .map(|w| (w.burn_id, w.xmr_amount.unwrap()))
+                .collect::<Vec<_>>(),
+            payouts
+        );
+        assert!(paid.iter().all(|w| w.status == WithdrawalStatus::Signed));
+        assert_eq!(
+            paid.iter().map(|w| w.xmr_fee.unwrap()).collect::<Vec<_>>(),
+            [33_333_335, 33_333_333, 33_333_333]
+        );
+        assert_eq!(
+            db.withdrawal(4).unwrap().unwrap().status,
+            WithdrawalStatus::Queued
+        );
+
+        // A batch with a withdrawal that isn't queued is recorded for none
+        let other = SignedTransfer {
+            tx_hash: B256::repeat_byte(0x05),
+            ..transfer
+        };
+        assert!(db
+            .record_withdrawals_signed(&[(4, 1), (1, 1)], &other)
+            .is_err());
+        assert_eq!(
+            db.withdrawal(4).unwrap().unwrap().status,
+            WithdrawalStatus::Queued
+        );
+        assert!(db.withdrawals_in_payout(other.tx_hash).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_operation_journal() {
+        let db = Database::open_in_memory().unwrap();
+        let mint = Intent::Mint {
+            tx_hash: B256::repeat_byte(0x01),
+            output_index: 0,
+        };
+        let fresh = db.begin_operation(&mint).unwrap();
+        assert_eq!(fresh.key, mint.key());
+        assert_eq!(fresh.status, OperationStatus::Pending);
+        assert!(fresh.sent.is_empty());
+
+        // A restarted attempt finds what the first one sent
+        db.record_operation_tx(&fresh.key, B256::repeat_byte(0x0a))
+            .unwrap();
+        db.record_operation_tx(&fresh.key, B256::repeat_byte(0x0b))
+            .unwrap();
+        db.record_operation_tx(&fresh.key, B256::repeat_byte(0x0a))
+            .unwrap();
+        let resumed = db.begin_operation(&mint).unwrap();
+        assert_eq!(
+            resumed.sent,
+            [B256::repeat_byte(0x0a), B256::repeat_byte(0x0b)]
+        );
+        assert_eq!(resumed.created_at, fresh.created_at);
+        assert_eq!(db.pending_operations().unwrap(), [resumed]);
+
+        db.complete_operation(&mint.key(), Some(B256::repeat_byte(0x0b)))
+            .unwrap();
+        let completed = db.begin_operation(&mint).unwrap();
+        assert_eq!(completed.status, OperationStatus::Completed);
+        assert_eq!(completed.tx_hash, Some(B256::repeat_byte(0x0b)));
+        assert_eq!(completed.candidate_txs()[0], B256::repeat_byte(0x0b));
+        assert!(db.pending_operations().unwrap().is_empty());
+
+        // An abandoned intent is reopened by the next attempt
+        let payout = Intent::Payout {
+            burn_ids: vec![1, 2],
+        };
+        db.begin_operation(&payout).unwrap();
+        db.abandon_operation(&payout.key(), "Transfer failed")
+            .unwrap();
+        assert_eq!(
+            db.operation(&payout.key())
+                .unwrap()
+                .unwrap()
+                .error
+                .as_deref(),
+            Some("Transfer failed")
+        );
+        let reopened = db.begin_operation(&payout).unwrap();
+        assert_eq!(reopened.status, OperationStatus::Pending);
+        assert_eq!(reopened.error, None);
+        assert!(db.complete_operation("payout:3", None).is_err());
+    }
+
+    #[test]
+    fn test_tx_costs_by_month() {
+        let db = Database::open_in_memory().unwrap();
+        let january: Month = "2025-01".parse().unwrap();
+        let at = |rfc3339: &str| {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let costs = [
+            TxCost {
+                tx_hash: B256::repeat_byte(0x01),
+                kind: CostKind::PostBlock,
+                gas_used: 90_000,
+                // Beyond i64
+                gas_price: 10_000_000_000_000_000_000,
+                xmr_fee: 0,
+                recorded_at: at("2025-01-01T00:00:00Z"),
+            },
+            TxCost {
+                recorded_at: at("2025-01-31T23:59:59.5Z"),
+                ..TxCost::payout(B256::repeat_byte(0x02), 30_000_000)
+            },
+            TxCost {
+                recorded_at: at("2025-02-01T00:00:00Z"),
+                ..TxCost::payout(B256::repeat_byte(0x03), 30_000_000)
+            },
+        ];
+        for cost in &costs {
+            db.record_tx_cost(cost).unwrap();
+        }
+        // Recording a transaction again changes nothing
+        db.record_tx_cost(&TxCost::payout(B256::repeat_byte(0x01), 1))
+            .unwrap();
+
+        assert_eq!(
+            db.tx_costs(january.start(), january.end()).unwrap(),
+            costs[..2]
+        );
+    }
+
+    #[test]
+    fn test_key_image_spends_follow_reorgs() {
+        let db = Database::open_in_memory().unwrap();
+        let key_image = B256::repeat_byte(0x0a);
+        assert!(db
+            .track_bridge_output(key_image, B256::repeat_byte(0x0b), 7_000)
+            .unwrap());
+        assert!(!db
+            .track_bridge_output(key_image, B256::repeat_byte(0x0b), 7_000)
+            .unwrap());
+
+        let spend = KeyImageSpend {
+            key_image,
+            block_height: 500,
+            tx_hash: B256::repeat_byte(0x0c),
+        };
+        let unrelated = KeyImageSpend {
+            key_image: B256::repeat_byte(0xff),
+            ..spend.clone()
+        };
+        assert_eq!(
+            db.record_key_image_spends(&[unrelated, spend.clone()])
+                .unwrap(),
+            vec![spend.clone()]
+        );
+        assert!(db.unspent_bridge_outputs().unwrap().is_empty());
+        assert!(!db.is_withdrawal_payout(spend.tx_hash).unwrap());
+
+        // The spend is undone if its block is reorged out
+        db.mark_orphaned(500).unwrap();
+        let unspent = db.unspent_bridge_outputs().unwrap();
+        assert_eq!(unspent.len(), 1);
+        assert_eq!(unspent[0].amount, 7_000);
+        assert_eq!(unspent[0].spent, None);
+    }
+
+    #[test]
+    fn test_attestations_are_replaced_per_height() {
+        let db = Database::open_in_memory().unwrap();
+        let signer = Address::repeat_byte(0x11);
+        let attestation = Attestation {
+            height: 600,
+            block_hash: B256::repeat_byte(0x01),
+            tx_merkle_root: B256::repeat_byte(0x02),
+            output_merkle_root: B256::repeat_byte(0x03),
+            signer,
+            signature: Bytes::from(vec![0x1b; 65]),
+        };
+        db.record_attestation(&attestation).unwrap();
+        assert_eq!(
+            db.attestation(600, signer).unwrap(),
+            Some(attestation.clone())
+        );
+        assert_eq!(
+            db.attestation(600, Address::repeat_byte(0x22)).unwrap(),
+            None
+        );
+
+        // Re-signed after a reorg
+        let resigned = Attestation {
+            block_hash: B256::repeat_byte(0x04),
+            signature: Bytes::from(vec![0x1c; 65]),
+            ..attestation
+        };
+        db.record_attestation(&resigned).unwrap();
+        assert_eq!(db.attestation(600, signer).unwrap(), Some(resigned.clone()));
+
+        let peer = Attestation {
+            signer: Address::repeat_byte(0x01),
+            ..resigned.clone()
+        };
+        db.record_attestation(&peer).unwrap();
+        assert_eq!(db.attestations(600).unwrap(), vec![peer, resigned]);
+        assert!(db.attestations(601).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+//! Monero subaddresses for per-user deposit attribution
+//!
+//! Subaddress `(major, minor)` of a wallet with keys `(a, B)` has spend key
+//! `D = B + Hs("SubAddr\0" || a || major || minor)·G` and view key `C = a·D`.
+//! Each bridge user is assigned a minor index in one account, so the spend key
+//! recovered from a detected output identifies who deposited.
+
+use crate::{merkle::write_varint, scanner::read_varint};
+use alloy::primitives::keccak256;
+use anyhow::{Context, Result};
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, edwards::EdwardsPoint, scalar::Scalar};
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Encoded length of a base58 block of 0..=8 bytes
+const BASE58_BLOCK_SIZES: [usize; 9] = [0, 2, 3, 5, 6, 7, 9, 10, 11];
+
+/// Account (`major`) and address (`minor`) index of a subaddress
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct SubaddressIndex {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl SubaddressIndex {
+    pub fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// `(0, 0)` is the wallet's primary address
+    pub fn is_primary(self) -> bool {
+        self.major == 0 && self.minor == 0
+    }
+}
+
+impl fmt::Display for SubaddressIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    Stagenet,
+}
+
+impl Network {
+    const ALL: [Network; 3] = [Self::Mainnet, Self::Testnet, Self::Stagenet];
+
+    /// Address prefix for primary addresses and subaddresses
+    fn prefixes(self) -> (u64, u64) {
+        match self {
+            Self::Mainnet => (18, 42),
+            Self::Testnet => (53, 63),
+            Self::Stagenet => (24, 36),
+        }
+    }
+
+    /// Address prefix for integrated addresses
+    fn integrated_prefix(self) -> u64 {
+        match self {
+            Self::Mainnet => 19,
+            Self::Testnet => 54,
+            Self::Stagenet => 25,
+        }
+    }
+
+    /// Port monerod serves RPC on unless `--rpc-bind-port` is given
+    pub fn default_rpc_port(self) -> u16 {
+        match self {
+            Self::Mainnet => 18081,
+            Self::Testnet => 28081,
+            Self::Stagenet => 38081,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Stagenet => "stagenet",
+        })
+    }
+}
+
+/// A decoded standard address or subaddress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoneroAddress {
+    pub network: Network,
+    pub is_subaddress: bool,
+    pub spend_public: [u8; 32],
+    pub view_public: [u8; 32],
+}
+
+impl MoneroAddress {
+    /// Decode and checksum an address string. Integrated addresses are
+    /// rejected: withdrawals don't carry payment IDs.
+    pub fn parse(address: &str) -> Result<Self> {
+        let data = base58_decode(address).context("Address is not valid base58")?;
+        let (prefix, prefix_len) = read_varint(&data).context("Address has no prefix")?;
+        anyhow::ensure!(
+            data.len() == prefix_len + 68,
+            "Address has the wrong length (integrated addresses are not supported)"
+        );
+
+        let (body, checksum) = data.split_at(data.len() - 4);
+        anyhow::ensure!(
+            keccak256(body)[..4] == *checksum,
+            "Address checksum mismatch"
+        );
+
+        let (network, is_subaddress) = Network::ALL
+            .into_iter()
+            .find_map(|network| match network.prefixes() {
+                (primary, _) if primary == prefix => Some((network, false)),
+                (_, subaddress) if subaddress == prefix => Some((network, true)),
+                _ => None,
+            })
+            .with_context(|| format!("Unknown address prefix {}", prefix))?;
+
+        let keys = &body[prefix_len..];
+        Ok(Self {
+            network,
+            is_subaddress,
+            spend_public: keys[..32].try_into().expect("32 bytes"),
+            view_public: keys[32..].try_into().expect("32 bytes"),
+        })
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet" => Ok(Self::Testnet),
+            "stagenet" => Ok(Self::Stagenet),
+            other => anyhow::bail!(
+                "Unknown Monero network '{}' (expected mainnet, testnet or stagenet)",
+                other
+            ),
+        }
+    }
+}
+
+/// Spend public key `D` of a subaddress; the primary address keeps `B`
+pub fn subaddress_spend_public(
+    view_secret: &Scalar,
+    spend_public: &EdwardsPoint,
+    index: SubaddressIndex,
+) -> EdwardsPoint {
+    if index.is_primary() {
+        return *spend_public;
+    }
+
+    let mut data = b"SubAddr\0".to_vec();
+    data.extend_from_slice(view_secret.as_bytes());
+    data.extend_from_slice(&index.major.to_le_bytes());
+    data.extend_from_slice(&index.minor.to_le_bytes());
+    let m = Scalar::from_bytes_mod_order(keccak256(&data).0);
+
+    spend_public + &m * ED25519_BASEPOINT_TABLE
+}
+
+/// Standard address string for a primary address or subaddress
+pub fn encode_address(
+    network: Network,
+    index: SubaddressIndex,
+    spend_public: &EdwardsPoint,
+    view_public: &EdwardsPoint,
+) -> String {
+    let (primary, subaddress) = network.prefixes();
+    let prefix = if index.is_primary() {
+        primary
+    } else {
+        subaddress
+    };
+    encode(prefix, spend_public, view_public, &[])
+}
+
+/// Integrated address string: a primary address followed by an 8-byte
+/// payment ID
+pub fn encode_integrated_address(
+    network: Network,
+    spend_public: &EdwardsPoint,
+    view_public: &EdwardsPoint,
+    payment_id: &[u8; 8],
+) -> String {
+    encode(
+        network.integrated_prefix(),
+        spend_public,
+        view_public,
+        payment_id,
+    )
+}
+
+/// `base58(prefix || spend || view || payment_id || checksum)`
+fn encode(
+    prefix: u64,
+    spend_public: &EdwardsPoint,
+    view_public: &EdwardsPoint,
+    payment_id: &[u8],
+) -> String {
+    let mut data = Vec::with_capacity(77);
+    write_varint(&mut data, prefix);
+    data.extend_from_slice(spend_public.compress().as_bytes());
+    data.extend_from_slice(view_public.compress().as_bytes());
+    data.extend_from_slice(payment_id);
+    let checksum = keccak256(&data);
+    data.extend_from_slice(&checksum[..4]);
+
+    base58_encode(&data)
+}
+
+/// Monero's base58: 8-byte blocks encoded independently into 11 characters
+fn base58_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() / 8 * 11 + 11);
+
+    for block in data.chunks(8) {
+        let mut num = block.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+        let mut encoded = vec![BASE58_ALPHABET[0]; BASE58_BLOCK_SIZES[block.len()]];
+        for slot in encoded.iter_mut().rev() {
+            *slot = BASE58_ALPHABET[(num % 58) as usize];
+            num /= 58;
+        }
+        out.push_str(std::str::from_utf8(&encoded).expect("alphabet is ASCII"));
+    }
+
+    out
+}
+
+/// Inverse of [`base58_encode`]
+fn base58_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() / 11 * 8 + 8);
+
+    for block in s.as_bytes().chunks(11) {
+        let size = BASE58_BLOCK_SIZES.iter().position(|&n| n == block.len())?;
+        let mut num: u128 = 0;
+        for c in block {
+            let digit = BASE58_ALPHABET.iter().position(|a| a == c)?;
+            num = num * 58 + digit as u128;
+        }
+        if num >> (size * 8) != 0 {
+            return None;
+        }
+        out.extend_from_slice(&(num as u64).to_be_bytes()[8 - size..]);
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base58_blocks() {
+        assert_eq!(base58_encode(&[0x00]), "11");
+        assert_eq!(base58_encode(&[0xff]), "5Q");
+        assert_eq!(base58_encode(&[0xff, 0xff]), "LUv");
+        assert_eq!(base58_encode(&[0x00; 8]), "11111111111");
+        assert_eq!(base58_encode(&[0xff; 8]), "jpXCZedGfVQ");
+
+        let data: Vec<u8> = (0..69).collect();
+        assert_eq!(base58_decode(&base58_encode(&data)), Some(data));
+        assert_eq!(base58_decode("zzzzzzzzzzz"), None);
+        assert_eq!(base58_decode("0"), None);
+    }
+
+    #[test]
+    fn test_address_format() {
+        let view_secret = Scalar::from_bytes_mod_order([3; 32]);
+        let spend_public = &Scalar::from_bytes_mod_order([5; 32]) * ED25519_BASEPOINT_TABLE;
+
+        let primary = SubaddressIndex::default();
+        let spend = subaddress_spend_public(&view_secret, &spend_public, primary);
+        let view_public = &view_secret * ED25519_BASEPOINT_TABLE;
+        let address = encode_address(Network::Mainnet, primary, &spend, &view_public);
+        assert_eq!(spend, spend_public);
+        assert_eq!(address.len(), 95);
+        assert!(address.starts_with('4'));
+
+        let user = SubaddressIndex::new(0, 7);
+        let spend = subaddress_spend_public(&view_secret, &spend_public, user);
+        let address = encode_address(Network::Mainnet, user, &spend, &(view_secret * spend));
+        assert_ne!(spend, spend_public);
+        assert!(address.starts_with('8'));
+
+        let parsed = MoneroAddress::parse(&address).unwrap();
+        assert_eq!(parsed.network, Network::Mainnet);
+        let stagenet = encode_address(Network::Stagenet, user, &spend, &(view_secret * spend));
+        assert!(stagenet.starts_with('7'));
+        assert_eq!(
+            MoneroAddress::parse(&stagenet).unwrap().network,
+            Network::Stagenet
+        );
+        assert!(parsed.is_subaddress);
+        assert_eq!(parsed.spend_public, spend.compress().to_bytes());
+
+        let integrated = encode_integrated_address(
+            Network::Mainnet,
+            &spend_public,
+            &(&view_secret * ED25519_BASEPOINT_TABLE),
+            &[0x12; 8],
+        );
+        assert_eq!(integrated.len(), 106);
+        assert!(integrated.starts_with('4'));
+        assert!(MoneroAddress::parse(&integrated).is_err());
+
+        let mut corrupted = address.into_bytes();
+        corrupted[10] = if corrupted[10] == b'2' { b'3' } else { b'2' };
+        assert!(MoneroAddress::parse(std::str::from_utf8(&corrupted).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! systemd readiness and watchdog notifications
+//!
+//! Run under a `Type=notify` unit, the oracle tells systemd it is ready once
+//! the contract and nodes check out, and with `WatchdogSec=` set it sends a
+//! heartbeat after every successful poll. A poll loop stuck on an RPC call
+//! that never returns stops the heartbeats, and systemd restarts the oracle
+//! instead of leaving the bridge silently frozen. Outside systemd
+//! (`NOTIFY_SOCKET` unset) every call here does nothing.
+
+use std::{env, time::Duration};
+use tracing::warn;
+
+/// Where to send notifications, read once from the environment systemd
+/// starts the service with
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    socket: Option<String>,
+    watchdog: Option<Duration>,
+}
+
+impl Notifier {
+    pub fn from_env() -> Self {
+        Self {
+            socket: env::var("NOTIFY_SOCKET").ok().filter(|s| !s.is_empty()),
+            watchdog: watchdog_timeout(
+                env::var("WATCHDOG_USEC").ok().as_deref(),
+                env::var("WATCHDOG_PID").ok().as_deref(),
+                std::process::id(),
+            ),
+        }
+    }
+
+    /// `WatchdogSec=` of the unit, if systemd expects heartbeats from this
+    /// process
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        self.watchdog.filter(|_| self.socket.is_some())
+    }
+
+    /// Startup is done; `Type=notify` units count as started from here
+    pub fn ready(&self, status: &str) {
+        self.notify(&format!("READY=1\nSTATUS={}", status));
+    }
+
+    /// A poll completed; resets the watchdog timer
+    pub fn heartbeat(&self, status: &str) {
+        if self.watchdog_timeout().is_some() {
+            self.notify(&format!("WATCHDOG=1\nSTATUS={}", status));
+        } else {
+            self.status(status);
+        }
+    }
+
+    /// Shown by `systemctl status`
+    pub fn status(&self, status: &str) {
+        self.notify(&format!("STATUS={}", status));
+    }
+
+    /// The oracle is exiting on purpose
+    pub fn stopping(&self) {
+        self.notify("STOPPING=1");
+    }
+
+    fn notify(&self, state: &str) {
+        let Some(socket) = &self.socket else {
+            return;
+        };
+        if let Err(e) = send(socket, state) {
+            warn!("⚠️  Failed to notify systemd at {}: {}", socket, e);
+        }
+    }
+}
+
+/// The watchdog timeout from `WATCHDOG_USEC`, unless `WATCHDOG_PID` names
+/// another process (a wrapper script that systemd started)
+fn watchdog_timeout(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if let Some(pid) = pid {
+        if pid.parse::<u32>().ok()? != own_pid {
+            return None;
+        }
+    }
+    match usec?.parse::<u64>().ok()? {
+        0 => None,
+        usec => Some(Duration::from_micros(usec)),
+    }
+}
+
+/// Send one datagram to `NOTIFY_SOCKET`: a path, or an abstract socket
+/// name prefixed with `@`
+#[cfg(unix)]
+fn send(socket: &str, state: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound()?;
+    match socket.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+            let addr = SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        _ => {
+            datagram.send_to(state.as_bytes(), socket)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &str, _state: &str) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_timeout() {
+        assert_eq!(
+            watchdog_timeout(Some("300000000"), None, 42),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            watchdog_timeout(Some("300000000"), Some("42"), 42),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(watchdog_timeout(Some("300000000"), Some("7"), 42), None);
+        assert_eq!(watchdog_timeout(Some("0"), None, 42), None);
+        assert_eq!(watchdog_timeout(None, Some("42"), 42), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_notify_socket() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = env::temp_dir().join(format!("monero-oracle-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixDatagram::bind(&path).unwrap();
+        let notifier = Notifier {
+            socket: Some(path.to_string_lossy().into_owned()),
+            watchdog: Some(Duration::from_secs(300)),
+        };
+
+        notifier.ready("Polling");
+        notifier.heartbeat("Contract at block 3100000");
+        let mut buf = [0; 256];
+        let n = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1\nSTATUS=Polling");
+        let n = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"WATCHDOG=1\nSTATUS=Contract at block 3100000");
+
+        // Without a socket nothing is sent and nothing fails
+        assert_eq!(Notifier::default().watchdog_timeout(), None);
+        Notifier::default().ready("Polling");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+//! `tx_extra` parsing
+//!
+//! `tx_extra` is a sequence of tagged fields: the tx public key, per-output
+//! additional public keys for transactions paying subaddresses, a nonce that
+//! usually carries a payment ID, merge-mining data in miner transactions, and
+//! zero padding. Nothing in consensus checks it, so parsing follows monerod:
+//! fields are read in order and parsing stops at the first malformed or
+//! unknown one, keeping what was read before it.
+
+use crate::scanner::read_varint;
+
+pub const TX_EXTRA_PADDING: u8 = 0x00;
+pub const TX_EXTRA_PUBKEY: u8 = 0x01;
+pub const TX_EXTRA_NONCE: u8 = 0x02;
+pub const TX_EXTRA_MERGE_MINING: u8 = 0x03;
+pub const TX_EXTRA_ADDITIONAL_PUBKEYS: u8 = 0x04;
+pub const TX_EXTRA_MYSTERIOUS_MINERGATE: u8 = 0xde;
+
+/// First byte of a nonce holding a 32-byte cleartext payment ID
+pub const NONCE_PAYMENT_ID: u8 = 0x00;
+/// First byte of a nonce holding an 8-byte encrypted payment ID
+pub const NONCE_ENCRYPTED_PAYMENT_ID: u8 = 0x01;
+
+/// Longest padding monerod accepts
+const MAX_PADDING: usize = 255;
+
+/// One `tx_extra` field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraField {
+    /// Zero bytes running to the end of `tx_extra`, including the tag
+    Padding(usize),
+    PubKey([u8; 32]),
+    Nonce(Nonce),
+    /// Merge-mining commitment of a miner transaction
+    MergeMining {
+        depth: u64,
+        merkle_root: [u8; 32],
+    },
+    AdditionalPubKeys(Vec<[u8; 32]>),
+    /// Opaque data some pools added to miner transactions
+    MysteriousMinergate(Vec<u8>),
+}
+
+/// Contents of a nonce field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nonce {
+    /// Deprecated unencrypted payment ID
+    PaymentId([u8; 32]),
+    /// Payment ID of an integrated address, encrypted to the recipient
+    EncryptedPaymentId([u8; 8]),
+    Other(Vec<u8>),
+}
+
+impl Nonce {
+    fn parse(data: &[u8]) -> Self {
+        match data {
+            [NONCE_PAYMENT_ID, id @ ..] if id.len() == 32 => {
+                Self::PaymentId(id.try_into().expect("32 bytes"))
+            }
+            [NONCE_ENCRYPTED_PAYMENT_ID, id @ ..] if id.len() == 8 => {
+                Self::EncryptedPaymentId(id.try_into().expect("8 bytes"))
+            }
+            _ => Self::Other(data.to_vec()),
+        }
+    }
+}
+
+/// A parsed `tx_extra`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxExtra {
+    pub fields: Vec<ExtraField>,
+    /// Bytes left unparsed after a malformed or unknown field
+    pub unparsed: usize,
+}
+
+impl TxExtra {
+    pub fn parse(extra: &[u8]) -> Self {
+        let mut fields = Vec::new();
+        let mut pos = 0;
+
+        while pos < extra.len() {
+            match parse_field(extra, pos) {
+                Some((field, next)) => {
+                    fields.push(field);
+                    pos = next;
+                }
+                None => break,
+            }
+        }
+
+        Self {
+            fields,
+            unparsed: extra.len() - pos,
+        }
+    }
+
+    /// Main tx public keys, in order. There is normally one; wallets use the
+    /// first, and scanning tries them all.
+    pub fn pubkeys(&self) -> impl Iterator<Item = &[u8; 32]> {
+        self.fields.iter().filter_map(|field| match field {
+            ExtraField::PubKey(key) => Some(key),
+            _ => None,
+        })
+    }
+
+    /// Per-output tx public keys, indexed like the outputs
+    pub fn additional_pubkeys(&self) -> &[[u8; 32]] {
+        self.fields
+            .iter()
+            .find_map(|field| match field {
+                ExtraField::AdditionalPubKeys(keys) => Some(keys.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    fn nonces(&self) -> impl Iterator<Item = &Nonce> {
+        self.fields.iter().filter_map(|field| match field {
+            ExtraField::Nonce(nonce) => Some(nonce),
+            _ => None,
+        })
+    }
+
+    /// Encrypted payment ID from the first nonce carrying one
+    pub fn encrypted_payment_id(&self) -> Option<[u8; 8]> {
+        self.nonces().find_map(|nonce| match nonce {
+            Nonce::EncryptedPaymentId(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Unencrypted payment ID from the first nonce carrying one
+    pub fn payment_id(&self) -> Option<[u8; 32]> {
+        self.nonces().find_map(|nonce| match nonce {
+            Nonce::PaymentId(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Merge-mining commitment, in miner transactions that have one
+    pub fn merge_mining(&self) -> Option<(u64, [u8; 32])> {
+        self.fields.iter().find_map(|field| match field {
+            ExtraField::MergeMining { depth, merkle_root } => Some((*depth, *merkle_root)),
+            _ => None,
+        })
+    }
+}
+
+/// The field starting at `pos` and the position after it
+fn parse_field(extra: &[u8], pos: usize) -> Option<(ExtraField, usize)> {
+    let tag = extra[pos];
+    let pos = pos + 1;
+
+    match tag {
+        TX_EXTRA_PADDING => {
+            let len = extra.len() - pos + 1;
+            if len > MAX_PADDING || extra[pos..].iter().any(|&byte| byte != 0) {
+                return None;
+            }
+            Some((ExtraField::Padding(len), extra.len()))
+        }
+        TX_EXTRA_PUBKEY => {
+            let key = read_key(extra, pos)?;
+            Some((ExtraField::PubKey(key), pos + 32))
+        }
+        TX_EXTRA_NONCE => {
+            let (data, next) = read_sized(extra, pos)?;
+            Some((ExtraField::Nonce(Nonce::parse(data)), next))
+        }
+        TX_EXTRA_MERGE_MINING => {
+            let (data, next) = read_sized(extra, pos)?;
+            let (depth, len) = read_varint(data)?;
+            let merkle_root = data.get(len..len + 32)?.try_into().ok()?;
+            Some((ExtraField::MergeMining { depth, merkle_root }, next))
+        }
+        TX_EXTRA_ADDITIONAL_PUBKEYS => {
+            let (count, len) = read_varint(&extra[pos..])?;
+            let mut pos = pos + len;
+            let mut keys = Vec::new();
+            for _ in 0..count {
+                keys.push(read_key(extra, pos)?);
+                pos += 32;
+            }
+            Some((ExtraField::AdditionalPubKeys(keys), pos))
+        }
+        TX_EXTRA_MYSTERIOUS_MINERGATE => {
+            let (data, next) = read_sized(extra, pos)?;
+            Some((ExtraField::MysteriousMinergate(data.to_vec()), next))
+        }
+        _ => None,
+    }
+}
+
+fn read_key(extra: &[u8], pos: usize) -> Option<[u8; 32]> {
+    extra.get(pos..pos.checked_add(32)?)?.try_into().ok()
+}
+
+/// A varint length followed by that many bytes
+fn read_sized(extra: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+    let (size, len) = read_varint(&extra[pos..])?;
+    let start = pos + len;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    Some((extra.get(start..end)?, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wallet_extra() {
+        let mut extra = vec![TX_EXTRA_PUBKEY];
+        extra.extend_from_slice(&[0x11; 32]);
+        extra.extend_from_slice(&[TX_EXTRA_NONCE, 9, NONCE_ENCRYPTED_PAYMENT_ID]);
+        extra.extend_from_slice(&[0x22; 8]);
+        extra.extend_from_slice(&[TX_EXTRA_ADDITIONAL_PUBKEYS, 2]);
+        extra.extend_from_slice(&[0x33; 32]);
+        extra.extend_from_slice(&[0x44; 32]);
+        extra.extend_from_slice(&[TX_EXTRA_PADDING, 0, 0]);
+
+        let parsed = TxExtra::parse(&extra);
+        assert_eq!(parsed.unparsed, 0);
+        assert_eq!(parsed.pubkeys().collect::<Vec<_>>(), [&[0x11; 32]]);
+        assert_eq!(parsed.encrypted_payment_id(), Some([0x22; 8]));
+        assert_eq!(parsed.payment_id(), None);
+        assert_eq!(parsed.additional_pubkeys(), [[0x33; 32], [0x44; 32]]);
+        assert_eq!(parsed.fields.last(), Some(&ExtraField::Padding(3)));
+    }
+
+    #[test]
+    fn test_parse_miner_extra() {
+        let mut extra = vec![TX_EXTRA_PUBKEY];
+        extra.extend_from_slice(&[0x11; 32]);
+        extra.extend_from_slice(&[TX_EXTRA_MERGE_MINING, 33, 5]);
+        extra.extend_from_slice(&[0x55; 32]);
+        extra.extend_from_slice(&[TX_EXTRA_NONCE, 3, 0xaa, 0xbb, 0xcc]);
+        extra.extend_from_slice(&[TX_EXTRA_MYSTERIOUS_MINERGATE, 1, 0x66]);
+        let mut payment_id = vec![TX_EXTRA_NONCE, 33, NONCE_PAYMENT_ID];
+        payment_id.extend_from_slice(&[0x77; 32]);
+        extra.extend_from_slice(&payment_id);
+
+        let parsed = TxExtra::parse(&extra);
+        assert_eq!(parsed.unparsed, 0);
+        assert_eq!(parsed.merge_mining(), Some((5, [0x55; 32])));
+        assert_eq!(
+            parsed.fields[2],
+            ExtraField::Nonce(Nonce::Other(vec![0xaa, 0xbb, 0xcc]))
+        );
+        assert_eq!(
+            parsed.fields[3],
+            ExtraField::MysteriousMinergate(vec![0x66])
+        );
+        assert_eq!(parsed.payment_id(), Some([0x77; 32]));
+        assert_eq!(parsed.encrypted_payment_id(), None);
+    }
+
+    #[test]
+    fn test_stops_at_malformed_field() {
+        let mut extra = vec![TX_EXTRA_PUBKEY];
+        extra.extend_from_slice(&[0x11; 32]);
+        // Unknown tag: the key before it is kept
+        extra.extend_from_slice(&[0x99, 1, 2]);
+        let parsed = TxExtra::parse(&extra);
+        assert_eq!(parsed.pubkeys().count(), 1);
+        assert_eq!(parsed.unparsed, 3);
+
+        // Truncated key, nonce running past the end, non-zero padding
+        assert_eq!(TxExtra::parse(&[TX_EXTRA_PUBKEY, 1, 2]).unparsed, 3);
+        assert_eq!(TxExtra::parse(&[TX_EXTRA_NONCE, 5, 1]).unparsed, 3);
+        assert_eq!(TxExtra::parse(&[TX_EXTRA_PADDING, 0, 1]).unparsed, 3);
+        assert_eq!(
+            TxExtra::parse(&[TX_EXTRA_ADDITIONAL_PUBKEYS, 1, 0]).fields,
+            []
+        );
+        assert_eq!(TxExtra::parse(&[]), TxExtra::default());
+    }
+}
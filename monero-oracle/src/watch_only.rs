@@ -0,0 +1,121 @@
+//! Watch-only copies of the bridge wallet, for checking its balance with
+//! official tooling
+//!
+//! `watch-only-wallet` has a spare monero-wallet-rpc (run with `--wallet-dir`)
+//! create a standard view-only wallet file from the bridge's primary address
+//! and `BRIDGE_VIEW_KEY`, which monero-wallet-cli and the GUI open as-is. A
+//! view-only wallet sees what the bridge received but not what it spent;
+//! given the payout wallet's key images it sees both, and its balance can be
+//! held against the payout wallet's and the oracle's records.
+//!
+//! `export-outputs` writes the payout wallet's outputs in the file format of
+//! monero-wallet-cli's `export_outputs`, which its `import_outputs` reads.
+//! `export-key-images` writes the signed key images as the JSON wallet-rpc's
+//! `export_key_images` returns. The CLI's own key image file is encrypted
+//! with a key only wallet2 derives, so the key images are imported through
+//! wallet-rpc, by `watch-only-wallet --key-images`, rather than by the CLI's
+//! `import_key_images`.
+
+use crate::{
+    monero_wallet::{Balance, KeyImageImport, KeyImages, MoneroWalletClient},
+    scanner::ViewKeys,
+    subaddress::{Network, SubaddressIndex},
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// A view-only wallet created by [`create`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchOnlyWallet {
+    /// Wallet file, in wallet-rpc's `--wallet-dir`
+    pub filename: String,
+    pub address: String,
+    pub restore_height: u64,
+    /// Balance of account 0 once synced, counting spends only if key images
+    /// were imported
+    pub balance: Balance,
+    pub key_images: Option<KeyImageImport>,
+}
+
+/// Create view-only wallet `filename` for the bridge's primary address in
+/// `wallet`, sync it from `restore_height` and import `key_images` into it
+pub async fn create(
+    wallet: &MoneroWalletClient,
+    keys: &ViewKeys,
+    network: Network,
+    filename: &str,
+    restore_height: u64,
+    key_images: Option<&KeyImages>,
+) -> Result<WatchOnlyWallet> {
+    let address = keys.address(SubaddressIndex::default(), network);
+    wallet
+        .generate_from_keys(filename, &address, &keys.view_key_hex(), restore_height)
+        .await
+        .with_context(|| format!("Failed to create view-only wallet {}", filename))?;
+    wallet.refresh(restore_height).await?;
+
+    let key_images = match key_images {
+        Some(key_images) => Some(
+            wallet
+                .import_key_images(key_images)
+                .await
+                .context("Key images don't match the bridge wallet's outputs")?,
+        ),
+        None => None,
+    };
+    Ok(WatchOnlyWallet {
+        filename: filename.to_string(),
+        address,
+        restore_height,
+        balance: wallet.get_balance(0).await?,
+        key_images,
+    })
+}
+
+/// Key images written by `export-key-images`
+pub fn read_key_images(path: &Path) -> Result<KeyImages> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let key_images: KeyImages = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not a key images file", path.display()))?;
+    key_images.validate()?;
+    Ok(key_images)
+}
+
+pub fn write_key_images(path: &Path, key_images: &KeyImages) -> Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(key_images)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monero_wallet::SignedKeyImage;
+
+    #[test]
+    fn test_key_images_file_roundtrip() {
+        let key_images = KeyImages {
+            offset: 0,
+            signed_key_images: vec![SignedKeyImage {
+                key_image: "a".repeat(64),
+                signature: "b".repeat(128),
+            }],
+        };
+        let path = std::env::temp_dir().join(format!(
+            "monero-oracle-key-images-{}.json",
+            std::process::id()
+        ));
+        write_key_images(&path, &key_images).unwrap();
+        assert_eq!(read_key_images(&path).unwrap(), key_images);
+
+        std::fs::write(
+            &path,
+            r#"{"signed_key_images":[{"key_image":"zz","signature":""}]}"#,
+        )
+        .unwrap();
+        assert!(read_key_images(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
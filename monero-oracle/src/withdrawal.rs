@@ -0,0 +1,267 @@
+//! Withdrawals: burns on Unichain that are paid out in XMR
+//!
+//! Every `BurnRequested` event emitted by the contract becomes a [`Withdrawal`]
+//! in the local database. Burns whose destination can't be paid (malformed
+//! address, wrong network, amount out of range) are kept as `rejected` so
+//! operators can see and refund them.
+//!
+//! Payable withdrawals move through a two-phase commit between Monero and
+//! Unichain, `queued` -> `signed` -> `broadcast` -> `confirmed_xmr` ->
+//! `confirmed_evm`: the transfer is signed and its tx hash and key stored
+//! before it is relayed, its Monero confirmations are recorded before the tx
+//! key is published with `confirmWithdrawal`, and each stage is persisted
+//! before the next begins. Whatever stage a crash interrupts is picked up
+//! from the stored state: a signed transfer is relayed again (never signed
+//! again), a broadcast one that dropped out of the mempool is rebroadcast,
+//! and a confirmation is looked up by its journaled transaction first. A step
+//! that keeps failing is retried with exponential backoff until the attempt
+//! limit, then the withdrawal is marked `failed` for an operator to resolve.
+
+use crate::subaddress::{MoneroAddress, Network};
+use alloy::primitives::{Address, B256, U256};
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Delay before the first retry of a failed step; doubled on every attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(60);
+/// Longest delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Where a withdrawal is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalStatus {
+    /// Waiting to be paid out
+    Queued,
+    /// Transfer signed and recorded, not yet relayed
+    Signed,
+    /// Transfer relayed to the Monero network
+    Broadcast,
+    /// Transfer has `MIN_CONFIRMATIONS` on Monero, not yet published
+    ConfirmedXmr,
+    /// Payment published to the contract with `confirmWithdrawal`
+    ConfirmedEvm,
+    /// Gave up after repeated failures; see `error`
+    Failed,
+    /// Can't be paid out; see `error`
+    Rejected,
+}
+
+impl fmt::Display for WithdrawalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Queued => "queued",
+            Self::Signed => "signed",
+            Self::Broadcast => "broadcast",
+            Self::ConfirmedXmr => "confirmed_xmr",
+            Self::ConfirmedEvm => "confirmed_evm",
+            Self::Failed => "failed",
+            Self::Rejected => "rejected",
+        })
+    }
+}
+
+impl FromStr for WithdrawalStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "queued" => Ok(Self::Queued),
+            "signed" => Ok(Self::Signed),
+            "broadcast" => Ok(Self::Broadcast),
+            "confirmed_xmr" => Ok(Self::ConfirmedXmr),
+            "confirmed_evm" => Ok(Self::ConfirmedEvm),
+            "failed" => Ok(Self::Failed),
+            "rejected" => Ok(Self::Rejected),
+            other => anyhow::bail!("Unknown withdrawal status '{}'", other),
+        }
+    }
+}
+
+impl WithdrawalStatus {
+    /// The stage a withdrawal moves to once this one completes, `None` for
+    /// final states
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Self::Queued => Some(Self::Signed),
+            Self::Signed => Some(Self::Broadcast),
+            Self::Broadcast => Some(Self::ConfirmedXmr),
+            Self::ConfirmedXmr => Some(Self::ConfirmedEvm),
+            Self::ConfirmedEvm | Self::Failed | Self::Rejected => None,
+        }
+    }
+}
+
+/// A burn of zeroXMR to be paid out on Monero
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    pub burn_id: u64,
+    pub user: Address,
+    pub lp: Address,
+    /// Amount in piconero
+    pub amount: u64,
+    pub xmr_address: String,
+    pub unichain_tx_hash: B256,
+    pub unichain_block: u64,
+    pub status: WithdrawalStatus,
+    pub error: Option<String>,
+    /// Failed attempts at the current step
+    pub attempts: u32,
+    /// Monero payout, once signed
+    pub xmr_tx_hash: Option<B256>,
+    /// Tx key proving the payout to `xmr_address`
+    pub xmr_tx_key: Option<String>,
+    /// This withdrawal's share of the payout's network fee, in piconero
+    pub xmr_fee: Option<u64>,
+    /// Piconero sent to `xmr_address`, net of any deducted fee
+    pub xmr_amount: Option<u64>,
+}
+
+/// A `BurnRequested` event as read from the contract logs
+#[derive(Debug, Clone)]
+pub struct BurnEvent {
+    pub burn_id: U256,
+    pub user: Address,
+    pub lp: Address,
+    pub amount: U256,
+    pub xmr_address: String,
+    pub unichain_tx_hash: B256,
+    pub unichain_block: u64,
+}
+
+impl Withdrawal {
+    /// Queue a burn, or reject it if it can't be paid out on `network`
+    pub fn from_burn(event: BurnEvent, network: Network) -> Result<Self> {
+        let burn_id = event
+            .burn_id
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Burn id {} out of range", event.burn_id))?;
+
+        let amount = u64::try_from(event.amount).ok();
+        let error = match (amount, check_address(&event.xmr_address, network)) {
+            (None, _) => Some(format!("Amount {} exceeds the Monero supply", event.amount)),
+            (Some(0), _) => Some("Amount is zero".to_string()),
+            (_, Err(e)) => Some(format!("{:#}", e)),
+            _ => None,
+        };
+
+        Ok(Self {
+            burn_id,
+            user: event.user,
+            lp: event.lp,
+            amount: amount.unwrap_or(0),
+            xmr_address: event.xmr_address,
+            unichain_tx_hash: event.unichain_tx_hash,
+            unichain_block: event.unichain_block,
+            status: if error.is_some() {
+                WithdrawalStatus::Rejected
+            } else {
+                WithdrawalStatus::Queued
+            },
+            error,
+            attempts: 0,
+            xmr_tx_hash: None,
+            xmr_tx_key: None,
+            xmr_fee: None,
+            xmr_amount: None,
+        })
+    }
+}
+
+/// How long to wait before retrying a step that has failed `attempts` times
+pub fn retry_delay(attempts: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// The destination must be a valid address for the network the bridge runs on
+fn check_address(address: &str, network: Network) -> Result<()> {
+    let parsed = MoneroAddress::parse(address.trim())?;
+    anyhow::ensure!(
+        parsed.network == network,
+        "Address is for {}, expected {}",
+        parsed.network,
+        network
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::ViewKeys;
+    use crate::subaddress::SubaddressIndex;
+    use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+
+    fn burn(amount: u64, xmr_address: String) -> BurnEvent {
+        BurnEvent {
+            burn_id: U256::from(7),
+            user: Address::repeat_byte(0x01),
+            lp: Address::repeat_byte(0x02),
+            amount: U256::from(amount),
+            xmr_address,
+            unichain_tx_hash: B256::repeat_byte(0x03),
+            unichain_block: 1_234,
+        }
+    }
+
+    #[test]
+    fn test_burns_are_queued_or_rejected() {
+        let keys = ViewKeys::new(
+            Scalar::from_bytes_mod_order([3; 32]),
+            &Scalar::from_bytes_mod_order([5; 32]) * ED25519_BASEPOINT_TABLE,
+        );
+        let address = keys.address(SubaddressIndex::new(0, 1), Network::Mainnet);
+
+        let queued = Withdrawal::from_burn(burn(1_000, address.clone()), Network::Mainnet).unwrap();
+        assert_eq!(queued.burn_id, 7);
+        assert_eq!(queued.status, WithdrawalStatus::Queued);
+        assert_eq!(queued.error, None);
+
+        let wrong_network = Withdrawal::from_burn(burn(1_000, address), Network::Stagenet).unwrap();
+        assert_eq!(wrong_network.status, WithdrawalStatus::Rejected);
+
+        let garbage =
+            Withdrawal::from_burn(burn(1_000, "not an address".into()), Network::Mainnet).unwrap();
+        assert_eq!(garbage.status, WithdrawalStatus::Rejected);
+        assert!(garbage.error.is_some());
+    }
+
+    #[test]
+    fn test_status_stages() {
+        let mut stages = vec![WithdrawalStatus::Queued];
+        while let Some(next) = stages.last().unwrap().next() {
+            stages.push(next);
+        }
+        assert_eq!(
+            stages,
+            [
+                WithdrawalStatus::Queued,
+                WithdrawalStatus::Signed,
+                WithdrawalStatus::Broadcast,
+                WithdrawalStatus::ConfirmedXmr,
+                WithdrawalStatus::ConfirmedEvm,
+            ]
+        );
+        for status in stages {
+            assert_eq!(
+                status.to_string().parse::<WithdrawalStatus>().unwrap(),
+                status
+            );
+        }
+        assert_eq!(WithdrawalStatus::Failed.next(), None);
+        assert_eq!(WithdrawalStatus::Rejected.next(), None);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(retry_delay(1), Duration::from_secs(60));
+        assert_eq!(retry_delay(2), Duration::from_secs(120));
+        assert_eq!(retry_delay(4), Duration::from_secs(480));
+        assert_eq!(retry_delay(10), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(u32::MAX), RETRY_MAX_DELAY);
+    }
+}
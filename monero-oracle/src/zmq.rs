@@ -0,0 +1,109 @@
+//! New-block notifications from monerod's ZMQ publisher (`MONERO_ZMQ_URL`)
+//!
+//! monerod started with `--zmq-pub tcp://127.0.0.1:18083` publishes every
+//! block it adds to its main chain. Subscribed to that, the oracle polls as
+//! soon as a block arrives instead of waiting out `POLL_INTERVAL_SECS`. The
+//! interval keeps running underneath: while the publisher is down or silent
+//! the oracle polls on schedule, and the subscriber keeps reconnecting.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Notify, time::timeout};
+use tracing::{info, warn};
+use zeromq::{Socket, SocketRecv, SubSocket};
+
+/// Topic carrying the ids of blocks added to the main chain
+pub const CHAIN_MAIN_TOPIC: &str = "json-minimal-chain_main";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Blocks average two minutes, and a SUB socket doesn't notice its publisher
+/// restarting, so reconnect after this long without a message
+const IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A `json-minimal-chain_main` message
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChainMain {
+    pub first_height: u64,
+    pub first_prev_id: String,
+    pub ids: Vec<String>,
+}
+
+impl ChainMain {
+    /// Height of the new tip
+    pub fn tip_height(&self) -> u64 {
+        self.first_height + (self.ids.len() as u64).saturating_sub(1)
+    }
+}
+
+/// Parse a published `<topic>:<json>` message
+pub fn parse_chain_main(message: &[u8]) -> Result<ChainMain> {
+    let message = std::str::from_utf8(message).context("ZMQ message is not UTF-8")?;
+    let json = message
+        .strip_prefix(CHAIN_MAIN_TOPIC)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .with_context(|| format!("Unexpected ZMQ message: {:.64}", message))?;
+    serde_json::from_str(json).context("Invalid chain_main message")
+}
+
+/// Subscribe to `url` in the background, waking `notify` for every new block
+pub fn spawn(url: String, notify: Arc<Notify>) {
+    tokio::spawn(async move {
+        loop {
+            match listen(&url, &notify).await {
+                Ok(()) => warn!(
+                    "⚠️  No blocks from ZMQ at {} in {} min; reconnecting",
+                    url,
+                    IDLE_TIMEOUT.as_secs() / 60
+                ),
+                Err(e) => warn!(
+                    "⚠️  ZMQ at {} unavailable, polling on schedule: {:#}",
+                    url, e
+                ),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+/// Forward notifications until the publisher has been quiet for
+/// [`IDLE_TIMEOUT`]
+async fn listen(url: &str, notify: &Notify) -> Result<()> {
+    let mut socket = SubSocket::new();
+    timeout(CONNECT_TIMEOUT, socket.connect(url))
+        .await
+        .context("Timed out connecting")?
+        .context("Failed to connect")?;
+    socket.subscribe(CHAIN_MAIN_TOPIC).await?;
+    info!("📡 Subscribed to monerod ZMQ at {}", url);
+
+    while let Ok(message) = timeout(IDLE_TIMEOUT, socket.recv()).await {
+        let message = message.context("Failed to receive")?.into_vec().concat();
+        match parse_chain_main(&message) {
+            Ok(chain) => {
+                info!("📡 New Monero block {} announced", chain.tip_height());
+                notify.notify_one();
+            }
+            Err(e) => warn!("⚠️  Ignoring ZMQ message: {:#}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chain_main() {
+        let message = br#"json-minimal-chain_main:{"first_height":3100000,"first_prev_id":"418015bb9ae982a1975da7d79277c2705727a56894ba0fb246adaabb1f4632e3","ids":["771fbcd656ec1464d3a02ead5e18644030007a0fc664c0a964d30922821a8148","a0c8a8a0e4bd71c29eb5ff3c0a8e4d07a8b1d6dcb0e5ffc29eb5a1c9e8d8d8f1"]}"#;
+        let chain = parse_chain_main(message).unwrap();
+        assert_eq!(chain.first_height, 3_100_000);
+        assert_eq!(chain.ids.len(), 2);
+        assert_eq!(chain.tip_height(), 3_100_001);
+
+        assert!(parse_chain_main(br#"json-minimal-txpool_add:[]"#).is_err());
+        assert!(parse_chain_main(b"json-minimal-chain_main:{}").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! End-to-end tests against a local chain: deploy `MockWrappedMonero` to
+//! Anvil, post blocks to it and check that the contract stores the roots the
+//! oracle computed and accepts the proofs it builds.
+//!
+//! Needs `anvil` (Foundry) on the PATH and the compiled mock, so the tests
+//! only build with the `anvil` feature:
+//!
+//! ```bash
+//! npx hardhat compile              # from the repository root
+//! cargo test --features anvil --test anvil
+//! ```
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    node_bindings::{Anvil, AnvilInstance},
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol,
+    transports::Transport,
+};
+use monero_oracle::{
+    config::Config,
+    eth::{self, StoredBlock, WrappedMonero},
+    fixtures::{Fixtures, RecordedBlock, EXPECTED_FILE},
+    merkle::{compute_output_merkle_root, compute_tx_merkle_root, output_leaf, MerkleHasher},
+    monero_rpc::{MoneroOutput, MoneroRpcClient},
+    oracle::OracleService,
+    proof::{output_merkle_proof, tx_merkle_proof},
+};
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+
+sol! {
+    /// The mock's proof checks, which the oracle's binding doesn't cover
+    #[sol(rpc)]
+    contract MockWrappedMonero {
+        function verifyTxInBlock(bytes32 txHash, uint256 blockHeight, bytes32[] memory merkleProof, uint256 index) public view returns (bool);
+        function verifyMerkleProof(bytes32 leaf, bytes32 root, bytes32[] calldata proof, uint256 index) public pure returns (bool);
+        function outputLeaf(bytes32 txHash, uint256 outputIndex, bytes32 ecdhAmount, bytes32 outputPubKey, bytes32 commitment) external pure returns (bytes32);
+    }
+}
+
+const ARTIFACT: &str = "artifacts/contracts/mocks/MockWrappedMonero.sol/MockWrappedMonero.json";
+
+#[derive(Deserialize)]
+struct Artifact {
+    bytecode: Bytes,
+}
+
+fn spawn_anvil() -> AnvilInstance {
+    Anvil::new()
+        .try_spawn()
+        .expect("Failed to start anvil; install Foundry (https://getfoundry.sh)")
+}
+
+fn deployer(anvil: &AnvilInstance) -> PrivateKeySigner {
+    anvil.keys()[0].clone().into()
+}
+
+/// Deploy the mock with `initial_block` as its latest Monero block; the
+/// sender becomes the oracle
+async fn deploy<T, P>(provider: &P, initial_block: u64) -> Address
+where
+    T: Transport + Clone,
+    P: Provider<T>,
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(ARTIFACT);
+    let json = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {} (run `npx hardhat compile` in the repository root)",
+            path.display(),
+            e
+        )
+    });
+    let artifact: Artifact = serde_json::from_str(&json).unwrap();
+    let code = [
+        artifact.bytecode.as_ref(),
+        &U256::from(initial_block).to_be_bytes::<32>(),
+    ]
+    .concat();
+    provider
+        .send_transaction(TransactionRequest::default().with_deploy_code(code))
+        .await
+        .unwrap()
+        .get_receipt()
+        .await
+        .unwrap()
+        .contract_address
+        .expect("Deployment created no contract")
+}
+
+#[tokio::test]
+async fn test_contract_accepts_rust_roots_and_proofs() {
+    let anvil = spawn_anvil();
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(deployer(&anvil)))
+        .on_http(anvil.endpoint_url());
+    let address = deploy(&provider, 99).await;
+    let bridge = WrappedMonero::new(address, &provider);
+    let mock = MockWrappedMonero::new(address, &provider);
+
+    // Odd counts, so each tree pairs its last node with itself
+    let tx_hashes: Vec<String> = (1..=5u8).map(|i| hex::encode([i; 32])).collect();
+    let outputs: Vec<MoneroOutput> = (1..=7u8)
+        .map(|i| MoneroOutput {
+            tx_hash: B256::repeat_byte(i),
+            output_index: u64::from(i % 3),
+            ecdh_amount: B256::repeat_byte(i + 0x10),
+            output_pub_key: B256::repeat_byte(i + 0x20),
+            commitment: B256::repeat_byte(i + 0x30),
+        })
+        .collect();
+    let posted = StoredBlock {
+        block_hash: B256::repeat_byte(0xbb),
+        tx_merkle_root: compute_tx_merkle_root(&tx_hashes, MerkleHasher::Keccak256),
+        output_merkle_root: compute_output_merkle_root(&outputs, MerkleHasher::Keccak256),
+    };
+    bridge
+        .postMoneroBlock(
+            U256::from(100),
+            posted.block_hash,
+            posted.tx_merkle_root,
+            posted.output_merkle_root,
+        )
+        .send()
+        .await
+        .unwrap()
+        .get_receipt()
+        .await
+        .unwrap();
+
+    assert_eq!(eth::get_block(&bridge, 100).await.unwrap(), Some(posted));
+    assert_eq!(eth::get_block(&bridge, 101).await.unwrap(), None);
+
+    for (index, tx_hash) in tx_hashes.iter().enumerate() {
+        let (root, proof) = tx_merkle_proof(&tx_hashes, index, MerkleHasher::Keccak256).unwrap();
+        assert_eq!(root, posted.tx_merkle_root);
+        let tx_hash: B256 = format!("0x{}", tx_hash).parse().unwrap();
+        let included = mock
+            .verifyTxInBlock(tx_hash, U256::from(100), proof, U256::from(index))
+            .call()
+            .await
+            .unwrap()
+            ._0;
+        assert!(included, "tx {} not accepted", index);
+    }
+
+    for (index, output) in outputs.iter().enumerate() {
+        let leaf = B256::from(output_leaf(output));
+        let contract_leaf = mock
+            .outputLeaf(
+                output.tx_hash,
+                U256::from(output.output_index),
+                output.ecdh_amount,
+                output.output_pub_key,
+                output.commitment,
+            )
+            .call()
+            .await
+            .unwrap()
+            ._0;
+        assert_eq!(leaf, contract_leaf, "output {} leaf", index);
+
+        let (root, proof) = output_merkle_proof(&outputs, index, MerkleHasher::Keccak256).unwrap();
+        assert_eq!(root, posted.output_merkle_root);
+        let included = mock
+            .verifyMerkleProof(leaf, root, proof.clone(), U256::from(index))
+            .call()
+            .await
+            .unwrap()
+            ._0;
+        assert!(included, "output {} not accepted", index);
+        // The index picks the hashing order at each level
+        if index ^ 1 < outputs.len() {
+            let misplaced = mock
+                .verifyMerkleProof(leaf, root, proof, U256::from(index ^ 1))
+                .call()
+                .await
+                .unwrap()
+                ._0;
+            assert!(!misplaced);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_oracle_posts_recorded_blocks() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let dirs: Vec<PathBuf> = fs::read_dir(&root)
+        .unwrap_or_else(|e| panic!("{}: {}", root.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|dir| dir.join(EXPECTED_FILE).is_file())
+        .collect();
+    assert!(!dirs.is_empty(), "No blocks recorded under tests/fixtures");
+
+    for dir in dirs {
+        let expected: RecordedBlock =
+            serde_json::from_str(&fs::read_to_string(dir.join(EXPECTED_FILE)).unwrap()).unwrap();
+        let monero = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+            .with_fixtures(Fixtures::Replay(dir.clone()));
+        let network = monero.get_info().await.unwrap().network();
+
+        let anvil = spawn_anvil();
+        let signer = deployer(&anvil);
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer.clone()))
+            .on_http(anvil.endpoint_url());
+        let address = deploy(&provider, expected.height - 1).await;
+
+        // The oracle reads its settings from the environment, as in production
+        let database = std::env::temp_dir().join(format!(
+            "monero-oracle-anvil-{}-{}.db",
+            expected.height,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&database);
+        for (key, value) in [
+            ("PRIVATE_KEY", hex::encode(signer.to_bytes())),
+            ("BRIDGE_ADDRESS", address.to_string()),
+            ("UNICHAIN_RPC_URL", anvil.endpoint()),
+            ("MONERO_RPC_URL", "http://127.0.0.1:9".to_string()),
+            ("MONERO_RPC_REPLAY_DIR", dir.display().to_string()),
+            ("MONERO_NETWORK", network.to_string()),
+            ("MIN_CONFIRMATIONS", "0".to_string()),
+            (
+                "OUTPUT_MERKLE_HASHER",
+                expected.output_merkle_hasher.to_string(),
+            ),
+            ("TX_MERKLE_HASHER", expected.tx_merkle_hasher.to_string()),
+            ("DATABASE_PATH", database.display().to_string()),
+        ] {
+            std::env::set_var(key, value);
+        }
+        OracleService::new(Config::from_env().unwrap())
+            .unwrap()
+            .backfill(expected.height, expected.height)
+            .await
+            .unwrap_or_else(|e| panic!("{}: {:#}", dir.display(), e));
+
+        let bridge = WrappedMonero::new(address, &provider);
+        assert_eq!(
+            eth::get_block(&bridge, expected.height).await.unwrap(),
+            Some(StoredBlock {
+                block_hash: expected.block_hash,
+                tx_merkle_root: expected.tx_merkle_root,
+                output_merkle_root: expected.output_merkle_root,
+            }),
+            "{}",
+            dir.display()
+        );
+        let latest = bridge.latestMoneroBlock().call().await.unwrap();
+        assert_eq!(latest.latestMoneroBlock, U256::from(expected.height));
+        fs::remove_file(&database).unwrap();
+    }
+}
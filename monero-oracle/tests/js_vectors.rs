@@ -0,0 +1,115 @@
+//! Checks `scripts/utils/merkle_vectors.json`, the roots and proofs the proof
+//! script's tests (`scripts/utils/compute_merkle_proof.test.js`) compare
+//! against, with the oracle's own trees. Each entry under `tx_trees` and
+//! `output_trees` is named after the [`MerkleHasher`] that builds it.
+//!
+//! After changing the block or a tree encoding, rewrite the expected values
+//! with `UPDATE_MERKLE_VECTORS=1 cargo test --test js_vectors`.
+
+use monero_oracle::{
+    merkle::{output_tree, tx_tree, MerkleHasher},
+    monero_rpc::{
+        outputs_from_transactions, parse_hex_to_b256, transaction_order, TransactionJson,
+    },
+};
+use serde_json::{json, Map, Value};
+use std::{fs, path::PathBuf};
+
+fn vectors_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../scripts/utils/merkle_vectors.json")
+}
+
+/// The block's transactions as the oracle fetches them, miner tx first
+fn block_transactions(block: &Value) -> Vec<(String, TransactionJson)> {
+    let miner = (
+        block["miner_tx_hash"].as_str().unwrap().to_string(),
+        serde_json::from_value(block["miner_tx"].clone()).unwrap(),
+    );
+    let transactions = block["transactions"].as_array().unwrap().iter().map(|tx| {
+        (
+            tx["tx_hash"].as_str().unwrap().to_string(),
+            serde_json::from_value(tx["as_json"].clone()).unwrap(),
+        )
+    });
+    std::iter::once(miner).chain(transactions).collect()
+}
+
+fn tx_trees(vectors: &Value) -> Map<String, Value> {
+    // The miner tx isn't in the tx tree
+    let tx_hashes: Vec<String> = block_transactions(&vectors["block"])
+        .into_iter()
+        .skip(1)
+        .map(|(tx_hash, _)| tx_hash)
+        .collect();
+    let target = vectors["tx_proof"]["tx_hash"].as_str().unwrap();
+    let leaf_index = tx_hashes
+        .iter()
+        .position(|tx_hash| tx_hash == target)
+        .expect("target tx is in the block");
+
+    let mut trees = Map::new();
+    for name in vectors["tx_trees"].as_object().unwrap().keys() {
+        let hasher: MerkleHasher = name.parse().unwrap();
+        let tree = tx_tree(&tx_hashes, hasher);
+        let proof = tree.proof(leaf_index).unwrap();
+        trees.insert(
+            name.clone(),
+            json!({
+                "root": tree.root(),
+                "leaf_index": leaf_index,
+                "siblings": proof.siblings,
+            }),
+        );
+    }
+    trees
+}
+
+fn output_trees(vectors: &Value) -> Map<String, Value> {
+    let transactions = block_transactions(&vectors["block"]);
+    let outputs = outputs_from_transactions(&transactions).unwrap();
+    let tx_order = transaction_order(&transactions).unwrap();
+    let target_tx =
+        parse_hex_to_b256(vectors["output_proof"]["tx_hash"].as_str().unwrap()).unwrap();
+    let target_index = vectors["output_proof"]["output_index"].as_u64().unwrap();
+
+    let mut trees = Map::new();
+    for name in vectors["output_trees"].as_object().unwrap().keys() {
+        let hasher: MerkleHasher = name.parse().unwrap();
+        let ordered = hasher.output_order(&outputs, &tx_order);
+        let leaf_index = ordered
+            .iter()
+            .position(|o| o.tx_hash == target_tx && o.output_index == target_index)
+            .expect("target output is in the block");
+        let tree = output_tree(&ordered, hasher);
+        let proof = tree.proof(leaf_index).unwrap();
+        trees.insert(
+            name.clone(),
+            json!({
+                "root": tree.root(),
+                "leaf_index": leaf_index,
+                "siblings": proof.siblings,
+            }),
+        );
+    }
+    trees
+}
+
+#[test]
+fn test_js_merkle_vectors_match_the_oracle() {
+    let path = vectors_path();
+    let json = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    let mut vectors: Value = serde_json::from_str(&json).unwrap();
+    let tx_trees = Value::Object(tx_trees(&vectors));
+    let output_trees = Value::Object(output_trees(&vectors));
+
+    if std::env::var_os("UPDATE_MERKLE_VECTORS").is_some() {
+        vectors["tx_trees"] = tx_trees;
+        vectors["output_trees"] = output_trees;
+        let mut json = serde_json::to_string_pretty(&vectors).unwrap();
+        json.push('\n');
+        fs::write(&path, json).unwrap();
+        return;
+    }
+    assert_eq!(vectors["tx_trees"], tx_trees);
+    assert_eq!(vectors["output_trees"], output_trees);
+}
@@ -0,0 +1,371 @@
+//! Property tests for the tx and output Merkle trees: proofs built for any
+//! leaf set verify against the posted root, odd levels duplicate their last
+//! node, and both agree with a reference transcription of the contract, in
+//! every version of either tree.
+//!
+//! `sol!` only generates types and bindings, it can't run contract code, so
+//! the reference below is WrappedMonero's verifiers and output leaf copied
+//! statement by statement, with `abi.encodePacked` done by alloy's Solidity
+//! encoder. `tests/anvil.rs` checks the same against the deployed bytecode.
+
+use alloy::{
+    primitives::{keccak256, FixedBytes, B256, U256},
+    sol,
+    sol_types::SolValue,
+};
+use monero_oracle::{
+    merkle::{
+        compute_output_merkle_root, compute_tx_merkle_root, merkle_root, output_leaf, MerkleHasher,
+    },
+    monero_rpc::MoneroOutput,
+    proof::{
+        output_merkle_proof, tx_merkle_proof, verify_output_proof, verify_tx_proof,
+        OutputInclusionProof, TxInclusionProof,
+    },
+};
+use proptest::prelude::*;
+use sha2::{Digest, Sha256};
+
+sol! {
+    /// WrappedMonero's output struct, as `mint` receives it
+    struct MoneroTxOutput {
+        bytes32 txHash;
+        uint256 outputIndex;
+        bytes32 ecdhAmount;
+        bytes32 outputPubKey;
+        bytes32 commitment;
+    }
+}
+
+/// `keccak256(abi.encodePacked(output.txHash, output.outputIndex, ...))` in `mint`
+fn reference_output_leaf(output: &MoneroTxOutput) -> B256 {
+    keccak256(
+        (
+            output.txHash,
+            output.outputIndex,
+            output.ecdhAmount,
+            output.outputPubKey,
+            output.commitment,
+        )
+            .abi_encode_packed(),
+    )
+}
+
+/// `outputLeafV2`
+fn reference_output_leaf_v2(output: &MoneroTxOutput) -> B256 {
+    keccak256(
+        (
+            LEAF_PREFIX,
+            output.txHash,
+            output.outputIndex,
+            output.ecdhAmount,
+            output.outputPubKey,
+            output.commitment,
+        )
+            .abi_encode_packed(),
+    )
+}
+
+/// The leaf `verifyTxInBlock` folds from `txHash`: the hash itself in
+/// version 1, `keccak256(abi.encodePacked(LEAF_PREFIX, txHash))` in version 2
+fn reference_tx_leaf(tx_hash: B256, hasher: MerkleHasher) -> B256 {
+    match hasher {
+        MerkleHasher::V2 => keccak256((LEAF_PREFIX, tx_hash).abi_encode_packed()),
+        MerkleHasher::Keccak256 | MerkleHasher::Sha256 => tx_hash,
+    }
+}
+
+// The contract's uint8 prefixes, shared by both trees; packed, a uint8 is one
+// byte like a bytes1
+const LEAF_PREFIX: FixedBytes<1> = FixedBytes([0x00]);
+const NODE_PREFIX: FixedBytes<1> = FixedBytes([0x01]);
+const ROOT_PREFIX: FixedBytes<1> = FixedBytes([0x02]);
+const VERSION_2: FixedBytes<1> = FixedBytes([2]);
+
+/// `verifyMerkleProof`, or `verifyMerkleProofSHA256` for the legacy hasher,
+/// or `verifyOutputProofV2`. `verifyTxInBlock` runs the keccak256 loop
+/// against the stored root.
+fn reference_verify(
+    leaf: B256,
+    root: B256,
+    proof: &[B256],
+    mut index: U256,
+    hasher: MerkleHasher,
+) -> bool {
+    let hash = |left: B256, right: B256| match hasher {
+        MerkleHasher::Keccak256 => keccak256((left, right).abi_encode_packed()),
+        MerkleHasher::Sha256 => {
+            B256::from_slice(&Sha256::digest((left, right).abi_encode_packed()))
+        }
+        MerkleHasher::V2 => keccak256((NODE_PREFIX, left, right).abi_encode_packed()),
+    };
+    let mut computed_hash = leaf;
+    for proof_element in proof {
+        computed_hash = if index % U256::from(2) == U256::ZERO {
+            hash(computed_hash, *proof_element)
+        } else {
+            hash(*proof_element, computed_hash)
+        };
+        index /= U256::from(2);
+    }
+    match hasher {
+        MerkleHasher::V2 => {
+            keccak256((ROOT_PREFIX, VERSION_2, computed_hash).abi_encode_packed()) == root
+        }
+        MerkleHasher::Keccak256 | MerkleHasher::Sha256 => computed_hash == root,
+    }
+}
+
+fn hashers() -> impl Strategy<Value = MerkleHasher> {
+    prop_oneof![
+        Just(MerkleHasher::Keccak256),
+        Just(MerkleHasher::Sha256),
+        Just(MerkleHasher::V2)
+    ]
+}
+
+fn output() -> impl Strategy<Value = MoneroOutput> {
+    (
+        any::<[u8; 32]>(),
+        any::<u64>(),
+        any::<[u8; 32]>(),
+        any::<[u8; 32]>(),
+        any::<[u8; 32]>(),
+    )
+        .prop_map(
+            |(tx_hash, output_index, ecdh_amount, output_pub_key, commitment)| MoneroOutput {
+                tx_hash: tx_hash.into(),
+                output_index,
+                ecdh_amount: ecdh_amount.into(),
+                output_pub_key: output_pub_key.into(),
+                commitment: commitment.into(),
+            },
+        )
+}
+
+/// A non-empty leaf set and a position in it
+fn outputs_and_index() -> impl Strategy<Value = (Vec<MoneroOutput>, usize)> {
+    prop::collection::vec(output(), 1..70)
+        .prop_flat_map(|outputs| (0..outputs.len(), Just(outputs)))
+        .prop_map(|(index, outputs)| (outputs, index))
+}
+
+/// A block's tx hashes in block order, its outputs in leaf order, and the
+/// same outputs shuffled
+type BlockOutputs = (Vec<B256>, Vec<MoneroOutput>, Vec<MoneroOutput>);
+
+fn block_outputs() -> impl Strategy<Value = BlockOutputs> {
+    prop::collection::hash_map(any::<[u8; 32]>(), (output(), 1..5u64), 1..12).prop_flat_map(|txs| {
+        let txs: Vec<_> = txs.into_iter().collect();
+        let tx_order: Vec<B256> = txs
+            .iter()
+            .map(|(tx_hash, _)| B256::from(*tx_hash))
+            .collect();
+        let outputs: Vec<MoneroOutput> = txs
+            .into_iter()
+            .flat_map(|(tx_hash, (output, count))| {
+                (0..count).map(move |output_index| MoneroOutput {
+                    tx_hash: tx_hash.into(),
+                    output_index,
+                    ..output.clone()
+                })
+            })
+            .collect();
+        (
+            Just(tx_order),
+            Just(outputs.clone()),
+            Just(outputs).prop_shuffle(),
+        )
+    })
+}
+
+fn tx_hashes_and_index() -> impl Strategy<Value = (Vec<String>, usize)> {
+    prop::collection::vec(any::<[u8; 32]>().prop_map(hex::encode), 1..70)
+        .prop_flat_map(|hashes| (0..hashes.len(), Just(hashes)))
+        .prop_map(|(index, hashes)| (hashes, index))
+}
+
+proptest! {
+    #[test]
+    fn output_proofs_round_trip(
+        (outputs, index) in outputs_and_index(),
+        hasher in hashers(),
+    ) {
+        let root = compute_output_merkle_root(&outputs, hasher);
+        let (proof_root, path) = output_merkle_proof(&outputs, index, hasher).unwrap();
+        prop_assert_eq!(proof_root, root);
+        // One sibling per level above the leaves
+        let depth = usize::BITS - (outputs.len() - 1).leading_zeros();
+        prop_assert_eq!(path.len(), depth as usize);
+
+        let output = &outputs[index];
+        let leaf = B256::from(hasher.output_leaf(output));
+        let proof = OutputInclusionProof {
+            block_height: 1,
+            block_hash: B256::ZERO,
+            output: output.clone(),
+            leaf_index: index as u64,
+            leaf,
+            output_merkle_root: root,
+            proof: path.clone(),
+            hasher,
+            verifier: hasher.verifier().to_string(),
+        };
+        prop_assert!(verify_output_proof(&proof));
+        prop_assert!(reference_verify(leaf, root, &path, U256::from(index), hasher));
+
+        // Any other leaf at that position fails, in Rust and in the contract
+        let mut forged = output.clone();
+        forged.output_index = forged.output_index.wrapping_add(1);
+        let forged_leaf = B256::from(hasher.output_leaf(&forged));
+        let forged_proof = OutputInclusionProof {
+            output: forged,
+            leaf: forged_leaf,
+            ..proof
+        };
+        prop_assert!(!verify_output_proof(&forged_proof));
+        prop_assert!(!reference_verify(forged_leaf, root, &path, U256::from(index), hasher));
+    }
+
+    #[test]
+    fn v2_root_ignores_output_order(
+        (tx_order, in_order, shuffled) in block_outputs(),
+    ) {
+        // However a node lists them, the leaves follow the block's tx order
+        let hasher = MerkleHasher::V2;
+        let ordered = hasher.output_order(&shuffled, &tx_order);
+        prop_assert_eq!(&ordered[..], &in_order[..]);
+        prop_assert_eq!(
+            compute_output_merkle_root(&ordered, hasher),
+            compute_output_merkle_root(&in_order, hasher)
+        );
+    }
+
+    #[test]
+    fn tx_proofs_round_trip(
+        (tx_hashes, index) in tx_hashes_and_index(),
+        hasher in prop_oneof![Just(MerkleHasher::Keccak256), Just(MerkleHasher::V2)],
+    ) {
+        let root = compute_tx_merkle_root(&tx_hashes, hasher);
+        let (proof_root, path) = tx_merkle_proof(&tx_hashes, index, hasher).unwrap();
+        prop_assert_eq!(proof_root, root);
+
+        let tx_hash: B256 = format!("0x{}", tx_hashes[index]).parse().unwrap();
+        let proof = TxInclusionProof {
+            block_height: 1,
+            block_hash: B256::ZERO,
+            tx_hash,
+            tx_index: index as u64,
+            tx_merkle_root: root,
+            proof: path.clone(),
+            hasher,
+        };
+        prop_assert!(verify_tx_proof(&proof));
+        let depth = usize::BITS - (tx_hashes.len() - 1).leading_zeros();
+        prop_assert_eq!(path.len(), depth as usize);
+        let leaf = reference_tx_leaf(tx_hash, hasher);
+        prop_assert!(reference_verify(leaf, root, &path, U256::from(index), hasher));
+
+        // Any other transaction at that position fails, in Rust and in the
+        // contract
+        let forged = B256::from(U256::from_be_bytes(tx_hash.0).wrapping_add(U256::from(1)));
+        let forged_proof = TxInclusionProof {
+            tx_hash: forged,
+            ..proof
+        };
+        prop_assert!(!verify_tx_proof(&forged_proof));
+        let forged_leaf = reference_tx_leaf(forged, hasher);
+        prop_assert!(!reference_verify(forged_leaf, root, &path, U256::from(index), hasher));
+    }
+
+    #[test]
+    fn tx_tree_duplicates_its_last_transaction(
+        tx_hashes in prop::collection::vec(any::<[u8; 32]>().prop_map(hex::encode), 1..70),
+        hasher in prop_oneof![Just(MerkleHasher::Keccak256), Just(MerkleHasher::V2)],
+    ) {
+        let root = compute_tx_merkle_root(&tx_hashes, hasher);
+        if tx_hashes.len() % 2 == 1 && tx_hashes.len() > 1 {
+            let mut padded = tx_hashes.clone();
+            padded.push(tx_hashes.last().unwrap().clone());
+            prop_assert_eq!(compute_tx_merkle_root(&padded, hasher), root);
+        }
+        // The root is over the leaves as the contract forms them
+        let leaves: Vec<[u8; 32]> = tx_hashes
+            .iter()
+            .map(|tx_hash| {
+                let tx_hash: B256 = format!("0x{}", tx_hash).parse().unwrap();
+                reference_tx_leaf(tx_hash, hasher).0
+            })
+            .collect();
+        prop_assert_eq!(merkle_root(leaves, hasher), root);
+    }
+
+    #[test]
+    fn tx_leaf_matches_contract_encoding(
+        tx_hash in any::<[u8; 32]>(),
+        hasher in prop_oneof![Just(MerkleHasher::Keccak256), Just(MerkleHasher::V2)],
+    ) {
+        prop_assert_eq!(
+            B256::from(hasher.tx_leaf(&tx_hash)),
+            reference_tx_leaf(tx_hash.into(), hasher)
+        );
+    }
+
+    #[test]
+    fn odd_levels_duplicate_their_last_node(
+        leaves in prop::collection::vec(any::<[u8; 32]>(), 1..70),
+        hasher in hashers(),
+    ) {
+        let root = merkle_root(leaves.clone(), hasher);
+        if leaves.len() == 1 {
+            // Version 2 roots commit to the version even over a single leaf
+            prop_assert_eq!(root == B256::from(leaves[0]), hasher != MerkleHasher::V2);
+        } else if leaves.len() % 2 == 1 {
+            let mut padded = leaves.clone();
+            padded.push(*leaves.last().unwrap());
+            prop_assert_eq!(merkle_root(padded, hasher), root);
+        } else {
+            // Pairing happens level by level: the first level matches the
+            // hand-built pairs
+            let paired: Vec<[u8; 32]> = leaves
+                .chunks(2)
+                .map(|pair| hasher.hash_pair(&pair[0], &pair[1]))
+                .collect();
+            prop_assert_eq!(merkle_root(paired, hasher), root);
+        }
+    }
+
+    #[test]
+    fn output_leaf_matches_contract_encoding(output in output()) {
+        let reference = reference_output_leaf(&MoneroTxOutput {
+            txHash: output.tx_hash,
+            outputIndex: U256::from(output.output_index),
+            ecdhAmount: output.ecdh_amount,
+            outputPubKey: output.output_pub_key,
+            commitment: output.commitment,
+        });
+        prop_assert_eq!(B256::from(output_leaf(&output)), reference);
+    }
+
+    #[test]
+    fn output_leaf_v2_matches_contract_encoding(output in output()) {
+        let reference = reference_output_leaf_v2(&MoneroTxOutput {
+            txHash: output.tx_hash,
+            outputIndex: U256::from(output.output_index),
+            ecdhAmount: output.ecdh_amount,
+            outputPubKey: output.output_pub_key,
+            commitment: output.commitment,
+        });
+        prop_assert_eq!(B256::from(MerkleHasher::V2.output_leaf(&output)), reference);
+    }
+}
+
+#[test]
+fn test_empty_tree_root_is_zero() {
+    assert_eq!(merkle_root(vec![], MerkleHasher::Keccak256), B256::ZERO);
+    assert_eq!(
+        compute_tx_merkle_root(&[], MerkleHasher::Keccak256),
+        B256::ZERO
+    );
+    assert!(output_merkle_proof(&[], 0, MerkleHasher::Keccak256).is_none());
+}
@@ -0,0 +1,104 @@
+//! Recomputes every block recorded under `tests/fixtures` from its saved
+//! monerod responses and checks the result against `expected.json`.
+//! Record a block with `monero-oracle record-fixture <height>`. A missing or
+//! empty fixture directory fails rather than checking nothing.
+//!
+//! `expected.json` is what the oracle computed when the block was recorded,
+//! so it only catches changes. The mainnet genesis block is also checked
+//! against values published for the chain, and its output root against one
+//! built here from them, without the crate's Merkle or commitment code.
+
+use alloy::primitives::{keccak256, B256, U256};
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY, scalar::Scalar,
+};
+use monero_oracle::{
+    fixtures::{Fixtures, RecordedBlock, EXPECTED_FILE},
+    merkle::MerkleHasher,
+    monero_rpc::MoneroRpcClient,
+};
+use std::{fs, path::PathBuf};
+
+/// Mainnet's genesis block, its miner tx and that tx's one output: the
+/// block reward of 17592186044415 piconero, before RingCT
+const GENESIS_HASH: &str = "418015bb9ae982a1975da7d79277c2705727a56894ba0fb246adaabb1f4632e3";
+const GENESIS_MINER_TX: &str = "c88ce9783b4f11190d7b9c17a69c1c52200f9faaee8e98dd07e6811175177139";
+const GENESIS_OUTPUT_KEY: &str = "9b2e4c0281c0b02e7c53291a94d1d0cbff8883f8024f5142ee494ffbbd088071";
+const GENESIS_REWARD: u64 = 17_592_186_044_415;
+
+/// RingCT's second generator H (`rctTypes.h`)
+const PEDERSEN_H: &str = "8b655970153799af2aeadc9ff1add0ea6c7251d54154cfa92c173a0dd39c1f94";
+
+fn recorded_blocks() -> Vec<(PathBuf, RecordedBlock)> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut blocks: Vec<_> = fs::read_dir(&root)
+        .unwrap_or_else(|e| panic!("{}: {}", root.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|dir| dir.join(EXPECTED_FILE).is_file())
+        .map(|dir| {
+            let json = fs::read_to_string(dir.join(EXPECTED_FILE)).unwrap();
+            let expected: RecordedBlock =
+                serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+            (dir, expected)
+        })
+        .collect();
+    blocks.sort_by_key(|(_, block)| block.height);
+    blocks
+}
+
+#[tokio::test]
+async fn test_recorded_blocks_replay() {
+    let blocks = recorded_blocks();
+    assert!(
+        !blocks.is_empty(),
+        "No blocks recorded under tests/fixtures"
+    );
+    for (dir, expected) in blocks {
+        // Nothing listens there: a request that wasn't recorded fails
+        let client = MoneroRpcClient::new("http://127.0.0.1:9".to_string())
+            .with_fixtures(Fixtures::Replay(dir.clone()));
+        let computed = RecordedBlock::compute(
+            &client,
+            expected.height,
+            expected.tx_merkle_hasher,
+            expected.output_merkle_hasher,
+        )
+        .await
+        .unwrap_or_else(|e| panic!("{}: {:#}", dir.display(), e));
+        assert_eq!(computed, expected, "{}", dir.display());
+    }
+}
+
+#[tokio::test]
+async fn test_genesis_block_matches_mainnet() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mainnet-0");
+    let client =
+        MoneroRpcClient::new("http://127.0.0.1:9".to_string()).with_fixtures(Fixtures::Replay(dir));
+    let block = client.fetch_block(0).await.unwrap();
+    assert_eq!(block.header.hash, GENESIS_HASH);
+    assert_eq!(block.miner_tx_hash(), GENESIS_MINER_TX);
+    assert!(block.tx_hashes.is_empty());
+
+    // The one leaf is the root: txHash ‖ uint256(0) ‖ reward (little-endian,
+    // in the last 8 bytes) ‖ output key ‖ G + reward·H
+    let mut amount = [0u8; 32];
+    amount[24..].copy_from_slice(&GENESIS_REWARD.to_le_bytes());
+    let h = CompressedEdwardsY(hex::decode(PEDERSEN_H).unwrap().try_into().unwrap())
+        .decompress()
+        .unwrap();
+    let commitment = ED25519_BASEPOINT_POINT + Scalar::from(GENESIS_REWARD) * h;
+    let mut leaf = hex::decode(GENESIS_MINER_TX).unwrap();
+    leaf.extend_from_slice(&U256::ZERO.to_be_bytes::<32>());
+    leaf.extend_from_slice(&amount);
+    leaf.extend_from_slice(&hex::decode(GENESIS_OUTPUT_KEY).unwrap());
+    leaf.extend_from_slice(commitment.compress().as_bytes());
+
+    let computed =
+        RecordedBlock::compute(&client, 0, MerkleHasher::Keccak256, MerkleHasher::Keccak256)
+            .await
+            .unwrap();
+    assert_eq!(computed.block_hash, GENESIS_HASH.parse::<B256>().unwrap());
+    assert_eq!(computed.tx_merkle_root, B256::ZERO);
+    assert_eq!(computed.output_merkle_root, keccak256(&leaf));
+    assert_eq!(computed.output_count, 1);
+}
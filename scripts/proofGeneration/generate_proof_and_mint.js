@@ -78,12 +78,16 @@ async function main() {
     // Set Monero RPC URL to match oracle
     process.env.MONERO_RPC_URL = 'http://xmr.privex.io:18081/json_rpc';
     const { computeTxMerkleProof, computeOutputMerkleProof } = require('../utils/compute_merkle_proof.js');
-    const merkleData = await computeTxMerkleProof(BLOCK_HEIGHT, TX_HASH, OUTPUT_INDEX);
+    const txTreeVersion = await bridge.txTreeVersionOf(BLOCK_HEIGHT);
+    const merkleData = await computeTxMerkleProof(BLOCK_HEIGHT, TX_HASH, { version: txTreeVersion });
     const txMerkleProof = merkleData.proof;
     const txIndex = merkleData.txIndex;
     
-    // Compute output Merkle proof
-    const outputMerkleData = await computeOutputMerkleProof(BLOCK_HEIGHT, TX_HASH, OUTPUT_INDEX);
+    // Compute output Merkle proof, in the tree version the block was posted with
+    const outputTreeVersion = await bridge.outputTreeVersionOf(BLOCK_HEIGHT);
+    const outputMerkleData = await computeOutputMerkleProof(BLOCK_HEIGHT, TX_HASH, OUTPUT_INDEX, {
+        version: outputTreeVersion
+    });
     const outputMerkleProof = outputMerkleData.proof;
     const globalOutputIndex = outputMerkleData.outputIndex;
     
@@ -1,14 +1,19 @@
 const axios = require('axios');
 const crypto = require('crypto');
 const { keccak_256 } = require('js-sha3');
+const ed = require('@noble/ed25519');
 
 // Monero RPC endpoint
 const MONERO_RPC = process.env.MONERO_RPC_URL || 'http://node.moneroworld.com:18089/json_rpc';
 
+// Pedersen commitment generator H (Monero's rctTypes.h)
+const PEDERSEN_H = '8b655970153799af2aeadc9ff1add0ea6c7251d54154cfa92c173a0dd39c1f94';
+
 /**
- * Compute Merkle proof for a transaction in a block
+ * Compute Merkle proof for a transaction in a block posted with tx tree
+ * version options.version (the contract's txTreeVersionOf(blockHeight))
  */
-async function computeTxMerkleProof(blockHeight, txHash) {
+async function computeTxMerkleProof(blockHeight, txHash, options = {}) {
     console.log(`\nComputing Merkle proof for TX ${txHash} in block ${blockHeight}...`);
     
     // 1. Get block data
@@ -27,24 +32,17 @@ async function computeTxMerkleProof(blockHeight, txHash) {
     
     const block = blockResponse.data.result;
     // NOTE: Oracle does NOT include miner TX in TX Merkle tree
-    const txHashes = block.tx_hashes;
+    const txHashes = block.tx_hashes || [];
     
     console.log(`  Block has ${txHashes.length} transactions`);
+    console.log(`  TX tree version: ${Number(options.version) || 1}`);
     
-    // 2. Find transaction index
-    // Normalize txHash to remove 0x prefix if present
-    const normalizedTxHash = txHash.startsWith('0x') ? txHash.slice(2) : txHash;
-    const txIndex = txHashes.findIndex(hash => hash === normalizedTxHash);
-    if (txIndex === -1) {
-        throw new Error(`Transaction ${txHash} not found in block ${blockHeight}`);
-    }
+    // 2. Build Merkle tree and compute proof
+    const { txIndex, proof, root } = txMerkleProof(txHashes, txHash, options);
     
     console.log(`  Transaction found at index ${txIndex}`);
-    
-    // 3. Build Merkle tree and compute proof
-    const proof = computeMerkleProofFromLeaves(txHashes, txIndex);
-    
     console.log(`  Merkle proof has ${proof.length} siblings`);
+    console.log(`  Computed root: ${root}`);
     
     return {
         txIndex,
@@ -54,168 +52,188 @@ async function computeTxMerkleProof(blockHeight, txHash) {
 }
 
 /**
- * Compute Merkle proof from list of leaves
- * Matches oracle implementation: uses SHA256 and duplicates last hash for odd numbers
+ * TX Merkle proof for `txHash` among the block's `txHashes` (miner tx
+ * excluded), against the root the oracle posted with tx tree version
+ * options.version (see treeEncoding; defaults to 1)
  */
-function computeMerkleProofFromLeaves(leaves, targetIndex) {
-    const { keccak256: ethersKeccak256, concat } = require('ethers');
-    const proof = [];
-    let currentLevel = leaves.map(leaf => '0x' + leaf);
-    let currentIndex = targetIndex;
+function txMerkleProof(txHashes, txHash, options = {}) {
+    const encoding = treeEncoding(options.version);
+    if (encoding.version === 3) {
+        throw new Error('Unknown tx tree version 3; only output trees have it');
+    }
     
-    while (currentLevel.length > 1) {
-        const nextLevel = [];
-        
-        for (let i = 0; i < currentLevel.length; i += 2) {
-            const left = currentLevel[i];
-            // Duplicate last hash for odd number (matches oracle)
-            const right = i + 1 < currentLevel.length ? currentLevel[i + 1] : left;
-            
-            // If this pair contains our target, save the sibling
-            if (i === currentIndex || i + 1 === currentIndex) {
-                const sibling = (i === currentIndex) ? right : left;
-                proof.push(sibling);
-            }
-            
-            // Hash pair using Ethereum's keccak256 (matches Solidity abi.encodePacked)
-            const hash = ethersKeccak256(concat([left, right]));
-            nextLevel.push(hash);
-        }
-        
-        // Move to next level
-        currentLevel = nextLevel;
-        currentIndex = Math.floor(currentIndex / 2);
+    // Normalize txHash to remove 0x prefix if present
+    const normalizedTxHash = txHash.startsWith('0x') ? txHash.slice(2) : txHash;
+    const txIndex = txHashes.findIndex(hash => hash === normalizedTxHash);
+    if (txIndex === -1) {
+        throw new Error(`Transaction ${txHash} not found in block`);
     }
     
-    return proof;
+    const leaves = txHashes.map(hash => encoding.txLeaf(Buffer.from(hash, 'hex')));
+    const { proof, root } = merkleProof(leaves, txIndex, encoding);
+    
+    return { txIndex, proof, root };
 }
 
 /**
- * Compute output Merkle proof
+ * Commitment G + amount·H that RingCT gives a cleartext (miner tx) amount,
+ * as the oracle's zero_commit computes it
  */
-async function computeOutputMerkleProof(blockHeight, txHash, outputIndex) {
-    console.log(`\nComputing output Merkle proof for output ${outputIndex} in TX ${txHash}...`);
-    
-    // 1. Get block data to get all transaction hashes
-    const blockResponse = await axios.post(MONERO_RPC, {
-        jsonrpc: '2.0',
-        id: '0',
-        method: 'get_block',
-        params: { height: blockHeight }
-    });
-    
-    if (blockResponse.data.error) {
-        throw new Error(`Failed to get block: ${blockResponse.data.error.message}`);
-    }
-    
-    const block = blockResponse.data.result;
-    const allTxHashes = block.tx_hashes || [];
-    
-    if (allTxHashes.length === 0) {
-        console.log(`  No transactions in block`);
-        return { outputIndex: 0, proof: [] };
-    }
-    
-    console.log(`  Block has ${allTxHashes.length} transactions`);
-    console.log(`  Fetching transaction details...`);
-    
-    // 2. Fetch all transactions using REST API endpoint
-    const rpcUrl = MONERO_RPC.replace('/json_rpc', '');
-    const txResponse = await axios.post(`${rpcUrl}/get_transactions`, {
-        txs_hashes: allTxHashes,
-        decode_as_json: true
-    });
-    
-    if (txResponse.data.status !== 'OK') {
-        throw new Error(`Failed to get transactions: ${txResponse.data.status}`);
-    }
-    
-    const transactions = txResponse.data.txs || [];
-    console.log(`  Fetched ${transactions.length} transactions`);
-    
-    // 3. Extract all outputs from all transactions
-    const { keccak256: ethersKeccak256, concat, zeroPadValue, toBeHex } = require('ethers');
-    const crypto = require('crypto');
-    
-    const allOutputs = [];
-    let targetGlobalIndex = -1;
-    let currentGlobalIndex = 0;
-    
-    // Normalize target TX hash
-    const normalizedTargetTx = txHash.startsWith('0x') ? txHash.slice(2) : txHash;
-    
-    for (const tx of transactions) {
-        const txJson = JSON.parse(tx.as_json);
-        const currentTxHash = tx.tx_hash;
+function zeroCommit(amount) {
+    const H = ed.ExtendedPoint.fromHex(PEDERSEN_H);
+    const commitment = ed.ExtendedPoint.BASE.add(H.multiplyUnsafe(BigInt(amount)));
+    return Buffer.from(commitment.toRawBytes()).toString('hex');
+}
+
+/**
+ * Outputs committed to by the output Merkle tree, matching the oracle's
+ * outputs_from_transactions: the miner tx's outputs first, then those of the
+ * block's other transactions in block order.
+ *
+ * Miner tx outputs have no ecdhInfo or outPk: their leaf takes the cleartext
+ * amount (8 bytes little-endian in the last 8 bytes of the field) and
+ * zeroCommit(amount).
+ *
+ * @param block { minerTxHash, minerTx, transactions: [{ txHash, json }] }
+ */
+function blockOutputs(block) {
+    const transactions = [
+        { txHash: block.minerTxHash, json: block.minerTx },
+        ...block.transactions
+    ];
+    const outputs = [];
+    
+    for (const { txHash, json } of transactions) {
+        const vout = json.vout;
+        if (!vout) {
+            continue;
+        }
         
-        const vout = txJson.vout || [];
-        const rctSigs = txJson.rct_signatures || {};
-        const ecdhInfo = rctSigs.ecdhInfo || [];
-        const outPk = rctSigs.outPk || [];
+        const coinbase = (json.vin || []).some(input => input.gen);
+        const rctSigs = json.rct_signatures;
+        if (!rctSigs && !coinbase) {
+            continue;
+        }
+        const ecdhInfo = rctSigs?.ecdhInfo || [];
+        const outPk = rctSigs?.outPk || [];
         
         for (let i = 0; i < vout.length; i++) {
             const output = vout[i];
             
-            // Extract output public key
-            let outputPubKey;
-            if (output.target?.key) {
-                outputPubKey = output.target.key;
-            } else if (output.target?.tagged_key?.key) {
-                outputPubKey = output.target.tagged_key.key;
-            } else {
+            // Output public key, before or after view tags
+            const outputPubKey = output.target?.key || output.target?.tagged_key?.key;
+            if (!outputPubKey) {
                 continue;
             }
             
-            // Get ECDH amount and commitment
-            const ecdhAmount = ecdhInfo[i]?.amount || '0'.repeat(16);
-            const commitment = outPk[i] || '0'.repeat(64);
-            
-            // Create output data matching oracle format
-            const outputData = {
-                txHash: '0x' + currentTxHash,
-                outputIndex: i,
-                ecdhAmount: '0x' + ecdhAmount.padStart(64, '0'),
-                outputPubKey: '0x' + outputPubKey,
-                commitment: '0x' + commitment
-            };
-            
-            allOutputs.push(outputData);
-            
-            // Check if this is our target output
-            if (currentTxHash === normalizedTargetTx && i === outputIndex) {
-                targetGlobalIndex = currentGlobalIndex;
+            let ecdhAmount;
+            let commitment;
+            if (coinbase) {
+                const amount = Buffer.alloc(32);
+                amount.writeBigUInt64LE(BigInt(output.amount), 24);
+                ecdhAmount = amount.toString('hex');
+                commitment = zeroCommit(output.amount);
+            } else {
+                if (!ecdhInfo[i] || !outPk[i]) {
+                    continue;
+                }
+                ecdhAmount = ecdhInfo[i].amount.padStart(64, '0');
+                commitment = outPk[i];
             }
             
-            currentGlobalIndex++;
+            outputs.push({ txHash, outputIndex: i, ecdhAmount, outputPubKey, commitment });
         }
     }
     
-    if (targetGlobalIndex === -1) {
-        throw new Error(`Output ${outputIndex} in TX ${txHash} not found in block`);
+    return outputs;
+}
+
+// Domain-separation prefixes of version 2 tx and output trees
+const LEAF_PREFIX = 0x00;
+const NODE_PREFIX = 0x01;
+const ROOT_PREFIX = 0x02;
+
+function keccak(...parts) {
+    return Buffer.from(keccak_256.arrayBuffer(Buffer.concat(parts)));
+}
+
+/**
+ * abi.encodePacked(txHash, outputIndex, ecdhAmount, outputPubKey, commitment)
+ */
+function packOutput(output) {
+    const outputIndex = Buffer.alloc(32);
+    outputIndex.writeBigUInt64BE(BigInt(output.outputIndex), 24);
+    return Buffer.concat([
+        Buffer.from(output.txHash, 'hex'),
+        outputIndex,
+        Buffer.from(output.ecdhAmount, 'hex'),
+        Buffer.from(output.outputPubKey, 'hex'),
+        Buffer.from(output.commitment, 'hex')
+    ]);
+}
+
+/**
+ * Tx and output tree encoding of `version`, as the contract's
+ * txTreeVersionOf / outputTreeVersionOf record it for a block (0, for blocks
+ * posted before versions were recorded, is version 1):
+ *
+ * - Version 1: tx leaf is the tx hash itself, output leaf
+ *   keccak256(packed output), node keccak256(left ‖ right); the top node is
+ *   the root
+ * - Version 2: tx leaf keccak256(0x00 ‖ txHash), output leaf
+ *   keccak256(0x00 ‖ packed output), node keccak256(0x01 ‖ left ‖ right),
+ *   root keccak256(0x02 ‖ uint8(2) ‖ top); outputs in canonical order
+ * - Version 3 (output trees of legacy contracts): version 1 with
+ *   sha256(left ‖ right) nodes
+ */
+function treeEncoding(version) {
+    version = Number(version) || 1;
+    if (version === 1 || version === 3) {
+        return {
+            version,
+            txLeaf: txHash => txHash,
+            outputLeaf: output => keccak(packOutput(output)),
+            node: version === 3
+                ? (left, right) => crypto.createHash('sha256').update(left).update(right).digest()
+                : (left, right) => keccak(left, right),
+            root: top => top
+        };
     }
-    
-    console.log(`  Total outputs in block: ${allOutputs.length}`);
-    console.log(`  Target output global index: ${targetGlobalIndex}`);
-    
-    // 4. Build output Merkle tree using SHA256 (matches oracle)
-    // Leaves are keccak256(abi.encodePacked(txHash, outputIndex, ecdhAmount, outputPubKey, commitment))
-    
-    const leaves = allOutputs.map(output => {
-        // Pack data like Solidity's abi.encodePacked
-        const packed = concat([
-            output.txHash,
-            zeroPadValue(toBeHex(output.outputIndex), 32),
-            output.ecdhAmount,
-            output.outputPubKey,
-            output.commitment
-        ]);
-        return Buffer.from(ethersKeccak256(packed).slice(2), 'hex');
-    });
-    
-    // 5. Compute Merkle proof using SHA256 for hashing
+    if (version === 2) {
+        return {
+            version,
+            txLeaf: txHash => keccak(Buffer.from([LEAF_PREFIX]), txHash),
+            outputLeaf: output => keccak(Buffer.from([LEAF_PREFIX]), packOutput(output)),
+            node: (left, right) => keccak(Buffer.from([NODE_PREFIX]), left, right),
+            root: top => keccak(Buffer.from([ROOT_PREFIX, version]), top)
+        };
+    }
+    throw new Error(`Unknown tree version ${version}`);
+}
+
+/**
+ * Version 2 leaf order: by the transaction's position in the block (miner tx
+ * first), then by output index
+ */
+function canonicalOrder(outputs, block) {
+    const txIndex = new Map(
+        [block.minerTxHash, ...block.transactions.map(tx => tx.txHash)]
+            .map((txHash, index) => [txHash, index])
+    );
+    const position = output => txIndex.get(output.txHash) ?? Number.MAX_SAFE_INTEGER;
+    return [...outputs].sort(
+        (a, b) => position(a) - position(b) || a.outputIndex - b.outputIndex
+    );
+}
+
+/**
+ * Sibling path for leaf `index` and the root the contract holds; odd levels
+ * duplicate their last node (matches oracle)
+ */
+function merkleProof(leaves, index, encoding) {
     const proof = [];
     let currentLevel = leaves;
-    let currentIndex = targetGlobalIndex;
+    let currentIndex = index;
     
     while (currentLevel.length > 1) {
         const nextLevel = [];
@@ -230,48 +248,127 @@ async function computeOutputMerkleProof(blockHeight, txHash, outputIndex) {
                 proof.push('0x' + sibling.toString('hex'));
             }
             
-            // Hash using SHA256 (matches oracle)
-            const hasher = crypto.createHash('sha256');
-            hasher.update(left);
-            hasher.update(right);
-            nextLevel.push(hasher.digest());
+            nextLevel.push(encoding.node(left, right));
         }
         
         currentLevel = nextLevel;
         currentIndex = Math.floor(currentIndex / 2);
     }
     
-    console.log(`  Output Merkle proof has ${proof.length} siblings`);
+    return {
+        proof,
+        root: '0x' + encoding.root(currentLevel[0]).toString('hex')
+    };
+}
+
+/**
+ * Output Merkle proof for output `outputIndex` of `txHash` in `block` (see
+ * blockOutputs), against the root the oracle posted with tree version
+ * options.version (see treeEncoding; defaults to 1)
+ */
+function outputMerkleProof(block, txHash, outputIndex, options = {}) {
+    const encoding = treeEncoding(options.version);
+    let outputs = blockOutputs(block);
+    if (encoding.version === 2) {
+        outputs = canonicalOrder(outputs, block);
+    }
     
-    // Debug: Verify proof by computing root
-    let computedHash = leaves[targetGlobalIndex];
-    let idx = targetGlobalIndex;
-    for (let i = 0; i < proof.length; i++) {
-        const sibling = Buffer.from(proof[i].slice(2), 'hex');
-        const hasher = crypto.createHash('sha256');
-        if (idx % 2 === 0) {
-            hasher.update(computedHash);
-            hasher.update(sibling);
-        } else {
-            hasher.update(sibling);
-            hasher.update(computedHash);
+    const targetTx = txHash.startsWith('0x') ? txHash.slice(2) : txHash;
+    const leafIndex = outputs.findIndex(
+        output => output.txHash === targetTx && output.outputIndex === outputIndex
+    );
+    if (leafIndex === -1) {
+        throw new Error(`Output ${outputIndex} in TX ${txHash} not found in block`);
+    }
+    
+    const { proof, root } = merkleProof(outputs.map(encoding.outputLeaf), leafIndex, encoding);
+    
+    return {
+        outputIndex: leafIndex,
+        proof,
+        root,
+        outputCount: outputs.length
+    };
+}
+
+/**
+ * Compute output Merkle proof for a block posted with output tree version
+ * options.version (the contract's outputTreeVersionOf(blockHeight))
+ */
+async function computeOutputMerkleProof(blockHeight, txHash, outputIndex, options = {}) {
+    console.log(`\nComputing output Merkle proof for output ${outputIndex} in TX ${txHash}...`);
+    
+    // 1. Get block data: the miner tx and the other transactions' hashes
+    const blockResponse = await axios.post(MONERO_RPC, {
+        jsonrpc: '2.0',
+        id: '0',
+        method: 'get_block',
+        params: { height: blockHeight }
+    });
+    
+    if (blockResponse.data.error) {
+        throw new Error(`Failed to get block: ${blockResponse.data.error.message}`);
+    }
+    
+    const result = blockResponse.data.result;
+    const allTxHashes = result.tx_hashes || [];
+    const block = {
+        minerTxHash: result.miner_tx_hash,
+        minerTx: JSON.parse(result.json).miner_tx,
+        transactions: []
+    };
+    
+    console.log(`  Block has ${allTxHashes.length} transactions besides the miner tx`);
+    
+    // 2. Fetch the other transactions using REST API endpoint
+    if (allTxHashes.length > 0) {
+        console.log(`  Fetching transaction details...`);
+        const rpcUrl = MONERO_RPC.replace('/json_rpc', '');
+        const txResponse = await axios.post(`${rpcUrl}/get_transactions`, {
+            txs_hashes: allTxHashes,
+            decode_as_json: true
+        });
+        
+        if (txResponse.data.status !== 'OK') {
+            throw new Error(`Failed to get transactions: ${txResponse.data.status}`);
+        }
+        
+        // Keep block order, whatever order the node answers in
+        const byHash = new Map((txResponse.data.txs || []).map(tx => [tx.tx_hash, tx]));
+        for (const hash of allTxHashes) {
+            const tx = byHash.get(hash);
+            if (!tx) {
+                throw new Error(`Transaction ${hash} missing from get_transactions`);
+            }
+            block.transactions.push({ txHash: hash, json: JSON.parse(tx.as_json) });
         }
-        computedHash = hasher.digest();
-        idx = Math.floor(idx / 2);
+        console.log(`  Fetched ${block.transactions.length} transactions`);
     }
-    const computedRoot = '0x' + computedHash.toString('hex');
-    console.log(`  Computed root from proof: ${computedRoot}`);
+    
+    // 3. Build the output tree (miner tx outputs first) and the proof
+    console.log(`  Output tree version: ${Number(options.version) || 1}`);
+    const { outputIndex: leafIndex, proof, root, outputCount } =
+        outputMerkleProof(block, txHash, outputIndex, options);
+    
+    console.log(`  Total outputs in block: ${outputCount}`);
+    console.log(`  Target output global index: ${leafIndex}`);
+    console.log(`  Output Merkle proof has ${proof.length} siblings`);
+    console.log(`  Computed root: ${root}`);
     console.log(`  Expected root (from oracle): Check on-chain`);
     
     return {
-        outputIndex: targetGlobalIndex,
+        outputIndex: leafIndex,
         proof
     };
 }
 
 module.exports = {
     computeTxMerkleProof,
-    computeOutputMerkleProof
+    computeOutputMerkleProof,
+    txMerkleProof,
+    outputMerkleProof,
+    blockOutputs,
+    zeroCommit
 };
 
 // CLI usage
@@ -279,20 +376,27 @@ if (require.main === module) {
     const blockHeight = parseInt(process.argv[2]);
     const txHash = process.argv[3];
     const outputIndex = parseInt(process.argv[4] || '0');
+    // The contract's txTreeVersionOf(blockHeight) and outputTreeVersionOf(blockHeight)
+    const txTreeVersion = parseInt(process.argv[5] || '1');
+    const outputTreeVersion = parseInt(process.argv[6] || '1');
     
     if (!blockHeight || !txHash) {
-        console.log('Usage: node compute_merkle_proof.js <blockHeight> <txHash> [outputIndex]');
+        console.log('Usage: node compute_merkle_proof.js <blockHeight> <txHash> [outputIndex] [txTreeVersion] [outputTreeVersion]');
         process.exit(1);
     }
     
     (async () => {
         try {
-            const txProof = await computeTxMerkleProof(blockHeight, txHash);
+            const txProof = await computeTxMerkleProof(blockHeight, txHash, {
+                version: txTreeVersion
+            });
             console.log('\nTX Merkle Proof:');
             console.log('  Index:', txProof.txIndex);
             console.log('  Proof:', JSON.stringify(txProof.proof, null, 2));
             
-            const outputProof = await computeOutputMerkleProof(txHash, outputIndex);
+            const outputProof = await computeOutputMerkleProof(blockHeight, txHash, outputIndex, {
+                version: outputTreeVersion
+            });
             console.log('\nOutput Merkle Proof:');
             console.log('  Index:', outputProof.outputIndex);
             console.log('  Proof:', JSON.stringify(outputProof.proof, null, 2));
@@ -0,0 +1,93 @@
+/**
+ * Tx and output proofs built by compute_merkle_proof.js against roots and proofs
+ * from the oracle's Rust MerkleTree (merkle_vectors.json, kept in step by
+ * monero-oracle/tests/js_vectors.rs).
+ *
+ * Run with: npm run test:scripts
+ */
+const test = require('node:test');
+const assert = require('node:assert');
+const vectors = require('./merkle_vectors.json');
+const {
+    txMerkleProof,
+    outputMerkleProof,
+    blockOutputs,
+    zeroCommit
+} = require('./compute_merkle_proof');
+
+// The block as computeOutputMerkleProof assembles it from monerod
+const block = {
+    minerTxHash: vectors.block.miner_tx_hash,
+    minerTx: vectors.block.miner_tx,
+    transactions: vectors.block.transactions.map(tx => ({ txHash: tx.tx_hash, json: tx.as_json }))
+};
+const { tx_hash: txHash, output_index: outputIndex } = vectors.output_proof;
+// The tx tree leaves out the miner tx
+const txHashes = vectors.block.transactions.map(tx => tx.tx_hash);
+
+test('miner tx outputs come first, with a cleartext amount and zero commitment', () => {
+    const outputs = blockOutputs(block);
+    const miner = outputs[0];
+    assert.strictEqual(miner.txHash, block.minerTxHash);
+    assert.strictEqual(miner.outputIndex, 0);
+
+    const amount = Buffer.from(miner.ecdhAmount, 'hex');
+    assert.strictEqual(amount.readBigUInt64LE(24), BigInt(block.minerTx.vout[0].amount));
+    assert.ok(amount.subarray(0, 24).every(byte => byte === 0));
+    assert.strictEqual(miner.commitment, zeroCommit(block.minerTx.vout[0].amount));
+});
+
+test('zero commitment of 0 is the basepoint G', () => {
+    assert.strictEqual(
+        zeroCommit(0),
+        '5866666666666666666666666666666666666666666666666666666666666666'
+    );
+});
+
+// Output tree version (outputTreeVersionOf) of each of the oracle's hashers
+const outputTreeOptions = {
+    keccak256: { version: 1 },
+    sha256: { version: 3 },
+    v2: { version: 2 }
+};
+
+for (const [hasher, expected] of Object.entries(vectors.output_trees)) {
+    test(`output proof matches the oracle's ${hasher} tree`, () => {
+        const proof = outputMerkleProof(block, txHash, outputIndex, outputTreeOptions[hasher]);
+        assert.strictEqual(proof.root, expected.root);
+        assert.strictEqual(proof.outputIndex, expected.leaf_index);
+        assert.deepStrictEqual(proof.proof, expected.siblings);
+    });
+}
+
+test('version 0, a block posted before versions were recorded, is version 1', () => {
+    const proof = outputMerkleProof(block, txHash, outputIndex, { version: 0 });
+    assert.strictEqual(proof.root, vectors.output_trees.keccak256.root);
+});
+
+test('an unknown tree version is an error', () => {
+    assert.throws(() => outputMerkleProof(block, txHash, outputIndex, { version: 4 }), /Unknown tree version/);
+    assert.throws(() => txMerkleProof(txHashes, vectors.tx_proof.tx_hash, { version: 3 }), /Unknown tx tree version/);
+});
+
+// Tx tree version (txTreeVersionOf) of each of the oracle's hashers
+const txTreeVersions = { keccak256: 1, v2: 2 };
+
+for (const [hasher, expected] of Object.entries(vectors.tx_trees)) {
+    test(`tx proof matches the oracle's ${hasher} tree`, () => {
+        const proof = txMerkleProof(txHashes, vectors.tx_proof.tx_hash, {
+            version: txTreeVersions[hasher]
+        });
+        assert.strictEqual(proof.root, expected.root);
+        assert.strictEqual(proof.txIndex, expected.leaf_index);
+        assert.deepStrictEqual(proof.proof, expected.siblings);
+    });
+}
+
+test('the miner tx is not in the tx tree', () => {
+    assert.throws(() => txMerkleProof(txHashes, block.minerTxHash), /not found in block/);
+});
+
+test('an output missing from the block is an error', () => {
+    assert.throws(() => outputMerkleProof(block, txHash, 7), /not found in block/);
+});